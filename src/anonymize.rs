@@ -0,0 +1,70 @@
+//! Replaces private text in tasks with hashed placeholders.
+//!
+//! Ids, dates, priorities, statuses, links and reminders are kept, so an
+//! anonymized data file reproduces the same structure as the original. Equal
+//! inputs map to equal placeholders within one [`Anonymizer`] (two tasks with
+//! the tag `home` still share a tag), and a random salt per export prevents
+//! guessing short values from their hash.
+
+use crate::task::{random_u64, Task};
+
+pub struct Anonymizer {
+    salt: u64,
+}
+
+impl Default for Anonymizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Anonymizer {
+    pub fn new() -> Self {
+        Anonymizer::with_salt(random_u64())
+    }
+
+    /// A fixed salt gives reproducible output.
+    pub fn with_salt(salt: u64) -> Self {
+        Anonymizer { salt }
+    }
+
+    fn hash(&self, text: &str) -> String {
+        // FNV-1a, seeded with the salt.
+        let mut hash = 0xcbf2_9ce4_8422_2325 ^ self.salt;
+        for byte in text.bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        format!("{:08x}", hash >> 32)
+    }
+
+    /// `kind-<hash>`; empty text stays empty.
+    pub fn placeholder(&self, kind: &str, text: &str) -> String {
+        if text.is_empty() {
+            return String::new();
+        }
+        format!("{kind}-{}", self.hash(text))
+    }
+
+    pub fn task(&self, task: &Task) -> Task {
+        let mut task = task.clone();
+        task.name = self.placeholder("task", &task.name);
+        task.description = self.placeholder("description", &task.description);
+        task.body = self.placeholder("body", &task.body);
+        task.project = task.project.map(|p| self.placeholder("project", &p));
+        task.jira = task.jira.map(|k| self.placeholder("issue", &k));
+        task.contact = task
+            .contact
+            .map(|c| format!("{}@example.com", self.placeholder("contact", &c)));
+        task.tags = task
+            .tags
+            .iter()
+            .map(|t| self.placeholder("tag", &t.to_lowercase()))
+            .collect();
+        task
+    }
+
+    pub fn tasks(&self, tasks: &[&Task]) -> Vec<Task> {
+        tasks.iter().map(|t| self.task(t)).collect()
+    }
+}
@@ -0,0 +1,309 @@
+//! Binary sidecar of a large data file (`tasks.json.cache`), read instead
+//! of parsing the JSON on startup.
+//!
+//! The cache holds the same document as the data file in a length-prefixed
+//! binary form, headed by a hash of the JSON it was made from. It is only
+//! used while that hash matches, so editing the data file by hand simply
+//! makes the next load parse the JSON and write a fresh cache. Files under
+//! [`MIN_SIZE`] parse quickly enough and get no cache.
+
+use std::borrow::Borrow;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::json::Value;
+use crate::schedule::Template;
+use crate::stats::History;
+use crate::storage::{self, FORMAT_VERSION};
+use crate::task::{Task, TaskId};
+
+/// Data files smaller than this are always read as JSON.
+pub const MIN_SIZE: u64 = 256 * 1024;
+
+const MAGIC: &[u8; 4] = b"TMC1";
+
+const NULL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const NUMBER: u8 = 3;
+const STRING: u8 = 4;
+const ARRAY: u8 = 5;
+const OBJECT: u8 = 6;
+
+/// The cache file kept for the data file at `path`.
+pub fn path_for(path: &Path) -> PathBuf {
+    let mut cache = path.as_os_str().to_os_string();
+    cache.push(".cache");
+    PathBuf::from(cache)
+}
+
+/// Hash of a data file's contents, enough to tell whether it changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContentHash(u64);
+
+impl ContentHash {
+    pub fn of(bytes: &[u8]) -> Self {
+        let mut hasher = HashWriter::new(io::sink());
+        hasher.update(bytes);
+        hasher.hash()
+    }
+
+    /// The hash as a number, for files other than the cache to record.
+    pub fn to_bits(self) -> u64 {
+        self.0
+    }
+
+    pub fn from_bits(bits: u64) -> Self {
+        ContentHash(bits)
+    }
+}
+
+/// Passes writes through to `inner` while hashing them, so a file can be
+/// hashed as it is written. The hash is FNV-1a over 8-byte words with a
+/// rotation to spread each word's high bytes.
+pub struct HashWriter<W> {
+    inner: W,
+    hash: u64,
+    /// Bytes not yet hashed, short of a whole word.
+    pending: [u8; 8],
+    filled: usize,
+}
+
+impl<W: Write> HashWriter<W> {
+    pub fn new(inner: W) -> Self {
+        HashWriter {
+            inner,
+            hash: 0xcbf2_9ce4_8422_2325,
+            pending: [0; 8],
+            filled: 0,
+        }
+    }
+
+    fn update(&mut self, mut bytes: &[u8]) {
+        if self.filled > 0 {
+            let n = bytes.len().min(8 - self.filled);
+            self.pending[self.filled..self.filled + n].copy_from_slice(&bytes[..n]);
+            self.filled += n;
+            bytes = &bytes[n..];
+            if self.filled < 8 {
+                return;
+            }
+            self.hash = mix(self.hash, u64::from_le_bytes(self.pending));
+            self.filled = 0;
+        }
+        let words = bytes.chunks_exact(8);
+        let rest = words.remainder();
+        for word in words {
+            self.hash = mix(self.hash, u64::from_le_bytes(word.try_into().unwrap()));
+        }
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.filled = rest.len();
+    }
+
+    pub fn hash(&self) -> ContentHash {
+        let mut tail = [0; 8];
+        tail[..self.filled].copy_from_slice(&self.pending[..self.filled]);
+        // The length keeps trailing zero bytes from hashing like no bytes.
+        let hash = mix(self.hash, u64::from_le_bytes(tail));
+        ContentHash(mix(hash, self.filled as u64))
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for HashWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn mix(hash: u64, word: u64) -> u64 {
+    (hash ^ word).wrapping_mul(0x0100_0000_01b3).rotate_left(29)
+}
+
+/// Reads the document cached at `path` if it was made from data with
+/// `hash`. A missing, stale or damaged cache gives `None`.
+pub fn read(path: &Path, hash: ContentHash) -> Option<Value> {
+    let bytes = fs::read(path).ok()?;
+    let rest = bytes.strip_prefix(MAGIC)?;
+    let (stored, rest) = rest.split_first_chunk::<8>()?;
+    if u64::from_le_bytes(*stored) != hash.0 {
+        return None;
+    }
+    let mut reader = Reader { bytes: rest };
+    let value = reader.value(0)?;
+    reader.bytes.is_empty().then_some(value)
+}
+
+/// Writes a cache of the document [`crate::storage::write`] produces for
+/// the same arguments, made from data with `hash`. Like the data file, it
+/// goes to a temporary sibling first.
+pub fn write<T: Borrow<Task>>(
+    path: &Path,
+    hash: ContentHash,
+    tasks: impl ExactSizeIterator<Item = T>,
+    history: &History,
+    templates: &[Template],
+    archived_projects: &[String],
+    aliases: &[(String, TaskId)],
+) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_os_string();
+    tmp.push(".tmp");
+    let mut out = BufWriter::new(File::create(&tmp)?);
+    out.write_all(MAGIC)?;
+    out.write_all(&hash.0.to_le_bytes())?;
+    let fields = 3
+        + usize::from(!templates.is_empty())
+        + usize::from(!archived_projects.is_empty())
+        + usize::from(!aliases.is_empty());
+    header(&mut out, OBJECT, fields)?;
+    key(&mut out, "version")?;
+    value(&mut out, &Value::from(FORMAT_VERSION))?;
+    key(&mut out, "tasks")?;
+    header(&mut out, ARRAY, tasks.len())?;
+    for task in tasks {
+        value(&mut out, &task.borrow().to_json())?;
+    }
+    key(&mut out, "history")?;
+    value(&mut out, &history.to_json())?;
+    if !templates.is_empty() {
+        key(&mut out, "templates")?;
+        value(
+            &mut out,
+            &Value::Array(templates.iter().map(Template::to_json).collect()),
+        )?;
+    }
+    if !archived_projects.is_empty() {
+        key(&mut out, "archived_projects")?;
+        value(&mut out, &Value::from(archived_projects.to_vec()))?;
+    }
+    if !aliases.is_empty() {
+        key(&mut out, "aliases")?;
+        value(&mut out, &storage::aliases_to_json(aliases))?;
+    }
+    out.into_inner().map_err(|err| err.into_error())?;
+    fs::rename(&tmp, path)
+}
+
+/// Writes a cache of `document`, made from data with `hash`.
+pub fn write_value(path: &Path, hash: ContentHash, document: &Value) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_os_string();
+    tmp.push(".tmp");
+    let mut out = BufWriter::new(File::create(&tmp)?);
+    out.write_all(MAGIC)?;
+    out.write_all(&hash.0.to_le_bytes())?;
+    value(&mut out, document)?;
+    out.into_inner().map_err(|err| err.into_error())?;
+    fs::rename(&tmp, path)
+}
+
+fn header(out: &mut impl Write, tag: u8, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len).map_err(|_| io::Error::other("too large to cache"))?;
+    out.write_all(&[tag])?;
+    out.write_all(&len.to_le_bytes())
+}
+
+fn key(out: &mut impl Write, key: &str) -> io::Result<()> {
+    value(out, &Value::String(key.to_string()))
+}
+
+fn value(out: &mut impl Write, value: &Value) -> io::Result<()> {
+    match value {
+        Value::Null => out.write_all(&[NULL]),
+        Value::Bool(false) => out.write_all(&[FALSE]),
+        Value::Bool(true) => out.write_all(&[TRUE]),
+        Value::Number(n) => {
+            out.write_all(&[NUMBER])?;
+            out.write_all(&n.to_le_bytes())
+        }
+        Value::String(s) => {
+            header(out, STRING, s.len())?;
+            out.write_all(s.as_bytes())
+        }
+        Value::Array(items) => {
+            header(out, ARRAY, items.len())?;
+            items.iter().try_for_each(|item| self::value(out, item))
+        }
+        Value::Object(fields) => {
+            header(out, OBJECT, fields.len())?;
+            fields.iter().try_for_each(|(name, field)| {
+                key(out, name)?;
+                self::value(out, field)
+            })
+        }
+    }
+}
+
+/// Nesting deeper than any data file has; guards against a damaged cache
+/// overflowing the stack.
+const MAX_DEPTH: usize = 64;
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Option<&[u8]> {
+        if n > self.bytes.len() {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn len(&mut self) -> Option<usize> {
+        let bytes = self.take(4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.len()?;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+
+    fn value(&mut self, depth: usize) -> Option<Value> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        Some(match self.take(1)?[0] {
+            NULL => Value::Null,
+            FALSE => Value::Bool(false),
+            TRUE => Value::Bool(true),
+            NUMBER => Value::Number(f64::from_le_bytes(self.take(8)?.try_into().ok()?)),
+            STRING => Value::String(self.string()?),
+            ARRAY => {
+                let len = self.len()?;
+                // Every item takes at least a byte, which bounds what a
+                // damaged length can make us allocate.
+                let mut items = Vec::with_capacity(len.min(self.bytes.len()));
+                for _ in 0..len {
+                    items.push(self.value(depth + 1)?);
+                }
+                Value::Array(items)
+            }
+            OBJECT => {
+                let len = self.len()?;
+                let mut fields = Vec::with_capacity(len.min(self.bytes.len()));
+                for _ in 0..len {
+                    if self.take(1)? != [STRING] {
+                        return None;
+                    }
+                    let name = self.string()?;
+                    fields.push((name, self.value(depth + 1)?));
+                }
+                Value::Object(fields)
+            }
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,55 @@
+//! `alias`: names for tasks, accepted wherever an id is.
+
+use task_manager::render::short_id;
+use task_manager::{Error, Result};
+
+use super::{Args, Context};
+
+pub fn alias(ctx: &mut Context, mut args: Args) -> Result<()> {
+    if let Some(name) = args.value("--remove")? {
+        args.finish()?;
+        if !ctx.manager.remove_alias(&name) {
+            return Err(Error::NotFound(format!("no alias `{name}`")));
+        }
+        println!("Removed alias {name}");
+        ctx.modified();
+        return Ok(());
+    }
+    let name = args.positional();
+    let reference = args.positional();
+    args.finish()?;
+    let (name, reference) = match (name, reference) {
+        (Some(name), Some(reference)) => (name, reference),
+        (Some(_), None) => return Err(Error::Invalid("usage: alias <name> <id>".into())),
+        (None, _) => return list(ctx),
+    };
+    let id = ctx.manager.resolve(&reference)?;
+    ctx.manager.set_alias(&name, id)?;
+    let task = ctx.manager.get(id).expect("resolved id exists");
+    println!(
+        "{name} is now \"{}\" ({})",
+        task.name,
+        short_id(&id.to_string())
+    );
+    ctx.modified();
+    Ok(())
+}
+
+fn list(ctx: &Context) -> Result<()> {
+    let aliases = ctx.manager.aliases();
+    if aliases.is_empty() {
+        println!("No aliases; add one with `alias <name> <id>`.");
+        return Ok(());
+    }
+    let width = aliases.iter().map(|(name, _)| name.chars().count()).max();
+    for (name, id) in aliases {
+        let task = ctx.manager.get(*id).expect("aliases name existing tasks");
+        println!(
+            "{name:<width$}  {}  {}",
+            short_id(&id.to_string()),
+            task.name,
+            width = width.unwrap_or_default()
+        );
+    }
+    Ok(())
+}
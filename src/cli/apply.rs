@@ -0,0 +1,67 @@
+//! Reconciling the data file with a YAML manifest.
+
+use std::fs;
+
+use task_manager::config::Operation;
+use task_manager::dates::Timestamp;
+use task_manager::manifest::{Action, Manifest};
+use task_manager::render::short_id;
+use task_manager::Result;
+
+use super::{Args, Context};
+
+pub fn apply(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let prune = args.flag("--prune");
+    let dry_run = args.flag("--dry-run");
+    // `--yes` is the older spelling of `--force`.
+    let force = args.flag("--force") | args.flag("--yes");
+    let path = args.required("manifest file")?;
+    args.finish()?;
+    let manifest = Manifest::parse(&fs::read_to_string(&path)?)?;
+    let plan = manifest.plan(&ctx.manager, Timestamp::now(), prune)?;
+    if plan.is_empty() {
+        println!("Nothing to do; the tasks already match {path}.");
+        return Ok(());
+    }
+    for action in &plan.actions {
+        match action {
+            Action::Create(task) => println!("+ {}", label(&task.project, &task.name)),
+            Action::Change { id, changes, .. } => {
+                let task = ctx.manager.get(*id).expect("planned task exists");
+                println!(
+                    "~ {} ({})",
+                    label(&task.project, &task.name),
+                    short_id(&id.to_string())
+                );
+                for change in changes {
+                    println!("    {}: {} -> {}", change.field, change.old, change.new);
+                }
+            }
+            Action::Remove(task) => println!(
+                "- {} ({})",
+                label(&task.project, &task.name),
+                short_id(&task.id.to_string())
+            ),
+        }
+    }
+    let (create, change, remove) = plan.counts();
+    println!("Plan: {create} to create, {change} to change, {remove} to remove.");
+    if dry_run {
+        return Ok(());
+    }
+    if !ctx.confirm(Operation::Apply, force, "Apply these changes?") {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    plan.apply(&mut ctx.manager)?;
+    ctx.modified();
+    println!("Applied.");
+    Ok(())
+}
+
+fn label(project: &Option<String>, name: &str) -> String {
+    match project {
+        Some(project) => format!("{project} / {name}"),
+        None => name.to_string(),
+    }
+}
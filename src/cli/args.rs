@@ -0,0 +1,123 @@
+//! Tiny argument parser: commands pull the flags they understand out of the
+//! argument list and whatever remains must be positional.
+
+use task_manager::{Error, Result};
+
+#[derive(Clone, Debug, Default)]
+pub struct Args {
+    items: Vec<String>,
+}
+
+impl Args {
+    pub fn new(items: Vec<String>) -> Self {
+        Args { items }
+    }
+
+    /// The arguments not taken yet.
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    /// Removes a boolean `--name` flag, returning whether it was present.
+    pub fn flag(&mut self, name: &str) -> bool {
+        let before = self.items.len();
+        self.items.retain(|item| item != name);
+        self.items.len() != before
+    }
+
+    /// Removes `--name value` or `--name=value`.
+    pub fn value(&mut self, name: &str) -> Result<Option<String>> {
+        let prefix = format!("{name}=");
+        for i in 0..self.items.len() {
+            if let Some(value) = self.items[i].strip_prefix(&prefix) {
+                let value = value.to_string();
+                self.items.remove(i);
+                return Ok(Some(value));
+            }
+            if self.items[i] == name {
+                if i + 1 >= self.items.len() {
+                    return Err(Error::Invalid(format!("`{name}` needs a value")));
+                }
+                let value = self.items.remove(i + 1);
+                self.items.remove(i);
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Removes and returns the first positional argument.
+    pub fn positional(&mut self) -> Option<String> {
+        let index = self.items.iter().position(|item| !is_flag(item))?;
+        Some(self.items.remove(index))
+    }
+
+    /// Like [`Args::positional`] but reports `what` when it is missing.
+    pub fn required(&mut self, what: &str) -> Result<String> {
+        self.positional()
+            .ok_or_else(|| Error::Invalid(format!("missing {what}")))
+    }
+
+    /// Returns the remaining positional arguments, rejecting unknown flags.
+    pub fn rest(self) -> Result<Vec<String>> {
+        if let Some(flag) = self.items.iter().find(|item| is_flag(item)) {
+            return Err(Error::Invalid(format!("unknown option `{flag}`")));
+        }
+        Ok(self.items)
+    }
+
+    /// Ensures every argument was consumed.
+    pub fn finish(self) -> Result<()> {
+        match self.rest()?.first() {
+            Some(extra) => Err(Error::Invalid(format!("unexpected argument `{extra}`"))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn is_flag(item: &str) -> bool {
+    item.starts_with("--") && item.len() > 2
+}
+
+/// Splits an interactive command line into words, honouring single and
+/// double quotes and backslash escapes.
+pub fn split_words(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_word = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(Error::Invalid("unterminated quote".into()));
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
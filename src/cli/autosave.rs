@@ -0,0 +1,182 @@
+//! When the interactive mode writes changes, as `[autosave]` configures:
+//! after every command, at most once per interval from a background timer,
+//! or only when the session ends. Whatever is unsaved is written on `quit`
+//! and at the end of input whichever strategy is chosen, and on Unix also
+//! when the session is ended with SIGTERM or SIGHUP.
+
+use std::process;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use task_manager::config::AutosaveStrategy;
+use task_manager::Result;
+
+use super::Context;
+
+/// How often the timer looks for unsaved changes and signals.
+const TICK: Duration = Duration::from_millis(200);
+
+/// How long a signal waits for a running command before exiting anyway.
+const GRACE: Duration = Duration::from_secs(2);
+
+/// The session's context, shared with the timer thread when there is one.
+pub struct Autosave {
+    ctx: Arc<Mutex<Context>>,
+    strategy: AutosaveStrategy,
+    /// Dropped to stop the timer.
+    stop: Option<Sender<()>>,
+    timer: Option<JoinHandle<()>>,
+}
+
+impl Autosave {
+    pub fn start(ctx: Context) -> Self {
+        let settings = ctx.config.autosave;
+        let ctx = Arc::new(Mutex::new(ctx));
+        let debounce = (settings.strategy == AutosaveStrategy::Debounce)
+            .then(|| Duration::from_secs(settings.interval.unsigned_abs()));
+        let (mut stop, mut timer) = (None, None);
+        if signals::install() || debounce.is_some() {
+            let (sender, receiver) = mpsc::channel::<()>();
+            let shared = Arc::clone(&ctx);
+            timer = Some(thread::spawn(move || {
+                let mut last_save = Instant::now();
+                while let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(TICK) {
+                    if let Some(signal) = signals::received() {
+                        save_and_exit(&shared, signal);
+                    }
+                    let Some(interval) = debounce else {
+                        continue;
+                    };
+                    if last_save.elapsed() < interval {
+                        continue;
+                    }
+                    let mut ctx = lock(&shared);
+                    if ctx.modified {
+                        // Left modified on failure, so the next tick retries.
+                        if let Err(err) = ctx.save_if_modified() {
+                            eprintln!("warning: autosave failed: {err}");
+                        }
+                        last_save = Instant::now();
+                    }
+                }
+            }));
+            stop = Some(sender);
+        }
+        Autosave {
+            ctx,
+            strategy: settings.strategy,
+            stop,
+            timer,
+        }
+    }
+
+    /// The context, held until the guard is dropped. The timer waits
+    /// meanwhile, so it never saves halfway through a command.
+    pub fn lock(&self) -> MutexGuard<'_, Context> {
+        lock(&self.ctx)
+    }
+
+    /// Called after each command that succeeded.
+    pub fn command_done(&self, ctx: &mut Context) -> Result<()> {
+        match self.strategy {
+            AutosaveStrategy::Change => ctx.save_if_modified(),
+            AutosaveStrategy::Debounce | AutosaveStrategy::Exit => Ok(()),
+        }
+    }
+
+    /// Stops the timer and writes whatever is unsaved.
+    pub fn finish(mut self) -> Result<()> {
+        drop(self.stop.take());
+        if let Some(timer) = self.timer.take() {
+            // A panic there has already been reported on stderr.
+            let _ = timer.join();
+        }
+        self.lock().save_if_modified()
+    }
+}
+
+fn lock(ctx: &Mutex<Context>) -> MutexGuard<'_, Context> {
+    ctx.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Writes what is unsaved and ends the process as `signal` would have. A
+/// command still running (say, waiting at a prompt) gets [`GRACE`] to
+/// finish; after that the process exits without its changes rather than
+/// saving them half done.
+fn save_and_exit(ctx: &Mutex<Context>, signal: i32) -> ! {
+    let started = Instant::now();
+    loop {
+        match ctx.try_lock() {
+            Ok(mut ctx) => {
+                if let Err(err) = ctx.save_if_modified() {
+                    eprintln!("warning: could not save before exiting: {err}");
+                }
+                break;
+            }
+            Err(TryLockError::Poisoned(poisoned)) => {
+                let _ = poisoned.into_inner().save_if_modified();
+                break;
+            }
+            Err(TryLockError::WouldBlock) if started.elapsed() < GRACE => {
+                thread::sleep(TICK);
+            }
+            Err(TryLockError::WouldBlock) => {
+                eprintln!("warning: a command was still running; its changes were not saved");
+                break;
+            }
+        }
+    }
+    process::exit(128 + signal)
+}
+
+/// SIGTERM and SIGHUP handling through the C library's `signal`, which std
+/// already links on Unix. The handler only records the signal; the timer
+/// thread notices it within a [`TICK`] and does the saving.
+#[cfg(unix)]
+mod signals {
+    use std::os::raw::c_int;
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    const SIGHUP: c_int = 1;
+    const SIGTERM: c_int = 15;
+    /// What `signal` returns when it fails.
+    const SIG_ERR: usize = usize::MAX;
+
+    static RECEIVED: AtomicI32 = AtomicI32::new(0);
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+    }
+
+    extern "C" fn record(signum: c_int) {
+        // An atomic store is all a handler can safely do.
+        RECEIVED.store(signum, Ordering::SeqCst);
+    }
+
+    /// Routes SIGTERM and SIGHUP to [`received`]; false if that failed.
+    pub fn install() -> bool {
+        [SIGTERM, SIGHUP].iter().all(|&signum| {
+            // SAFETY: `record` only touches an atomic, which is
+            // async-signal-safe, and matches the handler signature.
+            unsafe { signal(signum, record) != SIG_ERR }
+        })
+    }
+
+    /// The signal that asked the session to end, if one has.
+    pub fn received() -> Option<i32> {
+        Some(RECEIVED.load(Ordering::SeqCst)).filter(|&signum| signum != 0)
+    }
+}
+
+#[cfg(not(unix))]
+mod signals {
+    pub fn install() -> bool {
+        false
+    }
+
+    pub fn received() -> Option<i32> {
+        None
+    }
+}
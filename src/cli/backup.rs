@@ -0,0 +1,199 @@
+//! `backup remote`: the data file, encrypted, in an S3-compatible bucket.
+//!
+//! Encryption is `openssl enc` with an HMAC over the result (see
+//! [`crypto`](super::crypto)) and transfer is `curl` with AWS signature
+//! version 4, so both must be installed. Secrets reach them through the
+//! environment and stdin, never the command line where other users could
+//! read them. Any store can be backed up; a restore replaces its tasks,
+//! keeping the previous ones in a `.bak` data file.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use task_manager::config::{BackupSettings, Layers, Operation};
+use task_manager::{json, storage};
+use task_manager::{Error, Result};
+
+use super::crypto::Cipher;
+use super::http::{self, quote};
+use super::{Args, Context};
+
+const PASSPHRASE_VAR: &str = "TASK_MANAGER_BACKUP_PASSPHRASE";
+
+pub fn backup(ctx: &mut Context, mut args: Args) -> Result<()> {
+    match args.positional().as_deref() {
+        Some("remote") => match args.positional().as_deref() {
+            None => {
+                args.finish()?;
+                upload(ctx)
+            }
+            Some("restore") => {
+                let force = args.flag("--force");
+                args.finish()?;
+                restore(ctx, force)
+            }
+            Some(other) => Err(Error::Invalid(format!(
+                "unknown backup action `{other}` (expected restore)"
+            ))),
+        },
+        Some(other) => Err(Error::Invalid(format!(
+            "unknown backup target `{other}` (expected remote)"
+        ))),
+        None => Err(Error::Invalid(
+            "choose where to back up, e.g. `backup remote`".into(),
+        )),
+    }
+}
+
+fn upload(ctx: &Context) -> Result<()> {
+    let remote = Remote::from_settings(&ctx.config.backup, &ctx.layers()?)?;
+    let data = ctx.data_path();
+    if !data.exists() {
+        return Err(Error::NotFound(format!(
+            "{} does not exist yet; there is nothing to back up",
+            data.display()
+        )));
+    }
+    let document = storage::encode(&ctx.manager).to_pretty();
+    let encrypted = TempFile::beside(data, ".enc");
+    fs::write(&encrypted.0, remote.cipher().encrypt(document.as_bytes())?)?;
+    curl(&remote, &["--upload-file"], &encrypted.0)?;
+    println!("Backed up {} to {}", data.display(), remote.url);
+    Ok(())
+}
+
+fn restore(ctx: &mut Context, force: bool) -> Result<()> {
+    let remote = Remote::from_settings(&ctx.config.backup, &ctx.layers()?)?;
+    let data = ctx.data_path().to_path_buf();
+    let encrypted = TempFile::beside(&data, ".enc");
+    curl(&remote, &["--output"], &encrypted.0)?;
+    let plain = remote.cipher().decrypt(&fs::read(&encrypted.0)?)?;
+    let text = String::from_utf8(plain)
+        .map_err(|_| Error::Invalid("the decrypted backup is not a data file".into()))?;
+    let restored = storage::decode(&json::parse(&text)?)?;
+    let count = restored.len();
+    let question = format!(
+        "Replace the {} tasks in {} with the backup of {count} tasks?",
+        ctx.manager.len(),
+        data.display(),
+    );
+    if data.exists() && !ctx.confirm(Operation::Overwrite, force, &question) {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    let previous = ctx.replace(restored)?;
+    println!(
+        "Restored {count} tasks from {} to {}",
+        remote.url,
+        data.display()
+    );
+    if let Some(previous) = previous {
+        println!("The tasks it replaced are in {}", previous.display());
+    }
+    Ok(())
+}
+
+/// The settings `backup remote` needs, checked and with the environment
+/// filled in.
+struct Remote {
+    url: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    passphrase: String,
+}
+
+impl Remote {
+    fn cipher(&self) -> Cipher {
+        Cipher::new(self.passphrase.clone())
+    }
+
+    fn from_settings(settings: &BackupSettings, layers: &Layers) -> Result<Self> {
+        let required = |key: &str, value: &Option<String>, var: Option<&str>| {
+            http::required(layers, &format!("backup.{key}"), value, var)
+        };
+        let endpoint = required("endpoint", &settings.endpoint, None)?;
+        let bucket = required("bucket", &settings.bucket, None)?;
+        Ok(Remote {
+            url: format!("{endpoint}/{bucket}/{}", encode_path(&settings.object)),
+            region: settings.region.clone(),
+            access_key: required(
+                "access_key",
+                &settings.access_key,
+                Some("AWS_ACCESS_KEY_ID"),
+            )?,
+            secret_key: required(
+                "secret_key",
+                &settings.secret_key,
+                Some("AWS_SECRET_ACCESS_KEY"),
+            )?,
+            passphrase: required("passphrase", &settings.passphrase, Some(PASSPHRASE_VAR))?,
+        })
+    }
+}
+
+/// Percent-encodes an object key, keeping its `/` separators.
+fn encode_path(key: &str) -> String {
+    let mut out = String::new();
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+/// Runs curl against the backup object with `transfer` (`--upload-file` or
+/// `--output`) and `file`. The credentials go in a config read from stdin.
+fn curl(remote: &Remote, transfer: &[&str], file: &Path) -> Result<()> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--config", "-"])
+        .args(transfer)
+        .arg(file)
+        .arg(&remote.url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| Error::Invalid(format!("could not run curl: {err}")))?;
+    let config = format!(
+        "aws-sigv4 = \"aws:amz:{}:s3\"\nuser = \"{}:{}\"\n",
+        quote(&remote.region),
+        quote(&remote.access_key),
+        quote(&remote.secret_key)
+    );
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin.write_all(config.as_bytes())?;
+    drop(stdin);
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(Error::Invalid(format!(
+            "transfer to {} failed (curl: {})",
+            remote.url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// A file next to the data file, removed again when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn beside(data: &Path, suffix: &str) -> Self {
+        let mut path = data.as_os_str().to_owned();
+        path.push(suffix);
+        TempFile(PathBuf::from(path))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
@@ -0,0 +1,193 @@
+//! `calendar`: a month grid of due tasks, and the tasks due on one day.
+
+use task_manager::dates::{self, DateTime, Timestamp};
+use task_manager::render::highlight::Search;
+use task_manager::render::theme;
+use task_manager::{Error, Result, Task};
+
+use super::render;
+use super::{Args, Context};
+
+pub fn calendar(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let all = args.flag("--all");
+    let day = args.value("--day")?;
+    let month = args.positional();
+    args.finish()?;
+    let now = Timestamp::now();
+    let due: Vec<&Task> = ctx
+        .manager
+        .tasks()
+        .iter()
+        .filter(|t| t.due.is_some() && (all || t.status.is_open()))
+        .collect();
+    if let Some(day) = day {
+        if month.is_some() {
+            return Err(Error::Invalid(
+                "give either a month or `--day <date>`, not both".into(),
+            ));
+        }
+        return print_day(ctx, &due, dates::parse_when(&day, now)?, now);
+    }
+    let (year, month) = match month {
+        Some(month) => parse_month(&month, now)?,
+        None => {
+            let today = now.to_local();
+            (today.year, today.month)
+        }
+    };
+    print!(
+        "{}",
+        month_grid(
+            &due,
+            year,
+            month,
+            now,
+            ctx.config.glyphs.overdue,
+            render::color_enabled()
+        )
+    );
+    Ok(())
+}
+
+/// Reads `2024-06`, `06-2024` or a month name (this year).
+fn parse_month(input: &str, now: Timestamp) -> Result<(i64, u32)> {
+    let invalid = || {
+        Error::Invalid(format!(
+            "unrecognized month `{input}` (expected YYYY-MM or a month name)"
+        ))
+    };
+    if let Some(month) = dates::parse_month(input) {
+        return Ok((now.to_local().year, month));
+    }
+    let (a, b) = input.trim().split_once('-').ok_or_else(invalid)?;
+    let (year, month) = if a.len() == 4 { (a, b) } else { (b, a) };
+    let year: i64 = year.parse().map_err(|_| invalid())?;
+    let month: u32 = month.parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) {
+        return Err(invalid());
+    }
+    Ok((year, month))
+}
+
+fn month_grid(
+    tasks: &[&Task],
+    year: i64,
+    month: u32,
+    now: Timestamp,
+    overdue_mark: char,
+    color: bool,
+) -> String {
+    let first = DateTime::date(year, month, 1)
+        .to_local_timestamp()
+        .expect("first of the month is a valid date");
+    let first_day = first.day_number();
+    let days = dates::days_in_month(year, month) as i64;
+    let today = now.day_number();
+    let colors = theme::theme();
+    let mut counts = vec![0usize; days as usize];
+    for task in tasks {
+        let Some(due) = task.due else { continue };
+        let offset = due.day_number() - first_day;
+        if (0..days).contains(&offset) {
+            counts[offset as usize] += 1;
+        }
+    }
+
+    let title = first.format_local("%B %Y");
+    let mut out = format!("{}\n", format!("{title:^48}").trim_end());
+    let locale = dates::display_locale();
+    for weekday in dates::Weekday::week() {
+        let label: String = locale.weekday(weekday).chars().take(2).collect();
+        out.push_str(&format!(" {label:<6}"));
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    let lead = first.weekday().days_into_week() as usize;
+    let mut line = "       ".repeat(lead);
+    for (offset, &count) in counts.iter().enumerate() {
+        let day_number = first_day + offset as i64;
+        let overdue = count > 0 && day_number < today;
+        let (open, close) = if day_number == today {
+            ('[', ']')
+        } else {
+            (' ', ' ')
+        };
+        let marker = match (count, overdue) {
+            (0, _) => String::new(),
+            (1..=9, true) => format!("{count}{overdue_mark}"),
+            (_, true) => format!("+{overdue_mark}"),
+            (1..=99, false) => count.to_string(),
+            _ => "99".to_string(),
+        };
+        let cell = format!("{open}{:>2}{close}{marker:<2}", offset + 1);
+        let style = match (color, overdue, day_number == today) {
+            (false, ..) => "",
+            (true, true, _) => &colors.overdue,
+            (true, false, true) => &colors.today,
+            _ => "",
+        };
+        line.push_str(&format!("{} ", theme::paint(style, &cell)));
+        if (lead + offset) % 7 == 6 {
+            out.push_str(line.trim_end());
+            out.push('\n');
+            line.clear();
+        }
+    }
+    if !line.trim().is_empty() {
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    let total: usize = counts.iter().sum();
+    let overdue: usize = counts
+        .iter()
+        .enumerate()
+        .filter(|&(offset, _)| first_day + (offset as i64) < today)
+        .map(|(_, &count)| count)
+        .sum();
+    out.push_str(&format!(
+        "\n[d] today   n = tasks due   n{overdue_mark} = overdue\n{total} task(s) due this month"
+    ));
+    if overdue > 0 {
+        out.push_str(&format!(", {overdue} overdue"));
+    }
+    out.push('\n');
+    out
+}
+
+fn print_day(ctx: &Context, tasks: &[&Task], day: Timestamp, now: Timestamp) -> Result<()> {
+    let mut tasks: Vec<&Task> = tasks
+        .iter()
+        .copied()
+        .filter(|t| {
+            t.due
+                .is_some_and(|due| due.day_number() == day.day_number())
+        })
+        .collect();
+    println!("{} {}", day.format_local("%A"), day.to_local_date());
+    if tasks.is_empty() {
+        println!("No tasks due.");
+        return Ok(());
+    }
+    tasks.sort_by_key(|t| (t.due, std::cmp::Reverse(t.priority)));
+    let times = render::Times {
+        now,
+        relative: ctx.config.display.relative_times,
+    };
+    let color = render::color_enabled();
+    let tags = render::Tags {
+        styles: &ctx.config.tags,
+        color,
+    };
+    print!(
+        "{}",
+        render::task_table(
+            &tasks,
+            times,
+            tags,
+            &ctx.config.glyphs,
+            &Search::new(&[], color),
+            render::terminal_width()
+        )
+    );
+    Ok(())
+}
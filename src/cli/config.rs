@@ -0,0 +1,47 @@
+//! `config show`: the settings in effect and the layer each came from,
+//! whether the defaults, the file, an environment variable or a flag.
+
+use task_manager::config::Origin;
+use task_manager::{Error, Result};
+
+use super::{Args, Context};
+
+pub fn show(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let effective = args.flag("--effective");
+    match args.positional().as_deref() {
+        Some("show") => {}
+        Some(other) => {
+            return Err(Error::Invalid(format!(
+                "unknown `config` action `{other}` (expected show)"
+            )))
+        }
+        None => return Err(Error::Invalid("usage: config show [--effective]".into())),
+    }
+    args.finish()?;
+    let settings: Vec<_> = ctx
+        .layers()?
+        .settings()
+        .into_iter()
+        .filter(|s| effective || s.origin != Origin::Default)
+        .collect();
+    if settings.is_empty() {
+        println!("Every setting has its default; `config show --effective` lists them.");
+        return Ok(());
+    }
+    let lines: Vec<String> = settings
+        .iter()
+        .map(|s| match s.value.as_str() {
+            "" => format!("{} (unset)", s.key),
+            value => format!("{} = {value}", s.key),
+        })
+        .collect();
+    let width = lines
+        .iter()
+        .map(|l| l.chars().count())
+        .max()
+        .unwrap_or_default();
+    for (line, setting) in lines.iter().zip(&settings) {
+        println!("{line:<width$}  # {}", setting.origin);
+    }
+    Ok(())
+}
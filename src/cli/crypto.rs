@@ -0,0 +1,260 @@
+//! Passphrase encryption through `openssl enc`: AES-256-CBC with a
+//! PBKDF2-derived key, then an HMAC-SHA-256 tag over the ciphertext
+//! (encrypt-then-MAC, see [`digest`]). CBC alone notices neither tampering
+//! nor a wrong passphrase. The HMAC key comes from the same passphrase and
+//! salt through PBKDF2 with SHA-512, so it is independent of the cipher key.
+//!
+//! The passphrase reaches openssl through its environment and the data
+//! through pipes, so neither shows on a command line or touches the disk.
+//!
+//! [`FieldCipher`] does the same for many short values, such as the
+//! descriptions in a data file, without paying for PBKDF2 on each one.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+
+use task_manager::digest;
+use task_manager::task::random_u64;
+use task_manager::{Error, Result};
+
+/// Where the child openssl finds the passphrase.
+const PASS_VAR: &str = "PASSPHRASE";
+
+/// PBKDF2 rounds for the keys; slow enough to make guessing expensive.
+const KDF_ITERATIONS: &str = "600000";
+
+/// `openssl enc` output starts with this, then an 8-byte salt.
+const MAGIC: &[u8] = b"Salted__";
+
+/// Marks a value [`FieldCipher`] encrypted.
+const FIELD_PREFIX: &str = "enc:v1:";
+
+#[derive(Clone)]
+pub struct Cipher {
+    passphrase: String,
+    /// PBKDF2 rounds; one when the passphrase is already a random key.
+    iterations: &'static str,
+}
+
+impl Cipher {
+    pub fn new(passphrase: String) -> Self {
+        Cipher {
+            passphrase,
+            iterations: KDF_ITERATIONS,
+        }
+    }
+
+    /// `plain` encrypted under a fresh salt, with its tag.
+    pub fn encrypt(&self, plain: &[u8]) -> Result<Vec<u8>> {
+        let sealed = self.enc(&["-e", "-salt"], plain)?;
+        let key = self.mac_key(salt(&sealed)?)?;
+        Ok(digest::sign(&key, &sealed))
+    }
+
+    /// What [`Cipher::encrypt`] was given, checking the tag before
+    /// decrypting anything.
+    pub fn decrypt(&self, signed: &[u8]) -> Result<Vec<u8>> {
+        let key = self.mac_key(salt(signed)?)?;
+        let sealed = digest::verify(&key, signed).map_err(|_| {
+            Error::Invalid(
+                "the encrypted data failed authentication; is the passphrase right?".into(),
+            )
+        })?;
+        self.enc(&["-d"], sealed)
+    }
+
+    /// The HMAC key for `salt`: what `openssl enc -P` derives with SHA-512
+    /// instead of the cipher's SHA-256.
+    fn mac_key(&self, salt: &[u8]) -> Result<Vec<u8>> {
+        self.derive(salt, "sha512")
+    }
+
+    /// The cipher key PBKDF2 with `md` derives from the passphrase and `salt`.
+    fn derive(&self, salt: &[u8], md: &str) -> Result<Vec<u8>> {
+        let output = self.openssl(
+            &[
+                "enc",
+                "-aes-256-cbc",
+                "-pbkdf2",
+                "-iter",
+                self.iterations,
+                "-md",
+                md,
+                "-S",
+                &digest::to_hex(salt),
+                "-P",
+            ],
+            &[],
+        )?;
+        String::from_utf8_lossy(&output)
+            .lines()
+            .find_map(|line| line.strip_prefix("key=").and_then(digest::from_hex))
+            .ok_or_else(|| Error::Invalid("openssl did not print a derived key".into()))
+    }
+
+    fn enc(&self, mode: &[&str], input: &[u8]) -> Result<Vec<u8>> {
+        let mut args = vec!["enc", "-aes-256-cbc", "-pbkdf2", "-iter", self.iterations];
+        args.extend(mode);
+        self.openssl(&args, input).map_err(|err| {
+            let what = if mode.contains(&"-d") {
+                "decryption"
+            } else {
+                "encryption"
+            };
+            Error::Invalid(format!("{what} failed ({err})"))
+        })
+    }
+
+    /// Runs openssl with `args` and the passphrase, feeding it `input` and
+    /// returning what it writes.
+    fn openssl(&self, args: &[&str], input: &[u8]) -> Result<Vec<u8>> {
+        let mut child = Command::new("openssl")
+            .args(args)
+            .args(["-pass", &format!("env:{PASS_VAR}")])
+            .env(PASS_VAR, &self.passphrase)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| Error::Invalid(format!("could not run openssl: {err}")))?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        // Written from another thread so a full output pipe cannot stall it.
+        let output = thread::scope(|scope| {
+            scope.spawn(move || stdin.write_all(input));
+            child.wait_with_output()
+        })?;
+        if !output.status.success() {
+            return Err(Error::Invalid(format!(
+                "openssl: {}",
+                String::from_utf8_lossy(&output.stderr)
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+            )));
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// Encrypts values one at a time, each tied to where it is stored.
+///
+/// The passphrase goes through PBKDF2 once per salt, giving a random key
+/// that then stands in as the passphrase of each value, with a single
+/// round: every value still gets its own salt, key and IV, at the cost of
+/// one short openssl run. Values are written as `enc:v1:` and the hex of
+/// the salt, the `openssl enc` output and an HMAC over that output and the
+/// value's context, so one cannot be swapped for another unnoticed.
+///
+/// New values take the salt of those already read, so a data file keeps to
+/// one salt and PBKDF2 runs once per process (see [`DERIVED`]).
+pub struct FieldCipher {
+    cipher: Cipher,
+    /// The salt new values are written under: the first one read, else
+    /// picked on first use.
+    salt: Option<Vec<u8>>,
+}
+
+/// What PBKDF2 gives for one salt.
+#[derive(Clone)]
+struct FieldKeys {
+    cipher: Cipher,
+    mac: Vec<u8>,
+}
+
+/// The keys derived so far in this process, by passphrase and salt, so
+/// reading the data file again (as the daemon and the shell do) is quick.
+static DERIVED: Mutex<Vec<(String, Vec<u8>, FieldKeys)>> = Mutex::new(Vec::new());
+
+impl FieldCipher {
+    pub fn new(passphrase: String) -> Self {
+        FieldCipher {
+            cipher: Cipher::new(passphrase),
+            salt: None,
+        }
+    }
+
+    /// Whether `value` is something [`FieldCipher::encrypt`] wrote.
+    pub fn is_encrypted(value: &str) -> bool {
+        value.starts_with(FIELD_PREFIX)
+    }
+
+    /// Whether `value` was written under the salt new values get, so that
+    /// it need not be encrypted again.
+    pub fn is_current(&self, value: &str) -> bool {
+        let salt = value
+            .strip_prefix(FIELD_PREFIX)
+            .and_then(|hex| hex.get(..16))
+            .and_then(digest::from_hex);
+        salt.is_some() && salt == self.salt
+    }
+
+    /// `plain` encrypted, to be decrypted with the same `context`.
+    pub fn encrypt(&mut self, context: &str, plain: &str) -> Result<String> {
+        let salt = self
+            .salt
+            .get_or_insert_with(|| random_u64().to_be_bytes().to_vec())
+            .clone();
+        let keys = self.keys(&salt)?;
+        let sealed = keys.cipher.enc(&["-e", "-salt"], plain.as_bytes())?;
+        let tag = digest::hmac_sha256(&keys.mac, &[context.as_bytes(), &sealed].concat());
+        Ok(format!(
+            "{FIELD_PREFIX}{}",
+            digest::to_hex(&[&salt[..], &sealed, &tag].concat())
+        ))
+    }
+
+    /// What [`FieldCipher::encrypt`] was given for `context`, checking the
+    /// tag before decrypting anything.
+    pub fn decrypt(&mut self, context: &str, value: &str) -> Result<String> {
+        let malformed = || Error::Invalid(format!("the encrypted {context} is damaged"));
+        let bytes = value
+            .strip_prefix(FIELD_PREFIX)
+            .and_then(digest::from_hex)
+            .filter(|bytes| bytes.len() > 8 + digest::TAG_LEN)
+            .ok_or_else(malformed)?;
+        let (salt, signed) = bytes.split_at(8);
+        let keys = self.keys(salt)?;
+        let sealed = digest::verify(&keys.mac, &[context.as_bytes(), signed].concat())
+            .map(|data| data[context.len()..].to_vec())
+            .map_err(|_| {
+                Error::Invalid(format!(
+                    "the encrypted {context} failed authentication; is the passphrase right?"
+                ))
+            })?;
+        let plain = keys.cipher.enc(&["-d"], &sealed)?;
+        self.salt.get_or_insert_with(|| salt.to_vec());
+        String::from_utf8(plain).map_err(|_| malformed())
+    }
+
+    fn keys(&self, salt: &[u8]) -> Result<FieldKeys> {
+        let mut derived = DERIVED.lock().unwrap_or_else(|e| e.into_inner());
+        let passphrase = &self.cipher.passphrase;
+        if let Some((_, _, keys)) = derived
+            .iter()
+            .find(|(p, s, _)| p == passphrase && s == salt)
+        {
+            return Ok(keys.clone());
+        }
+        let key = self.cipher.derive(salt, "sha256")?;
+        let keys = FieldKeys {
+            cipher: Cipher {
+                passphrase: digest::to_hex(&key),
+                iterations: "1",
+            },
+            mac: self.cipher.mac_key(salt)?,
+        };
+        derived.push((passphrase.clone(), salt.to_vec(), keys.clone()));
+        Ok(keys)
+    }
+}
+
+/// The salt `openssl enc` wrote after its magic.
+fn salt(sealed: &[u8]) -> Result<&[u8]> {
+    sealed
+        .strip_prefix(MAGIC)
+        .and_then(|rest| rest.get(..8))
+        .ok_or_else(|| Error::Invalid("the data is not openssl-encrypted".into()))
+}
@@ -0,0 +1,300 @@
+//! `daemon`: a long-running process that fires reminders, runs due
+//! templates, turns new mail into tasks and backs up the data file,
+//! controlled through a Unix socket next to the data file
+//! (`tasks.json.sock`).
+//!
+//! The daemon re-reads the data file on every check, so commands run in the
+//! meantime are picked up; it only writes the file when `tick` created
+//! tasks, mail came in or a rule changed one.
+
+#[cfg(unix)]
+use std::path::PathBuf;
+
+use task_manager::{Error, Result};
+
+use super::{Args, Context};
+
+/// The control socket for `ctx`'s data file, unless `--socket` names one.
+#[cfg(unix)]
+fn socket_path(ctx: &Context, args: &mut Args) -> Result<PathBuf> {
+    Ok(match args.value("--socket")? {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let mut path = ctx.data_path().as_os_str().to_owned();
+            path.push(".sock");
+            PathBuf::from(path)
+        }
+    })
+}
+
+#[cfg(not(unix))]
+pub fn daemon(_ctx: &mut Context, _args: Args) -> Result<()> {
+    Err(Error::Invalid(
+        "the daemon needs Unix sockets and is not available on this platform".into(),
+    ))
+}
+
+#[cfg(unix)]
+pub fn daemon(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let socket = socket_path(ctx, &mut args)?;
+    match args.positional().as_deref() {
+        None | Some("run") => {
+            args.finish()?;
+            unix::run(ctx, &socket)
+        }
+        Some(command @ ("status" | "reload" | "stop")) => {
+            args.finish()?;
+            print!("{}", unix::send(&socket, command)?);
+            Ok(())
+        }
+        Some(other) => Err(Error::Invalid(format!(
+            "unknown daemon command `{other}` (expected run, status, reload or stop)"
+        ))),
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::fs;
+    use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::process::{self, Command};
+    use std::thread;
+    use std::time::Duration;
+
+    use task_manager::dates::Timestamp;
+    use task_manager::schedule;
+    use task_manager::storage::JsonStore;
+    use task_manager::{Error, Result};
+
+    use super::super::{mail, Context};
+
+    /// How often the socket is polled between checks.
+    const POLL: Duration = Duration::from_millis(200);
+
+    struct State {
+        started: Timestamp,
+        last_check: Timestamp,
+        last_backup: Option<Timestamp>,
+    }
+
+    enum Control {
+        Continue,
+        Stop,
+    }
+
+    pub fn run(ctx: &mut Context, socket: &Path) -> Result<()> {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(Error::Conflict(format!(
+                "a daemon is already listening on {}",
+                socket.display()
+            )));
+        }
+        // Left over from a daemon that did not shut down cleanly.
+        let _ = fs::remove_file(socket);
+        let listener = UnixListener::bind(socket)?;
+        listener.set_nonblocking(true)?;
+        let now = Timestamp::now();
+        let mut state = State {
+            started: now,
+            last_check: now,
+            last_backup: None,
+        };
+        log(&format!(
+            "daemon started (pid {}), listening on {}",
+            process::id(),
+            socket.display()
+        ));
+        let mut next_check = now;
+        let result = loop {
+            match listener.accept() {
+                Ok((stream, _)) => match handle(ctx, &mut state, stream) {
+                    Ok(Control::Stop) => break Ok(()),
+                    Ok(Control::Continue) => {}
+                    Err(err) => log(&format!("control: {err}")),
+                },
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {}
+                Err(err) => break Err(err.into()),
+            }
+            let now = Timestamp::now();
+            if now >= next_check {
+                if let Err(err) = check(ctx, &mut state, now) {
+                    log(&format!("error: {err}"));
+                }
+                next_check = now.add_secs(ctx.config.daemon.interval as i64);
+            }
+            thread::sleep(POLL);
+        };
+        let _ = fs::remove_file(socket);
+        log("daemon stopped");
+        result
+    }
+
+    /// Sends one command to a running daemon and returns its reply.
+    pub fn send(socket: &Path, command: &str) -> Result<String> {
+        let mut stream = UnixStream::connect(socket).map_err(|err| match err.kind() {
+            ErrorKind::NotFound | ErrorKind::ConnectionRefused => Error::NotFound(format!(
+                "no daemon is running (no socket at {})",
+                socket.display()
+            )),
+            _ => err.into(),
+        })?;
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        writeln!(stream, "{command}")?;
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply)?;
+        Ok(reply)
+    }
+
+    fn handle(ctx: &mut Context, state: &mut State, stream: UnixStream) -> Result<Control> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+        let mut line = String::new();
+        // Another instance checking whether we are alive sends nothing.
+        if BufReader::new(&stream).read_line(&mut line)? == 0 {
+            return Ok(Control::Continue);
+        }
+        let (reply, control) = match line.trim() {
+            "status" => (status(ctx, state), Control::Continue),
+            "reload" => match ctx.reload() {
+                Ok(()) => ("reloaded\n".to_string(), Control::Continue),
+                Err(err) => (format!("error: {err}\n"), Control::Continue),
+            },
+            "stop" => ("stopping\n".to_string(), Control::Stop),
+            other => (
+                format!("error: unknown command `{other}`\n"),
+                Control::Continue,
+            ),
+        };
+        (&stream).write_all(reply.as_bytes())?;
+        Ok(control)
+    }
+
+    fn status(ctx: &Context, state: &State) -> String {
+        let next_reminder = ctx
+            .manager
+            .tasks()
+            .iter()
+            .filter(|t| t.status.is_open())
+            .flat_map(|t| t.reminder_times())
+            .filter(|&t| t > state.last_check)
+            .min();
+        let mut out = format!(
+            "running since {} (pid {})\n{} tasks, {} templates\nlast check {}\n",
+            state.started,
+            process::id(),
+            ctx.manager.len(),
+            ctx.manager.templates().len(),
+            state.last_check
+        );
+        if let Some(next) = next_reminder {
+            out.push_str(&format!("next reminder {next}\n"));
+        }
+        if let Some(backup) = state.last_backup {
+            out.push_str(&format!("last backup {backup}\n"));
+        }
+        out
+    }
+
+    /// One round: pick up changes on disk, run due templates, take new mail
+    /// and run the rules, fire reminders that came due since the last round
+    /// and make a backup if one is due.
+    fn check(ctx: &mut Context, state: &mut State, now: Timestamp) -> Result<()> {
+        // A command holding the lock may be waiting for its user; the next
+        // round catches up.
+        let Some(_lock) = JsonStore::new(ctx.data_path()).lock(false)? else {
+            log("the tasks are being changed; skipped this check");
+            return Ok(());
+        };
+        ctx.reload()?;
+        let runs = schedule::tick(&mut ctx.manager, now)?;
+        for run in &runs {
+            log(&format!(
+                "created a task from template \"{}\"",
+                run.template
+            ));
+        }
+        if !runs.is_empty() {
+            ctx.modified();
+        }
+        // Mail that cannot be read is tried again next round, without
+        // holding up the rest of this one.
+        match mail::Inbox::from_settings(&ctx.config.mail, &ctx.layers()?) {
+            Ok(Some(inbox)) => match mail::poll(ctx, &inbox) {
+                Ok(names) => {
+                    for name in names {
+                        log(&format!("created a task from mail: {name}"));
+                    }
+                }
+                Err(err) => log(&format!("could not take mail from {inbox}: {err}")),
+            },
+            Ok(None) => {}
+            Err(err) => log(&format!("could not take mail: {err}")),
+        }
+        for line in ctx.apply_rules(now)? {
+            log(&format!("applied {line}"));
+        }
+        ctx.save_if_modified()?;
+
+        for task in ctx.manager.tasks().iter().filter(|t| t.status.is_open()) {
+            let Some(due) = task.due else {
+                continue;
+            };
+            for at in task.reminder_times() {
+                if at > state.last_check && at <= now {
+                    remind(ctx, &format!("\"{}\" is due {due}", task.name));
+                }
+            }
+        }
+        state.last_check = now;
+
+        if let Some(every) = ctx.config.daemon.backup_every {
+            let due = state
+                .last_backup
+                .is_none_or(|last| now.secs() - last.secs() >= every);
+            // Directory stores are meant to be kept in git instead.
+            if due && ctx.data_path().is_file() {
+                // Apart from the `.bak` that `restore` keeps.
+                let mut backup = ctx.data_path().as_os_str().to_owned();
+                backup.push(".daemon.bak");
+                fs::copy(ctx.data_path(), &backup)?;
+                state.last_backup = Some(now);
+                log(&format!("backed up to {}", Path::new(&backup).display()));
+            }
+        }
+        Ok(())
+    }
+
+    fn remind(ctx: &Context, message: &str) {
+        log(&format!("reminder: {message}"));
+        let settings = &ctx.config.daemon;
+        if settings.bell {
+            print!("\x07");
+            let _ = io::stdout().flush();
+        }
+        if let Some(sound) = &settings.sound {
+            let mut words = settings.sound_player.split_whitespace();
+            let program = words.next().expect("sound player is not empty");
+            match Command::new(program).args(words).arg(sound).spawn() {
+                // Reaped in the background so a long sound does not hold up
+                // the next check.
+                Ok(mut child) => {
+                    thread::spawn(move || child.wait());
+                }
+                Err(err) => log(&format!("could not run `{}`: {err}", settings.sound_player)),
+            }
+        }
+        if let Some(command) = &ctx.config.daemon.notify {
+            let mut words = command.split_whitespace();
+            let program = words.next().expect("notify command is not empty");
+            if let Err(err) = Command::new(program).args(words).arg(message).status() {
+                log(&format!("could not run `{command}`: {err}"));
+            }
+        }
+    }
+
+    fn log(message: &str) {
+        println!("{} {message}", Timestamp::now());
+    }
+}
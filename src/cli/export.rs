@@ -0,0 +1,285 @@
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+
+use task_manager::anonymize::Anonymizer;
+use task_manager::config::Operation;
+use task_manager::dates::Timestamp;
+use task_manager::export::graph::{self, Diagram, GraphFormat};
+use task_manager::export::site::{self, CALENDAR_FILE, PROJECTS_DIR};
+use task_manager::export::{self, escpos, html, ics, Codec};
+use task_manager::stats::Today;
+use task_manager::{filter, render};
+use task_manager::{Error, Result, Task};
+
+use super::tasks;
+use super::{Args, Context};
+
+pub fn export(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let html_path = args.value("--html")?;
+    let ics_path = args.value("--ics")?;
+    let json_path = args.value("--json")?;
+    let csv_path = args.value("--csv")?;
+    let markdown_path = args.value("--markdown")?;
+    let mermaid_path = args.value("--mermaid")?;
+    let toml_path = args.value("--toml")?;
+    let yaml_path = args.value("--yaml")?;
+    let taskwarrior_path = args.value("--taskwarrior")?;
+    let diagram = match args.value("--diagram")?.as_deref() {
+        None | Some("flowchart") => Diagram::Flowchart,
+        Some("state") => Diagram::State,
+        Some(other) => {
+            return Err(Error::Invalid(format!(
+                "unknown diagram `{other}` (expected flowchart or state)"
+            )))
+        }
+    };
+    let expression = args.value("--filter")?;
+    let anonymize = args.flag("--anonymize");
+    let include_private = args.flag("--include-private");
+    let force = args.flag("--force");
+    args.finish()?;
+    let now = Timestamp::now();
+    // Parsed before anything is written so a typo leaves no empty file.
+    let filter = expression
+        .map(|expression| filter::parse(&expression, now))
+        .transpose()?;
+    let outputs = [
+        (Format::Html, html_path),
+        (Format::Ics, ics_path),
+        (Format::Data(Codec::Json), json_path),
+        (Format::Data(Codec::Csv), csv_path),
+        (Format::Markdown, markdown_path),
+        (Format::Mermaid, mermaid_path),
+        (Format::Data(Codec::Toml), toml_path),
+        (Format::Data(Codec::Yaml), yaml_path),
+        (Format::Data(Codec::Taskwarrior), taskwarrior_path),
+    ];
+    let mut chosen = outputs
+        .into_iter()
+        .filter_map(|(format, path)| Some((format, path?)));
+    let (format, path) = match (chosen.next(), chosen.next()) {
+        (Some(output), None) => output,
+        _ => return Err(Error::Invalid(
+            "choose one output: `--html <file>`, `--ics <file>`, `--json <file>`, `--csv <file>`, `--toml <file>`, `--yaml <file>`, `--taskwarrior <file>`, `--markdown <file>` or `--mermaid <file>`"
+                .into(),
+        )),
+    };
+    if Path::new(&path).exists()
+        && !ctx.confirm(Operation::Overwrite, force, &format!("Overwrite {path}?"))
+    {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    let tasks: Vec<&Task> = match &filter {
+        Some(filter) => filter.apply(ctx.manager.tasks(), now),
+        None => ctx.manager.tasks().iter().collect(),
+    };
+    let tasks = shareable(tasks, include_private);
+    let anonymizer = anonymize.then(Anonymizer::new);
+    match format {
+        Format::Html | Format::Ics | Format::Markdown | Format::Mermaid => {
+            let copies: Vec<Task> = match &anonymizer {
+                Some(anonymizer) => tasks.iter().map(|t| anonymizer.task(t)).collect(),
+                None => Vec::new(),
+            };
+            let tasks: Vec<&Task> = match &anonymizer {
+                Some(_) => copies.iter().collect(),
+                None => tasks.clone(),
+            };
+            let contents = match format {
+                Format::Html => html::render(&tasks, now, &ctx.config.tags, &ctx.config.glyphs),
+                Format::Ics => ics::render(&tasks, now),
+                Format::Mermaid => graph::overview(&tasks, diagram, &ctx.config.glyphs),
+                _ => render::markdown(&tasks),
+            };
+            fs::write(&path, contents)?;
+        }
+        // Streamed one task at a time; large lists are never held twice.
+        Format::Data(codec) => {
+            let mut out = BufWriter::new(File::create(&path)?);
+            let rows = tasks.iter().map(|&task| match &anonymizer {
+                Some(anonymizer) => Cow::Owned(anonymizer.task(task)),
+                None => Cow::Borrowed(task),
+            });
+            codec.write(&mut out, rows)?;
+        }
+    }
+    println!("Exported {} tasks to {path}", tasks.len());
+    Ok(())
+}
+
+/// Prints today's agenda, as `today` shows it, on a receipt printer: a
+/// device file such as `/dev/usb/lp0`, or `tcp://host[:port]` for one on
+/// the network (port 9100 unless given).
+pub fn print(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let printer = args.value("--printer")?;
+    let width = match args.value("--width")? {
+        Some(width) => width
+            .parse()
+            .map_err(|_| Error::Invalid(format!("`{width}` is not a line width")))?,
+        None => ctx.config.printer.width.unwrap_or(escpos::DEFAULT_WIDTH),
+    };
+    args.finish()?;
+    let printer = printer
+        .or_else(|| ctx.config.printer.device.clone())
+        .ok_or_else(|| {
+            Error::Invalid(
+                "set `device` under [printer] or pass `--printer <device | tcp://host>`".into(),
+            )
+        })?;
+    let now = Timestamp::now();
+    let view = tasks::today_view(ctx);
+    let today = Today::of(&view, now);
+    let receipt = escpos::receipt(&today, now, width);
+    match printer.strip_prefix("tcp://") {
+        Some(host) => {
+            let address = if host
+                .rsplit_once(':')
+                .is_some_and(|(_, port)| !port.contains(']'))
+            {
+                host.to_string()
+            } else {
+                format!("{host}:9100")
+            };
+            TcpStream::connect(&address)
+                .map_err(|err| Error::Invalid(format!("could not reach {address}: {err}")))?
+                .write_all(&receipt)?;
+        }
+        None => File::create(&printer)?.write_all(&receipt)?,
+    }
+    let count = today.overdue.len() + today.due_today.len() + today.high_priority.len();
+    println!("Printed {count} tasks to {printer}");
+    Ok(())
+}
+
+/// `tasks` without the private ones, unless `include_private` was asked for.
+fn shareable(mut tasks: Vec<&Task>, include_private: bool) -> Vec<&Task> {
+    let hidden = tasks.iter().filter(|t| t.private).count();
+    if hidden > 0 && !include_private {
+        tasks.retain(|t| !t.private);
+        eprintln!("Left out {hidden} private task(s); pass --include-private to keep them.");
+    }
+    tasks
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Html,
+    Ics,
+    Markdown,
+    Mermaid,
+    /// A format that `import` reads back.
+    Data(Codec),
+}
+
+/// Runs every task through each format [`Codec`] knows and back, and lists
+/// any field that came back different. Nothing is written to disk.
+pub fn verify_roundtrip(ctx: &mut Context, args: Args) -> Result<()> {
+    args.finish()?;
+    let tasks = ctx.manager.tasks();
+    let mut failed = Vec::new();
+    for codec in Codec::ALL {
+        let differences = match export::round_trip(codec, tasks) {
+            Ok(differences) => differences,
+            Err(err) => {
+                println!("{codec:<12} cannot read its own output: {err}");
+                failed.push(codec.as_str());
+                continue;
+            }
+        };
+        if differences.is_empty() {
+            println!("{codec:<12} ok ({} tasks)", tasks.len());
+            continue;
+        }
+        println!("{codec:<12} {} difference(s)", differences.len());
+        for difference in &differences {
+            println!("  {difference}");
+        }
+        failed.push(codec.as_str());
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Invalid(format!(
+            "round trip changed tasks in {}",
+            failed.join(", ")
+        )))
+    }
+}
+
+pub fn graph(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let format = match args.value("--format")?.as_deref() {
+        None | Some("dot") => GraphFormat::Dot,
+        Some("mermaid") => GraphFormat::Mermaid,
+        Some(other) => {
+            return Err(Error::Invalid(format!(
+                "unknown graph format `{other}` (expected dot or mermaid)"
+            )))
+        }
+    };
+    let now = Timestamp::now();
+    let filter = filter::parse(&args.rest()?.join(" "), now)?;
+    let tasks = filter.apply(ctx.manager.tasks(), now);
+    print!("{}", graph::render(&tasks, format, &ctx.config.glyphs));
+    Ok(())
+}
+
+/// Writes the static site to a directory. Only files whose contents changed
+/// are rewritten, and project pages and calendar feeds left over from
+/// removed projects are deleted.
+pub fn publish(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let expression = args.value("--filter")?;
+    let include_private = args.flag("--include-private");
+    let dir = PathBuf::from(args.required("output directory")?);
+    args.finish()?;
+    let now = Timestamp::now();
+    let tasks: Vec<&Task> = match expression {
+        Some(expression) => filter::parse(&expression, now)?.apply(ctx.manager.tasks(), now),
+        None => ctx.manager.tasks().iter().collect(),
+    };
+    let tasks = shareable(tasks, include_private);
+    let pages = site::build(&tasks, now, &ctx.config.tags, &ctx.config.glyphs);
+    let projects = dir.join(PROJECTS_DIR);
+    fs::create_dir_all(&projects)?;
+    let mut changed = 0;
+    for page in &pages {
+        let path = dir.join(&page.path);
+        if fs::read(&path).ok().as_deref() != Some(page.contents.as_bytes()) {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, &page.contents)?;
+            changed += 1;
+        }
+    }
+    let mut removed = 0;
+    for entry in fs::read_dir(&projects)? {
+        let path = entry?.path();
+        let name = format!(
+            "{PROJECTS_DIR}/{}",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        );
+        let published = |name: &str| pages.iter().any(|page| page.path == name);
+        if path.extension().is_some_and(|e| e == "html") && !published(&name) {
+            fs::remove_file(&path)?;
+            removed += 1;
+        }
+        // Calendar feeds of projects that lost their dated tasks.
+        let calendar = path.join(CALENDAR_FILE);
+        if calendar.is_file() && !published(&format!("{name}/{CALENDAR_FILE}")) {
+            fs::remove_file(&calendar)?;
+            // Left in place if something else was put there.
+            let _ = fs::remove_dir(&path);
+            removed += 1;
+        }
+    }
+    println!(
+        "Published {} files to {} ({changed} changed, {removed} removed)",
+        pages.len(),
+        dir.display()
+    );
+    Ok(())
+}
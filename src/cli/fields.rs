@@ -0,0 +1,123 @@
+//! `encryption.fields`: descriptions and notes kept encrypted in the data
+//! file while names, dates, tags and the rest stay plain, so filters, other
+//! tools and `grep` still work on them.
+//!
+//! With the passphrase the fields are decrypted as the file is read and
+//! encrypted again as it is written, which needs `openssl` (see
+//! [`FieldCipher`]). Without it they stay encrypted, shown as stored, and
+//! only fields left as they were can be saved.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::env;
+use std::mem;
+
+use task_manager::config::EncryptionSettings;
+use task_manager::{Error, Result, Task, TaskId, TasksManager};
+
+use super::crypto::FieldCipher;
+
+const PASSPHRASE_VAR: &str = "TASK_MANAGER_PASSPHRASE";
+
+pub struct Fields {
+    cipher: Option<FieldCipher>,
+    /// Whether writing encrypts; decrypting needs only the passphrase.
+    encrypt: bool,
+    /// Each encrypted field's text and what was stored for it, so a save
+    /// only encrypts the fields that changed.
+    stored: HashMap<(TaskId, &'static str), (String, String)>,
+}
+
+impl Fields {
+    pub fn new(settings: &EncryptionSettings) -> Self {
+        let passphrase = settings
+            .passphrase
+            .clone()
+            .or_else(|| env::var(PASSPHRASE_VAR).ok())
+            .filter(|p| !p.is_empty());
+        Fields {
+            cipher: passphrase.map(FieldCipher::new),
+            encrypt: settings.fields,
+            stored: HashMap::new(),
+        }
+    }
+
+    /// Decrypts the fields of `manager` in place, if the passphrase is set.
+    pub fn open(&mut self, manager: &mut TasksManager) -> Result<()> {
+        let Some(cipher) = &mut self.cipher else {
+            return Ok(());
+        };
+        for task in manager.tasks_mut() {
+            let Task {
+                id,
+                description,
+                body,
+                ..
+            } = task;
+            for (field, value) in [("description", description), ("notes", body)] {
+                if FieldCipher::is_encrypted(value) {
+                    let plain = cipher.decrypt(&context(*id, field), value)?;
+                    let stored = mem::replace(value, plain.clone());
+                    self.stored.insert((*id, field), (plain, stored));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `manager` as it is to be written: with its fields encrypted when
+    /// `encryption.fields` is set.
+    pub fn seal<'a>(&mut self, manager: &'a TasksManager) -> Result<Cow<'a, TasksManager>> {
+        if !self.encrypt {
+            return Ok(Cow::Borrowed(manager));
+        }
+        let mut sealed = manager.clone();
+        let mut stored = HashMap::new();
+        for task in sealed.tasks_mut() {
+            let Task {
+                id,
+                name,
+                description,
+                body,
+                ..
+            } = task;
+            for (field, value) in [("description", description), ("notes", body)] {
+                // Without the passphrase nothing was decrypted, so that
+                // is what an encrypted-looking field must be.
+                let kept = self.cipher.is_none() && FieldCipher::is_encrypted(value);
+                if value.is_empty() || kept {
+                    continue;
+                }
+                let current = |encrypted: &str| {
+                    self.cipher
+                        .as_ref()
+                        .is_some_and(|c| c.is_current(encrypted))
+                };
+                let encrypted = match self.stored.get(&(*id, field)) {
+                    // Ones under an older salt are encrypted again, so the
+                    // file keeps to one and opening it to one PBKDF2 run.
+                    Some((plain, encrypted)) if plain == value && current(encrypted) => {
+                        encrypted.clone()
+                    }
+                    _ => {
+                        let cipher = self.cipher.as_mut().ok_or_else(|| {
+                            Error::Invalid(format!(
+                                "set `encryption.passphrase` or {PASSPHRASE_VAR} to save the {field} of \"{name}\" encrypted"
+                            ))
+                        })?;
+                        cipher.encrypt(&context(*id, field), value)?
+                    }
+                };
+                let plain = mem::replace(value, encrypted.clone());
+                stored.insert((*id, field), (plain, encrypted));
+            }
+        }
+        self.stored = stored;
+        Ok(Cow::Owned(sealed))
+    }
+}
+
+/// What a field's tag covers besides its text, tying it to its task.
+fn context(id: TaskId, name: &str) -> String {
+    format!("{name} of task {}", id.to_hex())
+}
@@ -0,0 +1,233 @@
+//! Keeping the data file under git: a commit after every change when
+//! `git.auto_commit` is set, and `history` and `rollback` on top of the log.
+//!
+//! Only the data file (or directory store) is ever added or committed, so
+//! it can live in a repository next to other work.
+
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use task_manager::config::{GitSettings, Operation};
+use task_manager::dates::Timestamp;
+use task_manager::{Error, Result, Task};
+
+use super::{Args, Context};
+
+/// Commits `data` with `message`, creating a repository beside it first if
+/// there is none, and pushes when configured. Does nothing when the data
+/// did not change.
+pub fn commit(data: &Path, message: &str, settings: &GitSettings) -> Result<()> {
+    let (dir, name) = locate(data);
+    if run(dir, ["rev-parse", "--is-inside-work-tree"]).is_err() {
+        git(dir, ["init", "-q"])?;
+    }
+    git(dir, [OsStr::new("add"), "-A".as_ref(), "--".as_ref(), name])?;
+    let unchanged = run(
+        dir,
+        [
+            OsStr::new("diff"),
+            "--cached".as_ref(),
+            "--quiet".as_ref(),
+            "--".as_ref(),
+            name,
+        ],
+    )
+    .is_ok();
+    if unchanged {
+        return Ok(());
+    }
+    git(
+        dir,
+        [
+            OsStr::new("commit"),
+            "-q".as_ref(),
+            "-m".as_ref(),
+            message.as_ref(),
+            "--".as_ref(),
+            name,
+        ],
+    )?;
+    if settings.auto_push {
+        match &settings.remote {
+            Some(remote) => git(dir, ["push", "-q", remote, "HEAD"])?,
+            None => git(dir, ["push", "-q"])?,
+        };
+    }
+    Ok(())
+}
+
+/// A commit message for the change from `before` to `after`: what happened
+/// to which tasks, then the command that did it.
+pub fn message(before: &[Task], after: &[Task], command: &str) -> String {
+    let find = |tasks: &'_ [Task], task: &Task| tasks.iter().any(|t| t.id == task.id);
+    let added: Vec<&Task> = after.iter().filter(|t| !find(before, t)).collect();
+    let removed: Vec<&Task> = before.iter().filter(|t| !find(after, t)).collect();
+    let changed: Vec<&Task> = after
+        .iter()
+        .filter(|t| before.iter().any(|b| b.id == t.id && b != *t))
+        .collect();
+    let subject = match (added.as_slice(), changed.as_slice(), removed.as_slice()) {
+        ([task], [], []) => format!("Add \"{}\"", task.name),
+        ([], [task], []) => format!("Update \"{}\"", task.name),
+        ([], [], [task]) => format!("Remove \"{}\"", task.name),
+        ([], [], []) => "Update task data".to_string(),
+        _ => {
+            let mut parts = Vec::new();
+            for (verb, count) in [
+                ("add", added.len()),
+                ("update", changed.len()),
+                ("remove", removed.len()),
+            ] {
+                if count > 0 {
+                    parts.push(format!("{verb} {count}"));
+                }
+            }
+            let mut subject = format!("{} tasks", parts.join(", "));
+            subject[..1].make_ascii_uppercase();
+            subject
+        }
+    };
+    format!("{subject}\n\ntask-manager {command}\n")
+}
+
+pub fn history(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let count = match args.value("-n")? {
+        Some(n) => n
+            .parse::<usize>()
+            .map_err(|_| Error::Invalid(format!("`-n` needs a number, found `{n}`")))?,
+        None => 20,
+    };
+    args.finish()?;
+    let (dir, name) = locate(ctx.data_path());
+    repository(ctx, dir)?;
+    let log = git(
+        dir,
+        [
+            OsStr::new("log"),
+            format!("-n{count}").as_ref(),
+            "--format=%h%x09%at%x09%s".as_ref(),
+            "--".as_ref(),
+            name,
+        ],
+    )?;
+    if log.trim().is_empty() {
+        println!("No commits of {} yet.", ctx.data_path().display());
+        return Ok(());
+    }
+    for line in log.lines() {
+        let mut fields = line.splitn(3, '\t');
+        let (Some(hash), Some(Ok(time)), Some(subject)) =
+            (fields.next(), fields.next().map(str::parse), fields.next())
+        else {
+            continue;
+        };
+        println!("{hash}  {}  {subject}", Timestamp::from_secs(time));
+    }
+    Ok(())
+}
+
+pub fn rollback(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let force = args.flag("--force");
+    let revision = args.required("commit")?;
+    args.finish()?;
+    let data = ctx.data_path().to_path_buf();
+    let (dir, name) = locate(&data);
+    repository(ctx, dir)?;
+    let mut spec = revision.clone();
+    spec.push_str(":./");
+    spec.push_str(&name.to_string_lossy());
+    if run(dir, ["cat-file", "-e", &spec]).is_err() {
+        return Err(Error::NotFound(format!(
+            "commit `{revision}` has no {}",
+            data.display()
+        )));
+    }
+    let target = git(dir, ["log", "-1", "--format=%h %s", &revision])?;
+    let target = target.trim();
+    let question = format!("Roll {} back to {target}?", data.display());
+    if !ctx.confirm(Operation::Overwrite, force, &question) {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    if data.is_dir() {
+        // Files added since then must go too.
+        git(
+            dir,
+            [
+                OsStr::new("rm"),
+                "-r".as_ref(),
+                "-q".as_ref(),
+                "--ignore-unmatch".as_ref(),
+                "--".as_ref(),
+                name,
+            ],
+        )?;
+    }
+    git(
+        dir,
+        [
+            OsStr::new("checkout"),
+            "-q".as_ref(),
+            revision.as_ref(),
+            "--".as_ref(),
+            name,
+        ],
+    )?;
+    commit(&data, &format!("Roll back to {target}\n"), &ctx.config.git)?;
+    ctx.reload()?;
+    println!("Rolled back to {target}; {} tasks now", ctx.manager.len());
+    Ok(())
+}
+
+/// The directory to run git in and the data file's name within it.
+fn locate(data: &Path) -> (&Path, &OsStr) {
+    let dir = match data.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    (dir, data.file_name().unwrap_or(data.as_os_str()))
+}
+
+fn repository(ctx: &Context, dir: &Path) -> Result<()> {
+    run(dir, ["rev-parse", "--is-inside-work-tree"])
+        .map(|_| ())
+        .map_err(|_| {
+            Error::NotFound(format!(
+                "{} is not in a git repository; set `git.auto_commit = true` to start one",
+                ctx.data_path().display()
+            ))
+        })
+}
+
+/// Runs git in `dir` and returns its output, or its first line of errors.
+fn git<I, S>(dir: &Path, args: I) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = run(dir, args)?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn run<I, S>(dir: &Path, args: I) -> Result<Output>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| Error::Invalid(format!("could not run git: {err}")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Invalid(format!(
+            "git failed: {}",
+            stderr.lines().next().unwrap_or("no output").trim()
+        )));
+    }
+    Ok(output)
+}
@@ -0,0 +1,445 @@
+//! `sync google`: keeping the tasks and Google Tasks in step.
+//!
+//! Each task list stands for the project of the same name. Tasks without
+//! a counterpart on the other side are copied across and linked; for a
+//! linked pair that differs, the side changed last wins, its name, notes,
+//! due date and completion copied to the other. Signing in uses OAuth's
+//! device flow: the first sync prints a code to enter in a browser, and
+//! the tokens are kept beside the config file so later syncs only refresh
+//! them. The API is reached through `curl` (see [`http`](super::http)).
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use task_manager::config::{GoogleSettings, Layers};
+use task_manager::dates::Timestamp;
+use task_manager::json::{self, Value};
+use task_manager::render::short_id;
+use task_manager::{Error, Result, Status, Task};
+
+use super::http::{required, Client};
+use super::Context;
+
+const API: &str = "https://tasks.googleapis.com/tasks/v1";
+const OAUTH: &str = "https://oauth2.googleapis.com";
+
+/// Reading and writing tasks, nothing else of the account.
+const SCOPE: &str = "https://www.googleapis.com/auth/tasks";
+
+pub fn sync(ctx: &mut Context, dry_run: bool) -> Result<()> {
+    let token = access_token(&ctx.config.google, &ctx.layers()?, &token_path(ctx))?;
+    let client = Client::new("Google").header(&format!("Authorization: Bearer {token}"));
+    let lists = lists(&client)?;
+    let Some(first) = lists.first() else {
+        return Err(Error::Invalid("Google Tasks has no task lists".into()));
+    };
+    let mut items = Vec::new();
+    for list in &lists {
+        items.extend(list.items(&client)?);
+    }
+    let user = ctx.config.user_name();
+    let (mut added, mut pulled, mut created, mut pushed, mut failed) = (0, 0, 0, 0, 0);
+    for item in &items {
+        let link = item.link();
+        let linked = ctx
+            .manager
+            .tasks()
+            .iter()
+            .position(|t| t.google.as_deref() == Some(link.as_str()));
+        let Some(index) = linked else {
+            let task = item.to_task();
+            if dry_run {
+                println!("+ {}", task.name);
+            } else {
+                let name = task.name.clone();
+                let id = ctx.manager.add(task);
+                println!("+ {name} ({})", short_id(&id.to_string()));
+            }
+            added += 1;
+            continue;
+        };
+        let task = &mut ctx.manager.tasks_mut()[index];
+        if item.matches(task) {
+            continue;
+        }
+        if item.updated > task.updated_at {
+            let action = if dry_run { "would update" } else { "updated" };
+            println!("{}: {action} here from Google Tasks", task.name);
+            if !dry_run {
+                item.apply(task, &user);
+            }
+            pulled += 1;
+            continue;
+        }
+        let result = if dry_run {
+            Ok(Value::Null)
+        } else {
+            client.request("PATCH", &item.url(), Some(&body(task)))
+        };
+        if let Err(err) = result {
+            eprintln!("warning: {}: {err}", task.name);
+            failed += 1;
+            continue;
+        }
+        let action = if dry_run { "would update" } else { "updated" };
+        println!("{}: {action} in Google Tasks", task.name);
+        pushed += 1;
+    }
+    for task in ctx.manager.tasks_mut() {
+        if task.google.is_some() || !task.status.is_open() || task.archived || task.private {
+            continue;
+        }
+        let list = task
+            .project
+            .as_deref()
+            .and_then(|project| lists.iter().find(|l| l.title.eq_ignore_ascii_case(project)))
+            .unwrap_or(first);
+        if !dry_run {
+            let url = format!("{API}/lists/{}/tasks", list.id);
+            let answer = client.request("POST", &url, Some(&body(task)));
+            match answer.and_then(|a| {
+                a.get("id")
+                    .and_then(Value::as_str)
+                    .map(String::from)
+                    .ok_or_else(|| Error::Invalid("Google sent no id for the new task".into()))
+            }) {
+                Ok(id) => task.google = Some(format!("{}/{id}", list.id)),
+                Err(err) => {
+                    eprintln!("warning: {}: {err}", task.name);
+                    failed += 1;
+                    continue;
+                }
+            }
+        }
+        let action = if dry_run { "would add" } else { "added" };
+        println!(
+            "{}: {action} it to {} in Google Tasks",
+            task.name, list.title
+        );
+        created += 1;
+    }
+    if !dry_run && added + pulled + created > 0 {
+        ctx.modified();
+    }
+    let prefix = if dry_run { "Would sync: " } else { "" };
+    let mut summary = format!(
+        "{prefix}{added} task(s) added here, {pulled} updated here, {created} added to Google Tasks, {pushed} updated there"
+    );
+    if failed > 0 {
+        summary.push_str(&format!(", {failed} failed"));
+    }
+    println!("{summary}, of {} in {} list(s).", items.len(), lists.len());
+    Ok(())
+}
+
+/// A task list, standing for the project of the same name.
+struct List {
+    id: String,
+    title: String,
+}
+
+/// What `sync google` reads of a task in a list.
+struct Item {
+    list: String,
+    /// The list's title, the project of a task made from the item.
+    project: String,
+    id: String,
+    title: String,
+    notes: String,
+    completed: bool,
+    /// The due date, `YYYY-MM-DD`; Google keeps no time of day.
+    due: Option<String>,
+    updated: Timestamp,
+}
+
+impl List {
+    /// The list's tasks, completed and hidden ones included.
+    fn items(&self, client: &Client) -> Result<Vec<Item>> {
+        let url = format!(
+            "{API}/lists/{}/tasks?showCompleted=true&showHidden=true&maxResults=100",
+            self.id
+        );
+        pages(client, &url)?
+            .iter()
+            .filter(|item| item.get("deleted").and_then(Value::as_bool) != Some(true))
+            .map(|item| Item::from_value(item, self))
+            .collect()
+    }
+}
+
+impl Item {
+    fn from_value(item: &Value, list: &List) -> Result<Self> {
+        let text = |key: &str| item.get(key).and_then(Value::as_str).unwrap_or("");
+        if text("id").is_empty() {
+            return Err(Error::Invalid("Google sent a task without an id".into()));
+        }
+        Ok(Item {
+            list: list.id.clone(),
+            project: list.title.clone(),
+            id: text("id").to_string(),
+            title: text("title").to_string(),
+            notes: text("notes").to_string(),
+            completed: text("status") == "completed",
+            due: text("due").get(..10).map(String::from),
+            updated: Timestamp::parse_rfc3339(text("updated"))?,
+        })
+    }
+
+    /// How a task links to the item.
+    fn link(&self) -> String {
+        format!("{}/{}", self.list, self.id)
+    }
+
+    fn url(&self) -> String {
+        format!("{API}/lists/{}/tasks/{}", self.list, self.id)
+    }
+
+    fn name(&self) -> &str {
+        match self.title.trim() {
+            "" => "Untitled",
+            name => name,
+        }
+    }
+
+    fn matches(&self, task: &Task) -> bool {
+        task.name == self.name()
+            && task.description_or_body() == self.notes
+            && due_date(task) == self.due
+            && completed(task) == self.completed
+    }
+
+    fn due(&self) -> Option<Timestamp> {
+        let due = self.due.as_deref()?;
+        Timestamp::parse_rfc3339(&format!("{due}T00:00:00Z")).ok()
+    }
+
+    fn to_task(&self) -> Task {
+        let mut task = Task::new(self.name());
+        task.set_description_or_body(&self.notes);
+        task.project = Some(self.project.clone());
+        task.google = Some(self.link());
+        task.due = self.due();
+        if self.completed {
+            task.set_status(Status::Done);
+        }
+        task
+    }
+
+    /// Copies the item's fields over the task's, completing or reopening
+    /// it to match, and dates the task as changed when the item was so the
+    /// next sync finds them equal.
+    fn apply(&self, task: &mut Task, user: &str) {
+        task.name = self.name().to_string();
+        task.set_description_or_body(&self.notes);
+        task.due = self.due();
+        if self.completed && !completed(task) {
+            task.finish(user);
+        } else if !self.completed && completed(task) {
+            task.set_status(Status::Todo);
+        }
+        task.updated_at = self.updated;
+    }
+}
+
+/// Whether Google Tasks should show the task ticked off. A task awaiting
+/// review is finished as far as its assignee is concerned.
+fn completed(task: &Task) -> bool {
+    matches!(task.status, Status::Done | Status::ReviewPending)
+}
+
+fn due_date(task: &Task) -> Option<String> {
+    task.due.map(|due| due.format("%Y-%m-%d"))
+}
+
+/// The task as Google Tasks writes it.
+fn body(task: &Task) -> Value {
+    let mut body = Value::object();
+    body.insert("title", task.name.as_str());
+    body.insert("notes", task.description_or_body());
+    let status = if completed(task) {
+        "completed"
+    } else {
+        "needsAction"
+    };
+    body.insert("status", status);
+    let due = due_date(task).map(|date| format!("{date}T00:00:00.000Z"));
+    body.insert("due", due);
+    body
+}
+
+fn lists(client: &Client) -> Result<Vec<List>> {
+    pages(client, &format!("{API}/users/@me/lists?maxResults=100"))?
+        .iter()
+        .map(|list| {
+            let text = |key: &str| list.get(key).and_then(Value::as_str).map(String::from);
+            let id = text("id")
+                .ok_or_else(|| Error::Invalid("Google sent a task list without an id".into()))?;
+            Ok(List {
+                id,
+                title: text("title").unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// The `items` of every page of results from `url`.
+fn pages(client: &Client, url: &str) -> Result<Vec<Value>> {
+    let mut items = Vec::new();
+    let mut page = url.to_string();
+    loop {
+        let response = client.request("GET", &page, None)?;
+        if let Some(found) = response.get("items").and_then(Value::as_array) {
+            items.extend_from_slice(found);
+        }
+        match response.get("nextPageToken").and_then(Value::as_str) {
+            Some(token) => page = format!("{url}&pageToken={token}"),
+            None => return Ok(items),
+        }
+    }
+}
+
+/// Where the OAuth tokens are kept: beside the config file, or the data
+/// file when there is none.
+fn token_path(ctx: &Context) -> PathBuf {
+    ctx.config_path
+        .as_deref()
+        .unwrap_or(ctx.data_path())
+        .with_file_name("google-token.json")
+}
+
+/// An access token for the API: the saved one while it lasts, else one
+/// refreshed with the saved refresh token, else one from signing in.
+fn access_token(settings: &GoogleSettings, layers: &Layers, path: &Path) -> Result<String> {
+    let client_id = required(
+        layers,
+        "google.client_id",
+        &settings.client_id,
+        Some("GOOGLE_CLIENT_ID"),
+    )?;
+    let client_secret = required(
+        layers,
+        "google.client_secret",
+        &settings.client_secret,
+        Some("GOOGLE_CLIENT_SECRET"),
+    )?;
+    let oauth = Client::new("Google");
+    let now = Timestamp::now();
+    let saved = fs::read_to_string(path)
+        .ok()
+        .and_then(|text| json::parse(&text).ok());
+    if let Some(saved) = &saved {
+        let text = |key: &str| saved.get(key).and_then(Value::as_str);
+        let expires = text("expires_at").and_then(|t| Timestamp::parse_rfc3339(t).ok());
+        if let (Some(token), Some(expires)) = (text("access_token"), expires) {
+            if expires > now.add_secs(60) {
+                return Ok(token.to_string());
+            }
+        }
+        if let Some(refresh) = text("refresh_token") {
+            let answer = oauth.post_form(
+                &format!("{OAUTH}/token"),
+                &[
+                    ("client_id", &client_id),
+                    ("client_secret", &client_secret),
+                    ("refresh_token", refresh),
+                    ("grant_type", "refresh_token"),
+                ],
+            )?;
+            // A revoked or expired grant is refused; signing in again
+            // replaces it.
+            if answer.get("access_token").is_some() {
+                return keep(path, &answer, Some(refresh), now);
+            }
+        }
+    }
+    let answer = sign_in(&oauth, &client_id, &client_secret)?;
+    keep(path, &answer, None, now)
+}
+
+/// Asks the user to approve access in a browser, polling until they do.
+fn sign_in(oauth: &Client, client_id: &str, client_secret: &str) -> Result<Value> {
+    let device = oauth.post_form(
+        &format!("{OAUTH}/device/code"),
+        &[("client_id", client_id), ("scope", SCOPE)],
+    )?;
+    let text = |key: &str| {
+        device
+            .get(key)
+            .and_then(Value::as_str)
+            .ok_or_else(|| refused(&device))
+    };
+    let (code, user_code, url) = (
+        text("device_code")?,
+        text("user_code")?,
+        text("verification_url")?,
+    );
+    let number =
+        |key: &str, default: i64| device.get(key).and_then(Value::as_i64).unwrap_or(default);
+    let mut interval = number("interval", 5);
+    let deadline = Timestamp::now().add_secs(number("expires_in", 1800));
+    println!("To let task-manager use Google Tasks, visit {url} and enter {user_code}");
+    loop {
+        thread::sleep(Duration::from_secs(interval.max(0) as u64));
+        let answer = oauth.post_form(
+            &format!("{OAUTH}/token"),
+            &[
+                ("client_id", client_id),
+                ("client_secret", client_secret),
+                ("device_code", code),
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+            ],
+        )?;
+        match answer.get("error").and_then(Value::as_str) {
+            None => return Ok(answer),
+            Some("authorization_pending") => {}
+            Some("slow_down") => interval += 5,
+            Some(_) => return Err(refused(&answer)),
+        }
+        if Timestamp::now() > deadline {
+            return Err(Error::Invalid(
+                "the sign-in code expired before it was entered".into(),
+            ));
+        }
+    }
+}
+
+fn refused(answer: &Value) -> Error {
+    let reason = ["error_description", "error"]
+        .iter()
+        .find_map(|key| answer.get(key).and_then(Value::as_str))
+        .unwrap_or("no reason given");
+    Error::Invalid(format!("Google refused the sign-in: {reason}"))
+}
+
+/// Saves the tokens in `answer`, readable by the user alone, and returns
+/// the access token. A refresh answer carries no refresh token, so the
+/// one it was made with is kept.
+fn keep(path: &Path, answer: &Value, refresh: Option<&str>, now: Timestamp) -> Result<String> {
+    let access = answer
+        .get("access_token")
+        .and_then(Value::as_str)
+        .ok_or_else(|| refused(answer))?;
+    let lifetime = answer
+        .get("expires_in")
+        .and_then(Value::as_i64)
+        .unwrap_or(3600);
+    let mut saved = Value::object();
+    saved.insert("access_token", access);
+    saved.insert("expires_at", now.add_secs(lifetime).to_rfc3339());
+    let refresh = answer
+        .get("refresh_token")
+        .and_then(Value::as_str)
+        .or(refresh);
+    saved.insert("refresh_token", refresh.map(String::from));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)?
+        .write_all(saved.to_pretty().as_bytes())?;
+    Ok(access.to_string())
+}
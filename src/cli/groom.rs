@@ -0,0 +1,78 @@
+//! `groom`: the decay policy for low-priority tasks. Those nobody has
+//! changed for a while are listed and, once confirmed, archived or removed.
+
+use task_manager::config::{GroomAction, Operation};
+use task_manager::dates::Timestamp;
+use task_manager::duration;
+use task_manager::render::short_id;
+use task_manager::{Error, Result};
+
+use super::{Args, Context};
+
+pub fn groom(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let force = args.flag("--force");
+    let after = match args.value("--after")? {
+        Some(text) => Some(duration::parse(&text)?),
+        None => ctx.config.groom.after,
+    };
+    let action = match args.value("--action")? {
+        Some(text) => text.parse()?,
+        None => ctx.config.groom.action,
+    };
+    args.finish()?;
+    let after = after.ok_or_else(|| {
+        Error::Invalid(
+            "no decay policy; set `groom.after` in the config file or pass `--after 6w`".into(),
+        )
+    })?;
+    let now = Timestamp::now();
+    let stale: Vec<_> = ctx
+        .manager
+        .stale(after, now)
+        .into_iter()
+        .map(|t| (t.id, t.name.clone(), t.updated_at))
+        .collect();
+    if stale.is_empty() {
+        println!(
+            "No low-priority tasks untouched for {}.",
+            duration::format(after)
+        );
+        return Ok(());
+    }
+    println!(
+        "Low-priority tasks untouched for {}:",
+        duration::format(after)
+    );
+    for (id, name, updated_at) in &stale {
+        println!(
+            "  {}  {name} (last changed {})",
+            short_id(&id.to_string()),
+            updated_at.relative_to(now)
+        );
+    }
+    let (verb, done) = match action {
+        GroomAction::Archive => ("Archive", "Archived"),
+        GroomAction::Trash => ("Remove", "Removed"),
+    };
+    let question = format!("{verb} these {} task(s)?", stale.len());
+    if !ctx.confirm(Operation::Groom, force, &question) {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    for (id, _, _) in &stale {
+        match action {
+            GroomAction::Archive => {
+                ctx.manager
+                    .get_mut(*id)
+                    .expect("stale task exists")
+                    .archived = true
+            }
+            GroomAction::Trash => {
+                ctx.manager.remove(*id)?;
+            }
+        }
+    }
+    println!("{done} {} task(s)", stale.len());
+    ctx.modified();
+    Ok(())
+}
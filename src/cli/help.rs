@@ -0,0 +1,138 @@
+//! `help`: the command reference. It is written from the tables the
+//! commands and parsers themselves use — [`COMMANDS`], the filter
+//! [`GRAMMAR`] and [`FIELDS`](filter::FIELDS), and [`update::fields`] — so
+//! it cannot fall behind them.
+
+use task_manager::filter::{self, GRAMMAR};
+use task_manager::{suggest, update, Error, Result};
+
+use super::{find_command, plugin, print_menu, Args, Command, Context, COMMANDS};
+
+/// Topics that are not commands.
+const TOPICS: [(&str, &str); 3] = [
+    (
+        "filters",
+        "the filter expressions of list, export --filter and others",
+    ),
+    (
+        "fields",
+        "the field=value assignments of set, template add and plugins",
+    ),
+    ("global", "flags taken before any command"),
+];
+
+/// Flags read before the command is looked up.
+const GLOBAL_FLAGS: [(&str, &str); 6] = [
+    ("--file <path>", "the data file, tasks.json by default"),
+    (
+        "--config <path>",
+        "the configuration file instead of the default one",
+    ),
+    (
+        "--setting <key=value>",
+        "one setting over the file and $TASK_MANAGER_* variables",
+    ),
+    (
+        "--brief",
+        "a sentence per line instead of tables, for screen readers",
+    ),
+    ("--timings", "how long each phase took, on stderr"),
+    ("--error-format json", "errors as JSON on stderr"),
+];
+
+pub fn help(_ctx: &mut Context, mut args: Args) -> Result<()> {
+    let topic = args.positional();
+    args.finish()?;
+    let Some(topic) = topic else {
+        print_menu();
+        println!("\nRun `help <command>` for one command, or `help <topic>`:");
+        for (name, summary) in TOPICS {
+            println!("  {name:<24} {summary}");
+        }
+        return Ok(());
+    };
+    match topic.as_str() {
+        "filters" => print!("{}", filters()),
+        "fields" => print!("{}", fields()),
+        "global" => print!("{}", table(&GLOBAL_FLAGS)),
+        name => match find_command(name) {
+            Some(command) => print!("{}", command_help(command)),
+            None => match plugin::find(name) {
+                Some(path) => println!(
+                    "`{name}` is a plugin ({}); see its own documentation.",
+                    path.display()
+                ),
+                None => {
+                    let candidates = COMMANDS
+                        .iter()
+                        .map(|c| c.name)
+                        .chain(TOPICS.iter().map(|&(t, _)| t));
+                    let names: Vec<String> = suggest::closest(name, candidates)
+                        .iter()
+                        .map(|n| format!("`{n}`"))
+                        .collect();
+                    return Err(Error::NotFound(format!(
+                        "no command or help topic `{name}`{}",
+                        suggest::did_you_mean(&names)
+                    )));
+                }
+            },
+        },
+    }
+    Ok(())
+}
+
+/// Usage, summary and the flags the usage line names, with pointers to the
+/// topics it relies on.
+fn command_help(command: &Command) -> String {
+    let mut out = format!(
+        "Usage: task-manager {}\n\n{}.\n",
+        command.usage,
+        capitalize(command.summary)
+    );
+    let mut flags: Vec<&str> = Vec::new();
+    for word in command.usage.split([' ', '|', '[', ']']) {
+        let flag = word.trim_end_matches([',', '.']);
+        if flag.starts_with("--") && !flags.contains(&flag) {
+            flags.push(flag);
+        }
+    }
+    if !flags.is_empty() {
+        out.push_str(&format!("\nFlags: {}\n", flags.join(", ")));
+    }
+    if command.usage.contains("filter") {
+        out.push_str("\nFilters are written as in `help filters`.\n");
+    }
+    if command.usage.contains("field=") {
+        out.push_str("\nAssignments are written as in `help fields`.\n");
+    }
+    out
+}
+
+fn filters() -> String {
+    format!(
+        "Filter expressions, e.g. priority>=high and due<friday and (tag:work or project:\"home office\"):\n\n{GRAMMAR}\nA bare word matches the name or description.\n\nFields:\n{}",
+        table(filter::FIELDS)
+    )
+}
+
+fn fields() -> String {
+    format!(
+        "Assignments are field=value, +tag or -tag, e.g. set 3f2a priority=high due=\"next tue\" +home:\n\n{}",
+        table(&update::fields())
+    )
+}
+
+fn table<T: AsRef<str>>(rows: &[(&str, T)]) -> String {
+    rows.iter()
+        .map(|(name, text)| format!("  {name:<24} {}\n", text.as_ref()))
+        .collect()
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,158 @@
+//! JSON over HTTPS through `curl`, for the syncs with web services.
+//!
+//! Credentials and request bodies reach curl in a config on stdin, never
+//! the command line where other users could read them. [`curl`] itself is
+//! shared with the other protocols curl speaks, such as IMAP for `mail`.
+
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use task_manager::config::{Layers, Origin};
+use task_manager::json::{self, Value};
+use task_manager::{Error, Result};
+
+/// The setting `key` (as `section.key`) that a service needs, whose value
+/// in the configuration is `value`. The service's usual variable `var`,
+/// such as `NOTION_TOKEN`, counts as the environment among the `layers`:
+/// it wins over the config file but not over a `TASK_MANAGER_` variable or
+/// `--setting`.
+pub fn required(
+    layers: &Layers,
+    key: &str,
+    value: &Option<String>,
+    var: Option<&str>,
+) -> Result<String> {
+    let given = matches!(layers.origin(key), Some(Origin::Env(_) | Origin::Flag(_)));
+    let from_var = var
+        .filter(|_| !given)
+        .and_then(|var| env::var(var).ok())
+        .filter(|v| !v.is_empty());
+    let value = from_var
+        .or_else(|| value.clone().filter(|v| !v.is_empty()))
+        .ok_or_else(|| {
+            let hint = var.map(|var| format!(" or set {var}")).unwrap_or_default();
+            Error::Invalid(format!("set `{key}` in the config file{hint}"))
+        })?;
+    if value.chars().any(char::is_control) {
+        return Err(Error::Invalid(format!(
+            "`{key}` contains a control character such as a line break"
+        )));
+    }
+    Ok(value)
+}
+
+/// What every request to one service carries: credentials and headers.
+pub struct Client {
+    /// The service's name, for error messages.
+    service: &'static str,
+    config: String,
+}
+
+impl Client {
+    pub fn new(service: &'static str) -> Self {
+        Client {
+            service,
+            config: "header = \"Accept: application/json\"\n".to_string(),
+        }
+    }
+
+    /// Basic authentication as `user`.
+    pub fn user(mut self, user: &str, password: &str) -> Self {
+        self.config
+            .push_str(&format!("user = \"{}:{}\"\n", quote(user), quote(password)));
+        self
+    }
+
+    pub fn header(mut self, header: &str) -> Self {
+        self.config
+            .push_str(&format!("header = \"{}\"\n", quote(header)));
+        self
+    }
+
+    /// Sends `body`, if any, as JSON to `url`, returning the JSON answered
+    /// (null for an empty answer).
+    pub fn request(&self, method: &str, url: &str, body: Option<&Value>) -> Result<Value> {
+        let mut config = self.config.clone();
+        if let Some(body) = body {
+            config.push_str(&format!(
+                "header = \"Content-Type: application/json\"\ndata = \"{}\"\n",
+                quote(&body.to_compact())
+            ));
+        }
+        let text = curl(&["--fail", "--request", method], url, &config)?;
+        let text = String::from_utf8_lossy(&text);
+        if text.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        self.parse(&text)
+    }
+
+    /// Posts `form` URL-encoded to `url`, as OAuth endpoints expect. Their
+    /// refusals are JSON too, so any answer is returned, not only a success.
+    pub fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<Value> {
+        let mut config = self.config.clone();
+        for (key, value) in form {
+            config.push_str(&format!(
+                "data-urlencode = \"{}={}\"\n",
+                quote(key),
+                quote(value)
+            ));
+        }
+        let text = curl(&["--request", "POST"], url, &config)?;
+        let text = String::from_utf8_lossy(&text);
+        self.parse(&text)
+    }
+
+    fn parse(&self, text: &str) -> Result<Value> {
+        json::parse(text).map_err(|err| {
+            Error::Invalid(format!(
+                "{} sent something other than JSON ({err})",
+                self.service
+            ))
+        })
+    }
+}
+
+/// Runs curl on `url` with `args` and `config`, returning what it wrote.
+pub fn curl(args: &[&str], url: &str, config: &str) -> Result<Vec<u8>> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--config", "-"])
+        .args(args)
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| Error::Invalid(format!("could not run curl: {err}")))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin.write_all(config.as_bytes())?;
+    drop(stdin);
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(Error::Invalid(format!(
+            "request to {url} failed (curl: {})",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Escapes a value for a double-quoted curl config string. Line breaks and
+/// tabs are written as curl's escapes, so a value cannot end its line and
+/// start another option.
+pub fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{b}' => out.push_str("\\v"),
+            c => out.push(c),
+        }
+    }
+    out
+}
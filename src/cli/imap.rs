@@ -0,0 +1,96 @@
+//! An IMAP folder through `curl`, for `mail`: unread messages are listed
+//! and fetched without marking them, then marked read or moved once they
+//! are tasks. The password reaches curl on stdin like the web syncs'
+//! credentials (see [`http`](super::http)).
+
+use task_manager::config::{Layers, MailSettings};
+use task_manager::{Error, Result};
+
+use super::http::{curl, quote, required};
+
+pub struct Folder {
+    /// The folder's `imaps://host/folder` URL.
+    pub url: String,
+    config: String,
+    move_to: Option<String>,
+}
+
+impl Folder {
+    /// The folder `settings` name, with the password from the environment
+    /// as [`required`] finds it; `None` when no folder is set.
+    pub fn from_settings(settings: &MailSettings, layers: &Layers) -> Result<Option<Self>> {
+        let Some(url) = settings.imap.clone() else {
+            return Ok(None);
+        };
+        let user = settings.user.as_deref().ok_or_else(|| {
+            Error::Invalid("set `mail.user` to the IMAP account's user name".into())
+        })?;
+        let password = required(
+            layers,
+            "mail.password",
+            &settings.password,
+            Some("IMAP_PASSWORD"),
+        )?;
+        Ok(Some(Folder {
+            url,
+            config: format!("user = \"{}:{}\"\n", quote(user), quote(&password)),
+            move_to: settings.move_to.clone(),
+        }))
+    }
+
+    /// The UIDs of the unread messages, oldest first.
+    pub fn unread(&self) -> Result<Vec<String>> {
+        let answer = self.command("UID SEARCH UNSEEN")?;
+        let answer = String::from_utf8_lossy(&answer);
+        let mut uids: Vec<String> = answer
+            .lines()
+            .filter_map(|line| line.strip_prefix("* SEARCH"))
+            .flat_map(str::split_whitespace)
+            .map(String::from)
+            .collect();
+        uids.sort_by_key(|uid| uid.parse::<u64>().unwrap_or(u64::MAX));
+        Ok(uids)
+    }
+
+    /// The message with `uid`, as it would be stored in a file. Peeking
+    /// leaves it unread.
+    pub fn fetch(&self, uid: &str) -> Result<Vec<u8>> {
+        let answer = self.command(&format!("UID FETCH {uid} BODY.PEEK[]"))?;
+        literal(&answer)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| Error::Invalid(format!("the IMAP server sent no message {uid}")))
+    }
+
+    /// Marks the messages with `uids` read, or moves them to `move_to`.
+    pub fn take(&self, uids: &[String]) -> Result<()> {
+        if uids.is_empty() {
+            return Ok(());
+        }
+        let set = uids.join(",");
+        match &self.move_to {
+            Some(folder) => self.command(&format!("UID MOVE {set} \"{}\"", quote(folder))),
+            None => self.command(&format!("UID STORE {set} +FLAGS (\\Seen)")),
+        }?;
+        Ok(())
+    }
+
+    fn command(&self, command: &str) -> Result<Vec<u8>> {
+        curl(
+            &["--ssl-reqd", "--request", command],
+            &self.url,
+            &self.config,
+        )
+    }
+}
+
+/// The first `{length}` literal in an IMAP answer: the message a FETCH
+/// sent.
+fn literal(answer: &[u8]) -> Option<&[u8]> {
+    let end = answer.windows(3).position(|w| w == b"}\r\n")?;
+    let start = answer[..end].iter().rposition(|&b| b == b'{')?;
+    let length: usize = std::str::from_utf8(&answer[start + 1..end])
+        .ok()?
+        .parse()
+        .ok()?;
+    answer[end + 3..].get(..length)
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs;
+
+use task_manager::export::{ics, Codec};
+use task_manager::import::matching;
+use task_manager::import::outlook::{self, DateOrder};
+use task_manager::render::short_id;
+use task_manager::{Error, Result, Task, TaskId};
+
+use super::triage;
+use super::{Args, Context};
+
+pub fn import(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let ics_path = args.value("--ics")?;
+    let outlook_path = args.value("--outlook")?;
+    let mut data = Vec::new();
+    for codec in Codec::ALL {
+        if let Some(path) = args.value(&format!("--{codec}"))? {
+            data.push((codec, path));
+        }
+    }
+    let order = if args.flag("--day-first") {
+        DateOrder::DayFirst
+    } else {
+        DateOrder::MonthFirst
+    };
+    let triage = args.flag("--triage");
+    let update = args.flag("--update");
+    args.finish()?;
+    let tasks = match (ics_path, outlook_path, data.as_slice()) {
+        (Some(path), None, []) => ics::parse(&fs::read_to_string(&path)?)?,
+        (None, Some(path), []) => outlook::parse(&fs::read_to_string(&path)?, order)?,
+        (None, None, [(codec, path)]) => codec.read(&fs::read_to_string(path)?)?,
+        (None, None, []) => {
+            return Err(Error::Invalid(
+                "choose an input, e.g. `import --ics tasks.ics`".into(),
+            ));
+        }
+        _ => return Err(Error::Invalid("choose one input format".into())),
+    };
+    add_imported(ctx, tasks, triage, update)
+}
+
+/// Adds the tasks not already in the list, after a triage pass if asked
+/// for, and updates or skips the rest, reporting the decision for each.
+pub(super) fn add_imported(
+    ctx: &mut Context,
+    tasks: Vec<Task>,
+    triage: bool,
+    update: bool,
+) -> Result<()> {
+    let total = tasks.len();
+    let mut new: Vec<Task> = Vec::new();
+    // Earlier rows of the file and their numbers, to catch repeats.
+    let mut earlier: Vec<Task> = Vec::new();
+    let mut rows: HashMap<TaskId, usize> = HashMap::new();
+    let (mut present, mut updated) = (0, 0);
+    for (index, task) in tasks.into_iter().enumerate() {
+        let row = index + 1;
+        if let Some((first, reason)) = matching::find(&earlier, &task) {
+            println!(
+                "{row:>4}  skipped  {} ({} as row {})",
+                task.name,
+                reason.describe(),
+                rows[&first.id]
+            );
+            present += 1;
+            continue;
+        }
+        rows.insert(task.id, row);
+        earlier.push(task.clone());
+        let Some((existing, reason)) = matching::find(ctx.manager.tasks(), &task) else {
+            println!("{row:>4}  new      {}", task.name);
+            new.push(task);
+            continue;
+        };
+        present += 1;
+        let id = existing.id;
+        let why = format!("{} as {}", reason.describe(), short_id(&id.to_string()));
+        if !update {
+            println!("{row:>4}  skipped  {} ({why})", task.name);
+        } else if matching::update(ctx.manager.get_mut(id).expect("matched"), &task) {
+            println!("{row:>4}  updated  {} ({why})", task.name);
+            updated += 1;
+        } else {
+            println!("{row:>4}  same     {} ({why})", task.name);
+        }
+    }
+    let mut discarded = 0;
+    if triage && !new.is_empty() {
+        let triage = triage::triage(new, &ctx.manager, &ctx.config)?;
+        triage.print_summary();
+        discarded = triage.discarded.len();
+        new = triage.accepted;
+    }
+    let added = new.len();
+    for task in new {
+        ctx.manager.add(task);
+    }
+    if added > 0 || updated > 0 {
+        ctx.modified();
+    }
+    let mut counts = format!("{present} already present");
+    if update {
+        counts.push_str(&format!(", {updated} updated"));
+    }
+    if discarded > 0 {
+        counts.push_str(&format!(", {discarded} discarded"));
+    }
+    println!("Imported {added} of {total} tasks ({counts})");
+    Ok(())
+}
@@ -0,0 +1,231 @@
+//! Links between tasks and JIRA issues, and `jira sync` to keep their
+//! completion in step.
+//!
+//! The sync talks to JIRA's REST API through `curl` (see
+//! [`http`](super::http)), which must be installed.
+
+use task_manager::config::{JiraSettings, Layers};
+use task_manager::json::Value;
+use task_manager::render::short_id;
+use task_manager::task::parse_issue_key;
+use task_manager::{Error, Result, Status};
+
+use super::http::{required, Client};
+use super::{Args, Context};
+
+pub fn jira(ctx: &mut Context, mut args: Args) -> Result<()> {
+    match args.positional().as_deref() {
+        Some("link") => {
+            let reference = args.required("task id")?;
+            let key = parse_issue_key(&args.required("issue key")?)?;
+            args.finish()?;
+            let id = ctx.manager.resolve(&reference)?;
+            if let Some(other) = ctx
+                .manager
+                .tasks()
+                .iter()
+                .find(|t| t.id != id && t.jira.as_deref() == Some(key.as_str()))
+            {
+                return Err(Error::Conflict(format!(
+                    "{key} is already linked to {} \"{}\"",
+                    short_id(&other.id.to_string()),
+                    other.name
+                )));
+            }
+            let task = ctx.manager.get_mut(id).expect("resolved task");
+            println!("Linked \"{}\" to {key}", task.name);
+            task.jira = Some(key);
+            ctx.modified();
+            Ok(())
+        }
+        Some("unlink") => {
+            let reference = args.required("task id")?;
+            args.finish()?;
+            let task = ctx.manager.find_mut(&reference)?;
+            match task.jira.take() {
+                Some(key) => println!("Unlinked \"{}\" from {key}", task.name),
+                None => println!("\"{}\" is not linked to an issue", task.name),
+            }
+            ctx.modified();
+            Ok(())
+        }
+        Some("sync") => {
+            let dry_run = args.flag("--dry-run");
+            args.finish()?;
+            sync(ctx, dry_run)
+        }
+        Some(other) => Err(Error::Invalid(format!(
+            "unknown jira command `{other}` (expected link, unlink or sync)"
+        ))),
+        None => Err(Error::Invalid(
+            "usage: jira link <id> <KEY> | jira unlink <id> | jira sync [--dry-run]".into(),
+        )),
+    }
+}
+
+/// Mirrors completion between linked tasks and their issues: an issue
+/// that is done finishes its task, and a done task moves its issue to a
+/// done status. Anything else, reopening included, is left alone.
+fn sync(ctx: &mut Context, dry_run: bool) -> Result<()> {
+    let site = Site::from_settings(
+        &ctx.config.jira,
+        ctx.config.links.jira.as_deref(),
+        &ctx.layers()?,
+    )?;
+    let user = ctx.config.user_name();
+    let linked: Vec<_> = ctx
+        .manager
+        .tasks()
+        .iter()
+        .filter_map(|t| Some((t.id, t.jira.clone()?)))
+        .collect();
+    if linked.is_empty() {
+        println!("No tasks are linked to JIRA issues; link one with `jira link <id> <KEY>`.");
+        return Ok(());
+    }
+    let verb = |done: &'static str, would: &'static str| if dry_run { would } else { done };
+    let (mut here, mut there, mut failed) = (0, 0, 0);
+    for (id, key) in linked {
+        let task = ctx.manager.get_mut(id).expect("linked task exists");
+        let result = site.is_done(&key).and_then(|issue_done| {
+            if issue_done && task.status.is_open() && task.status != Status::ReviewPending {
+                let action = if dry_run {
+                    "would complete"
+                } else {
+                    match task.finish(&user) {
+                        Status::Done => "completed",
+                        _ => "sent for review",
+                    }
+                };
+                println!("{key} is done: {action} \"{}\"", task.name);
+                here += 1;
+            } else if !issue_done && task.status == Status::Done {
+                let transition = site.done_transition(&key)?;
+                if !dry_run {
+                    site.transition(&key, &transition.id)?;
+                }
+                println!(
+                    "\"{}\" is done: {} {key} to {}",
+                    task.name,
+                    verb("moved", "would move"),
+                    transition.to
+                );
+                there += 1;
+            }
+            Ok(())
+        });
+        if let Err(err) = result {
+            eprintln!("warning: {key}: {err}");
+            failed += 1;
+        }
+    }
+    if here > 0 && !dry_run {
+        ctx.modified();
+    }
+    let mut summary = format!(
+        "{here} task(s) {} here, {there} issue(s) {} in JIRA",
+        verb("completed", "to complete"),
+        verb("moved", "to move")
+    );
+    if failed > 0 {
+        summary.push_str(&format!(", {failed} failed"));
+    }
+    println!("{summary}.");
+    Ok(())
+}
+
+/// The JIRA site with its credentials, checked and with the environment
+/// filled in.
+struct Site {
+    url: String,
+    client: Client,
+}
+
+/// A workflow transition that ends in a done status.
+struct Transition {
+    id: String,
+    /// The status it leads to.
+    to: String,
+}
+
+impl Site {
+    /// `links.jira` is the browse address, so without `jira.url` the site
+    /// is what comes before its `/browse/`.
+    fn from_settings(
+        settings: &JiraSettings,
+        browse: Option<&str>,
+        layers: &Layers,
+    ) -> Result<Self> {
+        let url = settings
+            .url
+            .clone()
+            .or_else(|| {
+                let base = browse?.trim_end_matches('/').strip_suffix("/browse")?;
+                Some(base.to_string())
+            })
+            .ok_or_else(|| Error::Invalid("set `jira.url` in the config file".into()))?;
+        let email = required(layers, "jira.email", &settings.email, Some("JIRA_EMAIL"))?;
+        let token = required(
+            layers,
+            "jira.token",
+            &settings.token,
+            Some("JIRA_API_TOKEN"),
+        )?;
+        Ok(Site {
+            url,
+            client: Client::new("JIRA").user(&email, &token),
+        })
+    }
+
+    /// Whether the issue's status is in JIRA's done category, whatever the
+    /// workflow calls it.
+    fn is_done(&self, key: &str) -> Result<bool> {
+        let issue = self.request("GET", &format!("issue/{key}?fields=status"), None)?;
+        let category = issue
+            .get("fields")
+            .and_then(|f| f.get("status"))
+            .and_then(|s| s.get("statusCategory"))
+            .and_then(|c| c.get("key"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::Invalid("JIRA sent no status".into()))?;
+        Ok(category == "done")
+    }
+
+    /// The first transition open to the issue that leads to a done status.
+    fn done_transition(&self, key: &str) -> Result<Transition> {
+        let response = self.request("GET", &format!("issue/{key}/transitions"), None)?;
+        response
+            .get("transitions")
+            .and_then(Value::as_array)
+            .unwrap_or_default()
+            .iter()
+            .find_map(|t| {
+                let to = t.get("to")?;
+                if to.get("statusCategory")?.get("key")?.as_str()? != "done" {
+                    return None;
+                }
+                Some(Transition {
+                    id: t.get("id")?.as_str()?.to_string(),
+                    to: to.get("name")?.as_str()?.to_string(),
+                })
+            })
+            .ok_or_else(|| {
+                Error::Invalid("no transition open to the issue leads to a done status".into())
+            })
+    }
+
+    fn transition(&self, key: &str, id: &str) -> Result<()> {
+        let mut transition = Value::object();
+        transition.insert("id", id);
+        let mut body = Value::object();
+        body.insert("transition", transition);
+        self.request("POST", &format!("issue/{key}/transitions"), Some(&body))?;
+        Ok(())
+    }
+
+    /// `method` on `path` under the REST API.
+    fn request(&self, method: &str, path: &str, body: Option<&Value>) -> Result<Value> {
+        let url = format!("{}/rest/api/2/{path}", self.url);
+        self.client.request(method, &url, body)
+    }
+}
@@ -0,0 +1,174 @@
+//! `mail`: new messages in a Maildir or an IMAP folder become tasks. Mail
+//! programs and IMAP sync tools such as mbsync or offlineimap deliver into
+//! the Maildir's `new/`; once a message is a task it is moved to `cur/` and
+//! flagged as seen, so it is taken only once. An IMAP folder is read
+//! directly (see [`imap`](super::imap)): its unread messages are taken,
+//! then marked read or moved to another folder.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use task_manager::config::{Layers, MailSettings};
+use task_manager::import::matching;
+use task_manager::mail::Message;
+use task_manager::render::short_id;
+use task_manager::{Error, Result};
+
+use super::imap::Folder;
+use super::import;
+use super::{Args, Context};
+
+pub fn mail(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let inbox = match args.value("--maildir")? {
+        Some(path) => Inbox::Maildir(PathBuf::from(path)),
+        None => Inbox::from_settings(&ctx.config.mail, &ctx.layers()?)?.ok_or_else(|| {
+            Error::Invalid("set `maildir` or `imap` under [mail] or pass `--maildir <dir>`".into())
+        })?,
+    };
+    let dry_run = args.flag("--dry-run");
+    let triage = args.flag("--triage");
+    args.finish()?;
+    let messages = inbox.read_new()?;
+    if messages.is_empty() {
+        println!("No new mail in {inbox}");
+        return Ok(());
+    }
+    if dry_run {
+        for (_, message) in &messages {
+            let task = message.to_task();
+            match &task.contact {
+                Some(from) => println!("+ {} (from {from})", task.name),
+                None => println!("+ {}", task.name),
+            }
+        }
+        return Ok(());
+    }
+    let tasks = messages.iter().map(|(_, m)| m.to_task()).collect();
+    import::add_imported(ctx, tasks, triage, false)?;
+    // The tasks are saved before the mail is marked, so none is lost.
+    ctx.save_if_modified()?;
+    inbox.take(&messages)
+}
+
+/// For the daemon: adds a task for each new message not already a task and
+/// marks them all seen. Returns the names of the tasks added.
+pub fn poll(ctx: &mut Context, inbox: &Inbox) -> Result<Vec<String>> {
+    let messages = inbox.read_new()?;
+    let mut added = Vec::new();
+    for (_, message) in &messages {
+        let task = message.to_task();
+        if matching::find(ctx.manager.tasks(), &task).is_none() {
+            let name = format!("{} ({})", task.name, short_id(&task.id.to_string()));
+            ctx.manager.add(task);
+            added.push(name);
+        }
+    }
+    if !added.is_empty() {
+        ctx.modified();
+        ctx.save_if_modified()?;
+    }
+    inbox.take(&messages)?;
+    Ok(added)
+}
+
+/// Where new mail is picked up.
+pub enum Inbox {
+    Maildir(PathBuf),
+    Imap(Folder),
+}
+
+impl Inbox {
+    /// The inbox the `[mail]` settings name, if any.
+    pub fn from_settings(settings: &MailSettings, layers: &Layers) -> Result<Option<Self>> {
+        if let Some(folder) = Folder::from_settings(settings, layers)? {
+            return Ok(Some(Inbox::Imap(folder)));
+        }
+        Ok(settings.maildir.clone().map(Inbox::Maildir))
+    }
+
+    /// The new messages, oldest first, each with what identifies it in
+    /// the inbox: its file name in `new/` or its IMAP UID. Messages that
+    /// are not email are reported and left where they are.
+    fn read_new(&self) -> Result<Vec<(String, Message)>> {
+        let raw = match self {
+            Inbox::Maildir(maildir) => read_new(maildir)?,
+            Inbox::Imap(folder) => {
+                let mut raw = Vec::new();
+                for uid in folder.unread()? {
+                    let message = folder.fetch(&uid)?;
+                    raw.push((uid, message));
+                }
+                raw
+            }
+        };
+        let mut messages = Vec::new();
+        for (key, raw) in raw {
+            match Message::parse(&String::from_utf8_lossy(&raw)) {
+                Ok(message) => messages.push((key, message)),
+                Err(err) => eprintln!("warning: skipped {key} in {self}: {err}"),
+            }
+        }
+        Ok(messages)
+    }
+
+    /// Marks `messages` as taken, so they are not read again.
+    fn take(&self, messages: &[(String, Message)]) -> Result<()> {
+        let keys: Vec<String> = messages.iter().map(|(key, _)| key.clone()).collect();
+        match self {
+            Inbox::Maildir(maildir) => {
+                for name in &keys {
+                    mark_seen(maildir, name)?;
+                }
+                Ok(())
+            }
+            Inbox::Imap(folder) => folder.take(&keys),
+        }
+    }
+}
+
+impl fmt::Display for Inbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Inbox::Maildir(maildir) => write!(f, "{}", maildir.display()),
+            Inbox::Imap(folder) => f.write_str(&folder.url),
+        }
+    }
+}
+
+/// The files in `maildir/new` by name, oldest first.
+fn read_new(maildir: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    let new = maildir.join("new");
+    if !new.is_dir() {
+        return Err(Error::NotFound(format!(
+            "{} is not a Maildir (it has no new/ folder)",
+            maildir.display()
+        )));
+    }
+    let mut paths: Vec<PathBuf> = fs::read_dir(&new)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    // Delivery names start with the time, so this is arrival order.
+    paths.sort();
+    let mut messages = Vec::new();
+    for path in paths.into_iter().filter(|p| p.is_file()) {
+        let name = path
+            .file_name()
+            .expect("read from a directory")
+            .to_string_lossy()
+            .into_owned();
+        messages.push((name, fs::read(&path)?));
+    }
+    Ok(messages)
+}
+
+/// Moves a message from `new/` to `cur/` with the seen flag, as a mail
+/// program does once it has been read.
+fn mark_seen(maildir: &Path, name: &str) -> Result<()> {
+    let base = name.split_once(":2,").map_or(name, |(base, _)| base);
+    fs::rename(
+        maildir.join("new").join(name),
+        maildir.join("cur").join(format!("{base}:2,S")),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,724 @@
+//! Command-line frontend: one-shot commands and the interactive prompt.
+
+mod alias;
+mod apply;
+mod args;
+mod autosave;
+mod backup;
+mod calendar;
+mod config;
+mod crypto;
+mod daemon;
+mod export;
+mod fields;
+mod git;
+mod google;
+mod groom;
+mod help;
+mod http;
+mod imap;
+mod import;
+mod jira;
+mod mail;
+mod notion;
+mod plugin;
+mod project;
+mod prompt;
+mod render;
+mod reprioritize;
+mod rules;
+mod schedule;
+mod sources;
+mod stats;
+mod store;
+mod sync;
+mod tags;
+mod tasks;
+mod triage;
+
+use std::borrow::Cow;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use task_manager::config::{Config, Layers, Operation, Origin, Override, ENV_PREFIX};
+use task_manager::dates::{self, Timestamp};
+use task_manager::json::Value;
+use task_manager::render::set_id_width;
+use task_manager::render::theme::{self, Theme};
+use task_manager::stats::Summary;
+use task_manager::storage::{JsonStore, Store, DEFAULT_FILE};
+use task_manager::suggest;
+use task_manager::timing;
+use task_manager::{Error, Result, Task, TasksManager};
+
+pub use args::Args;
+use autosave::Autosave;
+use fields::Fields;
+
+/// State shared by every command for the lifetime of one invocation.
+pub struct Context {
+    store: JsonStore,
+    pub manager: TasksManager,
+    pub config: Config,
+    /// Where `config` was read from, if anywhere.
+    config_path: Option<PathBuf>,
+    /// Settings from the environment and flags, applied over the file.
+    overrides: Vec<Override>,
+    /// The tasks as last read or saved, to tell which ones changed.
+    saved: Vec<Task>,
+    /// Encrypts descriptions and notes on the way to the data file.
+    fields: Fields,
+    /// The command being run, for git commit messages.
+    command: String,
+    modified: bool,
+}
+
+impl Context {
+    pub fn open(
+        path: PathBuf,
+        config_path: Option<PathBuf>,
+        overrides: Vec<Override>,
+    ) -> Result<Self> {
+        let config = timing::timed("config", || {
+            layers(config_path.as_deref(), &overrides)?.config()
+        })?;
+        dates::set_display_offset(config.display.timezone);
+        dates::set_display_format(&config.display.date_format, &config.display.time_format);
+        dates::set_display_locale(config.display.locale);
+        dates::set_week_start(config.display.week_start);
+        render::set_hyperlinks(&config.links);
+        theme::set_theme(Theme::from_settings(&config.theme));
+        let store = JsonStore::new(path);
+        let mut manager = store.load()?;
+        let mut fields = Fields::new(&config.encryption);
+        fields.open(&mut manager)?;
+        set_id_width(manager.id_width());
+        Ok(Context {
+            store,
+            saved: manager.tasks().to_vec(),
+            fields,
+            manager,
+            config,
+            config_path,
+            overrides,
+            command: String::new(),
+            modified: false,
+        })
+    }
+
+    /// Re-reads the data file and the configuration, dropping unsaved
+    /// changes.
+    pub fn reload(&mut self) -> Result<()> {
+        let fresh = Context::open(
+            self.store.path().to_path_buf(),
+            self.config_path.clone(),
+            self.overrides.clone(),
+        )?;
+        *self = fresh;
+        Ok(())
+    }
+
+    /// The configuration's layers, to tell where each setting came from.
+    pub fn layers(&self) -> Result<Layers> {
+        layers(self.config_path.as_deref(), &self.overrides)
+    }
+
+    pub fn data_path(&self) -> &Path {
+        self.store.path()
+    }
+
+    /// Marks the task list as changed so it is saved after the command.
+    pub fn modified(&mut self) {
+        self.modified = true;
+    }
+
+    /// Runs the automation rules on the tasks that came to match one since
+    /// the last save, marking the list modified if they changed anything.
+    /// Returns a description of each change.
+    pub fn apply_rules(&mut self, now: Timestamp) -> Result<Vec<String>> {
+        let firings =
+            task_manager::rules::run(&self.config.rules, &mut self.manager, &self.saved, now)?;
+        if !firings.is_empty() {
+            self.modified = true;
+        }
+        Ok(firings.iter().map(rules::describe).collect())
+    }
+
+    /// Whether to go ahead with `operation`: asks `question` when the
+    /// confirmation policy calls for it and `force` was not given.
+    pub fn confirm(&self, operation: Operation, force: bool, question: &str) -> bool {
+        !self.config.confirm.asks(operation, force) || prompt::confirm(question)
+    }
+
+    /// Saves `manager` as it is in place of the task list, keeping the
+    /// previous tasks in a `.bak` file (see [`JsonStore::replace`]). Unlike
+    /// [`Context::save_if_modified`] it neither runs rules nor touches
+    /// timestamps, as a restore must not. Returns the `.bak` file's path.
+    pub fn replace(&mut self, manager: TasksManager) -> Result<Option<PathBuf>> {
+        let previous = self.store.replace(&*self.fields.seal(&manager)?)?;
+        self.manager = manager;
+        self.saved = self.manager.tasks().to_vec();
+        self.modified = false;
+        set_id_width(self.manager.id_width());
+        Ok(previous)
+    }
+
+    /// The tasks as they are written to the data file, for writing them
+    /// elsewhere.
+    pub fn sealed(&mut self) -> Result<Cow<'_, TasksManager>> {
+        self.fields.seal(&self.manager)
+    }
+
+    pub fn save_if_modified(&mut self) -> Result<()> {
+        if self.modified {
+            let _span = timing::span("save");
+            let now = Timestamp::now();
+            for line in timing::timed("rules", || self.apply_rules(now))? {
+                println!("Applied {line}");
+            }
+            self.manager.touch_changed(&self.saved, now);
+            self.manager.record_snapshot(now);
+            self.store.save(&*self.fields.seal(&self.manager)?)?;
+            if self.config.git.auto_commit {
+                let _span = timing::span("git");
+                let message = git::message(&self.saved, self.manager.tasks(), &self.command);
+                // The change is saved either way; a failed commit is only
+                // worth a warning.
+                if let Err(err) = git::commit(self.data_path(), &message, &self.config.git) {
+                    eprintln!("warning: saved, but not committed: {err}");
+                }
+            }
+            self.saved = self.manager.tasks().to_vec();
+            self.modified = false;
+            set_id_width(self.manager.id_width());
+        }
+        Ok(())
+    }
+}
+
+/// The defaults, the file at `config_path` and `overrides` over them.
+fn layers(config_path: Option<&Path>, overrides: &[Override]) -> Result<Layers> {
+    let mut layers = match config_path {
+        Some(path) => Layers::new().read(path)?,
+        None => Layers::new(),
+    };
+    for item in overrides {
+        layers.set(item.clone())?;
+    }
+    Ok(layers)
+}
+
+pub struct Command {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub summary: &'static str,
+    run: fn(&mut Context, Args) -> Result<()>,
+}
+
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "help",
+        usage: "help [command | filters | fields | global]",
+        summary: "list the commands, or describe one command or topic",
+        run: help::help,
+    },
+    Command {
+        name: "add",
+        usage: "add [--name N] [--desc D] [--priority P] [--project P] [--tags T] [--due D] [--contact EMAIL] [--no-prompt] [--ignore-limits]",
+        summary: "create a task, prompting for fields not given as flags",
+        run: tasks::add,
+    },
+    Command {
+        name: "list",
+        usage: "list [--all] [--mine] [--private | --no-private] [--relative | --absolute] [--due|--created|--completed <period>] [filter]",
+        summary: "list open tasks outside archived projects, optionally filtered (e.g. \"priority>=high and tag:work\")",
+        run: tasks::list,
+    },
+    Command {
+        name: "show",
+        usage: "show [--relative | --absolute] <id>",
+        summary: "show every field of a task",
+        run: tasks::show,
+    },
+    Command {
+        name: "next",
+        usage: "next [--relative | --absolute]",
+        summary: "show the most urgent open task (priority, then due date, then age)",
+        run: tasks::next,
+    },
+    Command {
+        name: "roulette",
+        usage: "roulette [--relative | --absolute]",
+        summary: "pick an open task at random, favouring high priorities and overdue tasks",
+        run: tasks::roulette,
+    },
+    Command {
+        name: "today",
+        usage: "today [--relative | --absolute]",
+        summary: "show what needs doing today: overdue, due today, then urgent undated tasks",
+        run: tasks::today,
+    },
+    Command {
+        name: "print",
+        usage: "print [--printer <device | tcp://host[:port]>] [--width <chars>]",
+        summary: "print today's agenda on a receipt printer",
+        run: export::print,
+    },
+    Command {
+        name: "calendar",
+        usage: "calendar [YYYY-MM | month] [--all] | calendar --day <date> [--all]",
+        summary: "show a month of due tasks, or the tasks due on one day",
+        run: calendar::calendar,
+    },
+    Command {
+        name: "set",
+        usage: "set <id> field=value.. [+tag] [-tag] [--note N] [--ignore-limits]",
+        summary: "change single fields without prompting",
+        run: tasks::set,
+    },
+    Command {
+        name: "reschedule",
+        usage: "reschedule <id> | --overdue <+2d|-1w|+1mo|date>",
+        summary: "move due dates by a shift or to a new date",
+        run: tasks::reschedule,
+    },
+    Command {
+        name: "edit",
+        usage: "edit <id> --raw [--ignore-limits] | edit <id> --body",
+        summary: "edit a task as JSON, or its notes, in $EDITOR",
+        run: tasks::edit,
+    },
+    Command {
+        name: "done",
+        usage: "done <id> [--note N] [--follow-up | --no-follow-up]",
+        summary: "mark a task as done, or send it for review if it needs one",
+        run: tasks::done,
+    },
+    Command {
+        name: "approve",
+        usage: "approve <id>",
+        summary: "approve a task waiting for review, as someone other than who finished it",
+        run: tasks::approve,
+    },
+    Command {
+        name: "pin",
+        usage: "pin <id>",
+        summary: "keep a task at the top of listings",
+        run: tasks::pin,
+    },
+    Command {
+        name: "unpin",
+        usage: "unpin <id>",
+        summary: "let a pinned task sort normally again",
+        run: tasks::unpin,
+    },
+    Command {
+        name: "alias",
+        usage: "alias [<name> <id> | --remove <name>]",
+        summary: "name a task, to use the name wherever an id goes",
+        run: alias::alias,
+    },
+    Command {
+        name: "star",
+        usage: "star <id>",
+        summary: "add a task to your starred list, without changing how it sorts",
+        run: tasks::star,
+    },
+    Command {
+        name: "unstar",
+        usage: "unstar <id>",
+        summary: "take a task off your starred list",
+        run: tasks::unstar,
+    },
+    Command {
+        name: "starred",
+        usage: "starred [filter]",
+        summary: "list your starred open tasks",
+        run: tasks::starred,
+    },
+    Command {
+        name: "email",
+        usage: "email [--print] <id>",
+        summary: "write to the task's contact in your mail client, or print the mailto: address",
+        run: tasks::email,
+    },
+    Command {
+        name: "config",
+        usage: "config show [--effective]",
+        summary: "show the settings given, or with --effective all of them, and where each came from",
+        run: config::show,
+    },
+    Command {
+        name: "theme",
+        usage: "theme [default | solarized | high-contrast | monochrome]",
+        summary: "preview the colour themes, or switch to one until you quit",
+        run: render::theme,
+    },
+    Command {
+        name: "search",
+        usage: "search [--relative | --absolute] <words>",
+        summary: "find tasks, finished and archived ones too, whose name or description has words starting with these",
+        run: tasks::search,
+    },
+    Command {
+        name: "index",
+        usage: "index rebuild",
+        summary: "rebuild the search index kept beside the data file",
+        run: tasks::index,
+    },
+    Command {
+        name: "remove",
+        usage: "remove [--force] <id>",
+        summary: "delete a task",
+        run: tasks::remove,
+    },
+    Command {
+        name: "reprioritize",
+        usage: "reprioritize [--levels high,medium,low] [filter]",
+        summary: "rank open tasks by comparing them in pairs and reset their priorities to match",
+        run: reprioritize::reprioritize,
+    },
+    Command {
+        name: "normalize-priorities",
+        usage: "normalize-priorities [--dry-run] [filter]",
+        summary: "spread open tasks over the priority levels by due date and importance, after a preview",
+        run: reprioritize::normalize_priorities,
+    },
+    Command {
+        name: "groom",
+        usage: "groom [--after 6w] [--action archive|trash] [--force]",
+        summary: "archive or remove low-priority tasks nobody has touched for a while",
+        run: groom::groom,
+    },
+    Command {
+        name: "tags",
+        usage: "tags [list] | tags rename <old> <new> | tags merge <from> <into> | tags delete [--force] <tag> | tags prune",
+        summary: "list tag usage, or rename, merge and delete tags on every task",
+        run: tags::tags,
+    },
+    Command {
+        name: "project",
+        usage: "project [stats [name]] | project rename <old> <new> | project archive|unarchive <name> | project move <name> <filter>",
+        summary: "show per-project statistics, or rename, archive and move tasks between projects",
+        run: project::project,
+    },
+    Command {
+        name: "apply",
+        usage: "apply <manifest.yaml> [--prune] [--dry-run] [--force]",
+        summary: "create and update tasks to match a manifest, showing the plan first",
+        run: apply::apply,
+    },
+    Command {
+        name: "stats",
+        usage: "stats [snapshot | trend [--days N] | heatmap [--days N] [--json] | tags | projects [--weeks N] [--json]]",
+        summary: "show counts, record today's snapshot, chart the backlog or completions, or time tasks by tag or project",
+        run: stats::stats,
+    },
+    Command {
+        name: "export",
+        usage: "export --html|--ics|--json|--csv|--toml|--yaml|--taskwarrior|--markdown|--mermaid <file> [--diagram flowchart|state] [--filter <expr>] [--anonymize] [--include-private] [--force]",
+        summary: "write the task list to a file",
+        run: export::export,
+    },
+    Command {
+        name: "publish",
+        usage: "publish <dir> [--filter <expr>] [--include-private]",
+        summary: "write a static HTML and JSON report site to a directory",
+        run: export::publish,
+    },
+    Command {
+        name: "backup",
+        usage: "backup remote | backup remote restore [--force]",
+        summary: "upload the data file encrypted to S3-compatible storage, or restore it",
+        run: backup::backup,
+    },
+    Command {
+        name: "store",
+        usage: "store split <dir> [--by project|status|priority] | store join <file> [--force]",
+        summary: "copy the tasks into a directory of files for sharing in git, or back into one file",
+        run: store::store,
+    },
+    Command {
+        name: "history",
+        usage: "history [-n N]",
+        summary: "list the git commits of the data file",
+        run: git::history,
+    },
+    Command {
+        name: "rollback",
+        usage: "rollback <commit> [--force]",
+        summary: "restore the data file as of a git commit, committing the rollback",
+        run: git::rollback,
+    },
+    Command {
+        name: "rules",
+        usage: "rules [list] | rules test [--all]",
+        summary: "show the automation rules, or what they would change now",
+        run: rules::rules,
+    },
+    Command {
+        name: "import",
+        usage: "import --ics|--json|--csv|--toml|--yaml|--taskwarrior <file> | --outlook <file> [--day-first] [--update] [--triage]",
+        summary: "add tasks from another tool's export",
+        run: import::import,
+    },
+    Command {
+        name: "sync",
+        usage: "sync markdown <file> [--dry-run] [--triage] | sync notion [--dry-run] | sync google [--dry-run]",
+        summary: "import new checklist items, database rows or Google tasks and keep them in step",
+        run: sync::sync,
+    },
+    Command {
+        name: "mail",
+        usage: "mail [--maildir <dir>] [--dry-run] [--triage]",
+        summary: "turn new messages in a Maildir or IMAP folder into tasks",
+        run: mail::mail,
+    },
+    Command {
+        name: "jira",
+        usage: "jira link <id> <KEY> | jira unlink <id> | jira sync [--dry-run]",
+        summary: "link a task to a JIRA issue, or mirror completion with linked issues",
+        run: jira::jira,
+    },
+    Command {
+        name: "template",
+        usage:
+            "template add <name> <cron> [field=value...] | template list | template remove <name>",
+        summary: "manage recurring task templates",
+        run: schedule::template,
+    },
+    Command {
+        name: "tick",
+        usage: "tick [--dry-run]",
+        summary: "create tasks for templates whose schedule has fired, and run the rules",
+        run: schedule::tick,
+    },
+    Command {
+        name: "daemon",
+        usage: "daemon [run | status | reload | stop] [--socket <path>]",
+        summary: "run reminders, templates and backups in the background, or control the daemon",
+        run: daemon::daemon,
+    },
+    Command {
+        name: "graph",
+        usage: "graph [--format dot|mermaid] [filter]",
+        summary: "print the dependency and subtask graph",
+        run: export::graph,
+    },
+];
+
+/// Commands left out of the menu and suggestions: checks for developers
+/// and for bug reports.
+const HIDDEN: &[Command] = &[Command {
+    name: "verify-roundtrip",
+    usage: "verify-roundtrip",
+    summary: "check that every import format reads back what export writes",
+    run: export::verify_roundtrip,
+}];
+
+pub(super) fn find_command(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().chain(HIDDEN).find(|c| c.name == name)
+}
+
+/// How errors are written to stderr.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    #[default]
+    Text,
+    /// One JSON object per error, for wrappers:
+    /// `{"error":{"kind":"not_found","code":2,"message":"..."}}`. A refused
+    /// status change adds `reason`, `from` and `to`.
+    Json,
+}
+
+impl ErrorFormat {
+    fn report(self, err: &Error) {
+        match self {
+            ErrorFormat::Text => eprintln!("error: {err}"),
+            ErrorFormat::Json => {
+                let mut details = Value::object();
+                details.insert("kind", err.kind());
+                details.insert("code", i64::from(err.exit_code()));
+                details.insert("message", err.to_string());
+                if let Error::Transition(rejected) = err {
+                    details.insert("reason", rejected.reason.kind());
+                    details.insert("from", rejected.from.as_str());
+                    details.insert("to", rejected.to.as_str());
+                }
+                let mut out = Value::object();
+                out.insert("error", details);
+                eprintln!("{}", out.to_compact());
+            }
+        }
+    }
+}
+
+/// Entry point for the binary; returns the process exit code (see
+/// [`Error::exit_code`]).
+pub fn main() -> i32 {
+    let mut args = Args::new(std::env::args().skip(1).collect());
+    let format = match args.value("--error-format") {
+        Ok(None) => ErrorFormat::Text,
+        Ok(Some(format)) if format == "text" => ErrorFormat::Text,
+        Ok(Some(format)) if format == "json" => ErrorFormat::Json,
+        Ok(Some(other)) => {
+            let err = Error::Invalid(format!(
+                "unknown error format `{other}` (expected text or json)"
+            ));
+            ErrorFormat::Text.report(&err);
+            return err.exit_code();
+        }
+        Err(err) => {
+            ErrorFormat::Text.report(&err);
+            return err.exit_code();
+        }
+    };
+    match run(&mut args, format) {
+        Ok(()) => 0,
+        Err(err) => {
+            format.report(&err);
+            err.exit_code()
+        }
+    }
+}
+
+fn run(args: &mut Args, format: ErrorFormat) -> Result<()> {
+    if args.flag("--timings") {
+        timing::enable();
+    }
+    let result = run_command(args, format);
+    let spans = timing::take();
+    if !spans.is_empty() {
+        eprint!("Timings:\n{}", timing::report(&spans));
+    }
+    result
+}
+
+fn run_command(args: &mut Args, format: ErrorFormat) -> Result<()> {
+    let env = |name: &str| env::var_os(format!("{ENV_PREFIX}{name}")).filter(|v| !v.is_empty());
+    let path = match args.value("--file")? {
+        Some(path) => PathBuf::from(path),
+        None => env("FILE").map_or_else(|| PathBuf::from(DEFAULT_FILE), PathBuf::from),
+    };
+    let config_path = args
+        .value("--config")?
+        .map(PathBuf::from)
+        .or_else(|| env("CONFIG").map(PathBuf::from))
+        .or_else(Config::default_path);
+    // Variables that are not valid Unicode cannot name a setting.
+    let mut overrides =
+        Override::from_env(env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        }))?;
+    while let Some(setting) = args.value("--setting")? {
+        overrides.push(Override::flag(&setting)?);
+    }
+    if args.flag("--brief") {
+        overrides.push(Override {
+            key: "display.brief".into(),
+            value: Value::Bool(true),
+            origin: Origin::Flag("--brief".into()),
+        });
+    }
+    let name = args.positional();
+    // A command holds the lock from reading the tasks to saving them, so
+    // the daemon's checks cannot slip in between. The daemon takes it for
+    // each check instead, and the shell, which stays open, not at all.
+    // Where no lock file can be made, as in a read-only directory, commands
+    // go on without.
+    let _lock = match name.as_deref() {
+        Some("daemon") | None => None,
+        Some(_) => JsonStore::new(&path).lock(true).unwrap_or(None),
+    };
+    let mut ctx = Context::open(path, config_path, overrides)?;
+    match name {
+        Some(name) => {
+            timing::timed("command", || execute(&mut ctx, &name, std::mem::take(args)))?;
+            ctx.save_if_modified()
+        }
+        None => {
+            args.clone().finish()?;
+            interactive(ctx, format)
+        }
+    }
+}
+
+fn execute(ctx: &mut Context, name: &str, args: Args) -> Result<()> {
+    ctx.command = std::iter::once(name)
+        .chain(args.items().iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if find_command(name).is_none() {
+        if let Some(path) = plugin::find(name) {
+            return plugin::run(ctx, name, &path, args);
+        }
+    }
+    let command = find_command(name).ok_or_else(|| {
+        let names = suggest::closest(name, COMMANDS.iter().map(|c| c.name));
+        let names: Vec<String> = names.iter().map(|n| format!("`{n}`")).collect();
+        Error::Invalid(format!(
+            "unknown command `{name}`{}",
+            suggest::did_you_mean(&names)
+        ))
+    })?;
+    (command.run)(ctx, args)
+}
+
+pub(super) fn print_menu() {
+    println!("Commands:");
+    for command in COMMANDS {
+        println!("  {:<24} {}", command.usage, command.summary);
+    }
+    println!("  {:<24} leave the task manager", "quit");
+    let plugins = plugin::installed();
+    if !plugins.is_empty() {
+        println!("Plugins: {}", plugins.join(", "));
+    }
+}
+
+/// One line of counts, e.g. `12 open | 3 due today | 2 overdue | 1 in progress`.
+fn print_summary(ctx: &Context) {
+    let summary = Summary::of(ctx.manager.tasks(), Timestamp::now());
+    let mut overdue = format!("{} overdue", summary.overdue);
+    if summary.overdue > 0 && render::color_enabled() {
+        overdue = theme::paint(&theme::theme().overdue, &overdue);
+    }
+    println!(
+        "[{} open | {} due today | {overdue} | {} in progress]",
+        summary.open, summary.due_today, summary.in_progress
+    );
+}
+
+/// The menu-driven session: reads commands from stdin until `quit` or end
+/// of input, saving as `[autosave]` says and once more at the end.
+fn interactive(ctx: Context, format: ErrorFormat) -> Result<()> {
+    print_summary(&ctx);
+    print_menu();
+    let autosave = Autosave::start(ctx);
+    while let Some(line) = prompt::ask("> ") {
+        let words = match args::split_words(&line) {
+            Ok(words) => words,
+            Err(err) => {
+                format.report(&err);
+                continue;
+            }
+        };
+        let Some((name, rest)) = words.split_first() else {
+            continue;
+        };
+        match name.as_str() {
+            "quit" | "exit" | "q" => break,
+            "?" => print_menu(),
+            _ => {
+                let mut ctx = autosave.lock();
+                let result = execute(&mut ctx, name, Args::new(rest.to_vec()))
+                    .and_then(|()| autosave.command_done(&mut ctx));
+                if let Err(err) = result {
+                    format.report(&err);
+                }
+                print_summary(&ctx);
+            }
+        }
+    }
+    autosave.finish()
+}
@@ -0,0 +1,256 @@
+//! `sync notion`: keeping the tasks and a Notion database in step.
+//!
+//! Rows without a task become tasks, linked by their page id; after that
+//! completion is mirrored both ways, as `jira sync` does for issues. The
+//! API is reached through `curl` (see [`http`](super::http)), which must
+//! be installed.
+
+use task_manager::config::{Layers, NotionColumns, NotionSettings};
+use task_manager::dates::{self, Timestamp};
+use task_manager::json::Value;
+use task_manager::render::short_id;
+use task_manager::{Error, Result, Status, Task};
+
+use super::http::{required, Client};
+use super::Context;
+
+const API: &str = "https://api.notion.com/v1";
+
+/// The API version the requests are written against.
+const VERSION: &str = "2022-06-28";
+
+pub fn sync(ctx: &mut Context, dry_run: bool) -> Result<()> {
+    let database = Database::from_settings(&ctx.config.notion, &ctx.layers()?)?;
+    let rows = database.rows()?;
+    let user = ctx.config.user_name();
+    let now = Timestamp::now();
+    let (mut created, mut completed, mut pushed, mut failed) = (0, 0, 0, 0);
+    for row in &rows {
+        let linked = ctx
+            .manager
+            .tasks()
+            .iter()
+            .position(|t| t.notion.as_deref() == Some(row.id.as_str()));
+        let row_done = row.status.as_deref().is_some_and(|s| database.is_done(s));
+        let Some(index) = linked else {
+            let task = row.to_task(row_done, now);
+            if dry_run {
+                println!("+ {}", task.name);
+            } else {
+                let name = task.name.clone();
+                let id = ctx.manager.add(task);
+                println!("+ {name} ({})", short_id(&id.to_string()));
+            }
+            created += 1;
+            continue;
+        };
+        let task = &mut ctx.manager.tasks_mut()[index];
+        if row_done && task.status.is_open() && task.status != Status::ReviewPending {
+            let action = if dry_run {
+                "would complete"
+            } else {
+                match task.finish(&user) {
+                    Status::Done => "completed",
+                    _ => "sent for review",
+                }
+            };
+            println!("{} is done in Notion: {action} here", task.name);
+            completed += 1;
+        } else if !row_done && task.status == Status::Done {
+            let result = if dry_run {
+                Ok(())
+            } else {
+                database.mark_done(row)
+            };
+            if let Err(err) = result {
+                eprintln!("warning: {}: {err}", task.name);
+                failed += 1;
+                continue;
+            }
+            let action = if dry_run { "would mark" } else { "marked" };
+            println!(
+                "{} is done: {action} it {} in Notion",
+                task.name, database.settings.done
+            );
+            pushed += 1;
+        }
+    }
+    if !dry_run && created + completed > 0 {
+        ctx.modified();
+    }
+    let prefix = if dry_run { "Would sync: " } else { "" };
+    let mut summary = format!(
+        "{prefix}{created} row(s) added as tasks, {completed} task(s) completed here, {pushed} row(s) marked done"
+    );
+    if failed > 0 {
+        summary.push_str(&format!(", {failed} failed"));
+    }
+    println!("{summary}, of {} in the database.", rows.len());
+    Ok(())
+}
+
+/// The database with the credentials, checked and with the environment
+/// filled in.
+struct Database<'a> {
+    id: String,
+    settings: &'a NotionSettings,
+    client: Client,
+}
+
+/// What `sync notion` reads of a row.
+struct Row {
+    id: String,
+    name: String,
+    /// The status option, if one is chosen.
+    status: Option<String>,
+    /// Whether the status column is a status or a select property; they are
+    /// written differently.
+    status_kind: String,
+    due: Option<String>,
+    priority: Option<String>,
+    description: String,
+}
+
+impl<'a> Database<'a> {
+    fn from_settings(settings: &'a NotionSettings, layers: &Layers) -> Result<Self> {
+        let id = settings.database.clone().ok_or_else(|| {
+            Error::Invalid("set `notion.database` in the config file to the database's id".into())
+        })?;
+        let token = required(
+            layers,
+            "notion.token",
+            &settings.token,
+            Some("NOTION_TOKEN"),
+        )?;
+        let client = Client::new("Notion")
+            .header(&format!("Authorization: Bearer {token}"))
+            .header(&format!("Notion-Version: {VERSION}"));
+        Ok(Database {
+            id,
+            settings,
+            client,
+        })
+    }
+
+    fn is_done(&self, status: &str) -> bool {
+        status.eq_ignore_ascii_case(&self.settings.done)
+    }
+
+    /// Every row that is not archived, a page of results at a time.
+    fn rows(&self) -> Result<Vec<Row>> {
+        let url = format!("{API}/databases/{}/query", self.id);
+        let mut rows = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut query = Value::object();
+            query.insert("page_size", 100i64);
+            if let Some(cursor) = &cursor {
+                query.insert("start_cursor", cursor.as_str());
+            }
+            let response = self.client.request("POST", &url, Some(&query))?;
+            let pages = response
+                .get("results")
+                .and_then(Value::as_array)
+                .ok_or_else(|| Error::Invalid("Notion sent no rows".into()))?;
+            for page in pages {
+                let archived = ["archived", "in_trash"]
+                    .iter()
+                    .any(|key| page.get(key).and_then(Value::as_bool) == Some(true));
+                if !archived {
+                    rows.push(Row::from_page(page, &self.settings.columns)?);
+                }
+            }
+            cursor = response
+                .get("next_cursor")
+                .and_then(Value::as_str)
+                .map(String::from)
+                .filter(|_| response.get("has_more").and_then(Value::as_bool) == Some(true));
+            if cursor.is_none() {
+                return Ok(rows);
+            }
+        }
+    }
+
+    fn mark_done(&self, row: &Row) -> Result<()> {
+        let mut option = Value::object();
+        option.insert("name", self.settings.done.as_str());
+        let mut status = Value::object();
+        status.insert(&row.status_kind, option);
+        let mut properties = Value::object();
+        properties.insert(&self.settings.columns.status, status);
+        let mut body = Value::object();
+        body.insert("properties", properties);
+        let url = format!("{API}/pages/{}", row.id);
+        self.client.request("PATCH", &url, Some(&body))?;
+        Ok(())
+    }
+}
+
+impl Row {
+    fn from_page(page: &Value, columns: &NotionColumns) -> Result<Self> {
+        let id = page
+            .get("id")
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::Invalid("Notion sent a row without an id".into()))?;
+        let property = |name: &str| page.get("properties").and_then(|p| p.get(name));
+        let optional = |column: &Option<String>| column.as_deref().and_then(property);
+        let status = property(&columns.status);
+        let status_kind = status
+            .and_then(|s| s.get("type"))
+            .and_then(Value::as_str)
+            .unwrap_or("status");
+        Ok(Row {
+            id: id.to_string(),
+            name: property(&columns.name).map(text).unwrap_or_default(),
+            status: status.and_then(option),
+            status_kind: status_kind.to_string(),
+            due: optional(&columns.due)
+                .and_then(|d| d.get("date")?.get("start")?.as_str())
+                .map(String::from),
+            priority: optional(&columns.priority).and_then(option),
+            description: optional(&columns.description).map(text).unwrap_or_default(),
+        })
+    }
+
+    /// A new task from the row, finished if `done`. Values that do not
+    /// fit, such as an unknown priority, are left out.
+    fn to_task(&self, done: bool, now: Timestamp) -> Task {
+        let name = match self.name.trim() {
+            "" => "Untitled",
+            name => name,
+        };
+        let mut task = Task::new(name);
+        task.set_description_or_body(&self.description);
+        task.notion = Some(self.id.clone());
+        if let Some(priority) = self.priority.as_deref().and_then(|p| p.parse().ok()) {
+            task.priority = priority;
+        }
+        task.due = self.due.as_deref().and_then(|due| {
+            Timestamp::parse_rfc3339(due)
+                .or_else(|_| dates::parse_when(due, now))
+                .ok()
+        });
+        if done {
+            task.set_status(Status::Done);
+        }
+        task
+    }
+}
+
+/// The plain text of a title or text property.
+fn text(property: &Value) -> String {
+    let kind = property.get("type").and_then(Value::as_str).unwrap_or("");
+    property
+        .get(kind)
+        .and_then(Value::as_array)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|part| part.get("plain_text").and_then(Value::as_str))
+        .collect()
+}
+
+/// The chosen option's name of a status or select property.
+fn option(property: &Value) -> Option<String> {
+    let kind = property.get("type")?.as_str()?;
+    Some(property.get(kind)?.get("name")?.as_str()?.to_string())
+}
@@ -0,0 +1,119 @@
+//! Commands provided by plugins: `task-manager foo` runs
+//! `task-manager-foo` from `PATH` when there is no built-in `foo`. See
+//! [`task_manager::plugin`] for what it is sent and may answer.
+
+use std::env;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use task_manager::dates::Timestamp;
+use task_manager::plugin::{self, Reply};
+use task_manager::{filter, Error, Result, Task};
+
+use super::{Args, Context};
+
+/// The executable for command `name`, if one is on `PATH`.
+pub fn find(name: &str) -> Option<PathBuf> {
+    // A name with a separator would reach outside `PATH`.
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return None;
+    }
+    let file = format!("{}{name}", plugin::PREFIX);
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(&file))
+        .find(|path| is_executable(path))
+}
+
+/// Names of the plugin commands on `PATH`, sorted, for the menu.
+pub fn installed() -> Vec<String> {
+    let Some(path) = env::var_os("PATH") else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = env::split_paths(&path)
+        .filter_map(|dir| dir.read_dir().ok())
+        .flatten()
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let file = entry.file_name().into_string().ok()?;
+            let name = file.strip_prefix(plugin::PREFIX)?;
+            is_executable(&entry.path()).then(|| name.to_string())
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Runs the plugin at `path` as command `name`. `--select <filter>` picks
+/// the tasks it is sent, open ones outside archived projects by default;
+/// every other argument is passed on.
+pub fn run(ctx: &mut Context, name: &str, path: &Path, mut args: Args) -> Result<()> {
+    let now = Timestamp::now();
+    let tasks: Vec<&Task> = match args.value("--select")? {
+        Some(expression) => filter::parse(&expression, now)?.apply(ctx.manager.tasks(), now),
+        None => ctx
+            .manager
+            .tasks()
+            .iter()
+            .filter(|t| {
+                t.status.is_open()
+                    && !t.archived
+                    && !t
+                        .project
+                        .as_deref()
+                        .is_some_and(|p| ctx.manager.is_archived(p))
+            })
+            .collect(),
+    };
+    let request = plugin::request(name, args.items(), &tasks);
+    let mut command = Command::new(path);
+    command
+        .args(args.items())
+        .env("TASK_MANAGER_FILE", ctx.data_path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped());
+    if let Some(config) = &ctx.config_path {
+        command.env("TASK_MANAGER_CONFIG", config);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|err| Error::Invalid(format!("could not run {}: {err}", path.display())))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // A plugin that ignores its input may exit before reading it all.
+    let _ = stdin.write_all(request.to_compact().as_bytes());
+    drop(stdin);
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(Error::Invalid(format!(
+            "plugin `{name}` failed ({})",
+            output.status
+        )));
+    }
+    let reply = Reply::parse(&String::from_utf8_lossy(&output.stdout))
+        .map_err(|err| Error::Invalid(format!("plugin `{name}`: {err}")))?;
+    let applied = reply.apply(&mut ctx.manager, &ctx.config.workflow, now)?;
+    if let Some(message) = &reply.message {
+        println!("{message}");
+    }
+    if !applied.is_empty() {
+        println!(
+            "{name}: {} created, {} updated, {} removed",
+            applied.created, applied.updated, applied.removed
+        );
+        ctx.modified();
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -0,0 +1,184 @@
+//! `project`: per-project statistics and renaming, archiving and moving
+//! tasks between projects in bulk.
+
+use task_manager::dates::Timestamp;
+use task_manager::{filter, render, suggest};
+use task_manager::{Error, Result, Status, Task};
+
+use super::{Args, Context};
+
+/// Cells in a project's progress bar.
+const PROGRESS_WIDTH: usize = 10;
+
+pub fn project(ctx: &mut Context, mut args: Args) -> Result<()> {
+    match args.positional().as_deref() {
+        None | Some("stats") => {
+            let name = args.positional();
+            args.finish()?;
+            let name = name.map(|n| find(ctx, &n)).transpose()?;
+            print_stats(ctx, name.as_deref());
+            Ok(())
+        }
+        Some("rename") => {
+            let from = args.required("project to rename")?;
+            let to = project_name(args.required("new project name")?)?;
+            args.finish()?;
+            let from = find(ctx, &from)?;
+            // Changing only the case is a rename, not a merge.
+            if !to.eq_ignore_ascii_case(&from) && lookup(ctx, &to).is_some() {
+                return Err(Error::Conflict(format!(
+                    "project \"{to}\" already exists; use `project move \"{to}\" project:\"{from}\"` to move the tasks into it"
+                )));
+            }
+            let changed = ctx.manager.rename_project(&from, &to);
+            println!("Renamed project \"{from}\" to \"{to}\" ({changed} task(s))");
+            ctx.modified();
+            Ok(())
+        }
+        Some(command @ ("archive" | "unarchive")) => {
+            let name = args.required("project name")?;
+            args.finish()?;
+            let name = find(ctx, &name)?;
+            let archive = command == "archive";
+            if !ctx.manager.set_archived(&name, archive) {
+                let state = if archive { "already" } else { "not" };
+                println!("Project \"{name}\" is {state} archived.");
+                return Ok(());
+            }
+            if archive {
+                println!(
+                    "Archived project \"{name}\"; its tasks are hidden from `list` unless asked for with --all or project:"
+                );
+            } else {
+                println!("Restored project \"{name}\"");
+            }
+            ctx.modified();
+            Ok(())
+        }
+        Some("move") => {
+            let to = project_name(args.required("target project")?)?;
+            let expression = args.rest()?.join(" ");
+            if expression.trim().is_empty() {
+                return Err(Error::Invalid(
+                    "give a filter for the tasks to move, e.g. `project move work tag:office`"
+                        .into(),
+                ));
+            }
+            let now = Timestamp::now();
+            let filter = filter::parse(&expression, now)?;
+            // Keep the spelling of an existing project.
+            let to = lookup(ctx, &to).unwrap_or(to);
+            let ids: Vec<_> = filter
+                .apply(ctx.manager.tasks(), now)
+                .iter()
+                .filter(|t| t.project.as_deref() != Some(to.as_str()))
+                .map(|t| t.id)
+                .collect();
+            for &id in &ids {
+                let task = ctx.manager.get_mut(id).expect("filtered task exists");
+                task.project = Some(to.clone());
+            }
+            println!("Moved {} task(s) to project \"{to}\"", ids.len());
+            if !ids.is_empty() {
+                ctx.modified();
+            }
+            Ok(())
+        }
+        Some(other) => Err(Error::Invalid(format!(
+            "unknown project command `{other}` (expected stats, rename, archive, unarchive or move)"
+        ))),
+    }
+}
+
+fn print_stats(ctx: &Context, only: Option<&str>) {
+    let now = Timestamp::now();
+    let mut names = project_names(ctx);
+    if let Some(only) = only {
+        names.retain(|n| n == only);
+    }
+    if names.is_empty() {
+        println!("No projects.");
+        return;
+    }
+    println!(
+        "{:<24} {:>5} {:>11} {:>6} {:>5} {:>9} {:>7}  Progress",
+        "Project", "Todo", "In progress", "Review", "Done", "Cancelled", "Overdue"
+    );
+    for name in names {
+        let tasks: Vec<&Task> = ctx
+            .manager
+            .tasks()
+            .iter()
+            .filter(|t| {
+                t.project
+                    .as_deref()
+                    .is_some_and(|p| p.eq_ignore_ascii_case(&name))
+            })
+            .collect();
+        let count = |status: Status| tasks.iter().filter(|t| t.status == status).count();
+        let done = count(Status::Done);
+        let cancelled = count(Status::Cancelled);
+        // Cancelled tasks count neither for nor against progress.
+        let counted = tasks.len() - cancelled;
+        let progress = render::progress_bar(done, counted, PROGRESS_WIDTH, &ctx.config.glyphs);
+        let label = if ctx.manager.is_archived(&name) {
+            format!("{name} (archived)")
+        } else {
+            name
+        };
+        println!(
+            "{label:<24} {:>5} {:>11} {:>6} {done:>5} {cancelled:>9} {:>7}  {progress}",
+            count(Status::Todo),
+            count(Status::InProgress),
+            count(Status::ReviewPending),
+            tasks.iter().filter(|t| t.is_overdue(now)).count(),
+        );
+    }
+}
+
+/// Every project a task belongs to or that is archived, sorted, spelled as
+/// first seen.
+fn project_names(ctx: &Context) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let projects = ctx
+        .manager
+        .tasks()
+        .iter()
+        .filter_map(|t| t.project.as_deref())
+        .chain(ctx.manager.archived_projects().iter().map(String::as_str));
+    for name in projects {
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            names.push(name.to_string());
+        }
+    }
+    names.sort_by_key(|n| n.to_lowercase());
+    names
+}
+
+fn project_name(name: String) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::Invalid("project name cannot be empty".into()));
+    }
+    Ok(name.to_string())
+}
+
+fn lookup(ctx: &Context, name: &str) -> Option<String> {
+    project_names(ctx)
+        .into_iter()
+        .find(|n| n.eq_ignore_ascii_case(name.trim()))
+}
+
+fn find(ctx: &Context, name: &str) -> Result<String> {
+    lookup(ctx, name).ok_or_else(|| {
+        let names = project_names(ctx);
+        let close: Vec<String> = suggest::closest(name, names.iter().map(String::as_str))
+            .iter()
+            .map(|n| format!("\"{n}\""))
+            .collect();
+        Error::NotFound(format!(
+            "project \"{name}\" not found{}",
+            suggest::did_you_mean(&close)
+        ))
+    })
+}
@@ -0,0 +1,28 @@
+//! Line-based prompts on stdin/stdout.
+
+use std::io::{self, BufRead, Write};
+
+/// Prints `question` and reads one trimmed line. Returns `None` on EOF.
+pub fn ask(question: &str) -> Option<String> {
+    print!("{question}");
+    io::stdout().flush().ok()?;
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_string()),
+    }
+}
+
+/// Asks with a default shown in brackets; an empty answer keeps the default.
+pub fn ask_default(question: &str, default: &str) -> String {
+    match ask(&format!("{question} [{default}]: ")) {
+        Some(answer) if !answer.is_empty() => answer,
+        _ => default.to_string(),
+    }
+}
+
+/// Asks a yes/no question; anything but `y` or `yes` (including EOF) is no.
+pub fn confirm(question: &str) -> bool {
+    ask(&format!("{question} [y/N] "))
+        .is_some_and(|answer| matches!(answer.to_ascii_lowercase().as_str(), "y" | "yes"))
+}
@@ -0,0 +1,199 @@
+//! Terminal side of the layouts in [`task_manager::render`]: how wide to
+//! wrap, whether to colour and in which theme, and whether to write
+//! hyperlinks.
+//!
+//! Output that is not going to a terminal is not wrapped, so `list | grep`
+//! keeps one task per line; setting `COLUMNS` forces a width.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, IsTerminal};
+use std::process::{Command, Stdio};
+use std::sync::{PoisonError, RwLock};
+
+use task_manager::config::{Glyphs, HyperlinkMode, LinkSettings, ThemeName, ThemeSettings};
+use task_manager::dates::Timestamp;
+use task_manager::render::highlight::Search;
+use task_manager::render::links::{self, Links};
+use task_manager::render::theme::{self, Theme};
+use task_manager::{Priority, Result, Status, Task, TasksManager};
+
+use super::{Args, Context};
+
+pub use task_manager::render::{Tags, Times};
+
+/// `Some` with the address JIRA keys link to while hyperlinks are on.
+static HYPERLINKS: RwLock<Option<Option<String>>> = RwLock::new(None);
+
+/// Turns hyperlinks in [`task_table`] and [`task_detail`] on or off as
+/// `settings` and the terminal allow.
+pub fn set_hyperlinks(settings: &LinkSettings) {
+    let enabled = match settings.hyperlinks {
+        HyperlinkMode::Always => true,
+        HyperlinkMode::Never => false,
+        HyperlinkMode::Auto => io::stdout().is_terminal() && supports_hyperlinks(),
+    };
+    *HYPERLINKS.write().unwrap_or_else(PoisonError::into_inner) =
+        enabled.then(|| settings.jira.clone());
+}
+
+/// Whether the terminal is one known to open OSC 8 links. Others may show
+/// the escapes as text, so unknown terminals get none.
+fn supports_hyperlinks() -> bool {
+    let var = |name| env::var(name).unwrap_or_default();
+    matches!(
+        var("TERM_PROGRAM").as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper" | "Tabby"
+    ) || matches!(
+        var("TERM").as_str(),
+        "xterm-kitty" | "xterm-ghostty" | "foot" | "alacritty"
+    ) || ["WT_SESSION", "KONSOLE_VERSION", "DOMTERM"]
+        .iter()
+        .any(|name| env::var_os(name).is_some())
+        || var("VTE_VERSION").parse::<u32>().is_ok_and(|v| v >= 5000)
+}
+
+/// `text` with links added, when they are on, for the issue keys of `tasks`.
+fn linked<'a>(text: String, tasks: impl IntoIterator<Item = &'a Task>) -> String {
+    let settings = HYPERLINKS.read().unwrap_or_else(PoisonError::into_inner);
+    let Some(jira) = &*settings else {
+        return text;
+    };
+    let links = Links {
+        jira: jira.as_deref(),
+        issues: tasks
+            .into_iter()
+            .filter_map(|t| t.jira.as_deref())
+            .collect(),
+    };
+    text.lines()
+        .map(|line| links::linkify(line, &links) + "\n")
+        .collect()
+}
+
+/// [`task_manager::render::task_table`], with hyperlinks when they are on.
+pub fn task_table(
+    tasks: &[&Task],
+    times: Times,
+    tags: Tags,
+    glyphs: &Glyphs,
+    search: &Search,
+    width: Option<usize>,
+) -> String {
+    let table = task_manager::render::task_table(tasks, times, tags, glyphs, search, width);
+    linked(table, tasks.iter().copied())
+}
+
+/// `tasks` as `--brief` sentences, one per line, see
+/// [`task_manager::render::brief`].
+pub fn brief(tasks: &[&Task], now: Timestamp) -> String {
+    tasks
+        .iter()
+        .map(|task| format!("{}\n", task_manager::render::brief(task, now)))
+        .collect()
+}
+
+/// [`task_manager::render::task_detail`], with hyperlinks when they are on.
+pub fn task_detail(
+    task: &Task,
+    effective: Priority,
+    manager: &TasksManager,
+    times: Times,
+    glyphs: &Glyphs,
+    width: Option<usize>,
+) -> String {
+    let detail = task_manager::render::task_detail(task, effective, manager, times, glyphs, width);
+    linked(detail, [task])
+}
+
+/// The width to wrap output to, or `None` when it should not be wrapped.
+pub fn terminal_width() -> Option<usize> {
+    if let Some(columns) = env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.trim().parse().ok())
+        .filter(|&c: &usize| c > 0)
+    {
+        return Some(columns);
+    }
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    Some(stty_columns().unwrap_or(80))
+}
+
+/// Whether to colour output: only on a terminal, and not when `NO_COLOR` is
+/// set.
+pub fn color_enabled() -> bool {
+    io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+fn stty_columns() -> Option<usize> {
+    let tty = File::open("/dev/tty").ok()?;
+    let output = Command::new("stty")
+        .arg("size")
+        .stdin(tty)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    String::from_utf8(output.stdout)
+        .ok()?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+        .filter(|&c: &usize| c > 0)
+}
+
+/// `theme`: previews the colour themes, or switches to one for the rest of
+/// an interactive session.
+pub fn theme(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let name = args.positional();
+    args.finish()?;
+    let Some(name) = name else {
+        let current = theme::theme().name;
+        let color = color_enabled();
+        for name in ThemeName::ALL {
+            let marker = if name == current { '*' } else { ' ' };
+            let sample = preview(&Theme::named(name), color);
+            println!("{marker} {:<14} {sample}", name.as_str());
+        }
+        return Ok(());
+    };
+    let name: ThemeName = name.parse()?;
+    theme::set_theme(Theme::from_settings(&ThemeSettings {
+        name,
+        colors: ctx.config.theme.colors.clone(),
+    }));
+    println!(
+        "Using the {} theme until you quit; set `name` under [theme] in the config to keep it.",
+        name.as_str()
+    );
+    Ok(())
+}
+
+/// Every priority and status in the theme's colours.
+fn preview(theme: &Theme, color: bool) -> String {
+    let paint = |escape: &str, text: &str| {
+        if color {
+            theme::paint(escape, text)
+        } else {
+            text.to_string()
+        }
+    };
+    let priorities = [
+        Priority::Critical,
+        Priority::High,
+        Priority::Medium,
+        Priority::Low,
+    ]
+    .map(|p| paint(theme.priority(p), &p.to_string()));
+    let statuses = [
+        Status::Todo,
+        Status::InProgress,
+        Status::ReviewPending,
+        Status::Done,
+        Status::Cancelled,
+    ]
+    .map(|s| paint(theme.status(s), &s.to_string()));
+    format!("{}  {}", priorities.join(" "), statuses.join(" "))
+}
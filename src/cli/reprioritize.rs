@@ -0,0 +1,167 @@
+//! `reprioritize`: rank open tasks by asking which of two matters more,
+//! then spread the ranking over priority levels, for when everything has
+//! drifted to high. `normalize-priorities` does the same without asking,
+//! for the hundreds of tasks a bulk import leaves behind.
+
+use task_manager::dates::Timestamp;
+use task_manager::rank;
+use task_manager::render::short_id;
+use task_manager::{filter, Error, Priority, Result, Task, TaskId};
+
+use super::prompt::{ask, confirm};
+use super::{Args, Context};
+
+const DEFAULT_LEVELS: [Priority; 3] = [Priority::High, Priority::Medium, Priority::Low];
+
+pub fn reprioritize(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let levels = match args.value("--levels")? {
+        Some(list) => parse_levels(&list)?,
+        None => DEFAULT_LEVELS.to_vec(),
+    };
+    let now = Timestamp::now();
+    let filter = filter::parse(&args.rest()?.join(" "), now)?;
+    let tasks: Vec<&Task> = filter
+        .apply(ctx.manager.tasks(), now)
+        .into_iter()
+        .filter(|t| t.status.is_open() && !t.archived)
+        .collect();
+    if tasks.len() < 2 {
+        println!("Fewer than two open tasks to compare.");
+        return Ok(());
+    }
+    println!(
+        "Ranking {} tasks: at most {} questions. Answer 1 or 2, or q to stop without changes.",
+        tasks.len(),
+        rank::comparisons(tasks.len())
+    );
+    let Some(ranked) = rank::rank(tasks, |a, b| ask_which(a, b)) else {
+        println!("Stopped; nothing changed.");
+        return Ok(());
+    };
+    let proposed: Vec<(TaskId, Priority)> = ranked
+        .iter()
+        .zip(rank::spread(ranked.len(), &levels))
+        .map(|(task, priority)| (task.id, priority))
+        .collect();
+    println!();
+    let mut changes = 0;
+    for (place, (task, (_, priority))) in ranked.iter().zip(&proposed).enumerate() {
+        let change = if task.priority == *priority {
+            format!("{priority}")
+        } else {
+            changes += 1;
+            format!("{} -> {priority}", task.priority)
+        };
+        println!(
+            "{:>3}. {}  {change:<20} {}",
+            place + 1,
+            short_id(&task.id.to_string()),
+            task.name
+        );
+    }
+    if changes == 0 {
+        println!("Priorities already match the ranking.");
+        return Ok(());
+    }
+    apply(ctx, proposed, changes)
+}
+
+pub fn normalize_priorities(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let dry_run = args.flag("--dry-run");
+    let now = Timestamp::now();
+    let filter = filter::parse(&args.rest()?.join(" "), now)?;
+    let tasks: Vec<&Task> = filter
+        .apply(ctx.manager.tasks(), now)
+        .into_iter()
+        .filter(|t| t.status.is_open() && !t.archived)
+        .collect();
+    if tasks.is_empty() {
+        println!("No open tasks to normalize.");
+        return Ok(());
+    }
+    let ranked = rank::by_importance(tasks, ctx.manager.tasks(), now);
+    let levels = rank::spread_shares(ranked.len(), &ctx.config.priorities.shares);
+    let mut before = [0; 4];
+    let mut after = [0; 4];
+    let mut proposed = Vec::new();
+    for (task, priority) in ranked.iter().zip(levels) {
+        before[task.priority as usize] += 1;
+        after[priority as usize] += 1;
+        if task.priority != priority {
+            let id = task.id.to_string();
+            let id = short_id(&id);
+            println!("- {id}  {:<8}  {}", task.priority.to_string(), task.name);
+            println!("+ {id}  {:<8}  {}", priority.to_string(), task.name);
+            proposed.push((task.id, priority));
+        }
+    }
+    if proposed.is_empty() {
+        println!(
+            "Priorities of {} tasks already fit the scheme.",
+            ranked.len()
+        );
+        return Ok(());
+    }
+    let counts: Vec<String> = Priority::ALL
+        .iter()
+        .rev()
+        .map(|&p| format!("{p} {} -> {}", before[p as usize], after[p as usize]))
+        .collect();
+    println!(
+        "\n{} of {} tasks change: {}",
+        proposed.len(),
+        ranked.len(),
+        counts.join(", ")
+    );
+    if dry_run {
+        return Ok(());
+    }
+    let changes = proposed.len();
+    apply(ctx, proposed, changes)
+}
+
+/// Sets the proposed priorities once the person agrees to `changes` of them.
+fn apply(ctx: &mut Context, proposed: Vec<(TaskId, Priority)>, changes: usize) -> Result<()> {
+    if !confirm(&format!("Apply {changes} priority change(s)?")) {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    for (id, priority) in proposed {
+        ctx.manager
+            .get_mut(id)
+            .expect("ranked task exists")
+            .priority = priority;
+    }
+    ctx.modified();
+    println!("Updated {changes} task(s)");
+    Ok(())
+}
+
+/// Asks whether `a` matters more than `b`; `None` when the person quits.
+fn ask_which(a: &Task, b: &Task) -> Option<bool> {
+    println!("\nWhich matters more?");
+    println!("  1) {} ({})", a.name, a.priority);
+    println!("  2) {} ({})", b.name, b.priority);
+    loop {
+        match ask("[1/2/q] ")?.to_ascii_lowercase().as_str() {
+            "1" => return Some(true),
+            "2" => return Some(false),
+            "q" | "quit" => return None,
+            _ => {}
+        }
+    }
+}
+
+/// A comma-separated list of levels, most important first.
+fn parse_levels(list: &str) -> Result<Vec<Priority>> {
+    let levels = list
+        .split(',')
+        .map(|level| level.trim().parse())
+        .collect::<Result<Vec<Priority>>>()?;
+    if levels.is_empty() || levels.windows(2).any(|pair| pair[0] <= pair[1]) {
+        return Err(Error::Invalid(format!(
+            "`--levels` needs distinct priorities from highest to lowest, e.g. high,medium,low; found `{list}`"
+        )));
+    }
+    Ok(levels)
+}
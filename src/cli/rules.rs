@@ -0,0 +1,74 @@
+//! `rules`: the automation rules from the config, and a dry run of them.
+
+use task_manager::dates::Timestamp;
+use task_manager::render::short_id;
+use task_manager::rules::{self, Firing};
+use task_manager::{filter, Error, Result};
+
+use super::{Args, Context};
+
+pub fn rules(ctx: &mut Context, mut args: Args) -> Result<()> {
+    match args.positional().as_deref() {
+        None | Some("list") => {
+            args.finish()?;
+            list(ctx)
+        }
+        Some("test") => {
+            let all = args.flag("--all");
+            args.finish()?;
+            test(ctx, all)
+        }
+        Some(other) => Err(Error::Invalid(format!(
+            "unknown rules action `{other}` (expected list or test)"
+        ))),
+    }
+}
+
+fn list(ctx: &Context) -> Result<()> {
+    if ctx.config.rules.is_empty() {
+        println!("No rules; add `[[rules]]` tables to the config file.");
+        return Ok(());
+    }
+    let now = Timestamp::now();
+    for rule in &ctx.config.rules {
+        let matching = filter::parse(&rule.when, now)?
+            .apply(ctx.manager.tasks(), now)
+            .len();
+        println!("{}", rule.name);
+        println!("  when {} ({matching} task(s) match now)", rule.when);
+        println!("  set  {}", rule.set.join(" "));
+    }
+    Ok(())
+}
+
+/// What the rules would change now, as the next save or `tick` would, or
+/// with `all` on every task they match, as if each had just been added.
+/// Nothing is saved.
+fn test(ctx: &Context, all: bool) -> Result<()> {
+    let mut manager = ctx.manager.clone();
+    let before = if all { &[][..] } else { ctx.manager.tasks() };
+    let firings = rules::run(&ctx.config.rules, &mut manager, before, Timestamp::now())?;
+    if firings.is_empty() {
+        println!("No rule would change anything.");
+    }
+    for firing in &firings {
+        println!("Would apply {}", describe(firing));
+    }
+    Ok(())
+}
+
+/// One line for a rule that fired, e.g.
+/// `rule "triage bugs" on 1a2b3c4d: priority medium -> high`.
+pub fn describe(firing: &Firing) -> String {
+    let changes: Vec<String> = firing
+        .changes
+        .iter()
+        .map(|change| format!("{} {} -> {}", change.field, change.old, change.new))
+        .collect();
+    format!(
+        "rule \"{}\" on {}: {}",
+        firing.rule,
+        short_id(&firing.task.to_string()),
+        changes.join(", ")
+    )
+}
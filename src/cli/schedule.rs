@@ -0,0 +1,117 @@
+//! Recurring task templates and the `tick` command that materializes them
+//! and runs the automation rules.
+
+use task_manager::dates::Timestamp;
+use task_manager::render::short_id;
+use task_manager::rules;
+use task_manager::schedule::{self, Template};
+use task_manager::{Error, Result};
+
+use super::{Args, Context};
+
+pub fn template(ctx: &mut Context, mut args: Args) -> Result<()> {
+    match args.positional().as_deref() {
+        Some("add") => {
+            let name = args.required("template name")?;
+            let cron = args.required("cron expression")?.parse()?;
+            let fields = args.rest()?;
+            if ctx.manager.templates().iter().any(|t| t.name == name) {
+                return Err(Error::Conflict(format!(
+                    "a template named \"{name}\" already exists"
+                )));
+            }
+            let template = Template::new(name, cron, fields, Timestamp::now(), &ctx.manager)?;
+            match template.cron.next_after(template.last_run) {
+                Some(next) => println!(
+                    "Added template \"{}\", next run {next}",
+                    template.name
+                ),
+                None => println!("Added template \"{}\"; it never runs", template.name),
+            }
+            ctx.manager.templates_mut().push(template);
+            ctx.modified();
+            Ok(())
+        }
+        Some("list") => {
+            args.finish()?;
+            if ctx.manager.templates().is_empty() {
+                println!("No templates.");
+            }
+            for template in ctx.manager.templates() {
+                let next = template
+                    .cron
+                    .next_after(template.last_run)
+                    .map(|t| t.to_string())
+                    .unwrap_or_else(|| "never".into());
+                let line = format!(
+                    "{}  [{}]  next {next}  {}",
+                    template.name,
+                    template.cron,
+                    template.fields.join(" ")
+                );
+                println!("{}", line.trim_end());
+            }
+            Ok(())
+        }
+        Some("remove") => {
+            let name = args.required("template name")?;
+            args.finish()?;
+            let templates = ctx.manager.templates_mut();
+            let index = templates
+                .iter()
+                .position(|t| t.name == name)
+                .ok_or_else(|| Error::NotFound(format!("template \"{name}\" not found")))?;
+            templates.remove(index);
+            println!("Removed template \"{name}\"");
+            ctx.modified();
+            Ok(())
+        }
+        Some(other) => Err(Error::Invalid(format!(
+            "unknown template command `{other}` (expected add, list or remove)"
+        ))),
+        None => Err(Error::Invalid(
+            "usage: template add <name> <cron> [field=value...] | template list | template remove <name>"
+                .into(),
+        )),
+    }
+}
+
+pub fn tick(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let dry_run = args.flag("--dry-run");
+    args.finish()?;
+    let mut manager = ctx.manager.clone();
+    let now = Timestamp::now();
+    let runs = schedule::tick(&mut manager, now)?;
+    for run in &runs {
+        let skipped = match run.skipped {
+            0 => String::new(),
+            n => format!(" ({n} earlier run(s) skipped)"),
+        };
+        if dry_run {
+            println!(
+                "Would create \"{}\" scheduled for {}{skipped}",
+                run.template, run.fired_at
+            );
+        } else {
+            println!(
+                "Created {} from \"{}\" (run at {}){skipped}",
+                short_id(&run.task.to_string()),
+                run.template,
+                run.fired_at
+            );
+        }
+    }
+    let firings = rules::run(&ctx.config.rules, &mut manager, ctx.manager.tasks(), now)?;
+    for firing in &firings {
+        let verb = if dry_run { "Would apply" } else { "Applied" };
+        println!("{verb} {}", super::rules::describe(firing));
+    }
+    if runs.is_empty() && firings.is_empty() {
+        println!("Nothing to do.");
+    }
+    if !dry_run {
+        ctx.manager = manager;
+        ctx.modified();
+    }
+    Ok(())
+}
@@ -0,0 +1,59 @@
+//! Other people's task lists from `[[sources]]`, read fresh each time and
+//! shown next to the user's own. They are never saved, and commands that
+//! change tasks only look in the user's own list, so nothing here can be
+//! modified.
+
+use std::fs;
+use std::process::Command;
+
+use task_manager::config::{Source, SourceLocation};
+use task_manager::{json, storage};
+use task_manager::{Error, Result, TasksManager};
+
+/// A source as last read.
+pub struct Mirror {
+    pub name: String,
+    pub manager: TasksManager,
+}
+
+/// Reads every configured source. One that cannot be read is left out with
+/// a warning; a teammate's file being away should not hide the user's own
+/// tasks.
+pub fn load(sources: &[Source]) -> Vec<Mirror> {
+    let mut mirrors = Vec::new();
+    for source in sources {
+        match read(&source.location) {
+            Ok(manager) => mirrors.push(Mirror {
+                name: source.name.clone(),
+                manager,
+            }),
+            Err(err) => eprintln!(
+                "warning: could not read source `{}` ({}): {err}",
+                source.name, source.location
+            ),
+        }
+    }
+    mirrors
+}
+
+fn read(location: &SourceLocation) -> Result<TasksManager> {
+    let text = match location {
+        SourceLocation::Path(path) => fs::read_to_string(path)?,
+        SourceLocation::Url(url) => {
+            let output = Command::new("curl")
+                .args(["--silent", "--show-error", "--fail", "--location"])
+                .args(["--max-time", "10"])
+                .arg(url)
+                .output()
+                .map_err(|err| Error::Invalid(format!("could not run curl: {err}")))?;
+            if !output.status.success() {
+                return Err(Error::Invalid(
+                    String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                ));
+            }
+            String::from_utf8(output.stdout)
+                .map_err(|_| Error::Invalid("the file is not UTF-8".into()))?
+        }
+    };
+    storage::decode(&json::parse(&text)?)
+}
@@ -0,0 +1,375 @@
+use task_manager::dates::{self, Timestamp};
+use task_manager::duration;
+use task_manager::json::Value;
+use task_manager::render::bar_cells;
+use task_manager::render::short_id;
+use task_manager::render::theme;
+use task_manager::stats::{self, Analytics, Snapshot};
+use task_manager::{Error, Result};
+
+use super::render;
+use super::{Args, Context};
+
+const BAR_WIDTH: usize = 40;
+
+/// Weeks of throughput `stats tags` and `stats projects` show.
+const DEFAULT_WEEKS: usize = 8;
+
+pub fn stats(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let now = Timestamp::now();
+    match args.positional().as_deref() {
+        None => {
+            args.finish()?;
+            let snapshot = Snapshot::capture(ctx.manager.tasks(), now);
+            if ctx.config.display.brief {
+                print_brief_summary(&snapshot);
+            } else {
+                print_summary(&snapshot);
+            }
+            Ok(())
+        }
+        Some("snapshot") => {
+            args.finish()?;
+            // Saving records the snapshot; see `Context::save_if_modified`.
+            ctx.modified();
+            println!("Recorded snapshot for {}", now.to_local_date());
+            Ok(())
+        }
+        Some("trend") => {
+            let days = days(&mut args, 90)?;
+            args.finish()?;
+            print_trend(ctx, now, days);
+            Ok(())
+        }
+        Some("heatmap") => {
+            let json = args.flag("--json");
+            let days = days(&mut args, 365)?;
+            args.finish()?;
+            let counts = stats::completions(ctx.manager.tasks(), now, days);
+            if json {
+                println!("{}", heatmap_json(&counts).to_pretty());
+            } else if ctx.config.display.brief {
+                print!("{}", heatmap_summary(&counts));
+            } else {
+                print!(
+                    "{}",
+                    heatmap(&counts, &ctx.config.glyphs.shades, render::color_enabled())
+                );
+            }
+            Ok(())
+        }
+        Some(kind @ ("tags" | "projects")) => {
+            let json = args.flag("--json");
+            let weeks = match args.value("--weeks")? {
+                Some(weeks) => weeks
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&w| w > 0)
+                    .ok_or_else(|| Error::Invalid(format!("invalid week count `{weeks}`")))?,
+                None => DEFAULT_WEEKS,
+            };
+            args.finish()?;
+            let groups = if kind == "tags" {
+                stats::by_tag(ctx.manager.tasks(), now, weeks)
+            } else {
+                stats::by_project(ctx.manager.tasks(), now, weeks)
+            };
+            if json {
+                let mut out = Value::object();
+                out.insert("weeks", weeks);
+                out.insert(
+                    kind,
+                    Value::Array(groups.iter().map(Analytics::to_json).collect()),
+                );
+                println!("{}", out.to_pretty());
+            } else if groups.is_empty() {
+                println!("No {kind} yet.");
+            } else if ctx.config.display.brief {
+                for group in &groups {
+                    println!("{}", analytics_sentence(group, kind));
+                }
+            } else {
+                print_analytics(&groups, kind, weeks);
+            }
+            Ok(())
+        }
+        Some(other) => Err(Error::Invalid(format!(
+            "unknown stats command `{other}` (expected snapshot, trend, heatmap, tags or projects)"
+        ))),
+    }
+}
+
+/// Reads `--days N`.
+fn days(args: &mut Args, default: i64) -> Result<i64> {
+    match args.value("--days")? {
+        Some(days) => days
+            .parse::<i64>()
+            .ok()
+            .filter(|&d| d > 0)
+            .ok_or_else(|| Error::Invalid(format!("invalid day count `{days}`"))),
+        None => Ok(default),
+    }
+}
+
+fn print_summary(snapshot: &Snapshot) {
+    println!("{:<10} {:>6} {:>6}", "Priority", "Open", "Done");
+    for index in (0..4).rev() {
+        println!(
+            "{:<10} {:>6} {:>6}",
+            stats::priority_label(index),
+            snapshot.open[index],
+            snapshot.done[index]
+        );
+    }
+    println!(
+        "{:<10} {:>6} {:>6}",
+        "total",
+        snapshot.open_total(),
+        snapshot.done_total()
+    );
+    if !snapshot.projects.is_empty() {
+        println!();
+        println!("{:<20} {:>6} {:>6}", "Project", "Open", "Done");
+        for (name, counts) in &snapshot.projects {
+            println!("{name:<20} {:>6} {:>6}", counts.open, counts.done);
+        }
+    }
+}
+
+/// [`print_summary`] in sentences, for `--brief`.
+fn print_brief_summary(snapshot: &Snapshot) {
+    let open: Vec<String> = (0..4)
+        .rev()
+        .map(|index| format!("{} {}", snapshot.open[index], stats::priority_label(index)))
+        .collect();
+    println!(
+        "{} open and {} done. Open by priority: {}.",
+        snapshot.open_total(),
+        snapshot.done_total(),
+        open.join(", ")
+    );
+    for (name, counts) in &snapshot.projects {
+        println!(
+            "Project {name}: {} open, {} done.",
+            counts.open, counts.done
+        );
+    }
+}
+
+/// Prints the open backlog per day (per week for long ranges) as bars.
+fn print_trend(ctx: &Context, now: Timestamp, days: i64) {
+    let trend = ctx.manager.history().trend(now, days);
+    let step = if days > 31 { 7 } else { 1 };
+    let max = trend
+        .iter()
+        .filter_map(|(_, s)| s.map(Snapshot::open_total))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    if ctx.config.display.brief {
+        let recorded: Vec<(Timestamp, usize)> = trend
+            .iter()
+            .filter_map(|&(day, s)| Some((day, s?.open_total())))
+            .collect();
+        match (recorded.first(), recorded.last()) {
+            (Some(&(first, open)), _) if recorded.len() == 1 => println!(
+                "Open tasks over the last {days} days: {open} on {}, the only day recorded.",
+                first.to_local_date()
+            ),
+            (Some(&(first, from)), Some(&(last, to))) => println!(
+                "Open tasks over the last {days} days: {from} on {}, {to} on {}.",
+                first.to_local_date(),
+                last.to_local_date()
+            ),
+            _ => println!("No snapshots in the last {days} days."),
+        }
+        return;
+    }
+    println!("Open tasks over the last {days} days");
+    // Sample backwards from today so the latest day is always shown.
+    for (i, (day, snapshot)) in trend.iter().enumerate() {
+        if !(trend.len() - 1 - i).is_multiple_of(step) {
+            continue;
+        }
+        match snapshot {
+            Some(s) => {
+                let open = s.open_total();
+                let bar = ctx
+                    .config
+                    .glyphs
+                    .bar
+                    .to_string()
+                    .repeat(bar_cells(open, max, BAR_WIDTH));
+                println!("{}  {open:>5}  {bar}", day.to_local_date());
+            }
+            None => println!("{}      -", day.to_local_date()),
+        }
+    }
+}
+
+/// The totals under the heatmap, which `--brief` prints alone.
+fn heatmap_summary(counts: &[(Timestamp, usize)]) -> String {
+    let total: usize = counts.iter().map(|&(_, n)| n).sum();
+    let mut out = format!(
+        "{total} task(s) completed in the last {} days",
+        counts.len()
+    );
+    if let Some(&(day, n)) = counts.iter().rev().max_by_key(|&&(_, n)| n) {
+        if n > 0 {
+            out.push_str(&format!("; busiest day {} ({n})", day.to_local_date()));
+        }
+    }
+    let streak = |days: &mut dyn Iterator<Item = &(Timestamp, usize)>| {
+        days.take_while(|&&(_, n)| n > 0).count()
+    };
+    let mut longest = 0;
+    let mut run = 0;
+    for &(_, n) in counts {
+        run = if n > 0 { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    out.push_str(&format!(
+        "\nCurrent streak {} day(s), longest {longest}\n",
+        streak(&mut counts.iter().rev())
+    ));
+    out
+}
+
+/// A week per column, its first day at the top, shaded by how many tasks were
+/// completed that day relative to the busiest day.
+fn heatmap(counts: &[(Timestamp, usize)], shades: &[char; 5], color: bool) -> String {
+    let Some(&(first, _)) = counts.first() else {
+        return String::new();
+    };
+    let max = counts.iter().map(|&(_, n)| n).max().unwrap_or(0).max(1);
+    let lead = first.weekday().days_into_week() as usize;
+    let weeks = (lead + counts.len()).div_ceil(7);
+    // Shade levels; `None` before the first day.
+    let mut rows = vec![vec![None; weeks]; 7];
+    let mut months = vec![' '; weeks + 3];
+    let mut last_month = None;
+    for (i, &(day, count)) in counts.iter().enumerate() {
+        let (week, weekday) = ((lead + i) / 7, (lead + i) % 7);
+        rows[weekday][week] = Some((count * 4).div_ceil(max));
+        // Label a column with the month its first day falls in, when the
+        // month changes and the label fits.
+        let month = day.to_local().month;
+        if last_month != Some(month) {
+            last_month = Some(month);
+            let fits = months[week..week + 3].iter().all(|&c| c == ' ')
+                && (week == 0 || months[week - 1] == ' ');
+            if fits {
+                let label: Vec<char> = day.format_local("%b").chars().collect();
+                months[week..week + 3].copy_from_slice(&label);
+            }
+        }
+    }
+    let mut out = format!("    {}\n", months.iter().collect::<String>().trim_end());
+    let locale = dates::display_locale();
+    let heat = theme::theme().heat;
+    for ((index, row), weekday) in rows.iter().enumerate().zip(dates::Weekday::week()) {
+        // Every other day is labelled, as there is no room for all.
+        let label: String = match index {
+            0 | 2 | 4 => locale.weekday(weekday).chars().take(3).collect(),
+            _ => String::new(),
+        };
+        let cells: String = row
+            .iter()
+            .map(|&level| match level {
+                None => " ".to_string(),
+                Some(level) if level > 0 && color => {
+                    theme::paint(&heat, &shades[level].to_string())
+                }
+                Some(level) => shades[level].to_string(),
+            })
+            .collect();
+        out.push_str(&format!("{label:<4}{}\n", cells.trim_end()));
+    }
+    out.push('\n');
+    out.push_str(&heatmap_summary(counts));
+    out
+}
+
+fn heatmap_json(counts: &[(Timestamp, usize)]) -> Value {
+    let days: Vec<Value> = counts
+        .iter()
+        .map(|&(day, count)| {
+            let mut entry = Value::object();
+            entry.insert("date", day.format_local("%Y-%m-%d"));
+            entry.insert("completed", count);
+            entry
+        })
+        .collect();
+    let mut out = Value::object();
+    out.insert("total", counts.iter().map(|&(_, n)| n).sum::<usize>());
+    out.insert("days", Value::Array(days));
+    out
+}
+
+/// Completion times, the oldest open task and completions per week for
+/// each tag or project, as a table.
+fn print_analytics(groups: &[Analytics], kind: &str, weeks: usize) {
+    let label = if kind == "tags" { "Tag" } else { "Project" };
+    let names: Vec<String> = groups.iter().map(group_name).collect();
+    let width = names
+        .iter()
+        .map(|n| n.chars().count())
+        .fold(label.len(), usize::max);
+    println!(
+        "{label:<width$}  {:>5} {:>5}  {:>8} {:>8}  {:<17}  {:>8}  Done per week, last {weeks}",
+        "Open", "Done", "Mean", "Median", "Oldest open", "Per week"
+    );
+    for (group, name) in groups.iter().zip(&names) {
+        let oldest = group
+            .oldest_open
+            .map(|(id, age)| format!("{:<8} {}", span(age), short_id(&id.to_string())))
+            .unwrap_or_default();
+        let weeks: Vec<String> = group.per_week.iter().map(usize::to_string).collect();
+        println!(
+            "{name:<width$}  {:>5} {:>5}  {:>8} {:>8}  {oldest:<17}  {:>8.1}  {}",
+            group.open,
+            group.done,
+            group.mean.map(span).unwrap_or_default(),
+            group.median.map(span).unwrap_or_default(),
+            group.throughput(),
+            weeks.join(" ")
+        );
+    }
+}
+
+/// [`print_analytics`] in a sentence, for `--brief`.
+fn analytics_sentence(group: &Analytics, kind: &str) -> String {
+    let what = if kind == "tags" { "Tag" } else { "Project" };
+    let mut out = match &group.name {
+        Some(name) => format!("{what} {name}: {} open, {} done", group.open, group.done),
+        None => format!("No project: {} open, {} done", group.open, group.done),
+    };
+    if let (Some(mean), Some(median)) = (group.mean, group.median) {
+        out.push_str(&format!(
+            ", taking {} on average and {} at the median",
+            duration::humanize(mean),
+            duration::humanize(median)
+        ));
+    }
+    if let Some((_, age)) = group.oldest_open {
+        out.push_str(&format!(
+            "; the oldest open task is {} old",
+            duration::humanize(age)
+        ));
+    }
+    format!(
+        "{out}; {:.1} done a week over the last {} weeks.",
+        group.throughput(),
+        group.per_week.len()
+    )
+}
+
+fn group_name(group: &Analytics) -> String {
+    group.name.clone().unwrap_or_else(|| "(no project)".into())
+}
+
+/// A duration to the hour, or the minute when shorter, e.g. `2d4h`.
+fn span(secs: i64) -> String {
+    let unit = if secs >= 3600 { 3600 } else { 60 };
+    duration::format(secs - secs % unit)
+}
@@ -0,0 +1,58 @@
+//! `store`: moving the task list between a single data file and a
+//! directory store split into several files (see [`task_manager::split`]).
+
+use std::path::PathBuf;
+
+use task_manager::config::Operation;
+use task_manager::split::{self, SplitBy};
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Error, Result};
+
+use super::{Args, Context};
+
+pub fn store(ctx: &mut Context, mut args: Args) -> Result<()> {
+    match args.positional().as_deref() {
+        Some("split") => {
+            let by: SplitBy = match args.value("--by")? {
+                Some(by) => by.parse()?,
+                None => SplitBy::default(),
+            };
+            let dir = PathBuf::from(args.required("directory")?);
+            args.finish()?;
+            split::create(&dir, &*ctx.sealed()?, by)?;
+            println!(
+                "Wrote {} tasks to {}, one file per {}; use it with `--file {}`",
+                ctx.manager.len(),
+                dir.display(),
+                by.as_str(),
+                dir.display()
+            );
+            Ok(())
+        }
+        Some("join") => {
+            let force = args.flag("--force");
+            let path = PathBuf::from(args.required("data file")?);
+            args.finish()?;
+            if path.is_dir() {
+                return Err(Error::Invalid(format!(
+                    "{} is a directory; give the data file to write",
+                    path.display()
+                )));
+            }
+            let question = format!("Overwrite {}?", path.display());
+            if path.exists() && !ctx.confirm(Operation::Overwrite, force, &question) {
+                println!("Cancelled.");
+                return Ok(());
+            }
+            JsonStore::new(&path).save(&*ctx.sealed()?)?;
+            println!("Wrote {} tasks to {}", ctx.manager.len(), path.display());
+            Ok(())
+        }
+        Some(other) => Err(Error::Invalid(format!(
+            "unknown store action `{other}` (expected split or join)"
+        ))),
+        None => Err(Error::Invalid(
+            "choose an action, e.g. `store split tasks/ --by project`".into(),
+        )),
+    }
+}
@@ -0,0 +1,95 @@
+//! `sync`: keeping the tasks and a Markdown checklist, a Notion database
+//! (see [`notion`]) or Google Tasks (see [`google`]) in step.
+
+use std::fs;
+
+use task_manager::markdown;
+use task_manager::render::short_id;
+use task_manager::{Error, Result};
+
+use super::{google, notion, triage};
+use super::{Args, Context};
+
+pub fn sync(ctx: &mut Context, mut args: Args) -> Result<()> {
+    match args.positional().as_deref() {
+        Some("markdown") => {
+            let dry_run = args.flag("--dry-run");
+            let triage = args.flag("--triage");
+            let path = args.required("Markdown file")?;
+            args.finish()?;
+            let input = fs::read_to_string(&path)?;
+            let mut manager = ctx.manager.clone();
+            let mut sync = markdown::sync(&input, &mut manager, &ctx.config.user_name());
+            if triage && !sync.created.is_empty() {
+                let created = sync
+                    .created
+                    .iter()
+                    .map(|&id| manager.get(id).expect("created task exists").clone())
+                    .collect();
+                let triage = triage::triage(created, &manager, &ctx.config)?;
+                triage.print_summary();
+                for task in triage.accepted {
+                    let id = task.id;
+                    *manager.get_mut(id).expect("created task exists") = task;
+                }
+                for task in &triage.discarded {
+                    sync.discard(task.id, &mut manager);
+                }
+            }
+            for &id in &sync.created {
+                let task = manager.get(id).expect("created task exists");
+                println!("+ {} ({})", task.name, short_id(&id.to_string()));
+            }
+            for &id in &sync.completed {
+                let task = manager.get(id).expect("completed task exists");
+                println!("done {} ({})", task.name, short_id(&id.to_string()));
+            }
+            for &id in &sync.submitted {
+                let task = manager.get(id).expect("submitted task exists");
+                println!("review {} ({})", task.name, short_id(&id.to_string()));
+            }
+            for &id in &sync.checked {
+                let task = manager.get(id).expect("checked task exists");
+                println!("[x] {} ({})", task.name, short_id(&id.to_string()));
+            }
+            for id in &sync.missing {
+                eprintln!("warning: {path} refers to task {id}, which no longer exists");
+            }
+            println!(
+                "{} created, {} completed, {} sent for review, {} ticked in {path}.",
+                sync.created.len(),
+                sync.completed.len(),
+                sync.submitted.len(),
+                sync.checked.len()
+            );
+            if dry_run {
+                return Ok(());
+            }
+            if sync.changed_file() {
+                fs::write(&path, &sync.text)?;
+            }
+            if !sync.created.is_empty() || !sync.completed.is_empty() || !sync.submitted.is_empty()
+            {
+                ctx.manager = manager;
+                ctx.modified();
+            }
+            Ok(())
+        }
+        Some("notion") => {
+            let dry_run = args.flag("--dry-run");
+            args.finish()?;
+            notion::sync(ctx, dry_run)
+        }
+        Some("google") => {
+            let dry_run = args.flag("--dry-run");
+            args.finish()?;
+            google::sync(ctx, dry_run)
+        }
+        Some(other) => Err(Error::Invalid(format!(
+            "unknown sync source `{other}` (expected markdown, notion or google)"
+        ))),
+        None => Err(Error::Invalid(
+            "choose what to sync with, e.g. `sync markdown notes.md`".into(),
+        )),
+    }
+}
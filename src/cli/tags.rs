@@ -0,0 +1,131 @@
+//! `tags`: listing tags and renaming, merging or deleting them across all
+//! tasks at once.
+
+use task_manager::manager::TagCount;
+use task_manager::suggest;
+use task_manager::{Error, Result};
+
+use super::{Args, Context};
+
+pub fn tags(ctx: &mut Context, mut args: Args) -> Result<()> {
+    match args.positional().as_deref() {
+        None | Some("list") => {
+            args.finish()?;
+            let counts = ctx.manager.tag_counts();
+            if counts.is_empty() {
+                println!("No tags.");
+                return Ok(());
+            }
+            println!("{:<20} {:>6} {:>6}", "Tag", "Tasks", "Open");
+            for count in counts {
+                println!("{:<20} {:>6} {:>6}", count.tag, count.tasks, count.open);
+            }
+            Ok(())
+        }
+        Some("rename") => {
+            let from = tag(&mut args, "tag to rename")?;
+            let to = tag(&mut args, "new tag name")?;
+            args.finish()?;
+            let existing = find(ctx, &from)?;
+            // Changing only the case is a rename, not a merge.
+            if !to.eq_ignore_ascii_case(&from) && lookup(ctx, &to).is_some() {
+                return Err(Error::Conflict(format!(
+                    "tag `{to}` already exists; use `tags merge {from} {to}` to combine them"
+                )));
+            }
+            let changed = ctx.manager.rename_tag(&existing.tag, &to);
+            println!("Renamed #{} to #{to} on {changed} task(s)", existing.tag);
+            ctx.modified();
+            Ok(())
+        }
+        Some("merge") => {
+            let from = tag(&mut args, "tag to merge")?;
+            let into = tag(&mut args, "tag to merge into")?;
+            args.finish()?;
+            let source = find(ctx, &from)?;
+            let target = find(ctx, &into)?;
+            if source.tag.eq_ignore_ascii_case(&target.tag) {
+                return Err(Error::Invalid("cannot merge a tag into itself".into()));
+            }
+            let changed = ctx.manager.rename_tag(&source.tag, &target.tag);
+            println!(
+                "Merged #{} into #{} on {changed} task(s)",
+                source.tag, target.tag
+            );
+            ctx.modified();
+            Ok(())
+        }
+        Some("delete") => {
+            let force = args.flag("--force");
+            let name = tag(&mut args, "tag to delete")?;
+            args.finish()?;
+            let existing = find(ctx, &name)?;
+            if existing.open > 0 && !force {
+                return Err(Error::Conflict(format!(
+                    "#{} is still on {} open task(s); pass --force to delete it anyway",
+                    existing.tag, existing.open
+                )));
+            }
+            let changed = ctx.manager.remove_tag(&existing.tag);
+            println!("Deleted #{} from {changed} task(s)", existing.tag);
+            ctx.modified();
+            Ok(())
+        }
+        Some("prune") => {
+            args.finish()?;
+            let unused: Vec<TagCount> = ctx
+                .manager
+                .tag_counts()
+                .into_iter()
+                .filter(|c| c.open == 0)
+                .collect();
+            if unused.is_empty() {
+                println!("Every tag is on an open task.");
+                return Ok(());
+            }
+            for count in &unused {
+                ctx.manager.remove_tag(&count.tag);
+                println!(
+                    "Deleted #{} from {} finished task(s)",
+                    count.tag, count.tasks
+                );
+            }
+            ctx.modified();
+            Ok(())
+        }
+        Some(other) => Err(Error::Invalid(format!(
+            "unknown tags command `{other}` (expected list, rename, merge, delete or prune)"
+        ))),
+    }
+}
+
+/// The next argument as a tag name, without a leading `#`.
+fn tag(args: &mut Args, what: &str) -> Result<String> {
+    let tag = args.required(what)?;
+    let tag = tag.trim().trim_start_matches('#');
+    if tag.is_empty() {
+        return Err(Error::Invalid("tag name cannot be empty".into()));
+    }
+    Ok(tag.to_string())
+}
+
+fn lookup(ctx: &Context, tag: &str) -> Option<TagCount> {
+    ctx.manager
+        .tag_counts()
+        .into_iter()
+        .find(|c| c.tag.eq_ignore_ascii_case(tag))
+}
+
+fn find(ctx: &Context, tag: &str) -> Result<TagCount> {
+    lookup(ctx, tag).ok_or_else(|| {
+        let counts = ctx.manager.tag_counts();
+        let names: Vec<String> = suggest::closest(tag, counts.iter().map(|c| c.tag.as_str()))
+            .iter()
+            .map(|name| format!("#{name}"))
+            .collect();
+        Error::NotFound(format!(
+            "tag `{tag}` not found{}",
+            suggest::did_you_mean(&names)
+        ))
+    })
+}
@@ -0,0 +1,1098 @@
+//! Everyday task commands: add, list, show, done, remove.
+
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use task_manager::config::{Operation, PriorityInheritance};
+use task_manager::dates::{self, DateRange, Timestamp};
+use task_manager::filter::{self, Comparison, DateField, Filter};
+use task_manager::index::{self, SearchIndex};
+use task_manager::render::highlight::Search;
+use task_manager::render::links;
+use task_manager::render::{set_id_width, short_id};
+use task_manager::stats::Today;
+use task_manager::task::{parse_contact, parse_description, parse_tags, random_u64};
+use task_manager::update::{self, Update};
+use task_manager::workflow;
+use task_manager::{Error, Priority, Result, Status, Task, TaskId, TasksManager};
+
+use super::prompt::{self, ask, ask_default};
+use super::render;
+use super::sources;
+use super::{Args, Context};
+
+/// What to do with a new task whose name is close to an open one.
+enum Duplicate {
+    Create,
+    Merge(TaskId),
+    Cancel,
+}
+
+pub fn add(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let ignore_limits = args.flag("--ignore-limits");
+    let no_prompt = args.flag("--no-prompt");
+    let name = args.value("--name")?;
+    let description = match args.value("--desc")? {
+        Some(description) => Some(description),
+        None => args.value("--description")?,
+    };
+    let priority = args.value("--priority")?;
+    let project = args.value("--project")?;
+    let tags = args.value("--tags")?;
+    let due = args.value("--due")?;
+    let contact = args.value("--contact")?;
+    args.finish()?;
+    // Check what was given before asking for the rest, so a launcher gets
+    // its error straight away.
+    let now = Timestamp::now();
+    let priority = priority.map(|p| p.parse::<Priority>()).transpose()?;
+    let contact = contact.as_deref().map(parse_contact).transpose()?;
+    let description = description.as_deref().map(parse_description).transpose()?;
+    // `--due ""` means no due date rather than "ask".
+    let due = due
+        .map(|d| match d.trim() {
+            "" => Ok(None),
+            d => dates::parse_when(d, now).map(Some),
+        })
+        .transpose()?;
+    // A flag's value, or the answer to `question` unless prompts are off.
+    let field = |given: Option<String>, question: &str| match given {
+        Some(value) => value,
+        None if no_prompt => String::new(),
+        None => ask(question).unwrap_or_default(),
+    };
+
+    let name = match name.map(|n| n.trim().to_string()) {
+        Some(name) if !name.is_empty() => name,
+        Some(_) => return Err(Error::Invalid("the name cannot be empty".into())),
+        None if no_prompt => return Err(Error::Invalid("missing --name".into())),
+        None => loop {
+            match ask("Name: ") {
+                Some(name) if !name.is_empty() => break name,
+                Some(_) => println!("The name cannot be empty."),
+                None => return Ok(()),
+            }
+        },
+    };
+    let merge_into = match check_duplicates(ctx, &name, no_prompt) {
+        Duplicate::Create => None,
+        Duplicate::Merge(id) => Some(id),
+        Duplicate::Cancel => {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    };
+    let mut task = Task::new(name);
+    task.contact = contact;
+    task.description = match description {
+        Some(description) => description,
+        None => parse_description(&field(None, "Description: "))?,
+    };
+    task.priority = match priority {
+        Some(priority) => priority,
+        None if no_prompt => Priority::default(),
+        None => ask_default("Priority (low/medium/high/critical)", "medium").parse()?,
+    };
+    task.project = Some(field(project, "Project: ")).filter(|p| !p.is_empty());
+    task.tags = parse_tags(&field(tags, "Tags (comma separated): "));
+    task.due = match due {
+        Some(due) => due,
+        None if no_prompt => None,
+        None => {
+            let due = ask("Due (e.g. 2024-06-12 14:00, friday, tomorrow): ").unwrap_or_default();
+            if due.is_empty() {
+                None
+            } else {
+                Some(dates::parse_when(&due, Timestamp::now())?)
+            }
+        }
+    };
+    if let Some(id) = merge_into {
+        return merge(ctx, id, task, ignore_limits);
+    }
+    let id = ctx.manager.add(task);
+    if !ignore_limits {
+        if let Err(err) = check_limits(ctx, None, id) {
+            ctx.manager.remove(id)?;
+            return Err(err);
+        }
+    }
+    ctx.modified();
+    // The new id may start like an existing one for longer than ids are
+    // shown.
+    set_id_width(ctx.manager.id_width());
+    println!("Added task {}", short_id(&id.to_string()));
+    Ok(())
+}
+
+/// Lists open tasks with a name close to `name` and asks what to do. Without
+/// prompts the task is created and the near-duplicates are only reported.
+fn check_duplicates(ctx: &Context, name: &str, no_prompt: bool) -> Duplicate {
+    let similar: Vec<&Task> = ctx.manager.similar_open(name);
+    let Some(closest) = similar.first() else {
+        return Duplicate::Create;
+    };
+    if no_prompt {
+        for task in similar.iter().take(3) {
+            eprintln!(
+                "warning: similar to open task {} \"{}\"",
+                short_id(&task.id.to_string()),
+                task.name
+            );
+        }
+        return Duplicate::Create;
+    }
+    println!("Similar open tasks:");
+    for task in similar.iter().take(3) {
+        println!("  {}  {}", short_id(&task.id.to_string()), task.name);
+    }
+    loop {
+        let answer = ask("Create anyway, view the closest, merge into it or cancel? [c/v/m/N] ")
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match answer.as_str() {
+            "c" | "create" => return Duplicate::Create,
+            "v" | "view" => {
+                let times = render::Times {
+                    now: Timestamp::now(),
+                    relative: ctx.config.display.relative_times,
+                };
+                print!(
+                    "{}",
+                    render::task_detail(
+                        closest,
+                        effective_priority(ctx, closest),
+                        &ctx.manager,
+                        times,
+                        &ctx.config.glyphs,
+                        render::terminal_width()
+                    )
+                );
+            }
+            "m" | "merge" => return Duplicate::Merge(closest.id),
+            _ => return Duplicate::Cancel,
+        }
+    }
+}
+
+/// Folds the fields of a task being added into the existing task `id`:
+/// the description is appended, tags are combined, the higher priority wins
+/// and the project and due date fill in only what was missing.
+fn merge(ctx: &mut Context, id: TaskId, new: Task, ignore_limits: bool) -> Result<()> {
+    let before = ctx.manager.get(id).expect("merge target exists").clone();
+    let task = ctx.manager.get_mut(id).expect("merge target exists");
+    let description = new.description.trim();
+    if !description.is_empty() && !task.description.contains(description) {
+        if !task.description.is_empty() {
+            task.description.push_str("\n\n");
+        }
+        task.description.push_str(description);
+    }
+    task.priority = task.priority.max(new.priority);
+    if task.project.is_none() {
+        task.project = new.project;
+    }
+    for tag in new.tags {
+        if !task.has_tag(&tag) {
+            task.tags.push(tag);
+        }
+    }
+    if task.due.is_none() {
+        task.due = new.due;
+    }
+    if !ignore_limits {
+        if let Err(err) = check_limits(ctx, Some(&before), id) {
+            *ctx.manager.get_mut(id).expect("merge target exists") = before;
+            return Err(err);
+        }
+    }
+    ctx.modified();
+    println!(
+        "Merged into {} \"{}\"",
+        short_id(&id.to_string()),
+        before.name
+    );
+    Ok(())
+}
+
+pub fn list(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let all = args.flag("--all");
+    let mine = args.flag("--mine");
+    let show_private = args.flag("--private");
+    let hide_private =
+        args.flag("--no-private") || (ctx.config.display.hide_private && !show_private);
+    let times = times(ctx, &mut args);
+    let now = times.now;
+    let mut ranges = Filter::Any;
+    for (flag, field) in [
+        ("--due", DateField::Due),
+        ("--created", DateField::Created),
+        ("--completed", DateField::Completed),
+    ] {
+        if let Some(period) = args.value(flag)? {
+            let range = DateRange::parse(&period, now)?;
+            ranges = ranges.and(Filter::date_range(field, Comparison::Eq, range));
+        }
+    }
+    let filter = filter::parse(&args.rest()?.join(" "), now)?.and(ranges);
+    let open_only = !all && !filter.mentions_status();
+    let hide_archived = !all && !filter.mentions_project();
+    let color = render::color_enabled();
+    let search = Search::new(&filter.search_terms(), color);
+    let tags = render::Tags {
+        styles: &ctx.config.tags,
+        color,
+    };
+    // Filter and sort on the priorities shown, which may be inherited.
+    let view: Vec<Task> = ctx
+        .manager
+        .tasks()
+        .iter()
+        .map(|t| Task {
+            priority: effective_priority(ctx, t),
+            ..t.clone()
+        })
+        .collect();
+    let shown = |t: &Task, manager: &TasksManager| {
+        (!open_only || t.status.is_open())
+            && filter.matches(t, now)
+            && (all || !t.archived)
+            && !(hide_private && t.private)
+            && (!hide_archived || !t.project.as_deref().is_some_and(|p| manager.is_archived(p)))
+    };
+    let brief = ctx.config.display.brief;
+    let table = |tasks: &[&Task]| {
+        if brief {
+            return render::brief(tasks, now);
+        }
+        render::task_table(
+            tasks,
+            times,
+            tags,
+            &ctx.config.glyphs,
+            &search,
+            render::terminal_width(),
+        )
+    };
+    let tasks = list_order(view.iter().filter(|t| shown(t, &ctx.manager)).collect());
+    if tasks.is_empty() {
+        println!("No tasks.");
+    } else {
+        print!("{}", table(&tasks));
+    }
+    // Other people's lists, each under its name, unless only one's own
+    // tasks were asked for.
+    let mirrors = if mine {
+        Vec::new()
+    } else {
+        sources::load(&ctx.config.sources)
+    };
+    for mirror in &mirrors {
+        // Their private tasks are theirs, as in an export, unless asked for.
+        let tasks = list_order(
+            mirror
+                .manager
+                .tasks()
+                .iter()
+                .filter(|t| shown(t, &mirror.manager) && (show_private || !t.private))
+                .collect(),
+        );
+        if !tasks.is_empty() {
+            println!("\n{} (read-only)", mirror.name);
+            print!("{}", table(&tasks));
+        }
+    }
+    Ok(())
+}
+
+/// Pinned first, then open before finished, then by priority, due date
+/// (undated last) and age.
+fn list_order(mut tasks: Vec<&Task>) -> Vec<&Task> {
+    tasks.sort_by(|a, b| {
+        (
+            !a.pinned,
+            !a.status.is_open(),
+            b.priority,
+            a.due.is_none(),
+            a.due,
+            a.created_at,
+        )
+            .cmp(&(
+                !b.pinned,
+                !b.status.is_open(),
+                a.priority,
+                b.due.is_none(),
+                b.due,
+                b.created_at,
+            ))
+    });
+    tasks
+}
+
+/// Reads `--relative` / `--absolute`, which override the configured style.
+fn times(ctx: &Context, args: &mut Args) -> render::Times {
+    let relative = args.flag("--relative");
+    let absolute = args.flag("--absolute");
+    render::Times {
+        now: Timestamp::now(),
+        relative: relative || (ctx.config.display.relative_times && !absolute),
+    }
+}
+
+pub fn show(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let times = times(ctx, &mut args);
+    let reference = args.required("task id")?;
+    args.finish()?;
+    let task = match ctx.manager.find(&reference) {
+        Err(Error::NotFound(message)) => return show_mirrored(ctx, &reference, times, message),
+        found => found?,
+    };
+    let effective = effective_priority(ctx, task);
+    print!(
+        "{}",
+        render::task_detail(
+            task,
+            effective,
+            &ctx.manager,
+            times,
+            &ctx.config.glyphs,
+            render::terminal_width()
+        )
+    );
+    Ok(())
+}
+
+/// Shows a task from one of the read-only sources, failing with `message`
+/// when none has it either.
+fn show_mirrored(
+    ctx: &Context,
+    reference: &str,
+    times: render::Times,
+    message: String,
+) -> Result<()> {
+    for mirror in sources::load(&ctx.config.sources) {
+        let Ok(task) = mirror.manager.find(reference) else {
+            continue;
+        };
+        println!("From {} (read-only)", mirror.name);
+        print!(
+            "{}",
+            render::task_detail(
+                task,
+                task.priority,
+                &mirror.manager,
+                times,
+                &ctx.config.glyphs,
+                render::terminal_width()
+            )
+        );
+        return Ok(());
+    }
+    Err(Error::NotFound(message))
+}
+
+pub fn next(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let times = times(ctx, &mut args);
+    args.finish()?;
+    let Some(task) = ctx.manager.peek_highest_priority() else {
+        println!("No open tasks.");
+        return Ok(());
+    };
+    let effective = effective_priority(ctx, task);
+    print!(
+        "{}",
+        render::task_detail(
+            task,
+            effective,
+            &ctx.manager,
+            times,
+            &ctx.config.glyphs,
+            render::terminal_width()
+        )
+    );
+    Ok(())
+}
+
+/// A task picked at random for when nothing stands out, weighted towards
+/// high priorities and overdue tasks.
+pub fn roulette(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let times = times(ctx, &mut args);
+    args.finish()?;
+    let Some(task) = ctx.manager.roulette(times.now, random_u64()) else {
+        println!("No open tasks to pick from.");
+        return Ok(());
+    };
+    let effective = effective_priority(ctx, task);
+    print!(
+        "{}",
+        render::task_detail(
+            task,
+            effective,
+            &ctx.manager,
+            times,
+            &ctx.config.glyphs,
+            render::terminal_width()
+        )
+    );
+    Ok(())
+}
+
+/// The daily entry point: overdue tasks, then the ones due today, then
+/// urgent ones without a due date, each under its own heading. Private
+/// tasks and archived projects are left out as in `list`.
+pub fn today(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let times = times(ctx, &mut args);
+    args.finish()?;
+    let view = today_view(ctx);
+    let today = Today::of(&view, times.now);
+    if today.is_empty() {
+        println!("Nothing overdue or due today, and no urgent tasks.");
+        return Ok(());
+    }
+    let tags = render::Tags {
+        styles: &ctx.config.tags,
+        color: render::color_enabled(),
+    };
+    let sections = [
+        ("Overdue", &today.overdue),
+        ("Due today", &today.due_today),
+        ("High priority, no due date", &today.high_priority),
+    ];
+    let mut first = true;
+    for (heading, tasks) in sections {
+        if tasks.is_empty() {
+            continue;
+        }
+        if !first {
+            println!();
+        }
+        first = false;
+        println!("{heading} ({})", tasks.len());
+        if ctx.config.display.brief {
+            print!("{}", render::brief(tasks, times.now));
+            continue;
+        }
+        print!(
+            "{}",
+            render::task_table(
+                tasks,
+                times,
+                tags,
+                &ctx.config.glyphs,
+                &Search::new(&[], false),
+                render::terminal_width()
+            )
+        );
+    }
+    Ok(())
+}
+
+pub fn done(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let note = args.value("--note")?;
+    let follow_up = args.flag("--follow-up");
+    let no_follow_up = args.flag("--no-follow-up");
+    let reference = args.required("task id")?;
+    args.finish()?;
+    if follow_up && no_follow_up {
+        return Err(Error::Invalid(
+            "give either `--follow-up` or `--no-follow-up`, not both".into(),
+        ));
+    }
+    let user = ctx.config.user_name();
+    let task = ctx.manager.find_mut(&reference)?;
+    let mut finished = task.clone();
+    finished.finish(&user);
+    ctx.config
+        .workflow
+        .check(task, &finished, note.as_deref())?;
+    if let Some(note) = note.filter(|_| finished.status != task.status) {
+        workflow::record_note(&mut finished, task.status, &note, Timestamp::now());
+    }
+    *task = finished;
+    match task.status {
+        Status::ReviewPending => println!(
+            "Sent \"{}\" for review; someone else approves it with `approve`",
+            task.name
+        ),
+        _ => println!("Completed \"{}\"", task.name),
+    }
+    let mut next = task.follow_up();
+    ctx.modified();
+    // `--follow-up` adds it as suggested; the config asks first.
+    if !follow_up {
+        if no_follow_up || !ctx.config.done.follow_up || !prompt::confirm("Add a follow-up?") {
+            return Ok(());
+        }
+        next.name = ask_default("Name", &next.name);
+        next.description = ask_default("Description", &next.description);
+    }
+    let id = ctx.manager.add(next);
+    println!(
+        "Added follow-up {} \"{}\"",
+        short_id(&id.to_string()),
+        ctx.manager.get(id).expect("just added").name
+    );
+    Ok(())
+}
+
+pub fn approve(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let reference = args.required("task id")?;
+    args.finish()?;
+    let user = ctx.config.user_name();
+    let task = ctx.manager.find_mut(&reference)?;
+    let mut approved = task.clone();
+    approved.approve(&user)?;
+    ctx.config.workflow.check(task, &approved, None)?;
+    *task = approved;
+    println!("Approved \"{}\" as {user}", task.name);
+    ctx.modified();
+    Ok(())
+}
+
+pub fn pin(ctx: &mut Context, args: Args) -> Result<()> {
+    set_pinned(ctx, args, true)
+}
+
+pub fn unpin(ctx: &mut Context, args: Args) -> Result<()> {
+    set_pinned(ctx, args, false)
+}
+
+fn set_pinned(ctx: &mut Context, mut args: Args, pinned: bool) -> Result<()> {
+    let reference = args.required("task id")?;
+    args.finish()?;
+    let task = ctx.manager.find_mut(&reference)?;
+    if task.pinned == pinned {
+        let state = if pinned { "already" } else { "not" };
+        println!("\"{}\" is {state} pinned.", task.name);
+        return Ok(());
+    }
+    task.pinned = pinned;
+    let verb = if pinned { "Pinned" } else { "Unpinned" };
+    println!("{verb} \"{}\"", task.name);
+    ctx.modified();
+    Ok(())
+}
+
+pub fn star(ctx: &mut Context, args: Args) -> Result<()> {
+    set_starred(ctx, args, true)
+}
+
+pub fn unstar(ctx: &mut Context, args: Args) -> Result<()> {
+    set_starred(ctx, args, false)
+}
+
+/// Stars are per person: starring adds the current user to the task's
+/// `starred_by` and leaves everyone else's stars alone.
+fn set_starred(ctx: &mut Context, mut args: Args, starred: bool) -> Result<()> {
+    let reference = args.required("task id")?;
+    args.finish()?;
+    let user = ctx.config.user_name();
+    let task = ctx.manager.find_mut(&reference)?;
+    if task.is_starred_by(&user) == starred {
+        let state = if starred { "already" } else { "not" };
+        println!("\"{}\" is {state} starred.", task.name);
+        return Ok(());
+    }
+    if starred {
+        task.starred_by.push(user);
+    } else {
+        task.starred_by.retain(|name| *name != user);
+    }
+    let verb = if starred { "Starred" } else { "Unstarred" };
+    println!("{verb} \"{}\"", task.name);
+    ctx.modified();
+    Ok(())
+}
+
+/// The current user's starred open tasks, in the usual list order.
+pub fn starred(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let times = times(ctx, &mut args);
+    let now = times.now;
+    let filter = filter::parse(&args.rest()?.join(" "), now)?;
+    let user = ctx.config.user_name();
+    let view: Vec<Task> = ctx
+        .manager
+        .tasks()
+        .iter()
+        .filter(|t| t.is_starred_by(&user) && t.status.is_open() && !t.archived)
+        .map(|t| Task {
+            priority: effective_priority(ctx, t),
+            ..t.clone()
+        })
+        .filter(|t| filter.matches(t, now))
+        .collect();
+    if view.is_empty() {
+        println!("No starred tasks; star one with `star <id>`.");
+        return Ok(());
+    }
+    let color = render::color_enabled();
+    print!(
+        "{}",
+        render::task_table(
+            &list_order(view.iter().collect()),
+            times,
+            render::Tags {
+                styles: &ctx.config.tags,
+                color,
+            },
+            &ctx.config.glyphs,
+            &Search::new(&filter.search_terms(), color),
+            render::terminal_width()
+        )
+    );
+    Ok(())
+}
+
+/// Full-text search over every task, finished and archived ones included,
+/// through the word index kept beside the data file. With
+/// `encryption.fields` the index is only built in memory, since it would
+/// keep the encrypted words in plain text.
+pub fn search(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let times = times(ctx, &mut args);
+    let words = args.rest()?;
+    if words.is_empty() {
+        return Err(Error::Invalid("search needs at least one word".into()));
+    }
+    let query = words.join(" ");
+    let index = if ctx.config.encryption.fields {
+        // One written before the fields were encrypted goes too.
+        let _ = fs::remove_file(index::path_for(ctx.data_path()));
+        SearchIndex::build(ctx.manager.tasks())
+    } else {
+        index::load(ctx.data_path(), ctx.manager.tasks())
+    };
+    let found = index.search(&query);
+    let hide_private = ctx.config.display.hide_private;
+    let view: Vec<Task> = ctx
+        .manager
+        .tasks()
+        .iter()
+        .filter(|t| found.contains(&t.id) && !(hide_private && t.private))
+        .map(|t| Task {
+            priority: effective_priority(ctx, t),
+            ..t.clone()
+        })
+        .collect();
+    if view.is_empty() {
+        println!("No tasks match \"{query}\".");
+        return Ok(());
+    }
+    let color = render::color_enabled();
+    let terms: Vec<&str> = words.iter().map(String::as_str).collect();
+    print!(
+        "{}",
+        render::task_table(
+            &list_order(view.iter().collect()),
+            times,
+            render::Tags {
+                styles: &ctx.config.tags,
+                color,
+            },
+            &ctx.config.glyphs,
+            &Search::new(&terms, color),
+            render::terminal_width()
+        )
+    );
+    Ok(())
+}
+
+/// `index rebuild`: writes the search index from scratch.
+pub fn index(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let action = args.required("action (rebuild)")?;
+    args.finish()?;
+    if action != "rebuild" {
+        return Err(Error::Invalid(format!(
+            "unknown index action `{action}` (expected rebuild)"
+        )));
+    }
+    if ctx.config.encryption.fields {
+        return Err(Error::Invalid(
+            "no index is kept with `encryption.fields`, as it would hold the encrypted words in plain text".into(),
+        ));
+    }
+    let index = SearchIndex::build(ctx.manager.tasks());
+    let path = index::path_for(ctx.data_path());
+    index.write(&path)?;
+    println!(
+        "Indexed {} words from {} tasks in {}.",
+        index.len(),
+        ctx.manager.tasks().len(),
+        path.display()
+    );
+    Ok(())
+}
+
+pub fn remove(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let force = args.flag("--force");
+    let reference = args.required("task id")?;
+    args.finish()?;
+    let id = ctx.manager.resolve(&reference)?;
+    let name = &ctx.manager.get(id).expect("resolved task exists").name;
+    if !ctx.confirm(Operation::Remove, force, &format!("Remove \"{name}\"?")) {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    let task = ctx.manager.remove(id)?;
+    println!("Removed \"{}\"", task.name);
+    ctx.modified();
+    Ok(())
+}
+
+pub fn set(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let reference = args.required("task id")?;
+    let ignore_limits = args.flag("--ignore-limits");
+    let note = args.value("--note")?;
+    let now = Timestamp::now();
+    let updates = args
+        .rest()?
+        .iter()
+        .map(|arg| Update::parse(arg, now, &ctx.manager))
+        .collect::<Result<Vec<_>>>()?;
+    if updates.is_empty() {
+        return Err(Error::Invalid(
+            "nothing to set; pass `field=value`, `+tag` or `-tag`".into(),
+        ));
+    }
+    let task = ctx.manager.find_mut(&reference)?;
+    let (id, original) = (task.id, task.clone());
+    let mut updated = task.clone();
+    let changes = update::apply(&mut updated, &updates);
+    ctx.config
+        .workflow
+        .check(&original, &updated, note.as_deref())?;
+    if let Some(note) = &note {
+        if updated.status == original.status {
+            return Err(Error::Invalid(
+                "--note says why the status changes; set `status=` too".into(),
+            ));
+        }
+        workflow::record_note(&mut updated, original.status, note, now);
+    }
+    *task = updated;
+    let checked = ctx.manager.check_links(id).and_then(|()| {
+        if ignore_limits {
+            Ok(())
+        } else {
+            check_limits(ctx, Some(&original), id)
+        }
+    });
+    if let Err(err) = checked {
+        *ctx.manager.get_mut(id).expect("task exists") = original;
+        return Err(err);
+    }
+    // A related link may be stored on the other task; drop it there too.
+    let mut unlinked = Vec::new();
+    for update in &updates {
+        if let Update::RemoveRelated(other) = *update {
+            if ctx.manager.unrelate(id, other) && !changes.iter().any(|c| c.field == "related") {
+                unlinked.push(other);
+            }
+        }
+    }
+    if changes.is_empty() && unlinked.is_empty() {
+        println!("Nothing changed.");
+        return Ok(());
+    }
+    for change in &changes {
+        println!("{}: {} -> {}", change.field, change.old, change.new);
+    }
+    for other in unlinked {
+        println!("related: no longer related to {other}");
+    }
+    if let Some(note) = &note {
+        println!("note: {}", note.trim());
+    }
+    let project = ctx.manager.get(id).and_then(|t| t.project.clone());
+    if changes.iter().any(|c| c.field == "priority")
+        && ctx.config.priority_inheritance(project.as_deref()) == PriorityInheritance::Propagate
+    {
+        let count = ctx.manager.propagate_priority(id);
+        if count > 0 {
+            println!("priority also set on {count} subtask(s)");
+        }
+    }
+    ctx.modified();
+    Ok(())
+}
+
+/// `reschedule <id> <when>` or `reschedule --overdue <when>`, where `when`
+/// is a shift such as `+2d` or a date.
+pub fn reschedule(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let overdue = args.flag("--overdue");
+    let reference = if overdue {
+        None
+    } else {
+        Some(args.required("task id")?)
+    };
+    let when = args.required("new due date or shift, e.g. +2d")?;
+    args.finish()?;
+    let now = Timestamp::now();
+    // Checked once up front so a typo fails before any task changes.
+    dates::reschedule(&when, None, now)?;
+    let ids: Vec<TaskId> = match reference {
+        Some(reference) => vec![ctx.manager.resolve(&reference)?],
+        None => ctx
+            .manager
+            .tasks()
+            .iter()
+            .filter(|t| t.is_overdue(now))
+            .map(|t| t.id)
+            .collect(),
+    };
+    if ids.is_empty() {
+        println!("No overdue tasks.");
+        return Ok(());
+    }
+    for id in ids {
+        let task = ctx.manager.get_mut(id).expect("task exists");
+        let due = dates::reschedule(&when, task.due, now)?;
+        match task.due.replace(due) {
+            Some(old) => println!(
+                "{} {}: {old} -> {due}",
+                short_id(&id.to_string()),
+                task.name
+            ),
+            None => println!("{} {}: due {due}", short_id(&id.to_string()), task.name),
+        }
+    }
+    ctx.modified();
+    Ok(())
+}
+
+pub fn edit(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let raw = args.flag("--raw");
+    let body = args.flag("--body");
+    let ignore_limits = args.flag("--ignore-limits");
+    let reference = args.required("task id")?;
+    args.finish()?;
+    let id = ctx.manager.resolve(&reference)?;
+    if body {
+        return edit_body(ctx, id);
+    }
+    if !raw {
+        return Err(Error::Invalid(
+            "`edit` needs --raw for the task's JSON or --body for its notes; use `set` to change single fields"
+                .into(),
+        ));
+    }
+    let original = ctx.manager.get(id).expect("resolved task exists").clone();
+    let file = EditFile::create(&format!("task-{}.json", short_id(&id.to_string())))?;
+    let path = file.path();
+    let mut text = format!("{}\n", original.to_json().to_pretty());
+    loop {
+        fs::write(path, &text)?;
+        run_editor(path)?;
+        text = fs::read_to_string(path)?;
+        match replace_task(ctx, &original, &text, ignore_limits) {
+            Err(err) if io::stdin().is_terminal() => {
+                eprintln!("error: {err}");
+                if !prompt::confirm("Edit again?") {
+                    println!("Discarded the edit.");
+                    break Ok(());
+                }
+            }
+            result => break result,
+        }
+    }
+}
+
+/// Opens the task's body in the editor as plain text.
+fn edit_body(ctx: &mut Context, id: TaskId) -> Result<()> {
+    let file = EditFile::create(&format!("task-{}.txt", short_id(&id.to_string())))?;
+    let task = ctx.manager.get(id).expect("resolved task exists");
+    fs::write(file.path(), &task.body)?;
+    run_editor(file.path())?;
+    let body = fs::read_to_string(file.path())?.trim_end().to_string();
+    drop(file);
+    let task = ctx.manager.get_mut(id).expect("resolved task exists");
+    if body == task.body {
+        println!("Nothing changed.");
+        return Ok(());
+    }
+    task.body = body;
+    println!("Updated the notes of \"{}\"", task.name);
+    ctx.modified();
+    Ok(())
+}
+
+/// Puts the task read from `text` in place of `original`, unless it breaks
+/// a link or a limit.
+fn replace_task(ctx: &mut Context, original: &Task, text: &str, ignore_limits: bool) -> Result<()> {
+    let task = Task::from_edited_json(text, original)?;
+    if task == *original {
+        println!("Nothing changed.");
+        return Ok(());
+    }
+    ctx.config.workflow.check(original, &task, None)?;
+    let id = original.id;
+    *ctx.manager.get_mut(id).expect("task exists") = task;
+    let checked = ctx.manager.check_links(id).and_then(|()| {
+        if ignore_limits {
+            Ok(())
+        } else {
+            check_limits(ctx, Some(original), id)
+        }
+    });
+    if let Err(err) = checked {
+        *ctx.manager.get_mut(id).expect("task exists") = original.clone();
+        return Err(err);
+    }
+    println!(
+        "Updated \"{}\"",
+        ctx.manager.get(id).expect("task exists").name
+    );
+    ctx.modified();
+    Ok(())
+}
+
+/// `email <id>`: starts a message to the task's contact in the mail client,
+/// with the name as the subject and the description as the body.
+pub fn email(ctx: &mut Context, mut args: Args) -> Result<()> {
+    let print = args.flag("--print");
+    let reference = args.required("task id")?;
+    args.finish()?;
+    let task = ctx.manager.find(&reference)?;
+    let contact = task.contact.as_deref().ok_or_else(|| {
+        Error::Invalid(format!(
+            "\"{}\" has no contact; set one with `set {} contact=<address>`",
+            task.name,
+            short_id(&task.id.to_string())
+        ))
+    })?;
+    let url = links::mailto(contact, &task.name, &task.description);
+    if print {
+        println!("{url}");
+        return Ok(());
+    }
+    open_url(&url)?;
+    println!("Writing to {contact} about \"{}\"", task.name);
+    Ok(())
+}
+
+/// Hands `url` to the desktop's default handler for it.
+fn open_url(url: &str) -> Result<()> {
+    let (program, before): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else if cfg!(windows) {
+        // `start` takes its first quoted argument as a window title.
+        ("cmd", &["/C", "start", ""])
+    } else {
+        ("xdg-open", &[])
+    };
+    let status = Command::new(program)
+        .args(before)
+        .arg(url)
+        .status()
+        .map_err(|err| {
+            Error::Invalid(format!(
+                "could not run `{program}`: {err}; pass --print for the address instead"
+            ))
+        })?;
+    if !status.success() {
+        return Err(Error::Invalid(format!(
+            "`{program}` exited with {status}; pass --print for the address instead"
+        )));
+    }
+    Ok(())
+}
+
+/// Opens `path` in `$VISUAL` or `$EDITOR` (`vi` if neither is set) and
+/// waits for it to exit.
+/// A file for the editor in a directory of its own that only the user can
+/// read, both removed when dropped, so what is edited (notes possibly
+/// decrypted) is neither at a guessable path nor left behind on an error.
+pub struct EditFile {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl EditFile {
+    pub fn create(name: &str) -> Result<Self> {
+        let dir = env::temp_dir().join(format!("task-manager-{:016x}", random_u64()));
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        // Fails rather than use a directory someone else made.
+        builder.create(&dir)?;
+        let file = EditFile {
+            path: dir.join(name),
+            dir,
+        };
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(&file.path)?;
+        Ok(file)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for EditFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+pub fn run_editor(path: &Path) -> Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().expect("editor is not empty");
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|err| Error::Invalid(format!("could not run editor `{editor}`: {err}")))?;
+    if !status.success() {
+        return Err(Error::Invalid(format!(
+            "editor `{editor}` exited with {status}; the task was not changed"
+        )));
+    }
+    Ok(())
+}
+
+/// The tasks `today` picks from: outside archived projects, without private
+/// ones if they are hidden, and with their inherited priority.
+pub(super) fn today_view(ctx: &Context) -> Vec<Task> {
+    let hide_private = ctx.config.display.hide_private;
+    ctx.manager
+        .tasks()
+        .iter()
+        .filter(|t| !(hide_private && t.private))
+        .filter(|t| {
+            !t.project
+                .as_deref()
+                .is_some_and(|p| ctx.manager.is_archived(p))
+        })
+        .map(|t| Task {
+            priority: effective_priority(ctx, t),
+            ..t.clone()
+        })
+        .collect()
+}
+
+/// The priority shown for `task`: its own, or with the `max` inheritance
+/// policy the highest among its open subtasks.
+fn effective_priority(ctx: &Context, task: &Task) -> Priority {
+    match ctx.config.priority_inheritance(task.project.as_deref()) {
+        PriorityInheritance::Max => ctx.manager.max_priority(task.id),
+        _ => task.priority,
+    }
+}
+
+/// Applies the configured WIP limits, pointing at the override flag.
+fn check_limits(ctx: &Context, before: Option<&Task>, id: TaskId) -> Result<()> {
+    ctx.manager
+        .check_limits(&ctx.config.wip, before, id)
+        .map_err(|err| Error::Conflict(format!("{err}; pass --ignore-limits to go over it")))
+}
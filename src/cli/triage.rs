@@ -0,0 +1,123 @@
+//! Reviewing tasks from a bulk import one at a time before they are kept.
+
+use std::fs;
+
+use task_manager::config::Config;
+use task_manager::dates::Timestamp;
+use task_manager::render::short_id;
+use task_manager::{Result, Task, TasksManager};
+
+use super::prompt::{self, ask};
+use super::render;
+use super::tasks::{run_editor, EditFile};
+
+/// The outcome of a triage pass.
+#[derive(Default)]
+pub struct Triage {
+    pub accepted: Vec<Task>,
+    pub discarded: Vec<Task>,
+    /// How many of the accepted tasks were edited first.
+    pub edited: usize,
+}
+
+impl Triage {
+    pub fn print_summary(&self) {
+        println!(
+            "Triage: {} accepted ({} edited), {} discarded.",
+            self.accepted.len(),
+            self.edited,
+            self.discarded.len()
+        );
+    }
+}
+
+/// Shows each of `tasks` and asks whether to accept, edit or discard it.
+/// `manager` resolves links for the detail view. Quitting, or the end of
+/// input, discards the tasks not yet decided on.
+pub fn triage(tasks: Vec<Task>, manager: &TasksManager, config: &Config) -> Result<Triage> {
+    let times = render::Times {
+        now: Timestamp::now(),
+        relative: config.display.relative_times,
+    };
+    let total = tasks.len();
+    let mut triage = Triage::default();
+    let mut rest = tasks.into_iter().enumerate();
+    while let Some((i, mut task)) = rest.next() {
+        let mut edited = false;
+        let mut show = true;
+        loop {
+            if show {
+                println!("\n({}/{total})", i + 1);
+                print!(
+                    "{}",
+                    render::task_detail(
+                        &task,
+                        task.priority,
+                        manager,
+                        times,
+                        &config.glyphs,
+                        render::terminal_width()
+                    )
+                );
+                show = false;
+            }
+            let answer = ask("[a]ccept, [e]dit, [d]iscard, accept [r]est, [q]uit? ");
+            match answer.as_deref().map(str::to_ascii_lowercase).as_deref() {
+                Some("a" | "accept") => {
+                    triage.edited += usize::from(edited);
+                    triage.accepted.push(task);
+                    break;
+                }
+                Some("e" | "edit") => {
+                    if let Some(changed) = edit(&task)? {
+                        edited |= changed != task;
+                        task = changed;
+                    }
+                    show = true;
+                }
+                Some("d" | "discard") => {
+                    triage.discarded.push(task);
+                    break;
+                }
+                Some("r" | "rest") => {
+                    triage.edited += usize::from(edited);
+                    triage.accepted.push(task);
+                    triage.accepted.extend(rest.by_ref().map(|(_, task)| task));
+                    break;
+                }
+                Some("q" | "quit") | None => {
+                    triage.discarded.push(task);
+                    triage.discarded.extend(rest.by_ref().map(|(_, task)| task));
+                    break;
+                }
+                Some("") => {}
+                Some(other) => println!("Unknown answer `{other}`."),
+            }
+        }
+    }
+    Ok(triage)
+}
+
+/// Lets the user edit `task` as JSON. `None` when the edit was given up.
+fn edit(task: &Task) -> Result<Option<Task>> {
+    let file = EditFile::create(&format!("task-{}.json", short_id(&task.id.to_string())))?;
+    let path = file.path();
+    let mut text = format!("{}\n", task.to_json().to_pretty());
+    loop {
+        fs::write(path, &text)?;
+        if let Err(err) = run_editor(path) {
+            eprintln!("error: {err}");
+            return Ok(None);
+        }
+        text = fs::read_to_string(path)?;
+        match Task::from_edited_json(&text, task) {
+            Ok(edited) => return Ok(Some(edited)),
+            Err(err) => {
+                eprintln!("error: {err}");
+                if !prompt::confirm("Edit again?") {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! Settings from more than the configuration file. Each layer overrides
+//! the ones before it: the defaults, then the file, then environment
+//! variables (`TASK_MANAGER_DISPLAY_TIMEZONE=+02:00`), then command-line
+//! flags (`--setting display.timezone=+02:00`).
+//!
+//! Values outside the file are written as in it, so `true` and `48` are a
+//! boolean and a number; anything that does not read as TOML, such as
+//! `+02:00`, is taken as a string.
+
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::toml;
+
+use super::Config;
+
+/// Environment variables naming a setting start with this, followed by the
+/// section and key in capitals: `TASK_MANAGER_DISPLAY_RELATIVE_TIMES`.
+pub const ENV_PREFIX: &str = "TASK_MANAGER_";
+
+/// Variables with [`ENV_PREFIX`] that are not settings: the data file and
+/// the configuration file, which plugins are given too, and the passphrase
+/// of `encryption.fields`.
+const ENV_OTHERS: [&str; 3] = ["FILE", "CONFIG", "PASSPHRASE"];
+
+/// The settings with a single value, which environment variables can set,
+/// and their defaults written as in the file; empty for none.
+pub const SETTINGS: &[(&str, &str)] = &[
+    ("display.timezone", "\"UTC\""),
+    ("display.relative_times", "false"),
+    ("display.date_format", "\"%d-%m-%Y\""),
+    ("display.time_format", "\"%H:%M:%S\""),
+    ("display.hide_private", "false"),
+    ("display.locale", "\"en\""),
+    ("display.week_start", "\"monday\""),
+    ("display.brief", "false"),
+    ("user.name", ""),
+    ("confirm.remove", "true"),
+    ("confirm.overwrite", "true"),
+    ("confirm.apply", "true"),
+    ("confirm.groom", "true"),
+    ("autosave.strategy", "\"change\""),
+    ("autosave.interval", "\"30s\""),
+    ("done.follow_up", "false"),
+    ("groom.after", "\"none\""),
+    ("groom.action", "\"archive\""),
+    ("daemon.interval", "60"),
+    ("daemon.backup_every", "\"1d\""),
+    ("daemon.notify", ""),
+    ("daemon.bell", "false"),
+    ("daemon.sound", ""),
+    (
+        "daemon.sound_player",
+        if cfg!(target_os = "macos") {
+            "\"afplay\""
+        } else {
+            "\"paplay\""
+        },
+    ),
+    ("backup.endpoint", ""),
+    ("backup.bucket", ""),
+    ("backup.region", "\"us-east-1\""),
+    ("backup.object", "\"tasks.json.enc\""),
+    ("backup.access_key", ""),
+    ("backup.secret_key", ""),
+    ("backup.passphrase", ""),
+    ("jira.url", ""),
+    ("jira.email", ""),
+    ("jira.token", ""),
+    ("notion.database", ""),
+    ("notion.token", ""),
+    ("notion.done", "\"Done\""),
+    ("notion.columns.name", "\"Name\""),
+    ("notion.columns.status", "\"Status\""),
+    ("notion.columns.due", "\"Due\""),
+    ("notion.columns.priority", "\"Priority\""),
+    ("notion.columns.description", "\"Description\""),
+    ("google.client_id", ""),
+    ("google.client_secret", ""),
+    ("encryption.fields", "false"),
+    ("encryption.passphrase", ""),
+    ("git.auto_commit", "false"),
+    ("git.auto_push", "false"),
+    ("git.remote", ""),
+    ("links.hyperlinks", "\"auto\""),
+    ("links.jira", ""),
+    ("mail.maildir", ""),
+    ("mail.imap", ""),
+    ("mail.user", ""),
+    ("mail.password", ""),
+    ("mail.move_to", ""),
+    ("printer.device", ""),
+    ("printer.width", ""),
+    ("priorities.critical", "5"),
+    ("priorities.high", "20"),
+    ("priorities.medium", "50"),
+    ("priorities.low", "25"),
+    ("theme.name", "\"default\""),
+    ("glyphs.profile", "\"unicode\""),
+    ("wip.in_progress", ""),
+    ("wip.per_day", ""),
+    ("defaults.priority_inheritance", ""),
+];
+
+/// Settings whose values are not shown, only whether they are set.
+const SECRETS: [&str; 8] = [
+    "backup.access_key",
+    "backup.secret_key",
+    "backup.passphrase",
+    "jira.token",
+    "notion.token",
+    "google.client_secret",
+    "mail.password",
+    "encryption.passphrase",
+];
+
+/// Where a setting's value came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    File(PathBuf),
+    /// The environment variable's name.
+    Env(String),
+    /// The flag as given.
+    Flag(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => f.write_str("default"),
+            Origin::File(path) => write!(f, "{}", path.display()),
+            Origin::Env(name) => write!(f, "${name}"),
+            Origin::Flag(flag) => f.write_str(flag),
+        }
+    }
+}
+
+/// One setting given on top of the file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Override {
+    /// Dotted, as `section.key`.
+    pub key: String,
+    pub value: Value,
+    pub origin: Origin,
+}
+
+impl Override {
+    /// `section.key=value`, as given to `--setting`.
+    pub fn flag(arg: &str) -> Result<Self> {
+        let (key, value) = arg
+            .split_once('=')
+            .filter(|(key, _)| key.contains('.'))
+            .ok_or_else(|| {
+                Error::Invalid(format!(
+                    "`--setting` takes section.key=value, e.g. display.brief=true; found `{arg}`"
+                ))
+            })?;
+        Ok(Override {
+            key: key.trim().to_string(),
+            value: parse_value(value),
+            origin: Origin::Flag(format!("--setting {}", key.trim())),
+        })
+    }
+
+    /// The settings among `vars` (name and value pairs, as from
+    /// [`std::env::vars`]). Other variables with [`ENV_PREFIX`] are
+    /// rejected, as unknown keys in the file are.
+    pub fn from_env(vars: impl IntoIterator<Item = (String, String)>) -> Result<Vec<Self>> {
+        let mut overrides = Vec::new();
+        for (name, value) in vars {
+            let Some(rest) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            if ENV_OTHERS.contains(&rest) {
+                continue;
+            }
+            let key = SETTINGS
+                .iter()
+                .map(|&(key, _)| key)
+                .find(|key| key.replace('.', "_").eq_ignore_ascii_case(rest))
+                .ok_or_else(|| Error::Invalid(format!("unknown setting `${name}`")))?;
+            overrides.push(Override {
+                key: key.to_string(),
+                value: parse_value(&value),
+                origin: Origin::Env(name),
+            });
+        }
+        Ok(overrides)
+    }
+}
+
+/// A setting's final value, written as in the file, and where it came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Setting {
+    pub key: String,
+    /// Empty when the setting has no value.
+    pub value: String,
+    pub origin: Origin,
+}
+
+/// The layers read so far, merged.
+#[derive(Clone, Debug)]
+pub struct Layers {
+    root: Value,
+    /// The origin of every value in `root`, by dotted key.
+    origins: Vec<(String, Origin)>,
+}
+
+impl Default for Layers {
+    fn default() -> Self {
+        Layers {
+            root: Value::object(),
+            origins: Vec::new(),
+        }
+    }
+}
+
+impl Layers {
+    /// Only the defaults.
+    pub fn new() -> Self {
+        Layers::default()
+    }
+
+    /// Adds the file read from `path`, whose text is `input`.
+    pub fn with_file(mut self, input: &str, path: &Path) -> Result<Self> {
+        let in_file = |err: Error| Error::Invalid(format!("{}: {err}", path.display()));
+        let root = toml::parse(input).map_err(in_file)?;
+        Config::from_value(&root).map_err(in_file)?;
+        let mut leaves = Vec::new();
+        leaves_of(&root, String::new(), &mut leaves);
+        self.origins.extend(
+            leaves
+                .into_iter()
+                .map(|key| (key, Origin::File(path.to_path_buf()))),
+        );
+        self.root = root;
+        Ok(self)
+    }
+
+    /// Adds the file at `path`; a missing file adds nothing.
+    #[cfg(feature = "fs")]
+    pub fn read(self, path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => self.with_file(&text, path),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(self),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Adds `item` over everything before it. It is checked on its own, so
+    /// an error names where it came from.
+    pub fn set(&mut self, item: Override) -> Result<()> {
+        let path: Vec<&str> = item.key.split('.').collect();
+        let mut alone = Value::object();
+        insert(&mut alone, &path, item.value.clone())?;
+        Config::from_value(&alone)
+            .map_err(|err| Error::Invalid(format!("{}: {err}", item.origin)))?;
+        insert(&mut self.root, &path, item.value)?;
+        self.origins
+            .retain(|(key, _)| key != &item.key && !key.starts_with(&format!("{}.", item.key)));
+        self.origins.push((item.key, item.origin));
+        Ok(())
+    }
+
+    /// Where `key` (dotted, as `section.key`) got its value; `None` when
+    /// no layer gave it one.
+    pub fn origin(&self, key: &str) -> Option<&Origin> {
+        self.origins
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, origin)| origin)
+    }
+
+    /// The configuration the layers add up to.
+    pub fn config(&self) -> Result<Config> {
+        Config::from_value(&self.root)
+    }
+
+    /// Every setting in [`SETTINGS`], then those outside it that a layer
+    /// gave, with its value and origin. Secrets show as `(set)`.
+    pub fn settings(&self) -> Vec<Setting> {
+        let mut out: Vec<Setting> = SETTINGS
+            .iter()
+            .map(|&(key, default)| Setting {
+                key: key.to_string(),
+                value: default.to_string(),
+                origin: Origin::Default,
+            })
+            .collect();
+        for (key, origin) in &self.origins {
+            let path: Vec<&str> = key.split('.').collect();
+            let Some(value) = lookup(&self.root, &path) else {
+                continue;
+            };
+            let setting = Setting {
+                key: key.clone(),
+                value: value.to_compact(),
+                origin: origin.clone(),
+            };
+            match out.iter_mut().find(|s| s.key == *key) {
+                Some(slot) => *slot = setting,
+                None => out.push(setting),
+            }
+        }
+        for setting in &mut out {
+            if SECRETS.contains(&setting.key.as_str()) && !setting.value.is_empty() {
+                setting.value = "(set)".into();
+            }
+        }
+        out
+    }
+}
+
+/// `text` as a TOML value, or as a string when it is not one.
+fn parse_value(text: &str) -> Value {
+    toml::parse(&format!("value = {text}"))
+        .ok()
+        .and_then(|root| root.get("value").cloned())
+        .unwrap_or_else(|| Value::String(text.to_string()))
+}
+
+/// Sets `value` at `path` in `root`, making the tables on the way.
+fn insert(root: &mut Value, path: &[&str], value: Value) -> Result<()> {
+    let Value::Object(fields) = root else {
+        return Err(Error::Invalid(format!(
+            "cannot set `{}` inside a value that is not a table",
+            path.join(".")
+        )));
+    };
+    let (first, rest) = path.split_first().expect("keys are not empty");
+    let index = match fields.iter().position(|(key, _)| key == first) {
+        Some(index) => index,
+        None => {
+            fields.push((first.to_string(), Value::object()));
+            fields.len() - 1
+        }
+    };
+    if rest.is_empty() {
+        fields[index].1 = value;
+        Ok(())
+    } else {
+        insert(&mut fields[index].1, rest, value)
+    }
+}
+
+fn lookup<'a>(root: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter().try_fold(root, |value, key| value.get(key))
+}
+
+/// The dotted keys of the values in `value` that are not tables.
+fn leaves_of(value: &Value, prefix: String, out: &mut Vec<String>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                leaves_of(value, key, out);
+            }
+        }
+        _ => out.push(prefix),
+    }
+}
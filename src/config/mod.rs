@@ -0,0 +1,1653 @@
+//! User settings read from `config.toml`.
+//!
+//! The file is optional; every setting has a default. Unknown sections and
+//! keys are rejected so a typo does not silently leave a setting unapplied.
+//!
+//! ```toml
+//! [display]
+//! timezone = "+02:00"     # a fixed offset; zone names are not supported
+//! relative_times = true   # "due in 3 hours" instead of a date
+//! date_format = "%Y-%m-%d" # strftime; "%d-%m-%Y" by default
+//! time_format = "%H:%M"    # "%H:%M:%S" by default; "" for none
+//! hide_private = true      # list leaves out private tasks unless --private
+//! locale = "de"            # month and day names; en, de, es, fr, it, nl, pt
+//! week_start = "sunday"    # Monday by default
+//! brief = true             # one sentence per task, as `--brief`
+//!
+//! [user]
+//! name = "alice"     # who finishes and approves tasks; else $USER
+//!
+//! [confirm]
+//! remove = true      # ask before removing a task
+//! overwrite = true   # ask before replacing an existing file
+//! apply = true       # ask before applying a manifest
+//! groom = true       # ask before grooming stale tasks
+//!
+//! [autosave]
+//! strategy = "debounce"  # "change" (the default), "debounce" or "exit"
+//! interval = "30s"       # with debounce, the least time between saves
+//!
+//! [done]
+//! follow_up = true   # offer to add a follow-up task after `done`
+//!
+//! [groom]
+//! after = "6w"       # low-priority tasks untouched this long are stale
+//! action = "archive" # or "trash" to remove them
+//!
+//! [daemon]
+//! interval = 60              # seconds between checks
+//! backup_every = "1d"        # or "none"
+//! notify = "notify-send"     # run with each reminder's text
+//! bell = true               # ring the terminal bell
+//! sound = "/usr/share/sounds/freedesktop/stereo/bell.oga"
+//! sound_player = "paplay"    # the default; afplay on macOS
+//!
+//! [backup]
+//! endpoint = "https://s3.eu-central-1.amazonaws.com"
+//! bucket = "my-backups"
+//! region = "eu-central-1"    # us-east-1 by default
+//! object = "tasks.json.enc"  # the default
+//! access_key = "..."         # else AWS_ACCESS_KEY_ID
+//! secret_key = "..."         # else AWS_SECRET_ACCESS_KEY
+//! passphrase = "..."         # else TASK_MANAGER_BACKUP_PASSPHRASE
+//!
+//! [jira]
+//! url = "https://example.atlassian.net"   # for `jira sync`; else from links.jira
+//! email = "me@example.com"   # else JIRA_EMAIL
+//! token = "..."              # an API token; else JIRA_API_TOKEN
+//!
+//! [notion]
+//! database = "0123456789abcdef0123456789abcdef"   # for `sync notion`
+//! token = "..."              # an integration secret; else NOTION_TOKEN
+//! done = "Done"              # the status option meaning done, the default
+//!
+//! [notion.columns]           # the property holding each field; the defaults
+//! name = "Name"
+//! status = "Status"
+//! due = "Due"                # "" when the database has no such property
+//! priority = "Priority"
+//! description = "Description"
+//!
+//! [google]                   # an OAuth client for TVs and limited input
+//! client_id = "....apps.googleusercontent.com"   # for `sync google`; else GOOGLE_CLIENT_ID
+//! client_secret = "..."      # else GOOGLE_CLIENT_SECRET
+//!
+//! [encryption]
+//! fields = true              # descriptions and notes encrypted in the data file
+//! passphrase = "..."         # else TASK_MANAGER_PASSPHRASE
+//!
+//! [git]
+//! auto_commit = true         # commit the data file after every change
+//! auto_push = true           # then push, to `remote` or the default
+//! remote = "origin"
+//!
+//! [links]
+//! hyperlinks = "auto"        # clickable links; or "always", "never"
+//! jira = "https://example.atlassian.net/browse/"   # where JIRA keys point
+//!
+//! [mail]
+//! maildir = "/home/me/Mail/Inbox"   # new mail here becomes tasks, see `mail`
+//! # or, instead of a Maildir, an IMAP folder:
+//! imap = "imaps://imap.example.com/INBOX"
+//! user = "me@example.com"
+//! password = "..."           # else IMAP_PASSWORD
+//! move_to = "Tasks"          # where taken mail goes; else it is only marked read
+//!
+//! [printer]
+//! device = "/dev/usb/lp0"    # or "tcp://192.168.1.50:9100", see `print`
+//! width = 48                 # characters per line; 32 by default
+//!
+//! [priorities]
+//! critical = 5       # how `normalize-priorities` spreads open tasks over
+//! high = 20          # the levels, as shares; these are the defaults
+//! medium = 50
+//! low = 25
+//!
+//! [theme]
+//! name = "solarized"   # or "default", "high-contrast", "monochrome"
+//! critical = "#ff5f00" # override a priority, status or `overdue` colour
+//!
+//! [tags.urgent]
+//! color = "red"      # a colour name or "#rrggbb"
+//! icon = "🔥"        # shown before the tag
+//!
+//! [glyphs]
+//! profile = "ascii"  # or "unicode", the default
+//! pinned = "*"       # override single glyphs on top of the profile
+//! done = "x"         # shown before a status or priority (none by default)
+//!
+//! [wip]
+//! in_progress = 3   # tasks in progress at once
+//! per_day = 10      # open tasks due on the same day
+//!
+//! [defaults]
+//! priority_inheritance = "none"
+//!
+//! [projects.website]
+//! priority_inheritance = "propagate"
+//!
+//! [[rules]]
+//! name = "triage bugs"       # shown when it fires; else the `when`
+//! when = "tag:bug"           # a filter, as for `list`
+//! set = ["priority=high", "+triage"]
+//!
+//! [workflow]
+//! forbid = ["todo -> done"]              # status changes not allowed
+//! require_note = ["cancelled -> *"]      # ones that need --note "why"
+//!
+//! [[sources]]
+//! name = "bob"               # the label its tasks are listed under
+//! path = "/shared/bob/tasks.json"   # or url = "https://..."
+//! ```
+//!
+//! The file is one of several [`layers`]: environment variables and
+//! command-line flags override single settings on top of it.
+
+mod layers;
+
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::dates::{self, Locale, UtcOffset, Weekday, SECONDS_PER_DAY};
+use crate::duration;
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::rules::Rule;
+use crate::task::{Priority, Status};
+use crate::toml;
+use crate::workflow::Workflow;
+
+pub use layers::{Layers, Origin, Override, Setting, ENV_PREFIX, SETTINGS};
+
+/// Name of the configuration file inside the config directory.
+pub const CONFIG_FILE: &str = "config.toml";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    pub display: DisplaySettings,
+    /// The name recorded in review steps, when not taken from `$USER`.
+    pub user: Option<String>,
+    pub confirm: ConfirmPolicy,
+    pub autosave: AutosaveSettings,
+    pub done: DoneSettings,
+    pub groom: GroomSettings,
+    pub daemon: DaemonSettings,
+    pub backup: BackupSettings,
+    pub encryption: EncryptionSettings,
+    pub jira: JiraSettings,
+    pub notion: NotionSettings,
+    pub google: GoogleSettings,
+    pub git: GitSettings,
+    pub links: LinkSettings,
+    pub mail: MailSettings,
+    pub printer: PrinterSettings,
+    pub priorities: PriorityScheme,
+    pub theme: ThemeSettings,
+    pub tags: TagStyles,
+    pub glyphs: Glyphs,
+    pub wip: WipLimits,
+    /// Settings for tasks whose project has no entry in `projects`.
+    pub defaults: ProjectSettings,
+    /// Per-project overrides, keyed by project name.
+    pub projects: BTreeMap<String, ProjectSettings>,
+    /// Automation rules, in the order they run.
+    pub rules: Vec<Rule>,
+    /// Other people's task lists, shown read-only next to this one.
+    pub sources: Vec<Source>,
+    /// Which status changes are allowed.
+    pub workflow: Workflow,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DisplaySettings {
+    /// Offset timestamps are shown and entered in. It is fixed: there are
+    /// no daylight-saving rules, so it must be changed when the clocks are.
+    pub timezone: UtcOffset,
+    /// Show times relative to now ("2 days ago") rather than as dates.
+    pub relative_times: bool,
+    /// strftime patterns for showing dates, see [`dates::set_display_format`].
+    pub date_format: String,
+    pub time_format: String,
+    /// `list` leaves out private tasks unless given `--private`, for
+    /// screens others can see.
+    pub hide_private: bool,
+    /// Language of month and day names.
+    pub locale: Locale,
+    /// First day of the week in calendars, `this-week` and `@weekly`.
+    pub week_start: Weekday,
+    /// `list`, `today` and `stats` print a sentence per line instead of
+    /// tables and charts, for screen readers and speech.
+    pub brief: bool,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            timezone: UtcOffset::default(),
+            relative_times: false,
+            date_format: dates::DATE_FORMAT.to_string(),
+            time_format: dates::TIME_FORMAT.to_string(),
+            hide_private: false,
+            locale: Locale::ENGLISH,
+            week_start: Weekday::Monday,
+            brief: false,
+        }
+    }
+}
+
+/// An operation that may ask before going ahead. Operations not listed here
+/// never ask.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Removing a task.
+    Remove,
+    /// Writing over an existing file.
+    Overwrite,
+    /// Applying a manifest, which may create, change and remove many tasks.
+    Apply,
+    /// Archiving or removing stale tasks with `groom`.
+    Groom,
+}
+
+/// Which destructive operations ask for confirmation. All of them do unless
+/// turned off here or passed `--force`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConfirmPolicy {
+    pub remove: bool,
+    pub overwrite: bool,
+    pub apply: bool,
+    pub groom: bool,
+}
+
+impl Default for ConfirmPolicy {
+    fn default() -> Self {
+        ConfirmPolicy {
+            remove: true,
+            overwrite: true,
+            apply: true,
+            groom: true,
+        }
+    }
+}
+
+impl ConfirmPolicy {
+    /// Whether `operation` should ask first; `force` skips the question.
+    pub fn asks(&self, operation: Operation, force: bool) -> bool {
+        !force
+            && match operation {
+                Operation::Remove => self.remove,
+                Operation::Overwrite => self.overwrite,
+                Operation::Apply => self.apply,
+                Operation::Groom => self.groom,
+            }
+    }
+}
+
+/// When the interactive mode writes changes to the data file. Single
+/// commands always save once they finish.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AutosaveSettings {
+    pub strategy: AutosaveStrategy,
+    /// Seconds, for [`AutosaveStrategy::Debounce`].
+    pub interval: i64,
+}
+
+impl Default for AutosaveSettings {
+    fn default() -> Self {
+        AutosaveSettings {
+            strategy: AutosaveStrategy::default(),
+            interval: 30,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AutosaveStrategy {
+    /// After every command that changed something.
+    #[default]
+    Change,
+    /// At most once per interval, from a background timer.
+    Debounce,
+    /// When the session ends.
+    Exit,
+}
+
+impl FromStr for AutosaveStrategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "change" => Ok(AutosaveStrategy::Change),
+            "debounce" => Ok(AutosaveStrategy::Debounce),
+            "exit" => Ok(AutosaveStrategy::Exit),
+            _ => Err(Error::Invalid(format!(
+                "unknown autosave strategy `{s}` (expected change, debounce or exit)"
+            ))),
+        }
+    }
+}
+
+/// What `done` does besides finishing the task.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DoneSettings {
+    /// Ask whether to add a follow-up task.
+    pub follow_up: bool,
+}
+
+/// The decay policy `groom` applies to low-priority tasks nobody has
+/// touched for a while.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GroomSettings {
+    /// Seconds without a change before a task is stale; `None` leaves
+    /// grooming off unless `groom --after` is given.
+    pub after: Option<i64>,
+    pub action: GroomAction,
+}
+
+/// What `groom` does with stale tasks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GroomAction {
+    /// Keep them, hidden from listings.
+    #[default]
+    Archive,
+    /// Remove them.
+    Trash,
+}
+
+impl GroomAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GroomAction::Archive => "archive",
+            GroomAction::Trash => "trash",
+        }
+    }
+}
+
+impl FromStr for GroomAction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "archive" => Ok(GroomAction::Archive),
+            "trash" | "remove" => Ok(GroomAction::Trash),
+            _ => Err(Error::Invalid(format!(
+                "unknown groom action `{s}` (expected archive or trash)"
+            ))),
+        }
+    }
+}
+
+/// How `daemon` behaves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DaemonSettings {
+    /// Seconds between checks for reminders, templates and backups.
+    pub interval: u64,
+    /// Seconds between backups of the data file; `None` turns them off.
+    pub backup_every: Option<i64>,
+    /// Command run with the text of each reminder as its argument.
+    pub notify: Option<String>,
+    /// Ring the terminal bell when a reminder fires.
+    pub bell: bool,
+    /// Sound file played with `sound_player` when a reminder fires.
+    pub sound: Option<PathBuf>,
+    pub sound_player: String,
+}
+
+/// The program that plays `sound` unless configured otherwise.
+pub const DEFAULT_SOUND_PLAYER: &str = if cfg!(target_os = "macos") {
+    "afplay"
+} else {
+    "paplay"
+};
+
+impl Default for DaemonSettings {
+    fn default() -> Self {
+        DaemonSettings {
+            interval: 60,
+            backup_every: Some(SECONDS_PER_DAY),
+            notify: None,
+            bell: false,
+            sound: None,
+            sound_player: DEFAULT_SOUND_PLAYER.to_string(),
+        }
+    }
+}
+
+/// Where `backup remote` keeps the encrypted data file: an object in an
+/// S3-compatible bucket. Keys and the passphrase left unset here are read
+/// from the environment when needed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackupSettings {
+    pub endpoint: Option<String>,
+    pub bucket: Option<String>,
+    pub region: String,
+    pub object: String,
+    pub access_key: Option<String>,
+    pub secret_key: Option<String>,
+    pub passphrase: Option<String>,
+}
+
+/// Encrypting descriptions and notes in the data file while the rest of
+/// each task stays readable. A passphrase left unset here is read from the
+/// environment.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EncryptionSettings {
+    /// Write descriptions and notes encrypted.
+    pub fields: bool,
+    pub passphrase: Option<String>,
+}
+
+/// The JIRA site `jira sync` talks to. Credentials left unset here are
+/// read from the environment when needed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JiraSettings {
+    /// The site's address, e.g. `https://example.atlassian.net`.
+    pub url: Option<String>,
+    pub email: Option<String>,
+    pub token: Option<String>,
+}
+
+/// The Notion database `sync notion` keeps in step with the tasks. A token
+/// left unset here is read from the environment when needed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotionSettings {
+    pub database: Option<String>,
+    pub token: Option<String>,
+    /// The option of the status column that means done.
+    pub done: String,
+    pub columns: NotionColumns,
+}
+
+impl Default for NotionSettings {
+    fn default() -> Self {
+        NotionSettings {
+            database: None,
+            token: None,
+            done: "Done".to_string(),
+            columns: NotionColumns::default(),
+        }
+    }
+}
+
+/// Which database property holds each task field. Only the name and
+/// status are required; the others are left out when `None`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotionColumns {
+    /// The title property.
+    pub name: String,
+    /// A status or select property.
+    pub status: String,
+    /// A date property.
+    pub due: Option<String>,
+    /// A select property whose options are priority names.
+    pub priority: Option<String>,
+    /// A text property.
+    pub description: Option<String>,
+}
+
+impl Default for NotionColumns {
+    fn default() -> Self {
+        NotionColumns {
+            name: "Name".to_string(),
+            status: "Status".to_string(),
+            due: Some("Due".to_string()),
+            priority: Some("Priority".to_string()),
+            description: Some("Description".to_string()),
+        }
+    }
+}
+
+/// The OAuth client `sync google` signs in with. Values left unset here
+/// are read from the environment when needed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GoogleSettings {
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+/// Keeping the data file under git, see `history` and `rollback`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GitSettings {
+    /// Commit the data file after every command that changes it.
+    pub auto_commit: bool,
+    /// Push after each of those commits.
+    pub auto_push: bool,
+    /// Where to push; the branch's upstream when unset.
+    pub remote: Option<String>,
+}
+
+/// Turning email into tasks, see [`mail`].
+///
+/// [`mail`]: crate::mail
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MailSettings {
+    /// Maildir whose new messages become tasks.
+    pub maildir: Option<PathBuf>,
+    /// IMAP folder whose unread messages become tasks instead, as an
+    /// `imaps://host/folder` URL.
+    pub imap: Option<String>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    /// IMAP folder taken messages are moved to; otherwise they are only
+    /// marked read.
+    pub move_to: Option<String>,
+}
+
+/// The receipt printer `print` writes to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrinterSettings {
+    /// A device file, or `tcp://host[:port]` for a network printer.
+    pub device: Option<String>,
+    /// Characters per line; [`escpos::DEFAULT_WIDTH`] when unset.
+    ///
+    /// [`escpos::DEFAULT_WIDTH`]: crate::export::escpos::DEFAULT_WIDTH
+    pub width: Option<usize>,
+}
+
+/// How `normalize-priorities` spreads open tasks over the priority levels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PriorityScheme {
+    /// Each level's share of the tasks, most important first; the shares
+    /// need not add up to 100.
+    pub shares: [(Priority, usize); 4],
+}
+
+impl Default for PriorityScheme {
+    fn default() -> Self {
+        PriorityScheme {
+            shares: [
+                (Priority::Critical, 5),
+                (Priority::High, 20),
+                (Priority::Medium, 50),
+                (Priority::Low, 25),
+            ],
+        }
+    }
+}
+
+/// Clickable links in terminal output, see [`render::links`].
+///
+/// [`render::links`]: crate::render::links
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LinkSettings {
+    pub hyperlinks: HyperlinkMode,
+    /// Address JIRA keys are appended to, e.g.
+    /// `https://example.atlassian.net/browse/`.
+    pub jira: Option<String>,
+}
+
+/// When to write hyperlinks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HyperlinkMode {
+    /// On terminals known to support them.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for HyperlinkMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(HyperlinkMode::Auto),
+            "always" => Ok(HyperlinkMode::Always),
+            "never" => Ok(HyperlinkMode::Never),
+            _ => Err(Error::Invalid(format!(
+                "unknown hyperlink setting `{s}` (expected auto, always or never)"
+            ))),
+        }
+    }
+}
+
+/// Colours in terminal output, see [`render::theme`].
+///
+/// [`render::theme`]: crate::render::theme
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ThemeSettings {
+    pub name: ThemeName,
+    /// Colours replacing the theme's, keyed by one of [`ThemeName::KEYS`].
+    pub colors: BTreeMap<String, Color>,
+}
+
+/// A built-in colour theme.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ThemeName {
+    #[default]
+    Default,
+    Solarized,
+    /// Bold, bright colours and backgrounds for low vision or washed-out
+    /// screens.
+    HighContrast,
+    /// Bold, dim, underline and reverse video only.
+    Monochrome,
+}
+
+impl ThemeName {
+    pub const ALL: [ThemeName; 4] = [
+        ThemeName::Default,
+        ThemeName::Solarized,
+        ThemeName::HighContrast,
+        ThemeName::Monochrome,
+    ];
+
+    /// What a theme colours, as `[theme]` overrides name it.
+    pub const KEYS: [&'static str; 10] = [
+        "low",
+        "medium",
+        "high",
+        "critical",
+        "todo",
+        "in-progress",
+        "review-pending",
+        "done",
+        "cancelled",
+        "overdue",
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ThemeName::Default => "default",
+            ThemeName::Solarized => "solarized",
+            ThemeName::HighContrast => "high-contrast",
+            ThemeName::Monochrome => "monochrome",
+        }
+    }
+}
+
+impl FromStr for ThemeName {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.trim().to_ascii_lowercase().replace('_', "-");
+        ThemeName::ALL
+            .into_iter()
+            .find(|theme| theme.as_str() == name)
+            .ok_or_else(|| {
+                Error::Invalid(format!(
+                    "unknown theme `{s}` (expected default, solarized, high-contrast or monochrome)"
+                ))
+            })
+    }
+}
+
+/// A read-only task list from `[[sources]]`: another data file, or a copy
+/// of one on a web server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Source {
+    /// The label its tasks are listed under.
+    pub name: String,
+    pub location: SourceLocation,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SourceLocation {
+    Path(PathBuf),
+    /// An `http://` or `https://` address, fetched with curl.
+    Url(String),
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceLocation::Path(path) => write!(f, "{}", path.display()),
+            SourceLocation::Url(url) => f.write_str(url),
+        }
+    }
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        BackupSettings {
+            endpoint: None,
+            bucket: None,
+            region: "us-east-1".to_string(),
+            object: "tasks.json.enc".to_string(),
+            access_key: None,
+            secret_key: None,
+            passphrase: None,
+        }
+    }
+}
+
+/// How individual tags are shown, keyed by tag name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TagStyles(BTreeMap<String, TagStyle>);
+
+impl TagStyles {
+    /// The style for `tag`, matched ignoring case as tag filters are.
+    pub fn get(&self, tag: &str) -> Option<&TagStyle> {
+        self.0
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(tag))
+            .map(|(_, style)| style)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TagStyle {
+    pub color: Option<Color>,
+    /// Emoji or icon-font glyph shown in front of the tag.
+    pub icon: Option<String>,
+}
+
+/// A colour for terminal and HTML output: one of the eight standard
+/// terminal colours or an exact `#rrggbb`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    const NAMES: [(&'static str, Color); 8] = [
+        ("black", Color::Black),
+        ("red", Color::Red),
+        ("green", Color::Green),
+        ("yellow", Color::Yellow),
+        ("blue", Color::Blue),
+        ("magenta", Color::Magenta),
+        ("cyan", Color::Cyan),
+        ("white", Color::White),
+    ];
+
+    /// The escape sequence that switches the terminal foreground to this
+    /// colour.
+    pub fn ansi(self) -> String {
+        match self {
+            Color::Rgb(r, g, b) => format!("\x1b[38;2;{r};{g};{b}m"),
+            named => {
+                let code = Color::NAMES
+                    .iter()
+                    .position(|&(_, c)| c == named)
+                    .expect("named colours are listed");
+                format!("\x1b[{}m", 30 + code)
+            }
+        }
+    }
+
+    /// The colour as a CSS value.
+    pub fn css(self) -> String {
+        match self {
+            Color::Rgb(r, g, b) => format!("#{r:02x}{g:02x}{b:02x}"),
+            named => Color::NAMES
+                .iter()
+                .find(|&&(_, c)| c == named)
+                .map(|(name, _)| name.to_string())
+                .expect("named colours are listed"),
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let text = s.trim().to_ascii_lowercase();
+        if let Some(&(_, color)) = Color::NAMES.iter().find(|(name, _)| *name == text) {
+            return Ok(color);
+        }
+        let hex = text
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+        match hex {
+            Some(hex) => {
+                let channel =
+                    |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).expect("hex digits");
+                Ok(Color::Rgb(channel(0), channel(2), channel(4)))
+            }
+            None => Err(Error::Invalid(format!(
+                "unknown colour `{s}` (expected a name such as red or cyan, or #rrggbb)"
+            ))),
+        }
+    }
+}
+
+/// Symbols in listings and exports, from the `unicode` (default) or
+/// `ascii` profile with per-glyph overrides.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Glyphs {
+    /// Marks an overdue task.
+    pub overdue: char,
+    /// Marks a pinned task.
+    pub pinned: String,
+    /// Stands for text left out of a snippet.
+    pub ellipsis: String,
+    /// Separates the parts of a label, such as id and priority in `graph`.
+    pub separator: String,
+    /// Draws the bars of `stats trend`.
+    pub bar: char,
+    /// The done and remaining parts of progress bars.
+    pub progress: [char; 2],
+    /// Heatmap cells from no tasks to the busiest day.
+    pub shades: [char; 5],
+    /// Shown before each priority, in [`Priority::ALL`] order; empty for
+    /// none.
+    pub priority: [String; 4],
+    /// Shown before each status, in [`Status::ALL`] order.
+    pub status: [String; 5],
+}
+
+impl Default for Glyphs {
+    fn default() -> Self {
+        Glyphs::unicode()
+    }
+}
+
+impl Glyphs {
+    pub fn unicode() -> Self {
+        Glyphs {
+            overdue: '!',
+            pinned: "📌".into(),
+            ellipsis: "…".into(),
+            separator: "·".into(),
+            bar: '#',
+            progress: ['█', '░'],
+            shades: ['·', '░', '▒', '▓', '█'],
+            priority: Default::default(),
+            status: Default::default(),
+        }
+    }
+
+    /// Plain ASCII for terminals and fonts without Unicode.
+    pub fn ascii() -> Self {
+        Glyphs {
+            pinned: "^".into(),
+            ellipsis: "...".into(),
+            separator: "-".into(),
+            progress: ['#', '.'],
+            shades: ['.', '-', '+', '*', '#'],
+            ..Glyphs::unicode()
+        }
+    }
+
+    /// `priority` with its glyph, if it has one.
+    pub fn priority_label(&self, priority: Priority) -> String {
+        let index = Priority::ALL.iter().position(|&p| p == priority);
+        label(
+            &self.priority[index.expect("listed priority")],
+            priority.as_str(),
+        )
+    }
+
+    /// `status` with its glyph, if it has one.
+    pub fn status_label(&self, status: Status) -> String {
+        let index = Status::ALL.iter().position(|&s| s == status);
+        label(&self.status[index.expect("listed status")], status.as_str())
+    }
+}
+
+fn label(glyph: &str, word: &str) -> String {
+    if glyph.is_empty() {
+        word.to_string()
+    } else {
+        format!("{glyph} {word}")
+    }
+}
+
+/// Work-in-progress limits; `None` means unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WipLimits {
+    pub in_progress: Option<usize>,
+    pub per_day: Option<usize>,
+}
+
+/// Settings that can differ between projects. `None` falls back to
+/// [`Config::defaults`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProjectSettings {
+    pub priority_inheritance: Option<PriorityInheritance>,
+}
+
+/// How a parent task's priority relates to its subtasks'.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PriorityInheritance {
+    /// Priorities are independent.
+    #[default]
+    None,
+    /// Changing a parent's priority changes its open subtasks' to match.
+    Propagate,
+    /// A parent counts as the highest priority among itself and its open
+    /// subtasks.
+    Max,
+}
+
+impl PriorityInheritance {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PriorityInheritance::None => "none",
+            PriorityInheritance::Propagate => "propagate",
+            PriorityInheritance::Max => "max",
+        }
+    }
+}
+
+impl FromStr for PriorityInheritance {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(PriorityInheritance::None),
+            "propagate" => Ok(PriorityInheritance::Propagate),
+            "max" | "max-of-children" => Ok(PriorityInheritance::Max),
+            _ => Err(Error::Invalid(format!(
+                "unknown priority inheritance `{s}` (expected none, propagate or max)"
+            ))),
+        }
+    }
+}
+
+impl Config {
+    /// The settings in effect for tasks in `project`.
+    pub fn project(&self, project: Option<&str>) -> ProjectSettings {
+        let specific = project
+            .and_then(|name| {
+                self.projects
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            })
+            .map(|(_, settings)| *settings)
+            .unwrap_or_default();
+        ProjectSettings {
+            priority_inheritance: specific
+                .priority_inheritance
+                .or(self.defaults.priority_inheritance),
+        }
+    }
+
+    pub fn priority_inheritance(&self, project: Option<&str>) -> PriorityInheritance {
+        self.project(project)
+            .priority_inheritance
+            .unwrap_or_default()
+    }
+
+    /// `$XDG_CONFIG_HOME/task-manager/config.toml`, falling back to
+    /// `~/.config/task-manager/config.toml`.
+    #[cfg(feature = "fs")]
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .filter(|dir| !dir.is_empty())
+                    .map(|home| PathBuf::from(home).join(".config"))
+            })?;
+        Some(base.join("task-manager").join(CONFIG_FILE))
+    }
+
+    /// Who is using the tool: `user.name`, else the login name from the
+    /// environment.
+    pub fn user_name(&self) -> String {
+        self.user
+            .clone()
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    /// Reads `path`; a missing file gives the defaults.
+    #[cfg(feature = "fs")]
+    pub fn load(path: &Path) -> Result<Self> {
+        Layers::new().read(path)?.config()
+    }
+
+    pub fn parse(input: &str) -> Result<Self> {
+        Config::from_value(&toml::parse(input)?)
+    }
+
+    /// The configuration in `root`, a parsed configuration file.
+    pub fn from_value(root: &Value) -> Result<Self> {
+        let mut config = Config::default();
+        for (section, value) in entries(root, "")? {
+            match section.as_str() {
+                "display" => {
+                    for (key, value) in entries(value, "display")? {
+                        match key.as_str() {
+                            "timezone" => {
+                                config.display.timezone = string(value, "display", key)?.parse()?
+                            }
+                            "relative_times" => {
+                                config.display.relative_times = boolean(value, "display", key)?
+                            }
+                            "brief" => config.display.brief = boolean(value, "display", key)?,
+                            "hide_private" => {
+                                config.display.hide_private = boolean(value, "display", key)?
+                            }
+                            "locale" => {
+                                config.display.locale = string(value, "display", key)?.parse()?
+                            }
+                            "week_start" => {
+                                let day = string(value, "display", key)?;
+                                config.display.week_start =
+                                    Weekday::parse(day.trim()).ok_or_else(|| {
+                                        Error::Invalid(format!(
+                                            "`display.week_start` must be a day of the week, found `{day}`"
+                                        ))
+                                    })?;
+                            }
+                            "date_format" | "time_format" => {
+                                let format = string(value, "display", key)?;
+                                dates::check_format(format)?;
+                                if key == "date_format" {
+                                    if format.trim().is_empty() {
+                                        return Err(Error::Invalid(
+                                            "`display.date_format` cannot be empty".into(),
+                                        ));
+                                    }
+                                    config.display.date_format = format.to_string();
+                                } else {
+                                    config.display.time_format = format.to_string();
+                                }
+                            }
+                            _ => return Err(unknown("display.", key)),
+                        }
+                    }
+                }
+                "user" => {
+                    for (key, value) in entries(value, "user")? {
+                        match key.as_str() {
+                            "name" => {
+                                let name = string(value, "user", key)?.trim();
+                                config.user = Some(name.to_string()).filter(|n| !n.is_empty());
+                            }
+                            _ => return Err(unknown("user.", key)),
+                        }
+                    }
+                }
+                "confirm" => {
+                    for (key, value) in entries(value, "confirm")? {
+                        let setting = match key.as_str() {
+                            "remove" => &mut config.confirm.remove,
+                            "overwrite" => &mut config.confirm.overwrite,
+                            "apply" => &mut config.confirm.apply,
+                            "groom" => &mut config.confirm.groom,
+                            _ => return Err(unknown("confirm.", key)),
+                        };
+                        *setting = boolean(value, "confirm", key)?;
+                    }
+                }
+                "autosave" => {
+                    for (key, value) in entries(value, "autosave")? {
+                        match key.as_str() {
+                            "strategy" => {
+                                config.autosave.strategy =
+                                    string(value, "autosave", key)?.parse()?
+                            }
+                            "interval" => {
+                                config.autosave.interval =
+                                    duration::parse(string(value, "autosave", key)?)?.max(1)
+                            }
+                            _ => return Err(unknown("autosave.", key)),
+                        }
+                    }
+                }
+                "done" => {
+                    for (key, value) in entries(value, "done")? {
+                        match key.as_str() {
+                            "follow_up" => config.done.follow_up = boolean(value, "done", key)?,
+                            _ => return Err(unknown("done.", key)),
+                        }
+                    }
+                }
+                "groom" => {
+                    for (key, value) in entries(value, "groom")? {
+                        match key.as_str() {
+                            "after" => {
+                                let text = string(value, "groom", key)?;
+                                config.groom.after = if text.eq_ignore_ascii_case("none") {
+                                    None
+                                } else {
+                                    Some(duration::parse(text)?)
+                                };
+                            }
+                            "action" => {
+                                config.groom.action = string(value, "groom", key)?.parse()?
+                            }
+                            _ => return Err(unknown("groom.", key)),
+                        }
+                    }
+                }
+                "daemon" => {
+                    for (key, value) in entries(value, "daemon")? {
+                        match key.as_str() {
+                            "interval" => {
+                                config.daemon.interval =
+                                    count(value, "daemon.interval")?.max(1) as u64
+                            }
+                            "backup_every" => {
+                                let text = string(value, "daemon", key)?;
+                                config.daemon.backup_every = if text.eq_ignore_ascii_case("none") {
+                                    None
+                                } else {
+                                    Some(duration::parse(text)?)
+                                };
+                            }
+                            "notify" => {
+                                let command = string(value, "daemon", key)?.trim();
+                                config.daemon.notify =
+                                    Some(command.to_string()).filter(|c| !c.is_empty());
+                            }
+                            "bell" => config.daemon.bell = boolean(value, "daemon", key)?,
+                            "sound" => {
+                                let path = string(value, "daemon", key)?.trim();
+                                config.daemon.sound =
+                                    Some(PathBuf::from(path)).filter(|_| !path.is_empty());
+                            }
+                            "sound_player" => {
+                                let player = string(value, "daemon", key)?.trim();
+                                if player.is_empty() {
+                                    return Err(Error::Invalid(
+                                        "`daemon.sound_player` cannot be empty".into(),
+                                    ));
+                                }
+                                config.daemon.sound_player = player.to_string();
+                            }
+                            _ => return Err(unknown("daemon.", key)),
+                        }
+                    }
+                }
+                "backup" => config.backup = backup(value)?,
+                "jira" => {
+                    for (key, value) in entries(value, "jira")? {
+                        let text = string(value, "jira", key)?.trim();
+                        let optional = Some(text.to_string()).filter(|t| !t.is_empty());
+                        match key.as_str() {
+                            "url" => {
+                                if !(text.is_empty()
+                                    || text.starts_with("http://")
+                                    || text.starts_with("https://"))
+                                {
+                                    return Err(Error::Invalid(format!(
+                                        "`jira.url` must start with http:// or https://, not `{text}`"
+                                    )));
+                                }
+                                config.jira.url =
+                                    optional.map(|u| u.trim_end_matches('/').to_string())
+                            }
+                            "email" => config.jira.email = optional,
+                            "token" => config.jira.token = optional,
+                            _ => return Err(unknown("jira.", key)),
+                        }
+                    }
+                }
+                "notion" => config.notion = notion(value)?,
+                "google" => {
+                    for (key, value) in entries(value, "google")? {
+                        let text = string(value, "google", key)?.trim();
+                        let optional = Some(text.to_string()).filter(|t| !t.is_empty());
+                        match key.as_str() {
+                            "client_id" => config.google.client_id = optional,
+                            "client_secret" => config.google.client_secret = optional,
+                            _ => return Err(unknown("google.", key)),
+                        }
+                    }
+                }
+                "encryption" => {
+                    for (key, value) in entries(value, "encryption")? {
+                        match key.as_str() {
+                            "fields" => {
+                                config.encryption.fields = boolean(value, "encryption", key)?
+                            }
+                            "passphrase" => {
+                                let passphrase = string(value, "encryption", key)?;
+                                if passphrase.is_empty() {
+                                    return Err(Error::Invalid(
+                                        "`encryption.passphrase` cannot be empty".into(),
+                                    ));
+                                }
+                                config.encryption.passphrase = Some(passphrase.to_string());
+                            }
+                            _ => return Err(unknown("encryption.", key)),
+                        }
+                    }
+                }
+                "git" => {
+                    for (key, value) in entries(value, "git")? {
+                        match key.as_str() {
+                            "auto_commit" => config.git.auto_commit = boolean(value, "git", key)?,
+                            "auto_push" => config.git.auto_push = boolean(value, "git", key)?,
+                            "remote" => {
+                                let remote = string(value, "git", key)?.trim();
+                                config.git.remote =
+                                    Some(remote.to_string()).filter(|r| !r.is_empty());
+                            }
+                            _ => return Err(unknown("git.", key)),
+                        }
+                    }
+                }
+                "links" => {
+                    for (key, value) in entries(value, "links")? {
+                        match key.as_str() {
+                            "hyperlinks" => {
+                                config.links.hyperlinks = string(value, "links", key)?.parse()?
+                            }
+                            "jira" => {
+                                let base = string(value, "links", key)?.trim();
+                                if !(base.is_empty()
+                                    || base.starts_with("http://")
+                                    || base.starts_with("https://"))
+                                {
+                                    return Err(Error::Invalid(format!(
+                                        "`links.jira` must start with http:// or https://, not `{base}`"
+                                    )));
+                                }
+                                config.links.jira =
+                                    Some(base.to_string()).filter(|b| !b.is_empty());
+                            }
+                            _ => return Err(unknown("links.", key)),
+                        }
+                    }
+                }
+                "mail" => {
+                    for (key, value) in entries(value, "mail")? {
+                        let text = string(value, "mail", key)?.trim();
+                        let optional = Some(text.to_string()).filter(|t| !t.is_empty());
+                        match key.as_str() {
+                            "maildir" => {
+                                config.mail.maildir =
+                                    Some(PathBuf::from(text)).filter(|_| !text.is_empty());
+                            }
+                            "imap" => {
+                                if !(text.is_empty()
+                                    || text.starts_with("imaps://")
+                                    || text.starts_with("imap://"))
+                                {
+                                    return Err(Error::Invalid(format!(
+                                        "`mail.imap` must start with imaps:// or imap://, not `{text}`"
+                                    )));
+                                }
+                                config.mail.imap = optional;
+                            }
+                            "user" => config.mail.user = optional,
+                            "password" => config.mail.password = optional,
+                            "move_to" => config.mail.move_to = optional,
+                            _ => return Err(unknown("mail.", key)),
+                        }
+                    }
+                    if config.mail.maildir.is_some() && config.mail.imap.is_some() {
+                        return Err(Error::Invalid(
+                            "set either `mail.maildir` or `mail.imap`, not both".into(),
+                        ));
+                    }
+                }
+                "printer" => {
+                    for (key, value) in entries(value, "printer")? {
+                        match key.as_str() {
+                            "device" => {
+                                let device = string(value, "printer", key)?.trim();
+                                config.printer.device =
+                                    Some(device.to_string()).filter(|d| !d.is_empty());
+                            }
+                            "width" => config.printer.width = Some(count(value, "printer.width")?),
+                            _ => return Err(unknown("printer.", key)),
+                        }
+                    }
+                }
+                "priorities" => {
+                    for (key, value) in entries(value, "priorities")? {
+                        let level: Priority =
+                            key.parse().map_err(|_| unknown("priorities.", key))?;
+                        let share = count(value, &format!("priorities.{key}"))?;
+                        for entry in &mut config.priorities.shares {
+                            if entry.0 == level {
+                                entry.1 = share;
+                            }
+                        }
+                    }
+                    if config
+                        .priorities
+                        .shares
+                        .iter()
+                        .all(|&(_, share)| share == 0)
+                    {
+                        return Err(Error::Invalid(
+                            "`priorities` needs a share above 0 for some level".into(),
+                        ));
+                    }
+                }
+                "theme" => {
+                    for (key, value) in entries(value, "theme")? {
+                        if key == "name" {
+                            config.theme.name = string(value, "theme", key)?.parse()?;
+                            continue;
+                        }
+                        let name = key.replace('_', "-");
+                        if !ThemeName::KEYS.contains(&name.as_str()) {
+                            return Err(unknown("theme.", key));
+                        }
+                        let color = string(value, "theme", key)?.parse()?;
+                        config.theme.colors.insert(name, color);
+                    }
+                }
+                "tags" => {
+                    for (name, value) in entries(value, "tags")? {
+                        let style = tag_style(value, &format!("tags.{name}"))?;
+                        config.tags.0.insert(name.clone(), style);
+                    }
+                }
+                "glyphs" => config.glyphs = glyphs(value)?,
+                "wip" => {
+                    for (key, value) in entries(value, "wip")? {
+                        match key.as_str() {
+                            "in_progress" => {
+                                config.wip.in_progress = Some(count(value, "wip.in_progress")?)
+                            }
+                            "per_day" => config.wip.per_day = Some(count(value, "wip.per_day")?),
+                            _ => return Err(unknown("wip.", key)),
+                        }
+                    }
+                }
+                "defaults" => config.defaults = project_settings(value, "defaults")?,
+                "rules" => config.rules = rules(value)?,
+                "sources" => config.sources = sources(value)?,
+                "workflow" => config.workflow = workflow(value)?,
+                "projects" => {
+                    for (name, value) in entries(value, "projects")? {
+                        let settings = project_settings(value, &format!("projects.{name}"))?;
+                        config.projects.insert(name.clone(), settings);
+                    }
+                }
+                _ => return Err(unknown("", section)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+fn rules(value: &Value) -> Result<Vec<Rule>> {
+    let Value::Array(tables) = value else {
+        return Err(Error::Invalid(
+            "`rules` must be an array of tables, written `[[rules]]`".into(),
+        ));
+    };
+    let mut rules = Vec::new();
+    for table in tables {
+        let (mut name, mut when, mut set) = (None, None, Vec::new());
+        for (key, value) in entries(table, "rules")? {
+            match key.as_str() {
+                "name" => name = Some(string(value, "rules", key)?.to_string()),
+                "when" => when = Some(string(value, "rules", key)?.to_string()),
+                "set" => {
+                    let items = value.as_array().ok_or_else(|| {
+                        Error::Invalid("`rules.set` must be an array of strings".into())
+                    })?;
+                    for item in items {
+                        set.push(string(item, "rules", key)?.to_string());
+                    }
+                }
+                _ => return Err(unknown("rules.", key)),
+            }
+        }
+        let when = when.ok_or_else(|| Error::Invalid("every rule needs a `when`".into()))?;
+        rules.push(Rule::new(name, when, set)?);
+    }
+    Ok(rules)
+}
+
+fn workflow(value: &Value) -> Result<Workflow> {
+    let mut workflow = Workflow::default();
+    for (key, value) in entries(value, "workflow")? {
+        let list = match key.as_str() {
+            "forbid" => &mut workflow.forbid,
+            "require_note" => &mut workflow.require_note,
+            _ => return Err(unknown("workflow.", key)),
+        };
+        let items = value.as_array().ok_or_else(|| {
+            Error::Invalid(format!("`workflow.{key}` must be an array of strings"))
+        })?;
+        for item in items {
+            list.push(string(item, "workflow", key)?.parse()?);
+        }
+    }
+    Ok(workflow)
+}
+
+fn sources(value: &Value) -> Result<Vec<Source>> {
+    let Value::Array(tables) = value else {
+        return Err(Error::Invalid(
+            "`sources` must be an array of tables, written `[[sources]]`".into(),
+        ));
+    };
+    let mut sources: Vec<Source> = Vec::new();
+    for table in tables {
+        let (mut name, mut location) = (None, None);
+        for (key, value) in entries(table, "sources")? {
+            let text = string(value, "sources", key)?.trim();
+            let found = match key.as_str() {
+                "name" => {
+                    name = Some(text.to_string());
+                    continue;
+                }
+                "path" => SourceLocation::Path(PathBuf::from(text)),
+                "url" if text.starts_with("http://") || text.starts_with("https://") => {
+                    SourceLocation::Url(text.to_string())
+                }
+                "url" => {
+                    return Err(Error::Invalid(format!(
+                        "`sources.url` must start with http:// or https://, not `{text}`"
+                    )))
+                }
+                _ => return Err(unknown("sources.", key)),
+            };
+            if location.replace(found).is_some() {
+                return Err(Error::Invalid(
+                    "a source has either a `path` or a `url`, not both".into(),
+                ));
+            }
+        }
+        let name = name
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| Error::Invalid("every source needs a `name`".into()))?;
+        let location = location
+            .ok_or_else(|| Error::Invalid(format!("source `{name}` needs a `path` or a `url`")))?;
+        if sources.iter().any(|source| source.name == name) {
+            return Err(Error::Invalid(format!("source `{name}` is defined twice")));
+        }
+        sources.push(Source { name, location });
+    }
+    Ok(sources)
+}
+
+fn project_settings(value: &Value, name: &str) -> Result<ProjectSettings> {
+    let mut settings = ProjectSettings::default();
+    for (key, value) in entries(value, name)? {
+        match key.as_str() {
+            "priority_inheritance" => {
+                settings.priority_inheritance = Some(string(value, name, key)?.parse()?)
+            }
+            _ => return Err(unknown(&format!("{name}."), key)),
+        }
+    }
+    Ok(settings)
+}
+
+fn notion(value: &Value) -> Result<NotionSettings> {
+    let mut notion = NotionSettings::default();
+    for (key, value) in entries(value, "notion")? {
+        if key == "columns" {
+            notion.columns = notion_columns(value)?;
+            continue;
+        }
+        let text = string(value, "notion", key)?.trim();
+        let optional = Some(text.to_string()).filter(|t| !t.is_empty());
+        match key.as_str() {
+            "database" => notion.database = optional.map(|d| d.replace('-', "")),
+            "token" => notion.token = optional,
+            "done" if text.is_empty() => {
+                return Err(Error::Invalid("`notion.done` cannot be empty".into()))
+            }
+            "done" => notion.done = text.to_string(),
+            _ => return Err(unknown("notion.", key)),
+        }
+    }
+    Ok(notion)
+}
+
+fn notion_columns(value: &Value) -> Result<NotionColumns> {
+    let mut columns = NotionColumns::default();
+    for (key, value) in entries(value, "notion.columns")? {
+        let text = string(value, "notion.columns", key)?.trim();
+        let optional = Some(text.to_string()).filter(|t| !t.is_empty());
+        match key.as_str() {
+            "name" | "status" if text.is_empty() => {
+                return Err(Error::Invalid(format!(
+                    "`notion.columns.{key}` cannot be empty"
+                )))
+            }
+            "name" => columns.name = text.to_string(),
+            "status" => columns.status = text.to_string(),
+            "due" => columns.due = optional,
+            "priority" => columns.priority = optional,
+            "description" => columns.description = optional,
+            _ => return Err(unknown("notion.columns.", key)),
+        }
+    }
+    Ok(columns)
+}
+
+fn backup(value: &Value) -> Result<BackupSettings> {
+    let mut backup = BackupSettings::default();
+    for (key, value) in entries(value, "backup")? {
+        let text = string(value, "backup", key)?.trim();
+        let optional = Some(text.to_string()).filter(|t| !t.is_empty());
+        match key.as_str() {
+            "endpoint" => backup.endpoint = optional.map(|e| e.trim_end_matches('/').to_string()),
+            "bucket" => backup.bucket = optional,
+            "access_key" => backup.access_key = optional,
+            "secret_key" => backup.secret_key = optional,
+            "passphrase" => backup.passphrase = optional,
+            "region" | "object" if text.is_empty() => {
+                return Err(Error::Invalid(format!("`backup.{key}` cannot be empty")))
+            }
+            "region" => backup.region = text.to_string(),
+            "object" => backup.object = text.trim_start_matches('/').to_string(),
+            _ => return Err(unknown("backup.", key)),
+        }
+    }
+    Ok(backup)
+}
+
+fn glyphs(value: &Value) -> Result<Glyphs> {
+    let entries = entries(value, "glyphs")?;
+    // The profile is the base the other keys change, wherever it is listed.
+    let mut glyphs = match entries.iter().find(|(key, _)| key == "profile") {
+        None => Glyphs::default(),
+        Some((key, value)) => match string(value, "glyphs", key)?.trim() {
+            "unicode" => Glyphs::unicode(),
+            "ascii" => Glyphs::ascii(),
+            other => {
+                return Err(Error::Invalid(format!(
+                    "unknown glyph profile `{other}` (expected unicode or ascii)"
+                )))
+            }
+        },
+    };
+    let character = |value: &Value, key: &str| {
+        let mut chars = string(value, "glyphs", key)?.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(Error::Invalid(format!(
+                "`glyphs.{key}` must be a single character"
+            ))),
+        }
+    };
+    for (key, value) in entries {
+        if let Some(index) = Priority::ALL.iter().position(|p| p.as_str() == key) {
+            glyphs.priority[index] = string(value, "glyphs", key)?.trim().to_string();
+            continue;
+        }
+        if let Some(index) = Status::ALL
+            .iter()
+            .position(|s| s.as_str() == key.replace('_', "-"))
+        {
+            glyphs.status[index] = string(value, "glyphs", key)?.trim().to_string();
+            continue;
+        }
+        match key.as_str() {
+            "profile" => {}
+            "overdue" => glyphs.overdue = character(value, key)?,
+            "bar" => glyphs.bar = character(value, key)?,
+            "pinned" => glyphs.pinned = string(value, "glyphs", key)?.trim().to_string(),
+            "ellipsis" => glyphs.ellipsis = string(value, "glyphs", key)?.to_string(),
+            "separator" => glyphs.separator = string(value, "glyphs", key)?.to_string(),
+            "progress" => {
+                let progress: Vec<char> = string(value, "glyphs", key)?.chars().collect();
+                glyphs.progress = progress.try_into().map_err(|_| {
+                    Error::Invalid(
+                        "`glyphs.progress` must be two characters, done then remaining".into(),
+                    )
+                })?;
+            }
+            "shades" => {
+                let shades: Vec<char> = string(value, "glyphs", key)?.chars().collect();
+                glyphs.shades = shades.try_into().map_err(|_| {
+                    Error::Invalid(
+                        "`glyphs.shades` must be five characters, from none to most".into(),
+                    )
+                })?;
+            }
+            _ => return Err(unknown("glyphs.", key)),
+        }
+    }
+    Ok(glyphs)
+}
+
+fn tag_style(value: &Value, name: &str) -> Result<TagStyle> {
+    let mut style = TagStyle::default();
+    for (key, value) in entries(value, name)? {
+        match key.as_str() {
+            "color" => style.color = Some(string(value, name, key)?.parse()?),
+            "icon" => {
+                let icon = string(value, name, key)?.trim();
+                style.icon = Some(icon.to_string()).filter(|i| !i.is_empty());
+            }
+            _ => return Err(unknown(&format!("{name}."), key)),
+        }
+    }
+    Ok(style)
+}
+
+fn string<'a>(value: &'a Value, section: &str, key: &str) -> Result<&'a str> {
+    value
+        .as_str()
+        .ok_or_else(|| Error::Invalid(format!("`{section}.{key}` must be a string")))
+}
+
+fn boolean(value: &Value, section: &str, key: &str) -> Result<bool> {
+    value
+        .as_bool()
+        .ok_or_else(|| Error::Invalid(format!("`{section}.{key}` must be true or false")))
+}
+
+fn entries<'a>(value: &'a Value, name: &str) -> Result<&'a [(String, Value)]> {
+    match value {
+        Value::Object(entries) => Ok(entries),
+        _ => Err(Error::Invalid(format!("`{name}` must be a table"))),
+    }
+}
+
+fn unknown(prefix: &str, key: &str) -> Error {
+    Error::Invalid(format!("unknown setting `{prefix}{key}`"))
+}
+
+fn count(value: &Value, name: &str) -> Result<usize> {
+    value
+        .as_i64()
+        .filter(|&n| n >= 0)
+        .map(|n| n as usize)
+        .ok_or_else(|| Error::Invalid(format!("`{name}` must be a non-negative integer")))
+}
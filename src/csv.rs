@@ -0,0 +1,146 @@
+//! RFC 4180 CSV reading and writing.
+
+use std::io::{self, Write};
+
+use crate::error::{Error, Result};
+
+/// Parses CSV text into rows of fields. Quoted fields may contain commas,
+/// doubled quotes and line breaks. A leading byte-order mark is ignored.
+pub fn parse(input: &str) -> Result<Vec<Vec<String>>> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut chars = input.chars().peekable();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut quote_line = 1;
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push('\n');
+                }
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => {
+                in_quotes = true;
+                quote_line = line;
+            }
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                line += 1;
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(Error::Invalid(format!(
+            "CSV line {quote_line}: quoted field is never closed"
+        )));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Rows keyed by the header line, with case-insensitive column lookup.
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut rows = parse(input)?.into_iter();
+        let headers = rows
+            .next()
+            .ok_or_else(|| Error::Invalid("CSV file is empty".into()))?
+            .into_iter()
+            .map(|h| h.trim().to_string())
+            .collect();
+        Ok(Table {
+            headers,
+            rows: rows.filter(|r| r.iter().any(|f| !f.is_empty())).collect(),
+        })
+    }
+
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.headers
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(name))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = Row<'_>> {
+        self.rows.iter().map(move |fields| Row {
+            table: self,
+            fields,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+pub struct Row<'a> {
+    table: &'a Table,
+    fields: &'a [String],
+}
+
+impl Row<'_> {
+    /// The trimmed value of column `name`, or `None` when the column is
+    /// missing or the cell is blank.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        let index = self.table.column(name)?;
+        self.fields
+            .get(index)
+            .map(|f| f.trim())
+            .filter(|f| !f.is_empty())
+    }
+
+    /// The value of column `name` as written, or `None` when the column is
+    /// missing.
+    pub fn raw(&self, name: &str) -> Option<&str> {
+        let index = self.table.column(name)?;
+        Some(self.fields.get(index).map_or("", String::as_str))
+    }
+}
+
+/// Writes one CSV record terminated by CRLF, quoting fields as needed.
+pub fn write_row<W: Write, S: AsRef<str>>(out: &mut W, fields: &[S]) -> io::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        let field = field.as_ref();
+        if field.contains([',', '"', '\n', '\r']) || field.starts_with(' ') {
+            write!(out, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            out.write_all(field.as_bytes())?;
+        }
+    }
+    out.write_all(b"\r\n")
+}
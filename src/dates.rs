@@ -0,0 +1,1115 @@
+//! Calendar arithmetic, formatting and parsing of user-entered dates.
+//!
+//! Timestamps are stored as seconds since the Unix epoch in UTC. People see
+//! them in the display time zone, a fixed offset set once at start-up with
+//! [`set_display_offset`]; calendar days (`due<friday`, date-only input, the
+//! daily stats) follow the same offset. Likewise the format they are shown
+//! in is set once with [`set_display_format`], the language of month and
+//! day names with [`set_display_locale`] and the first day of the week with
+//! [`set_week_start`].
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicI64, AtomicU32, AtomicUsize, Ordering};
+use std::sync::{PoisonError, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::duration;
+use crate::error::{Error, Result};
+
+/// Date part of the format used when rendering timestamps for people,
+/// unless configured otherwise.
+pub const DATE_FORMAT: &str = "%d-%m-%Y";
+
+/// Time part of the default format; it follows the date after a space.
+pub const TIME_FORMAT: &str = "%H:%M:%S";
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+static DISPLAY_OFFSET: AtomicI64 = AtomicI64::new(0);
+
+/// The offset timestamps are shown in; UTC unless configured.
+pub fn display_offset() -> UtcOffset {
+    UtcOffset(DISPLAY_OFFSET.load(Ordering::Relaxed))
+}
+
+pub fn set_display_offset(offset: UtcOffset) {
+    DISPLAY_OFFSET.store(offset.0, Ordering::Relaxed);
+}
+
+/// The configured date and time formats; `None` until set.
+static DISPLAY_FORMAT: RwLock<Option<(String, String)>> = RwLock::new(None);
+
+/// Sets the strftime patterns timestamps are shown with, as checked by
+/// [`check_format`]. An empty `time` shows dates alone.
+pub fn set_display_format(date: &str, time: &str) {
+    let mut format = DISPLAY_FORMAT
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    *format = Some((date.to_string(), time.to_string()));
+}
+
+fn display_format<T>(f: impl FnOnce(&str, &str) -> T) -> T {
+    let format = DISPLAY_FORMAT
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    match &*format {
+        Some((date, time)) => f(date, time),
+        None => f(DATE_FORMAT, TIME_FORMAT),
+    }
+}
+
+/// Milliseconds since the Unix epoch, when the embedder keeps the time.
+static CLOCK: RwLock<Option<fn() -> i64>> = RwLock::new(None);
+
+/// Makes [`Timestamp::now`] and new ids read `clock`, in milliseconds since
+/// the Unix epoch, instead of the system clock. Targets without one, such as
+/// `wasm32-unknown-unknown`, must set it (to `Date.now()`, say) first.
+pub fn set_clock(clock: fn() -> i64) {
+    *CLOCK.write().unwrap_or_else(PoisonError::into_inner) = Some(clock);
+}
+
+/// Nanoseconds since the Unix epoch by [`set_clock`]'s clock, else the
+/// system's.
+pub(crate) fn unix_nanos() -> i128 {
+    match *CLOCK.read().unwrap_or_else(PoisonError::into_inner) {
+        Some(clock) => i128::from(clock()) * 1_000_000,
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as i128),
+    }
+}
+
+static WEEK_START: AtomicU32 = AtomicU32::new(0);
+
+/// The day weeks start on, for calendars, `this-week` and `@weekly`;
+/// Monday unless configured.
+pub fn week_start() -> Weekday {
+    Weekday::from_index(WEEK_START.load(Ordering::Relaxed))
+}
+
+pub fn set_week_start(day: Weekday) {
+    WEEK_START.store(day.index(), Ordering::Relaxed);
+}
+
+/// Index into [`LOCALES`] of the language names are shown in.
+static DISPLAY_LOCALE: AtomicUsize = AtomicUsize::new(0);
+
+pub fn display_locale() -> Locale {
+    LOCALES[DISPLAY_LOCALE.load(Ordering::Relaxed)]
+}
+
+pub fn set_display_locale(locale: Locale) {
+    let index = LOCALES
+        .iter()
+        .position(|l| l.code == locale.code)
+        .unwrap_or(0);
+    DISPLAY_LOCALE.store(index, Ordering::Relaxed);
+}
+
+/// Rejects a pattern with a conversion [`Timestamp::format`] does not
+/// support, which would otherwise show up literally in every date.
+pub fn check_format(pattern: &str) -> Result<()> {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        match chars.next() {
+            Some(spec) if "YymdeHMSjaAbBFTRZ%".contains(spec) => {}
+            Some(spec) => {
+                return Err(Error::Invalid(format!(
+                    "unsupported `%{spec}` in date format `{pattern}`"
+                )))
+            }
+            None => {
+                return Err(Error::Invalid(format!(
+                    "date format `{pattern}` ends with a lone `%`"
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A fixed offset from UTC, in seconds east of Greenwich.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct UtcOffset(i64);
+
+impl UtcOffset {
+    pub const UTC: UtcOffset = UtcOffset(0);
+
+    pub fn secs(self) -> i64 {
+        self.0
+    }
+}
+
+impl fmt::Display for UtcOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 == 0 {
+            return f.pad("UTC");
+        }
+        let sign = if self.0 < 0 { '-' } else { '+' };
+        let minutes = self.0.abs() / 60;
+        f.pad(&format!("{sign}{:02}:{:02}", minutes / 60, minutes % 60))
+    }
+}
+
+impl FromStr for UtcOffset {
+    type Err = Error;
+
+    /// Accepts `UTC`, `Z` and offsets written `+02:00`, `+0200` or `+2`,
+    /// optionally after `UTC` (`UTC-05:00`).
+    fn from_str(s: &str) -> Result<Self> {
+        let text = s.trim();
+        let invalid = || {
+            Error::Invalid(format!(
+                "invalid time zone `{text}` (expected UTC or a fixed offset such as +02:00; zone names are not supported)"
+            ))
+        };
+        let upper = text.to_ascii_uppercase();
+        let rest = upper
+            .strip_prefix("UTC")
+            .or_else(|| upper.strip_prefix("GMT"))
+            .unwrap_or(&upper);
+        if rest.is_empty() || rest == "Z" {
+            return Ok(UtcOffset::UTC);
+        }
+        let digits = rest.get(1..).ok_or_else(invalid)?;
+        let (hours, minutes) = match digits.split_once(':') {
+            Some((h, m)) => (h, m),
+            None if digits.len() == 4 => digits.split_at(2),
+            None => (digits, "0"),
+        };
+        let hours: i64 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i64 = minutes.parse().map_err(|_| invalid())?;
+        if hours > 14 || minutes > 59 {
+            return Err(invalid());
+        }
+        let secs = hours * 3600 + minutes * 60;
+        match rest.as_bytes()[0] {
+            b'+' => Ok(UtcOffset(secs)),
+            b'-' => Ok(UtcOffset(-secs)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    pub fn now() -> Self {
+        Timestamp(unix_nanos().div_euclid(1_000_000_000) as i64)
+    }
+
+    pub const fn from_secs(secs: i64) -> Self {
+        Timestamp(secs)
+    }
+
+    pub const fn secs(self) -> i64 {
+        self.0
+    }
+
+    pub fn add_secs(self, secs: i64) -> Self {
+        Timestamp(self.0 + secs)
+    }
+
+    pub fn to_datetime(self) -> DateTime {
+        let days = self.0.div_euclid(SECONDS_PER_DAY);
+        let rem = self.0.rem_euclid(SECONDS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        DateTime {
+            year,
+            month,
+            day,
+            hour: (rem / 3600) as u32,
+            minute: (rem % 3600 / 60) as u32,
+            second: (rem % 60) as u32,
+        }
+    }
+
+    /// The date and time in the display time zone.
+    pub fn to_local(self) -> DateTime {
+        self.add_secs(display_offset().0).to_datetime()
+    }
+
+    /// Days since the epoch in the display time zone, used to compare
+    /// calendar days.
+    pub fn day_number(self) -> i64 {
+        (self.0 + display_offset().0).div_euclid(SECONDS_PER_DAY)
+    }
+
+    /// Local midnight at the start of this timestamp's day.
+    pub fn start_of_day(self) -> Self {
+        Timestamp(self.day_number() * SECONDS_PER_DAY - display_offset().0)
+    }
+
+    pub fn end_of_day(self) -> Self {
+        self.start_of_day().add_secs(SECONDS_PER_DAY - 1)
+    }
+
+    pub fn weekday(self) -> Weekday {
+        Weekday::from_index((self.day_number() + 3).rem_euclid(7) as u32)
+    }
+
+    /// Renders the timestamp in UTC using a strftime-style pattern, for
+    /// machine-readable output. Names are always English.
+    ///
+    /// Supported: `%Y %y %m %d %e %H %M %S %j %a %A %b %B %F %T %R %Z %%`.
+    pub fn format(self, pattern: &str) -> String {
+        self.format_in(pattern, UtcOffset::UTC, Locale::ENGLISH)
+    }
+
+    /// Renders the timestamp in the display time zone and language, for
+    /// people.
+    pub fn format_local(self, pattern: &str) -> String {
+        self.format_in(pattern, display_offset(), display_locale())
+    }
+
+    /// The date alone, in the display format and time zone.
+    pub fn to_local_date(self) -> String {
+        display_format(|date, _| self.format_local(date))
+    }
+
+    fn format_in(self, pattern: &str, offset: UtcOffset, locale: Locale) -> String {
+        let shifted = self.add_secs(offset.0);
+        let dt = shifted.to_datetime();
+        let weekday =
+            Weekday::from_index((shifted.0.div_euclid(SECONDS_PER_DAY) + 3).rem_euclid(7) as u32);
+        let mut out = String::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => out.push_str(&dt.year.to_string()),
+                Some('y') => out.push_str(&format!("{:02}", dt.year.rem_euclid(100))),
+                Some('m') => out.push_str(&format!("{:02}", dt.month)),
+                Some('d') => out.push_str(&format!("{:02}", dt.day)),
+                Some('e') => out.push_str(&format!("{:>2}", dt.day)),
+                Some('H') => out.push_str(&format!("{:02}", dt.hour)),
+                Some('M') => out.push_str(&format!("{:02}", dt.minute)),
+                Some('S') => out.push_str(&format!("{:02}", dt.second)),
+                Some('j') => out.push_str(&format!("{:03}", dt.day_of_year())),
+                Some('a') => out.push_str(&abbreviate(locale.weekday(weekday))),
+                Some('A') => out.push_str(locale.weekday(weekday)),
+                Some('b') => out.push_str(&abbreviate(locale.month(dt.month))),
+                Some('B') => out.push_str(locale.month(dt.month)),
+                Some('F') => out.push_str(&self.format_in("%Y-%m-%d", offset, locale)),
+                Some('T') => out.push_str(&self.format_in("%H:%M:%S", offset, locale)),
+                Some('R') => out.push_str(&self.format_in("%H:%M", offset, locale)),
+                Some('Z') => out.push_str(&offset.to_string()),
+                Some('%') => out.push('%'),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+                None => out.push('%'),
+            }
+        }
+        out
+    }
+
+    /// Machine-readable RFC 3339 form, always in UTC, with the year in four
+    /// digits as [`Timestamp::parse_rfc3339`] reads it back.
+    pub fn to_rfc3339(self) -> String {
+        let dt = self.to_datetime();
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second
+        )
+    }
+
+    /// Whether the year in UTC has four digits, so that the data file can
+    /// hold it.
+    pub fn is_storable(self) -> bool {
+        (0..=9999).contains(&self.to_datetime().year)
+    }
+
+    /// Parses `YYYY-MM-DDTHH:MM:SS` followed by `Z` or a `±HH:MM` offset,
+    /// as long as the instant is one the data file can hold.
+    pub fn parse_rfc3339(input: &str) -> Result<Self> {
+        let invalid = || Error::Invalid(format!("invalid RFC 3339 timestamp `{input}`"));
+        if input.len() < 19 || !input.is_char_boundary(19) {
+            return Err(invalid());
+        }
+        let (head, zone) = input.split_at(19);
+        let head = head.replacen('T', " ", 1).replacen('t', " ", 1);
+        let base = parse_date_time_in(&head, UtcOffset::UTC).ok_or_else(invalid)?;
+        // Fractional seconds are accepted but dropped.
+        let zone = match zone.strip_prefix('.') {
+            Some(rest) => rest.trim_start_matches(|c: char| c.is_ascii_digit()),
+            None => zone,
+        };
+        let offset = match zone {
+            "Z" | "z" => 0,
+            _ => parse_offset(zone).ok_or_else(invalid)?,
+        };
+        let ts = base.add_secs(-offset);
+        if !ts.is_storable() {
+            return Err(Error::Invalid(format!(
+                "`{input}` is outside the years 0000 to 9999 in UTC"
+            )));
+        }
+        Ok(ts)
+    }
+
+    /// How far this is from `now` in words: `in 3 hours`, `2 days ago` or
+    /// `just now`.
+    pub fn relative_to(self, now: Timestamp) -> String {
+        let diff = self.0 - now.0;
+        if diff.abs() < 60 {
+            "just now".to_string()
+        } else if diff > 0 {
+            format!("in {}", duration::humanize(diff))
+        } else {
+            format!("{} ago", duration::humanize(diff))
+        }
+    }
+
+    /// Reads a timestamp from the data file: RFC 3339, or for files written
+    /// before timestamps were stored in UTC, a date and time without an
+    /// offset, taken as local to the display time zone.
+    pub fn parse_stored(input: &str) -> Result<Self> {
+        Timestamp::parse_rfc3339(input).or_else(|err| {
+            let naive = input.trim().replacen('T', " ", 1);
+            let naive = match naive.rsplit_once('.') {
+                Some((head, fraction))
+                    if head.contains(':') && fraction.chars().all(|c| c.is_ascii_digit()) =>
+                {
+                    head
+                }
+                _ => &naive,
+            };
+            match naive.split_once(' ') {
+                Some((_, time)) if time.contains(':') => parse_date_time(naive).ok_or(err),
+                _ => Err(err),
+            }
+        })
+    }
+}
+
+/// The date and time in the display format and time zone.
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = display_format(|date, time| match time {
+            "" => self.format_local(date),
+            time => self.format_local(&format!("{date} {time}")),
+        });
+        f.pad(&text)
+    }
+}
+
+/// An RFC 3339 `±HH:MM` offset in seconds, hours 00 to 23 and minutes 00
+/// to 59.
+fn parse_offset(zone: &str) -> Option<i64> {
+    let sign = match zone.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let (hours, minutes) = zone[1..].split_once(':')?;
+    let two_digits = |text: &str| {
+        (text.len() == 2 && text.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| text.parse::<i64>().ok())
+            .flatten()
+    };
+    let hours = two_digits(hours).filter(|&h| h <= 23)?;
+    let minutes = two_digits(minutes).filter(|&m| m <= 59)?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// A broken-down date and time, in UTC unless stated otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl DateTime {
+    pub fn date(year: i64, month: u32, day: u32) -> Self {
+        DateTime {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+        }
+    }
+
+    /// Converts to a timestamp, rejecting out-of-range fields and years
+    /// without four digits.
+    pub fn to_timestamp(self) -> Option<Timestamp> {
+        if !(0..=9999).contains(&self.year)
+            || !(1..=12).contains(&self.month)
+            || self.day == 0
+            || self.day > days_in_month(self.year, self.month)
+            || self.hour > 23
+            || self.minute > 59
+            || self.second > 59
+        {
+            return None;
+        }
+        let days = days_from_civil(self.year, self.month, self.day);
+        let secs = self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64;
+        Some(Timestamp(days * SECONDS_PER_DAY + secs))
+    }
+
+    /// Converts a date and time in the display time zone to a timestamp.
+    pub fn to_local_timestamp(self) -> Option<Timestamp> {
+        self.to_timestamp()
+            .map(|ts| ts.add_secs(-display_offset().0))
+    }
+
+    pub fn day_of_year(self) -> u32 {
+        (days_from_civil(self.year, self.month, self.day) - days_from_civil(self.year, 1, 1)) as u32
+            + 1
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    /// Monday is 0.
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL[index as usize % 7]
+    }
+
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+
+    /// Days since the start of the week, 0 to 6, with the configured
+    /// [`week_start`].
+    pub fn days_into_week(self) -> u32 {
+        (self.index() + 7 - week_start().index()) % 7
+    }
+
+    /// The seven days in order from the configured [`week_start`].
+    pub fn week() -> [Weekday; 7] {
+        let start = week_start().index();
+        std::array::from_fn(|i| Weekday::from_index(start + i as u32))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Weekday::Monday => "Monday",
+            Weekday::Tuesday => "Tuesday",
+            Weekday::Wednesday => "Wednesday",
+            Weekday::Thursday => "Thursday",
+            Weekday::Friday => "Friday",
+            Weekday::Saturday => "Saturday",
+            Weekday::Sunday => "Sunday",
+        }
+    }
+
+    /// Accepts full names and common abbreviations ("tue", "thurs"), in
+    /// English or the display language.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.to_lowercase();
+        if input.chars().count() < 2 {
+            return None;
+        }
+        [Locale::ENGLISH, display_locale()]
+            .into_iter()
+            .find_map(|locale| {
+                Self::ALL
+                    .into_iter()
+                    .find(|&day| locale.weekday(day).to_lowercase().starts_with(&input))
+            })
+    }
+}
+
+/// Month and weekday names in one language, for dates shown to people.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Locale {
+    /// The ISO 639-1 code, such as `de`.
+    pub code: &'static str,
+    months: [&'static str; 12],
+    /// Monday first.
+    weekdays: [&'static str; 7],
+}
+
+impl Locale {
+    pub const ENGLISH: Locale = LOCALES[0];
+
+    /// The name of `month`, 1 to 12.
+    pub fn month(self, month: u32) -> &'static str {
+        self.months[(month as usize + 11) % 12]
+    }
+
+    pub fn weekday(self, day: Weekday) -> &'static str {
+        self.weekdays[day.index() as usize]
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale::ENGLISH
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.code)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = Error;
+
+    /// Accepts a language code, alone or as the start of a POSIX locale
+    /// name such as `de_DE.UTF-8`.
+    fn from_str(s: &str) -> Result<Self> {
+        let code = s
+            .trim()
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let code = if code == "c" || code == "posix" {
+            "en".to_string()
+        } else {
+            code
+        };
+        LOCALES
+            .iter()
+            .find(|l| l.code == code)
+            .copied()
+            .ok_or_else(|| {
+                let known: Vec<&str> = LOCALES.iter().map(|l| l.code).collect();
+                Error::Invalid(format!(
+                    "unknown locale `{}` (expected one of {})",
+                    s.trim(),
+                    known.join(", ")
+                ))
+            })
+    }
+}
+
+/// The languages month and day names can be shown in.
+pub const LOCALES: [Locale; 7] = [
+    Locale {
+        code: "en",
+        months: [
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ],
+        weekdays: [
+            "Monday",
+            "Tuesday",
+            "Wednesday",
+            "Thursday",
+            "Friday",
+            "Saturday",
+            "Sunday",
+        ],
+    },
+    Locale {
+        code: "de",
+        months: [
+            "Januar",
+            "Februar",
+            "März",
+            "April",
+            "Mai",
+            "Juni",
+            "Juli",
+            "August",
+            "September",
+            "Oktober",
+            "November",
+            "Dezember",
+        ],
+        weekdays: [
+            "Montag",
+            "Dienstag",
+            "Mittwoch",
+            "Donnerstag",
+            "Freitag",
+            "Samstag",
+            "Sonntag",
+        ],
+    },
+    Locale {
+        code: "es",
+        months: [
+            "enero",
+            "febrero",
+            "marzo",
+            "abril",
+            "mayo",
+            "junio",
+            "julio",
+            "agosto",
+            "septiembre",
+            "octubre",
+            "noviembre",
+            "diciembre",
+        ],
+        weekdays: [
+            "lunes",
+            "martes",
+            "miércoles",
+            "jueves",
+            "viernes",
+            "sábado",
+            "domingo",
+        ],
+    },
+    Locale {
+        code: "fr",
+        months: [
+            "janvier",
+            "février",
+            "mars",
+            "avril",
+            "mai",
+            "juin",
+            "juillet",
+            "août",
+            "septembre",
+            "octobre",
+            "novembre",
+            "décembre",
+        ],
+        weekdays: [
+            "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+        ],
+    },
+    Locale {
+        code: "it",
+        months: [
+            "gennaio",
+            "febbraio",
+            "marzo",
+            "aprile",
+            "maggio",
+            "giugno",
+            "luglio",
+            "agosto",
+            "settembre",
+            "ottobre",
+            "novembre",
+            "dicembre",
+        ],
+        weekdays: [
+            "lunedì",
+            "martedì",
+            "mercoledì",
+            "giovedì",
+            "venerdì",
+            "sabato",
+            "domenica",
+        ],
+    },
+    Locale {
+        code: "nl",
+        months: [
+            "januari",
+            "februari",
+            "maart",
+            "april",
+            "mei",
+            "juni",
+            "juli",
+            "augustus",
+            "september",
+            "oktober",
+            "november",
+            "december",
+        ],
+        weekdays: [
+            "maandag",
+            "dinsdag",
+            "woensdag",
+            "donderdag",
+            "vrijdag",
+            "zaterdag",
+            "zondag",
+        ],
+    },
+    Locale {
+        code: "pt",
+        months: [
+            "janeiro",
+            "fevereiro",
+            "março",
+            "abril",
+            "maio",
+            "junho",
+            "julho",
+            "agosto",
+            "setembro",
+            "outubro",
+            "novembro",
+            "dezembro",
+        ],
+        weekdays: [
+            "segunda", "terça", "quarta", "quinta", "sexta", "sábado", "domingo",
+        ],
+    },
+];
+
+/// The first three letters of a name, for `%a` and `%b`.
+fn abbreviate(name: &str) -> String {
+    name.chars().take(3).collect()
+}
+
+/// Month number (1-12) for a month name or an abbreviation of at least
+/// three letters ("jun", "sept"), in English or the display language.
+pub fn parse_month(input: &str) -> Option<u32> {
+    let input = input.trim().to_lowercase();
+    if input.chars().count() < 3 {
+        return None;
+    }
+    [Locale::ENGLISH, display_locale()]
+        .into_iter()
+        .find_map(|locale| {
+            (1..=12).find(|&month| locale.month(month).to_lowercase().starts_with(&input))
+        })
+}
+
+pub fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Inverse of [`days_from_civil`].
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Parses a date the way people type it at the prompt.
+///
+/// Accepts `YYYY-MM-DD` and `DD-MM-YYYY` (optionally followed by `HH:MM[:SS]`),
+/// `today`, `tomorrow`, `yesterday`, weekday names (the nearest such day,
+/// today included) and `next <weekday>` (strictly after today, also written
+/// `next-monday`). Relative words may also be followed by a time. Inputs
+/// without a time resolve to the end of that day so a task due "friday" is
+/// not overdue until friday is over. RFC 3339 timestamps, as other tools
+/// and the exports write them, are taken as given.
+pub fn parse_when(input: &str, now: Timestamp) -> Result<Timestamp> {
+    if let Ok(ts) = Timestamp::parse_rfc3339(input.trim()) {
+        return Ok(ts);
+    }
+    let text = input.trim().to_ascii_lowercase().replace('_', " ");
+    let invalid = || Error::Invalid(format!("unrecognized date `{}`", input.trim()));
+    if text.is_empty() {
+        return Err(invalid());
+    }
+    if let Some(ts) = parse_date_time(&text) {
+        return Ok(ts);
+    }
+
+    let words: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|word| !word.is_empty())
+        .collect();
+    let (day_words, time) = match words.split_last() {
+        Some((last, rest)) if !rest.is_empty() && last.contains(':') => {
+            (rest, Some(parse_time(last).ok_or_else(invalid)?))
+        }
+        _ => (&words[..], None),
+    };
+    let today = now.start_of_day();
+    let day = match day_words {
+        ["today"] => today,
+        ["tomorrow"] => today.add_secs(SECONDS_PER_DAY),
+        ["yesterday"] => today.add_secs(-SECONDS_PER_DAY),
+        ["next", name] => next_weekday(today, Weekday::parse(name).ok_or_else(invalid)?, false),
+        [name] => next_weekday(today, Weekday::parse(name).ok_or_else(invalid)?, true),
+        _ => return Err(invalid()),
+    };
+    Ok(match time {
+        Some(secs) => day.add_secs(secs),
+        None => day.end_of_day(),
+    })
+}
+
+/// A relative move of a date: `+2d`, `-1w`, `+1mo` or `+1y`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shift {
+    Days(i64),
+    Months(i64),
+}
+
+impl Shift {
+    /// Parses a sign, a count and a unit: `d` for days, `w` for weeks, `mo`
+    /// for months or `y` for years.
+    pub fn parse(input: &str) -> Result<Self> {
+        let text = input.trim().to_ascii_lowercase();
+        let invalid = || Error::Invalid(format!("invalid date shift `{}`", input.trim()));
+        let (sign, rest) = match text.split_at_checked(1) {
+            Some(("+", rest)) => (1, rest),
+            Some(("-", rest)) => (-1, rest),
+            _ => return Err(invalid()),
+        };
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let count: i64 = rest[..split].parse().map_err(|_| invalid())?;
+        let count = sign * count;
+        Ok(match &rest[split..] {
+            "d" => Shift::Days(count),
+            "w" => Shift::Days(count.checked_mul(7).ok_or_else(invalid)?),
+            "mo" => Shift::Months(count),
+            "y" => Shift::Months(count.checked_mul(12).ok_or_else(invalid)?),
+            _ => return Err(invalid()),
+        })
+    }
+
+    /// `time` moved by the shift in the display time zone, at the same time
+    /// of day. Moving by months keeps the day of the month, or takes the
+    /// last day of a shorter month: a month after 31 January is the end of
+    /// February. Shifts that leave the years 1 to 9999 are rejected.
+    pub fn apply(self, time: Timestamp) -> Result<Timestamp> {
+        let out_of_range = || Error::Invalid(format!("{self} moves the date past year 1 or 9999"));
+        let shifted = match self {
+            // Offsets are fixed, so every day is exactly this long.
+            Shift::Days(days) => days
+                .checked_mul(SECONDS_PER_DAY)
+                .and_then(|secs| time.secs().checked_add(secs))
+                .map(Timestamp::from_secs),
+            Shift::Months(months) if months.unsigned_abs() > SHIFT_YEARS * 12 => None,
+            Shift::Months(months) => {
+                let local = time.to_local();
+                let index = local.year * 12 + i64::from(local.month) - 1 + months;
+                let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+                DateTime {
+                    year,
+                    month,
+                    day: local.day.min(days_in_month(year, month)),
+                    ..local
+                }
+                .to_local_timestamp()
+            }
+        };
+        let first = DateTime::date(1, 1, 1).to_timestamp();
+        let last = DateTime {
+            hour: 23,
+            minute: 59,
+            second: 59,
+            ..DateTime::date(SHIFT_YEARS as i64, 12, 31)
+        }
+        .to_timestamp();
+        shifted
+            .filter(|&shifted| first <= Some(shifted) && Some(shifted) <= last)
+            .ok_or_else(out_of_range)
+    }
+}
+
+/// Shifts land between year 1 and this one.
+const SHIFT_YEARS: u64 = 9999;
+
+impl fmt::Display for Shift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Shift::Days(days) => write!(f, "{days:+}d"),
+            Shift::Months(months) => write!(f, "{months:+}mo"),
+        }
+    }
+}
+
+/// The new due date for `reschedule`: `input` is either a [`Shift`] of the
+/// current due date (of the end of today when there is none) or a date as
+/// [`parse_when`] reads it.
+pub fn reschedule(input: &str, due: Option<Timestamp>, now: Timestamp) -> Result<Timestamp> {
+    if input.trim_start().starts_with(['+', '-']) {
+        let shift = Shift::parse(input)?;
+        return shift.apply(due.unwrap_or_else(|| now.end_of_day()));
+    }
+    parse_when(input, now)
+}
+
+/// A span of whole days in the display time zone, both ends included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateRange {
+    /// Local midnight at the start of the first day.
+    pub start: Timestamp,
+    /// The last second of the last day.
+    pub end: Timestamp,
+}
+
+impl DateRange {
+    /// The single day containing `time`.
+    pub fn day(time: Timestamp) -> Self {
+        DateRange {
+            start: time.start_of_day(),
+            end: time.end_of_day(),
+        }
+    }
+
+    /// Parses a period relative to `now`: `this-week`, `last-month`,
+    /// `next-year` and so on (weeks start on [`week_start`], and `this`/`last`/`next`
+    /// may be written with a space), or any single day [`parse_when`] reads,
+    /// such as `yesterday` or `2024-06-01`.
+    pub fn parse(input: &str, now: Timestamp) -> Result<Self> {
+        let text = input.trim().to_ascii_lowercase().replace(['_', ' '], "-");
+        let period = text.split_once('-').and_then(|(which, unit)| {
+            let offset = match which {
+                "this" => 0,
+                "last" => -1,
+                "next" => 1,
+                _ => return None,
+            };
+            matches!(unit, "week" | "month" | "year").then_some((offset, unit))
+        });
+        let Some((offset, unit)) = period else {
+            return parse_when(input, now).map(DateRange::day);
+        };
+        let today = now.start_of_day();
+        let (first, after) = match unit {
+            "week" => {
+                let start = today.add_secs(
+                    -(i64::from(today.weekday().days_into_week()) - 7 * offset) * SECONDS_PER_DAY,
+                );
+                (start, Shift::Days(7))
+            }
+            "month" => {
+                let local = today.to_local();
+                let first = DateTime::date(local.year, local.month, 1)
+                    .to_local_timestamp()
+                    .expect("the first of a month is valid");
+                (Shift::Months(offset).apply(first)?, Shift::Months(1))
+            }
+            _ => {
+                let year = today.to_local().year + offset;
+                let first = DateTime::date(year, 1, 1)
+                    .to_local_timestamp()
+                    .expect("the first of January is valid");
+                (first, Shift::Months(12))
+            }
+        };
+        Ok(DateRange {
+            start: first,
+            end: after.apply(first)?.add_secs(-1),
+        })
+    }
+}
+
+/// The first `weekday` on or after `from` (or strictly after when
+/// `include_today` is false), at the start of that day.
+pub fn next_weekday(from: Timestamp, weekday: Weekday, include_today: bool) -> Timestamp {
+    let current = from.weekday().index() as i64;
+    let mut delta = (weekday.index() as i64 - current).rem_euclid(7);
+    if delta == 0 && !include_today {
+        delta = 7;
+    }
+    from.start_of_day().add_secs(delta * SECONDS_PER_DAY)
+}
+
+fn parse_time(text: &str) -> Option<i64> {
+    let mut parts = text.split(':');
+    let hour: i64 = parts.next()?.parse().ok()?;
+    let minute: i64 = parts.next()?.parse().ok()?;
+    let second: i64 = match parts.next() {
+        Some(s) => s.parse().ok()?,
+        None => 0,
+    };
+    if parts.next().is_some() || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    Some(hour * 3600 + minute * 60 + second)
+}
+
+/// Parses an absolute `YYYY-MM-DD` / `DD-MM-YYYY` date with optional time,
+/// in the display time zone.
+fn parse_date_time(text: &str) -> Option<Timestamp> {
+    parse_date_time_in(text, display_offset())
+}
+
+fn parse_date_time_in(text: &str, offset: UtcOffset) -> Option<Timestamp> {
+    let mut parts = text.split_whitespace();
+    let date = parts.next()?;
+    let time = parts.next();
+    if parts.next().is_some() {
+        return None;
+    }
+    let fields: Vec<&str> = date.split(['-', '.', '/']).collect();
+    let [a, b, c] = fields[..] else {
+        return None;
+    };
+    let (year, month, day) = if a.len() == 4 {
+        (a.parse().ok()?, b.parse().ok()?, c.parse().ok()?)
+    } else if c.len() == 4 {
+        (c.parse().ok()?, b.parse().ok()?, a.parse().ok()?)
+    } else {
+        return None;
+    };
+    let day = DateTime::date(year, month, day)
+        .to_timestamp()?
+        .add_secs(-offset.0);
+    let ts = match time {
+        Some(time) => day.add_secs(parse_time(time)?),
+        None => day.add_secs(SECONDS_PER_DAY - 1),
+    };
+    // The offset may still carry it past year 0 or 9999.
+    Some(ts).filter(|ts| ts.is_storable())
+}
@@ -0,0 +1,107 @@
+//! Lengths of time as people write and read them, used wherever a duration
+//! is entered or shown: reminder lead times, the grooming threshold, the
+//! daemon's backup interval and relative times.
+//!
+//! [`parse`] reads amounts with units in any of their usual spellings,
+//! `15m`, `1h30m`, `1h 30m`, `45 min`, `2 days`, `1.5h`, and takes a bare
+//! number as minutes. [`format`] writes the compact form back (`1h30m`) and
+//! [`humanize`] the largest whole unit in words (`3 hours`).
+
+use crate::dates::SECONDS_PER_DAY;
+use crate::error::{Error, Result};
+
+const WEEK: i64 = 7 * SECONDS_PER_DAY;
+
+/// Unit spellings and their length in seconds.
+const UNITS: [(&[&str], i64); 5] = [
+    (&["s", "sec", "secs", "second", "seconds"], 1),
+    (&["m", "min", "mins", "minute", "minutes"], 60),
+    (&["h", "hr", "hrs", "hour", "hours"], 3600),
+    (&["d", "day", "days"], SECONDS_PER_DAY),
+    (&["w", "wk", "wks", "week", "weeks"], WEEK),
+];
+
+/// Parses a duration into whole seconds.
+pub fn parse(input: &str) -> Result<i64> {
+    let text = input.trim().to_ascii_lowercase();
+    let invalid = || {
+        Error::Invalid(format!(
+            "invalid duration `{}` (e.g. 15m, 1h30m, 2d or 45 min)",
+            input.trim()
+        ))
+    };
+    if text.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(minutes) = text.parse::<u32>() {
+        return Ok(i64::from(minutes) * 60);
+    }
+    let mut rest = text.as_str();
+    let mut total = 0.0;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        let number_end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or_else(invalid)?;
+        let amount: f64 = rest[..number_end].parse().map_err(|_| invalid())?;
+        rest = rest[number_end..].trim_start();
+        let unit_end = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let (word, after) = rest.split_at(unit_end);
+        let (_, size) = UNITS
+            .iter()
+            .find(|(names, _)| names.contains(&word))
+            .ok_or_else(invalid)?;
+        total += amount * *size as f64;
+        rest = after;
+    }
+    Ok(total.round() as i64)
+}
+
+/// Renders seconds compactly, e.g. `1d2h`, `15m`, `0m`; [`parse`] reads it
+/// back.
+pub fn format(secs: i64) -> String {
+    let mut rest = secs.abs();
+    let mut out = String::new();
+    for (unit, size) in [
+        ("w", WEEK),
+        ("d", SECONDS_PER_DAY),
+        ("h", 3600),
+        ("m", 60),
+        ("s", 1),
+    ] {
+        if rest >= size {
+            out.push_str(&format!("{}{unit}", rest / size));
+            rest %= size;
+        }
+    }
+    if out.is_empty() {
+        out.push_str("0m");
+    }
+    out
+}
+
+/// Renders a duration rounded down to its largest unit, e.g. `3 hours`,
+/// `2 days`, `less than a minute`.
+pub fn humanize(secs: i64) -> String {
+    let secs = secs.abs();
+    for (unit, size) in [
+        ("year", 365 * SECONDS_PER_DAY),
+        ("month", 30 * SECONDS_PER_DAY),
+        ("week", WEEK),
+        ("day", SECONDS_PER_DAY),
+        ("hour", 3600),
+        ("minute", 60),
+    ] {
+        let count = secs / size;
+        if count > 0 {
+            let plural = if count == 1 { "" } else { "s" };
+            return format!("{count} {unit}{plural}");
+        }
+    }
+    "less than a minute".to_string()
+}
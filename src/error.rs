@@ -0,0 +1,91 @@
+use std::fmt;
+use std::io;
+
+use crate::json;
+use crate::workflow::Rejected;
+
+#[derive(Debug)]
+pub enum Error {
+    /// No task (or other named item) matched the given reference. The
+    /// message names it and may suggest what was meant.
+    NotFound(String),
+    /// User input or stored data failed validation.
+    Invalid(String),
+    /// The change is valid on its own but clashes with the existing tasks:
+    /// a limit, a dependency cycle or a duplicate link.
+    Conflict(String),
+    /// A status change the workflow does not allow; the details let a
+    /// frontend say what to do instead.
+    Transition(Rejected),
+    /// The data file is not valid JSON.
+    Json(json::ParseError),
+    Io(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// A stable name for the kind of failure, for scripts.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::NotFound(_) => "not_found",
+            Error::Invalid(_) | Error::Json(_) => "invalid",
+            Error::Io(_) => "io",
+            Error::Conflict(_) => "conflict",
+            Error::Transition(_) => "transition",
+        }
+    }
+
+    /// The process exit code for this kind of failure. These are part of
+    /// the command-line interface and must not change: 2 not found,
+    /// 3 validation, 4 I/O, 5 conflict (including a refused status change).
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::NotFound(_) => 2,
+            Error::Invalid(_) | Error::Json(_) => 3,
+            Error::Io(_) => 4,
+            Error::Conflict(_) | Error::Transition(_) => 5,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound(message) | Error::Invalid(message) | Error::Conflict(message) => {
+                f.write_str(message)
+            }
+            Error::Transition(rejected) => write!(f, "{rejected}"),
+            Error::Json(err) => write!(f, "invalid JSON at {err}"),
+            Error::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Json(err) => Some(err),
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<Rejected> for Error {
+    fn from(rejected: Rejected) -> Self {
+        Error::Transition(rejected)
+    }
+}
+
+impl From<json::ParseError> for Error {
+    fn from(err: json::ParseError) -> Self {
+        Error::Json(err)
+    }
+}
@@ -0,0 +1,172 @@
+//! CSV export, one row per task, written as it goes, and the reader that
+//! takes it back.
+//!
+//! Times are RFC 3339 in UTC; tags, dependencies, related tasks and
+//! reminder lead times (in seconds) are separated by spaces; flags are
+//! `yes` or blank; the review log is a JSON array. A blank cell is an
+//! absent value, and columns missing from older exports take defaults.
+
+use std::borrow::Borrow;
+use std::io::{self, Write};
+
+use crate::csv::{write_row, Table};
+use crate::dates::Timestamp;
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+use crate::task::{Task, TaskId};
+
+pub const HEADERS: [&str; 26] = [
+    "id",
+    "name",
+    "description",
+    "status",
+    "priority",
+    "project",
+    "tags",
+    "due",
+    "created",
+    "completed",
+    "parent",
+    "depends_on",
+    "jira",
+    "updated",
+    "related",
+    "reminders",
+    "pinned",
+    "starred_by",
+    "archived",
+    "private",
+    "review",
+    "review_log",
+    "contact",
+    "body",
+    "notion",
+    "google",
+];
+
+pub fn write<W: Write, T: Borrow<Task>>(
+    out: &mut W,
+    tasks: impl IntoIterator<Item = T>,
+) -> io::Result<()> {
+    write_row(out, &HEADERS)?;
+    for task in tasks {
+        let task = task.borrow();
+        let time = |t: Option<Timestamp>| t.map(Timestamp::to_rfc3339).unwrap_or_default();
+        let ids = |ids: &[TaskId]| {
+            ids.iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let flag = |set: bool| if set { "yes" } else { "" }.to_string();
+        let review_log = if task.review_log.is_empty() {
+            String::new()
+        } else {
+            task.to_json()
+                .get("review_log")
+                .map_or_else(String::new, Value::to_compact)
+        };
+        write_row(
+            out,
+            &[
+                task.id.to_string(),
+                task.name.clone(),
+                task.description.clone(),
+                task.status.to_string(),
+                task.priority.to_string(),
+                task.project.clone().unwrap_or_default(),
+                task.tags.join(" "),
+                time(task.due),
+                task.created_at.to_rfc3339(),
+                time(task.completed_at),
+                task.parent.map(|p| p.to_string()).unwrap_or_default(),
+                ids(&task.depends_on),
+                task.jira.clone().unwrap_or_default(),
+                task.updated_at.to_rfc3339(),
+                ids(&task.related),
+                task.reminders
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                flag(task.pinned),
+                task.starred_by.join(","),
+                flag(task.archived),
+                flag(task.private),
+                flag(task.review),
+                review_log,
+                task.contact.clone().unwrap_or_default(),
+                task.body.clone(),
+                task.notion.clone().unwrap_or_default(),
+                task.google.clone().unwrap_or_default(),
+            ],
+        )?;
+    }
+    out.flush()
+}
+
+/// Reads tasks written by [`write`]. Rows without an id get a new one.
+pub fn parse(input: &str) -> Result<Vec<Task>> {
+    let table = Table::parse(input)?;
+    let mut tasks = Vec::new();
+    for (index, row) in table.rows().enumerate() {
+        // Cells are taken as written: names, descriptions and bodies may
+        // start or end with spaces.
+        let cell = |name: &str| row.raw(name).filter(|cell| !cell.is_empty());
+        let list = |name: &str| -> Vec<Value> {
+            cell(name)
+                .map(|cell| cell.split_whitespace().map(Value::from).collect())
+                .unwrap_or_default()
+        };
+        let mut value = Value::object();
+        value.insert(
+            "id",
+            cell("id").map_or_else(|| TaskId::generate().to_string(), str::to_string),
+        );
+        value.insert("name", row.raw("name").unwrap_or_default());
+        for (field, column) in [
+            ("description", "description"),
+            ("status", "status"),
+            ("priority", "priority"),
+            ("project", "project"),
+            ("due", "due"),
+            ("created_at", "created"),
+            ("updated_at", "updated"),
+            ("completed_at", "completed"),
+            ("parent", "parent"),
+            ("jira", "jira"),
+            ("contact", "contact"),
+            ("body", "body"),
+            ("notion", "notion"),
+            ("google", "google"),
+        ] {
+            value.insert(field, cell(column));
+        }
+        for field in ["tags", "depends_on", "related"] {
+            value.insert(field, Value::Array(list(field)));
+        }
+        let reminders = list("reminders")
+            .iter()
+            .map(|lead| lead.as_str().and_then(|lead| lead.parse::<i64>().ok()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| {
+                Error::Invalid(format!("row {}: reminders must be seconds", index + 1))
+            })?;
+        value.insert("reminders", reminders);
+        // Names may hold spaces, so the list is comma-separated.
+        let starred_by: Vec<Value> = cell("starred_by")
+            .map(|cell| cell.split(',').map(Value::from).collect())
+            .unwrap_or_default();
+        value.insert("starred_by", Value::Array(starred_by));
+        for field in ["pinned", "archived", "private", "review"] {
+            value.insert(field, cell(field).is_some_and(|c| c != "no"));
+        }
+        if let Some(log) = cell("review_log") {
+            value.insert("review_log", json::parse(log)?);
+        }
+        let task = Task::from_json(&value)
+            .map_err(|err| Error::Invalid(format!("row {}: {err}", index + 1)))?;
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
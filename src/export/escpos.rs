@@ -0,0 +1,119 @@
+//! Today's agenda as a paper checklist, in the ESC/POS commands receipt
+//! printers understand.
+//!
+//! Text goes out in code page 1252, which covers Western European letters;
+//! anything else prints as `?`. Lines are wrapped to the paper's width in
+//! characters: 32 for 58 mm rolls, 48 for 80 mm ones.
+
+use crate::dates::Timestamp;
+use crate::render::{short_id, wrap};
+use crate::stats::Today;
+use crate::task::Task;
+
+/// Characters per line on a 58 mm roll, which 80 mm printers print too.
+pub const DEFAULT_WIDTH: usize = 32;
+
+const ESC: u8 = 0x1b;
+const GS: u8 = 0x1d;
+
+pub fn receipt(today: &Today, now: Timestamp, width: usize) -> Vec<u8> {
+    let mut out = Receipt {
+        bytes: Vec::new(),
+        width: width.max(16),
+    };
+    // Reset, then select code page 16 (WPC1252).
+    out.bytes.extend([ESC, b'@', ESC, b't', 16]);
+    // Centred, bold and double height for the heading.
+    out.bytes
+        .extend([ESC, b'a', 1, ESC, b'E', 1, GS, b'!', 0x01]);
+    out.line("TODAY");
+    out.bytes.extend([GS, b'!', 0x00, ESC, b'E', 0]);
+    out.line(&now.format_local("%A %e %B %Y"));
+    out.bytes.extend([ESC, b'a', 0]);
+    out.rule();
+    let sections = [
+        ("Overdue", &today.overdue),
+        ("Due today", &today.due_today),
+        ("High priority", &today.high_priority),
+    ];
+    if today.is_empty() {
+        out.line("Nothing due today.");
+    }
+    for (heading, tasks) in sections {
+        if tasks.is_empty() {
+            continue;
+        }
+        out.bytes.extend([ESC, b'E', 1]);
+        out.line(&format!("{heading} ({})", tasks.len()));
+        out.bytes.extend([ESC, b'E', 0]);
+        for task in tasks {
+            out.item(task, now);
+        }
+        out.line("");
+    }
+    out.rule();
+    // Feed past the cutter, then cut leaving a hinge.
+    out.bytes.extend([ESC, b'd', 4, GS, b'V', 66, 0]);
+    out.bytes
+}
+
+struct Receipt {
+    bytes: Vec<u8>,
+    width: usize,
+}
+
+impl Receipt {
+    fn line(&mut self, text: &str) {
+        self.bytes.extend(text.chars().map(encode));
+        self.bytes.push(b'\n');
+    }
+
+    fn rule(&mut self) {
+        self.line(&"-".repeat(self.width));
+    }
+
+    /// A checkbox and the wrapped name, then when it is due (or its
+    /// priority, without a due date) and the short id under it.
+    fn item(&mut self, task: &Task, now: Timestamp) {
+        let detail = match task.due {
+            Some(due) if due.day_number() == now.day_number() => due.format_local("%R"),
+            Some(due) => format!("due {}", due.to_local_date()),
+            None => task.priority.to_string(),
+        };
+        let mut lines = wrap(&task.name, self.width - 4);
+        // A blank name still gets its checkbox.
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        for (i, line) in lines.iter().enumerate() {
+            let prefix = if i == 0 { "[ ] " } else { "    " };
+            self.line(&format!("{prefix}{line}"));
+        }
+        let id = task.id.to_string();
+        let id = short_id(&id);
+        let pad = (self.width - 4).saturating_sub(detail.chars().count() + id.len());
+        self.line(&format!("    {detail}{:pad$}{id}", ""));
+    }
+}
+
+/// The character in code page 1252. Control characters would be taken as
+/// printer commands, so they print as spaces.
+fn encode(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        '\u{a0}'..='\u{ff}' => c as u32 as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        '„' => 0x84,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        c if c.is_control() => b' ',
+        _ => b'?',
+    }
+}
@@ -0,0 +1,314 @@
+//! Dependency and subtask graphs in Graphviz DOT and Mermaid syntax, and
+//! Mermaid project overviews for documentation.
+//!
+//! Dependency edges point from the prerequisite to the task waiting on it;
+//! subtask edges are dashed and point from the parent to the child; related
+//! tasks are joined by a dotted line without arrows. Nodes are
+//! filled by status and outlined by priority, and the longest chain of open
+//! dependencies (the critical path) is drawn in bold red.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::config::Glyphs;
+use crate::task::{Priority, Status, Task, TaskId};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+pub fn render(tasks: &[&Task], format: GraphFormat, glyphs: &Glyphs) -> String {
+    match format {
+        GraphFormat::Dot => dot(tasks, glyphs),
+        GraphFormat::Mermaid => mermaid(tasks, glyphs),
+    }
+}
+
+fn status_fill(status: Status) -> &'static str {
+    match status {
+        Status::Todo => "#f6f8fa",
+        Status::InProgress => "#ddf4ff",
+        Status::ReviewPending => "#fbefff",
+        Status::Done => "#dafbe1",
+        Status::Cancelled => "#eaeef2",
+    }
+}
+
+fn priority_stroke(priority: Priority) -> (&'static str, u32) {
+    match priority {
+        Priority::Low => ("#8c959f", 1),
+        Priority::Medium => ("#9a6700", 1),
+        Priority::High => ("#bc4c00", 2),
+        Priority::Critical => ("#cf222e", 3),
+    }
+}
+
+const CRITICAL_EDGE: &str = "#cf222e";
+
+fn node_id(id: TaskId) -> String {
+    format!("t{}", &id.to_hex()[..12])
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Edge {
+    Dependency,
+    Subtask,
+    Related,
+}
+
+/// Edges between tasks that are both part of the graph, as
+/// `(from, to, kind)`.
+fn edges(tasks: &[&Task]) -> Vec<(TaskId, TaskId, Edge)> {
+    let present: HashSet<TaskId> = tasks.iter().map(|t| t.id).collect();
+    let mut edges = Vec::new();
+    for task in tasks {
+        if let Some(parent) = task.parent.filter(|p| present.contains(p)) {
+            edges.push((parent, task.id, Edge::Subtask));
+        }
+        for dep in task.depends_on.iter().filter(|d| present.contains(d)) {
+            edges.push((*dep, task.id, Edge::Dependency));
+        }
+        for other in task.related.iter().filter(|r| present.contains(r)) {
+            edges.push((task.id, *other, Edge::Related));
+        }
+    }
+    edges
+}
+
+/// Longest chain of open tasks linked by dependencies, as a set of
+/// `(prerequisite, dependent)` edges.
+pub fn critical_path(tasks: &[&Task]) -> HashSet<(TaskId, TaskId)> {
+    let by_id: HashMap<TaskId, &Task> = tasks.iter().map(|t| (t.id, *t)).collect();
+    let mut memo: HashMap<TaskId, (usize, Option<TaskId>)> = HashMap::new();
+
+    fn longest(
+        id: TaskId,
+        by_id: &HashMap<TaskId, &Task>,
+        memo: &mut HashMap<TaskId, (usize, Option<TaskId>)>,
+        visiting: &mut HashSet<TaskId>,
+    ) -> usize {
+        if let Some(&(len, _)) = memo.get(&id) {
+            return len;
+        }
+        if !visiting.insert(id) {
+            return 0;
+        }
+        let mut best = (1, None);
+        for dep in &by_id[&id].depends_on {
+            if by_id.get(dep).is_some_and(|t| t.status.is_open()) {
+                let len = longest(*dep, by_id, memo, visiting) + 1;
+                if len > best.0 {
+                    best = (len, Some(*dep));
+                }
+            }
+        }
+        visiting.remove(&id);
+        memo.insert(id, best);
+        best.0
+    }
+
+    let mut visiting = HashSet::new();
+    let mut end = None;
+    let mut end_len = 1;
+    for task in tasks.iter().filter(|t| t.status.is_open()) {
+        let len = longest(task.id, &by_id, &mut memo, &mut visiting);
+        if len > end_len {
+            end_len = len;
+            end = Some(task.id);
+        }
+    }
+
+    let mut path = HashSet::new();
+    while let Some(current) = end {
+        let next = memo.get(&current).and_then(|&(_, next)| next);
+        if let Some(prev) = next {
+            path.insert((prev, current));
+        }
+        end = next;
+    }
+    path
+}
+
+fn short(id: TaskId) -> String {
+    id.to_hex()[..8].to_string()
+}
+
+pub fn dot(tasks: &[&Task], glyphs: &Glyphs) -> String {
+    let critical = critical_path(tasks);
+    let mut out = String::from("digraph tasks {\n");
+    out.push_str("  rankdir=LR;\n");
+    out.push_str("  node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\"];\n");
+    for task in tasks {
+        let (stroke, width) = priority_stroke(task.priority);
+        out.push_str(&format!(
+            "  {} [label=\"{}\\n{} {} {}\", fillcolor=\"{}\", color=\"{}\", penwidth={}];\n",
+            node_id(task.id),
+            dot_escape(&task.name),
+            short(task.id),
+            dot_escape(&glyphs.separator),
+            dot_escape(&glyphs.priority_label(task.priority)),
+            status_fill(task.status),
+            stroke,
+            width
+        ));
+    }
+    for (from, to, kind) in edges(tasks) {
+        let attrs = match kind {
+            Edge::Subtask => " [style=dashed, arrowhead=none]".to_string(),
+            Edge::Related => " [style=dotted, dir=none]".to_string(),
+            Edge::Dependency if critical.contains(&(from, to)) => {
+                format!(" [color=\"{CRITICAL_EDGE}\", penwidth=3]")
+            }
+            Edge::Dependency => String::new(),
+        };
+        out.push_str(&format!(
+            "  {} -> {}{};\n",
+            node_id(from),
+            node_id(to),
+            attrs
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn mermaid(tasks: &[&Task], glyphs: &Glyphs) -> String {
+    let mut out = String::from("flowchart LR\n");
+    for task in tasks {
+        out.push_str(&format!(
+            "  {}[\"{}<br/>{} {} {}\"]\n",
+            node_id(task.id),
+            mermaid_escape(&task.name),
+            short(task.id),
+            mermaid_escape(&glyphs.separator),
+            mermaid_escape(&glyphs.priority_label(task.priority))
+        ));
+    }
+    mermaid_links(tasks, &mut out);
+    out
+}
+
+/// Mermaid edges between `tasks`, then the styles of their nodes and of the
+/// critical path.
+fn mermaid_links(tasks: &[&Task], out: &mut String) {
+    let critical = critical_path(tasks);
+    let mut critical_links = Vec::new();
+    for (index, (from, to, kind)) in edges(tasks).into_iter().enumerate() {
+        let arrow = match kind {
+            Edge::Dependency => "-->",
+            Edge::Subtask => "-.-",
+            Edge::Related => "~~~",
+        };
+        out.push_str(&format!("  {} {arrow} {}\n", node_id(from), node_id(to)));
+        if kind == Edge::Dependency && critical.contains(&(from, to)) {
+            critical_links.push(index.to_string());
+        }
+    }
+    for task in tasks {
+        let (stroke, width) = priority_stroke(task.priority);
+        out.push_str(&format!(
+            "  style {} fill:{},stroke:{},stroke-width:{}px\n",
+            node_id(task.id),
+            status_fill(task.status),
+            stroke,
+            width
+        ));
+    }
+    if !critical_links.is_empty() {
+        out.push_str(&format!(
+            "  linkStyle {} stroke:{CRITICAL_EDGE},stroke-width:3px\n",
+            critical_links.join(",")
+        ));
+    }
+}
+
+/// How `export --mermaid` lays out the project overview.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Diagram {
+    /// Tasks boxed by project, with their status and priority.
+    #[default]
+    Flowchart,
+    /// Tasks grouped by status, with dependencies between them.
+    State,
+}
+
+/// A Mermaid overview of `tasks` for documentation and wikis. Unlike
+/// [`mermaid`], tasks are grouped: by project in a flowchart, by status in
+/// a state diagram.
+pub fn overview(tasks: &[&Task], diagram: Diagram, glyphs: &Glyphs) -> String {
+    match diagram {
+        Diagram::Flowchart => project_flowchart(tasks, glyphs),
+        Diagram::State => status_diagram(tasks),
+    }
+}
+
+fn project_flowchart(tasks: &[&Task], glyphs: &Glyphs) -> String {
+    let mut projects: Vec<Option<&str>> = Vec::new();
+    for task in tasks {
+        if !projects.contains(&task.project.as_deref()) {
+            projects.push(task.project.as_deref());
+        }
+    }
+    // Named projects alphabetically, then the tasks without one.
+    projects.sort_by_key(|p| (p.is_none(), p.map(str::to_lowercase)));
+    let mut out = String::from("flowchart LR\n");
+    for (index, project) in projects.iter().enumerate() {
+        out.push_str(&format!(
+            "  subgraph p{index}[\"{}\"]\n",
+            mermaid_escape(project.unwrap_or("No project"))
+        ));
+        for task in tasks.iter().filter(|t| t.project.as_deref() == *project) {
+            out.push_str(&format!(
+                "    {}[\"{}<br/>{} {} {}\"]\n",
+                node_id(task.id),
+                mermaid_escape(&task.name),
+                task.status,
+                mermaid_escape(&glyphs.separator),
+                mermaid_escape(&glyphs.priority_label(task.priority))
+            ));
+        }
+        out.push_str("  end\n");
+    }
+    mermaid_links(tasks, &mut out);
+    out
+}
+
+fn status_diagram(tasks: &[&Task]) -> String {
+    let mut out = String::from("stateDiagram-v2\n  direction LR\n");
+    for status in Status::ALL {
+        let members: Vec<&&Task> = tasks.iter().filter(|t| t.status == status).collect();
+        // Mermaid rejects composite states without anything inside.
+        if members.is_empty() {
+            continue;
+        }
+        out.push_str(&format!(
+            "  state \"{status}\" as s_{} {{\n",
+            status.as_str().replace('-', "_")
+        ));
+        for task in members {
+            out.push_str(&format!(
+                "    state \"{}\" as {}\n",
+                mermaid_escape(&task.name),
+                node_id(task.id)
+            ));
+        }
+        out.push_str("  }\n");
+    }
+    for (from, to, kind) in edges(tasks) {
+        if kind == Edge::Dependency {
+            out.push_str(&format!("  {} --> {}\n", node_id(from), node_id(to)));
+        }
+    }
+    out
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
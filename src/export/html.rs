@@ -0,0 +1,162 @@
+//! Standalone HTML report of the current tasks, grouped by project and status.
+
+use std::collections::BTreeMap;
+
+use crate::config::{Glyphs, TagStyles};
+use crate::dates::Timestamp;
+use crate::task::{Status, Task};
+
+const STYLE: &str = "\
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2rem auto; max-width: 60rem; color: #24292f; }
+h1 { border-bottom: 2px solid #d0d7de; padding-bottom: .3rem; }
+h2 { margin-top: 2rem; }
+h3 { color: #57606a; font-size: 1rem; text-transform: uppercase; letter-spacing: .05em; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1rem; }
+th, td { text-align: left; padding: .4rem .6rem; border-bottom: 1px solid #d0d7de; vertical-align: top; }
+th { background: #f6f8fa; }
+.desc { color: #57606a; font-size: .9rem; }
+.tag { display: inline-block; background: #ddf4ff; color: #0969da; border-radius: 1rem; padding: 0 .5rem; margin-right: .25rem; font-size: .8rem; }
+.priority-low { color: #57606a; }
+.priority-medium { color: #9a6700; }
+.priority-high { color: #bc4c00; font-weight: bold; }
+.priority-critical { color: #cf222e; font-weight: bold; }
+.overdue { color: #cf222e; }
+.meta { color: #57606a; font-size: .85rem; }
+";
+
+/// Renders `tasks` as a self-contained HTML page with inline styles. Tags
+/// with a configured colour or icon are drawn with it.
+pub fn render(tasks: &[&Task], now: Timestamp, tags: &TagStyles, glyphs: &Glyphs) -> String {
+    let mut projects: BTreeMap<&str, Vec<&Task>> = BTreeMap::new();
+    for &task in tasks {
+        projects
+            .entry(task.project.as_deref().unwrap_or(""))
+            .or_default()
+            .push(task);
+    }
+
+    let mut out = String::new();
+    open_page(&mut out, "Tasks");
+    let open = tasks.iter().filter(|t| t.status.is_open()).count();
+    out.push_str(&format!(
+        "<p class=\"meta\">{} tasks, {} open. Generated {}.</p>\n",
+        tasks.len(),
+        open,
+        now
+    ));
+
+    for (project, tasks) in &projects {
+        out.push_str(&format!("<h2>{}</h2>\n", escape(project_title(project))));
+        status_tables(&mut out, tasks, now, tags, glyphs);
+    }
+    close_page(&mut out);
+    out
+}
+
+/// Starts a page with the shared styles and `title` as its heading.
+pub(crate) fn open_page(out: &mut String, title: &str) {
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n<style>\n", escape(title)));
+    out.push_str(STYLE);
+    out.push_str(&format!(
+        "</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape(title)
+    ));
+}
+
+pub(crate) fn close_page(out: &mut String) {
+    out.push_str("</body>\n</html>\n");
+}
+
+/// How a project is headed; tasks without one are grouped as "No project".
+pub(crate) fn project_title(project: &str) -> &str {
+    if project.is_empty() {
+        "No project"
+    } else {
+        project
+    }
+}
+
+/// One table of `tasks` per status, in workflow order.
+pub(crate) fn status_tables(
+    out: &mut String,
+    tasks: &[&Task],
+    now: Timestamp,
+    tags: &TagStyles,
+    glyphs: &Glyphs,
+) {
+    for status in Status::ALL {
+        let group: Vec<&&Task> = tasks.iter().filter(|t| t.status == status).collect();
+        if group.is_empty() {
+            continue;
+        }
+        out.push_str(&format!(
+            "<h3>{} ({})</h3>\n",
+            escape(&glyphs.status_label(status)),
+            group.len()
+        ));
+        out.push_str("<table>\n<tr><th>Task</th><th>Priority</th><th>Due</th><th>Tags</th></tr>\n");
+        for task in group {
+            render_row(out, task, now, tags, glyphs);
+        }
+        out.push_str("</table>\n");
+    }
+}
+
+fn render_row(out: &mut String, task: &Task, now: Timestamp, tags: &TagStyles, glyphs: &Glyphs) {
+    out.push_str("<tr><td>");
+    if task.pinned {
+        out.push_str(&format!("{} ", escape(&glyphs.pinned)));
+    }
+    out.push_str(&escape(&task.name));
+    if !task.description.is_empty() {
+        out.push_str(&format!(
+            "<div class=\"desc\">{}</div>",
+            escape(&task.description)
+        ));
+    }
+    out.push_str(&format!(
+        "</td><td class=\"priority-{}\">{}</td>",
+        task.priority,
+        escape(&glyphs.priority_label(task.priority))
+    ));
+    match task.due {
+        Some(due) if task.is_overdue(now) => {
+            out.push_str(&format!("<td class=\"overdue\">{due}</td>"));
+        }
+        Some(due) => out.push_str(&format!("<td>{due}</td>")),
+        None => out.push_str("<td></td>"),
+    }
+    out.push_str("<td>");
+    for tag in &task.tags {
+        let style = tags.get(tag);
+        match style.and_then(|s| s.color) {
+            Some(color) => out.push_str(&format!(
+                "<span class=\"tag\" style=\"color: {0}; background: none; border: 1px solid {0}\">",
+                color.css()
+            )),
+            None => out.push_str("<span class=\"tag\">"),
+        }
+        if let Some(icon) = style.and_then(|s| s.icon.as_deref()) {
+            out.push_str(&format!("{} ", escape(icon)));
+        }
+        out.push_str(&format!("{}</span>", escape(tag)));
+    }
+    out.push_str("</td></tr>\n");
+}
+
+/// Escapes text for use in HTML element content and attribute values.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
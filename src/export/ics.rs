@@ -0,0 +1,457 @@
+//! iCalendar (RFC 5545) `VTODO` export and import.
+//!
+//! Reminders map to `VALARM` components triggered relative to the due date
+//! (`TRIGGER;RELATED=END:-PT15M`), so calendar apps show the same reminders
+//! the CLI uses. On import, triggers relative to the end (the due date), to
+//! the start (`DTSTART`, the default) and absolute `DATE-TIME` triggers are
+//! all converted back into lead times; alarms relative to a start the to-do
+//! does not have, or on one without a due date, are dropped.
+//!
+//! [`feed`] writes the same components as a calendar to subscribe to, named
+//! and stamped with each task's last change so unchanged tasks give the same
+//! file.
+
+use std::collections::HashMap;
+
+use crate::dates::{DateTime, Timestamp, SECONDS_PER_DAY};
+use crate::error::{Error, Result};
+use crate::task::{Priority, Status, Task, TaskId};
+
+const PROJECT_PROPERTY: &str = "X-TASK-MANAGER-PROJECT";
+
+pub fn render(tasks: &[&Task], now: Timestamp) -> String {
+    let mut out = String::new();
+    line(&mut out, "BEGIN:VCALENDAR");
+    line(&mut out, "VERSION:2.0");
+    line(&mut out, "PRODID:-//task-manager//EN");
+    for task in tasks {
+        render_todo(&mut out, task, now);
+    }
+    line(&mut out, "END:VCALENDAR");
+    out
+}
+
+/// A calendar called `name` for clients to subscribe to.
+pub fn feed(tasks: &[&Task], name: &str) -> String {
+    let mut out = String::new();
+    line(&mut out, "BEGIN:VCALENDAR");
+    line(&mut out, "VERSION:2.0");
+    line(&mut out, "PRODID:-//task-manager//EN");
+    line(&mut out, &format!("X-WR-CALNAME:{}", escape(name)));
+    for task in tasks {
+        render_todo(&mut out, task, task.updated_at);
+    }
+    line(&mut out, "END:VCALENDAR");
+    out
+}
+
+/// One `VTODO`, stamped with `stamp`.
+fn render_todo(out: &mut String, task: &Task, stamp: Timestamp) {
+    line(out, "BEGIN:VTODO");
+    line(out, &format!("UID:{}", task.id));
+    line(out, &format!("DTSTAMP:{}", ics_time(stamp)));
+    line(out, &format!("CREATED:{}", ics_time(task.created_at)));
+    line(out, &format!("SUMMARY:{}", escape(&task.name)));
+    if !task.description.is_empty() {
+        line(out, &format!("DESCRIPTION:{}", escape(&task.description)));
+    }
+    line(out, &format!("PRIORITY:{}", ics_priority(task.priority)));
+    line(out, &format!("STATUS:{}", ics_status(task.status)));
+    if let Some(due) = task.due {
+        line(out, &format!("DUE:{}", ics_time(due)));
+    }
+    if let Some(completed) = task.completed_at {
+        line(out, &format!("COMPLETED:{}", ics_time(completed)));
+    }
+    if !task.tags.is_empty() {
+        let tags: Vec<String> = task.tags.iter().map(|t| escape(t)).collect();
+        line(out, &format!("CATEGORIES:{}", tags.join(",")));
+    }
+    if let Some(project) = &task.project {
+        line(out, &format!("{PROJECT_PROPERTY}:{}", escape(project)));
+    }
+    if let Some(parent) = task.parent {
+        line(out, &format!("RELATED-TO;RELTYPE=PARENT:{parent}"));
+    }
+    for dep in &task.depends_on {
+        line(out, &format!("RELATED-TO;RELTYPE=DEPENDS-ON:{dep}"));
+    }
+    if task.due.is_some() {
+        for &lead in &task.reminders {
+            line(out, "BEGIN:VALARM");
+            line(out, "ACTION:DISPLAY");
+            line(out, &format!("DESCRIPTION:{}", escape(&task.name)));
+            line(out, &format!("TRIGGER;RELATED=END:{}", ics_duration(-lead)));
+            line(out, "END:VALARM");
+        }
+    }
+    line(out, "END:VTODO");
+}
+
+/// Writes a content line, folding it at 75 octets as RFC 5545 requires.
+fn line(out: &mut String, text: &str) {
+    let mut width = 0;
+    for c in text.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+fn ics_time(ts: Timestamp) -> String {
+    ts.format("%Y%m%dT%H%M%SZ")
+}
+
+fn ics_priority(priority: Priority) -> u32 {
+    match priority {
+        Priority::Critical => 1,
+        Priority::High => 3,
+        Priority::Medium => 5,
+        Priority::Low => 9,
+    }
+}
+
+fn ics_status(status: Status) -> &'static str {
+    match status {
+        Status::Todo => "NEEDS-ACTION",
+        // iCalendar has no review state; the work is still in process.
+        Status::InProgress | Status::ReviewPending => "IN-PROCESS",
+        Status::Done => "COMPLETED",
+        Status::Cancelled => "CANCELLED",
+    }
+}
+
+/// Formats signed seconds as an RFC 5545 duration, e.g. `-PT1H30M`.
+fn ics_duration(secs: i64) -> String {
+    let sign = if secs < 0 { "-" } else { "" };
+    let mut rest = secs.abs();
+    let days = rest / SECONDS_PER_DAY;
+    rest %= SECONDS_PER_DAY;
+    let mut out = format!("{sign}P");
+    if days > 0 {
+        out.push_str(&format!("{days}D"));
+    }
+    if rest > 0 || days == 0 {
+        out.push('T');
+        let (hours, minutes, seconds) = (rest / 3600, rest % 3600 / 60, rest % 60);
+        if hours > 0 {
+            out.push_str(&format!("{hours}H"));
+        }
+        if minutes > 0 {
+            out.push_str(&format!("{minutes}M"));
+        }
+        if seconds > 0 || rest == 0 {
+            out.push_str(&format!("{seconds}S"));
+        }
+    }
+    out
+}
+
+fn parse_duration(text: &str) -> Option<i64> {
+    let (sign, rest) = match text.as_bytes().first()? {
+        b'-' => (-1, &text[1..]),
+        b'+' => (1, &text[1..]),
+        _ => (1, text),
+    };
+    let rest = rest.strip_prefix('P')?;
+    let mut total = 0;
+    let mut digits = String::new();
+    for c in rest.chars() {
+        match c {
+            'T' => continue,
+            '0'..='9' => digits.push(c),
+            unit => {
+                let size = match unit {
+                    'W' => 7 * SECONDS_PER_DAY,
+                    'D' => SECONDS_PER_DAY,
+                    'H' => 3600,
+                    'M' => 60,
+                    'S' => 1,
+                    _ => return None,
+                };
+                total += digits.parse::<i64>().ok()? * size;
+                digits.clear();
+            }
+        }
+    }
+    Some(sign * total)
+}
+
+/// Parses `20240612T120000Z`, `20240612T120000` or a bare `20240612` date
+/// (end of that day). `DATE-TIME` values ending in `Z` are UTC; floating
+/// ones and plain `DATE`s are taken as local to the display time zone.
+fn parse_time(text: &str) -> Option<Timestamp> {
+    let utc = text.ends_with('Z');
+    let text = text.trim_end_matches('Z');
+    let (date, time) = match text.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (text, None),
+    };
+    if date.len() != 8 {
+        return None;
+    }
+    let mut dt = DateTime::date(
+        date[..4].parse().ok()?,
+        date[4..6].parse().ok()?,
+        date[6..].parse().ok()?,
+    );
+    match time {
+        Some(time) if time.len() == 6 => {
+            dt.hour = time[..2].parse().ok()?;
+            dt.minute = time[2..4].parse().ok()?;
+            dt.second = time[4..].parse().ok()?;
+            if utc {
+                dt.to_timestamp()
+            } else {
+                dt.to_local_timestamp()
+            }
+        }
+        Some(_) => None,
+        None => dt.to_local_timestamp().map(Timestamp::end_of_day),
+    }
+}
+
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn parse_property(line: &str) -> Option<Property> {
+    let (head, value) = line.split_once(':')?;
+    let mut parts = head.split(';');
+    let name = parts.next()?.to_ascii_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.to_ascii_uppercase(), v.trim_matches('"').to_string()))
+        .collect();
+    Some(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+/// Unfolds continuation lines (those starting with a space or tab).
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in input.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if raw.is_empty() => {}
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+#[derive(Default)]
+struct Pending {
+    task: Option<Task>,
+    uid: Option<String>,
+    parent: Option<String>,
+    depends: Vec<String>,
+    start: Option<Timestamp>,
+    alarms: Vec<Property>,
+}
+
+/// Parses every `VTODO` in an iCalendar document.
+///
+/// UIDs that are task ids are kept, others are turned into one (the same
+/// for the same UID); `RELATED-TO` links are resolved between the imported
+/// tasks.
+pub fn parse(input: &str) -> Result<Vec<Task>> {
+    let mut pending: Vec<Pending> = Vec::new();
+    let mut current: Option<Pending> = None;
+    let mut in_alarm = false;
+    for (index, line) in unfold(input).iter().enumerate() {
+        let property = parse_property(line).ok_or_else(|| {
+            Error::Invalid(format!("line {}: malformed iCalendar line", index + 1))
+        })?;
+        let value = property.value.as_str();
+        match (property.name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VTODO") => {
+                current = Some(Pending {
+                    task: Some(Task::new("")),
+                    ..Pending::default()
+                });
+            }
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VTODO") => {
+                pending.extend(current.take());
+            }
+            ("BEGIN", Some(_)) if value.eq_ignore_ascii_case("VALARM") => in_alarm = true,
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VALARM") => in_alarm = false,
+            ("TRIGGER", Some(todo)) if in_alarm => todo.alarms.push(property),
+            (_, Some(_)) if in_alarm => {}
+            (name, Some(todo)) => {
+                let task = todo.task.as_mut().expect("pending task");
+                match name {
+                    "UID" => todo.uid = Some(value.to_string()),
+                    "SUMMARY" => task.name = unescape(value),
+                    "DESCRIPTION" => task.description = unescape(value),
+                    "PRIORITY" => task.priority = priority_from_ics(value),
+                    "STATUS" => task.status = status_from_ics(value),
+                    "DUE" => task.due = parse_time(value),
+                    "DTSTART" => todo.start = parse_time(value),
+                    "COMPLETED" => task.completed_at = parse_time(value),
+                    "CREATED" => {
+                        if let Some(created) = parse_time(value) {
+                            task.created_at = created;
+                        }
+                    }
+                    "CATEGORIES" => {
+                        for tag in split_list(value) {
+                            if !tag.is_empty() && !task.has_tag(&tag) {
+                                task.tags.push(tag);
+                            }
+                        }
+                    }
+                    PROJECT_PROPERTY => task.project = Some(unescape(value)),
+                    "RELATED-TO" => match property.param("RELTYPE").map(str::to_ascii_uppercase) {
+                        None => todo.parent = Some(value.to_string()),
+                        Some(kind) if kind == "PARENT" => todo.parent = Some(value.to_string()),
+                        Some(kind) if kind == "DEPENDS-ON" => todo.depends.push(value.to_string()),
+                        Some(_) => {}
+                    },
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut ids: HashMap<String, TaskId> = HashMap::new();
+    for todo in &mut pending {
+        let task = todo.task.as_mut().expect("pending task");
+        if let Some(uid) = &todo.uid {
+            task.id = uid
+                .parse()
+                .unwrap_or_else(|_| TaskId::from_external(&format!("ics:{uid}")));
+            ids.insert(uid.clone(), task.id);
+        }
+    }
+
+    let mut tasks = Vec::new();
+    for todo in pending {
+        let mut task = todo.task.expect("pending task");
+        if task.name.is_empty() {
+            task.name = "(untitled)".to_string();
+        }
+        task.parent = todo.parent.and_then(|uid| ids.get(&uid).copied());
+        task.depends_on = todo
+            .depends
+            .iter()
+            .filter_map(|uid| ids.get(uid).copied())
+            .collect();
+        for alarm in &todo.alarms {
+            if let Some(lead) = alarm_lead_time(alarm, todo.start, task.due) {
+                if !task.reminders.contains(&lead) {
+                    task.reminders.push(lead);
+                }
+            }
+        }
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+/// Converts a `TRIGGER` into seconds before the due date. Durations are
+/// relative to `start` unless the trigger says `RELATED=END`.
+fn alarm_lead_time(
+    trigger: &Property,
+    start: Option<Timestamp>,
+    due: Option<Timestamp>,
+) -> Option<i64> {
+    let due = due?;
+    let at = if trigger
+        .param("VALUE")
+        .is_some_and(|v| v.eq_ignore_ascii_case("DATE-TIME"))
+    {
+        parse_time(&trigger.value)?
+    } else {
+        let offset = parse_duration(&trigger.value)?;
+        match trigger.param("RELATED").map(str::to_ascii_uppercase) {
+            Some(related) if related == "END" => due.add_secs(offset),
+            None => start?.add_secs(offset),
+            Some(related) if related == "START" => start?.add_secs(offset),
+            Some(_) => return None,
+        }
+    };
+    Some(due.secs() - at.secs())
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            _ if escaped => {
+                current.push('\\');
+                current.push(c);
+                escaped = false;
+            }
+            '\\' => escaped = true,
+            ',' => items.push(unescape(&std::mem::take(&mut current))),
+            _ => current.push(c),
+        }
+    }
+    items.push(unescape(&current));
+    items
+}
+
+fn priority_from_ics(value: &str) -> Priority {
+    match value.trim().parse::<u32>().unwrap_or(0) {
+        1 | 2 => Priority::Critical,
+        3 | 4 => Priority::High,
+        6..=9 => Priority::Low,
+        _ => Priority::Medium,
+    }
+}
+
+fn status_from_ics(value: &str) -> Status {
+    match value.trim().to_ascii_uppercase().as_str() {
+        "IN-PROCESS" => Status::InProgress,
+        "COMPLETED" => Status::Done,
+        "CANCELLED" => Status::Cancelled,
+        _ => Status::Todo,
+    }
+}
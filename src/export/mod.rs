@@ -0,0 +1,144 @@
+//! Writers that turn the task list into files for other tools and people.
+
+pub mod csv;
+pub mod escpos;
+pub mod graph;
+pub mod html;
+pub mod ics;
+pub mod site;
+pub mod taskwarrior;
+pub mod toml;
+pub mod yaml;
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::error::Result;
+use crate::json::{self, Value};
+use crate::render::short_id;
+use crate::stats::History;
+use crate::storage;
+use crate::task::Task;
+
+/// The formats that carry every field of a task. Each has a writer and a
+/// reader, and reading what was written gives back the same tasks; see
+/// [`round_trip`]. A format is added here with both halves or not at all.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Codec {
+    Json,
+    Csv,
+    Toml,
+    Yaml,
+    Taskwarrior,
+}
+
+impl Codec {
+    pub const ALL: [Codec; 5] = [
+        Codec::Json,
+        Codec::Csv,
+        Codec::Toml,
+        Codec::Yaml,
+        Codec::Taskwarrior,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Codec::Json => "json",
+            Codec::Csv => "csv",
+            Codec::Toml => "toml",
+            Codec::Yaml => "yaml",
+            Codec::Taskwarrior => "taskwarrior",
+        }
+    }
+
+    /// Writes `tasks` one at a time. JSON is the data file layout without
+    /// history or templates.
+    pub fn write<W: Write, T: Borrow<Task>>(
+        self,
+        out: &mut W,
+        tasks: impl IntoIterator<Item = T>,
+    ) -> io::Result<()> {
+        match self {
+            Codec::Json => storage::write(out, tasks, &History::default(), &[], &[], &[]),
+            Codec::Csv => csv::write(out, tasks),
+            Codec::Toml => toml::write(out, tasks),
+            Codec::Yaml => yaml::write(out, tasks),
+            Codec::Taskwarrior => taskwarrior::write(out, tasks),
+        }
+    }
+
+    pub fn read(self, input: &str) -> Result<Vec<Task>> {
+        match self {
+            Codec::Json => Ok(storage::decode(&json::parse(input)?)?.tasks().to_vec()),
+            Codec::Csv => csv::parse(input),
+            Codec::Toml => toml::parse(input),
+            Codec::Yaml => yaml::parse(input),
+            Codec::Taskwarrior => taskwarrior::parse(input),
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// A field that came back different from a trip through a format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difference {
+    /// The short id of the task, or `-` for a change in the number of tasks.
+    pub task: String,
+    pub field: String,
+    pub written: String,
+    pub read: String,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} {} -> {}",
+            self.task, self.field, self.written, self.read
+        )
+    }
+}
+
+/// Writes `tasks` with `codec`, reads them back and lists what differs;
+/// empty when the trip changed nothing.
+pub fn round_trip(codec: Codec, tasks: &[Task]) -> Result<Vec<Difference>> {
+    let mut out = Vec::new();
+    codec.write(&mut out, tasks)?;
+    let text = String::from_utf8(out).expect("writers produce UTF-8");
+    let read = codec.read(&text)?;
+    let mut differences = Vec::new();
+    if read.len() != tasks.len() {
+        differences.push(Difference {
+            task: "-".into(),
+            field: "tasks".into(),
+            written: tasks.len().to_string(),
+            read: read.len().to_string(),
+        });
+    }
+    for (written, read) in tasks.iter().zip(&read) {
+        if written == read {
+            continue;
+        }
+        let (Value::Object(fields), copy) = (written.to_json(), read.to_json()) else {
+            unreachable!("a task serializes to an object")
+        };
+        for (field, value) in fields {
+            let other = copy.get(&field).cloned().unwrap_or(Value::Null);
+            if value != other {
+                differences.push(Difference {
+                    task: short_id(&written.id.to_string()).to_string(),
+                    field,
+                    written: value.to_compact(),
+                    read: other.to_compact(),
+                });
+            }
+        }
+    }
+    Ok(differences)
+}
@@ -0,0 +1,149 @@
+//! A read-only static site of the tasks, for any web server or GitHub
+//! Pages: `index.html` with a summary per project, a page per project under
+//! `projects/` and the tasks as JSON in `tasks.json`. Projects with dated
+//! tasks also get a calendar feed, `projects/<name>/calendar.ics`, to
+//! subscribe to in calendar apps.
+//!
+//! The same tasks give the same files byte for byte, so the directory can
+//! be committed and diffed. Pages carry no generation time; the date only
+//! matters for which tasks are marked overdue.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::config::{Glyphs, TagStyles};
+use crate::dates::Timestamp;
+use crate::export::html::{self, escape};
+use crate::export::ics;
+use crate::stats::History;
+use crate::storage;
+use crate::task::Task;
+
+/// Directory of the per-project pages, relative to the site root.
+pub const PROJECTS_DIR: &str = "projects";
+
+/// Name of a project's calendar feed, in a directory named like its page.
+pub const CALENDAR_FILE: &str = "calendar.ics";
+
+/// One file of the site.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Page {
+    /// Path relative to the site root, with `/` separators.
+    pub path: String,
+    pub contents: String,
+}
+
+/// Builds every file of the site for `tasks`.
+pub fn build(tasks: &[&Task], now: Timestamp, tags: &TagStyles, glyphs: &Glyphs) -> Vec<Page> {
+    let mut tasks = tasks.to_vec();
+    tasks.sort_by_key(|t| (t.created_at, t.id));
+    let mut projects: BTreeMap<&str, Vec<&Task>> = BTreeMap::new();
+    for &task in &tasks {
+        projects
+            .entry(task.project.as_deref().unwrap_or(""))
+            .or_default()
+            .push(task);
+    }
+    let slugs = slugs(projects.keys().copied());
+
+    let mut index = String::new();
+    html::open_page(&mut index, "Tasks");
+    let open = tasks.iter().filter(|t| t.status.is_open()).count();
+    index.push_str(&format!(
+        "<p class=\"meta\">{} tasks, {open} open. <a href=\"tasks.json\">tasks.json</a></p>\n",
+        tasks.len()
+    ));
+    index.push_str(
+        "<table>\n<tr><th>Project</th><th>Open</th><th>Overdue</th><th>Total</th></tr>\n",
+    );
+    let mut project_pages = Vec::new();
+    for ((project, tasks), slug) in projects.iter().zip(&slugs) {
+        let title = html::project_title(project);
+        let path = format!("{PROJECTS_DIR}/{slug}.html");
+        index.push_str(&format!(
+            "<tr><td><a href=\"{path}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape(title),
+            tasks.iter().filter(|t| t.status.is_open()).count(),
+            tasks.iter().filter(|t| t.is_overdue(now)).count(),
+            tasks.len()
+        ));
+        let dated: Vec<&Task> = tasks.iter().copied().filter(|t| t.due.is_some()).collect();
+        let calendar = format!("{slug}/{CALENDAR_FILE}");
+        let mut page = String::new();
+        html::open_page(&mut page, title);
+        page.push_str("<p class=\"meta\"><a href=\"../index.html\">All projects</a>");
+        if !dated.is_empty() {
+            page.push_str(&format!(" · <a href=\"{calendar}\">Calendar feed</a>"));
+        }
+        page.push_str("</p>\n");
+        html::status_tables(&mut page, tasks, now, tags, glyphs);
+        html::close_page(&mut page);
+        project_pages.push(Page {
+            path,
+            contents: page,
+        });
+        if !dated.is_empty() {
+            project_pages.push(Page {
+                path: format!("{PROJECTS_DIR}/{calendar}"),
+                contents: ics::feed(&dated, title),
+            });
+        }
+    }
+    index.push_str("</table>\n");
+    html::close_page(&mut index);
+
+    let mut json = Vec::new();
+    storage::write(
+        &mut json,
+        tasks.iter().copied(),
+        &History::default(),
+        &[],
+        &[],
+        &[],
+    )
+    .expect("writing to memory succeeds");
+    let mut pages = vec![
+        Page {
+            path: "index.html".into(),
+            contents: index,
+        },
+        Page {
+            path: "tasks.json".into(),
+            contents: String::from_utf8(json).expect("JSON is UTF-8"),
+        },
+    ];
+    pages.extend(project_pages);
+    pages
+}
+
+/// File names for `projects`, in the same order: lowercase letters, digits
+/// and dashes, made unique with a numeric suffix. Tasks without a project
+/// get `no-project`.
+fn slugs<'a>(projects: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut taken = BTreeSet::new();
+    let mut slugs = Vec::new();
+    for project in projects {
+        let mut base = String::new();
+        for c in html::project_title(project)
+            .chars()
+            .flat_map(char::to_lowercase)
+        {
+            if c.is_ascii_alphanumeric() {
+                base.push(c);
+            } else if !base.ends_with('-') {
+                base.push('-');
+            }
+        }
+        let base = match base.trim_matches('-') {
+            "" => "project".to_string(),
+            trimmed => trimmed.to_string(),
+        };
+        let mut slug = base.clone();
+        let mut n = 2;
+        while !taken.insert(slug.clone()) {
+            slug = format!("{base}-{n}");
+            n += 1;
+        }
+        slugs.push(slug);
+    }
+    slugs
+}
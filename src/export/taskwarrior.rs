@@ -0,0 +1,285 @@
+//! Taskwarrior's JSON, as written by `task export` and read by
+//! `task import`, and the reader that takes it back.
+//!
+//! The name is Taskwarrior's `description` and dates are in its compact
+//! form (`20240301T090000Z`). In-progress and review-pending tasks are
+//! `pending` with the exact status in a `state` attribute, and critical ones
+//! are priority `H` with `critical: "yes"`. The review log becomes
+//! annotations (`submitted by alice`). Fields Taskwarrior lacks are kept as
+//! user-defined attributes: `notes` for the description, `parent`,
+//! `related`, `reminders` (seconds), `starred_by` (names, all three
+//! comma-separated), `jira`, `notion`, `google`, `contact`, `body`, and
+//! `pinned`, `archived`, `private` and `review` set to `yes`. Taskwarrior
+//! keeps attributes it has no definition for, so they survive a trip
+//! through it.
+//!
+//! Other Taskwarrior data reads too: a `start` date makes a pending task in
+//! progress and other annotations are added to the description.
+
+use std::borrow::Borrow;
+use std::io::{self, Write};
+
+use crate::dates::Timestamp;
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+use crate::task::{Priority, ReviewAction, Status, Task, TaskId};
+
+const DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+pub fn write<W: Write, T: Borrow<Task>>(
+    out: &mut W,
+    tasks: impl IntoIterator<Item = T>,
+) -> io::Result<()> {
+    out.write_all(b"[")?;
+    let mut empty = true;
+    for task in tasks {
+        let separator = if empty { "\n" } else { ",\n" };
+        write!(out, "{separator}{}", to_value(task.borrow()).to_compact())?;
+        empty = false;
+    }
+    out.write_all(if empty { b"]\n" } else { b"\n]\n" })?;
+    out.flush()
+}
+
+fn to_value(task: &Task) -> Value {
+    let date = |t: Timestamp| t.format(DATE_FORMAT);
+    let ids = |ids: &[TaskId]| ids.iter().map(ToString::to_string).collect::<Vec<_>>();
+    let mut obj = Value::object();
+    obj.insert("uuid", task.id.to_string());
+    obj.insert("description", task.name.as_str());
+    let status = match task.status {
+        Status::Done => "completed",
+        Status::Cancelled => "deleted",
+        Status::Todo | Status::InProgress | Status::ReviewPending => "pending",
+    };
+    obj.insert("status", status);
+    if matches!(task.status, Status::InProgress | Status::ReviewPending) {
+        obj.insert("state", task.status.as_str());
+    }
+    obj.insert("entry", date(task.created_at));
+    obj.insert("modified", date(task.updated_at));
+    if let Some(due) = task.due {
+        obj.insert("due", date(due));
+    }
+    if let Some(end) = task.completed_at {
+        obj.insert("end", date(end));
+    }
+    if let Some(project) = &task.project {
+        obj.insert("project", project.as_str());
+    }
+    if !task.tags.is_empty() {
+        obj.insert("tags", task.tags.clone());
+    }
+    let priority = match task.priority {
+        Priority::Low => "L",
+        Priority::Medium => "M",
+        Priority::High | Priority::Critical => "H",
+    };
+    obj.insert("priority", priority);
+    if task.priority == Priority::Critical {
+        obj.insert("critical", "yes");
+    }
+    if !task.depends_on.is_empty() {
+        obj.insert("depends", ids(&task.depends_on));
+    }
+    if !task.review_log.is_empty() {
+        let annotations = task
+            .review_log
+            .iter()
+            .map(|event| {
+                let mut annotation = Value::object();
+                annotation.insert("entry", date(event.at));
+                annotation.insert(
+                    "description",
+                    format!("{} by {}", event.action.as_str(), event.by),
+                );
+                annotation
+            })
+            .collect();
+        obj.insert("annotations", Value::Array(annotations));
+    }
+    if !task.description.is_empty() {
+        obj.insert("notes", task.description.as_str());
+    }
+    if let Some(parent) = task.parent {
+        obj.insert("parent", parent.to_string());
+    }
+    if !task.related.is_empty() {
+        obj.insert("related", ids(&task.related).join(","));
+    }
+    if !task.reminders.is_empty() {
+        let reminders: Vec<String> = task.reminders.iter().map(ToString::to_string).collect();
+        obj.insert("reminders", reminders.join(","));
+    }
+    if let Some(jira) = &task.jira {
+        obj.insert("jira", jira.as_str());
+    }
+    if let Some(notion) = &task.notion {
+        obj.insert("notion", notion.as_str());
+    }
+    if let Some(google) = &task.google {
+        obj.insert("google", google.as_str());
+    }
+    if let Some(contact) = &task.contact {
+        obj.insert("contact", contact.as_str());
+    }
+    if !task.body.is_empty() {
+        obj.insert("body", task.body.as_str());
+    }
+    if !task.starred_by.is_empty() {
+        obj.insert("starred_by", task.starred_by.join(","));
+    }
+    for (key, set) in [
+        ("pinned", task.pinned),
+        ("archived", task.archived),
+        ("private", task.private),
+        ("review", task.review),
+    ] {
+        if set {
+            obj.insert(key, "yes");
+        }
+    }
+    obj
+}
+
+/// Reads the JSON array written by [`write`] or by `task export`.
+pub fn parse(input: &str) -> Result<Vec<Task>> {
+    let Value::Array(items) = json::parse(input)? else {
+        return Err(Error::Invalid(
+            "Taskwarrior data must be a JSON array of tasks".into(),
+        ));
+    };
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            from_value(item).map_err(|err| Error::Invalid(format!("task {}: {err}", index + 1)))
+        })
+        .collect()
+}
+
+fn from_value(item: &Value) -> Result<Task> {
+    let text = |key: &str| item.get(key).and_then(Value::as_str);
+    let date = |key: &str| -> Result<Value> {
+        text(key)
+            .map(parse_date)
+            .transpose()
+            .map(|time| time.map(Timestamp::to_rfc3339).into())
+    };
+    // Lists are arrays since Taskwarrior 2.6 and comma-separated before.
+    let list = |key: &str| -> Vec<Value> {
+        match item.get(key) {
+            Some(Value::Array(items)) => items.to_vec(),
+            Some(Value::String(list)) => list
+                .split(',')
+                .filter(|s| !s.is_empty())
+                .map(Value::from)
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+    let mut value = Value::object();
+    value.insert(
+        "id",
+        text("uuid").map_or_else(|| TaskId::generate().to_string(), str::to_string),
+    );
+    value.insert("name", text("description").unwrap_or_default());
+    let status = match (text("state"), text("status")) {
+        (Some(state), _) => state,
+        (None, Some("completed")) => "done",
+        (None, Some("deleted")) => "cancelled",
+        (None, _) if text("start").is_some() => "in-progress",
+        (None, _) => "todo",
+    };
+    value.insert("status", status);
+    let priority = match (text("critical"), text("priority")) {
+        (Some("yes"), _) => "critical",
+        (_, Some("H")) => "high",
+        (_, Some("L")) => "low",
+        _ => "medium",
+    };
+    value.insert("priority", priority);
+    value.insert("created_at", date("entry")?);
+    value.insert("updated_at", date("modified")?);
+    value.insert("due", date("due")?);
+    value.insert("completed_at", date("end")?);
+    value.insert("project", text("project"));
+    value.insert("tags", Value::Array(list("tags")));
+    value.insert("depends_on", Value::Array(list("depends")));
+    value.insert("related", Value::Array(list("related")));
+    value.insert("parent", text("parent"));
+    value.insert("jira", text("jira"));
+    value.insert("notion", text("notion"));
+    value.insert("google", text("google"));
+    value.insert("contact", text("contact"));
+    value.insert("body", text("body").unwrap_or_default());
+    value.insert("starred_by", Value::Array(list("starred_by")));
+    let reminders = list("reminders")
+        .iter()
+        .map(|lead| lead.as_str().and_then(|lead| lead.parse::<i64>().ok()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| Error::Invalid("`reminders` must be seconds".into()))?;
+    value.insert("reminders", reminders);
+    for key in ["pinned", "archived", "private", "review"] {
+        value.insert(key, text(key) == Some("yes"));
+    }
+    let mut description = text("notes").unwrap_or_default().to_string();
+    let mut review_log = Vec::new();
+    for annotation in item
+        .get("annotations")
+        .and_then(Value::as_array)
+        .unwrap_or_default()
+    {
+        let note = annotation
+            .get("description")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let event = [ReviewAction::Submitted, ReviewAction::Approved]
+            .into_iter()
+            .find_map(|action| {
+                let by = note.strip_prefix(action.as_str())?.strip_prefix(" by ")?;
+                Some((action, by))
+            });
+        match (event, annotation.get("entry").and_then(Value::as_str)) {
+            (Some((action, by)), Some(entry)) => {
+                let mut event = Value::object();
+                event.insert("action", action.as_str());
+                event.insert("by", by);
+                event.insert("at", parse_date(entry)?.to_rfc3339());
+                review_log.push(event);
+            }
+            _ => {
+                if !description.is_empty() {
+                    description.push('\n');
+                }
+                description.push_str(note);
+            }
+        }
+    }
+    value.insert("description", description);
+    value.insert("review_log", Value::Array(review_log));
+    Task::from_json(&value)
+}
+
+/// A Taskwarrior date, `20240301T090000Z`.
+fn parse_date(input: &str) -> Result<Timestamp> {
+    let invalid = || Error::Invalid(format!("invalid Taskwarrior date `{input}`"));
+    let digits = |range: std::ops::Range<usize>| {
+        input
+            .get(range)
+            .filter(|part| part.bytes().all(|b| b.is_ascii_digit()))
+            .ok_or_else(invalid)
+    };
+    if input.len() != 16 || input.as_bytes()[8] != b'T' || !input.ends_with('Z') {
+        return Err(invalid());
+    }
+    Timestamp::parse_rfc3339(&format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        digits(0..4)?,
+        digits(4..6)?,
+        digits(6..8)?,
+        digits(9..11)?,
+        digits(11..13)?,
+        digits(13..15)?
+    ))
+}
@@ -0,0 +1,76 @@
+//! TOML export, one `[[tasks]]` table per task with the fields of the JSON
+//! data file, and the reader that takes it back. TOML has no null, so
+//! absent values are left out.
+
+use std::borrow::Borrow;
+use std::io::{self, Write};
+
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+use crate::task::Task;
+
+pub fn write<W: Write, T: Borrow<Task>>(
+    out: &mut W,
+    tasks: impl IntoIterator<Item = T>,
+) -> io::Result<()> {
+    let mut first = true;
+    for task in tasks {
+        let Value::Object(fields) = task.borrow().to_json() else {
+            unreachable!("a task serializes to an object")
+        };
+        let mut table = String::from(if first {
+            "[[tasks]]\n"
+        } else {
+            "\n[[tasks]]\n"
+        });
+        for (key, value) in &fields {
+            if !value.is_null() {
+                table.push_str(key);
+                table.push_str(" = ");
+                inline(&mut table, value);
+                table.push('\n');
+            }
+        }
+        out.write_all(table.as_bytes())?;
+        first = false;
+    }
+    out.flush()
+}
+
+/// `value` on one line: arrays in brackets, objects as inline tables.
+fn inline(out: &mut String, value: &Value) {
+    match value {
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                inline(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            out.push('{');
+            for (i, (key, field)) in fields.iter().filter(|(_, f)| !f.is_null()).enumerate() {
+                out.push_str(if i > 0 { ", " } else { " " });
+                out.push_str(key);
+                out.push_str(" = ");
+                inline(out, field);
+            }
+            out.push_str(" }");
+        }
+        // JSON escapes are valid in TOML basic strings.
+        Value::String(s) => json::write_string(out, s),
+        other => out.push_str(&other.to_compact()),
+    }
+}
+
+/// Reads tasks written by [`write`].
+pub fn parse(input: &str) -> Result<Vec<Task>> {
+    match crate::toml::parse(input)?.get("tasks") {
+        None => Ok(Vec::new()),
+        Some(Value::Array(tasks)) => tasks.iter().map(Task::from_json).collect(),
+        Some(_) => Err(Error::Invalid("`tasks` must be an array of tables".into())),
+    }
+}
@@ -0,0 +1,73 @@
+//! YAML export, a `tasks:` sequence with the fields of the JSON data file,
+//! and the reader that takes it back. Values are written in flow style, so
+//! strings are always double-quoted and nothing is left to YAML's guesses
+//! about plain scalars.
+
+use std::borrow::Borrow;
+use std::io::{self, Write};
+
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+use crate::task::Task;
+
+pub fn write<W: Write, T: Borrow<Task>>(
+    out: &mut W,
+    tasks: impl IntoIterator<Item = T>,
+) -> io::Result<()> {
+    let mut empty = true;
+    out.write_all(b"tasks:")?;
+    for task in tasks {
+        let Value::Object(fields) = task.borrow().to_json() else {
+            unreachable!("a task serializes to an object")
+        };
+        let mut item = String::new();
+        for (i, (key, value)) in fields.iter().enumerate() {
+            item.push_str(if i == 0 { "\n  - " } else { "\n    " });
+            item.push_str(key);
+            item.push_str(": ");
+            flow(&mut item, value);
+        }
+        out.write_all(item.as_bytes())?;
+        empty = false;
+    }
+    out.write_all(if empty { b" []\n" } else { b"\n" })?;
+    out.flush()
+}
+
+fn flow(out: &mut String, value: &Value) {
+    match value {
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                flow(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            out.push('{');
+            for (i, (key, field)) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                out.push_str(key);
+                out.push_str(": ");
+                flow(out, field);
+            }
+            out.push('}');
+        }
+        Value::String(s) => json::write_string(out, s),
+        other => out.push_str(&other.to_compact()),
+    }
+}
+
+/// Reads tasks written by [`write`].
+pub fn parse(input: &str) -> Result<Vec<Task>> {
+    match crate::yaml::parse(input)?.get("tasks") {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(tasks)) => tasks.iter().map(Task::from_json).collect(),
+        Some(_) => Err(Error::Invalid("`tasks` must be a sequence".into())),
+    }
+}
@@ -0,0 +1,218 @@
+//! Task predicates, built either in code or from the filter expression
+//! language accepted by `list`.
+
+mod parse;
+
+use std::cmp::Ordering;
+
+use crate::dates::{DateRange, Timestamp};
+use crate::task::{Priority, Status, Task};
+
+pub(crate) use parse::either;
+pub use parse::{parse, FIELDS, GRAMMAR};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    pub fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Eq => ordering == Ordering::Equal,
+            Comparison::Ne => ordering != Ordering::Equal,
+            Comparison::Lt => ordering == Ordering::Less,
+            Comparison::Le => ordering != Ordering::Greater,
+            Comparison::Gt => ordering == Ordering::Greater,
+            Comparison::Ge => ordering != Ordering::Less,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Eq => "=",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+}
+
+/// Which date of a task a [`Filter::Date`] compares.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateField {
+    Due,
+    Created,
+    Completed,
+}
+
+impl DateField {
+    fn of(self, task: &Task) -> Option<Timestamp> {
+        match self {
+            DateField::Due => task.due,
+            DateField::Created => Some(task.created_at),
+            DateField::Completed => task.completed_at,
+        }
+    }
+}
+
+/// Status predicate: a concrete status or one of the open/closed groups.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatusMatch {
+    Is(Status),
+    Open,
+    Closed,
+}
+
+/// A boolean expression over task fields.
+///
+/// Build one with the constructor functions and combine with [`Filter::and`],
+/// [`Filter::or`] and [`Filter::negate`], or parse one with [`parse`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    Any,
+    Priority(Comparison, Priority),
+    /// Compares calendar days; tasks without the date never match.
+    Date(DateField, Comparison, Timestamp),
+    Status(StatusMatch),
+    Tag(String),
+    Project(String),
+    /// Case-insensitive substring of the name or description.
+    Text(String),
+    Overdue,
+    Private,
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    pub fn priority(cmp: Comparison, priority: Priority) -> Self {
+        Filter::Priority(cmp, priority)
+    }
+
+    pub fn due(cmp: Comparison, when: Timestamp) -> Self {
+        Filter::Date(DateField::Due, cmp, when)
+    }
+
+    /// Compares `field` with a range of days: `=` matches days inside it
+    /// and `!=` days outside; `<` and `>=` compare with its first day, `<=`
+    /// and `>` with its last.
+    pub fn date_range(field: DateField, cmp: Comparison, range: DateRange) -> Self {
+        let first = |cmp| Filter::Date(field, cmp, range.start);
+        let last = |cmp| Filter::Date(field, cmp, range.end);
+        match cmp {
+            Comparison::Eq => first(Comparison::Ge).and(last(Comparison::Le)),
+            Comparison::Ne => first(Comparison::Lt).or(last(Comparison::Gt)),
+            Comparison::Lt | Comparison::Ge => first(cmp),
+            Comparison::Le | Comparison::Gt => last(cmp),
+        }
+    }
+
+    pub fn status(status: StatusMatch) -> Self {
+        Filter::Status(status)
+    }
+
+    pub fn tag(tag: impl Into<String>) -> Self {
+        Filter::Tag(tag.into())
+    }
+
+    pub fn project(project: impl Into<String>) -> Self {
+        Filter::Project(project.into())
+    }
+
+    pub fn text(text: impl Into<String>) -> Self {
+        Filter::Text(text.into())
+    }
+
+    pub fn and(self, other: Filter) -> Self {
+        match (self, other) {
+            (Filter::Any, other) | (other, Filter::Any) => other,
+            (a, b) => Filter::And(Box::new(a), Box::new(b)),
+        }
+    }
+
+    pub fn or(self, other: Filter) -> Self {
+        Filter::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn negate(self) -> Self {
+        Filter::Not(Box::new(self))
+    }
+
+    pub fn matches(&self, task: &Task, now: Timestamp) -> bool {
+        match self {
+            Filter::Any => true,
+            Filter::Priority(cmp, priority) => cmp.holds(task.priority.cmp(priority)),
+            Filter::Date(field, cmp, when) => field
+                .of(task)
+                .is_some_and(|date| cmp.holds(date.day_number().cmp(&when.day_number()))),
+            Filter::Status(StatusMatch::Is(status)) => task.status == *status,
+            Filter::Status(StatusMatch::Open) => task.status.is_open(),
+            Filter::Status(StatusMatch::Closed) => !task.status.is_open(),
+            Filter::Tag(tag) => task.has_tag(tag),
+            Filter::Project(project) => task
+                .project
+                .as_deref()
+                .is_some_and(|p| p.eq_ignore_ascii_case(project)),
+            Filter::Text(text) => {
+                let needle = text.to_lowercase();
+                task.name.to_lowercase().contains(&needle)
+                    || task.description.to_lowercase().contains(&needle)
+            }
+            Filter::Overdue => task.is_overdue(now),
+            Filter::Private => task.private,
+            Filter::And(a, b) => a.matches(task, now) && b.matches(task, now),
+            Filter::Or(a, b) => a.matches(task, now) || b.matches(task, now),
+            Filter::Not(inner) => !inner.matches(task, now),
+        }
+    }
+
+    /// Whether the expression says anything about status, in which case
+    /// listings should not add their default "open tasks only" restriction.
+    pub fn mentions_status(&self) -> bool {
+        match self {
+            Filter::Status(_) => true,
+            Filter::Date(DateField::Completed, ..) => true,
+            Filter::And(a, b) | Filter::Or(a, b) => a.mentions_status() || b.mentions_status(),
+            Filter::Not(inner) => inner.mentions_status(),
+            _ => false,
+        }
+    }
+
+    /// Whether the expression names a project, in which case listings show
+    /// its tasks even if the project is archived.
+    pub fn mentions_project(&self) -> bool {
+        match self {
+            Filter::Project(_) => true,
+            Filter::And(a, b) | Filter::Or(a, b) => a.mentions_project() || b.mentions_project(),
+            Filter::Not(inner) => inner.mentions_project(),
+            _ => false,
+        }
+    }
+
+    /// The text the expression searches for, leaving out negated terms;
+    /// used to highlight matches.
+    pub fn search_terms(&self) -> Vec<&str> {
+        match self {
+            Filter::Text(text) => vec![text.as_str()],
+            Filter::And(a, b) | Filter::Or(a, b) => {
+                let mut terms = a.search_terms();
+                terms.extend(b.search_terms());
+                terms
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn apply<'a>(&self, tasks: &'a [Task], now: Timestamp) -> Vec<&'a Task> {
+        tasks.iter().filter(|t| self.matches(t, now)).collect()
+    }
+}
@@ -0,0 +1,354 @@
+//! Parser for filter expressions such as
+//! `priority>=high and due<friday and (tag:work or project:"home office")`.
+//!
+//! ```text
+//! expr      := and_expr (("or" | "||") and_expr)*
+//! and_expr  := unary (["and" | "&&"] unary)*
+//! unary     := ("not" | "!") unary | "(" expr ")" | predicate
+//! predicate := field ":" value | field cmp value | word | "quoted text"
+//! cmp       := "=" | "==" | "!=" | "<" | "<=" | ">" | ">="
+//! ```
+//!
+//! Fields are listed in [`FIELDS`]: `priority`, `due`, `created`,
+//! `completed` (comparable), and `status`, `tag`, `project`, `is` (matched
+//! with `:`). Dates accept anything
+//! [`crate::dates::parse_when`] does and compare by calendar day; periods
+//! such as `this-week` or `last-month` compare as ranges of days (see
+//! [`Filter::date_range`]). A bare word matches the name or description.
+
+use crate::dates::{DateRange, Timestamp};
+use crate::error::{Error, Result};
+use crate::filter::{Comparison, DateField, Filter, StatusMatch};
+use crate::task::Priority;
+
+/// The grammar above, for `help filters`.
+pub const GRAMMAR: &str = r#"expr      := and_expr (("or" | "||") and_expr)*
+and_expr  := unary (["and" | "&&"] unary)*
+unary     := ("not" | "!") unary | "(" expr ")" | predicate
+predicate := field ":" value | field cmp value | word | "quoted text"
+cmp       := "=" | "==" | "!=" | "<" | "<=" | ">" | ">="
+"#;
+
+/// The fields a predicate can name and the values each takes.
+pub const FIELDS: &[(&str, &str)] = &[
+    (
+        "priority",
+        "low, medium, high or critical (or pri); compared with cmp",
+    ),
+    (
+        "due",
+        "a date such as friday or 2030-03-01, or a period such as this-week; compared by day",
+    ),
+    ("created", "as due"),
+    ("completed", "as due"),
+    (
+        "status",
+        "todo, in-progress, review-pending, done, cancelled, open or closed",
+    ),
+    ("tag", "a tag, without the +"),
+    ("project", "a project name"),
+    ("is", "overdue, private, open or closed"),
+];
+
+/// Parses `input` into a [`Filter`]; `now` anchors relative dates.
+pub fn parse(input: &str, now: Timestamp) -> Result<Filter> {
+    let tokens = lex(input).map_err(|err| err.render(input))?;
+    if tokens.is_empty() {
+        return Ok(Filter::Any);
+    }
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        now,
+        end: input.chars().count(),
+    };
+    let filter = parser.expr().map_err(|err| err.render(input))?;
+    if let Some(token) = parser.tokens.get(parser.pos) {
+        let message = match token.kind {
+            Kind::RParen => "unbalanced `)`".to_string(),
+            _ => format!("unexpected `{}`", token.text),
+        };
+        return Err(SyntaxError::at(token.start, message).render(input));
+    }
+    Ok(filter)
+}
+
+struct SyntaxError {
+    column: usize,
+    message: String,
+}
+
+impl SyntaxError {
+    fn at(column: usize, message: impl Into<String>) -> Self {
+        SyntaxError {
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Formats the error with the expression and a caret under the offending
+    /// position.
+    fn render(self, input: &str) -> Error {
+        Error::Invalid(format!(
+            "invalid filter: {} (column {})\n  {}\n  {}^",
+            self.message,
+            self.column + 1,
+            input,
+            " ".repeat(self.column)
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Word,
+    Quoted,
+    LParen,
+    RParen,
+    Colon,
+    Cmp(Comparison),
+    Not,
+    And,
+    Or,
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    kind: Kind,
+    text: String,
+    start: usize,
+}
+
+fn lex(input: &str) -> std::result::Result<Vec<Token>, SyntaxError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let next = chars.get(i + 1).copied();
+        let (kind, len) = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('(', _) => (Kind::LParen, 1),
+            (')', _) => (Kind::RParen, 1),
+            (':', _) => (Kind::Colon, 1),
+            ('<', Some('=')) => (Kind::Cmp(Comparison::Le), 2),
+            ('<', _) => (Kind::Cmp(Comparison::Lt), 1),
+            ('>', Some('=')) => (Kind::Cmp(Comparison::Ge), 2),
+            ('>', _) => (Kind::Cmp(Comparison::Gt), 1),
+            ('=', Some('=')) => (Kind::Cmp(Comparison::Eq), 2),
+            ('=', _) => (Kind::Cmp(Comparison::Eq), 1),
+            ('!', Some('=')) => (Kind::Cmp(Comparison::Ne), 2),
+            ('!', _) => (Kind::Not, 1),
+            ('&', Some('&')) => (Kind::And, 2),
+            ('|', Some('|')) => (Kind::Or, 2),
+            ('"' | '\'', _) => {
+                let close = chars[i + 1..]
+                    .iter()
+                    .position(|&ch| ch == c)
+                    .ok_or_else(|| SyntaxError::at(start, "unterminated quote"))?;
+                let text: String = chars[i + 1..i + 1 + close].iter().collect();
+                tokens.push(Token {
+                    kind: Kind::Quoted,
+                    text,
+                    start,
+                });
+                i += close + 2;
+                continue;
+            }
+            _ => {
+                let len = chars[i..]
+                    .iter()
+                    .position(|&ch| ch.is_whitespace() || "()<>=!:\"'".contains(ch))
+                    .unwrap_or(chars.len() - i);
+                if len == 0 {
+                    return Err(SyntaxError::at(start, format!("unexpected `{c}`")));
+                }
+                let text: String = chars[i..i + len].iter().collect();
+                let kind = match text.to_ascii_lowercase().as_str() {
+                    "and" => Kind::And,
+                    "or" => Kind::Or,
+                    "not" => Kind::Not,
+                    _ => Kind::Word,
+                };
+                (kind, len)
+            }
+        };
+        tokens.push(Token {
+            kind,
+            text: chars[i..i + len].iter().collect(),
+            start,
+        });
+        i += len;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    now: Timestamp,
+    end: usize,
+}
+
+type Parsed<T> = std::result::Result<T, SyntaxError>;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_kind(&self) -> Option<Kind> {
+        self.peek().map(|t| t.kind)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expr(&mut self) -> Parsed<Filter> {
+        let mut filter = self.and_expr()?;
+        while self.peek_kind() == Some(Kind::Or) {
+            self.pos += 1;
+            filter = filter.or(self.and_expr()?);
+        }
+        Ok(filter)
+    }
+
+    fn and_expr(&mut self) -> Parsed<Filter> {
+        let mut filter = self.unary()?;
+        loop {
+            match self.peek_kind() {
+                Some(Kind::And) => self.pos += 1,
+                Some(Kind::Word | Kind::Quoted | Kind::LParen | Kind::Not) => {}
+                _ => return Ok(filter),
+            }
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Parsed<Filter> {
+        let Some(token) = self.next() else {
+            return Err(SyntaxError::at(self.end, "expression ends too early"));
+        };
+        match token.kind {
+            Kind::Not => Ok(self.unary()?.negate()),
+            Kind::LParen => {
+                let inner = self.expr()?;
+                match self.next() {
+                    Some(t) if t.kind == Kind::RParen => Ok(inner),
+                    _ => Err(SyntaxError::at(token.start, "`(` is never closed")),
+                }
+            }
+            Kind::Quoted => Ok(Filter::Text(token.text)),
+            Kind::Word => self.predicate(token),
+            _ => Err(SyntaxError::at(
+                token.start,
+                format!("expected a condition, found `{}`", token.text),
+            )),
+        }
+    }
+
+    fn value(&mut self, after: &Token, op: &str) -> Parsed<(String, usize)> {
+        match self.next() {
+            Some(t) if matches!(t.kind, Kind::Word | Kind::Quoted) => Ok((t.text, t.start)),
+            Some(t) => Err(SyntaxError::at(
+                t.start,
+                format!("expected a value after `{}{op}`", after.text),
+            )),
+            None => Err(SyntaxError::at(
+                self.end,
+                format!("expected a value after `{}{op}`", after.text),
+            )),
+        }
+    }
+
+    fn predicate(&mut self, field: Token) -> Parsed<Filter> {
+        let cmp = match self.peek_kind() {
+            Some(Kind::Colon) => Comparison::Eq,
+            Some(Kind::Cmp(cmp)) => cmp,
+            _ => return Ok(Filter::Text(field.text)),
+        };
+        let op = self.next().map(|t| t.text).unwrap_or_default();
+        let (value, start) = self.value(&field, &op)?;
+        let name = field.text.to_ascii_lowercase();
+        let date_field = match name.as_str() {
+            "due" => Some(DateField::Due),
+            "created" => Some(DateField::Created),
+            "completed" => Some(DateField::Completed),
+            _ => None,
+        };
+        if let Some(date_field) = date_field {
+            let range = DateRange::parse(&value, self.now)
+                .map_err(|err| SyntaxError::at(start, err.to_string()))?;
+            return Ok(Filter::date_range(date_field, cmp, range));
+        }
+        if name == "priority" || name == "pri" {
+            let priority: Priority = value
+                .parse()
+                .map_err(|err: Error| SyntaxError::at(start, err.to_string()))?;
+            return Ok(Filter::Priority(cmp, priority));
+        }
+
+        let filter = match name.as_str() {
+            "status" => Filter::Status(match value.to_ascii_lowercase().as_str() {
+                "open" => StatusMatch::Open,
+                "closed" => StatusMatch::Closed,
+                other => StatusMatch::Is(
+                    other
+                        .parse()
+                        .map_err(|err: Error| SyntaxError::at(start, err.to_string()))?,
+                ),
+            }),
+            "tag" | "tags" => Filter::Tag(value.trim_start_matches('#').to_string()),
+            "project" | "proj" => Filter::Project(value),
+            "is" => match value.to_ascii_lowercase().as_str() {
+                "overdue" => Filter::Overdue,
+                "private" => Filter::Private,
+                "open" => Filter::Status(StatusMatch::Open),
+                "closed" => Filter::Status(StatusMatch::Closed),
+                _ => {
+                    return Err(SyntaxError::at(
+                        start,
+                        format!(
+                        "unknown `is:` value `{value}` (expected overdue, private, open or closed)"
+                    ),
+                    ))
+                }
+            },
+            _ => {
+                return Err(SyntaxError::at(
+                    field.start,
+                    format!(
+                        "unknown field `{}` (expected {})",
+                        field.text,
+                        either(FIELDS)
+                    ),
+                ))
+            }
+        };
+        match cmp {
+            Comparison::Eq => Ok(filter),
+            Comparison::Ne => Ok(filter.negate()),
+            _ => Err(SyntaxError::at(
+                field.start,
+                format!("`{}` only supports `:`, `=` and `!=`", field.text),
+            )),
+        }
+    }
+}
+
+/// The names in `fields` as `a, b or c`.
+pub(crate) fn either(fields: &[(&str, &str)]) -> String {
+    let names: Vec<&str> = fields.iter().map(|&(name, _)| name).collect();
+    match names.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} or {last}", rest.join(", ")),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,74 @@
+//! Recognising imported tasks that are already in the list, so importing the
+//! same file twice does not duplicate it.
+//!
+//! A task matches by id, which survives `export` and is derived from the
+//! source's own id where it has one (see [`TaskId::from_external`]), or else
+//! by name and due date: the same words ignoring case and spacing, due on
+//! the same day or both without a due date.
+//!
+//! [`TaskId::from_external`]: crate::task::TaskId::from_external
+
+use crate::task::Task;
+
+/// Why an imported task was taken for an existing one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Match {
+    Id,
+    NameAndDue,
+}
+
+impl Match {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Match::Id => "same id",
+            Match::NameAndDue => "same name and due date",
+        }
+    }
+}
+
+/// The task in `existing` that `imported` is a copy of, if any. An id match
+/// wins over a name match elsewhere in the list.
+pub fn find<'a>(existing: &'a [Task], imported: &Task) -> Option<(&'a Task, Match)> {
+    if let Some(task) = existing.iter().find(|t| t.id == imported.id) {
+        return Some((task, Match::Id));
+    }
+    let name = normalize(&imported.name);
+    let day = imported.due.map(|due| due.end_of_day());
+    existing
+        .iter()
+        .find(|t| t.due.map(|due| due.end_of_day()) == day && normalize(&t.name) == name)
+        .map(|task| (task, Match::NameAndDue))
+}
+
+/// Copies what an import carries from `imported` onto `task`: name,
+/// description, priority, status, due date and reminders, plus any new tags.
+/// Project, links and review state exist only here and are kept. Returns
+/// whether anything changed.
+pub fn update(task: &mut Task, imported: &Task) -> bool {
+    let before = task.clone();
+    task.name = imported.name.clone();
+    if !imported.description.is_empty() {
+        task.description = imported.description.clone();
+    }
+    task.priority = imported.priority;
+    if task.status != imported.status {
+        task.set_status(imported.status);
+        task.completed_at = imported.completed_at.or(task.completed_at);
+    }
+    task.due = imported.due;
+    task.reminders = imported.reminders.clone();
+    for tag in &imported.tags {
+        if !task.tags.contains(tag) {
+            task.tags.push(tag.clone());
+        }
+    }
+    *task != before
+}
+
+/// Lowercase words separated by single spaces.
+fn normalize(name: &str) -> String {
+    name.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
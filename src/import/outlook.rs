@@ -0,0 +1,316 @@
+//! Outlook Tasks import, from the classic CSV export and from the JSON task
+//! schema used by Exchange / Microsoft To Do (`todoTask`).
+//!
+//! Importance maps to priority (`High`, `Normal`, `Low`), `Notes` or `body` to
+//! the description and categories to tags. Outlook due dates carry no time,
+//! so they resolve to the end of the day like date-only input elsewhere. An
+//! active reminder becomes a lead time before the due date; a reminder on a
+//! task without a due date makes the reminder time the due time. The
+//! `Waiting on someone else` and `Deferred` statuses stay open and are kept
+//! as the tags `waiting` and `deferred`. An `ID` column or `id` field gives
+//! the task a stable id, so importing it again finds the same task.
+
+use crate::csv::Table;
+use crate::dates::{DateTime, Timestamp};
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+use crate::task::{Priority, Status, Task, TaskId};
+
+/// How to read numeric dates such as `03/04/2024`, which Outlook writes in
+/// the exporting machine's locale.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DateOrder {
+    /// `MM/DD/YYYY`, the US default.
+    #[default]
+    MonthFirst,
+    /// `DD/MM/YYYY` and `DD.MM.YYYY`.
+    DayFirst,
+}
+
+/// Parses either export format, choosing by the first non-blank character.
+pub fn parse(input: &str, order: DateOrder) -> Result<Vec<Task>> {
+    let trimmed = input.trim_start_matches('\u{feff}').trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        parse_json(trimmed)
+    } else {
+        parse_csv(input, order)
+    }
+}
+
+pub fn parse_csv(input: &str, order: DateOrder) -> Result<Vec<Task>> {
+    let table = Table::parse(input)?;
+    if table.column("Subject").is_none() {
+        return Err(Error::Invalid(
+            "not an Outlook tasks export: no `Subject` column".into(),
+        ));
+    }
+    let mut tasks = Vec::new();
+    for (index, row) in table.rows().enumerate() {
+        let line = index + 2;
+        let date = |column: &str| -> Result<Option<DateTime>> {
+            row.get(column)
+                .map(|text| {
+                    parse_date(text, order).ok_or_else(|| {
+                        Error::Invalid(format!(
+                            "row {line}: unrecognised date `{text}` in `{column}`"
+                        ))
+                    })
+                })
+                .transpose()
+        };
+
+        let mut task = Task::new(row.get("Subject").unwrap_or("(untitled)"));
+        task.description = row.get("Notes").unwrap_or_default().to_string();
+        if let Some(importance) = row.get("Priority").or_else(|| row.get("Importance")) {
+            task.priority = priority_from_importance(importance);
+        }
+        let (status, tag) = status_from_outlook(row.get("Status").unwrap_or_default());
+        task.set_status(status);
+        task.tags.extend(tag.map(str::to_string));
+        if let Some(categories) = row.get("Categories") {
+            add_categories(&mut task, categories.split([';', ',']));
+        }
+        task.due = date("Due Date")?
+            .and_then(|d| d.to_local_timestamp())
+            .map(Timestamp::end_of_day);
+        if let Some(completed) = date("Date Completed")?.and_then(|d| d.to_local_timestamp()) {
+            if task.status == Status::Done {
+                task.completed_at = Some(completed);
+            }
+        }
+
+        let reminder_on = row.get("Reminder On/Off").is_some_and(is_true);
+        if let (true, Some(mut at)) = (reminder_on, date("Reminder Date")?) {
+            if let Some(time) = row.get("Reminder Time") {
+                let (hour, minute, second) = parse_time_of_day(time).ok_or_else(|| {
+                    Error::Invalid(format!("row {line}: unrecognised time `{time}`"))
+                })?;
+                at.hour = hour;
+                at.minute = minute;
+                at.second = second;
+            }
+            if let Some(at) = at.to_local_timestamp() {
+                add_reminder(&mut task, at);
+            }
+        }
+        if let Some(id) = row.get("ID") {
+            task.id = TaskId::from_external(&format!("outlook:{id}"));
+        }
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+/// Reads a `todoTask` object, an array of them, or a collection response
+/// (`{"value": [...]}`).
+pub fn parse_json(input: &str) -> Result<Vec<Task>> {
+    let value = json::parse(input)?;
+    let items = match &value {
+        Value::Array(items) => items.as_slice(),
+        Value::Object(_) => match value.get("value").and_then(Value::as_array) {
+            Some(items) => items,
+            None => std::slice::from_ref(&value),
+        },
+        _ => return Err(Error::Invalid("expected a task object or array".into())),
+    };
+    items.iter().map(task_from_json).collect()
+}
+
+fn task_from_json(value: &Value) -> Result<Task> {
+    let text = |key: &str| value.get(key).and_then(Value::as_str).map(str::trim);
+    let mut task = Task::new(text("title").or(text("subject")).unwrap_or("(untitled)"));
+    if let Some(id) = text("id").filter(|id| !id.is_empty()) {
+        task.id = TaskId::from_external(&format!("outlook:{id}"));
+    }
+    if let Some(body) = value.get("body") {
+        let content = body
+            .get("content")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let html = body
+            .get("contentType")
+            .and_then(Value::as_str)
+            .is_some_and(|t| t.eq_ignore_ascii_case("html"));
+        task.description = if html {
+            strip_html(content)
+        } else {
+            content.trim().to_string()
+        };
+    }
+    if let Some(importance) = text("importance") {
+        task.priority = priority_from_importance(importance);
+    }
+    let (status, tag) = status_from_outlook(text("status").unwrap_or_default());
+    task.set_status(status);
+    task.tags.extend(tag.map(str::to_string));
+    if let Some(categories) = value.get("categories").and_then(Value::as_array) {
+        add_categories(&mut task, categories.iter().filter_map(Value::as_str));
+    }
+    if let Some(created) = text("createdDateTime").and_then(parse_graph_time) {
+        task.created_at = created;
+    }
+    task.due = json_time(value, "dueDateTime")?.map(Timestamp::end_of_day);
+    if task.status == Status::Done {
+        if let Some(completed) = json_time(value, "completedDateTime")? {
+            task.completed_at = Some(completed);
+        }
+    }
+    let reminder_on = value
+        .get("isReminderOn")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    if reminder_on {
+        if let Some(at) = json_time(value, "reminderDateTime")? {
+            add_reminder(&mut task, at);
+        }
+    }
+    Ok(task)
+}
+
+/// Reads a `dateTimeTimeZone` object. Times are taken as UTC, which is what
+/// the export uses unless the client asked for another zone.
+fn json_time(value: &Value, key: &str) -> Result<Option<Timestamp>> {
+    let Some(field) = value.get(key).filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
+    let text = field
+        .get("dateTime")
+        .or(Some(field))
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::Invalid(format!("`{key}` must hold a `dateTime`")))?;
+    parse_graph_time(text)
+        .map(Some)
+        .ok_or_else(|| Error::Invalid(format!("unrecognised date `{text}` in `{key}`")))
+}
+
+/// Graph times omit the offset (`2024-06-12T00:00:00.0000000`).
+fn parse_graph_time(text: &str) -> Option<Timestamp> {
+    let has_offset =
+        text.ends_with(['Z', 'z']) || text.bytes().rev().take(6).any(|b| b == b'+' || b == b'-');
+    if has_offset {
+        Timestamp::parse_rfc3339(text).ok()
+    } else {
+        Timestamp::parse_rfc3339(&format!("{text}Z")).ok()
+    }
+}
+
+fn priority_from_importance(value: &str) -> Priority {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "high" => Priority::High,
+        "low" => Priority::Low,
+        _ => Priority::Medium,
+    }
+}
+
+/// Maps both the CSV wording (`Not Started`) and the JSON one
+/// (`notStarted`), returning a tag to keep statuses that have no equivalent.
+fn status_from_outlook(value: &str) -> (Status, Option<&'static str>) {
+    let key = value
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match key.as_str() {
+        "inprogress" => (Status::InProgress, None),
+        "completed" => (Status::Done, None),
+        "waitingonsomeoneelse" | "waitingonothers" => (Status::Todo, Some("waiting")),
+        "deferred" => (Status::Todo, Some("deferred")),
+        _ => (Status::Todo, None),
+    }
+}
+
+/// Outlook category names may contain spaces (`Red Category`); tags may not.
+fn add_categories<'a>(task: &mut Task, categories: impl Iterator<Item = &'a str>) {
+    for category in categories {
+        let tag = category.split_whitespace().collect::<Vec<_>>().join("-");
+        if !tag.is_empty() && !task.has_tag(&tag) {
+            task.tags.push(tag);
+        }
+    }
+}
+
+fn add_reminder(task: &mut Task, at: Timestamp) {
+    let due = *task.due.get_or_insert(at);
+    let lead = due.secs() - at.secs();
+    if lead >= 0 && !task.reminders.contains(&lead) {
+        task.reminders.push(lead);
+    }
+}
+
+fn is_true(value: &str) -> bool {
+    matches!(
+        value.to_ascii_lowercase().as_str(),
+        "true" | "on" | "yes" | "1"
+    )
+}
+
+/// Accepts `YYYY-MM-DD` and numeric dates separated by `/`, `.` or `-` with
+/// a four-digit year last. Dots always mean day first. Outlook writes
+/// `None` for an empty date in some locales.
+pub fn parse_date(text: &str, order: DateOrder) -> Option<DateTime> {
+    if text.eq_ignore_ascii_case("none") {
+        return None;
+    }
+    let date = text.split_whitespace().next()?;
+    let parts: Vec<&str> = date.split(['/', '.', '-']).collect();
+    let [a, b, c] = parts.as_slice() else {
+        return None;
+    };
+    let (a, b, c): (i64, u32, u32) = (a.parse().ok()?, b.parse().ok()?, c.parse().ok()?);
+    let dt = if a > 31 {
+        DateTime::date(a, b, c)
+    } else if order == DateOrder::DayFirst || date.contains('.') {
+        DateTime::date(c as i64, b, a as u32)
+    } else {
+        DateTime::date(c as i64, a as u32, b)
+    };
+    dt.to_timestamp().map(|_| dt)
+}
+
+/// `9:00:00 AM`, `21:30` and similar.
+fn parse_time_of_day(text: &str) -> Option<(u32, u32, u32)> {
+    let lower = text.trim().to_ascii_lowercase();
+    let (clock, meridiem) = match lower
+        .strip_suffix("am")
+        .or_else(|| lower.strip_suffix("a.m."))
+    {
+        Some(rest) => (rest.trim(), Some(false)),
+        None => match lower
+            .strip_suffix("pm")
+            .or_else(|| lower.strip_suffix("p.m."))
+        {
+            Some(rest) => (rest.trim(), Some(true)),
+            None => (lower.as_str(), None),
+        },
+    };
+    let mut parts = clock.split(':').map(|p| p.parse::<u32>().ok());
+    let mut hour = parts.next()??;
+    let minute = parts.next().unwrap_or(Some(0))?;
+    let second = parts.next().unwrap_or(Some(0))?;
+    match meridiem {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some(pm) => hour = hour % 12 + if pm { 12 } else { 0 },
+        None => {}
+    }
+    (hour < 24 && minute < 60 && second < 60).then_some((hour, minute, second))
+}
+
+fn strip_html(html: &str) -> String {
+    let mut out = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
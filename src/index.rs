@@ -0,0 +1,160 @@
+//! Word index over task names, descriptions and bodies (`tasks.json.index`), so
+//! `search` stays quick on large archives.
+//!
+//! The index maps each word to the tasks containing it and is headed by a
+//! fingerprint of the text it was built from. A stale index, after a hand
+//! edit say, is never used: [`load`] builds a fresh one instead. Saving
+//! keeps an existing index up to date, and `index rebuild` writes one from
+//! scratch.
+//!
+//! ```text
+//! TMI1 5c1e0b7f2a9d4e36
+//! report 0a1b2c3d… 4e5f6a7b…
+//! write 4e5f6a7b…
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cache::{ContentHash, HashWriter};
+use crate::task::{Task, TaskId};
+
+const MAGIC: &str = "TMI1";
+
+/// The index file kept for the data file (or store directory) at `path`.
+pub fn path_for(path: &Path) -> PathBuf {
+    let mut index = path.as_os_str().to_os_string();
+    index.push(".index");
+    PathBuf::from(index)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchIndex {
+    fingerprint: ContentHash,
+    words: BTreeMap<String, BTreeSet<TaskId>>,
+}
+
+impl SearchIndex {
+    pub fn build(tasks: &[Task]) -> Self {
+        let mut words: BTreeMap<String, BTreeSet<TaskId>> = BTreeMap::new();
+        for task in tasks {
+            let text = [&task.name, &task.description, &task.body];
+            for word in text.into_iter().flat_map(|text| words_in(text)) {
+                words.entry(word).or_default().insert(task.id);
+            }
+        }
+        SearchIndex {
+            fingerprint: fingerprint(tasks),
+            words,
+        }
+    }
+
+    /// Number of distinct words.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Tasks containing every word of `query`, each as the start of a word
+    /// and ignoring case: `rep` finds "Write report".
+    pub fn search(&self, query: &str) -> BTreeSet<TaskId> {
+        let mut found: Option<BTreeSet<TaskId>> = None;
+        for prefix in words_in(query) {
+            let ids: BTreeSet<TaskId> = self
+                .words
+                .range(prefix.clone()..)
+                .take_while(|(word, _)| word.starts_with(&prefix))
+                .flat_map(|(_, ids)| ids.iter().copied())
+                .collect();
+            found = Some(match found {
+                Some(found) => found.intersection(&ids).copied().collect(),
+                None => ids,
+            });
+        }
+        found.unwrap_or_default()
+    }
+
+    /// Reads the index at `path` if it was built from `tasks`.
+    pub fn read(path: &Path, tasks: &[Task]) -> Option<Self> {
+        let text = fs::read_to_string(path).ok()?;
+        let mut lines = text.lines();
+        let stored = lines.next()?.strip_prefix(MAGIC)?.trim();
+        let fingerprint = ContentHash::from_bits(u64::from_str_radix(stored, 16).ok()?);
+        if fingerprint != self::fingerprint(tasks) {
+            return None;
+        }
+        let mut words = BTreeMap::new();
+        for line in lines {
+            let mut fields = line.split(' ');
+            let word = fields.next()?.to_string();
+            let ids = fields.map(|id| id.parse().ok()).collect::<Option<_>>()?;
+            words.insert(word, ids);
+        }
+        Some(SearchIndex { fingerprint, words })
+    }
+
+    /// Writes the index to a temporary sibling first, like the data file.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut out = format!("{MAGIC} {:016x}\n", self.fingerprint.to_bits());
+        for (word, ids) in &self.words {
+            out.push_str(word);
+            for id in ids {
+                out.push(' ');
+                out.push_str(&id.to_hex());
+            }
+            out.push('\n');
+        }
+        let mut tmp = path.as_os_str().to_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, out)?;
+        fs::rename(&tmp, path)
+    }
+}
+
+/// The index kept for the data file at `path`, or a fresh one written in
+/// its place when it is missing or stale.
+pub fn load(path: &Path, tasks: &[Task]) -> SearchIndex {
+    let path = path_for(path);
+    SearchIndex::read(&path, tasks).unwrap_or_else(|| {
+        let index = SearchIndex::build(tasks);
+        // The index only saves time; failing to write it is not an error.
+        let _ = index.write(&path);
+        index
+    })
+}
+
+/// Rebuilds an existing index after a save. Data files nobody has searched
+/// get no index.
+pub fn refresh(path: &Path, tasks: &[Task]) {
+    let path = path_for(path);
+    if path.exists() {
+        let _ = SearchIndex::build(tasks).write(&path);
+    }
+}
+
+/// Hash of the text the index covers, which is far quicker to compute than
+/// the index itself.
+fn fingerprint(tasks: &[Task]) -> ContentHash {
+    let mut hasher = HashWriter::new(io::sink());
+    for task in tasks {
+        // Writing to a sink cannot fail.
+        let _ = write!(
+            hasher,
+            "{}\0{}\0{}\0{}\0",
+            task.id, task.name, task.description, task.body
+        );
+    }
+    hasher.hash()
+}
+
+/// The lowercase words of `text`, split at anything but letters and digits.
+fn words_in(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
@@ -0,0 +1,48 @@
+//! Core library of the task manager: the task model, the in-memory manager,
+//! persistence and export formats. The command-line frontend lives in the
+//! binary crate.
+//!
+//! Everything that touches the filesystem sits behind the default `fs`
+//! feature. Without it the core builds for `wasm32-unknown-unknown`: tasks
+//! are loaded and saved through a [`storage::Store`] the embedder provides,
+//! and the clock comes from [`dates::set_clock`].
+
+pub mod anonymize;
+#[cfg(feature = "fs")]
+pub mod cache;
+pub mod config;
+pub mod csv;
+pub mod dates;
+pub mod digest;
+pub mod duration;
+pub mod error;
+pub mod export;
+pub mod filter;
+pub mod import;
+#[cfg(feature = "fs")]
+pub mod index;
+pub mod json;
+pub mod mail;
+pub mod manager;
+pub mod manifest;
+pub mod markdown;
+pub mod plugin;
+pub mod rank;
+pub mod render;
+pub mod rules;
+pub mod schedule;
+#[cfg(feature = "fs")]
+pub mod split;
+pub mod stats;
+pub mod storage;
+pub mod suggest;
+pub mod task;
+pub mod timing;
+pub mod toml;
+pub mod update;
+pub mod workflow;
+pub mod yaml;
+
+pub use error::{Error, Result};
+pub use manager::TasksManager;
+pub use task::{Priority, Status, Task, TaskId};
@@ -0,0 +1,257 @@
+//! Turning email into tasks: the subject becomes the name, the plain-text
+//! body the description (or the task's body, when it is too long for one)
+//! and the sender the contact.
+//!
+//! Only what a mail program needs to show a message is read: folded and
+//! encoded headers (`=?utf-8?q?...?=`), quoted-printable and base64 bodies
+//! in UTF-8 or Latin-1, and the first `text/plain` part of a multipart
+//! message. A trailing `-- ` signature is left out of the description.
+
+use crate::error::{Error, Result};
+use crate::task::{self, Task};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Message {
+    pub subject: String,
+    /// The sender's address, without their name.
+    pub from: Option<String>,
+    pub body: String,
+}
+
+impl Message {
+    /// Parses a message in RFC 5322 form, as stored in a Maildir.
+    pub fn parse(raw: &str) -> Result<Self> {
+        let raw = raw.replace("\r\n", "\n");
+        let part = Part::parse(&raw);
+        if part.headers.is_empty() {
+            return Err(Error::Invalid("not an email: no headers".into()));
+        }
+        let from = part.header("from").and_then(|from| {
+            let from = decode_words(from);
+            let address = match (from.rfind('<'), from.rfind('>')) {
+                (Some(start), Some(end)) if start < end => &from[start + 1..end],
+                _ => from.as_str(),
+            };
+            task::parse_contact(address).ok()
+        });
+        let body = part.text().unwrap_or_default();
+        let body = match body.find("\n-- \n") {
+            Some(end) => &body[..end],
+            None => body.strip_prefix("-- \n").map_or(body.as_str(), |_| ""),
+        };
+        Ok(Message {
+            subject: part.header("subject").map(decode_words).unwrap_or_default(),
+            from,
+            body: body.trim().to_string(),
+        })
+    }
+
+    /// A new task for the message.
+    pub fn to_task(&self) -> Task {
+        let name = self.subject.trim();
+        let mut task = Task::new(if name.is_empty() {
+            "(no subject)"
+        } else {
+            name
+        });
+        task.set_description_or_body(&self.body);
+        task.contact = self.from.clone();
+        task
+    }
+}
+
+/// A message or one part of a multipart one.
+struct Part<'a> {
+    /// Names in lowercase, values unfolded.
+    headers: Vec<(String, String)>,
+    body: &'a str,
+}
+
+impl<'a> Part<'a> {
+    fn parse(raw: &'a str) -> Self {
+        let (head, body) = match raw.find("\n\n") {
+            Some(end) => (&raw[..end], &raw[end + 2..]),
+            None => (raw, ""),
+        };
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in head.lines() {
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+            } else if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+            }
+        }
+        Part { headers, body }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The content type in lowercase, and its `name=value` parameters.
+    fn content_type(&self) -> (String, Vec<(String, String)>) {
+        let value = self.header("content-type").unwrap_or("text/plain");
+        let mut fields = value.split(';');
+        let kind = fields.next().unwrap_or_default().trim().to_lowercase();
+        let params = fields
+            .filter_map(|field| {
+                let (name, value) = field.split_once('=')?;
+                Some((
+                    name.trim().to_lowercase(),
+                    value.trim().trim_matches('"').to_string(),
+                ))
+            })
+            .collect();
+        (kind, params)
+    }
+
+    /// The first plain-text content, decoded.
+    fn text(&self) -> Option<String> {
+        let (kind, params) = self.content_type();
+        if kind.starts_with("multipart/") {
+            let boundary = params.iter().find(|(n, _)| n == "boundary")?;
+            let delimiter = format!("--{}", boundary.1);
+            return self
+                .body
+                .split(&delimiter)
+                .skip(1)
+                .take_while(|part| !part.starts_with("--"))
+                .find_map(|part| Part::parse(part.strip_prefix('\n').unwrap_or(part)).text());
+        }
+        if kind != "text/plain" {
+            return None;
+        }
+        let bytes = match self
+            .header("content-transfer-encoding")
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("quoted-printable") => quoted_printable(self.body, false),
+            Some("base64") => base64(self.body)?,
+            _ => self.body.as_bytes().to_vec(),
+        };
+        let charset = params.iter().find(|(n, _)| n == "charset");
+        Some(decode_charset(
+            &bytes,
+            charset.map_or("utf-8", |(_, c)| c.as_str()),
+        ))
+    }
+}
+
+/// Decodes the `=?charset?Q|B?text?=` words in a header; whitespace
+/// between two of them is dropped.
+fn decode_words(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let Some((text, length)) = encoded_word(&rest[start + 2..]) else {
+            out.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            after_word = false;
+            continue;
+        };
+        let between = &rest[..start];
+        if !(after_word && between.trim().is_empty()) {
+            out.push_str(between);
+        }
+        out.push_str(&text);
+        rest = &rest[start + 2 + length + 2..];
+        after_word = true;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Decodes the encoded word at the start of `word`, just after its `=?`:
+/// its text, and its length up to the closing `?=`.
+fn encoded_word(word: &str) -> Option<(String, usize)> {
+    let (charset, rest) = word.split_once('?')?;
+    let (encoding, rest) = rest.split_once('?')?;
+    let (text, _) = rest.split_once("?=")?;
+    let bytes = match encoding {
+        "Q" | "q" => quoted_printable(text, true),
+        "B" | "b" => base64(text)?,
+        _ => return None,
+    };
+    let length = charset.len() + encoding.len() + text.len() + 2;
+    Some((decode_charset(&bytes, charset), length))
+}
+
+/// Quoted-printable, with `_` for a space in headers.
+fn quoted_printable(text: &str, header: bool) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'=' if bytes.get(i + 1) == Some(&b'\n') => i += 2,
+            b'=' => {
+                let hex = text
+                    .get(i + 1..i + 3)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 3;
+                    }
+                    None => {
+                        out.push(b'=');
+                        i += 1;
+                    }
+                }
+            }
+            b'_' if header => {
+                out.push(b' ');
+                i += 1;
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn base64(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        bits = bits << 6 | u32::from(value);
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// UTF-8, or Latin-1 for the charsets that are a superset of it; anything
+/// that fails is read as UTF-8 with replacement characters.
+fn decode_charset(bytes: &[u8], charset: &str) -> String {
+    match charset.to_lowercase().as_str() {
+        "iso-8859-1" | "iso-8859-15" | "latin1" | "windows-1252" => {
+            bytes.iter().map(|&b| char::from(b)).collect()
+        }
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
@@ -0,0 +1,684 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+use crate::config::WipLimits;
+use crate::dates::{Timestamp, SECONDS_PER_DAY};
+use crate::error::{Error, Result};
+use crate::schedule::Template;
+use crate::stats::{History, Snapshot, DEFAULT_RETENTION_DAYS};
+use crate::suggest;
+use crate::task::{Priority, Status, Task, TaskId};
+
+/// Shortest id prefix accepted when referring to a task.
+pub const MIN_ID_PREFIX: usize = 4;
+
+/// How widely a tag is used, see [`TasksManager::tag_counts`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagCount {
+    pub tag: String,
+    pub tasks: usize,
+    /// Of those, tasks not yet finished.
+    pub open: usize,
+}
+
+/// In-memory collection of tasks; the single entry point for mutations.
+#[derive(Clone, Debug, Default)]
+pub struct TasksManager {
+    tasks: Vec<Task>,
+    history: History,
+    templates: Vec<Template>,
+    /// Projects hidden from listings, see [`TasksManager::is_archived`].
+    archived: Vec<String>,
+    /// Names given to tasks, see [`TasksManager::set_alias`].
+    aliases: Vec<(String, TaskId)>,
+}
+
+impl TasksManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_tasks(tasks: Vec<Task>) -> Self {
+        TasksManager {
+            tasks,
+            history: History::default(),
+            templates: Vec::new(),
+            archived: Vec::new(),
+            aliases: Vec::new(),
+        }
+    }
+
+    pub fn with_history(mut self, history: History) -> Self {
+        self.history = history;
+        self
+    }
+
+    pub fn with_templates(mut self, templates: Vec<Template>) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    pub fn with_archived(mut self, archived: Vec<String>) -> Self {
+        self.archived = archived;
+        self
+    }
+
+    pub fn with_aliases(mut self, aliases: Vec<(String, TaskId)>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Recurring task templates, see [`crate::schedule`].
+    pub fn templates(&self) -> &[Template] {
+        &self.templates
+    }
+
+    pub fn templates_mut(&mut self) -> &mut Vec<Template> {
+        &mut self.templates
+    }
+
+    /// Daily aggregate snapshots kept alongside the tasks.
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Records today's aggregate counts, replacing an earlier snapshot from
+    /// the same day.
+    pub fn record_snapshot(&mut self, now: Timestamp) {
+        let snapshot = Snapshot::capture(&self.tasks, now);
+        self.history.record(snapshot, DEFAULT_RETENTION_DAYS);
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    /// The tasks to change in place; adding and removing go through
+    /// [`TasksManager::add`] and [`TasksManager::remove`].
+    pub fn tasks_mut(&mut self) -> &mut [Task] {
+        &mut self.tasks
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    pub fn add(&mut self, task: Task) -> TaskId {
+        let id = task.id;
+        self.tasks.push(task);
+        id
+    }
+
+    pub fn get(&self, id: TaskId) -> Option<&Task> {
+        self.tasks.iter().find(|t| t.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: TaskId) -> Option<&mut Task> {
+        self.tasks.iter_mut().find(|t| t.id == id)
+    }
+
+    pub fn remove(&mut self, id: TaskId) -> Result<Task> {
+        let index = self
+            .tasks
+            .iter()
+            .position(|t| t.id == id)
+            .ok_or_else(|| Error::NotFound(format!("task {id} not found")))?;
+        let task = self.tasks.remove(index);
+        for other in &mut self.tasks {
+            if other.parent == Some(id) {
+                other.parent = None;
+            }
+            other.depends_on.retain(|&dep| dep != id);
+            other.related.retain(|&related| related != id);
+        }
+        self.aliases.retain(|&(_, aliased)| aliased != id);
+        Ok(task)
+    }
+
+    /// The most urgent task that can be worked on now (see
+    /// [`TasksManager::is_actionable`]): highest priority first, then
+    /// earliest due date (tasks without one last), then oldest.
+    pub fn peek_highest_priority(&self) -> Option<&Task> {
+        self.tasks
+            .iter()
+            .filter(|t| self.is_actionable(t))
+            .min_by_key(|t| (Reverse(t.priority), t.due.is_none(), t.due, t.created_at))
+    }
+
+    /// Whether a task `task` depends on is still open.
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        task.depends_on
+            .iter()
+            .filter_map(|&id| self.get(id))
+            .any(|dep| dep.status.is_open())
+    }
+
+    /// Whether `task` can be worked on now: to do or in progress, not
+    /// blocked, and neither it nor its project archived.
+    pub fn is_actionable(&self, task: &Task) -> bool {
+        matches!(task.status, Status::Todo | Status::InProgress)
+            && !task.archived
+            && !task.project.as_deref().is_some_and(|p| self.is_archived(p))
+            && !self.is_blocked(task)
+    }
+
+    /// An actionable task picked at random, likelier the higher its priority
+    /// and the longer it is overdue; `roll` is a random number.
+    pub fn roulette(&self, now: Timestamp, roll: u64) -> Option<&Task> {
+        let weight = |task: &Task| -> u64 {
+            let priority = match task.priority {
+                Priority::Low => 1,
+                Priority::Medium => 2,
+                Priority::High => 4,
+                Priority::Critical => 8,
+            };
+            // Twice as likely once overdue, up to four times after two weeks.
+            let overdue = match task.due {
+                Some(due) if task.is_overdue(now) => {
+                    2 + ((now.secs() - due.secs()) / (7 * SECONDS_PER_DAY)).min(2) as u64
+                }
+                _ => 1,
+            };
+            priority * overdue
+        };
+        let candidates: Vec<(&Task, u64)> = self
+            .tasks
+            .iter()
+            .filter(|t| self.is_actionable(t))
+            .map(|t| (t, weight(t)))
+            .collect();
+        let total: u64 = candidates.iter().map(|&(_, w)| w).sum();
+        let mut point = roll.checked_rem(total)?;
+        for (task, weight) in candidates {
+            if point < weight {
+                return Some(task);
+            }
+            point -= weight;
+        }
+        None
+    }
+
+    /// Removes and returns the task [`TasksManager::peek_highest_priority`]
+    /// would show.
+    pub fn pop_highest_priority(&mut self) -> Option<Task> {
+        let id = self.peek_highest_priority()?.id;
+        self.remove(id).ok()
+    }
+
+    /// Sets `updated_at` to `now` on every task that differs from its
+    /// counterpart in `before`, unless the change set it already. Tasks not
+    /// in `before` are new and keep the time they were created with.
+    pub fn touch_changed(&mut self, before: &[Task], now: Timestamp) -> usize {
+        let mut touched = 0;
+        for task in &mut self.tasks {
+            let Some(old) = before.iter().find(|b| b.id == task.id) else {
+                continue;
+            };
+            if task != old && task.updated_at == old.updated_at {
+                task.updated_at = now;
+                touched += 1;
+            }
+        }
+        touched
+    }
+
+    /// Open low-priority tasks nobody has changed for `after` seconds,
+    /// oldest change first. Pinned and archived tasks are left alone.
+    pub fn stale(&self, after: i64, now: Timestamp) -> Vec<&Task> {
+        let cutoff = now.add_secs(-after);
+        let mut stale: Vec<&Task> = self
+            .tasks
+            .iter()
+            .filter(|t| {
+                t.priority == Priority::Low
+                    && t.status.is_open()
+                    && !t.pinned
+                    && !t.archived
+                    && t.updated_at <= cutoff
+            })
+            .collect();
+        stale.sort_by_key(|t| (t.updated_at, t.id));
+        stale
+    }
+
+    /// Subtasks whose `parent` is `id`.
+    pub fn children(&self, id: TaskId) -> impl Iterator<Item = &Task> {
+        self.tasks.iter().filter(move |t| t.parent == Some(id))
+    }
+
+    /// The highest priority among `id` and its open descendants.
+    pub fn max_priority(&self, id: TaskId) -> Priority {
+        let mut best = self.get(id).map(|t| t.priority).unwrap_or_default();
+        for child in self.descendants(id) {
+            if child.status.is_open() {
+                best = best.max(child.priority);
+            }
+        }
+        best
+    }
+
+    /// Sets every open descendant of `id` to its priority and returns how
+    /// many changed.
+    pub fn propagate_priority(&mut self, id: TaskId) -> usize {
+        let Some(priority) = self.get(id).map(|t| t.priority) else {
+            return 0;
+        };
+        let ids: Vec<TaskId> = self.descendants(id).map(|t| t.id).collect();
+        let mut changed = 0;
+        for task in self.tasks.iter_mut().filter(|t| ids.contains(&t.id)) {
+            if task.status.is_open() && task.priority != priority {
+                task.priority = priority;
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Tasks related to `id`, whichever of the two the link is stored on.
+    pub fn related(&self, id: TaskId) -> Vec<&Task> {
+        let own = self
+            .get(id)
+            .map(|t| t.related.as_slice())
+            .unwrap_or_default();
+        self.tasks
+            .iter()
+            .filter(|t| t.id != id && (own.contains(&t.id) || t.related.contains(&id)))
+            .collect()
+    }
+
+    /// Removes the related link between `a` and `b` from whichever side
+    /// holds it; returns whether there was one.
+    pub fn unrelate(&mut self, a: TaskId, b: TaskId) -> bool {
+        let mut found = false;
+        for (task, other) in [(a, b), (b, a)] {
+            if let Some(task) = self.get_mut(task) {
+                let before = task.related.len();
+                task.related.retain(|&id| id != other);
+                found |= task.related.len() != before;
+            }
+        }
+        found
+    }
+
+    /// Subtasks of `id` at any depth.
+    pub fn descendants(&self, id: TaskId) -> impl Iterator<Item = &Task> {
+        let mut seen = HashSet::from([id]);
+        let mut queue = vec![id];
+        let mut found = Vec::new();
+        while let Some(current) = queue.pop() {
+            for child in self.children(current) {
+                if seen.insert(child.id) {
+                    queue.push(child.id);
+                    found.push(child);
+                }
+            }
+        }
+        found.into_iter()
+    }
+
+    /// Every tag in use with how many tasks carry it, most used first. Tags
+    /// differing only in case count as one, spelled as first seen.
+    pub fn tag_counts(&self) -> Vec<TagCount> {
+        let mut counts: Vec<TagCount> = Vec::new();
+        for task in &self.tasks {
+            for tag in &task.tags {
+                let index = match counts.iter().position(|c| c.tag.eq_ignore_ascii_case(tag)) {
+                    Some(index) => index,
+                    None => {
+                        counts.push(TagCount {
+                            tag: tag.clone(),
+                            tasks: 0,
+                            open: 0,
+                        });
+                        counts.len() - 1
+                    }
+                };
+                counts[index].tasks += 1;
+                if task.status.is_open() {
+                    counts[index].open += 1;
+                }
+            }
+        }
+        counts.sort_by(|a, b| {
+            b.tasks
+                .cmp(&a.tasks)
+                .then_with(|| a.tag.to_lowercase().cmp(&b.tag.to_lowercase()))
+        });
+        counts
+    }
+
+    /// Replaces `from` with `to` on every task and returns how many changed.
+    /// A task that already has `to` just loses `from`, so this also merges
+    /// two tags.
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> usize {
+        let mut changed = 0;
+        for task in self.tasks.iter_mut().filter(|t| t.has_tag(from)) {
+            let has_target = task
+                .tags
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(to) && !t.eq_ignore_ascii_case(from));
+            if has_target {
+                task.tags.retain(|t| !t.eq_ignore_ascii_case(from));
+            } else {
+                for tag in task
+                    .tags
+                    .iter_mut()
+                    .filter(|t| t.eq_ignore_ascii_case(from))
+                {
+                    *tag = to.to_string();
+                }
+            }
+            changed += 1;
+        }
+        changed
+    }
+
+    /// Takes `tag` off every task and returns how many changed.
+    pub fn remove_tag(&mut self, tag: &str) -> usize {
+        let mut changed = 0;
+        for task in self.tasks.iter_mut().filter(|t| t.has_tag(tag)) {
+            task.tags.retain(|t| !t.eq_ignore_ascii_case(tag));
+            changed += 1;
+        }
+        changed
+    }
+
+    /// Names of archived projects.
+    pub fn archived_projects(&self) -> &[String] {
+        &self.archived
+    }
+
+    /// Whether `project` is archived. Tasks in archived projects are kept
+    /// but left out of listings unless asked for.
+    pub fn is_archived(&self, project: &str) -> bool {
+        self.archived
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(project))
+    }
+
+    /// Archives or restores `project`; returns whether anything changed.
+    pub fn set_archived(&mut self, project: &str, archived: bool) -> bool {
+        if archived == self.is_archived(project) {
+            return false;
+        }
+        if archived {
+            self.archived.push(project.to_string());
+        } else {
+            self.archived.retain(|p| !p.eq_ignore_ascii_case(project));
+        }
+        true
+    }
+
+    /// Task aliases and the tasks they name, in the order given.
+    pub fn aliases(&self) -> &[(String, TaskId)] {
+        &self.aliases
+    }
+
+    /// The alias of task `id`, if it has one.
+    pub fn alias_of(&self, id: TaskId) -> Option<&str> {
+        self.aliases
+            .iter()
+            .find(|&&(_, aliased)| aliased == id)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Names task `id` `name`, so that `name` is accepted wherever an id
+    /// is. A task has at most one alias: an earlier one is replaced, as is
+    /// the task an existing alias named. Names are letters, digits, `-` and
+    /// `_`, and cannot be all hex digits, or they could be read as an id.
+    pub fn set_alias(&mut self, name: &str, id: TaskId) -> Result<()> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(Error::Invalid(format!(
+                "alias `{name}` may only use letters, digits, `-` and `_`"
+            )));
+        }
+        if name.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+            return Err(Error::Invalid(format!(
+                "alias `{name}` looks like a task id; use a word with other letters"
+            )));
+        }
+        self.get(id)
+            .ok_or_else(|| Error::NotFound(format!("task {id} not found")))?;
+        self.aliases
+            .retain(|(n, aliased)| *aliased != id && !n.eq_ignore_ascii_case(name));
+        self.aliases.push((name.to_string(), id));
+        Ok(())
+    }
+
+    /// Removes alias `name`; returns whether there was one.
+    pub fn remove_alias(&mut self, name: &str) -> bool {
+        let before = self.aliases.len();
+        self.aliases.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        self.aliases.len() != before
+    }
+
+    /// Hex digits needed for every task's id to start differently, like
+    /// git's abbreviated hashes; never fewer than [`MIN_ID_PREFIX`].
+    pub fn id_width(&self) -> usize {
+        let mut ids: Vec<String> = self.tasks.iter().map(|t| t.id.to_hex()).collect();
+        ids.sort_unstable();
+        ids.windows(2)
+            .map(|pair| {
+                let common = pair[0]
+                    .bytes()
+                    .zip(pair[1].bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                common + 1
+            })
+            .fold(MIN_ID_PREFIX, usize::max)
+            .min(32)
+    }
+
+    /// Moves every task in project `from` to `to`, keeping it archived if
+    /// it was, and returns how many tasks changed.
+    pub fn rename_project(&mut self, from: &str, to: &str) -> usize {
+        let mut changed = 0;
+        for task in &mut self.tasks {
+            if task
+                .project
+                .as_deref()
+                .is_some_and(|p| p.eq_ignore_ascii_case(from))
+            {
+                task.project = Some(to.to_string());
+                changed += 1;
+            }
+        }
+        for name in self
+            .archived
+            .iter_mut()
+            .filter(|p| p.eq_ignore_ascii_case(from))
+        {
+            *name = to.to_string();
+        }
+        changed
+    }
+
+    /// Rejects a change to `id` that takes it over a work-in-progress limit.
+    /// `before` is the task as it was (`None` for a new task); limits only
+    /// apply when the task newly enters the counted state, so lowering a
+    /// limit does not block unrelated edits.
+    pub fn check_limits(
+        &self,
+        limits: &WipLimits,
+        before: Option<&Task>,
+        id: TaskId,
+    ) -> Result<()> {
+        let task = self
+            .get(id)
+            .ok_or_else(|| Error::NotFound(format!("task {id} not found")))?;
+        let started = task.status == Status::InProgress
+            && before.is_none_or(|b| b.status != Status::InProgress);
+        if let Some(limit) = limits.in_progress.filter(|_| started) {
+            let count = self
+                .tasks
+                .iter()
+                .filter(|t| t.status == Status::InProgress)
+                .count();
+            if count > limit {
+                return Err(Error::Conflict(format!(
+                    "WIP limit reached: {} tasks are already in progress (limit {limit})",
+                    count - 1
+                )));
+            }
+        }
+
+        let day = |t: &Task| {
+            t.due
+                .filter(|_| t.status.is_open())
+                .map(Timestamp::day_number)
+        };
+        let scheduled = day(task).filter(|&d| before.is_none_or(|b| day(b) != Some(d)));
+        if let (Some(limit), Some(due_day)) = (limits.per_day, scheduled) {
+            let count = self
+                .tasks
+                .iter()
+                .filter(|t| day(t) == Some(due_day))
+                .count();
+            if count > limit {
+                return Err(Error::Conflict(format!(
+                    "day limit reached: {} open tasks are already due on {} (limit {limit})",
+                    count - 1,
+                    task.due.expect("scheduled task").to_local_date()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rejects parent and dependency links of `id` that point at missing
+    /// tasks or would make a task its own ancestor or prerequisite.
+    pub fn check_links(&self, id: TaskId) -> Result<()> {
+        let task = self
+            .get(id)
+            .ok_or_else(|| Error::NotFound(format!("task {id} not found")))?;
+        for link in task
+            .parent
+            .iter()
+            .chain(&task.depends_on)
+            .chain(&task.related)
+        {
+            if self.get(*link).is_none() {
+                return Err(Error::NotFound(format!("linked task {link} not found")));
+            }
+        }
+        if task.related.contains(&id) {
+            return Err(Error::Invalid(format!(
+                "\"{}\" cannot be related to itself",
+                task.name
+            )));
+        }
+
+        let mut seen = HashSet::new();
+        let mut ancestor = task.parent;
+        while let Some(current) = ancestor {
+            if current == id {
+                return Err(Error::Conflict(format!(
+                    "\"{}\" cannot be a subtask of its own subtask",
+                    task.name
+                )));
+            }
+            if !seen.insert(current) {
+                break;
+            }
+            ancestor = self.get(current).and_then(|t| t.parent);
+        }
+
+        let mut seen = HashSet::new();
+        let mut stack = task.depends_on.clone();
+        while let Some(current) = stack.pop() {
+            if current == id {
+                return Err(Error::Conflict(format!(
+                    "\"{}\" would depend on itself",
+                    task.name
+                )));
+            }
+            if seen.insert(current) {
+                if let Some(dep) = self.get(current) {
+                    stack.extend(&dep.depends_on);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Open tasks whose names are near-duplicates of `name` (see
+    /// [`suggest::similarity`]), most similar first.
+    pub fn similar_open(&self, name: &str) -> Vec<&Task> {
+        let mut similar: Vec<(f64, &Task)> = self
+            .tasks
+            .iter()
+            .filter(|t| t.status.is_open())
+            .map(|t| (suggest::similarity(name, &t.name), t))
+            .filter(|&(score, _)| score >= suggest::DUPLICATE_THRESHOLD)
+            .collect();
+        similar.sort_by(|a, b| b.0.total_cmp(&a.0));
+        similar.into_iter().map(|(_, task)| task).collect()
+    }
+
+    /// Resolves an alias, a full id or a unique id prefix (dashes
+    /// optional).
+    pub fn resolve(&self, reference: &str) -> Result<TaskId> {
+        if let Some(&(_, id)) = self
+            .aliases
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(reference.trim()))
+        {
+            return Ok(id);
+        }
+        let needle: String = reference
+            .trim()
+            .chars()
+            .filter(|&c| c != '-')
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if needle.len() < MIN_ID_PREFIX || !needle.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(self.not_found(reference));
+        }
+        let mut matches = self
+            .tasks
+            .iter()
+            .filter(|t| t.id.to_hex().starts_with(&needle));
+        match (matches.next(), matches.next()) {
+            (Some(task), None) => Ok(task.id),
+            (Some(_), Some(_)) => Err(Error::Invalid(format!(
+                "task id `{reference}` is ambiguous; type more characters"
+            ))),
+            (None, _) => Err(self.not_found(reference)),
+        }
+    }
+
+    /// "Not found", suggesting tasks whose names are close to `reference`
+    /// in case a name was typed instead of an id.
+    fn not_found(&self, reference: &str) -> Error {
+        let names = suggest::closest(reference.trim(), self.tasks.iter().map(|t| t.name.as_str()));
+        let width = self.id_width();
+        let suggestions: Vec<String> = names
+            .iter()
+            .filter_map(|name| self.tasks.iter().find(|t| t.name == *name))
+            .map(|t| format!("\"{}\" ({})", t.name, &t.id.to_hex()[..width]))
+            .collect();
+        Error::NotFound(format!(
+            "task `{reference}` not found{}",
+            suggest::did_you_mean(&suggestions)
+        ))
+    }
+
+    pub fn find(&self, reference: &str) -> Result<&Task> {
+        let id = self.resolve(reference)?;
+        Ok(self.get(id).expect("resolved id exists"))
+    }
+
+    pub fn find_mut(&mut self, reference: &str) -> Result<&mut Task> {
+        let id = self.resolve(reference)?;
+        Ok(self.get_mut(id).expect("resolved id exists"))
+    }
+}
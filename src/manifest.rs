@@ -0,0 +1,354 @@
+//! Declarative task lists: a YAML manifest of templates, projects and tasks
+//! that the data file is reconciled against.
+//!
+//! ```yaml
+//! templates:
+//!   bug:
+//!     priority: high
+//!     tags: [bug]
+//! projects:
+//!   - name: website
+//!     tasks:
+//!       - name: Fix the contact form
+//!         template: bug
+//!         due: 2024-07-01
+//! tasks:
+//!   - name: Renew passport
+//!     remind: [1w, 1d]
+//! ```
+//!
+//! Tasks are matched to existing ones by project and name (ignoring case).
+//! Fields use the same names and values as `set`; fields the manifest leaves
+//! out are not touched, so a task completed by hand stays done. Pruning only
+//! ever removes tasks from projects the manifest declares.
+
+use std::collections::BTreeMap;
+
+use crate::dates::Timestamp;
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::manager::TasksManager;
+use crate::task::{Task, TaskId};
+use crate::update::{self, FieldChange, Update};
+use crate::yaml;
+
+/// One task entry: its name, project and `field=value` assignments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaskSpec {
+    pub name: String,
+    pub project: Option<String>,
+    pub fields: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Manifest {
+    pub projects: Vec<String>,
+    pub tasks: Vec<TaskSpec>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Create(Task),
+    Change {
+        id: TaskId,
+        changes: Vec<FieldChange>,
+        updates: Vec<Update>,
+    },
+    Remove(Task),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Plan {
+    pub actions: Vec<Action>,
+}
+
+impl Manifest {
+    pub fn parse(input: &str) -> Result<Self> {
+        let root = yaml::parse(input)?;
+        if root.is_null() {
+            return Ok(Manifest::default());
+        }
+        let Value::Object(entries) = &root else {
+            return Err(Error::Invalid("a manifest must be a mapping".into()));
+        };
+        for (key, _) in entries {
+            if !matches!(key.as_str(), "templates" | "projects" | "tasks") {
+                return Err(Error::Invalid(format!(
+                    "unknown manifest section `{key}` (expected templates, projects or tasks)"
+                )));
+            }
+        }
+
+        let mut templates = BTreeMap::new();
+        match root.get("templates") {
+            None | Some(Value::Null) => {}
+            Some(Value::Object(entries)) => {
+                for (name, fields) in entries {
+                    let fields = fields_of(fields, &format!("template `{name}`"))?;
+                    templates.insert(name.clone(), fields);
+                }
+            }
+            Some(_) => return Err(Error::Invalid("`templates` must be a mapping".into())),
+        }
+
+        let mut manifest = Manifest::default();
+        for project in list(&root, "projects")? {
+            let name = text(project.get("name"))
+                .filter(|n| !n.is_empty())
+                .ok_or_else(|| Error::Invalid("every project needs a `name`".into()))?;
+            for (key, _) in entries_of(project) {
+                if !matches!(key.as_str(), "name" | "tasks") {
+                    return Err(Error::Invalid(format!(
+                        "project `{name}`: unknown key `{key}` (expected name or tasks)"
+                    )));
+                }
+            }
+            for task in list(project, "tasks")? {
+                manifest
+                    .tasks
+                    .push(task_spec(task, Some(&name), &templates)?);
+            }
+            manifest.projects.push(name);
+        }
+        for task in list(&root, "tasks")? {
+            manifest.tasks.push(task_spec(task, None, &templates)?);
+        }
+
+        for (index, spec) in manifest.tasks.iter().enumerate() {
+            if manifest.tasks[..index]
+                .iter()
+                .any(|other| spec.matches(other))
+            {
+                return Err(Error::Invalid(format!(
+                    "task `{}` is listed twice{}",
+                    spec.name,
+                    spec.project
+                        .as_ref()
+                        .map(|p| format!(" in project `{p}`"))
+                        .unwrap_or_default()
+                )));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Works out what `apply` would do without changing `manager`. With
+    /// `prune`, tasks in declared projects that the manifest does not list
+    /// are removed.
+    pub fn plan(&self, manager: &TasksManager, now: Timestamp, prune: bool) -> Result<Plan> {
+        let mut plan = Plan::default();
+        let mut matched = Vec::new();
+        for spec in &self.tasks {
+            let updates = spec.updates(now, manager)?;
+            let mut existing = manager.tasks().iter().filter(|t| spec.describes(t));
+            match (existing.next(), existing.next()) {
+                (None, _) => {
+                    let mut task = Task::new(spec.name.clone());
+                    update::apply(&mut task, &updates);
+                    plan.actions.push(Action::Create(task));
+                }
+                (Some(task), None) => {
+                    matched.push(task.id);
+                    let changes = update::apply(&mut task.clone(), &updates);
+                    if !changes.is_empty() {
+                        plan.actions.push(Action::Change {
+                            id: task.id,
+                            changes,
+                            updates,
+                        });
+                    }
+                }
+                (Some(_), Some(_)) => {
+                    return Err(Error::Conflict(format!(
+                        "several tasks are named `{}`; rename one to tell them apart",
+                        spec.name
+                    )))
+                }
+            }
+        }
+        if prune {
+            for task in manager.tasks() {
+                let declared = task.project.as_ref().is_some_and(|project| {
+                    self.projects
+                        .iter()
+                        .any(|p| p.eq_ignore_ascii_case(project))
+                });
+                if declared && !matched.contains(&task.id) {
+                    plan.actions.push(Action::Remove(task.clone()));
+                }
+            }
+        }
+        Ok(plan)
+    }
+}
+
+impl TaskSpec {
+    fn matches(&self, other: &TaskSpec) -> bool {
+        self.name.eq_ignore_ascii_case(&other.name) && same_project(&self.project, &other.project)
+    }
+
+    fn describes(&self, task: &Task) -> bool {
+        self.name.eq_ignore_ascii_case(&task.name) && same_project(&self.project, &task.project)
+    }
+
+    fn updates(&self, now: Timestamp, manager: &TasksManager) -> Result<Vec<Update>> {
+        let mut updates = vec![Update::Project(self.project.clone())];
+        for (field, value) in &self.fields {
+            let update = Update::parse(&format!("{field}={value}"), now, manager)
+                .map_err(|err| Error::Invalid(format!("task `{}`: {err}", self.name)))?;
+            updates.push(update);
+        }
+        Ok(updates)
+    }
+}
+
+impl Plan {
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Counts of tasks to create, change and remove.
+    pub fn counts(&self) -> (usize, usize, usize) {
+        let mut counts = (0, 0, 0);
+        for action in &self.actions {
+            match action {
+                Action::Create(_) => counts.0 += 1,
+                Action::Change { .. } => counts.1 += 1,
+                Action::Remove(_) => counts.2 += 1,
+            }
+        }
+        counts
+    }
+
+    /// Carries out the plan. If a resulting link is invalid, `manager` is
+    /// left as it was.
+    pub fn apply(self, manager: &mut TasksManager) -> Result<()> {
+        let backup = manager.clone();
+        let mut touched = Vec::new();
+        for action in self.actions {
+            match action {
+                Action::Create(task) => touched.push(manager.add(task)),
+                Action::Change { id, updates, .. } => {
+                    let task = manager
+                        .get_mut(id)
+                        .ok_or_else(|| Error::NotFound(format!("task {id} not found")))?;
+                    update::apply(task, &updates);
+                    touched.push(id);
+                }
+                Action::Remove(task) => {
+                    manager.remove(task.id)?;
+                }
+            }
+        }
+        for id in touched {
+            if let Err(err) = manager.check_links(id) {
+                *manager = backup;
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn same_project(a: &Option<String>, b: &Option<String>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn task_spec(
+    value: &Value,
+    project: Option<&str>,
+    templates: &BTreeMap<String, Vec<(String, String)>>,
+) -> Result<TaskSpec> {
+    let name = text(value.get("name"))
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| Error::Invalid("every task needs a `name`".into()))?;
+    let context = format!("task `{name}`");
+    let mut fields = Vec::new();
+    if let Some(template) = value.get("template") {
+        let template = text(Some(template)).unwrap_or_default();
+        let defaults = templates
+            .get(&template)
+            .ok_or_else(|| Error::Invalid(format!("{context}: unknown template `{template}`")))?;
+        fields.extend(defaults.iter().cloned());
+    }
+    for (field, value) in fields_of(value, &context)? {
+        match field.as_str() {
+            "name" | "template" => {}
+            "project" if project.is_some() => {
+                return Err(Error::Invalid(format!(
+                    "{context}: tasks under a project cannot set `project`"
+                )))
+            }
+            _ => {
+                fields.retain(|(f, _)| *f != field);
+                fields.push((field, value));
+            }
+        }
+    }
+    let project = match project {
+        Some(project) => Some(project.to_string()),
+        None => fields
+            .iter()
+            .position(|(f, _)| f == "project")
+            .map(|i| fields.remove(i).1)
+            .filter(|p| !p.is_empty() && !p.eq_ignore_ascii_case("none")),
+    };
+    Ok(TaskSpec {
+        name,
+        project,
+        fields,
+    })
+}
+
+/// The entries of a mapping as `field=value` strings; lists are joined with
+/// commas and null clears the field.
+fn fields_of(value: &Value, context: &str) -> Result<Vec<(String, String)>> {
+    let Value::Object(entries) = value else {
+        return Err(Error::Invalid(format!("{context} must be a mapping")));
+    };
+    entries
+        .iter()
+        .map(|(key, value)| {
+            let text = match value {
+                Value::Array(items) => items
+                    .iter()
+                    .map(|item| text(Some(item)))
+                    .collect::<Option<Vec<_>>>()
+                    .map(|items| items.join(",")),
+                Value::Null => Some(String::new()),
+                other => text(Some(other)),
+            };
+            let text = text.ok_or_else(|| {
+                Error::Invalid(format!("{context}: `{key}` must be a value or a list"))
+            })?;
+            Ok((key.to_ascii_lowercase(), text))
+        })
+        .collect()
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) => Some(s.trim_end_matches('\n').to_string()),
+        Value::Number(_) | Value::Bool(_) => Some(value?.to_compact()),
+        _ => None,
+    }
+}
+
+fn entries_of(value: &Value) -> &[(String, Value)] {
+    match value {
+        Value::Object(entries) => entries,
+        _ => &[],
+    }
+}
+
+fn list<'a>(value: &'a Value, key: &str) -> Result<&'a [Value]> {
+    match value.get(key) {
+        None | Some(Value::Null) => Ok(&[]),
+        Some(Value::Array(items)) => Ok(items),
+        Some(_) => Err(Error::Invalid(format!("`{key}` must be a list"))),
+    }
+}
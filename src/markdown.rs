@@ -0,0 +1,170 @@
+//! Markdown checklists as a live task list.
+//!
+//! Every `- [ ]` or `- [x]` item (also with `*` or `+` bullets, at any
+//! indent) is a task. The first sync creates a task for each new item and
+//! tags the line with its id in an HTML comment, which Markdown renderers
+//! hide:
+//!
+//! ```markdown
+//! - [ ] Renew passport <!-- task:8fa6d4dc-45d8-4ee2-98ac-ebe83785dabd -->
+//! ```
+//!
+//! After that the comment keeps the line and the task together whatever
+//! happens to the text. Items in fenced code blocks are ignored. Ticking a
+//! box finishes its open task (sending it for review if it needs one), and
+//! a task finished elsewhere ticks its box; nothing else is copied either
+//! way.
+
+use crate::manager::TasksManager;
+use crate::task::{Status, Task, TaskId};
+
+pub(crate) const MARKER: &str = "<!-- task:";
+
+/// What a sync did, and the new text of the file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sync {
+    pub text: String,
+    /// Tasks created for new items.
+    pub created: Vec<TaskId>,
+    /// Tasks completed because their box was ticked.
+    pub completed: Vec<TaskId>,
+    /// Tasks sent for review because their box was ticked.
+    pub submitted: Vec<TaskId>,
+    /// Tasks whose box was ticked because they were finished.
+    pub checked: Vec<TaskId>,
+    /// Ids in the file that match no task, e.g. removed ones. Their lines
+    /// are left alone.
+    pub missing: Vec<TaskId>,
+}
+
+impl Sync {
+    /// Whether the file needs writing back.
+    pub fn changed_file(&self) -> bool {
+        !self.created.is_empty() || !self.checked.is_empty()
+    }
+
+    /// Takes back the creation of `id`: removes the task from `manager` and
+    /// its id from the item, so the next sync offers the item again.
+    pub fn discard(&mut self, id: TaskId, manager: &mut TasksManager) {
+        if !self.created.contains(&id) {
+            return;
+        }
+        self.created.retain(|&created| created != id);
+        let _ = manager.remove(id);
+        self.text = self.text.replacen(&format!(" {MARKER}{id} -->"), "", 1);
+    }
+}
+
+/// One checklist line, split around the parts a sync reads or rewrites.
+struct Item<'a> {
+    /// Indent and bullet, up to the `[`.
+    prefix: &'a str,
+    checked: bool,
+    text: &'a str,
+    id: Option<TaskId>,
+}
+
+fn item(line: &str) -> Option<Item<'_>> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    let mut chars = rest.chars();
+    if !matches!(chars.next(), Some('-' | '*' | '+')) || chars.next() != Some(' ') {
+        return None;
+    }
+    let prefix = &line[..indent + 2];
+    let rest = &line[indent + 2..];
+    let checked = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let mut text = rest[3..].trim();
+    let mut id = None;
+    if let Some(start) = text.rfind(MARKER) {
+        let comment = text[start + MARKER.len()..].trim_end();
+        if let Some(parsed) = comment
+            .strip_suffix("-->")
+            .and_then(|c| c.trim().parse().ok())
+        {
+            id = Some(parsed);
+            text = text[..start].trim_end();
+        }
+    }
+    if text.is_empty() && id.is_none() {
+        return None;
+    }
+    Some(Item {
+        prefix,
+        checked,
+        text,
+        id,
+    })
+}
+
+/// Reconciles the checklist in `input` with `manager`, creating and
+/// finishing tasks as needed. Tasks are finished as `user`.
+pub fn sync(input: &str, manager: &mut TasksManager, user: &str) -> Sync {
+    let mut sync = Sync::default();
+    let mut in_code = false;
+    for raw in input.split_inclusive('\n') {
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let ending = &raw[line.len()..];
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        let Some(item) = item(line).filter(|_| !in_code) else {
+            sync.text.push_str(raw);
+            continue;
+        };
+        let rewritten = match item.id {
+            None => {
+                let mut task = Task::new(item.text);
+                if item.checked {
+                    task.set_status(Status::Done);
+                }
+                let id = manager.add(task);
+                sync.created.push(id);
+                Some(format!(
+                    "{}[{}] {} {MARKER}{id} -->",
+                    item.prefix,
+                    if item.checked { 'x' } else { ' ' },
+                    item.text
+                ))
+            }
+            Some(id) => match manager.get_mut(id) {
+                None => {
+                    sync.missing.push(id);
+                    None
+                }
+                Some(task)
+                    if item.checked
+                        && task.status.is_open()
+                        && task.status != Status::ReviewPending =>
+                {
+                    match task.finish(user) {
+                        Status::Done => sync.completed.push(id),
+                        _ => sync.submitted.push(id),
+                    }
+                    None
+                }
+                Some(task) if !item.checked && !task.status.is_open() => {
+                    sync.checked.push(id);
+                    Some(format!(
+                        "{}[x]{}",
+                        item.prefix,
+                        &line[item.prefix.len() + 3..]
+                    ))
+                }
+                Some(_) => None,
+            },
+        };
+        match rewritten {
+            Some(line) => {
+                sync.text.push_str(&line);
+                sync.text.push_str(ending);
+            }
+            None => sync.text.push_str(raw),
+        }
+    }
+    sync
+}
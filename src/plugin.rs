@@ -0,0 +1,177 @@
+//! The protocol spoken with plugins: executables named
+//! `task-manager-<command>` that add commands of their own.
+//!
+//! A plugin gets a [`request`] on stdin, the selected tasks in the data
+//! file's JSON form, and may answer on stdout with the changes it wants:
+//!
+//! ```json
+//! {
+//!   "create": [{"name": "Follow up", "set": ["priority=high", "+email"]}],
+//!   "update": [{"id": "3f2a", "set": ["status=done"]}],
+//!   "remove": ["9b1c"],
+//!   "message": "1 created, 1 finished, 1 removed"
+//! }
+//! ```
+//!
+//! Every key is optional and an empty answer changes nothing. `set` takes
+//! the `field=value`, `+tag` and `-tag` assignments of the `set` command and
+//! ids may be shortened as on the command line. The whole answer is checked
+//! before any of it is applied, so a plugin's changes go in all or nothing.
+
+use crate::dates::Timestamp;
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+use crate::manager::TasksManager;
+use crate::task::Task;
+use crate::update::{self, Update};
+use crate::workflow::Workflow;
+
+/// Plugins are found on `PATH` under this prefix and the command name.
+pub const PREFIX: &str = "task-manager-";
+
+/// Version of the request and answer format, sent with each request.
+pub const PROTOCOL_VERSION: i64 = 1;
+
+/// What a plugin reads on stdin: `{"version": 1, "command": "...",
+/// "args": [...], "tasks": [...]}`.
+pub fn request(command: &str, args: &[String], tasks: &[&Task]) -> Value {
+    let mut root = Value::object();
+    root.insert("version", PROTOCOL_VERSION);
+    root.insert("command", command);
+    root.insert(
+        "args",
+        Value::Array(args.iter().map(|a| Value::from(a.as_str())).collect()),
+    );
+    root.insert(
+        "tasks",
+        Value::Array(tasks.iter().map(|t| t.to_json()).collect()),
+    );
+    root
+}
+
+/// A plugin's answer, as read from its stdout.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Reply {
+    /// Names of new tasks and the assignments to make on each.
+    pub create: Vec<(String, Vec<String>)>,
+    /// Task references and the assignments to make on each.
+    pub update: Vec<(String, Vec<String>)>,
+    pub remove: Vec<String>,
+    /// Shown to the user once the changes are made.
+    pub message: Option<String>,
+}
+
+/// How many tasks a [`Reply`] created, changed and removed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Applied {
+    pub created: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+impl Applied {
+    pub fn is_empty(&self) -> bool {
+        *self == Applied::default()
+    }
+}
+
+impl Reply {
+    /// Parses an answer; blank output is an empty one.
+    pub fn parse(output: &str) -> Result<Self> {
+        if output.trim().is_empty() {
+            return Ok(Reply::default());
+        }
+        let root = json::parse(output)?;
+        let invalid = |what: &str| Error::Invalid(format!("plugin answer: {what}"));
+        let list = |key: &str| -> Result<&[Value]> {
+            match root.get(key) {
+                None | Some(Value::Null) => Ok(&[]),
+                Some(value) => value
+                    .as_array()
+                    .ok_or_else(|| invalid(&format!("`{key}` must be an array"))),
+            }
+        };
+        let changes = |key: &str, field: &str| -> Result<Vec<(String, Vec<String>)>> {
+            list(key)?
+                .iter()
+                .map(|entry| {
+                    let target = entry
+                        .get(field)
+                        .and_then(Value::as_str)
+                        .ok_or_else(|| invalid(&format!("each of `{key}` needs a `{field}`")))?;
+                    let set = match entry.get("set") {
+                        None => Vec::new(),
+                        Some(set) => strings(set)
+                            .ok_or_else(|| invalid("`set` must be an array of strings"))?,
+                    };
+                    Ok((target.to_string(), set))
+                })
+                .collect()
+        };
+        let remove = match root.get("remove") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(value) => {
+                strings(value).ok_or_else(|| invalid("`remove` must be an array of ids"))?
+            }
+        };
+        Ok(Reply {
+            create: changes("create", "name")?,
+            update: changes("update", "id")?,
+            remove,
+            message: root
+                .get("message")
+                .and_then(Value::as_str)
+                .map(String::from),
+        })
+    }
+
+    /// Makes the changes on `manager`, or none of them if any is invalid,
+    /// refers to a missing task or is refused by `workflow`.
+    pub fn apply(
+        &self,
+        manager: &mut TasksManager,
+        workflow: &Workflow,
+        now: Timestamp,
+    ) -> Result<Applied> {
+        let mut next = manager.clone();
+        let mut applied = Applied::default();
+        for (name, set) in &self.create {
+            let mut task = Task::new(name.clone());
+            update::apply(&mut task, &parse_updates(set, now, &next)?);
+            let id = next.add(task);
+            next.check_links(id)?;
+            applied.created += 1;
+        }
+        for (reference, set) in &self.update {
+            let updates = parse_updates(set, now, &next)?;
+            let id = next.resolve(reference)?;
+            let task = next.get_mut(id).expect("resolved task exists");
+            let original = task.clone();
+            update::apply(task, &updates);
+            workflow.check(&original, task, None)?;
+            next.check_links(id)?;
+            applied.updated += 1;
+        }
+        for reference in &self.remove {
+            let id = next.resolve(reference)?;
+            next.remove(id)?;
+            applied.removed += 1;
+        }
+        *manager = next;
+        Ok(applied)
+    }
+}
+
+fn parse_updates(set: &[String], now: Timestamp, manager: &TasksManager) -> Result<Vec<Update>> {
+    set.iter()
+        .map(|arg| Update::parse(arg, now, manager))
+        .collect()
+}
+
+fn strings(value: &Value) -> Option<Vec<String>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|v| v.as_str().map(String::from))
+        .collect()
+}
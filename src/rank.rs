@@ -0,0 +1,108 @@
+//! Putting tasks in order by asking which of two matters more, for
+//! `reprioritize`, or by due dates and importance hints, for
+//! `normalize-priorities`, and spreading the order over priority levels.
+
+use std::cmp::Reverse;
+
+use crate::dates::{Timestamp, SECONDS_PER_DAY};
+use crate::task::{Priority, Task};
+
+/// Sorts `items` most important first by binary insertion, which needs
+/// about `n log2 n` answers. `prefer(a, b)` says whether `a` matters more
+/// than `b`; when it returns `None` (the person quit) so does this.
+pub fn rank<T>(items: Vec<T>, mut prefer: impl FnMut(&T, &T) -> Option<bool>) -> Option<Vec<T>> {
+    let mut ranked: Vec<T> = Vec::with_capacity(items.len());
+    for item in items {
+        let (mut low, mut high) = (0, ranked.len());
+        while low < high {
+            let middle = (low + high) / 2;
+            if prefer(&item, &ranked[middle])? {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+        ranked.insert(low, item);
+    }
+    Some(ranked)
+}
+
+/// The most comparisons [`rank`] asks for `n` items.
+pub fn comparisons(n: usize) -> usize {
+    (1..n)
+        .map(|len| (len + 1).next_power_of_two().trailing_zeros() as usize)
+        .sum()
+}
+
+/// A priority for each place in a ranked list of `len`, most important
+/// first, splitting the list into equal bands, one per level in `levels`.
+pub fn spread(len: usize, levels: &[Priority]) -> Vec<Priority> {
+    (0..len)
+        .map(|place| levels[place * levels.len() / len])
+        .collect()
+}
+
+/// Like [`spread`], but with bands as wide as each level's share of
+/// `shares`, e.g. 5 critical, 20 high, 50 medium and 25 low for those
+/// percentages. Levels come most important first; those with no share are
+/// skipped.
+pub fn spread_shares(len: usize, shares: &[(Priority, usize)]) -> Vec<Priority> {
+    let total: usize = shares.iter().map(|&(_, share)| share).sum();
+    let mut out = Vec::with_capacity(len);
+    let mut before = 0;
+    for &(level, share) in shares {
+        before += share;
+        // Rounded, so that small lists still get the larger levels.
+        let end = if before == total {
+            len
+        } else {
+            (len * before + total / 2) / total.max(1)
+        };
+        out.resize(end.max(out.len()), level);
+    }
+    out
+}
+
+/// Orders `tasks` most important first without asking: overdue tasks and
+/// those due soon first, then by the priority each came with, which
+/// importers take from the source, with pinned tasks and ones that other
+/// open tasks in `all` depend on moved up. Ties go to the earlier due date,
+/// then the older task.
+pub fn by_importance<'a>(mut tasks: Vec<&'a Task>, all: &[Task], now: Timestamp) -> Vec<&'a Task> {
+    let blocking = |task: &Task| {
+        all.iter()
+            .filter(|t| t.status.is_open() && t.depends_on.contains(&task.id))
+            .count()
+    };
+    tasks.sort_by_cached_key(|task| {
+        (
+            Reverse(importance(task, blocking(task), now)),
+            task.due.map_or(i64::MAX, Timestamp::secs),
+            task.created_at,
+        )
+    });
+    tasks
+}
+
+/// The score [`by_importance`] sorts on, for a task `blocking` open tasks
+/// depend on. How soon it is due counts most, its priority next.
+pub fn importance(task: &Task, blocking: usize, now: Timestamp) -> i64 {
+    let due = match task
+        .due
+        .map(|due| (due.secs() - now.secs()).div_euclid(SECONDS_PER_DAY))
+    {
+        Some(days) if days < 0 => 400,
+        Some(0) => 300,
+        Some(1..=7) => 200,
+        Some(8..=30) => 100,
+        _ => 0,
+    };
+    let hint = match task.priority {
+        Priority::Critical => 150,
+        Priority::High => 100,
+        Priority::Medium => 50,
+        Priority::Low => 0,
+    };
+    let pinned = if task.pinned { 50 } else { 0 };
+    due + hint + pinned + 25 * blocking.min(4) as i64
+}
@@ -0,0 +1,109 @@
+//! Marking search matches in `list` output.
+//!
+//! Matches are found the way the filter finds them, ignoring case. Colour is
+//! only used when writing to a terminal and `NO_COLOR` is not set; snippets
+//! of matching descriptions are shown either way.
+
+use std::ops::Range;
+
+pub const RESET: &str = "\x1b[0m";
+
+/// Characters of context kept on each side of a match in a snippet.
+const SNIPPET_RADIUS: usize = 30;
+
+/// The terms being searched for and whether to colour them.
+#[derive(Clone, Debug, Default)]
+pub struct Search {
+    terms: Vec<String>,
+    color: bool,
+}
+
+impl Search {
+    pub fn new(terms: &[&str], color: bool) -> Self {
+        Search {
+            terms: terms
+                .iter()
+                .filter(|t| !t.is_empty())
+                .map(|t| t.to_string())
+                .collect(),
+            color,
+        }
+    }
+
+    /// `text` with every match coloured.
+    pub fn mark(&self, text: &str) -> String {
+        if !self.color {
+            return text.to_string();
+        }
+        let mark = super::theme::theme().mark;
+        let mut out = String::new();
+        let mut last = 0;
+        for range in self.matches(text) {
+            out.push_str(&text[last..range.start]);
+            out.push_str(&mark);
+            out.push_str(&text[range.clone()]);
+            out.push_str(RESET);
+            last = range.end;
+        }
+        out.push_str(&text[last..]);
+        out
+    }
+
+    /// The part of `text` around its first match, on one line, with
+    /// `ellipsis` where it was cut. `None` if nothing matches.
+    pub fn snippet(&self, text: &str, ellipsis: &str) -> Option<String> {
+        let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let first = self.matches(&flat).into_iter().next()?;
+        let before = flat[..first.start].chars().count();
+        let start = flat
+            .char_indices()
+            .nth(before.saturating_sub(SNIPPET_RADIUS))
+            .map_or(0, |(i, _)| i);
+        let end = flat[first.end..]
+            .char_indices()
+            .nth(SNIPPET_RADIUS)
+            .map_or(flat.len(), |(i, _)| first.end + i);
+        let mut snippet = String::new();
+        if start > 0 {
+            snippet.push_str(ellipsis);
+        }
+        snippet.push_str(&flat[start..end]);
+        if end < flat.len() {
+            snippet.push_str(ellipsis);
+        }
+        Some(snippet)
+    }
+
+    /// Byte ranges of all matches in `text`, sorted and merged.
+    fn matches(&self, text: &str) -> Vec<Range<usize>> {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for term in &self.terms {
+            let term: Vec<char> = term.chars().collect();
+            for start in 0..chars.len() {
+                let Some(window) = chars.get(start..start + term.len()) else {
+                    break;
+                };
+                let found = window
+                    .iter()
+                    .zip(&term)
+                    .all(|(&(_, a), &b)| a.to_lowercase().eq(b.to_lowercase()));
+                if found {
+                    let end = chars
+                        .get(start + term.len())
+                        .map_or(text.len(), |&(i, _)| i);
+                    ranges.push(chars[start].0..end);
+                }
+            }
+        }
+        ranges.sort_by_key(|r| r.start);
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+}
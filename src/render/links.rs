@@ -0,0 +1,131 @@
+//! Clickable links in terminal output, as OSC 8 hyperlinks.
+//!
+//! Rendered text is scanned for web addresses (`https://…`), GitHub
+//! references (`owner/repo#123`) and the JIRA keys of linked issues, and
+//! each is wrapped in the escape sequences that make terminals open it on
+//! click. Like colour, links only ever add escapes: the visible text and its
+//! width stay the same, and terminals without support show the text plain.
+
+use crate::task::parse_issue_key;
+
+/// Where references that are not already addresses point.
+#[derive(Clone, Debug, Default)]
+pub struct Links<'a> {
+    /// Address JIRA keys are appended to, e.g.
+    /// `https://example.atlassian.net/browse/`; keys are not linked without
+    /// it.
+    pub jira: Option<&'a str>,
+    /// The issue keys to link. Other words shaped like keys, such as
+    /// `UTF-8`, are left alone.
+    pub issues: Vec<&'a str>,
+}
+
+/// `text` made a hyperlink to `url`.
+pub fn hyperlink(text: &str, url: &str) -> String {
+    format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\")
+}
+
+/// A `mailto:` address that opens a new message to `to` with `subject` and
+/// `body` filled in.
+pub fn mailto(to: &str, subject: &str, body: &str) -> String {
+    let mut url = format!("mailto:{}", percent_encode(to, "@"));
+    let mut separator = '?';
+    for (field, value) in [("subject", subject), ("body", body)] {
+        if !value.is_empty() {
+            url.push_str(&format!("{separator}{field}={}", percent_encode(value, "")));
+            separator = '&';
+        }
+    }
+    url
+}
+
+/// `text` with every byte but unreserved characters and `keep` written as
+/// `%XX`; line breaks become `%0D%0A`, as mail clients expect.
+fn percent_encode(text: &str, keep: &str) -> String {
+    let text = text.replace("\r\n", "\n").replace('\n', "\r\n");
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric()
+            || b"-._~".contains(&byte)
+            || keep.as_bytes().contains(&byte)
+        {
+            out.push(char::from(byte));
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+/// `text` with every address and known reference in it made a hyperlink.
+/// Colour codes already in `text` are kept and end a word.
+pub fn linkify(text: &str, links: &Links) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let len = if c == '\x1b' {
+            // A colour code runs to its final letter: `ESC [ 1 ; 3 3 m`.
+            rest[1..]
+                .find(|c: char| c.is_ascii_alphabetic())
+                .map_or(rest.len(), |i| i + 2)
+        } else if c.is_whitespace() {
+            c.len_utf8()
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '\x1b')
+                .unwrap_or(rest.len());
+            out.push_str(&link_word(&rest[..end], links));
+            rest = &rest[end..];
+            continue;
+        };
+        out.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+    out
+}
+
+/// Links `word` without the brackets and punctuation around it, as in
+/// `[PROJ-1]` or `(see https://example.com).`
+fn link_word(word: &str, links: &Links) -> String {
+    let start = word
+        .find(|c: char| !"([<\"'".contains(c))
+        .unwrap_or(word.len());
+    let end = start
+        + word[start..]
+            .trim_end_matches(|c: char| ".,;:!?)]>\"'".contains(c))
+            .len();
+    let inner = &word[start..end];
+    match target(inner, links) {
+        Some(url) => format!(
+            "{}{}{}",
+            &word[..start],
+            hyperlink(inner, &url),
+            &word[end..]
+        ),
+        None => word.to_string(),
+    }
+}
+
+fn target(word: &str, links: &Links) -> Option<String> {
+    for scheme in ["https://", "http://"] {
+        if word.len() > scheme.len() && word.starts_with(scheme) {
+            return Some(word.to_string());
+        }
+    }
+    if let Some((repo, number)) = word.split_once('#') {
+        let (owner, name) = repo.split_once('/')?;
+        let valid = !owner.is_empty()
+            && owner.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c))
+            && !number.is_empty()
+            && number.chars().all(|c| c.is_ascii_digit());
+        // GitHub sends issue numbers that are pull requests on to them.
+        return valid.then(|| format!("https://github.com/{owner}/{name}/issues/{number}"));
+    }
+    let base = links.jira?;
+    let known = links.issues.contains(&word) && parse_issue_key(word).is_ok();
+    known.then(|| format!("{base}{word}"))
+}
@@ -0,0 +1,576 @@
+//! Text layout of tasks: the task table, the detail view, Markdown
+//! checklists, word wrapping and bars.
+//!
+//! These are pure functions from tasks to `String`, shared by the command
+//! line and usable for golden tests. Their output is stable: a change to it
+//! is a deliberate change to the tool's output and updates the golden files
+//! under `tests/golden`. Callers decide the width (`None` for no wrapping)
+//! and whether to colour; colour only ever adds ANSI escapes, which
+//! [`strip_ansi`] (behind the `test-util` feature) removes again.
+
+pub mod highlight;
+pub mod links;
+pub mod theme;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::config::{Glyphs, TagStyles};
+use crate::dates::Timestamp;
+use crate::duration;
+use crate::manager::MIN_ID_PREFIX;
+use crate::markdown::MARKER;
+use crate::{Priority, Status, Task, TasksManager};
+
+use highlight::{Search, RESET};
+
+/// Narrowest name column worth keeping the table layout for.
+const MIN_NAME_WIDTH: usize = 20;
+
+/// Indentation of values in the detail view (`  priority:    `).
+const DETAIL_INDENT: usize = 15;
+
+/// How timestamps are shown: as dates, or relative to `now`.
+#[derive(Clone, Copy, Debug)]
+pub struct Times {
+    pub now: Timestamp,
+    pub relative: bool,
+}
+
+impl Times {
+    fn show(self, time: Timestamp) -> String {
+        if self.relative {
+            time.relative_to(self.now)
+        } else {
+            time.to_string()
+        }
+    }
+}
+
+/// How tags are decorated in listings: with their configured icons, and
+/// coloured when `color` is set.
+#[derive(Clone, Copy, Debug)]
+pub struct Tags<'a> {
+    pub styles: &'a TagStyles,
+    pub color: bool,
+}
+
+impl Tags<'_> {
+    /// The tag as it appears in a title, icon first.
+    fn label(self, tag: &str) -> String {
+        match self.styles.get(tag).and_then(|s| s.icon.as_deref()) {
+            Some(icon) => format!("{icon}#{tag}"),
+            None => format!("#{tag}"),
+        }
+    }
+
+    /// `line` with search matches marked and the task's coloured tags
+    /// painted. Matches inside a tag keep the tag's colour around them.
+    fn paint(self, line: &str, task: &Task, search: &Search) -> String {
+        let mut spans: Vec<(usize, usize, String)> = Vec::new();
+        if self.color {
+            for tag in &task.tags {
+                let Some(color) = self.styles.get(tag).and_then(|s| s.color) else {
+                    continue;
+                };
+                let label = self.label(tag);
+                let mut from = 0;
+                while let Some(i) = line[from..].find(&label) {
+                    let (start, end) = (from + i, from + i + label.len());
+                    let bounded = line[..start]
+                        .chars()
+                        .next_back()
+                        .is_none_or(char::is_whitespace)
+                        && line[end..].chars().next().is_none_or(char::is_whitespace);
+                    if bounded {
+                        spans.push((start, end, color.ansi()));
+                    }
+                    from = end;
+                }
+            }
+        }
+        spans.sort_by_key(|&(start, ..)| start);
+        let mut out = String::new();
+        let mut last = 0;
+        for (start, end, color) in spans {
+            if start < last {
+                continue;
+            }
+            out.push_str(&search.mark(&line[last..start]));
+            let marked = search
+                .mark(&line[start..end])
+                .replace(RESET, &format!("{RESET}{color}"));
+            out.push_str(&format!("{color}{marked}{RESET}"));
+            last = end;
+        }
+        out.push_str(&search.mark(&line[last..]));
+        out
+    }
+}
+
+/// Greedy word wrap. Words longer than the width are hyphenated across
+/// lines; line breaks in `text` are kept.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(2);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut len = 0;
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            if len > 0 && len + 1 + word.len() <= width {
+                line.push(' ');
+                line.extend(&word);
+                len += 1 + word.len();
+                continue;
+            }
+            if len > 0 {
+                lines.push(std::mem::take(&mut line));
+            }
+            while word.len() > width {
+                // Break after an existing hyphen if there is one in reach.
+                let (at, hyphen) = match word[..width].iter().rposition(|&c| c == '-') {
+                    Some(i) if i > 0 => (i + 1, false),
+                    _ => (width - 1, true),
+                };
+                let rest = word.split_off(at);
+                let mut piece: String = word.into_iter().collect();
+                if hyphen {
+                    piece.push('-');
+                }
+                lines.push(piece);
+                word = rest;
+            }
+            len = word.len();
+            line = word.into_iter().collect();
+        }
+        lines.push(line);
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+fn wrap_opt(text: &str, width: Option<usize>) -> Vec<String> {
+    match width {
+        Some(width) => wrap(text, width),
+        None => text.lines().map(str::to_string).collect(),
+    }
+}
+
+/// The name followed by the linked issue and tags, as shown in listings.
+fn title(task: &Task, tags: Tags, glyphs: &Glyphs) -> String {
+    let mut title = if task.pinned {
+        format!("{} {}", glyphs.pinned, task.name)
+    } else {
+        task.name.clone()
+    };
+    if let Some(key) = &task.jira {
+        title.push_str(&format!(" [{key}]"));
+    }
+    for tag in &task.tags {
+        title.push(' ');
+        title.push_str(&tags.label(tag));
+    }
+    title
+}
+
+/// The title lines of a listed task with search matches marked, followed
+/// by a snippet of the description if the search matches it.
+fn title_lines(
+    task: &Task,
+    tags: Tags,
+    glyphs: &Glyphs,
+    search: &Search,
+    width: Option<usize>,
+) -> Vec<String> {
+    let mut lines: Vec<String> = wrap_opt(&title(task, tags, glyphs), width)
+        .iter()
+        .map(|line| tags.paint(line, task, search))
+        .collect();
+    if let Some(snippet) = search.snippet(&task.description, &glyphs.ellipsis) {
+        lines.extend(
+            wrap_opt(&snippet, width)
+                .iter()
+                .map(|line| search.mark(line)),
+        );
+    }
+    lines
+}
+
+/// The `list` output: a table, or on terminals too narrow for one, a block
+/// of two lines per task.
+pub fn task_table(
+    tasks: &[&Task],
+    times: Times,
+    tags: Tags,
+    glyphs: &Glyphs,
+    search: &Search,
+    width: Option<usize>,
+) -> String {
+    // Priority and status columns widen for configured glyphs.
+    let labels: Vec<(String, String)> = tasks
+        .iter()
+        .map(|t| {
+            (
+                glyphs.priority_label(t.priority),
+                glyphs.status_label(t.status),
+            )
+        })
+        .collect();
+    let column = |label: fn(&(String, String)) -> &String, min: usize| {
+        labels
+            .iter()
+            .map(|l| label(l).chars().count())
+            .fold(min, usize::max)
+    };
+    let priority_width = column(|(p, _)| p, 8);
+    let status_width = column(|(_, s)| s, 11);
+    // The due column widens for longer configured date formats.
+    let dues: Vec<String> = tasks
+        .iter()
+        .map(|t| t.due.map(|d| times.show(d)).unwrap_or_default())
+        .collect();
+    let due_width = dues.iter().map(|d| d.chars().count()).fold(19, usize::max);
+    // Id, priority, status and due date with its overdue marker, plus the
+    // gaps between them.
+    let id_width = id_width();
+    let prefix = id_width + 2 + priority_width + 2 + status_width + 2 + due_width + 2;
+    let name_width = width.map(|w| w.saturating_sub(prefix));
+    if name_width.is_some_and(|w| w < MIN_NAME_WIDTH) {
+        return stacked(
+            tasks,
+            times,
+            tags,
+            glyphs,
+            search,
+            width.unwrap_or_default(),
+        );
+    }
+    let mut out = format!(
+        "{:<id_width$}  {:<priority_width$}  {:<status_width$}  {:<due_width$}  Name\n",
+        "ID", "Priority", "Status", "Due"
+    );
+    let theme = tags.color.then(theme::theme);
+    // Padded before colouring, so escapes do not count towards the width.
+    let paint = |escape: Option<&str>, text: String| match escape {
+        Some(escape) => theme::paint(escape, &text),
+        None => text,
+    };
+    for ((task, (priority, status)), due) in tasks.iter().zip(&labels).zip(dues) {
+        let overdue = task.is_overdue(times.now);
+        let marker = if overdue { glyphs.overdue } else { ' ' };
+        let priority = paint(
+            theme.as_ref().map(|t| t.priority(task.priority)),
+            format!("{priority:<priority_width$}"),
+        );
+        let status = paint(
+            theme.as_ref().map(|t| t.status(task.status)),
+            format!("{status:<status_width$}"),
+        );
+        let due = paint(
+            theme
+                .as_ref()
+                .filter(|_| overdue)
+                .map(|t| t.overdue.as_str()),
+            format!("{due:<due_width$}{marker}"),
+        );
+        let mut name = title_lines(task, tags, glyphs, search, name_width).into_iter();
+        out.push_str(&format!(
+            "{:<id_width$}  {priority}  {status}  {due} {}\n",
+            short_id(&task.id.to_string()),
+            name.next().unwrap_or_default()
+        ));
+        for line in name {
+            out.push_str(&format!("{:prefix$}{line}\n", ""));
+        }
+    }
+    out
+}
+
+fn stacked(
+    tasks: &[&Task],
+    times: Times,
+    tags: Tags,
+    glyphs: &Glyphs,
+    search: &Search,
+    width: usize,
+) -> String {
+    const INDENT: usize = 10;
+    let inner = width.saturating_sub(INDENT);
+    let mut out = String::new();
+    for task in tasks {
+        let mut details = vec![
+            glyphs.priority_label(task.priority),
+            glyphs.status_label(task.status),
+        ];
+        if let Some(due) = task.due {
+            let overdue = if task.is_overdue(times.now) {
+                " (overdue)"
+            } else {
+                ""
+            };
+            details.push(format!("due {}{overdue}", times.show(due)));
+        }
+        let lines = title_lines(task, tags, glyphs, search, Some(inner))
+            .into_iter()
+            .chain(wrap(&details.join(", "), inner));
+        for (i, line) in lines.enumerate() {
+            if i == 0 {
+                out.push_str(&format!(
+                    "{:<INDENT$}{line}\n",
+                    short_id(&task.id.to_string())
+                ));
+            } else {
+                out.push_str(&format!("{:INDENT$}{line}\n", ""));
+            }
+        }
+    }
+    out
+}
+
+/// The `show` output. `effective` is the priority after inheritance.
+pub fn task_detail(
+    task: &Task,
+    effective: Priority,
+    manager: &TasksManager,
+    times: Times,
+    glyphs: &Glyphs,
+    width: Option<usize>,
+) -> String {
+    let mut fields: Vec<(&str, String)> = vec![
+        ("id", task.id.to_string()),
+        ("status", glyphs.status_label(task.status)),
+    ];
+    if let Some(alias) = manager.alias_of(task.id) {
+        fields.insert(1, ("alias", alias.to_string()));
+    }
+    let priority = glyphs.priority_label(task.priority);
+    if effective == task.priority {
+        fields.push(("priority", priority));
+    } else {
+        fields.push((
+            "priority",
+            format!("{priority} ({effective} from subtasks)"),
+        ));
+    }
+    if let Some(project) = &task.project {
+        fields.push(("project", project.clone()));
+    }
+    if !task.tags.is_empty() {
+        fields.push(("tags", task.tags.join(", ")));
+    }
+    if let Some(key) = &task.jira {
+        fields.push(("jira", key.clone()));
+    }
+    if let Some(page) = &task.notion {
+        fields.push(("notion", page.clone()));
+    }
+    if let Some(linked) = &task.google {
+        fields.push(("google", linked.clone()));
+    }
+    if let Some(contact) = &task.contact {
+        fields.push(("contact", contact.clone()));
+    }
+    if task.pinned {
+        fields.push(("pinned", "yes".to_string()));
+    }
+    if !task.starred_by.is_empty() {
+        fields.push(("starred by", task.starred_by.join(", ")));
+    }
+    if task.review {
+        fields.push(("review", "required".to_string()));
+    }
+    if task.archived {
+        fields.push(("archived", "yes".to_string()));
+    }
+    if task.private {
+        fields.push(("private", "yes".to_string()));
+    }
+    fields.push(("created", times.show(task.created_at)));
+    if task.updated_at != task.created_at {
+        fields.push(("updated", times.show(task.updated_at)));
+    }
+    if let Some(due) = task.due {
+        fields.push(("due", times.show(due)));
+    }
+    if let Some(completed) = task.completed_at {
+        fields.push(("completed", times.show(completed)));
+    }
+    for event in &task.review_log {
+        fields.push((
+            event.action.as_str(),
+            format!("{} by {}", times.show(event.at), event.by),
+        ));
+    }
+    if !task.reminders.is_empty() {
+        let leads: Vec<String> = task
+            .reminders
+            .iter()
+            .map(|&lead| format!("{} before", duration::format(lead)))
+            .collect();
+        fields.push(("reminders", leads.join(", ")));
+    }
+    if let Some(parent) = task.parent.and_then(|id| manager.get(id)) {
+        fields.push((
+            "parent",
+            format!("{} {}", short_id(&parent.id.to_string()), parent.name),
+        ));
+    }
+    for dep in task.depends_on.iter().filter_map(|&id| manager.get(id)) {
+        fields.push((
+            "depends on",
+            format!(
+                "{} {} ({})",
+                short_id(&dep.id.to_string()),
+                dep.name,
+                dep.status
+            ),
+        ));
+    }
+    for other in manager.related(task.id) {
+        fields.push((
+            "related",
+            format!(
+                "{} {} ({})",
+                short_id(&other.id.to_string()),
+                other.name,
+                other.status
+            ),
+        ));
+    }
+    for child in manager.children(task.id) {
+        fields.push((
+            "subtask",
+            format!(
+                "{} {} ({})",
+                short_id(&child.id.to_string()),
+                child.name,
+                child.status
+            ),
+        ));
+    }
+
+    let mut out = String::new();
+    for line in wrap_opt(&task.name, width) {
+        out.push_str(&format!("{line}\n"));
+    }
+    let value_width = width.map(|w| w.saturating_sub(DETAIL_INDENT));
+    for (label, value) in fields {
+        let label = format!("  {label}:");
+        for (i, line) in wrap_opt(&value, value_width).into_iter().enumerate() {
+            let label = if i == 0 { label.as_str() } else { "" };
+            out.push_str(&format!("{label:<DETAIL_INDENT$}{line}\n"));
+        }
+    }
+    for text in [&task.description, &task.body] {
+        if !text.is_empty() {
+            out.push('\n');
+            for line in wrap_opt(text, width) {
+                out.push_str(&format!("{line}\n"));
+            }
+        }
+    }
+    out
+}
+
+/// A Markdown checklist of `tasks`, one `- [ ]` item each, tagged with the
+/// task id the way `sync markdown` reads it back.
+pub fn markdown(tasks: &[&Task]) -> String {
+    let mut out = String::new();
+    for task in tasks {
+        let check = if task.status.is_open() { ' ' } else { 'x' };
+        // A line break would end the item.
+        let name = task.name.split_whitespace().collect::<Vec<_>>().join(" ");
+        out.push_str(&format!("- [{check}] {name} {MARKER}{} -->\n", task.id));
+    }
+    out
+}
+
+/// One plain sentence about a task for `--brief` output, made to be read
+/// aloud: `Pay rent: high priority, in progress, due in 2 days, project
+/// home. Id 3f2a1b4c.` Nothing is shown by layout, symbols or colour.
+pub fn brief(task: &Task, now: Timestamp) -> String {
+    let name = task.name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut facts = vec![format!("{} priority", task.priority)];
+    if task.status != Status::Todo {
+        facts.push(task.status.as_str().replace('-', " "));
+    }
+    if let Some(due) = task.due {
+        facts.push(format!("due {}", due.relative_to(now)));
+    }
+    if let Some(project) = &task.project {
+        facts.push(format!("project {project}"));
+    }
+    format!(
+        "{name}: {}. Id {}.",
+        facts.join(", "),
+        short_id(&task.id.to_string())
+    )
+}
+
+/// How many of `width` cells stand for `part` out of `whole`. Rounded down,
+/// so a bar is only full once the whole is reached.
+pub fn bar_cells(part: usize, whole: usize, width: usize) -> usize {
+    (part.min(whole) * width).checked_div(whole).unwrap_or(0)
+}
+
+/// `done` out of `total` as a bar `width` cells wide and a percentage,
+/// like `██████░░░░  60%`; just `-` when there is nothing to count.
+pub fn progress_bar(done: usize, total: usize, width: usize, glyphs: &Glyphs) -> String {
+    if total == 0 {
+        return format!("{:>w$}", "-", w = width + 5);
+    }
+    let [fill, rest] = glyphs.progress;
+    let cells = bar_cells(done, total, width);
+    format!(
+        "{}{} {:>3}%",
+        fill.to_string().repeat(cells),
+        rest.to_string().repeat(width - cells),
+        done.min(total) * 100 / total
+    )
+}
+
+static ID_WIDTH: AtomicUsize = AtomicUsize::new(8);
+
+/// Characters of an id [`short_id`] shows: eight until set, which the
+/// command line does from [`TasksManager::id_width`] so that ids are as
+/// short as they can be while still telling every task apart.
+pub fn id_width() -> usize {
+    ID_WIDTH.load(Ordering::Relaxed)
+}
+
+/// Shows `digits` hex digits of each id, past the first dash if need be.
+pub fn set_id_width(digits: usize) {
+    let digits = digits.clamp(MIN_ID_PREFIX, 12);
+    let width = if digits > 8 { digits + 1 } else { digits };
+    ID_WIDTH.store(width, Ordering::Relaxed);
+}
+
+/// The start of an id, [`id_width`] characters long: enough to tell tasks
+/// apart.
+pub fn short_id(id: &str) -> &str {
+    &id[..id_width()]
+}
+
+/// `text` without ANSI escape sequences, for comparing coloured output.
+#[cfg(feature = "test-util")]
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        // CSI sequences run to their final byte, `@` to `~`.
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,174 @@
+//! Colour themes for terminal output.
+//!
+//! A theme gives the escape sequence for each priority and status in task
+//! tables, for overdue dates, today in the calendar, search matches and
+//! the heatmap. Four are built in and `[theme]` can replace single colours
+//! of any of them. Like the date display settings, the theme in use is
+//! process-wide: it is set once from the configuration and may be switched
+//! during an interactive session.
+
+use std::sync::{PoisonError, RwLock};
+
+use crate::config::{ThemeName, ThemeSettings};
+use crate::task::{Priority, Status};
+
+use super::highlight::RESET;
+
+/// Escape sequences, each empty for plain text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub name: ThemeName,
+    /// Low, medium, high and critical.
+    pub priority: [String; 4],
+    /// To do, in progress, review pending, done and cancelled.
+    pub status: [String; 5],
+    pub overdue: String,
+    pub today: String,
+    /// Search matches.
+    pub mark: String,
+    /// Busy days in the heatmap.
+    pub heat: String,
+}
+
+static THEME: RwLock<Option<Theme>> = RwLock::new(None);
+
+/// The theme in use: the default one until [`set_theme`] is called.
+pub fn theme() -> Theme {
+    THEME
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .unwrap_or_else(|| Theme::named(ThemeName::Default))
+}
+
+pub fn set_theme(theme: Theme) {
+    *THEME.write().unwrap_or_else(PoisonError::into_inner) = Some(theme);
+}
+
+impl Theme {
+    pub fn named(name: ThemeName) -> Self {
+        let [low, medium, high, critical, todo, progress, review, done, cancelled, overdue, today, mark, heat] =
+            match name {
+                ThemeName::Default => [
+                    "\x1b[2m",
+                    "",
+                    "\x1b[33m",
+                    "\x1b[1;31m",
+                    "",
+                    "\x1b[36m",
+                    "\x1b[35m",
+                    "\x1b[32m",
+                    "\x1b[2m",
+                    "\x1b[31m",
+                    "\x1b[1m",
+                    "\x1b[1;33m",
+                    "\x1b[32m",
+                ],
+                ThemeName::Solarized => [
+                    "\x1b[38;2;88;110;117m",
+                    "\x1b[38;2;181;137;0m",
+                    "\x1b[38;2;203;75;22m",
+                    "\x1b[1;38;2;220;50;47m",
+                    "",
+                    "\x1b[38;2;38;139;210m",
+                    "\x1b[38;2;211;54;130m",
+                    "\x1b[38;2;133;153;0m",
+                    "\x1b[38;2;88;110;117m",
+                    "\x1b[38;2;220;50;47m",
+                    "\x1b[1;38;2;42;161;152m",
+                    "\x1b[1;38;2;181;137;0m",
+                    "\x1b[38;2;133;153;0m",
+                ],
+                ThemeName::HighContrast => [
+                    "\x1b[37m",
+                    "\x1b[97m",
+                    "\x1b[1;93m",
+                    "\x1b[1;97;41m",
+                    "\x1b[97m",
+                    "\x1b[1;96m",
+                    "\x1b[1;95m",
+                    "\x1b[1;92m",
+                    "\x1b[37m",
+                    "\x1b[1;97;41m",
+                    "\x1b[1;4m",
+                    "\x1b[1;30;103m",
+                    "\x1b[1;92m",
+                ],
+                ThemeName::Monochrome => [
+                    "\x1b[2m",
+                    "",
+                    "\x1b[1m",
+                    "\x1b[1;4m",
+                    "",
+                    "\x1b[1m",
+                    "\x1b[4m",
+                    "\x1b[2m",
+                    "\x1b[2;9m",
+                    "\x1b[1;4m",
+                    "\x1b[7m",
+                    "\x1b[7m",
+                    "\x1b[1m",
+                ],
+            };
+        Theme {
+            name,
+            priority: [low, medium, high, critical].map(String::from),
+            status: [todo, progress, review, done, cancelled].map(String::from),
+            overdue: overdue.to_string(),
+            today: today.to_string(),
+            mark: mark.to_string(),
+            heat: heat.to_string(),
+        }
+    }
+
+    /// The configured theme with its overridden colours.
+    pub fn from_settings(settings: &ThemeSettings) -> Self {
+        let mut theme = Theme::named(settings.name);
+        for (key, color) in &settings.colors {
+            let slot = match key.as_str() {
+                "low" => &mut theme.priority[0],
+                "medium" => &mut theme.priority[1],
+                "high" => &mut theme.priority[2],
+                "critical" => &mut theme.priority[3],
+                "todo" => &mut theme.status[0],
+                "in-progress" => &mut theme.status[1],
+                "review-pending" => &mut theme.status[2],
+                "done" => &mut theme.status[3],
+                "cancelled" => &mut theme.status[4],
+                "overdue" => &mut theme.overdue,
+                // Keys are checked when the configuration is read.
+                _ => continue,
+            };
+            *slot = color.ansi();
+        }
+        theme
+    }
+
+    pub fn priority(&self, priority: Priority) -> &str {
+        &self.priority[match priority {
+            Priority::Low => 0,
+            Priority::Medium => 1,
+            Priority::High => 2,
+            Priority::Critical => 3,
+        }]
+    }
+
+    pub fn status(&self, status: Status) -> &str {
+        &self.status[match status {
+            Status::Todo => 0,
+            Status::InProgress => 1,
+            Status::ReviewPending => 2,
+            Status::Done => 3,
+            Status::Cancelled => 4,
+        }]
+    }
+}
+
+/// `text` in `escape`, reset afterwards; unchanged when `escape` is empty.
+pub fn paint(escape: &str, text: &str) -> String {
+    if escape.is_empty() {
+        text.to_string()
+    } else {
+        format!("{escape}{text}{RESET}")
+    }
+}
@@ -0,0 +1,106 @@
+//! Automation rules from the `[[rules]]` tables of the config: when a task
+//! comes to match a filter, apply `set` updates to it.
+//!
+//! A rule fires on a task once, when it starts to match: the task is new,
+//! or its previous version did not match at the time of its last change.
+//! So `when = "tag:bug"` fires when the tag is added and not again after
+//! someone lowers the priority by hand, and `when = "is:overdue"` fires on
+//! the first check after the due date passes. Rules run in order, each on
+//! the result of the ones before.
+
+use crate::dates::Timestamp;
+use crate::error::{Error, Result};
+use crate::filter;
+use crate::manager::TasksManager;
+use crate::task::{Task, TaskId};
+use crate::update::{self, FieldChange, Update};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    /// How the rule is reported; the `when` expression unless named.
+    pub name: String,
+    /// A filter expression, written as for `list`.
+    pub when: String,
+    /// Updates written as for `set`, like `priority=high` or `+triage`.
+    pub set: Vec<String>,
+}
+
+impl Rule {
+    /// Checks `when` and `set` so a mistake shows when the config is read
+    /// rather than on some later save. Updates cannot name tasks, which
+    /// differ from one data file to the next.
+    pub fn new(name: Option<String>, when: String, set: Vec<String>) -> Result<Self> {
+        let now = Timestamp::now();
+        let in_rule = |err: Error| Error::Invalid(format!("rule `{when}`: {err}"));
+        filter::parse(&when, now).map_err(in_rule)?;
+        if set.is_empty() {
+            return Err(in_rule(Error::Invalid("`set` is empty".into())));
+        }
+        let empty = TasksManager::default();
+        for update in &set {
+            Update::parse(update, now, &empty).map_err(in_rule)?;
+        }
+        Ok(Rule {
+            name: name.unwrap_or_else(|| when.clone()),
+            when,
+            set,
+        })
+    }
+
+    fn matches(&self, task: &Task, at: Timestamp) -> Result<bool> {
+        Ok(filter::parse(&self.when, at)?.matches(task, at))
+    }
+}
+
+/// A rule that changed a task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Firing {
+    pub rule: String,
+    pub task: TaskId,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Applies `rules` to the tasks of `manager` that match one at `now` and
+/// did not as they were in `before`. Rules that matched but changed
+/// nothing are left out of the result.
+pub fn run(
+    rules: &[Rule],
+    manager: &mut TasksManager,
+    before: &[Task],
+    now: Timestamp,
+) -> Result<Vec<Firing>> {
+    let mut firings = Vec::new();
+    for rule in rules {
+        let updates = rule
+            .set
+            .iter()
+            .map(|update| Update::parse(update, now, manager))
+            .collect::<Result<Vec<_>>>()?;
+        let mut ids = Vec::new();
+        for task in manager.tasks() {
+            if !rule.matches(task, now)? {
+                continue;
+            }
+            let previous = before.iter().find(|t| t.id == task.id);
+            let matched = match previous {
+                Some(previous) => rule.matches(previous, previous.updated_at.min(now))?,
+                None => false,
+            };
+            if !matched {
+                ids.push(task.id);
+            }
+        }
+        for id in ids {
+            let task = manager.get_mut(id).expect("listed above");
+            let changes = update::apply(task, &updates);
+            if !changes.is_empty() {
+                firings.push(Firing {
+                    rule: rule.name.clone(),
+                    task: id,
+                    changes,
+                });
+            }
+        }
+    }
+    Ok(firings)
+}
@@ -0,0 +1,314 @@
+//! Recurring task templates: a task description plus a cron schedule.
+//!
+//! `tick` turns each template whose schedule has fired since its last run
+//! into a task and moves the template's watermark forward, so running it
+//! from the system scheduler every few minutes is enough. Schedules are
+//! evaluated in the display time zone.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::dates::{self, Timestamp, SECONDS_PER_DAY};
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::manager::TasksManager;
+use crate::task::{Task, TaskId};
+use crate::update::{self, Update};
+
+/// How far ahead [`Cron::next_after`] looks before giving up, in days. Long
+/// enough for schedules such as "Monday the 29th of February".
+const SEARCH_DAYS: i64 = 400 * 366;
+
+/// A five-field cron expression (`minute hour day-of-month month
+/// day-of-week`) or one of the `@daily`-style shorthands.
+///
+/// Fields take `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps
+/// (`*/15`, `8-18/2`); months and weekdays also take three-letter names.
+/// Sunday is 0 or 7. As in cron, when both day fields are restricted a day
+/// matching either one fires.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cron {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// The first firing strictly after `after`, to the minute.
+    pub fn next_after(&self, after: Timestamp) -> Option<Timestamp> {
+        let local = after.to_local();
+        let mut minute = local.hour as i64 * 60 + local.minute as i64 + 1;
+        let mut day = after.start_of_day();
+        for _ in 0..SEARCH_DAYS {
+            if self.day_matches(day) {
+                while minute < 24 * 60 {
+                    if has(self.hours, minute / 60) && has(self.minutes, minute % 60) {
+                        return Some(day.add_secs(minute * 60));
+                    }
+                    minute += 1;
+                }
+            }
+            minute = 0;
+            day = day.add_secs(SECONDS_PER_DAY).start_of_day();
+        }
+        None
+    }
+
+    fn day_matches(&self, day: Timestamp) -> bool {
+        let date = day.to_local();
+        if !has(self.months, date.month as i64) {
+            return false;
+        }
+        let by_day = has(self.days, date.day as i64);
+        let by_weekday = has(self.weekdays, (day.weekday().index() as i64 + 1) % 7);
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => by_weekday,
+            (false, true) => by_day,
+            (false, false) => by_day || by_weekday,
+        }
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.source)
+    }
+}
+
+impl FromStr for Cron {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let source = s.split_whitespace().collect::<Vec<_>>().join(" ");
+        let invalid =
+            |detail: &str| Error::Invalid(format!("invalid cron expression `{source}`: {detail}"));
+        let expanded = match source.to_ascii_lowercase().as_str() {
+            "@yearly" | "@annually" => "0 0 1 1 *".to_string(),
+            "@monthly" => "0 0 1 * *".to_string(),
+            // At the start of the configured week; cron counts from Sunday.
+            "@weekly" => format!("0 0 * * {}", (dates::week_start().index() + 1) % 7),
+            "@daily" | "@midnight" => "0 0 * * *".to_string(),
+            "@hourly" => "0 * * * *".to_string(),
+            _ if source.starts_with('@') => return Err(invalid("unknown shorthand")),
+            _ => source.clone(),
+        };
+        let fields: Vec<&str> = expanded.split(' ').collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(invalid(
+                "expected five fields: minute hour day-of-month month day-of-week",
+            ));
+        };
+        let mut weekdays = field(weekday, 0, 7, &WEEKDAY_NAMES).map_err(|e| invalid(&e))?;
+        // 7 is another name for Sunday.
+        if has(weekdays, 7) {
+            weekdays |= 1;
+        }
+        Ok(Cron {
+            minutes: field(minute, 0, 59, &[]).map_err(|e| invalid(&e))?,
+            hours: field(hour, 0, 23, &[]).map_err(|e| invalid(&e))?,
+            days: field(day, 1, 31, &[]).map_err(|e| invalid(&e))?,
+            months: field(month, 1, 12, &MONTH_NAMES).map_err(|e| invalid(&e))?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+            source,
+        })
+    }
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+fn has(set: u64, value: i64) -> bool {
+    (0..64).contains(&value) && set & (1 << value) != 0
+}
+
+/// Parses one cron field into a bit set. `names[i]` stands for `min + i`.
+fn field(text: &str, min: i64, max: i64, names: &[&str]) -> std::result::Result<u64, String> {
+    let value = |text: &str| -> std::result::Result<i64, String> {
+        let lower = text.to_ascii_lowercase();
+        let n = match names.iter().position(|name| *name == lower) {
+            Some(index) => min + index as i64,
+            None => text
+                .parse()
+                .map_err(|_| format!("`{text}` is not a number"))?,
+        };
+        if (min..=max).contains(&n) {
+            Ok(n)
+        } else {
+            Err(format!("{n} is outside {min}-{max}"))
+        }
+    };
+    let mut set = 0;
+    for item in text.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: i64 = step
+                    .parse()
+                    .ok()
+                    .filter(|&s| s > 0)
+                    .ok_or_else(|| format!("invalid step `{step}`"))?;
+                (range, step)
+            }
+            None => (item, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (value(a)?, value(b)?),
+                // `5/15` runs from 5 to the end of the range.
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let n = value(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("range `{range}` runs backwards"));
+        }
+        let mut n = start;
+        while n <= end {
+            set |= 1 << n;
+            n += step;
+        }
+    }
+    Ok(set)
+}
+
+/// A recurring task: `fields` are `set`-style assignments (`priority=high`,
+/// `+tag`, `due=tomorrow`) applied to a new task named `name` each time the
+/// schedule fires. Relative dates are taken from the firing time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    pub name: String,
+    pub cron: Cron,
+    pub fields: Vec<String>,
+    /// Firings up to this time have been handled.
+    pub last_run: Timestamp,
+}
+
+/// One task created by [`tick`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Run {
+    pub template: String,
+    pub task: TaskId,
+    pub fired_at: Timestamp,
+    /// Earlier firings since the last run that were folded into this one.
+    pub skipped: usize,
+}
+
+impl Template {
+    /// A template that first fires after `now`. The fields are checked here
+    /// so mistakes show up straight away rather than at the next tick.
+    pub fn new(
+        name: String,
+        cron: Cron,
+        fields: Vec<String>,
+        now: Timestamp,
+        manager: &TasksManager,
+    ) -> Result<Self> {
+        let template = Template {
+            name,
+            cron,
+            fields,
+            last_run: now,
+        };
+        template.task(now, manager)?;
+        Ok(template)
+    }
+
+    /// The task this template creates when it fires at `at`.
+    pub fn task(&self, at: Timestamp, manager: &TasksManager) -> Result<Task> {
+        let updates = self
+            .fields
+            .iter()
+            .map(|field| Update::parse(field, at, manager))
+            .collect::<Result<Vec<_>>>()
+            .map_err(|err| Error::Invalid(format!("template `{}`: {err}", self.name)))?;
+        let mut task = Task::new(self.name.clone());
+        update::apply(&mut task, &updates);
+        Ok(task)
+    }
+
+    /// The firings in `(last_run, now]`, oldest first.
+    pub fn firings(&self, now: Timestamp) -> Vec<Timestamp> {
+        let mut firings = Vec::new();
+        let mut after = self.last_run;
+        while let Some(next) = self.cron.next_after(after).filter(|&t| t <= now) {
+            firings.push(next);
+            after = next;
+        }
+        firings
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut obj = Value::object();
+        obj.insert("name", self.name.as_str());
+        obj.insert("cron", self.cron.as_str());
+        obj.insert(
+            "fields",
+            Value::Array(
+                self.fields
+                    .iter()
+                    .map(|f| Value::from(f.as_str()))
+                    .collect(),
+            ),
+        );
+        obj.insert("last_run", self.last_run.to_rfc3339());
+        obj
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let invalid = || Error::Invalid("malformed task template".into());
+        let text = |key: &str| value.get(key).and_then(Value::as_str).ok_or_else(invalid);
+        let fields = value
+            .get("fields")
+            .and_then(Value::as_array)
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|f| f.as_str().map(str::to_string).ok_or_else(invalid))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Template {
+            name: text("name")?.to_string(),
+            cron: text("cron")?.parse()?,
+            fields,
+            last_run: Timestamp::parse_stored(text("last_run")?)?,
+        })
+    }
+}
+
+/// Creates a task for every template whose schedule fired in
+/// `(last_run, now]` and moves the watermarks to `now`. Several missed
+/// firings of one template give a single task, for the latest of them.
+pub fn tick(manager: &mut TasksManager, now: Timestamp) -> Result<Vec<Run>> {
+    let mut runs = Vec::new();
+    for index in 0..manager.templates().len() {
+        let template = &manager.templates()[index];
+        let firings = template.firings(now);
+        if let Some(&fired_at) = firings.last() {
+            let task = template.task(fired_at, manager)?;
+            runs.push(Run {
+                template: template.name.clone(),
+                task: manager.add(task),
+                fired_at,
+                skipped: firings.len() - 1,
+            });
+        }
+        manager.templates_mut()[index].last_run = now;
+    }
+    Ok(runs)
+}
@@ -0,0 +1,223 @@
+//! A data store spread over a directory instead of one file, for task lists
+//! kept in git by a team: each project (or status, or priority) gets its own
+//! file, so changes touch fewer lines and rarely conflict.
+//!
+//! ```text
+//! tasks/
+//!   store.json          version, how tasks are split, history, templates
+//!   tasks/website.json  {"version": 1, "tasks": [...]}
+//!   tasks/no-project.json
+//! ```
+//!
+//! Files under `tasks/` are only a grouping: loading reads every one of
+//! them, so moving a task between files by hand is harmless. Tasks are
+//! written in creation order and unchanged files are left alone.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+use crate::manager::TasksManager;
+use crate::schedule::Template;
+use crate::storage::{self, FORMAT_VERSION};
+use crate::task::Task;
+
+/// The file holding everything but the tasks, which also marks a directory
+/// as a store.
+pub const STORE_FILE: &str = "store.json";
+
+/// Directory of the task files, inside the store.
+pub const TASKS_DIR: &str = "tasks";
+
+/// Which file a task is written to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SplitBy {
+    #[default]
+    Project,
+    Status,
+    Priority,
+}
+
+impl SplitBy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SplitBy::Project => "project",
+            SplitBy::Status => "status",
+            SplitBy::Priority => "priority",
+        }
+    }
+
+    /// The file name, without `.json`, for `task`.
+    fn key(self, task: &Task) -> String {
+        match self {
+            SplitBy::Project => match task.project.as_deref().map(slug) {
+                Some(slug) if !slug.is_empty() => slug,
+                _ => "no-project".to_string(),
+            },
+            SplitBy::Status => task.status.as_str().to_string(),
+            SplitBy::Priority => task.priority.as_str().to_string(),
+        }
+    }
+}
+
+impl FromStr for SplitBy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "project" => Ok(SplitBy::Project),
+            "status" => Ok(SplitBy::Status),
+            "priority" => Ok(SplitBy::Priority),
+            _ => Err(Error::Invalid(format!(
+                "unknown split `{s}` (expected project, status or priority)"
+            ))),
+        }
+    }
+}
+
+/// Whether `path` is a directory store rather than a data file.
+pub fn is_store(path: &Path) -> bool {
+    path.join(STORE_FILE).is_file()
+}
+
+/// Turns `dir`, which must be empty or missing, into a store holding
+/// `manager`.
+pub fn create(dir: &Path, manager: &TasksManager, by: SplitBy) -> Result<()> {
+    if fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(Error::Conflict(format!(
+            "{} is not empty; choose a new directory",
+            dir.display()
+        )));
+    }
+    fs::create_dir_all(dir.join(TASKS_DIR))?;
+    save_as(dir, manager, by)
+}
+
+pub fn load(dir: &Path) -> Result<TasksManager> {
+    let mut tasks = Vec::new();
+    for path in task_files(dir)? {
+        let file = read_json(&path)?;
+        let in_file = |err: Error| Error::Invalid(format!("{}: {err}", path.display()));
+        let items = file
+            .get("tasks")
+            .and_then(Value::as_array)
+            .ok_or_else(|| in_file(Error::Invalid("no `tasks` array".into())))?;
+        for item in items {
+            tasks.push(Task::from_json(item).map_err(in_file)?);
+        }
+    }
+    tasks.sort_by_key(|t| (t.created_at, t.id));
+    let mut root = read_json(&dir.join(STORE_FILE))?;
+    root.insert("tasks", Value::Array(Vec::new()));
+    let rest = storage::decode(&root)?;
+    Ok(TasksManager::from_tasks(tasks)
+        .with_history(rest.history().clone())
+        .with_templates(rest.templates().to_vec())
+        .with_archived(rest.archived_projects().to_vec())
+        .with_aliases(rest.aliases().to_vec()))
+}
+
+/// Writes `manager` split the way the store already is.
+pub fn save(dir: &Path, manager: &TasksManager) -> Result<()> {
+    let root = read_json(&dir.join(STORE_FILE))?;
+    let by = match root.get("split_by").and_then(Value::as_str) {
+        Some(by) => by.parse()?,
+        None => SplitBy::default(),
+    };
+    save_as(dir, manager, by)
+}
+
+fn save_as(dir: &Path, manager: &TasksManager, by: SplitBy) -> Result<()> {
+    let mut root = Value::object();
+    root.insert("version", FORMAT_VERSION);
+    root.insert("split_by", by.as_str());
+    root.insert("history", manager.history().to_json());
+    if !manager.templates().is_empty() {
+        root.insert(
+            "templates",
+            Value::Array(manager.templates().iter().map(Template::to_json).collect()),
+        );
+    }
+    if !manager.archived_projects().is_empty() {
+        root.insert("archived_projects", manager.archived_projects().to_vec());
+    }
+    if !manager.aliases().is_empty() {
+        root.insert("aliases", storage::aliases_to_json(manager.aliases()));
+    }
+    write_if_changed(&dir.join(STORE_FILE), &root)?;
+
+    let mut groups: BTreeMap<String, Vec<&Task>> = BTreeMap::new();
+    for task in manager.tasks() {
+        groups.entry(by.key(task)).or_default().push(task);
+    }
+    let tasks_dir = dir.join(TASKS_DIR);
+    fs::create_dir_all(&tasks_dir)?;
+    let mut written = Vec::new();
+    for (key, mut tasks) in groups {
+        tasks.sort_by_key(|t| (t.created_at, t.id));
+        let mut file = Value::object();
+        file.insert("version", FORMAT_VERSION);
+        file.insert(
+            "tasks",
+            Value::Array(tasks.iter().map(|t| t.to_json()).collect()),
+        );
+        let path = tasks_dir.join(format!("{key}.json"));
+        write_if_changed(&path, &file)?;
+        written.push(path);
+    }
+    for path in task_files(dir)? {
+        if !written.contains(&path) {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// The `.json` files under the tasks directory, sorted by name.
+fn task_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir.join(TASKS_DIR))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn read_json(path: &Path) -> Result<Value> {
+    let text = fs::read_to_string(path)?;
+    json::parse(&text).map_err(|err| Error::Invalid(format!("{}: {err}", path.display())))
+}
+
+/// Replaces `path` with `value` through a temporary sibling, unless it
+/// already holds exactly that.
+fn write_if_changed(path: &Path, value: &Value) -> Result<()> {
+    let mut contents = value.to_pretty();
+    contents.push('\n');
+    if fs::read(path).ok().as_deref() == Some(contents.as_bytes()) {
+        return Ok(());
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Lowercase letters, digits and dashes.
+fn slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
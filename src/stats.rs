@@ -0,0 +1,436 @@
+//! Aggregate counts and their day-by-day history.
+//!
+//! One [`Snapshot`] per calendar day is kept in the data file; saving again on
+//! the same day replaces it, so each entry holds the state at the last save of
+//! that day. Trend reports read this history instead of replaying every task.
+//!
+//! Per-tag and per-project [`Analytics`] are worked out from the tasks
+//! instead, as [`completions`] are.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use crate::dates::{Timestamp, SECONDS_PER_DAY};
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::task::{Priority, Status, Task, TaskId};
+
+/// Snapshots older than this many days are dropped.
+pub const DEFAULT_RETENTION_DAYS: i64 = 400;
+
+/// Counts indexed by [`Priority`] (low, medium, high, critical).
+pub type PriorityCounts = [usize; 4];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProjectCounts {
+    pub open: usize,
+    pub done: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Start of the day the snapshot describes.
+    pub day: Timestamp,
+    pub open: PriorityCounts,
+    pub done: PriorityCounts,
+    pub projects: BTreeMap<String, ProjectCounts>,
+}
+
+impl Snapshot {
+    pub fn capture(tasks: &[Task], now: Timestamp) -> Self {
+        let mut snapshot = Snapshot {
+            day: now.start_of_day(),
+            open: [0; 4],
+            done: [0; 4],
+            projects: BTreeMap::new(),
+        };
+        for task in tasks {
+            let index = task.priority as usize;
+            let project = task
+                .project
+                .as_ref()
+                .map(|name| snapshot.projects.entry(name.clone()).or_default());
+            if task.status.is_open() {
+                snapshot.open[index] += 1;
+                if let Some(counts) = project {
+                    counts.open += 1;
+                }
+            } else if task.status == Status::Done {
+                snapshot.done[index] += 1;
+                if let Some(counts) = project {
+                    counts.done += 1;
+                }
+            }
+        }
+        snapshot
+    }
+
+    pub fn open_total(&self) -> usize {
+        self.open.iter().sum()
+    }
+
+    pub fn done_total(&self) -> usize {
+        self.done.iter().sum()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut obj = Value::object();
+        obj.insert("day", self.day.format_local("%Y-%m-%d"));
+        obj.insert("open", self.open.to_vec());
+        obj.insert("done", self.done.to_vec());
+        let mut projects = Value::object();
+        for (name, counts) in &self.projects {
+            projects.insert(name, vec![counts.open, counts.done]);
+        }
+        obj.insert("projects", projects);
+        obj
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let invalid = || Error::Invalid("malformed stats snapshot".into());
+        let day = value
+            .get("day")
+            .and_then(Value::as_str)
+            .ok_or_else(invalid)?;
+        // Days are local to the display time zone.
+        let day = Timestamp::parse_rfc3339(&format!("{day}T00:00:00Z"))?
+            .to_datetime()
+            .to_local_timestamp()
+            .ok_or_else(invalid)?;
+        let counts = |key: &str| -> Result<PriorityCounts> {
+            let items = value
+                .get(key)
+                .and_then(Value::as_array)
+                .ok_or_else(invalid)?;
+            let mut counts = [0; 4];
+            for (slot, item) in counts.iter_mut().zip(items) {
+                *slot = item.as_i64().ok_or_else(invalid)? as usize;
+            }
+            Ok(counts)
+        };
+        let mut projects = BTreeMap::new();
+        if let Some(Value::Object(fields)) = value.get("projects") {
+            for (name, pair) in fields {
+                let pair = pair.as_array().ok_or_else(invalid)?;
+                let get = |i: usize| pair.get(i).and_then(Value::as_i64).unwrap_or(0) as usize;
+                projects.insert(
+                    name.clone(),
+                    ProjectCounts {
+                        open: get(0),
+                        done: get(1),
+                    },
+                );
+            }
+        }
+        Ok(Snapshot {
+            day,
+            open: counts("open")?,
+            done: counts("done")?,
+            projects,
+        })
+    }
+}
+
+/// Day-ordered snapshots, at most one per day.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct History {
+    snapshots: Vec<Snapshot>,
+}
+
+impl History {
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    /// Stores `snapshot`, replacing any earlier one for the same day, and
+    /// drops entries older than `retention_days`.
+    pub fn record(&mut self, snapshot: Snapshot, retention_days: i64) {
+        let cutoff = snapshot.day.add_secs(-retention_days * SECONDS_PER_DAY);
+        self.snapshots
+            .retain(|s| s.day != snapshot.day && s.day >= cutoff);
+        let index = self.snapshots.partition_point(|s| s.day < snapshot.day);
+        self.snapshots.insert(index, snapshot);
+    }
+
+    /// The snapshot in effect on `day`: the latest one taken on or before it.
+    pub fn as_of(&self, day: Timestamp) -> Option<&Snapshot> {
+        let day = day.start_of_day();
+        self.snapshots.iter().rev().find(|s| s.day <= day)
+    }
+
+    /// One entry per day over the last `days` days ending at `now`, carrying
+    /// the previous snapshot forward over days without one.
+    pub fn trend(&self, now: Timestamp, days: i64) -> Vec<(Timestamp, Option<&Snapshot>)> {
+        let today = now.start_of_day();
+        (0..days)
+            .rev()
+            .map(|back| {
+                let day = today.add_secs(-back * SECONDS_PER_DAY);
+                (day, self.as_of(day))
+            })
+            .collect()
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::Array(self.snapshots.iter().map(Snapshot::to_json).collect())
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let mut snapshots = match value {
+            Value::Null => Vec::new(),
+            Value::Array(items) => items
+                .iter()
+                .map(Snapshot::from_json)
+                .collect::<Result<Vec<_>>>()?,
+            _ => return Err(Error::Invalid("`history` must be an array".into())),
+        };
+        snapshots.sort_by_key(|s| s.day);
+        snapshots.dedup_by_key(|s| s.day);
+        Ok(History { snapshots })
+    }
+}
+
+/// The backlog at a glance, as the interactive prompt shows it above the
+/// menu. Archived tasks are left out, as they are from listings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub open: usize,
+    /// Open tasks due later today; ones already past due count as overdue.
+    pub due_today: usize,
+    pub overdue: usize,
+    pub in_progress: usize,
+}
+
+impl Summary {
+    pub fn of(tasks: &[Task], now: Timestamp) -> Self {
+        let mut summary = Summary::default();
+        let today = now.day_number();
+        for task in tasks.iter().filter(|t| t.status.is_open() && !t.archived) {
+            summary.open += 1;
+            if task.is_overdue(now) {
+                summary.overdue += 1;
+            } else if task.due.is_some_and(|due| due.day_number() == today) {
+                summary.due_today += 1;
+            }
+            if task.status == Status::InProgress {
+                summary.in_progress += 1;
+            }
+        }
+        summary
+    }
+}
+
+/// The `today` view: what needs attention today, most pressing first. A
+/// task is listed in the first section it fits; archived and finished ones
+/// are left out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Today<'a> {
+    /// Past their due date, longest overdue first; ties go by priority,
+    /// then age.
+    pub overdue: Vec<&'a Task>,
+    /// Due later today, soonest first.
+    pub due_today: Vec<&'a Task>,
+    /// High or critical priority and no due date, highest first, then
+    /// oldest.
+    pub high_priority: Vec<&'a Task>,
+}
+
+impl<'a> Today<'a> {
+    pub fn of(tasks: impl IntoIterator<Item = &'a Task>, now: Timestamp) -> Self {
+        let mut today = Today::default();
+        for task in tasks {
+            if !task.status.is_open() || task.archived {
+                continue;
+            }
+            match task.due {
+                Some(_) if task.is_overdue(now) => today.overdue.push(task),
+                Some(due) if due.day_number() == now.day_number() => today.due_today.push(task),
+                None if task.priority >= Priority::High => today.high_priority.push(task),
+                _ => {}
+            }
+        }
+        today
+            .overdue
+            .sort_by_key(|t| (t.due, Reverse(t.priority), t.created_at));
+        today
+            .due_today
+            .sort_by_key(|t| (t.due, Reverse(t.priority), t.created_at));
+        today
+            .high_priority
+            .sort_by_key(|t| (Reverse(t.priority), t.created_at));
+        today
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overdue.is_empty() && self.due_today.is_empty() && self.high_priority.is_empty()
+    }
+}
+
+/// Tasks completed on each of the last `days` days up to today, oldest
+/// first, read from `completed_at` in the display time zone. Unlike the
+/// snapshots this needs no history: it is worked out from the tasks.
+pub fn completions(tasks: &[Task], now: Timestamp, days: i64) -> Vec<(Timestamp, usize)> {
+    let today = now.start_of_day();
+    let first = today.add_secs(-(days - 1) * SECONDS_PER_DAY).day_number();
+    let mut counts = vec![0; days.max(0) as usize];
+    for completed in tasks.iter().filter_map(|t| t.completed_at) {
+        let index = completed.day_number() - first;
+        if (0..days).contains(&index) {
+            counts[index as usize] += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let back = days - 1 - i as i64;
+            (today.add_secs(-back * SECONDS_PER_DAY), count)
+        })
+        .collect()
+}
+
+/// How fast the tasks of one tag or project get done, see [`by_tag`] and
+/// [`by_project`]. Cancelled tasks count for nothing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analytics {
+    /// The tag or project as first spelled; `None` for tasks without a
+    /// project.
+    pub name: Option<String>,
+    pub open: usize,
+    pub done: usize,
+    /// Mean seconds from creation to completion of the done tasks.
+    pub mean: Option<i64>,
+    /// Median of the same, the middle two averaged.
+    pub median: Option<i64>,
+    /// The open task created first, and its age in seconds.
+    pub oldest_open: Option<(TaskId, i64)>,
+    /// Tasks completed in each of the weeks up to now, oldest first: the
+    /// last entry is the seven days ending now.
+    pub per_week: Vec<usize>,
+}
+
+/// [`Analytics`] for each tag, alphabetically, over `weeks` weeks. Tags
+/// are matched ignoring case; a task counts once for each of its tags.
+pub fn by_tag(tasks: &[Task], now: Timestamp, weeks: usize) -> Vec<Analytics> {
+    let mut groups: Vec<(String, Vec<&Task>)> = Vec::new();
+    for task in tasks {
+        for tag in &task.tags {
+            group_into(&mut groups, tag, task);
+        }
+    }
+    groups.sort_by_key(|(name, _)| name.to_lowercase());
+    groups
+        .into_iter()
+        .map(|(name, tasks)| Analytics::of(Some(name), &tasks, now, weeks))
+        .collect()
+}
+
+/// [`Analytics`] for each project, alphabetically, then for tasks without
+/// one if there are any.
+pub fn by_project(tasks: &[Task], now: Timestamp, weeks: usize) -> Vec<Analytics> {
+    let mut groups: Vec<(String, Vec<&Task>)> = Vec::new();
+    let mut none = Vec::new();
+    for task in tasks {
+        match &task.project {
+            Some(project) => group_into(&mut groups, project, task),
+            None => none.push(task),
+        }
+    }
+    groups.sort_by_key(|(name, _)| name.to_lowercase());
+    let mut out: Vec<Analytics> = groups
+        .into_iter()
+        .map(|(name, tasks)| Analytics::of(Some(name), &tasks, now, weeks))
+        .collect();
+    if !none.is_empty() {
+        out.push(Analytics::of(None, &none, now, weeks));
+    }
+    out
+}
+
+fn group_into<'a>(groups: &mut Vec<(String, Vec<&'a Task>)>, name: &str, task: &'a Task) {
+    match groups
+        .iter_mut()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+    {
+        Some((_, tasks)) => tasks.push(task),
+        None => groups.push((name.to_string(), vec![task])),
+    }
+}
+
+impl Analytics {
+    fn of(name: Option<String>, tasks: &[&Task], now: Timestamp, weeks: usize) -> Self {
+        let mut leads: Vec<i64> = tasks
+            .iter()
+            .filter(|t| t.status == Status::Done)
+            .filter_map(|t| Some(t.completed_at?.secs() - t.created_at.secs()))
+            .map(|lead| lead.max(0))
+            .collect();
+        leads.sort_unstable();
+        let median = match leads.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(leads[n / 2]),
+            n => Some((leads[n / 2 - 1] + leads[n / 2]) / 2),
+        };
+        let oldest_open = tasks
+            .iter()
+            .filter(|t| t.status.is_open())
+            .min_by_key(|t| (t.created_at, t.id))
+            .map(|t| (t.id, (now.secs() - t.created_at.secs()).max(0)));
+        let week = 7 * SECONDS_PER_DAY;
+        let mut per_week = vec![0; weeks];
+        for completed in tasks
+            .iter()
+            .filter(|t| t.status == Status::Done)
+            .filter_map(|t| t.completed_at)
+        {
+            let back = (now.secs() - completed.secs()).div_euclid(week);
+            if (0..weeks as i64).contains(&back) {
+                per_week[weeks - 1 - back as usize] += 1;
+            }
+        }
+        Analytics {
+            name,
+            open: tasks.iter().filter(|t| t.status.is_open()).count(),
+            done: leads.len(),
+            mean: (!leads.is_empty()).then(|| leads.iter().sum::<i64>() / leads.len() as i64),
+            median,
+            oldest_open,
+            per_week,
+        }
+    }
+
+    /// Tasks completed per week on average over [`Analytics::per_week`].
+    pub fn throughput(&self) -> f64 {
+        if self.per_week.is_empty() {
+            return 0.0;
+        }
+        self.per_week.iter().sum::<usize>() as f64 / self.per_week.len() as f64
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut out = Value::object();
+        out.insert("name", self.name.clone().map_or(Value::Null, Value::from));
+        out.insert("open", self.open);
+        out.insert("done", self.done);
+        let seconds = |secs: Option<i64>| secs.map_or(Value::Null, Value::from);
+        out.insert("mean_seconds", seconds(self.mean));
+        out.insert("median_seconds", seconds(self.median));
+        let oldest = self.oldest_open.map_or(Value::Null, |(id, age)| {
+            let mut oldest = Value::object();
+            oldest.insert("id", id.to_string());
+            oldest.insert("age_seconds", age);
+            oldest
+        });
+        out.insert("oldest_open", oldest);
+        out.insert(
+            "per_week",
+            Value::Array(self.per_week.iter().map(|&n| Value::from(n)).collect()),
+        );
+        out
+    }
+}
+
+/// Label for a [`PriorityCounts`] slot.
+pub fn priority_label(index: usize) -> &'static str {
+    Priority::ALL[index].as_str()
+}
@@ -0,0 +1,390 @@
+use std::borrow::Borrow;
+#[cfg(feature = "fs")]
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+#[cfg(feature = "fs")]
+use std::io::{BufWriter, ErrorKind};
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+#[cfg(feature = "fs")]
+use crate::cache::{self, ContentHash, HashWriter};
+use crate::error::{Error, Result};
+#[cfg(feature = "fs")]
+use crate::index;
+use crate::json::{self, Value};
+use crate::manager::TasksManager;
+use crate::schedule::Template;
+#[cfg(feature = "fs")]
+use crate::split;
+use crate::stats::History;
+use crate::task::{Task, TaskId};
+#[cfg(feature = "fs")]
+use crate::timing;
+
+/// Version written to the `version` field of the data file.
+pub const FORMAT_VERSION: i64 = 1;
+
+/// Default data file, relative to the working directory.
+#[cfg(feature = "fs")]
+pub const DEFAULT_FILE: &str = "tasks.json";
+
+/// Where the tasks are kept between runs. [`JsonStore`] keeps them in files;
+/// an embedder without a filesystem, such as a web frontend, implements it
+/// over whatever it has (browser storage, a server) or uses
+/// [`MemoryStore`].
+pub trait Store {
+    /// Reads the tasks; a store with nothing in it yet yields an empty
+    /// manager.
+    fn load(&self) -> Result<TasksManager>;
+
+    fn save(&self, manager: &TasksManager) -> Result<()>;
+}
+
+/// Keeps the data file's JSON document in memory, e.g. for a frontend to
+/// hand to and take from its own storage.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    document: Mutex<Option<String>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+
+    /// A store holding `document`, in the data file's format.
+    pub fn from_json(document: impl Into<String>) -> Self {
+        MemoryStore {
+            document: Mutex::new(Some(document.into())),
+        }
+    }
+
+    /// The document last saved (or given), if any.
+    pub fn to_json(&self) -> Option<String> {
+        self.document
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl Store for MemoryStore {
+    fn load(&self) -> Result<TasksManager> {
+        match self.to_json() {
+            Some(document) => decode(&json::parse(&document)?),
+            None => Ok(TasksManager::new()),
+        }
+    }
+
+    fn save(&self, manager: &TasksManager) -> Result<()> {
+        let document = encode(manager).to_pretty();
+        *self.document.lock().unwrap_or_else(PoisonError::into_inner) = Some(document);
+        Ok(())
+    }
+}
+
+/// Persists a [`TasksManager`] as a single JSON document, or in a
+/// directory store when the path is one (see [`crate::split`]).
+#[cfg(feature = "fs")]
+#[derive(Clone, Debug)]
+pub struct JsonStore {
+    path: PathBuf,
+}
+
+#[cfg(feature = "fs")]
+impl JsonStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        JsonStore { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Takes the advisory lock that processes changing the tasks share,
+    /// held on `<path>.lock` since the data file itself is replaced on
+    /// every save. With `wait` it waits for the lock, else it gives `None`
+    /// while another process holds it. The lock is released when the
+    /// returned file is dropped.
+    pub fn lock(&self, wait: bool) -> Result<Option<File>> {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(PathBuf::from(path))?;
+        if wait {
+            file.lock()?;
+            return Ok(Some(file));
+        }
+        match file.try_lock() {
+            Ok(()) => Ok(Some(file)),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(err)) => Err(err.into()),
+        }
+    }
+
+    /// Saves `manager` in place of the tasks the store holds, first keeping
+    /// those in a single data file at `<path>.bak`: a copy of the data file,
+    /// or the tasks of a directory store. Returns that file's path, or
+    /// `None` when the store held nothing yet.
+    pub fn replace(&self, manager: &TasksManager) -> Result<Option<PathBuf>> {
+        let mut backup = self.path.clone().into_os_string();
+        backup.push(".bak");
+        let backup = PathBuf::from(backup);
+        let kept = if split::is_store(&self.path) {
+            let previous = split::load(&self.path)?;
+            fs::write(&backup, encode(&previous).to_pretty())?;
+            true
+        } else {
+            match fs::copy(&self.path, &backup) {
+                Ok(_) => true,
+                Err(err) if err.kind() == ErrorKind::NotFound => false,
+                Err(err) => return Err(err.into()),
+            }
+        };
+        self.save(manager)?;
+        Ok(kept.then_some(backup))
+    }
+
+    fn save_file(&self, manager: &TasksManager) -> Result<()> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let mut out = HashWriter::new(BufWriter::new(File::create(&tmp)?));
+        write(
+            &mut out,
+            manager.tasks(),
+            manager.history(),
+            manager.templates(),
+            manager.archived_projects(),
+            manager.aliases(),
+        )?;
+        let hash = out.hash();
+        out.into_inner()
+            .into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        timing::timed("cache write", || self.refresh_cache(manager, hash));
+        Ok(())
+    }
+
+    /// Rewrites the cache after a save, or removes it once the data file
+    /// has shrunk below the size worth caching. Errors are ignored: a stale
+    /// cache is never read, and a missing one only costs time.
+    fn refresh_cache(&self, manager: &TasksManager, hash: ContentHash) {
+        let cache = cache::path_for(&self.path);
+        let size = fs::metadata(&self.path).map_or(0, |m| m.len());
+        if size < cache::MIN_SIZE {
+            let _ = fs::remove_file(&cache);
+            return;
+        }
+        let _ = cache::write(
+            &cache,
+            hash,
+            manager.tasks().iter(),
+            manager.history(),
+            manager.templates(),
+            manager.archived_projects(),
+            manager.aliases(),
+        );
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Store for JsonStore {
+    /// Loads the data file; a missing file yields an empty manager.
+    fn load(&self) -> Result<TasksManager> {
+        let _span = timing::span("load");
+        if split::is_store(&self.path) {
+            return split::load(&self.path);
+        }
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(TasksManager::new()),
+            Err(err) => return Err(err.into()),
+        };
+        if (bytes.len() as u64) < cache::MIN_SIZE {
+            let document = timing::timed("parse", || parse(&bytes))?;
+            return timing::timed("decode", || decode(&document));
+        }
+        let cache = cache::path_for(&self.path);
+        let hash = ContentHash::of(&bytes);
+        if let Some(document) = timing::timed("cache read", || cache::read(&cache, hash)) {
+            return timing::timed("decode", || decode(&document));
+        }
+        let document = timing::timed("parse", || parse(&bytes))?;
+        let manager = timing::timed("decode", || decode(&document))?;
+        // The cache only saves time; failing to write it is not an error.
+        let _ = timing::timed("cache write", || {
+            cache::write_value(&cache, hash, &document)
+        });
+        Ok(manager)
+    }
+
+    /// Writes to a temporary sibling first so a crash never leaves a
+    /// truncated data file behind. The manager's snapshot for today is
+    /// refreshed by the caller (see [`TasksManager::record_snapshot`]).
+    fn save(&self, manager: &TasksManager) -> Result<()> {
+        let _span = timing::span("write");
+        if split::is_store(&self.path) {
+            split::save(&self.path, manager)?;
+        } else {
+            self.save_file(manager)?;
+        }
+        timing::timed("index", || index::refresh(&self.path, manager.tasks()));
+        Ok(())
+    }
+}
+
+#[cfg(feature = "fs")]
+fn parse(bytes: &[u8]) -> Result<Value> {
+    let text =
+        std::str::from_utf8(bytes).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+    Ok(json::parse(text)?)
+}
+
+pub fn encode(manager: &TasksManager) -> Value {
+    let mut root = Value::object();
+    root.insert("version", FORMAT_VERSION);
+    root.insert(
+        "tasks",
+        Value::Array(manager.tasks().iter().map(Task::to_json).collect()),
+    );
+    root.insert("history", manager.history().to_json());
+    if !manager.templates().is_empty() {
+        root.insert(
+            "templates",
+            Value::Array(manager.templates().iter().map(Template::to_json).collect()),
+        );
+    }
+    if !manager.archived_projects().is_empty() {
+        root.insert("archived_projects", manager.archived_projects().to_vec());
+    }
+    if !manager.aliases().is_empty() {
+        root.insert("aliases", aliases_to_json(manager.aliases()));
+    }
+    root
+}
+
+/// Aliases as an object from each name to its task's id.
+pub(crate) fn aliases_to_json(aliases: &[(String, TaskId)]) -> Value {
+    let mut object = Value::object();
+    for (name, id) in aliases {
+        object.insert(name, id.to_string());
+    }
+    object
+}
+
+/// Writes the same document as [`encode`], pretty-printed, one task at a
+/// time, so the whole file never has to be built in memory.
+pub fn write<W: Write, T: Borrow<Task>>(
+    out: &mut W,
+    tasks: impl IntoIterator<Item = T>,
+    history: &History,
+    templates: &[Template],
+    archived_projects: &[String],
+    aliases: &[(String, TaskId)],
+) -> io::Result<()> {
+    write!(out, "{{\n  \"version\": {FORMAT_VERSION},\n  \"tasks\": [")?;
+    let mut empty = true;
+    for task in tasks {
+        let separator = if empty { "" } else { "," };
+        write!(
+            out,
+            "{separator}\n    {}",
+            task.borrow().to_json().to_pretty_at(2)
+        )?;
+        empty = false;
+    }
+    out.write_all(if empty { b"]" } else { b"\n  ]" })?;
+    write!(
+        out,
+        ",\n  \"history\": {}",
+        history.to_json().to_pretty_at(1)
+    )?;
+    if !templates.is_empty() {
+        let templates = Value::Array(templates.iter().map(Template::to_json).collect());
+        write!(out, ",\n  \"templates\": {}", templates.to_pretty_at(1))?;
+    }
+    if !archived_projects.is_empty() {
+        let archived = Value::from(archived_projects.to_vec());
+        write!(
+            out,
+            ",\n  \"archived_projects\": {}",
+            archived.to_pretty_at(1)
+        )?;
+    }
+    if !aliases.is_empty() {
+        write!(
+            out,
+            ",\n  \"aliases\": {}",
+            aliases_to_json(aliases).to_pretty_at(1)
+        )?;
+    }
+    out.write_all(b"\n}\n")?;
+    out.flush()
+}
+
+pub fn decode(root: &Value) -> Result<TasksManager> {
+    let version = root
+        .get("version")
+        .and_then(Value::as_i64)
+        .unwrap_or(FORMAT_VERSION);
+    if version > FORMAT_VERSION {
+        return Err(Error::Invalid(format!(
+            "data file version {version} is newer than supported version {FORMAT_VERSION}"
+        )));
+    }
+    let tasks = root
+        .get("tasks")
+        .and_then(Value::as_array)
+        .ok_or_else(|| Error::Invalid("data file has no `tasks` array".into()))?;
+    let tasks = tasks
+        .iter()
+        .map(Task::from_json)
+        .collect::<Result<Vec<_>>>()?;
+    let history = History::from_json(root.get("history").unwrap_or(&Value::Null))?;
+    let templates = match root.get("templates") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(Template::from_json)
+            .collect::<Result<Vec<_>>>()?,
+        Some(_) => return Err(Error::Invalid("`templates` must be an array".into())),
+    };
+    let archived = match root.get("archived_projects") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| Error::Invalid("`archived_projects` must be a list of names".into()))?,
+        Some(_) => {
+            return Err(Error::Invalid(
+                "`archived_projects` must be an array".into(),
+            ))
+        }
+    };
+    let aliases = match root.get("aliases") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Object(fields)) => fields
+            .iter()
+            .map(|(name, id)| {
+                let id = id
+                    .as_str()
+                    .ok_or_else(|| Error::Invalid(format!("alias `{name}` must be a task id")))?;
+                Ok((name.clone(), id.parse()?))
+            })
+            .collect::<Result<Vec<_>>>()?,
+        Some(_) => return Err(Error::Invalid("`aliases` must be an object".into())),
+    };
+    Ok(TasksManager::from_tasks(tasks)
+        .with_history(history)
+        .with_templates(templates)
+        .with_archived(archived)
+        .with_aliases(aliases))
+}
@@ -0,0 +1,85 @@
+//! "Did you mean" suggestions and near-duplicate detection based on edit
+//! distance.
+
+use std::collections::HashSet;
+
+/// Levenshtein distance between `a` and `b`, ignoring case and counting a
+/// swap of two neighbouring characters (`lsit`) as one edit.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    // rows[i][j] is the distance between a[..i] and b[..j].
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// The candidates close enough to `word` to be what was meant, closest
+/// first. Allows about one edit per three characters.
+pub fn closest<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let limit = (word.chars().count() / 3).max(1);
+    let mut matches: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(word, candidate), candidate))
+        .filter(|&(distance, _)| distance <= limit)
+        .collect();
+    matches.sort_by_key(|&(distance, _)| distance);
+    let mut seen = HashSet::new();
+    matches
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .filter(|candidate| seen.insert(*candidate))
+        .collect()
+}
+
+/// `; did you mean ...?` for up to three suggestions, or nothing.
+pub fn did_you_mean(suggestions: &[String]) -> String {
+    match suggestions {
+        [] => String::new(),
+        [only] => format!("; did you mean {only}?"),
+        _ => {
+            let shown = &suggestions[..suggestions.len().min(3)];
+            let (last, rest) = shown.split_last().expect("at least two suggestions");
+            format!("; did you mean {} or {last}?", rest.join(", "))
+        }
+    }
+}
+
+/// Similarity at or above which two task names count as near-duplicates.
+pub const DUPLICATE_THRESHOLD: f64 = 0.85;
+
+/// `name` lowercased with punctuation dropped and whitespace collapsed, so
+/// "Buy milk!" and "buy  milk" compare equal.
+pub fn normalize(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// How alike two names are after [`normalize`], from 0.0 (nothing in
+/// common) to 1.0 (the same).
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize(a), normalize(b));
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(&a, &b) as f64 / longest as f64
+}
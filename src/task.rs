@@ -0,0 +1,743 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::dates::{self, Timestamp};
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+
+/// A random (version 4) UUID identifying a task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId(u128);
+
+impl TaskId {
+    pub fn generate() -> Self {
+        let raw = (u128::from(random_u64()) << 64) | u128::from(random_u64());
+        let versioned = (raw & !(0xF << 76)) | (0x4 << 76);
+        TaskId((versioned & !(0x3 << 62)) | (0x2 << 62))
+    }
+
+    /// The same id every time for a task another tool knows as `key`, so
+    /// importing it twice finds the first copy. A 128-bit FNV-1a hash,
+    /// marked as a version 8 (custom) UUID.
+    pub fn from_external(key: &str) -> Self {
+        let mut hash: u128 = 0x6c62272e07bb014262b821756295c58d;
+        for byte in key.bytes() {
+            hash ^= u128::from(byte);
+            hash = hash.wrapping_mul(0x0000000001000000000000000000013b);
+        }
+        let versioned = (hash & !(0xF << 76)) | (0x8 << 76);
+        TaskId((versioned & !(0x3 << 62)) | (0x2 << 62))
+    }
+
+    pub const fn from_u128(raw: u128) -> Self {
+        TaskId(raw)
+    }
+
+    pub const fn as_u128(self) -> u128 {
+        self.0
+    }
+
+    /// The id as 32 lowercase hex digits without dashes.
+    pub fn to_hex(self) -> String {
+        format!("{:032x}", self.0)
+    }
+}
+
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = self.to_hex();
+        write!(
+            f,
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )
+    }
+}
+
+impl FromStr for TaskId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let hex: String = s.chars().filter(|&c| c != '-').collect();
+        if hex.len() != 32 {
+            return Err(Error::Invalid(format!("invalid task id `{s}`")));
+        }
+        u128::from_str_radix(&hex, 16)
+            .map(TaskId)
+            .map_err(|_| Error::Invalid(format!("invalid task id `{s}`")))
+    }
+}
+
+/// A random number from the standard library's hasher seeding, good enough
+/// for ids and picks though not for secrets.
+pub fn random_u64() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_i128(dates::unix_nanos());
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.finish()
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 4] = [
+        Priority::Low,
+        Priority::Medium,
+        Priority::High,
+        Priority::Critical,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl FromStr for Priority {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "l" | "low" => Ok(Priority::Low),
+            "m" | "med" | "medium" => Ok(Priority::Medium),
+            "h" | "high" => Ok(Priority::High),
+            "c" | "crit" | "critical" => Ok(Priority::Critical),
+            _ => Err(Error::Invalid(format!(
+                "unknown priority `{s}` (expected low, medium, high or critical)"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    /// Finished, waiting for someone else to approve it.
+    ReviewPending,
+    Done,
+    Cancelled,
+}
+
+impl Status {
+    pub const ALL: [Status; 5] = [
+        Status::Todo,
+        Status::InProgress,
+        Status::ReviewPending,
+        Status::Done,
+        Status::Cancelled,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in-progress",
+            Status::ReviewPending => "review-pending",
+            Status::Done => "done",
+            Status::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the task still needs attention.
+    pub fn is_open(self) -> bool {
+        matches!(
+            self,
+            Status::Todo | Status::InProgress | Status::ReviewPending
+        )
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl FromStr for Status {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s
+            .trim()
+            .to_ascii_lowercase()
+            .replace(['_', ' '], "-")
+            .as_str()
+        {
+            "todo" => Ok(Status::Todo),
+            "in-progress" | "inprogress" | "doing" => Ok(Status::InProgress),
+            "review-pending" | "review" | "in-review" => Ok(Status::ReviewPending),
+            "done" => Ok(Status::Done),
+            "cancelled" | "canceled" => Ok(Status::Cancelled),
+            _ => Err(Error::Invalid(format!(
+                "unknown status `{s}` (expected todo, in-progress, review-pending, done or cancelled)"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Task {
+    pub id: TaskId,
+    pub name: String,
+    /// A short summary, at most [`DESCRIPTION_LIMIT`] characters when
+    /// entered; longer notes go in `body`.
+    pub description: String,
+    /// Free-form notes of any length, shown only in the detail view.
+    pub body: String,
+    pub priority: Priority,
+    pub status: Status,
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+    pub created_at: Timestamp,
+    /// When the task last changed; see [`TasksManager::touch_changed`](crate::TasksManager::touch_changed).
+    pub updated_at: Timestamp,
+    pub due: Option<Timestamp>,
+    pub completed_at: Option<Timestamp>,
+    /// The task this one is a subtask of.
+    pub parent: Option<TaskId>,
+    /// Tasks that must be finished before this one can start.
+    pub depends_on: Vec<TaskId>,
+    /// Tasks this one is related to without waiting on them. A link is
+    /// stored on one task and reads both ways; see
+    /// [`TasksManager::related`](crate::TasksManager::related).
+    pub related: Vec<TaskId>,
+    /// Reminder lead times, in seconds before the due date.
+    pub reminders: Vec<i64>,
+    /// Key of the linked JIRA issue, e.g. `PROJ-123`.
+    pub jira: Option<String>,
+    /// Id of the Notion page (database row) `sync notion` keeps it in step
+    /// with.
+    pub notion: Option<String>,
+    /// The Google Tasks task `sync google` keeps it in step with, as
+    /// `<list id>/<task id>`.
+    pub google: Option<String>,
+    /// Email address of the person the task concerns, for `email`.
+    pub contact: Option<String>,
+    /// Listed first whatever the sort order.
+    pub pinned: bool,
+    /// Who starred the task, for their own `starred` list. Unlike pinning
+    /// it changes nothing about where the task sorts.
+    pub starred_by: Vec<String>,
+    /// Put away by `groom`: kept, but left out of listings.
+    pub archived: bool,
+    /// Left out of exports and the published site unless asked for, and
+    /// out of listings where `display.hide_private` is set.
+    pub private: bool,
+    /// Finishing the task sends it for review instead of marking it done.
+    pub review: bool,
+    /// Who sent the task for review and who approved it, oldest first.
+    pub review_log: Vec<ReviewEvent>,
+}
+
+/// A step of the review workflow, recorded on the task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReviewEvent {
+    pub action: ReviewAction,
+    pub by: String,
+    pub at: Timestamp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReviewAction {
+    /// The task was finished and is waiting for approval.
+    Submitted,
+    Approved,
+}
+
+impl ReviewAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReviewAction::Submitted => "submitted",
+            ReviewAction::Approved => "approved",
+        }
+    }
+}
+
+impl ReviewEvent {
+    fn to_json(&self) -> Value {
+        let mut obj = Value::object();
+        obj.insert("action", self.action.as_str());
+        obj.insert("by", self.by.as_str());
+        obj.insert("at", self.at.to_rfc3339());
+        obj
+    }
+
+    fn from_json(value: &Value) -> Result<Self> {
+        let action = match required_str(value, "action")? {
+            "submitted" => ReviewAction::Submitted,
+            "approved" => ReviewAction::Approved,
+            other => {
+                return Err(Error::Invalid(format!(
+                    "unknown review action `{other}` (expected submitted or approved)"
+                )))
+            }
+        };
+        Ok(ReviewEvent {
+            action,
+            by: required_str(value, "by")?.to_string(),
+            at: Timestamp::parse_stored(required_str(value, "at")?)?,
+        })
+    }
+}
+
+impl Task {
+    pub fn new(name: impl Into<String>) -> Self {
+        let now = Timestamp::now();
+        Task {
+            id: TaskId::generate(),
+            name: name.into(),
+            description: String::new(),
+            body: String::new(),
+            priority: Priority::default(),
+            status: Status::default(),
+            project: None,
+            tags: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            due: None,
+            completed_at: None,
+            parent: None,
+            depends_on: Vec::new(),
+            related: Vec::new(),
+            reminders: Vec::new(),
+            jira: None,
+            notion: None,
+            google: None,
+            contact: None,
+            pinned: false,
+            starred_by: Vec::new(),
+            archived: false,
+            private: false,
+            review: false,
+            review_log: Vec::new(),
+        }
+    }
+
+    /// A new task carrying on from this one: `Follow up on <name>`, with
+    /// its description, project and tags, and related to it.
+    pub fn follow_up(&self) -> Task {
+        let mut task = Task::new(format!("Follow up on {}", self.name));
+        task.description = self.description.clone();
+        task.project = self.project.clone();
+        task.tags = self.tags.clone();
+        task.related.push(self.id);
+        task
+    }
+
+    pub fn is_starred_by(&self, user: &str) -> bool {
+        self.starred_by.iter().any(|name| name == user)
+    }
+
+    pub fn is_overdue(&self, now: Timestamp) -> bool {
+        self.status.is_open() && self.due.is_some_and(|due| due < now)
+    }
+
+    /// Finishes the task as `user`: marks it done, or when it needs review
+    /// sends it for review. Returns the new status.
+    pub fn finish(&mut self, user: &str) -> Status {
+        if self.review && self.status != Status::Done {
+            if self.status != Status::ReviewPending {
+                self.set_status(Status::ReviewPending);
+                self.review_log.push(ReviewEvent {
+                    action: ReviewAction::Submitted,
+                    by: user.to_string(),
+                    at: Timestamp::now(),
+                });
+            }
+        } else {
+            self.set_status(Status::Done);
+        }
+        self.status
+    }
+
+    /// Approves a task waiting for review as `user`, who must not be the
+    /// one who sent it for review, and marks it done.
+    pub fn approve(&mut self, user: &str) -> Result<()> {
+        if self.status != Status::ReviewPending {
+            return Err(Error::Conflict(format!(
+                "\"{}\" is {}, not waiting for review",
+                self.name, self.status
+            )));
+        }
+        let submitter = self
+            .review_log
+            .iter()
+            .rev()
+            .find(|e| e.action == ReviewAction::Submitted)
+            .map(|e| e.by.as_str());
+        if submitter == Some(user) {
+            return Err(Error::Conflict(format!(
+                "\"{}\" was sent for review by {user}; someone else has to approve it",
+                self.name
+            )));
+        }
+        self.set_status(Status::Done);
+        self.review_log.push(ReviewEvent {
+            action: ReviewAction::Approved,
+            by: user.to_string(),
+            at: Timestamp::now(),
+        });
+        Ok(())
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Sets text from elsewhere, such as an email or a synced service's
+    /// notes: as the description when it is one line that fits, else as the
+    /// body.
+    pub fn set_description_or_body(&mut self, text: &str) {
+        if text.contains('\n') || text.chars().count() > DESCRIPTION_LIMIT {
+            self.description.clear();
+            self.body = text.to_string();
+        } else {
+            self.description = text.to_string();
+        }
+    }
+
+    /// What [`Task::set_description_or_body`] was given: the description, or
+    /// the body if there is none.
+    pub fn description_or_body(&self) -> &str {
+        if self.description.is_empty() {
+            &self.body
+        } else {
+            &self.description
+        }
+    }
+
+    /// Moves the task to `status`, keeping `completed_at` in sync.
+    pub fn set_status(&mut self, status: Status) {
+        if status == Status::Done && self.status != Status::Done {
+            self.completed_at = Some(Timestamp::now());
+        } else if status != Status::Done {
+            self.completed_at = None;
+        }
+        self.status = status;
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut obj = Value::object();
+        obj.insert("id", self.id.to_string());
+        obj.insert("name", self.name.as_str());
+        obj.insert("description", self.description.as_str());
+        obj.insert("body", self.body.as_str());
+        obj.insert("priority", self.priority.as_str());
+        obj.insert("status", self.status.as_str());
+        obj.insert("project", self.project.clone());
+        obj.insert("tags", self.tags.clone());
+        obj.insert("created_at", self.created_at.to_rfc3339());
+        obj.insert("updated_at", self.updated_at.to_rfc3339());
+        obj.insert("due", self.due.map(Timestamp::to_rfc3339));
+        obj.insert("completed_at", self.completed_at.map(Timestamp::to_rfc3339));
+        obj.insert("parent", self.parent.map(|id| id.to_string()));
+        obj.insert(
+            "depends_on",
+            self.depends_on
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        );
+        obj.insert(
+            "related",
+            self.related
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        );
+        obj.insert("reminders", self.reminders.clone());
+        obj.insert("jira", self.jira.clone());
+        obj.insert("notion", self.notion.clone());
+        obj.insert("google", self.google.clone());
+        obj.insert("contact", self.contact.clone());
+        obj.insert("pinned", self.pinned);
+        obj.insert("starred_by", self.starred_by.clone());
+        obj.insert("archived", self.archived);
+        obj.insert("private", self.private);
+        obj.insert("review", self.review);
+        obj.insert(
+            "review_log",
+            Value::Array(self.review_log.iter().map(ReviewEvent::to_json).collect()),
+        );
+        obj
+    }
+
+    /// Moments at which the reminders fire, earliest first.
+    pub fn reminder_times(&self) -> Vec<Timestamp> {
+        let Some(due) = self.due else {
+            return Vec::new();
+        };
+        let mut times: Vec<Timestamp> = self
+            .reminders
+            .iter()
+            .map(|&lead| due.add_secs(-lead))
+            .collect();
+        times.sort();
+        times
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let id = required_str(value, "id")?.parse()?;
+        let name = required_str(value, "name")?.to_string();
+        let description = optional_str(value, "description")?
+            .unwrap_or_default()
+            .to_string();
+        let body = optional_str(value, "body")?.unwrap_or_default().to_string();
+        let priority = match optional_str(value, "priority")? {
+            Some(p) => p.parse()?,
+            None => Priority::default(),
+        };
+        let status = match optional_str(value, "status")? {
+            Some(s) => s.parse()?,
+            None => Status::default(),
+        };
+        let project = optional_str(value, "project")?.map(str::to_string);
+        let tags = string_array(value, "tags")?;
+        let depends_on = string_array(value, "depends_on")?
+            .iter()
+            .map(|id| id.parse())
+            .collect::<Result<Vec<_>>>()?;
+        let related = string_array(value, "related")?
+            .iter()
+            .map(|id| id.parse())
+            .collect::<Result<Vec<_>>>()?;
+        let created_at = optional_time(value, "created_at")?.unwrap_or_else(Timestamp::now);
+        Ok(Task {
+            id,
+            name,
+            description,
+            body,
+            priority,
+            status,
+            project,
+            tags,
+            created_at,
+            // Files from before changes were tracked count from creation.
+            updated_at: optional_time(value, "updated_at")?.unwrap_or(created_at),
+            due: optional_time(value, "due")?,
+            completed_at: optional_time(value, "completed_at")?,
+            parent: optional_str(value, "parent")?.map(str::parse).transpose()?,
+            depends_on,
+            related,
+            reminders: match value.get("reminders") {
+                None | Some(Value::Null) => Vec::new(),
+                Some(Value::Array(items)) => items
+                    .iter()
+                    .map(Value::as_i64)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| {
+                        Error::Invalid("`reminders` must be an array of seconds".into())
+                    })?,
+                Some(_) => {
+                    return Err(Error::Invalid(
+                        "`reminders` must be an array of seconds".into(),
+                    ))
+                }
+            },
+            jira: optional_str(value, "jira")?.map(str::to_string),
+            notion: optional_str(value, "notion")?.map(str::to_string),
+            google: optional_str(value, "google")?.map(str::to_string),
+            contact: optional_str(value, "contact")?.map(str::to_string),
+            pinned: optional_bool(value, "pinned")?,
+            starred_by: string_array(value, "starred_by")?,
+            archived: optional_bool(value, "archived")?,
+            private: optional_bool(value, "private")?,
+            review: optional_bool(value, "review")?,
+            review_log: match value.get("review_log") {
+                None | Some(Value::Null) => Vec::new(),
+                Some(Value::Array(events)) => events
+                    .iter()
+                    .map(ReviewEvent::from_json)
+                    .collect::<Result<_>>()?,
+                Some(_) => {
+                    return Err(Error::Invalid("field `review_log` must be an array".into()))
+                }
+            },
+        })
+    }
+
+    /// Reads `text`, a hand-edited copy of `original`'s JSON. Unlike
+    /// [`Task::from_json`] it rejects unknown fields, a changed id and a
+    /// description made longer than [`DESCRIPTION_LIMIT`], and its errors
+    /// give the line of the field at fault.
+    pub fn from_edited_json(text: &str, original: &Task) -> Result<Self> {
+        let value = json::parse(text)?;
+        let Value::Object(fields) = &value else {
+            return Err(Error::Invalid("a task must be a JSON object".into()));
+        };
+        let known = original.to_json();
+        let at = |key: &str, message: String| match text.lines().position(|line| {
+            line.trim_start()
+                .strip_prefix(&format!("\"{key}\""))
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        }) {
+            Some(index) => Error::Invalid(format!("line {}: {message}", index + 1)),
+            None => Error::Invalid(message),
+        };
+        for (key, field) in fields {
+            if known.get(key).is_none() {
+                let Value::Object(names) = &known else {
+                    unreachable!("a task serializes to an object")
+                };
+                let names: Vec<&str> = names.iter().map(|(name, _)| name.as_str()).collect();
+                return Err(at(
+                    key,
+                    format!("unknown field `{key}` (expected {})", names.join(", ")),
+                ));
+            }
+            // Checking each field against the original on its own pins an
+            // error on the field that caused it.
+            let mut probe = known.clone();
+            if let Value::Object(probe) = &mut probe {
+                for (_, value) in probe.iter_mut().filter(|(name, _)| name == key) {
+                    *value = field.clone();
+                }
+            }
+            Task::from_json(&probe).map_err(|err| at(key, err.to_string()))?;
+        }
+        let mut task = Task::from_json(&value)?;
+        if task.id != original.id {
+            return Err(at("id", "the id cannot be changed".into()));
+        }
+        if task.description != original.description {
+            task.description = parse_description(&task.description)
+                .map_err(|err| at("description", err.to_string()))?;
+        }
+        Ok(task)
+    }
+}
+
+/// Splits a comma- or space-separated tag list, dropping leading `#`s.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split([',', ' ']) {
+        let tag = tag.trim().trim_start_matches('#');
+        if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Validates a JIRA issue key (`PROJ-123`), returning it in upper case.
+pub fn parse_issue_key(input: &str) -> Result<String> {
+    let key = input.trim().to_ascii_uppercase();
+    let valid = key.split_once('-').is_some_and(|(project, number)| {
+        project.starts_with(|c: char| c.is_ascii_alphabetic())
+            && project
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !number.is_empty()
+            && number.chars().all(|c| c.is_ascii_digit())
+    });
+    if valid {
+        Ok(key)
+    } else {
+        Err(Error::Invalid(format!(
+            "`{input}` is not a JIRA issue key (expected e.g. PROJ-123)"
+        )))
+    }
+}
+
+/// Longest description accepted from the user, in characters.
+pub const DESCRIPTION_LIMIT: usize = 200;
+
+/// Checks that `input` fits in a description, returning it trimmed.
+pub fn parse_description(input: &str) -> Result<String> {
+    let description = input.trim();
+    let length = description.chars().count();
+    if length > DESCRIPTION_LIMIT {
+        return Err(Error::Invalid(format!(
+            "the description is limited to {DESCRIPTION_LIMIT} characters, this one has {length}; put longer notes in the body with `edit <id> --body`"
+        )));
+    }
+    Ok(description.to_string())
+}
+
+/// Checks that `input` looks like an email address (`name@example.com`),
+/// returning it trimmed.
+pub fn parse_contact(input: &str) -> Result<String> {
+    let address = input.trim();
+    let valid = address.split_once('@').is_some_and(|(local, domain)| {
+        !local.is_empty()
+            && !domain.contains('@')
+            && domain.contains('.')
+            && !domain.starts_with('.')
+            && !domain.ends_with('.')
+            && !address.contains(|c: char| c.is_whitespace() || "<>,;".contains(c))
+    });
+    if valid {
+        Ok(address.to_string())
+    } else {
+        Err(Error::Invalid(format!(
+            "`{input}` is not an email address (expected e.g. ana@example.com)"
+        )))
+    }
+}
+
+pub(crate) fn required_str<'a>(value: &'a Value, key: &str) -> Result<&'a str> {
+    optional_str(value, key)?.ok_or_else(|| Error::Invalid(format!("missing field `{key}`")))
+}
+
+pub(crate) fn optional_str<'a>(value: &'a Value, key: &str) -> Result<Option<&'a str>> {
+    match value.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(_) => Err(Error::Invalid(format!("field `{key}` must be a string"))),
+    }
+}
+
+pub(crate) fn optional_bool(value: &Value, key: &str) -> Result<bool> {
+    match value.get(key) {
+        None | Some(Value::Null) => Ok(false),
+        Some(Value::Bool(b)) => Ok(*b),
+        Some(_) => Err(Error::Invalid(format!(
+            "field `{key}` must be true or false"
+        ))),
+    }
+}
+
+pub(crate) fn string_array(value: &Value, key: &str) -> Result<Vec<String>> {
+    let invalid = || Error::Invalid(format!("`{key}` must be an array of strings"));
+    match value.get(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid),
+        Some(_) => Err(invalid()),
+    }
+}
+
+pub(crate) fn optional_time(value: &Value, key: &str) -> Result<Option<Timestamp>> {
+    optional_str(value, key)?
+        .map(Timestamp::parse_stored)
+        .transpose()
+}
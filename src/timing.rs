@@ -0,0 +1,102 @@
+//! Wall-clock spans around the phases of a command, for `--timings`.
+//!
+//! Code marks a phase with [`span`], which measures until the returned guard
+//! is dropped. Nothing is recorded until [`enable`] is called, so the spans
+//! cost an atomic load otherwise. Spans opened while another is open nest
+//! under it, and [`report`] lists them in the order they started.
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SPANS: Mutex<Vec<Span>> = Mutex::new(Vec::new());
+
+thread_local! {
+    /// Spans open on this thread, so a background save does not nest under
+    /// the command running meanwhile.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A phase that has been measured.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub name: &'static str,
+    /// How many spans were open around it.
+    pub depth: usize,
+    /// `None` while it is still open.
+    pub elapsed: Option<Duration>,
+}
+
+/// Measures until dropped.
+#[must_use = "the span ends when the guard is dropped"]
+pub struct Guard {
+    /// The span's place in the list and when it started, when recording.
+    started: Option<(usize, Instant)>,
+}
+
+/// Starts recording spans.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Opens a span called `name`.
+pub fn span(name: &'static str) -> Guard {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return Guard { started: None };
+    }
+    let mut spans = SPANS.lock().unwrap_or_else(PoisonError::into_inner);
+    spans.push(Span {
+        name,
+        depth: DEPTH.replace(DEPTH.get() + 1),
+        elapsed: None,
+    });
+    Guard {
+        started: Some((spans.len() - 1, Instant::now())),
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let Some((index, start)) = self.started else {
+            return;
+        };
+        let elapsed = start.elapsed();
+        DEPTH.set(DEPTH.get() - 1);
+        let mut spans = SPANS.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(span) = spans.get_mut(index) {
+            span.elapsed = Some(elapsed);
+        }
+    }
+}
+
+/// Runs `f` inside a span called `name`.
+pub fn timed<T>(name: &'static str, f: impl FnOnce() -> T) -> T {
+    let _span = span(name);
+    f()
+}
+
+/// Removes and returns the spans recorded so far.
+pub fn take() -> Vec<Span> {
+    std::mem::take(&mut *SPANS.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// One line per span, indented by nesting, with milliseconds, e.g.
+/// `  load        12.34 ms`.
+pub fn report(spans: &[Span]) -> String {
+    let width = spans
+        .iter()
+        .map(|s| 2 * s.depth + s.name.len())
+        .fold(0, usize::max);
+    let mut out = String::new();
+    for span in spans {
+        let label = format!("{:indent$}{}", "", span.name, indent = 2 * span.depth);
+        let time = match span.elapsed {
+            Some(elapsed) => format!("{:>9.2} ms", elapsed.as_secs_f64() * 1000.0),
+            None => format!("{:>12}", "unfinished"),
+        };
+        out.push_str(&format!("  {label:<width$}  {time}\n"));
+    }
+    out
+}
@@ -0,0 +1,377 @@
+//! A TOML subset for configuration files, read into [`json::Value`]s.
+//!
+//! Supported: `key = value` pairs with bare, quoted or dotted keys, `[table]`
+//! and `[[array.of.tables]]` headers, basic and literal strings (including
+//! the `"""` and `'''` multi-line forms), integers, floats, booleans, arrays
+//! (which may span lines) and inline tables. Dates are kept as strings.
+//!
+//! [`json::Value`]: crate::json::Value
+
+use crate::error::{Error, Result};
+use crate::json::Value;
+
+pub fn parse(input: &str) -> Result<Value> {
+    let mut parser = Parser {
+        chars: input.trim_start_matches('\u{feff}').chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let mut root = Value::object();
+    // Path of the table that `key = value` lines currently go into.
+    let mut current: Vec<String> = Vec::new();
+    loop {
+        parser.skip_blank_lines();
+        let Some(c) = parser.peek() else {
+            break;
+        };
+        let line = parser.line;
+        if c == '[' {
+            let array = parser.starts_with("[[");
+            parser.pos += if array { 2 } else { 1 };
+            let path = parser.key_path()?;
+            parser.expect(']')?;
+            if array {
+                parser.expect(']')?;
+            }
+            parser.end_of_line()?;
+            if array {
+                let (last, parents) = path.split_last().expect("non-empty key path");
+                let parent = table_at(&mut root, parents, line)?;
+                match parent_entry(parent, last) {
+                    Some(Value::Array(items)) => items.push(Value::object()),
+                    Some(_) => return Err(error(line, &format!("`{last}` is not an array"))),
+                    None => parent.insert(last, Value::Array(vec![Value::object()])),
+                }
+            } else {
+                table_at(&mut root, &path, line)?;
+            }
+            current = path;
+            continue;
+        }
+        let path = parser.key_path()?;
+        parser.skip_spaces();
+        parser.expect('=')?;
+        let value = parser.value()?;
+        parser.end_of_line()?;
+        let (last, parents) = path.split_last().expect("non-empty key path");
+        let mut full = current.clone();
+        full.extend_from_slice(parents);
+        let table = table_at(&mut root, &full, line)?;
+        if table.get(last).is_some() {
+            return Err(error(line, &format!("`{last}` is defined twice")));
+        }
+        table.insert(last, value);
+    }
+    Ok(root)
+}
+
+fn error(line: usize, message: &str) -> Error {
+    Error::Invalid(format!("TOML line {line}: {message}"))
+}
+
+fn parent_entry<'a>(table: &'a mut Value, key: &str) -> Option<&'a mut Value> {
+    match table {
+        Value::Object(entries) => entries.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v),
+        _ => None,
+    }
+}
+
+/// The table at `path`, creating missing ones. A path through an array of
+/// tables goes into its last element.
+fn table_at<'a>(root: &'a mut Value, path: &[String], line: usize) -> Result<&'a mut Value> {
+    let mut table = root;
+    for key in path {
+        if parent_entry(table, key).is_none() {
+            table.insert(key, Value::object());
+        }
+        let next = parent_entry(table, key).expect("entry was just inserted");
+        if let Value::Array(items) = next {
+            table = match items.last_mut() {
+                Some(last @ Value::Object(_)) => last,
+                _ => return Err(error(line, &format!("`{key}` is not a table"))),
+            };
+        } else if matches!(next, Value::Object(_)) {
+            table = next;
+        } else {
+            return Err(error(line, &format!("`{key}` is not a table")));
+        }
+    }
+    Ok(table)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while self.peek().is_some_and(|c| c != '\n') {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skips whitespace, newlines and comments, as allowed inside arrays and
+    /// between statements.
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') => {
+                    self.bump();
+                }
+                Some('\r') if self.chars.get(self.pos + 1) == Some(&'\n') => {
+                    self.pos += 1;
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<()> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.peek() == Some('\r') {
+            self.pos += 1;
+        }
+        match self.bump() {
+            None | Some('\n') => Ok(()),
+            Some(c) => Err(error(self.line, &format!("unexpected `{c}`"))),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_spaces();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(error(
+                self.line,
+                &format!("expected `{expected}`, found `{c}`"),
+            )),
+            None => Err(error(self.line, &format!("expected `{expected}`"))),
+        }
+    }
+
+    fn key_path(&mut self) -> Result<Vec<String>> {
+        let mut path = Vec::new();
+        loop {
+            self.skip_spaces();
+            let key = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return Err(error(self.line, "expected a key"));
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            path.push(key);
+            self.skip_spaces();
+            if self.peek() == Some('.') {
+                self.pos += 1;
+            } else {
+                return Ok(path);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_blank_lines();
+                    if self.peek() == Some(']') {
+                        self.pos += 1;
+                        return Ok(Value::Array(items));
+                    }
+                    items.push(self.value()?);
+                    self.skip_blank_lines();
+                    match self.peek() {
+                        Some(',') => self.pos += 1,
+                        Some(']') => {}
+                        _ => return Err(error(self.line, "expected `,` or `]` in array")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut table = Value::object();
+                self.skip_spaces();
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                    return Ok(table);
+                }
+                loop {
+                    let path = self.key_path()?;
+                    self.expect('=')?;
+                    let value = self.value()?;
+                    let (last, parents) = path.split_last().expect("non-empty key path");
+                    let line = self.line;
+                    let inner = table_at(&mut table, parents, line)?;
+                    if inner.get(last).is_some() {
+                        return Err(error(line, &format!("`{last}` is defined twice")));
+                    }
+                    inner.insert(last, value);
+                    self.skip_spaces();
+                    match self.bump() {
+                        Some(',') => {}
+                        Some('}') => return Ok(table),
+                        _ => return Err(error(self.line, "expected `,` or `}` in inline table")),
+                    }
+                }
+            }
+            _ => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| !matches!(c, ',' | ']' | '}' | '#' | '\n' | '\r'))
+                {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                scalar(text.trim(), self.line)
+            }
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String> {
+        let multi = self.starts_with("\"\"\"");
+        self.pos += if multi { 3 } else { 1 };
+        if multi && self.peek() == Some('\n') {
+            self.bump();
+        }
+        let mut out = String::new();
+        loop {
+            if multi && self.starts_with("\"\"\"") {
+                self.pos += 3;
+                return Ok(out);
+            }
+            match self.bump() {
+                None => return Err(error(self.line, "unterminated string")),
+                Some('"') if !multi => return Ok(out),
+                Some('\n') if !multi => return Err(error(self.line - 1, "unterminated string")),
+                Some('\\') => match self.bump() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('u') => out.push(self.unicode_escape(4)?),
+                    Some('U') => out.push(self.unicode_escape(8)?),
+                    Some('\n') if multi => {
+                        while self.peek().is_some_and(char::is_whitespace) {
+                            self.bump();
+                        }
+                    }
+                    Some(c) => return Err(error(self.line, &format!("unknown escape `\\{c}`"))),
+                    None => return Err(error(self.line, "unterminated string")),
+                },
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self, digits: usize) -> Result<char> {
+        let hex: String = (0..digits).filter_map(|_| self.bump()).collect();
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| error(self.line, &format!("invalid unicode escape `{hex}`")))
+    }
+
+    fn literal_string(&mut self) -> Result<String> {
+        let multi = self.starts_with("'''");
+        self.pos += if multi { 3 } else { 1 };
+        if multi && self.peek() == Some('\n') {
+            self.bump();
+        }
+        let mut out = String::new();
+        loop {
+            if multi && self.starts_with("'''") {
+                self.pos += 3;
+                return Ok(out);
+            }
+            match self.bump() {
+                None => return Err(error(self.line, "unterminated string")),
+                Some('\'') if !multi => return Ok(out),
+                Some('\n') if !multi => return Err(error(self.line - 1, "unterminated string")),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+}
+
+fn scalar(text: &str, line: usize) -> Result<Value> {
+    match text {
+        "" => Err(error(line, "missing value")),
+        "true" => Ok(Value::Bool(true)),
+        "false" => Ok(Value::Bool(false)),
+        "inf" | "+inf" | "-inf" | "nan" | "+nan" | "-nan" => {
+            Err(error(line, "infinite and NaN values are not supported"))
+        }
+        _ => {
+            let digits = text.replace('_', "");
+            if let Ok(n) = digits.parse::<i64>() {
+                return Ok(Value::from(n));
+            }
+            if digits.contains(['.', 'e', 'E']) {
+                if let Ok(n) = digits.parse::<f64>() {
+                    return Ok(Value::Number(n));
+                }
+            }
+            // Dates and times stay strings for the caller to interpret.
+            if text.starts_with(|c: char| c.is_ascii_digit()) && text.contains(['-', ':']) {
+                return Ok(Value::String(text.to_string()));
+            }
+            Err(error(
+                line,
+                &format!("invalid value `{text}` (strings must be quoted)"),
+            ))
+        }
+    }
+}
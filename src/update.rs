@@ -0,0 +1,295 @@
+//! Single-field modifications written as `field=value`, `+tag` or `-tag`.
+
+use crate::dates::{self, Timestamp};
+use crate::duration;
+use crate::error::{Error, Result};
+use crate::filter;
+use crate::manager::TasksManager;
+use crate::task::{
+    parse_contact, parse_description, parse_tags, Priority, Status, Task, TaskId, DESCRIPTION_LIMIT,
+};
+
+/// The fields `field=value` can set and the values each takes. Besides
+/// these, `+tag` adds a tag and `-tag` removes one. `{limit}` stands for
+/// [`DESCRIPTION_LIMIT`]; [`fields`] fills it in for `help fields`.
+pub const FIELDS: &[(&str, &str)] = &[
+    ("name", "any text but empty"),
+    (
+        "description",
+        "a summary of up to {limit} characters (or desc)",
+    ),
+    (
+        "body",
+        "notes of any length; edit <id> --body opens them in $EDITOR",
+    ),
+    ("priority", "low, medium, high or critical (or pri)"),
+    (
+        "status",
+        "todo, in-progress, review-pending, done or cancelled",
+    ),
+    ("project", "a project name; empty or none clears it"),
+    (
+        "due",
+        "a date and time such as \"next tue 10:00\"; empty or none clears it",
+    ),
+    ("tags", "comma-separated tags, replacing the existing ones"),
+    ("parent", "a task id; empty or none clears it"),
+    (
+        "depends",
+        "comma-separated task ids; depends+= and depends-= add or remove one",
+    ),
+    (
+        "related",
+        "comma-separated task ids; related+= and related-= add or remove one",
+    ),
+    (
+        "remind",
+        "lead times before the due date such as 1h,15m; remind+= and remind-= add or remove one",
+    ),
+    ("review", "yes or no: whether finishing needs approval"),
+    ("archived", "yes or no"),
+    ("private", "yes or no"),
+    ("contact", "an email address; empty or none clears it"),
+];
+
+/// [`FIELDS`] as shown to the user, with the limits filled in.
+pub fn fields() -> Vec<(&'static str, String)> {
+    FIELDS
+        .iter()
+        .map(|&(name, text)| {
+            (
+                name,
+                text.replace("{limit}", &DESCRIPTION_LIMIT.to_string()),
+            )
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Update {
+    Name(String),
+    Description(String),
+    Body(String),
+    Priority(Priority),
+    Status(Status),
+    Project(Option<String>),
+    Due(Option<Timestamp>),
+    Tags(Vec<String>),
+    AddTag(String),
+    RemoveTag(String),
+    Parent(Option<TaskId>),
+    Depends(Vec<TaskId>),
+    AddDependency(TaskId),
+    RemoveDependency(TaskId),
+    Related(Vec<TaskId>),
+    AddRelated(TaskId),
+    RemoveRelated(TaskId),
+    Reminders(Vec<i64>),
+    AddReminder(i64),
+    RemoveReminder(i64),
+    Review(bool),
+    Archived(bool),
+    Private(bool),
+    Contact(Option<String>),
+}
+
+impl Update {
+    /// Parses one assignment. Empty values (and `none`) clear optional fields.
+    /// Task references (`parent=`, `depends=`, `related=` and their `+=`/`-=`
+    /// forms) are resolved against `manager`.
+    pub fn parse(arg: &str, now: Timestamp, manager: &TasksManager) -> Result<Self> {
+        if let Some(tag) = arg.strip_prefix('+') {
+            return non_empty_tag(tag).map(Update::AddTag);
+        }
+        if let Some(tag) = arg.strip_prefix('-') {
+            return non_empty_tag(tag).map(Update::RemoveTag);
+        }
+        let (field, value) = arg.split_once('=').ok_or_else(|| {
+            Error::Invalid(format!(
+                "expected `field=value`, `+tag` or `-tag`, found `{arg}`"
+            ))
+        })?;
+        let value = value.trim();
+        let cleared = value.is_empty() || value.eq_ignore_ascii_case("none");
+        Ok(match field.trim().to_ascii_lowercase().as_str() {
+            "name" if value.is_empty() => {
+                return Err(Error::Invalid("the name cannot be empty".into()))
+            }
+            "name" => Update::Name(value.to_string()),
+            "description" | "desc" => Update::Description(parse_description(value)?),
+            "body" => Update::Body(value.to_string()),
+            "priority" | "pri" => Update::Priority(value.parse()?),
+            "status" => Update::Status(value.parse()?),
+            "project" if cleared => Update::Project(None),
+            "project" => Update::Project(Some(value.to_string())),
+            "due" if cleared => Update::Due(None),
+            "due" => Update::Due(Some(dates::parse_when(value, now)?)),
+            "tags" => Update::Tags(parse_tags(value)),
+            "parent" if cleared => Update::Parent(None),
+            "parent" => Update::Parent(Some(manager.resolve(value)?)),
+            "depends" => Update::Depends(resolve_list(manager, value)?),
+            "depends+" => Update::AddDependency(manager.resolve(value)?),
+            "depends-" => Update::RemoveDependency(manager.resolve(value)?),
+            "related" => Update::Related(resolve_list(manager, value)?),
+            "related+" => Update::AddRelated(manager.resolve(value)?),
+            "related-" => Update::RemoveRelated(manager.resolve(value)?),
+            "remind" => Update::Reminders(
+                value
+                    .split([',', ' '])
+                    .filter(|lead| !lead.is_empty() && !lead.eq_ignore_ascii_case("none"))
+                    .map(duration::parse)
+                    .collect::<Result<_>>()?,
+            ),
+            "remind+" => Update::AddReminder(duration::parse(value)?),
+            "remind-" => Update::RemoveReminder(duration::parse(value)?),
+            "review" => Update::Review(yes_no("review", value)?),
+            "archived" => Update::Archived(yes_no("archived", value)?),
+            "private" => Update::Private(yes_no("private", value)?),
+            "contact" if cleared => Update::Contact(None),
+            "contact" => Update::Contact(Some(parse_contact(value)?)),
+            other => {
+                return Err(Error::Invalid(format!(
+                    "unknown field `{other}` (expected {})",
+                    filter::either(FIELDS)
+                )))
+            }
+        })
+    }
+
+    fn apply(&self, task: &mut Task) {
+        match self {
+            Update::Name(name) => task.name.clone_from(name),
+            Update::Description(text) => task.description.clone_from(text),
+            Update::Body(text) => task.body.clone_from(text),
+            Update::Priority(priority) => task.priority = *priority,
+            Update::Status(status) => task.set_status(*status),
+            Update::Project(project) => task.project.clone_from(project),
+            Update::Due(due) => task.due = *due,
+            Update::Tags(tags) => task.tags.clone_from(tags),
+            Update::AddTag(tag) => {
+                if !task.has_tag(tag) {
+                    task.tags.push(tag.clone());
+                }
+            }
+            Update::RemoveTag(tag) => task.tags.retain(|t| !t.eq_ignore_ascii_case(tag)),
+            Update::Parent(parent) => task.parent = *parent,
+            Update::Depends(ids) => task.depends_on.clone_from(ids),
+            Update::AddDependency(id) => {
+                if !task.depends_on.contains(id) {
+                    task.depends_on.push(*id);
+                }
+            }
+            Update::RemoveDependency(id) => task.depends_on.retain(|dep| dep != id),
+            Update::Related(ids) => task.related.clone_from(ids),
+            Update::AddRelated(id) => {
+                if !task.related.contains(id) {
+                    task.related.push(*id);
+                }
+            }
+            Update::RemoveRelated(id) => task.related.retain(|other| other != id),
+            Update::Reminders(leads) => task.reminders.clone_from(leads),
+            Update::AddReminder(lead) => {
+                if !task.reminders.contains(lead) {
+                    task.reminders.push(*lead);
+                }
+            }
+            Update::RemoveReminder(lead) => task.reminders.retain(|r| r != lead),
+            Update::Review(review) => task.review = *review,
+            Update::Archived(archived) => task.archived = *archived,
+            Update::Private(private) => task.private = *private,
+            Update::Contact(contact) => task.contact.clone_from(contact),
+        }
+    }
+}
+
+fn resolve_list(manager: &TasksManager, value: &str) -> Result<Vec<TaskId>> {
+    value
+        .split([',', ' '])
+        .filter(|id| !id.is_empty() && !id.eq_ignore_ascii_case("none"))
+        .map(|id| manager.resolve(id))
+        .collect()
+}
+
+fn yes_no(field: &str, value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "yes" | "true" | "on" => Ok(true),
+        "no" | "false" | "off" | "none" | "" => Ok(false),
+        _ => Err(Error::Invalid(format!(
+            "expected `{field}=yes` or `{field}=no`, found `{value}`"
+        ))),
+    }
+}
+
+fn non_empty_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim().trim_start_matches('#');
+    if tag.is_empty() {
+        return Err(Error::Invalid("tag name cannot be empty".into()));
+    }
+    Ok(tag.to_string())
+}
+
+/// One field whose value differed before and after an update.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// Applies every update to `task` and reports the fields that really changed.
+/// Parse all updates first so a bad argument leaves the task untouched.
+pub fn apply(task: &mut Task, updates: &[Update]) -> Vec<FieldChange> {
+    let before = snapshot(task);
+    for update in updates {
+        update.apply(task);
+    }
+    before
+        .into_iter()
+        .zip(snapshot(task))
+        .filter(|(old, new)| old.1 != new.1)
+        .map(|((field, old), (_, new))| FieldChange { field, old, new })
+        .collect()
+}
+
+fn snapshot(task: &Task) -> [(&'static str, String); 16] {
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "none".to_string());
+    let list = |items: Vec<String>| optional(Some(items.join(", ")).filter(|s| !s.is_empty()));
+    [
+        ("name", task.name.clone()),
+        ("description", task.description.clone()),
+        ("body", task.body.clone()),
+        ("priority", task.priority.to_string()),
+        ("status", task.status.to_string()),
+        ("project", optional(task.project.clone())),
+        ("due", optional(task.due.map(|d| d.to_string()))),
+        ("tags", list(task.tags.clone())),
+        ("parent", optional(task.parent.map(|id| id.to_string()))),
+        (
+            "depends",
+            list(task.depends_on.iter().map(ToString::to_string).collect()),
+        ),
+        (
+            "related",
+            list(task.related.iter().map(ToString::to_string).collect()),
+        ),
+        (
+            "remind",
+            list(
+                task.reminders
+                    .iter()
+                    .map(|&lead| duration::format(lead))
+                    .collect(),
+            ),
+        ),
+        ("review", if task.review { "yes" } else { "no" }.to_string()),
+        (
+            "archived",
+            if task.archived { "yes" } else { "no" }.to_string(),
+        ),
+        (
+            "private",
+            if task.private { "yes" } else { "no" }.to_string(),
+        ),
+        ("contact", optional(task.contact.clone())),
+    ]
+}
@@ -0,0 +1,163 @@
+//! Which status changes are allowed, from the `[workflow]` section of the
+//! config, plus the review rule that always holds: a task that needs review
+//! is only done once someone other than who finished it approves it.
+//!
+//! Transitions are written `from -> to`, with `*` for any status, e.g.
+//! `todo -> done` or `cancelled -> *`. `forbid` lists the ones not allowed
+//! at all and `require_note` the ones that need a note saying why, which is
+//! kept in the task's description.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::dates::Timestamp;
+use crate::error::{Error, Result};
+use crate::task::{ReviewAction, Status, Task};
+
+/// A pattern of status changes, e.g. `cancelled -> *`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    /// `None` matches any status.
+    pub from: Option<Status>,
+    pub to: Option<Status>,
+}
+
+impl Transition {
+    pub fn matches(self, from: Status, to: Status) -> bool {
+        self.from.is_none_or(|s| s == from) && self.to.is_none_or(|s| s == to)
+    }
+}
+
+impl FromStr for Transition {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (from, to) = s.split_once("->").ok_or_else(|| {
+            Error::Invalid(format!(
+                "expected a transition like `todo -> done`, found `{s}`"
+            ))
+        })?;
+        let status = |text: &str| match text.trim() {
+            "*" => Ok(None),
+            text => text.parse().map(Some),
+        };
+        Ok(Transition {
+            from: status(from)?,
+            to: status(to)?,
+        })
+    }
+}
+
+impl fmt::Display for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = |s: Option<Status>| s.map_or("*", Status::as_str);
+        write!(f, "{} -> {}", status(self.from), status(self.to))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Workflow {
+    pub forbid: Vec<Transition>,
+    pub require_note: Vec<Transition>,
+}
+
+/// A status change the workflow does not allow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rejected {
+    /// The task's name.
+    pub task: String,
+    pub from: Status,
+    pub to: Status,
+    pub reason: Reason,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reason {
+    /// Listed under `forbid`.
+    Forbidden(Transition),
+    /// Listed under `require_note` and no note was given.
+    NeedsNote(Transition),
+    /// The task needs review and was not approved.
+    NeedsReview,
+}
+
+impl Reason {
+    /// A stable name for scripts, as in [`Error::kind`].
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Reason::Forbidden(_) => "forbidden",
+            Reason::NeedsNote(_) => "needs_note",
+            Reason::NeedsReview => "needs_review",
+        }
+    }
+}
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, from, to) = (&self.task, self.from, self.to);
+        match &self.reason {
+            Reason::Forbidden(rule) => write!(
+                f,
+                "\"{name}\" cannot go from {from} to {to}: the workflow forbids `{rule}`"
+            ),
+            Reason::NeedsNote(_) => write!(
+                f,
+                "\"{name}\" going from {from} to {to} needs a note; pass --note \"why\""
+            ),
+            Reason::NeedsReview => write!(
+                f,
+                "\"{name}\" needs review; finish it with `done` and have someone else `approve` it"
+            ),
+        }
+    }
+}
+
+impl Workflow {
+    /// Whether `before` may become `after` with `note` given. Changes that
+    /// keep the status are always allowed.
+    pub fn check(
+        &self,
+        before: &Task,
+        after: &Task,
+        note: Option<&str>,
+    ) -> std::result::Result<(), Rejected> {
+        let (from, to) = (before.status, after.status);
+        if from == to {
+            return Ok(());
+        }
+        let rejected = |reason| Rejected {
+            task: after.name.clone(),
+            from,
+            to,
+            reason,
+        };
+        // Approving is the one way from review-pending to done.
+        let approved = from == Status::ReviewPending
+            && after.review_log.last().map(|e| e.action) == Some(ReviewAction::Approved);
+        if to == Status::Done && after.review && !approved {
+            return Err(rejected(Reason::NeedsReview));
+        }
+        if let Some(&rule) = self.forbid.iter().find(|t| t.matches(from, to)) {
+            return Err(rejected(Reason::Forbidden(rule)));
+        }
+        let blank = note.is_none_or(|note| note.trim().is_empty());
+        match self.require_note.iter().find(|t| t.matches(from, to)) {
+            Some(&rule) if blank => Err(rejected(Reason::NeedsNote(rule))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Keeps `note` on the task as a dated line at the end of its description,
+/// e.g. `2024-06-01 cancelled -> todo: the client asked again`.
+pub fn record_note(task: &mut Task, from: Status, note: &str, now: Timestamp) {
+    if !task.description.is_empty() {
+        task.description.push('\n');
+    }
+    task.description.push_str(&format!(
+        "{} {from} -> {}: {}",
+        now.to_local_date(),
+        task.status,
+        note.trim()
+    ));
+}
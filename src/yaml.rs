@@ -0,0 +1,394 @@
+//! A YAML subset for hand-written files, read into [`json::Value`]s.
+//!
+//! Supported: block mappings and sequences nested by indentation, `- key:`
+//! items, flow collections (`[a, b]`, `{k: v}`), single- and double-quoted
+//! strings, `|` and `>` block scalars and `#` comments. Anchors, tags and
+//! multiple documents are not. Plain scalars become numbers, booleans or
+//! null where YAML would read them so; everything else is a string.
+//!
+//! [`json::Value`]: crate::json::Value
+
+use crate::error::{Error, Result};
+use crate::json::Value;
+
+struct Line {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+struct Parser {
+    lines: Vec<Line>,
+    pos: usize,
+}
+
+pub fn parse(input: &str) -> Result<Value> {
+    let mut lines = Vec::new();
+    for (index, raw) in input.trim_start_matches('\u{feff}').lines().enumerate() {
+        if raw[..raw.len() - raw.trim_start().len()].contains('\t') {
+            return Err(error(index + 1, "tabs cannot be used for indentation"));
+        }
+        let text = strip_comment(raw).trim_end();
+        let content = text.trim_start();
+        if content.is_empty() || content == "---" {
+            continue;
+        }
+        if content == "..." {
+            break;
+        }
+        lines.push(Line {
+            number: index + 1,
+            indent: text.len() - content.len(),
+            text: content.to_string(),
+        });
+    }
+    let mut parser = Parser { lines, pos: 0 };
+    let Some(first) = parser.lines.first() else {
+        return Ok(Value::Null);
+    };
+    let value = parser.block(first.indent)?;
+    if let Some(line) = parser.lines.get(parser.pos) {
+        return Err(error(line.number, "unexpected indentation"));
+    }
+    Ok(value)
+}
+
+fn error(line: usize, message: &str) -> Error {
+    Error::Invalid(format!("YAML line {line}: {message}"))
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Line> {
+        self.lines.get(self.pos)
+    }
+
+    fn block(&mut self, indent: usize) -> Result<Value> {
+        match self.peek() {
+            Some(line) if is_item(&line.text) => self.sequence(indent),
+            Some(_) => self.mapping(indent),
+            None => Ok(Value::Null),
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value> {
+        let mut items = Vec::new();
+        while let Some(line) = self.peek() {
+            if line.indent < indent || !is_item(&line.text) {
+                break;
+            }
+            if line.indent > indent {
+                return Err(error(line.number, "unexpected indentation"));
+            }
+            let number = line.number;
+            let rest = line.text[1..].trim_start().to_string();
+            let offset = line.text.len() - rest.len();
+            if rest.is_empty() {
+                self.pos += 1;
+                items.push(self.nested(indent)?);
+            } else if is_item(&rest) || split_key(&rest).is_some() {
+                // `- key: value` or `- - x` opens a collection whose first line
+                // is the rest of this one.
+                let line = &mut self.lines[self.pos];
+                line.indent += offset;
+                line.text = rest;
+                let inner = line.indent;
+                items.push(self.block(inner)?);
+            } else {
+                self.pos += 1;
+                items.push(self.inline(&rest, indent, number)?);
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value> {
+        let mut map = Value::object();
+        while let Some(line) = self.peek() {
+            if line.indent < indent {
+                break;
+            }
+            let number = line.number;
+            if line.indent > indent {
+                return Err(error(number, "unexpected indentation"));
+            }
+            let Some((key, rest)) = split_key(&line.text) else {
+                return Err(error(number, "expected `key: value`"));
+            };
+            let (key, rest) = (unquote(key, number)?, rest.to_string());
+            self.pos += 1;
+            if map.get(&key).is_some() {
+                return Err(error(number, &format!("duplicate key `{key}`")));
+            }
+            let value = if rest.is_empty() {
+                match self.peek() {
+                    // A sequence may sit at the same indentation as its key.
+                    Some(next) if next.indent == indent && is_item(&next.text) => {
+                        self.sequence(indent)?
+                    }
+                    _ => self.nested(indent)?,
+                }
+            } else {
+                self.inline(&rest, indent, number)?
+            };
+            map.insert(&key, value);
+        }
+        Ok(map)
+    }
+
+    /// The block indented under a line ending in `:` or `-`, or null.
+    fn nested(&mut self, indent: usize) -> Result<Value> {
+        match self.peek() {
+            Some(next) if next.indent > indent => {
+                let inner = next.indent;
+                self.block(inner)
+            }
+            _ => Ok(Value::Null),
+        }
+    }
+
+    fn inline(&mut self, text: &str, indent: usize, number: usize) -> Result<Value> {
+        match text {
+            "|" | "|-" | ">" | ">-" => Ok(Value::String(self.block_scalar(text, indent))),
+            _ if text.starts_with(['[', '{']) => {
+                let mut flow = Flow {
+                    chars: text.chars().collect(),
+                    pos: 0,
+                    line: number,
+                };
+                let value = flow.value()?;
+                flow.skip_spaces();
+                if flow.pos < flow.chars.len() {
+                    return Err(error(number, "unexpected text after flow collection"));
+                }
+                Ok(value)
+            }
+            _ if text.starts_with(['"', '\'']) => Ok(Value::String(unquote(text, number)?)),
+            _ => Ok(plain(text)),
+        }
+    }
+
+    /// Lines indented deeper than `indent`. `|` keeps line breaks, `>` folds
+    /// them into spaces; a trailing `-` drops the final newline.
+    fn block_scalar(&mut self, header: &str, indent: usize) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|line| line.indent > indent) {
+            self.pos += 1;
+        }
+        let lines = &self.lines[start..self.pos];
+        let base = lines.iter().map(|l| l.indent).min().unwrap_or(0);
+        let texts: Vec<String> = lines
+            .iter()
+            .map(|l| format!("{}{}", " ".repeat(l.indent - base), l.text))
+            .collect();
+        let mut text = if header.starts_with('|') {
+            texts.join("\n")
+        } else {
+            texts.join(" ")
+        };
+        if !header.ends_with('-') && !text.is_empty() {
+            text.push('\n');
+        }
+        text
+    }
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Splits `key: value` at the first `:` followed by a space or the end of the
+/// line, outside quotes.
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    if text.starts_with(['[', '{']) {
+        return None;
+    }
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') if i == 0 => quote = Some(c),
+            (None, ':') => {
+                let rest = &text[i + 1..];
+                if rest.is_empty() || rest.starts_with(' ') {
+                    return Some((text[..i].trim_end(), rest.trim()));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), _) if escaped => escaped = false,
+            (Some('"'), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') if previous == ' ' || "[{,:-".contains(previous) => quote = Some(c),
+            (None, '#') if previous == ' ' || previous == '\t' || i == 0 => return &line[..i],
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+fn unquote(text: &str, line: usize) -> Result<String> {
+    let text = text.trim();
+    if let Some(inner) = text.strip_prefix('\'') {
+        let inner = inner
+            .strip_suffix('\'')
+            .ok_or_else(|| error(line, "unterminated string"))?;
+        return Ok(inner.replace("''", "'"));
+    }
+    let Some(inner) = text.strip_prefix('"') else {
+        return Ok(text.to_string());
+    };
+    let inner = inner
+        .strip_suffix('"')
+        .ok_or_else(|| error(line, "unterminated string"))?;
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('/') => out.push('/'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                let c = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| error(line, &format!("invalid unicode escape `{hex}`")))?;
+                out.push(c);
+            }
+            Some(other) => {
+                return Err(error(line, &format!("unknown escape `\\{other}`")));
+            }
+            None => return Err(error(line, "unterminated string")),
+        }
+    }
+    Ok(out)
+}
+
+fn plain(text: &str) -> Value {
+    match text {
+        "~" | "null" | "Null" | "NULL" => Value::Null,
+        "true" | "True" | "TRUE" => Value::Bool(true),
+        "false" | "False" | "FALSE" => Value::Bool(false),
+        _ => match text.parse::<f64>() {
+            Ok(n) if n.is_finite() && !text.starts_with('+') && !text.ends_with('.') => {
+                Value::Number(n)
+            }
+            _ => Value::String(text.to_string()),
+        },
+    }
+}
+
+struct Flow {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Flow {
+    fn skip_spaces(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip_spaces();
+        match self.chars.get(self.pos) {
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                while !self.close(']')? {
+                    items.push(self.value()?);
+                }
+                Ok(Value::Array(items))
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut map = Value::object();
+                while !self.close('}')? {
+                    let key = match self.scalar(true)? {
+                        Value::String(key) => key,
+                        other => other.to_compact(),
+                    };
+                    self.skip_spaces();
+                    if self.chars.get(self.pos) != Some(&':') {
+                        return Err(error(self.line, "expected `:` in flow mapping"));
+                    }
+                    self.pos += 1;
+                    let value = self.value()?;
+                    map.insert(&key, value);
+                }
+                Ok(map)
+            }
+            _ => self.scalar(false),
+        }
+    }
+
+    /// Consumes a separating `,` and reports whether `end` closes the
+    /// collection.
+    fn close(&mut self, end: char) -> Result<bool> {
+        self.skip_spaces();
+        if self.chars.get(self.pos) == Some(&',') {
+            self.pos += 1;
+            self.skip_spaces();
+        }
+        match self.chars.get(self.pos) {
+            Some(&c) if c == end => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(error(self.line, &format!("missing `{end}`"))),
+        }
+    }
+
+    fn scalar(&mut self, key: bool) -> Result<Value> {
+        self.skip_spaces();
+        let start = self.pos;
+        if let Some(&q) = self.chars.get(self.pos).filter(|c| matches!(c, '"' | '\'')) {
+            self.pos += 1;
+            while let Some(&c) = self.chars.get(self.pos) {
+                self.pos += 1;
+                if c == '\\' && q == '"' {
+                    self.pos += 1;
+                } else if c == q {
+                    if q == '\'' && self.chars.get(self.pos) == Some(&'\'') {
+                        self.pos += 1;
+                        continue;
+                    }
+                    let text: String = self.chars[start..self.pos].iter().collect();
+                    return unquote(&text, self.line).map(Value::String);
+                }
+            }
+            return Err(error(self.line, "unterminated string"));
+        }
+        while let Some(&c) = self.chars.get(self.pos) {
+            if matches!(c, ',' | ']' | '}') || (key && c == ':') {
+                break;
+            }
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        Ok(plain(text.trim()))
+    }
+}
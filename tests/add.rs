@@ -0,0 +1,119 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Priority, TasksManager};
+
+/// Runs the binary on the data file in `dir`, answering prompts with
+/// `input`.
+fn run(dir: &Path, args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_task-manager"))
+        .env_clear()
+        .arg("--file")
+        .arg(dir.join("tasks.json"))
+        .arg("--config")
+        .arg(dir.join("config.toml"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn load(dir: &Path) -> TasksManager {
+    JsonStore::new(dir.join("tasks.json")).load().unwrap()
+}
+
+#[test]
+fn flags_fill_fields_and_only_the_rest_is_asked() {
+    let dir = std::env::temp_dir().join(format!("task-manager-add-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    // Description, priority and due date are asked for; the rest is given.
+    let asked = run(
+        &dir,
+        &[
+            "add",
+            "--name",
+            "Call the bank",
+            "--project",
+            "Home",
+            "--tags",
+            "money, phone",
+        ],
+        "About the loan\nhigh\n\n",
+    );
+    let prompts = String::from_utf8_lossy(&asked.stdout).into_owned();
+    let quiet = run(
+        &dir,
+        &[
+            "add",
+            "--no-prompt",
+            "--name",
+            "Buy stamps",
+            "--due",
+            "2030-01-02",
+        ],
+        "",
+    );
+    let manager = load(&dir);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(asked.status.success(), "{prompts}");
+    assert!(prompts.contains("Description: ") && prompts.contains("Priority"));
+    assert!(!prompts.contains("Name: ") && !prompts.contains("Project: "));
+    let bank = &manager.tasks()[0];
+    assert_eq!(bank.description, "About the loan");
+    assert_eq!(bank.priority, Priority::High);
+    assert_eq!(bank.project.as_deref(), Some("Home"));
+    assert_eq!(bank.tags, ["money", "phone"]);
+    assert_eq!(bank.due, None);
+
+    assert!(quiet.status.success());
+    assert!(String::from_utf8_lossy(&quiet.stdout).starts_with("Added task "));
+    let stamps = &manager.tasks()[1];
+    assert_eq!(stamps.priority, Priority::Medium);
+    assert_eq!((stamps.project.as_ref(), stamps.tags.len()), (None, 0));
+    assert!(stamps.due.is_some());
+}
+
+#[test]
+fn bad_flags_fail_before_any_prompt() {
+    let dir = std::env::temp_dir().join(format!("task-manager-add-bad-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let long = "x".repeat(201);
+    let cases = [
+        (vec!["add", "--no-prompt"], "missing --name"),
+        (vec!["add", "--name", " "], "the name cannot be empty"),
+        (vec!["add", "--name", "x", "--priority", "urgent"], "urgent"),
+        (vec!["add", "--name", "x", "--due", "someday"], "someday"),
+        (vec!["add", "--name", "x", "--contact", "nobody"], "nobody"),
+        (
+            vec!["add", "--name", "x", "--description", &long],
+            "limited to 200",
+        ),
+    ];
+    let outputs: Vec<Output> = cases.iter().map(|(args, _)| run(&dir, args, "")).collect();
+    let saved = dir.join("tasks.json").exists();
+    fs::remove_dir_all(&dir).unwrap();
+
+    for ((args, expected), output) in cases.iter().zip(outputs) {
+        assert!(!output.status.success(), "{args:?}");
+        assert_eq!(output.stdout, b"", "{args:?} asked something");
+        let message = String::from_utf8_lossy(&output.stderr);
+        assert!(message.contains(expected), "{args:?}: {message}");
+    }
+    assert!(!saved);
+}
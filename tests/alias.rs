@@ -0,0 +1,24 @@
+use task_manager::storage::{MemoryStore, Store};
+use task_manager::{Task, TasksManager};
+
+#[test]
+fn aliases_and_short_ids_resolve_to_tasks() {
+    let milk = Task::new("Buy milk");
+    let id = milk.id;
+    let mut manager = TasksManager::from_tasks(vec![milk, Task::new("Call the bank")]);
+    manager.set_alias("buy-milk", id).unwrap();
+    assert!(manager.set_alias("cafe", id).is_err());
+    assert!(manager.set_alias("buy milk", id).is_err());
+
+    let store = MemoryStore::new();
+    store.save(&manager).unwrap();
+    let mut loaded = store.load().unwrap();
+    assert_eq!(loaded.resolve("Buy-Milk").unwrap(), id);
+    assert_eq!(loaded.alias_of(id), Some("buy-milk"));
+    let width = loaded.id_width();
+    assert!(width >= 4);
+    assert_eq!(loaded.resolve(&id.to_hex()[..width]).unwrap(), id);
+
+    loaded.remove(id).unwrap();
+    assert!(loaded.aliases().is_empty());
+}
@@ -0,0 +1,53 @@
+use task_manager::dates::{DateTime, Timestamp};
+use task_manager::stats;
+use task_manager::{Priority, Status, Task};
+
+fn day(d: u32) -> Timestamp {
+    DateTime::date(2024, 6, d).to_timestamp().unwrap()
+}
+
+fn task(name: &str, priority: Priority, due: Option<u32>, created: u32) -> Task {
+    let mut task = Task::new(name);
+    task.priority = priority;
+    task.due = due.map(day);
+    task.created_at = day(created);
+    task
+}
+
+#[test]
+fn analytics_time_completions_per_tag_and_project() {
+    let now = day(29);
+    let finished = |name: &str, created: u32, completed: u32, tag: &str| {
+        let mut task = task(name, Priority::Medium, None, created);
+        task.tags = vec![tag.into()];
+        task.set_status(Status::Done);
+        task.completed_at = Some(day(completed));
+        task
+    };
+    let mut open = task("open", Priority::Low, None, 2);
+    open.tags = vec!["Work".into()];
+    open.project = Some("site".into());
+    let tasks = vec![
+        finished("a", 1, 2, "work"),
+        finished("b", 1, 4, "work"),
+        finished("c", 10, 28, "work"),
+        finished("d", 20, 27, "home"),
+        open,
+    ];
+    let tags = stats::by_tag(&tasks, now, 4);
+    assert_eq!(tags.len(), 2);
+    let (home, work) = (&tags[0], &tags[1]);
+    assert_eq!(home.name.as_deref(), Some("home"));
+    assert_eq!((work.open, work.done), (1, 3));
+    let days = |n: i64| n * 86_400;
+    assert_eq!(work.mean, Some(days(22) / 3));
+    assert_eq!(work.median, Some(days(3)));
+    assert_eq!(work.oldest_open, Some((tasks[4].id, days(27))));
+    assert_eq!(work.per_week, [2, 0, 0, 1]);
+    assert_eq!(work.throughput(), 0.75);
+
+    let projects = stats::by_project(&tasks, now, 4);
+    let names: Vec<Option<&str>> = projects.iter().map(|p| p.name.as_deref()).collect();
+    assert_eq!(names, [Some("site"), None]);
+    assert_eq!(projects[1].done, 4);
+}
@@ -0,0 +1,55 @@
+use task_manager::anonymize::Anonymizer;
+use task_manager::dates::Timestamp;
+use task_manager::{Priority, Task};
+
+fn private_task(name: &str, tags: &[&str]) -> Task {
+    let mut task = Task::new(name);
+    task.description = "call the bank about the mortgage".into();
+    task.project = Some("home".into());
+    task.tags = tags.iter().map(|t| t.to_string()).collect();
+    task.contact = Some("ann@example.org".into());
+    task.jira = Some("FIN-12".into());
+    task.priority = Priority::High;
+    task.due = Some(Timestamp::from_secs(1_700_000_000));
+    task.reminders = vec![3600];
+    task
+}
+
+#[test]
+fn private_text_is_replaced_and_structure_kept() {
+    let original = private_task("renew mortgage", &["Money"]);
+    let task = Anonymizer::with_salt(7).task(&original);
+
+    assert!(task.name.starts_with("task-"), "{}", task.name);
+    assert!(task.description.starts_with("description-"));
+    // An empty body has nothing to hide and stays empty.
+    assert_eq!(task.body, "");
+    assert!(task.project.as_deref().unwrap().starts_with("project-"));
+    assert!(task.jira.as_deref().unwrap().starts_with("issue-"));
+    let contact = task.contact.as_deref().unwrap();
+    assert!(contact.starts_with("contact-") && contact.ends_with("@example.com"));
+    assert!(!format!("{task:?}").contains("mortgage"));
+
+    assert_eq!(task.id, original.id);
+    assert_eq!(task.priority, original.priority);
+    assert_eq!(task.due, original.due);
+    assert_eq!(task.reminders, original.reminders);
+}
+
+#[test]
+fn equal_text_shares_a_placeholder_under_one_salt() {
+    let a = private_task("renew mortgage", &["Money", "home"]);
+    let b = private_task("file taxes", &["money"]);
+    let anonymizer = Anonymizer::with_salt(7);
+    let tasks = anonymizer.tasks(&[&a, &b]);
+
+    assert_ne!(tasks[0].name, tasks[1].name);
+    assert_eq!(tasks[0].project, tasks[1].project);
+    // Tags are matched without regard to case, as everywhere else.
+    assert_eq!(tasks[0].tags[0], tasks[1].tags[0]);
+    assert_ne!(tasks[0].tags[0], tasks[0].tags[1]);
+
+    // The same salt reproduces the output; another salt does not.
+    assert_eq!(Anonymizer::with_salt(7).task(&a).name, tasks[0].name);
+    assert_ne!(Anonymizer::with_salt(8).task(&a).name, tasks[0].name);
+}
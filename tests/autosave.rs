@@ -0,0 +1,53 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+
+use task_manager::storage::{JsonStore, Store};
+
+#[test]
+fn sigterm_saves_the_interactive_session() {
+    let dir = std::env::temp_dir().join(format!("task-manager-autosave-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_task-manager"))
+        .env_clear()
+        .arg("--file")
+        .arg(dir.join("tasks.json"))
+        .arg("--config")
+        .arg(dir.join("config.toml"))
+        .args(["--setting", "autosave.strategy=exit"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Stdin stays open, so only the signal ends the session.
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(stdin, "add --no-prompt --name \"water plants\"").unwrap();
+    // Kept open until the end, so the prompt never writes to a closed pipe.
+    let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+    let added = stdout
+        .by_ref()
+        .map_while(Result::ok)
+        .any(|line| line.contains("Added task"));
+    assert!(added);
+    let killed = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    let status = child.wait().unwrap();
+    drop(stdout);
+    let saved = JsonStore::new(dir.join("tasks.json")).load();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(killed.success());
+    assert_eq!(status.code(), Some(128 + 15));
+    let names: Vec<String> = saved
+        .unwrap()
+        .tasks()
+        .iter()
+        .map(|t| t.name.clone())
+        .collect();
+    assert_eq!(names, ["water plants"]);
+}
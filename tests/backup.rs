@@ -0,0 +1,96 @@
+use std::fs;
+
+use task_manager::config::Config;
+use task_manager::digest;
+use task_manager::split::{self, SplitBy};
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Task, TasksManager};
+
+#[test]
+fn backup_settings_parse() {
+    let config = Config::parse(
+        "[backup]\nendpoint = \"https://s3.example.com/\"\nbucket = \"tasks\"\nobject = \"/me/tasks.enc\"\naccess_key = \"\"\n",
+    )
+    .unwrap();
+    let backup = &config.backup;
+    assert_eq!(backup.endpoint.as_deref(), Some("https://s3.example.com"));
+    assert_eq!(backup.bucket.as_deref(), Some("tasks"));
+    assert_eq!(backup.object, "me/tasks.enc");
+    assert_eq!(backup.region, "us-east-1");
+    // Empty secrets are unset, so the environment can supply them.
+    assert_eq!(backup.access_key, None);
+    for bad in [
+        "[backup]\nregion = \"\"\n",
+        "[backup]\nobject = \" \"\n",
+        "[backup]\nbucket = 3\n",
+        "[backup]\npassword = \"x\"\n",
+    ] {
+        assert!(Config::parse(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn tags_authenticate_what_they_sign() {
+    // RFC 4231, test case 2.
+    assert_eq!(
+        digest::to_hex(&digest::hmac_sha256(
+            b"Jefe",
+            b"what do ya want for nothing?"
+        )),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+    // Keys longer than a block are hashed first (test case 6).
+    assert_eq!(
+        digest::to_hex(&digest::hmac_sha256(
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First"
+        )),
+        "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+    );
+    assert_eq!(
+        digest::to_hex(&digest::sha256(b"")),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+
+    let signed = digest::sign(b"key", b"Salted__ciphertext");
+    assert_eq!(
+        digest::verify(b"key", &signed).unwrap(),
+        b"Salted__ciphertext"
+    );
+    assert!(digest::verify(b"other key", &signed).is_err());
+    let mut altered = signed.clone();
+    altered[9] ^= 1;
+    assert!(digest::verify(b"key", &altered).is_err());
+    assert!(digest::verify(b"key", &signed[..20]).is_err());
+}
+
+#[test]
+fn replacing_a_store_keeps_the_previous_tasks() {
+    let dir = std::env::temp_dir().join(format!("task-manager-restore-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let old = TasksManager::from_tasks(vec![Task::new("old task")]);
+    let new = TasksManager::from_tasks(vec![Task::new("restored"), Task::new("also restored")]);
+
+    // A data file is copied as it was.
+    let file = JsonStore::new(dir.join("tasks.json"));
+    assert_eq!(file.replace(&old).unwrap(), None);
+    let before = fs::read(file.path()).unwrap();
+    let backup = file.replace(&new).unwrap().unwrap();
+    assert_eq!(backup, dir.join("tasks.json.bak"));
+    assert_eq!(fs::read(&backup).unwrap(), before);
+    assert_eq!(file.load().unwrap().len(), 2);
+
+    // A directory store's tasks go into a single data file.
+    let store_dir = dir.join("tasks.d");
+    split::create(&store_dir, &old, SplitBy::Project).unwrap();
+    let store = JsonStore::new(&store_dir);
+    let backup = store.replace(&new).unwrap().unwrap();
+    let kept = JsonStore::new(&backup).load().unwrap();
+    let reloaded = store.load().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(backup, dir.join("tasks.d.bak"));
+    assert_eq!(kept.tasks()[0].name, "old task");
+    assert_eq!(reloaded.len(), 2);
+}
@@ -0,0 +1,65 @@
+use task_manager::dates::{DateTime, Timestamp};
+use task_manager::task::DESCRIPTION_LIMIT;
+use task_manager::update::{self, Update};
+use task_manager::{Task, TasksManager};
+
+/// Wednesday 2024-06-12 12:00 UTC.
+fn now() -> Timestamp {
+    DateTime {
+        year: 2024,
+        month: 6,
+        day: 12,
+        hour: 12,
+        minute: 0,
+        second: 0,
+    }
+    .to_timestamp()
+    .unwrap()
+}
+
+#[test]
+fn long_descriptions_go_in_the_body() {
+    let manager = TasksManager::new();
+    let long = "x".repeat(DESCRIPTION_LIMIT + 1);
+    let err = Update::parse(&format!("description={long}"), now(), &manager).unwrap_err();
+    assert!(err.to_string().contains("--body"), "{err}");
+    assert_eq!(
+        Update::parse(&format!("body={long}"), now(), &manager).unwrap(),
+        Update::Body(long)
+    );
+    let (_, text) = update::fields()
+        .into_iter()
+        .find(|&(name, _)| name == "description")
+        .unwrap();
+    assert!(text.contains(&format!("up to {} characters", DESCRIPTION_LIMIT)));
+}
+
+#[test]
+fn hand_edited_tasks_keep_to_the_description_limit() {
+    let mut task = Task::new("Write report");
+    task.description = "x".repeat(DESCRIPTION_LIMIT + 10);
+    let text = |description: &str| {
+        let mut edited = task.clone();
+        edited.description = description.into();
+        edited.to_json().to_pretty()
+    };
+
+    let long = "y".repeat(DESCRIPTION_LIMIT + 1);
+    let err = Task::from_edited_json(&text(&long), &task).unwrap_err();
+    assert!(err.to_string().contains("--body"), "{err}");
+    assert!(err.to_string().starts_with("line "), "{err}");
+    let fits = "y".repeat(DESCRIPTION_LIMIT);
+    assert_eq!(
+        Task::from_edited_json(&text(&fits), &task)
+            .unwrap()
+            .description,
+        fits
+    );
+    // A description already over the limit may stay as it is.
+    assert_eq!(
+        Task::from_edited_json(&text(&task.description), &task)
+            .unwrap()
+            .description,
+        task.description
+    );
+}
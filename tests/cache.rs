@@ -0,0 +1,107 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Write;
+
+use task_manager::cache::{self, ContentHash, HashWriter};
+use task_manager::index::SearchIndex;
+use task_manager::storage::{self, JsonStore, Store};
+use task_manager::timing;
+use task_manager::{Priority, Task, TasksManager};
+
+fn manager() -> TasksManager {
+    let mut report = Task::new("write \"report\" ✍");
+    report.priority = Priority::High;
+    report.tags = vec!["work".into()];
+    report.reminders = vec![900, -60];
+    let mut manager = TasksManager::from_tasks(vec![report, Task::new("")]);
+    manager.set_archived("old", true);
+    manager
+}
+
+#[test]
+fn cache_holds_the_encoded_document() {
+    let manager = manager();
+    let path = std::env::temp_dir().join(format!("task-manager-cache-{}", std::process::id()));
+    let hash = ContentHash::of(b"{}");
+    cache::write(
+        &path,
+        hash,
+        manager.tasks().iter(),
+        manager.history(),
+        manager.templates(),
+        manager.archived_projects(),
+        manager.aliases(),
+    )
+    .unwrap();
+    let cached = cache::read(&path, hash);
+    let stale = cache::read(&path, ContentHash::of(b"{ }"));
+
+    // A damaged cache is ignored rather than misread.
+    let mut bytes = fs::read(&path).unwrap();
+    bytes.truncate(bytes.len() - 1);
+    fs::write(&path, bytes).unwrap();
+    let truncated = cache::read(&path, hash);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(cached, Some(storage::encode(&manager)));
+    assert_eq!(stale, None);
+    assert_eq!(truncated, None);
+}
+
+#[test]
+fn hash_does_not_depend_on_how_writes_are_split() {
+    let text = storage::encode(&manager()).to_pretty();
+    let mut writer = HashWriter::new(Vec::new());
+    for chunk in text.as_bytes().chunks(5) {
+        writer.write_all(chunk).unwrap();
+    }
+    assert_eq!(writer.hash(), ContentHash::of(text.as_bytes()));
+    assert_ne!(
+        writer.hash(),
+        ContentHash::of(format!("{text}\0").as_bytes())
+    );
+    assert_eq!(writer.into_inner(), text.as_bytes());
+}
+
+#[test]
+fn timings_nest_the_phases_of_loading_and_saving() {
+    let path =
+        std::env::temp_dir().join(format!("task-manager-timings-{}.json", std::process::id()));
+    let store = JsonStore::new(&path);
+    timing::enable();
+    store.save(&manager()).unwrap();
+    store.load().unwrap();
+    let spans = timing::take();
+    fs::remove_file(&path).unwrap();
+
+    let named: Vec<(&str, usize)> = spans.iter().map(|s| (s.name, s.depth)).collect();
+    for phase in [("write", 0), ("load", 0), ("parse", 1), ("decode", 1)] {
+        assert!(named.contains(&phase), "{phase:?} missing from {named:?}");
+    }
+    assert!(spans.iter().all(|s| s.elapsed.is_some()));
+    assert!(timing::report(&spans).contains("\n    parse "));
+}
+
+#[test]
+fn search_index_matches_word_prefixes_and_notices_edits() {
+    let mut tasks = manager().tasks().to_vec();
+    tasks[1].name = "Quarterly planning".into();
+    tasks[1].description = "Draft the REPORT outline".into();
+    let index = SearchIndex::build(&tasks);
+    assert_eq!(
+        index.search("rep"),
+        BTreeSet::from([tasks[0].id, tasks[1].id])
+    );
+    assert_eq!(index.search("quart Report"), BTreeSet::from([tasks[1].id]));
+    assert!(index.search("port").is_empty());
+
+    let path =
+        std::env::temp_dir().join(format!("task-manager-index-{}.index", std::process::id()));
+    index.write(&path).unwrap();
+    let read = SearchIndex::read(&path, &tasks);
+    tasks[0].name = "Write summary".into();
+    let stale = SearchIndex::read(&path, &tasks);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(read, Some(index));
+    assert_eq!(stale, None);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use std::path::Path;
+use std::process::Output;
+
+use common::{binary, stdout, TempDir};
+
+use task_manager::dates::{self, DateTime};
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Status, Task, TasksManager};
+
+fn due(name: &str, day: u32) -> Task {
+    let mut task = Task::new(name);
+    task.due = Some(
+        DateTime::date(2024, 6, day)
+            .to_timestamp()
+            .unwrap()
+            .add_secs(12 * 3600),
+    );
+    task
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    binary(dir).args(args).output().unwrap()
+}
+
+#[test]
+fn month_names_parse_from_three_letters() {
+    assert_eq!(dates::parse_month("June"), Some(6));
+    assert_eq!(dates::parse_month(" SEPT "), Some(9));
+    assert_eq!(dates::parse_month("jun"), Some(6));
+    assert_eq!(dates::parse_month("ju"), None);
+    assert_eq!(dates::parse_month("smarch"), None);
+}
+
+#[test]
+fn calendar_counts_open_tasks_due_each_day() {
+    let dir = TempDir::new("calendar");
+    let mut paid = due("pay rent", 3);
+    paid.set_status(Status::Done);
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(vec![
+            paid,
+            due("call plumber", 3),
+            due("book dentist", 3),
+            due("renew passport", 30),
+        ]))
+        .unwrap();
+
+    let june = stdout(run(&dir, &["calendar", "2024-06"]));
+    let all = stdout(run(&dir, &["calendar", "06-2024", "--all"]));
+    let day = stdout(run(&dir, &["calendar", "--day", "2024-06-03"]));
+    let bad = run(&dir, &["calendar", "2024-13"]);
+    let both = run(&dir, &["calendar", "june", "--day", "2024-06-03"]);
+
+    let lines: Vec<&str> = june.lines().collect();
+    assert_eq!(lines[0].trim(), "June 2024");
+    assert!(lines[1].starts_with(" Mo"), "{june}");
+    // June 2024 starts on a Saturday; the past days with tasks are overdue.
+    assert!(lines[2].starts_with(&" ".repeat(35)), "{june}");
+    assert!(lines[3].starts_with("  3 2!"), "{june}");
+    assert!(june.contains(" 30 1!"), "{june}");
+    assert!(
+        june.ends_with("3 task(s) due this month, 3 overdue\n"),
+        "{june}"
+    );
+    assert!(all.contains("  3 3!"), "{all}");
+
+    assert!(day.starts_with("Monday 03-06-2024\n"), "{day}");
+    assert!(day.contains("call plumber") && day.contains("book dentist"));
+    assert!(!day.contains("pay rent"), "{day}");
+    assert!(!bad.status.success());
+    assert!(!both.status.success());
+}
@@ -0,0 +1,98 @@
+//! What the tests that run the binary share: a scratch directory, the
+//! command itself, and stand-ins for the programs it calls.
+
+// Each test crate uses only some of these.
+#![allow(dead_code)]
+
+use std::fs;
+use std::io::Write;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// A scratch directory under the system's temporary directory, removed
+/// when the test ends, whether it passed or not.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// An empty directory named for the test and this process, so runs of
+    /// the suite side by side do not meet.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("task-manager-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// The binary on the data and config files in `dir`, with nothing from
+/// the environment but `PATH`, where `dir/bin` comes first.
+pub fn binary(dir: &Path) -> Command {
+    let path = format!(
+        "{}:{}",
+        dir.join("bin").display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let mut command = Command::new(env!("CARGO_BIN_EXE_task-manager"));
+    command
+        .env_clear()
+        .env("PATH", path)
+        .arg("--file")
+        .arg(dir.join("tasks.json"))
+        .arg("--config")
+        .arg(dir.join("config.toml"));
+    command
+}
+
+/// Runs `command`, answering its prompts with `input`.
+pub fn answering(command: &mut Command, input: &str) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// What a run printed, once it has succeeded.
+pub fn stdout(output: Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Puts `script` in `dir/bin` as `name`, ahead of the real program.
+#[cfg(unix)]
+pub fn fake(dir: &Path, name: &str, script: &str) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin = dir.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let path = bin.join(name);
+    fs::write(&path, script).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+}
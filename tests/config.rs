@@ -0,0 +1,150 @@
+use std::path::Path;
+
+use task_manager::config::{
+    AutosaveStrategy, Config, Layers, Origin, Override, SourceLocation, SETTINGS,
+};
+
+fn error(input: &str) -> String {
+    Config::parse(input).unwrap_err().to_string()
+}
+
+#[test]
+fn toml_errors_name_the_line() {
+    assert_eq!(
+        error("[display]\nbrief = true\nbrief = false\n"),
+        "TOML line 3: `brief` is defined twice"
+    );
+    assert!(error("[display\n").contains("TOML line 1"));
+    assert!(error("[user]\nname = \"alice\n").contains("line 2: unterminated string"));
+    assert!(error("[user]\nname =\n").contains("line 2: missing value"));
+    assert!(error("[wip]\nin_progress = [1, 2\n").contains("`,` or `]`"));
+    assert!(error("[user]\nname = \"\\q\"\n").contains("unknown escape"));
+    // Well-formed TOML can still hold settings that do not exist.
+    assert!(error("[display]\ncolour = true\n").contains("unknown setting `display.colour`"));
+    assert!(error("[dispaly]\n").contains("unknown setting `dispaly`"));
+}
+
+#[test]
+fn wip_limits_parse() {
+    let config = Config::parse("[wip]\nin_progress = 3\nper_day = 10\n").unwrap();
+    assert_eq!(config.wip.in_progress, Some(3));
+    assert_eq!(config.wip.per_day, Some(10));
+    assert_eq!(Config::parse("").unwrap().wip.in_progress, None);
+    for bad in [
+        "[wip]\nin_progress = -1\n",
+        "[wip]\nper_day = \"ten\"\n",
+        "[wip]\nper_week = 3\n",
+    ] {
+        assert!(Config::parse(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn git_settings_parse() {
+    let config =
+        Config::parse("[git]\nauto_commit = true\nauto_push = true\nremote = \"origin\"\n")
+            .unwrap();
+    assert!(config.git.auto_commit && config.git.auto_push);
+    assert_eq!(config.git.remote.as_deref(), Some("origin"));
+    assert!(Config::parse("[git]\npush = true\n").is_err());
+}
+
+#[test]
+fn sources_parse() {
+    let config = Config::parse(
+        "[[sources]]\nname = \"bob\"\npath = \"shared/bob.json\"\n\n[[sources]]\nname = \"team\"\nurl = \"https://example.com/tasks.json\"\n",
+    )
+    .unwrap();
+    assert_eq!(config.sources.len(), 2);
+    assert_eq!(config.sources[0].name, "bob");
+    assert_eq!(
+        config.sources[1].location,
+        SourceLocation::Url("https://example.com/tasks.json".into())
+    );
+    for bad in [
+        "[[sources]]\npath = \"a.json\"\n",
+        "[[sources]]\nname = \"a\"\n",
+        "[[sources]]\nname = \"a\"\npath = \"a.json\"\nurl = \"https://x\"\n",
+        "[[sources]]\nname = \"a\"\nurl = \"ftp://x\"\n",
+    ] {
+        assert!(Config::parse(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn autosave_strategy_is_configured() {
+    assert_eq!(
+        Config::default().autosave.strategy,
+        AutosaveStrategy::Change
+    );
+    let config = Config::parse("[autosave]\nstrategy = \"debounce\"\ninterval = \"2m\"\n").unwrap();
+    assert_eq!(config.autosave.strategy, AutosaveStrategy::Debounce);
+    assert_eq!(config.autosave.interval, 120);
+    assert!(Config::parse("[autosave]\nstrategy = \"never\"\n").is_err());
+}
+
+#[test]
+fn settings_layer_env_and_flags_over_the_file() {
+    let defaults: String = SETTINGS
+        .iter()
+        .filter(|(_, default)| !default.is_empty())
+        .map(|(key, default)| {
+            let (section, key) = key.split_once('.').unwrap();
+            format!("[{section}]\n{key} = {default}\n")
+        })
+        .collect();
+    assert_eq!(Config::parse(&defaults).unwrap(), Config::default());
+
+    let path = Path::new("config.toml");
+    let mut layers = Layers::new()
+        .with_file(
+            "[display]\nrelative_times = true\nbrief = true\n[backup]\npassphrase = \"x\"\n",
+            path,
+        )
+        .unwrap();
+    let env = [
+        ("TASK_MANAGER_DISPLAY_TIMEZONE", "+02:00"),
+        ("TASK_MANAGER_DISPLAY_BRIEF", "false"),
+        ("TASK_MANAGER_FILE", "tasks.json"),
+        ("HOME", "/home/ana"),
+    ];
+    let vars = env.iter().map(|(k, v)| (k.to_string(), v.to_string()));
+    for item in Override::from_env(vars).unwrap() {
+        layers.set(item).unwrap();
+    }
+    layers
+        .set(Override::flag("display.timezone=-05:00").unwrap())
+        .unwrap();
+    let config = layers.config().unwrap();
+    assert!(config.display.relative_times && !config.display.brief);
+    assert_eq!(config.display.timezone, "-05:00".parse().unwrap());
+
+    let settings = layers.settings();
+    let origin = |key: &str| {
+        let setting = settings.iter().find(|s| s.key == key).unwrap();
+        (setting.value.as_str(), setting.origin.to_string())
+    };
+    assert_eq!(
+        origin("display.timezone"),
+        ("\"-05:00\"", "--setting display.timezone".into())
+    );
+    assert_eq!(
+        origin("display.brief"),
+        ("false", "$TASK_MANAGER_DISPLAY_BRIEF".into())
+    );
+    assert_eq!(
+        origin("display.relative_times"),
+        ("true", "config.toml".into())
+    );
+    assert_eq!(origin("backup.passphrase"), ("(set)", "config.toml".into()));
+    assert_eq!(origin("git.remote"), ("", Origin::Default.to_string()));
+
+    let typo = [(
+        "TASK_MANAGER_DISPLAY_TIMZONE".to_string(),
+        "UTC".to_string(),
+    )];
+    assert!(Override::from_env(typo).is_err());
+    assert!(layers
+        .set(Override::flag("display.week_start=someday").unwrap())
+        .is_err());
+}
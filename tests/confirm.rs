@@ -0,0 +1,89 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use common::{answering, binary, stdout, TempDir};
+
+use task_manager::config::{Config, Operation};
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Task, TasksManager};
+
+/// Runs the binary on the data file in `dir`, answering prompts with
+/// `input`.
+fn run(dir: &Path, args: &[&str], input: &str) -> Output {
+    answering(binary(dir).args(args), input)
+}
+
+fn names(dir: &Path) -> Vec<String> {
+    let manager = JsonStore::new(dir.join("tasks.json")).load().unwrap();
+    manager.tasks().iter().map(|t| t.name.clone()).collect()
+}
+
+#[test]
+fn confirm_settings_parse() {
+    let config = Config::parse("[confirm]\nremove = false\n").unwrap();
+    assert!(!config.confirm.asks(Operation::Remove, false));
+    assert!(config.confirm.asks(Operation::Overwrite, false));
+    assert!(!config.confirm.asks(Operation::Overwrite, true));
+    assert!(Config::parse("[confirm]\nlist = true\n").is_err());
+}
+
+#[test]
+fn remove_asks_unless_forced_or_turned_off() {
+    let dir = TempDir::new("confirm-remove");
+    let tasks = vec![
+        Task::new("call supplier"),
+        Task::new("order paper"),
+        Task::new("water plants"),
+    ];
+    let ids: Vec<String> = tasks.iter().map(|t| t.id.to_hex()).collect();
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(tasks))
+        .unwrap();
+
+    let declined = stdout(run(&dir, &["remove", &ids[0]], "n\n"));
+    let kept = names(&dir);
+    // No answer at all is a no, too.
+    let unanswered = stdout(run(&dir, &["remove", &ids[0]], ""));
+    let accepted = stdout(run(&dir, &["remove", &ids[0]], "y\n"));
+    let forced = stdout(run(&dir, &["remove", "--force", &ids[1]], ""));
+    fs::write(dir.join("config.toml"), "[confirm]\nremove = false\n").unwrap();
+    let unasked = stdout(run(&dir, &["remove", &ids[2]], ""));
+
+    assert_eq!(declined, "Remove \"call supplier\"? [y/N] Cancelled.\n");
+    assert_eq!(kept, ["call supplier", "order paper", "water plants"]);
+    assert!(unanswered.ends_with("Cancelled.\n"), "{unanswered}");
+    assert_eq!(
+        accepted,
+        "Remove \"call supplier\"? [y/N] Removed \"call supplier\"\n"
+    );
+    assert_eq!(forced, "Removed \"order paper\"\n");
+    assert_eq!(unasked, "Removed \"water plants\"\n");
+    assert!(names(&dir).is_empty());
+}
+
+#[test]
+fn export_asks_before_writing_over_a_file() {
+    let dir = TempDir::new("confirm-export");
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(vec![Task::new("call supplier")]))
+        .unwrap();
+    let target = dir.join("out.json");
+    let target = target.to_str().unwrap();
+
+    // A new file is written without a question.
+    let fresh = stdout(run(&dir, &["export", "--json", target], ""));
+    fs::write(target, "keep me").unwrap();
+    let declined = stdout(run(&dir, &["export", "--json", target], "no\n"));
+    let after_declined = fs::read_to_string(target).unwrap();
+    let forced = stdout(run(&dir, &["export", "--json", target, "--force"], ""));
+    let after_forced = fs::read_to_string(target).unwrap();
+
+    assert_eq!(fresh, format!("Exported 1 tasks to {target}\n"));
+    assert_eq!(declined, format!("Overwrite {target}? [y/N] Cancelled.\n"));
+    assert_eq!(after_declined, "keep me");
+    assert_eq!(forced, format!("Exported 1 tasks to {target}\n"));
+    assert!(after_forced.contains("call supplier"), "{after_forced}");
+}
@@ -0,0 +1,212 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use task_manager::config::{Config, DEFAULT_SOUND_PLAYER};
+use task_manager::dates::Timestamp;
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Task, TasksManager};
+
+#[test]
+fn bell_and_sound_settings_parse() {
+    let defaults = Config::parse("").unwrap().daemon;
+    assert!(!defaults.bell);
+    assert_eq!(defaults.sound, None);
+    assert_eq!(defaults.sound_player, DEFAULT_SOUND_PLAYER);
+
+    let daemon = Config::parse(
+        "[daemon]\nbell = true\nsound = \" /tmp/ding.oga \"\nsound_player = \"mpv --really-quiet\"\n",
+    )
+    .unwrap()
+    .daemon;
+    assert!(daemon.bell);
+    assert_eq!(daemon.sound, Some(PathBuf::from("/tmp/ding.oga")));
+    assert_eq!(daemon.sound_player, "mpv --really-quiet");
+    // An empty sound is no sound.
+    let quiet = Config::parse("[daemon]\nsound = \"\"\n").unwrap().daemon;
+    assert_eq!(quiet.sound, None);
+    for bad in [
+        "[daemon]\nbell = \"yes\"\n",
+        "[daemon]\nsound_player = \" \"\n",
+        "[daemon]\nsound = 1\n",
+    ] {
+        assert!(Config::parse(bad).is_err(), "{bad}");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn reminders_ring_the_bell_and_play_the_sound() {
+    let dir = std::env::temp_dir().join(format!("task-manager-daemon-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    // `touch` stands in for a player, leaving the "sound" file behind.
+    let played = dir.join("played");
+    fs::write(
+        dir.join("config.toml"),
+        format!(
+            "[daemon]\ninterval = 1\nbackup_every = \"none\"\nbell = true\nsound = \"{}\"\nsound_player = \"touch\"\n",
+            played.display()
+        ),
+    )
+    .unwrap();
+    let mut task = Task::new("stretch");
+    task.due = Some(Timestamp::now().add_secs(2));
+    task.reminders = vec![0];
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(vec![task]))
+        .unwrap();
+
+    let command = |args: &[&str]| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_task-manager"));
+        command
+            .env_clear()
+            .arg("--file")
+            .arg(dir.join("tasks.json"))
+            .arg("--config")
+            .arg(dir.join("config.toml"))
+            .args(args);
+        command
+    };
+    let daemon = command(&["daemon", "run"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let started = Instant::now();
+    while !played.exists() && started.elapsed() < Duration::from_secs(15) {
+        thread::sleep(Duration::from_millis(100));
+    }
+    let stopped = command(&["daemon", "stop"]).output().unwrap();
+    let log = daemon.wait_with_output().unwrap();
+    let rang = played.exists();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(stopped.status.success());
+    let log = String::from_utf8_lossy(&log.stdout);
+    assert!(rang, "{log}");
+    assert!(log.contains("reminder: \"stretch\" is due"), "{log}");
+    assert!(log.contains('\x07'), "{log}");
+}
+
+#[cfg(unix)]
+#[test]
+fn mail_that_cannot_be_read_does_not_hold_up_reminders() {
+    let dir = std::env::temp_dir().join(format!("task-manager-daemon-mail-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let played = dir.join("played");
+    fs::write(
+        dir.join("config.toml"),
+        format!(
+            "[daemon]\ninterval = 1\nbackup_every = \"none\"\nsound = \"{}\"\nsound_player = \"touch\"\n\n[mail]\nmaildir = \"{}\"\n",
+            played.display(),
+            dir.join("no-such-maildir").display()
+        ),
+    )
+    .unwrap();
+    let mut task = Task::new("stretch");
+    task.due = Some(Timestamp::now().add_secs(2));
+    task.reminders = vec![0];
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(vec![task]))
+        .unwrap();
+
+    let command = |args: &[&str]| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_task-manager"));
+        command
+            .env_clear()
+            .arg("--file")
+            .arg(dir.join("tasks.json"))
+            .arg("--config")
+            .arg(dir.join("config.toml"))
+            .args(args);
+        command
+    };
+    let daemon = command(&["daemon", "run"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let started = Instant::now();
+    while !played.exists() && started.elapsed() < Duration::from_secs(15) {
+        thread::sleep(Duration::from_millis(100));
+    }
+    command(&["daemon", "stop"]).output().unwrap();
+    let log = daemon.wait_with_output().unwrap();
+    let rang = played.exists();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let log = String::from_utf8_lossy(&log.stdout);
+    assert!(rang, "{log}");
+    assert!(log.contains("could not take mail from"), "{log}");
+    assert!(log.contains("is not a Maildir"), "{log}");
+}
+
+#[cfg(unix)]
+#[test]
+fn checks_keep_their_own_backup_and_wait_for_commands() {
+    let dir = std::env::temp_dir().join(format!("task-manager-daemon-lock-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("config.toml"),
+        "[daemon]\ninterval = 1\nbackup_every = \"1s\"\n",
+    )
+    .unwrap();
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(vec![Task::new("stretch")]))
+        .unwrap();
+    let command = |args: &[&str]| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_task-manager"));
+        command
+            .env_clear()
+            .arg("--file")
+            .arg(dir.join("tasks.json"))
+            .arg("--config")
+            .arg(dir.join("config.toml"))
+            .args(args);
+        command
+    };
+
+    // While another process holds the lock, a command waits for it and a
+    // check is skipped.
+    let lock = JsonStore::new(dir.join("tasks.json"))
+        .lock(true)
+        .unwrap()
+        .unwrap();
+    let mut adding = command(&["add", "--no-prompt", "--name", "stand up"])
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let daemon = command(&["daemon", "run"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    thread::sleep(Duration::from_millis(1500));
+    let waited = adding.try_wait().unwrap().is_none();
+    let backed_up_while_locked = dir.join("tasks.json.daemon.bak").exists();
+    drop(lock);
+    let added = adding.wait().unwrap();
+    let backup = dir.join("tasks.json.daemon.bak");
+    let started = Instant::now();
+    while !backup.exists() && started.elapsed() < Duration::from_secs(15) {
+        thread::sleep(Duration::from_millis(100));
+    }
+    command(&["daemon", "stop"]).output().unwrap();
+    let log = daemon.wait_with_output().unwrap();
+    let backed_up = backup.exists();
+    let restore_backup = dir.join("tasks.json.bak").exists();
+    let saved = JsonStore::new(dir.join("tasks.json")).load().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let log = String::from_utf8_lossy(&log.stdout);
+    assert!(waited);
+    assert!(!backed_up_while_locked, "{log}");
+    assert!(log.contains("skipped this check"), "{log}");
+    assert!(added.success());
+    assert_eq!(saved.len(), 2);
+    assert!(backed_up, "{log}");
+    assert!(log.contains("backed up to"), "{log}");
+    assert!(!restore_backup);
+}
@@ -0,0 +1,265 @@
+use task_manager::config::Config;
+use task_manager::dates::{self, set_display_offset, DateRange, Shift, Timestamp, Weekday};
+use task_manager::duration;
+
+/// Central European time without daylight saving. Every test sets the same
+/// offset, as the setting is shared by the whole test binary.
+fn cet() {
+    set_display_offset("+01:00".parse().unwrap());
+}
+
+fn local(input: &str) -> Timestamp {
+    dates::parse_when(input, Timestamp::from_secs(0)).unwrap()
+}
+
+#[test]
+fn shifts_parse() {
+    assert_eq!(Shift::parse("+2d").unwrap(), Shift::Days(2));
+    assert_eq!(Shift::parse("-1w").unwrap(), Shift::Days(-7));
+    assert_eq!(Shift::parse("+3MO").unwrap(), Shift::Months(3));
+    assert_eq!(Shift::parse("+1y").unwrap(), Shift::Months(12));
+    for bad in ["2d", "+d", "+2", "+2m", "+-2d", ""] {
+        assert!(Shift::parse(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn day_shifts_cross_month_and_year_ends() {
+    cet();
+    let shift = |shift: Shift, from: &str| shift.apply(local(from)).unwrap();
+    assert_eq!(
+        shift(Shift::Days(2), "2024-02-28 09:15"),
+        local("2024-03-01 09:15")
+    );
+    assert_eq!(
+        shift(Shift::Days(1), "2024-12-31 23:30"),
+        local("2025-01-01 23:30")
+    );
+    assert_eq!(
+        shift(Shift::Days(-7), "2024-03-03 00:30"),
+        local("2024-02-25 00:30")
+    );
+}
+
+#[test]
+fn huge_shifts_are_errors() {
+    cet();
+    for bad in ["+2000000000000000000w", "-900000000000000000y"] {
+        assert!(Shift::parse(bad).is_err(), "{bad}");
+    }
+    let now = local("2024-05-15 10:00");
+    for shift in [
+        "+200000000000000d",
+        "+9223372036854775807d",
+        "+8000y",
+        "-2024y",
+        "+99999999mo",
+    ] {
+        let err = dates::reschedule(shift, Some(now), now).unwrap_err();
+        assert!(err.to_string().contains("year 1 or 9999"), "{shift}: {err}");
+    }
+    assert!(dates::reschedule("+7975y", Some(now), now).is_ok());
+}
+
+#[test]
+fn month_shifts_clamp_to_the_end_of_the_month() {
+    cet();
+    let end_of_january = local("2024-01-31 18:00");
+    assert_eq!(
+        Shift::Months(1).apply(end_of_january).unwrap(),
+        local("2024-02-29 18:00")
+    );
+    assert_eq!(
+        Shift::Months(13).apply(end_of_january).unwrap(),
+        local("2025-02-28 18:00")
+    );
+    assert_eq!(
+        Shift::Months(-2).apply(local("2024-01-15 00:30")).unwrap(),
+        local("2023-11-15 00:30")
+    );
+    // Just after local midnight is still the previous day in UTC.
+    assert_eq!(
+        Shift::Months(1).apply(local("2024-03-01 00:30")).unwrap(),
+        local("2024-04-01 00:30")
+    );
+    assert_eq!(
+        Shift::Months(12).apply(local("2024-02-29")).unwrap(),
+        local("2025-02-28")
+    );
+}
+
+#[test]
+fn reschedule_shifts_or_replaces_the_due_date() {
+    cet();
+    // Wednesday 2024-05-15, 10:00 local.
+    let now = local("2024-05-15 10:00");
+    let due = local("2024-05-10 17:00");
+    assert_eq!(
+        dates::reschedule("+2d", Some(due), now).unwrap(),
+        local("2024-05-12 17:00")
+    );
+    assert_eq!(
+        dates::reschedule("+1d", None, now).unwrap(),
+        local("2024-05-16")
+    );
+    let monday = dates::reschedule("next-monday", Some(due), now).unwrap();
+    assert_eq!(monday, local("2024-05-20"));
+    assert_eq!(monday.weekday(), Weekday::Monday);
+    assert_eq!(
+        dates::reschedule("tomorrow 9:00", Some(due), now).unwrap(),
+        local("2024-05-16 09:00")
+    );
+    assert!(dates::reschedule("+2x", Some(due), now).is_err());
+}
+
+#[test]
+fn machine_timestamps_are_rfc_3339_in_utc_whatever_the_display_zone() {
+    cet();
+    let time = local("2024-05-15 09:30");
+    assert_eq!(time.to_rfc3339(), "2024-05-15T08:30:00Z");
+    for input in [
+        "2024-05-15T08:30:00Z",
+        "2024-05-15T10:30:00+02:00",
+        "2024-05-15t08:30:00.250z",
+    ] {
+        assert_eq!(local(input), time, "{input}");
+        assert_eq!(Timestamp::parse_stored(input).unwrap(), time, "{input}");
+    }
+    // Files from before times were stored in UTC are display-local.
+    assert_eq!(
+        Timestamp::parse_stored("2024-05-15T09:30:00").unwrap(),
+        time
+    );
+}
+
+#[test]
+fn periods_cover_whole_local_days() {
+    cet();
+    // Wednesday 2024-05-15, just after local midnight.
+    let now = local("2024-05-15 00:30");
+    let range = |input: &str| {
+        let range = DateRange::parse(input, now).unwrap();
+        (range.start, range.end)
+    };
+    let start = |input: &str| local(input).start_of_day();
+    // A date without a time is the end of that day.
+    let end = local;
+    assert_eq!(range("this-week"), (start("2024-05-13"), end("2024-05-19")));
+    assert_eq!(range("last week"), (start("2024-05-06"), end("2024-05-12")));
+    assert_eq!(
+        range("last-month"),
+        (start("2024-04-01"), end("2024-04-30"))
+    );
+    assert_eq!(
+        range("next-month"),
+        (start("2024-06-01"), end("2024-06-30"))
+    );
+    assert_eq!(range("this-year"), (start("2024-01-01"), end("2024-12-31")));
+    assert_eq!(range("yesterday"), (start("2024-05-14"), end("2024-05-14")));
+    // A weekday is still the day, not a period.
+    assert_eq!(
+        range("next-monday"),
+        (start("2024-05-20"), end("2024-05-20"))
+    );
+    assert!(DateRange::parse("last-fortnight", now).is_err());
+
+    dates::set_week_start(Weekday::Sunday);
+    let sunday_weeks = (range("this-week"), range("next-week"));
+    dates::set_week_start(Weekday::Monday);
+    assert_eq!(sunday_weeks.0, (start("2024-05-12"), end("2024-05-18")));
+    assert_eq!(sunday_weeks.1, (start("2024-05-19"), end("2024-05-25")));
+}
+
+#[test]
+fn configured_formats_apply_to_every_rendered_date() {
+    cet();
+    let config =
+        Config::parse("[display]\ndate_format = \"%Y/%m/%d\"\ntime_format = \"%H:%M\"\n").unwrap();
+    dates::set_display_format(&config.display.date_format, &config.display.time_format);
+    let time = local("2024-05-15 09:05");
+    assert_eq!(time.to_string(), "2024/05/15 09:05");
+    assert_eq!(time.to_local_date(), "2024/05/15");
+    dates::set_display_format("%e %b %Y", "");
+    assert_eq!(time.to_string(), "15 May 2024");
+
+    let config = Config::parse("[display]\nlocale = \"fr_FR.UTF-8\"\n").unwrap();
+    dates::set_display_locale(config.display.locale);
+    let french = (time.format_local("%A %e %B"), time.format("%a %b"));
+    let parsed = (Weekday::parse("mercredi"), dates::parse_month("févr"));
+    dates::set_display_locale(dates::Locale::ENGLISH);
+    assert_eq!(french.0, "mercredi 15 mai");
+    assert_eq!(french.1, "Wed May", "machine-readable output stays English");
+    assert_eq!(parsed, (Some(Weekday::Wednesday), Some(2)));
+    assert!(Config::parse("[display]\nlocale = \"tlh\"\n").is_err());
+
+    for bad in ["%Y-%m-%Q", "%d.%m.%", ""] {
+        let input = format!("[display]\ndate_format = \"{bad}\"\n");
+        assert!(Config::parse(&input).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn durations_parse_in_any_usual_spelling_and_render_back() {
+    for (input, secs) in [
+        ("15", 900),
+        ("15m", 900),
+        ("1h30m", 5400),
+        ("1h 30m", 5400),
+        ("45 min", 2700),
+        ("2 days", 2 * 86_400),
+        ("1.5h", 5400),
+        ("1 hour, 30 minutes", 5400),
+        ("1W2D", 9 * 86_400),
+    ] {
+        assert_eq!(duration::parse(input).unwrap(), secs, "{input}");
+    }
+    for bad in ["", "h", "1h30", "5 fortnights", "-5m", "1..5h"] {
+        assert!(duration::parse(bad).is_err(), "{bad}");
+    }
+    assert_eq!(duration::format(5400), "1h30m");
+    assert_eq!(
+        duration::parse(&duration::format(788_645)).unwrap(),
+        788_645
+    );
+    assert_eq!(duration::humanize(3 * 3600 + 59), "3 hours");
+    assert_eq!(duration::humanize(30), "less than a minute");
+}
+
+#[test]
+fn stored_years_have_four_digits() {
+    cet();
+    for year in ["0000", "0999", "9999"] {
+        let time = Timestamp::parse_rfc3339(&format!("{year}-06-01T12:00:00Z")).unwrap();
+        assert_eq!(time.to_rfc3339(), format!("{year}-06-01T12:00:00Z"));
+        assert_eq!(Timestamp::parse_stored(&time.to_rfc3339()).unwrap(), time);
+        assert!(time.is_storable());
+    }
+    assert!(dates::parse_when("0999-06-01", Timestamp::from_secs(0)).is_ok());
+    // Local midnight of the first day is still in the year before in UTC.
+    assert!(dates::parse_when("0000-01-01 00:30", Timestamp::from_secs(0)).is_err());
+    assert!(dates::parse_when("10000-01-01", Timestamp::from_secs(0)).is_err());
+    assert!(!Timestamp::parse_rfc3339("9999-12-31T23:59:59Z")
+        .unwrap()
+        .add_secs(1)
+        .is_storable());
+}
+
+#[test]
+fn rfc_3339_offsets_and_instants_are_range_checked() {
+    for bad in [
+        "2024-05-15T08:30:00+99:00",
+        "2024-05-15T08:30:00+05:60",
+        "2024-05-15T08:30:00+24:00",
+        "2024-05-15T08:30:00+5:00",
+        "2024-05-15T08:30:00+-5:00",
+        "9999-12-31T23:59:59-05:00",
+        "0000-01-01T00:00:00+01:00",
+    ] {
+        assert!(Timestamp::parse_rfc3339(bad).is_err(), "{bad}");
+    }
+    let last = Timestamp::parse_rfc3339("9999-12-31T23:59:59Z").unwrap();
+    assert_eq!(
+        Timestamp::parse_rfc3339("9999-12-31T23:59:59+23:59").unwrap(),
+        last.add_secs(-(23 * 3600 + 59 * 60))
+    );
+}
@@ -0,0 +1,108 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::path::Path;
+use std::process::Output;
+
+use common::{binary, fake, stdout, TempDir};
+
+use task_manager::json;
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Priority, Task, TasksManager};
+
+/// Stand-in editors: each changes the task's JSON in its own way, and the
+/// first keeps a copy of what it was given.
+const EDITORS: &[(&str, &str)] = &[
+    (
+        "rename",
+        "#!/bin/sh\ncp \"$1\" \"$(dirname \"$0\")/given\"\nsed -i -e 's/\"name\": \"call supplier\"/\"name\": \"call the supplier\"/' -e 's/\"priority\": \"medium\"/\"priority\": \"high\"/' \"$1\"\n",
+    ),
+    (
+        "add-field",
+        "#!/bin/sh\nsed -i 's/\"pinned\": false,/\"pinned\": false,\\n  \"colour\": \"red\",/' \"$1\"\n",
+    ),
+    (
+        "bad-priority",
+        "#!/bin/sh\nsed -i 's/\"priority\": \"high\"/\"priority\": \"urgent\"/' \"$1\"\n",
+    ),
+    (
+        "new-id",
+        "#!/bin/sh\nsed -i 's/\"id\": \"./\"id\": \"0/' \"$1\"\n",
+    ),
+];
+
+fn edit(dir: &Path, editor: &str, args: &[&str]) -> Output {
+    binary(dir)
+        .env("VISUAL", editor)
+        .arg("edit")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn raw_edits_are_checked_before_they_are_saved() {
+    let dir = TempDir::new("edit-raw");
+    for (name, script) in EDITORS {
+        fake(&dir, name, script);
+    }
+    let mut task = Task::new("call supplier");
+    // An id starting with 0 would not change under `new-id`.
+    while task.id.to_hex().starts_with('0') {
+        task = Task::new("call supplier");
+    }
+    let original = task.clone();
+    let id = task.id.to_hex();
+    let store = JsonStore::new(dir.join("tasks.json"));
+    store.save(&TasksManager::from_tasks(vec![task])).unwrap();
+
+    let renamed = stdout(edit(&dir, "rename", &["--raw", &id]));
+    let given = std::fs::read_to_string(dir.join("bin/given")).unwrap();
+    let unchanged = stdout(edit(&dir, "true", &["--raw", &id]));
+    let added = edit(&dir, "add-field", &["--raw", &id]);
+    let bad_priority = edit(&dir, "bad-priority", &["--raw", &id]);
+    let new_id = edit(&dir, "new-id", &["--raw", &id]);
+    let failed_editor = edit(&dir, "false", &["--raw", &id]);
+    let no_mode = edit(&dir, "true", &[&id]);
+    let saved = store.load().unwrap();
+
+    // The editor is given the task alone, as the data file writes it.
+    assert_eq!(json::parse(&given).unwrap(), original.to_json());
+    assert_eq!(renamed, "Updated \"call the supplier\"\n");
+    assert_eq!(unchanged, "Nothing changed.\n");
+
+    assert_eq!(added.status.code(), Some(3));
+    assert!(
+        stderr(&added).contains("unknown field `colour`"),
+        "{}",
+        stderr(&added)
+    );
+    // Errors point at the line of the field at fault.
+    assert!(
+        stderr(&bad_priority).starts_with("error: line 6: unknown priority `urgent`"),
+        "{}",
+        stderr(&bad_priority)
+    );
+    assert!(
+        stderr(&new_id).contains("line 2: the id cannot be changed"),
+        "{}",
+        stderr(&new_id)
+    );
+    assert!(stderr(&failed_editor).contains("the task was not changed"));
+    assert!(stderr(&no_mode).contains("needs --raw"));
+    for failed in [&added, &bad_priority, &new_id, &failed_editor, &no_mode] {
+        assert!(!failed.status.success());
+        assert!(failed.stdout.is_empty());
+    }
+
+    let tasks = saved.tasks();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].id, original.id);
+    assert_eq!(tasks[0].name, "call the supplier");
+    assert_eq!(tasks[0].priority, Priority::High);
+}
@@ -0,0 +1,267 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use common::{binary, stdout, TempDir};
+
+use task_manager::config::Config;
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Task, TasksManager};
+
+fn run(dir: &Path, settings: &[&str], args: &[&str]) -> Output {
+    let mut command = binary(dir);
+    for setting in settings {
+        command.args(["--setting", setting]);
+    }
+    command.args(args).output().unwrap()
+}
+
+#[test]
+fn encryption_settings_parse() {
+    let config = Config::parse("[encryption]\nfields = true\npassphrase = \"s3cret\"\n").unwrap();
+    assert!(config.encryption.fields);
+    assert_eq!(config.encryption.passphrase.as_deref(), Some("s3cret"));
+    assert!(!Config::default().encryption.fields);
+    assert!(Config::parse("[encryption]\npassphrase = \"\"\n").is_err());
+    assert!(Config::parse("[encryption]\nfields = \"yes\"\n").is_err());
+}
+
+#[test]
+fn descriptions_and_notes_are_encrypted_in_the_data_file() {
+    if Command::new("openssl").arg("version").output().is_err() {
+        eprintln!("skipped: openssl is not installed");
+        return;
+    }
+    let dir = TempDir::new("fields");
+    let mut task = Task::new("visit gran");
+    task.description = "door code 4711".into();
+    task.body = "spare key under the pot".into();
+    let id = task.id.to_hex();
+    let store = JsonStore::new(dir.join("tasks.json"));
+    store.save(&TasksManager::from_tasks(vec![task])).unwrap();
+    let keyed = ["encryption.fields=true", "encryption.passphrase=s3cret"];
+
+    // Any save encrypts the fields.
+    stdout(run(&dir, &keyed, &["set", &id, "priority=high"]));
+    let sealed = fs::read_to_string(dir.join("tasks.json")).unwrap();
+    let shown = stdout(run(&dir, &keyed, &["show", &id]));
+    // Without the passphrase the rest stays usable, and unchanged fields
+    // are saved as they were.
+    let listed = stdout(run(&dir, &["encryption.fields=true"], &["list"]));
+    stdout(run(
+        &dir,
+        &["encryption.fields=true"],
+        &["set", &id, "priority=low"],
+    ));
+    let resaved = fs::read_to_string(dir.join("tasks.json")).unwrap();
+    let added = run(
+        &dir,
+        &["encryption.fields=true"],
+        &["add", "--no-prompt", "--name", "x", "--description", "y"],
+    );
+    let wrong = run(
+        &dir,
+        &["encryption.fields=true", "encryption.passphrase=guess"],
+        &["list"],
+    );
+    // Turned off, the next save writes them in plain text again.
+    stdout(run(
+        &dir,
+        &["encryption.passphrase=s3cret"],
+        &["set", &id, "priority=medium"],
+    ));
+    let opened = store.load().unwrap();
+
+    assert!(sealed.contains("visit gran"), "{sealed}");
+    assert!(sealed.contains("enc:v1:"), "{sealed}");
+    assert!(!sealed.contains("4711") && !sealed.contains("spare key"));
+    assert!(shown.contains("door code 4711"), "{shown}");
+    assert!(shown.contains("spare key under the pot"), "{shown}");
+    assert!(listed.contains("visit gran"), "{listed}");
+
+    let values = |file: &str| -> Vec<String> {
+        file.lines()
+            .filter(|line| line.contains("enc:v1:"))
+            .map(String::from)
+            .collect()
+    };
+    assert_eq!(values(&resaved), values(&sealed));
+    assert!(!added.status.success());
+    assert!(String::from_utf8_lossy(&added.stderr).contains("TASK_MANAGER_PASSPHRASE"));
+    assert!(!wrong.status.success());
+    assert!(String::from_utf8_lossy(&wrong.stderr).contains("failed authentication"));
+    assert_eq!(opened.tasks()[0].description, "door code 4711");
+    assert_eq!(opened.tasks()[0].body, "spare key under the pot");
+}
+
+#[test]
+fn no_search_index_is_kept_for_encrypted_fields() {
+    if Command::new("openssl").arg("version").output().is_err() {
+        eprintln!("skipped: openssl is not installed");
+        return;
+    }
+    let dir = TempDir::new("fields-index");
+    let mut task = Task::new("visit gran");
+    task.description = "door code 4711".into();
+    let id = task.id.to_hex();
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(vec![task]))
+        .unwrap();
+    let index = dir.join("tasks.json.index");
+    // An index from before the fields were encrypted holds their words.
+    stdout(run(&dir, &[], &["search", "door"]));
+    let had_index = index.exists();
+    let keyed = ["encryption.fields=true", "encryption.passphrase=s3cret"];
+
+    let found = stdout(run(&dir, &keyed, &["search", "door"]));
+    let removed = !index.exists();
+    stdout(run(&dir, &keyed, &["set", &id, "priority=high"]));
+    let after_save = index.exists();
+    let rebuilt = run(&dir, &keyed, &["index", "rebuild"]);
+    let after_rebuild = index.exists();
+
+    assert!(had_index);
+    assert!(found.contains("visit gran"), "{found}");
+    assert!(removed);
+    assert!(!after_save);
+    assert!(!rebuilt.status.success());
+    assert!(String::from_utf8_lossy(&rebuilt.stderr).contains("encryption.fields"));
+    assert!(!after_rebuild);
+}
+
+#[test]
+fn the_passphrase_can_come_from_the_environment() {
+    if Command::new("openssl").arg("version").output().is_err() {
+        eprintln!("skipped: openssl is not installed");
+        return;
+    }
+    let dir = TempDir::new("fields-env");
+    let with_passphrase = |args: &[&str]| {
+        binary(&dir)
+            .env("TASK_MANAGER_PASSPHRASE", "s3cret")
+            .env("TASK_MANAGER_ENCRYPTION_FIELDS", "true")
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    stdout(with_passphrase(&[
+        "add",
+        "--no-prompt",
+        "--name",
+        "visit gran",
+        "--description",
+        "door code 4711",
+    ]));
+    let sealed = fs::read_to_string(dir.join("tasks.json")).unwrap();
+    let listed = stdout(with_passphrase(&["list"]));
+    let opened = JsonStore::new(dir.join("tasks.json")).load().unwrap();
+    let id = opened.tasks()[0].id.to_hex();
+    let shown = stdout(with_passphrase(&["show", &id]));
+
+    assert!(
+        sealed.contains("enc:v1:") && !sealed.contains("4711"),
+        "{sealed}"
+    );
+    assert!(listed.contains("visit gran"), "{listed}");
+    assert!(shown.contains("door code 4711"), "{shown}");
+}
+
+#[cfg(unix)]
+#[test]
+fn notes_are_edited_in_a_private_file_that_is_removed() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new("fields-edit");
+    let task = Task::new("visit gran");
+    let id = task.id.to_hex();
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(vec![task]))
+        .unwrap();
+    // Notes where the file was, and how it could be read, then a new line.
+    let editor = dir.join("editor");
+    fs::write(
+        &editor,
+        format!(
+            "#!/bin/sh\necho \"$1 $(stat -c %a \"$1\") $(stat -c %a \"$(dirname \"$1\")\")\" > {}\necho 'door code 4711' >> \"$1\"\n",
+            dir.join("seen").display()
+        ),
+    )
+    .unwrap();
+    let failing = dir.join("failing");
+    fs::write(
+        &failing,
+        format!(
+            "#!/bin/sh\necho \"$1\" > {}\nexit 1\n",
+            dir.join("failed").display()
+        ),
+    )
+    .unwrap();
+    for script in [&editor, &failing] {
+        fs::set_permissions(script, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let edit = |editor: &Path| {
+        binary(&dir)
+            .env("VISUAL", editor)
+            .args(["edit", "--body", &id])
+            .output()
+            .unwrap()
+    };
+
+    stdout(edit(&editor));
+    let refused = edit(&failing);
+    let seen = fs::read_to_string(dir.join("seen")).unwrap();
+    let failed = fs::read_to_string(dir.join("failed")).unwrap();
+    let saved = JsonStore::new(dir.join("tasks.json")).load().unwrap();
+
+    let mut seen = seen.split_whitespace();
+    let path = Path::new(seen.next().unwrap());
+    assert_eq!(seen.next(), Some("600"));
+    assert_eq!(seen.next(), Some("700"));
+    assert!(!path.exists() && !path.parent().unwrap().exists());
+    assert!(!refused.status.success());
+    let failed = Path::new(failed.trim());
+    assert_ne!(failed, path);
+    assert!(!failed.exists() && !failed.parent().unwrap().exists());
+    assert_eq!(saved.tasks()[0].body, "door code 4711");
+}
+
+#[test]
+fn a_data_file_keeps_to_one_salt() {
+    if Command::new("openssl").arg("version").output().is_err() {
+        eprintln!("skipped: openssl is not installed");
+        return;
+    }
+    let dir = TempDir::new("fields-salt");
+    let keyed = ["encryption.fields=true", "encryption.passphrase=s3cret"];
+    let add = |name: &str, description: &str| {
+        stdout(run(
+            &dir,
+            &keyed,
+            &[
+                "add",
+                "--no-prompt",
+                "--name",
+                name,
+                "--description",
+                description,
+            ],
+        ))
+    };
+
+    add("visit gran", "door code 4711");
+    add("water plants", "the fern twice");
+    let file = fs::read_to_string(dir.join("tasks.json")).unwrap();
+    let opened = JsonStore::new(dir.join("tasks.json")).load().unwrap();
+
+    // The salt is the first eight bytes after the prefix.
+    let salts: Vec<&str> = file
+        .match_indices("enc:v1:")
+        .map(|(at, _)| &file[at + 7..at + 23])
+        .collect();
+    assert_eq!(salts.len(), 2, "{file}");
+    assert_eq!(salts[0], salts[1]);
+    assert_eq!(opened.len(), 2);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use common::{binary, stdout, TempDir};
+
+use task_manager::json::{self, Value};
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Error, Priority, Status, Task, TasksManager};
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    binary(dir).args(args).output().unwrap()
+}
+
+/// The `kind`, `code` and `message` of an error written as JSON.
+fn reported(output: &Output) -> (String, i64, String) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let value = json::parse(stderr.trim_end()).unwrap();
+    let error = value.get("error").unwrap();
+    (
+        error.get("kind").and_then(Value::as_str).unwrap().into(),
+        error.get("code").and_then(Value::as_i64).unwrap(),
+        error.get("message").and_then(Value::as_str).unwrap().into(),
+    )
+}
+
+#[test]
+fn each_kind_of_error_has_its_own_code() {
+    assert_eq!(Error::NotFound("task".into()).exit_code(), 2);
+    assert_eq!(Error::Invalid("bad".into()).exit_code(), 3);
+    assert_eq!(Error::Io(std::io::ErrorKind::Other.into()).exit_code(), 4);
+    assert_eq!(Error::Conflict("clash".into()).exit_code(), 5);
+    assert_eq!(Error::Conflict("clash".into()).kind(), "conflict");
+}
+
+#[test]
+fn failures_exit_with_their_code_and_leave_the_tasks_alone() {
+    let dir = TempDir::new("errors");
+    let tasks = vec![Task::new("call supplier"), Task::new("order paper")];
+    let ids: Vec<String> = tasks.iter().map(|t| t.id.to_hex()).collect();
+    let store = JsonStore::new(dir.join("tasks.json"));
+    store.save(&TasksManager::from_tasks(tasks)).unwrap();
+    fs::write(dir.join("config.toml"), "[wip]\nin_progress = 1\n").unwrap();
+    // A data file that cannot be read.
+    let broken = TempDir::new("errors-io");
+    fs::create_dir(broken.join("tasks.json")).unwrap();
+
+    let missing = run(&dir, &["show", "zzzz"]);
+    let invalid = run(&dir, &["set", &ids[0], "priority=bogus"]);
+    let unreadable = run(&broken, &["list"]);
+    stdout(run(&dir, &["set", &ids[0], "status=in-progress"]));
+    let over_limit = run(&dir, &["set", &ids[1], "status=in-progress"]);
+    let saved = store.load().unwrap();
+
+    assert_eq!(missing.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8_lossy(&missing.stderr),
+        "error: task `zzzz` not found\n"
+    );
+    assert_eq!(invalid.status.code(), Some(3));
+    assert_eq!(unreadable.status.code(), Some(4));
+    assert_eq!(over_limit.status.code(), Some(5));
+    assert!(String::from_utf8_lossy(&over_limit.stderr).contains("WIP limit reached"));
+    assert!(missing.stdout.is_empty() && over_limit.stdout.is_empty());
+    // Only the change that succeeded was saved.
+    let statuses: Vec<Status> = saved.tasks().iter().map(|t| t.status).collect();
+    assert_eq!(statuses, [Status::InProgress, Status::Todo]);
+    assert_eq!(saved.tasks()[0].priority, Priority::Medium);
+}
+
+#[test]
+fn errors_can_be_written_as_json() {
+    let dir = TempDir::new("errors-json");
+    let task = Task::new("call supplier");
+    let id = task.id.to_hex();
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(vec![task]))
+        .unwrap();
+
+    let missing = run(&dir, &["--error-format", "json", "show", "zzzz"]);
+    let invalid = run(
+        &dir,
+        &["--error-format", "json", "set", &id, "priority=bogus"],
+    );
+    let text = run(&dir, &["--error-format", "text", "show", "zzzz"]);
+    let unknown = run(&dir, &["--error-format", "yaml", "list"]);
+
+    assert_eq!(missing.status.code(), Some(2));
+    assert_eq!(
+        reported(&missing),
+        ("not_found".into(), 2, "task `zzzz` not found".into())
+    );
+    assert_eq!(invalid.status.code(), Some(3));
+    let (kind, code, message) = reported(&invalid);
+    assert_eq!((kind.as_str(), code), ("invalid", 3));
+    assert!(message.contains("unknown priority `bogus`"), "{message}");
+    assert_eq!(
+        String::from_utf8_lossy(&text.stderr),
+        "error: task `zzzz` not found\n"
+    );
+    assert_eq!(unknown.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&unknown.stderr).contains("unknown error format `yaml`"));
+}
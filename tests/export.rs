@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Status, Task, TasksManager};
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_task-manager"))
+        .env_clear()
+        .arg("--file")
+        .arg(dir.join("tasks.json"))
+        .arg("--config")
+        .arg(dir.join("config.toml"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn export_writes_only_tasks_matching_the_filter() {
+    let dir = std::env::temp_dir().join(format!("task-manager-export-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let mut filed = Task::new("file report");
+    filed.tags = vec!["work".into()];
+    filed.set_status(Status::Done);
+    let mut meeting = Task::new("plan meeting");
+    meeting.tags = vec!["work".into()];
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(vec![
+            filed,
+            meeting,
+            Task::new("water plants"),
+        ]))
+        .unwrap();
+
+    let out = dir.join("work.json");
+    let out_path = out.to_str().unwrap();
+    let exported = run(
+        &dir,
+        &["export", "--json", out_path, "--filter", "tag:work"],
+    );
+    let work = JsonStore::new(&out).load();
+    let csv = dir.join("open.csv");
+    let csv_path = csv.to_str().unwrap();
+    let anonymized = run(
+        &dir,
+        &[
+            "export",
+            "--csv",
+            csv_path,
+            "--filter",
+            "tag:work and status:todo",
+            "--anonymize",
+        ],
+    );
+    let rows = fs::read_to_string(&csv);
+    let bad = dir.join("bad.json");
+    let typo = run(
+        &dir,
+        &[
+            "export",
+            "--json",
+            bad.to_str().unwrap(),
+            "--filter",
+            "tag:",
+        ],
+    );
+    let bad_exists = bad.exists();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(exported.status.success());
+    // Finished tasks are exported unless the filter leaves them out.
+    let names: Vec<String> = work
+        .unwrap()
+        .tasks()
+        .iter()
+        .map(|t| t.name.clone())
+        .collect();
+    assert_eq!(names, ["file report", "plan meeting"]);
+    assert!(anonymized.status.success());
+    let rows = rows.unwrap();
+    assert_eq!(rows.lines().count(), 2, "{rows}");
+    assert!(
+        !rows.contains("plan meeting") && rows.contains("task-"),
+        "{rows}"
+    );
+    // A bad expression is reported before the file is created.
+    assert!(!typo.status.success());
+    assert!(!bad_exists);
+}
@@ -0,0 +1,165 @@
+use task_manager::dates::{DateTime, Timestamp};
+use task_manager::filter::{self, Comparison, Filter};
+use task_manager::{Priority, Status, Task};
+
+/// Wednesday 2024-06-12 12:00 UTC.
+fn now() -> Timestamp {
+    DateTime {
+        year: 2024,
+        month: 6,
+        day: 12,
+        hour: 12,
+        minute: 0,
+        second: 0,
+    }
+    .to_timestamp()
+    .unwrap()
+}
+
+fn day(d: u32) -> Timestamp {
+    DateTime::date(2024, 6, d).to_timestamp().unwrap()
+}
+
+fn task(name: &str, priority: Priority, due: Option<u32>, tags: &[&str]) -> Task {
+    let mut task = Task::new(name);
+    task.priority = priority;
+    task.due = due.map(day);
+    task.tags = tags.iter().map(|t| t.to_string()).collect();
+    task
+}
+
+fn names(expr: &str, tasks: &[Task]) -> Vec<String> {
+    let filter = filter::parse(expr, now()).unwrap();
+    filter
+        .apply(tasks, now())
+        .into_iter()
+        .map(|t| t.name.clone())
+        .collect()
+}
+
+fn sample() -> Vec<Task> {
+    let mut done = task("file taxes", Priority::Critical, Some(10), &["home"]);
+    done.set_status(Status::Done);
+    let mut report = task("write report", Priority::High, Some(13), &["work"]);
+    report.project = Some("Q3 review".into());
+    vec![
+        report,
+        task("plan offsite", Priority::Medium, Some(20), &["work"]),
+        task("fix bike", Priority::Low, None, &["home"]),
+        task("call bank", Priority::High, Some(11), &["errand"]),
+        done,
+    ]
+}
+
+#[test]
+fn combines_comparisons_and_tags() {
+    let tasks = sample();
+    assert_eq!(
+        names("priority>=high and due<friday and tag:work", &tasks),
+        ["write report"]
+    );
+}
+
+#[test]
+fn due_compares_calendar_days() {
+    let tasks = sample();
+    assert_eq!(
+        names("due<=2024-06-13 and status:open", &tasks),
+        ["write report", "call bank"]
+    );
+    assert_eq!(names("due=today", &tasks), Vec::<String>::new());
+    assert_eq!(names("due:2024-06-20", &tasks), ["plan offsite"]);
+}
+
+#[test]
+fn periods_compare_as_ranges_of_days() {
+    let tasks = sample();
+    // The week of Monday 10 to Sunday 16 June.
+    assert_eq!(
+        names("due:this-week", &tasks),
+        ["write report", "call bank", "file taxes"]
+    );
+    assert_eq!(names("due!=this-week", &tasks), ["plan offsite"]);
+    assert_eq!(names("due>this-week", &tasks), ["plan offsite"]);
+    assert_eq!(names("due:next-week", &tasks), ["plan offsite"]);
+    assert_eq!(names("due<last-week", &tasks), Vec::<String>::new());
+    assert_eq!(
+        names("due:this-month and priority>=high", &tasks),
+        ["write report", "call bank", "file taxes"]
+    );
+}
+
+#[test]
+fn private_tasks_match_is_private() {
+    let mut tasks = sample();
+    tasks[2].private = true;
+    assert_eq!(names("is:private", &tasks), ["fix bike"]);
+    assert_eq!(names("not is:private and tag:home", &tasks), ["file taxes"]);
+}
+
+#[test]
+fn boolean_operators_and_parentheses() {
+    let tasks = sample();
+    assert_eq!(
+        names("(tag:home or tag:errand) and not status:done", &tasks),
+        ["fix bike", "call bank"]
+    );
+    assert_eq!(names("!tag:work && priority<high", &tasks), ["fix bike"]);
+    assert_eq!(
+        names("tag:work || tag:errand priority=high", &tasks).len(),
+        3
+    );
+}
+
+#[test]
+fn bare_words_and_quotes_search_text() {
+    let tasks = sample();
+    assert_eq!(names("report", &tasks), ["write report"]);
+    assert_eq!(names("project:\"q3 review\"", &tasks), ["write report"]);
+    assert_eq!(names("is:overdue", &tasks), ["call bank"]);
+}
+
+#[test]
+fn builder_matches_parsed_expression() {
+    let built = Filter::priority(Comparison::Ge, Priority::High).and(Filter::tag("work"));
+    assert_eq!(
+        filter::parse("priority>=high tag:work", now()).unwrap(),
+        built
+    );
+    assert!(!built.mentions_status());
+    assert!(filter::parse("status:closed", now())
+        .unwrap()
+        .mentions_status());
+}
+
+#[test]
+fn empty_expression_matches_everything() {
+    assert_eq!(filter::parse("  ", now()).unwrap(), Filter::Any);
+}
+
+fn error(expr: &str) -> String {
+    filter::parse(expr, now()).unwrap_err().to_string()
+}
+
+#[test]
+fn errors_point_at_the_problem() {
+    let message = error("priority>= and tag:work");
+    assert!(
+        message.contains("expected a value after `priority>=`"),
+        "{message}"
+    );
+    assert!(message.contains("column 12"), "{message}");
+    assert!(
+        message.ends_with("\n  priority>= and tag:work\n             ^"),
+        "{message}"
+    );
+
+    assert!(error("priority>=urgent").contains("unknown priority `urgent`"));
+    assert!(error("colour:red").contains("unknown field `colour`"));
+    assert!(error("(tag:work or tag:home").contains("`(` is never closed"));
+    assert!(error("tag:work)").contains("unbalanced `)`"));
+    assert!(error("tag<work").contains("only supports"));
+    assert!(error("due<someday").contains("unrecognized date"));
+    assert!(error("tag:work and").contains("expression ends too early"));
+    assert!(error("name:\"oops").contains("unterminated quote"));
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use common::{binary, stdout, TempDir};
+
+use task_manager::storage::{JsonStore, Store};
+use task_manager::Priority;
+
+/// Runs the binary with a git identity and no git config but the repo's.
+fn run(dir: &Path, args: &[&str]) -> Output {
+    binary(dir)
+        .env("HOME", dir)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_AUTHOR_NAME", "Ana")
+        .env("GIT_AUTHOR_EMAIL", "ana@example.com")
+        .env("GIT_COMMITTER_NAME", "Ana")
+        .env("GIT_COMMITTER_EMAIL", "ana@example.com")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Runs git in `dir` and returns what it printed.
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .env("HOME", dir)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn every_change_is_a_commit_that_can_be_rolled_back() {
+    if Command::new("git").arg("--version").output().is_err() {
+        eprintln!("skipped: git is not installed");
+        return;
+    }
+    let dir = TempDir::new("git");
+    fs::write(dir.join("config.toml"), "[git]\nauto_commit = true\n").unwrap();
+    let load = || JsonStore::new(dir.join("tasks.json")).load().unwrap();
+
+    let missing = run(&dir, &["history"]);
+    stdout(run(
+        &dir,
+        &["add", "--no-prompt", "--name", "call supplier"],
+    ));
+    stdout(run(&dir, &["add", "--no-prompt", "--name", "order paper"]));
+    let ids: Vec<String> = load().tasks().iter().map(|t| t.id.to_hex()).collect();
+    stdout(run(&dir, &["set", &ids[0], "priority=high"]));
+    stdout(run(&dir, &["remove", "--force", &ids[1]]));
+    // Reading commits nothing.
+    stdout(run(&dir, &["list"]));
+    let history = stdout(run(&dir, &["history"]));
+    let last = git(&dir, &["log", "-1", "--format=%B"]);
+    let tracked = git(&dir, &["ls-files"]);
+    let before_update = git(&dir, &["rev-parse", "--short", "HEAD~2"]);
+    let before_update = before_update.trim();
+    let rolled_back = stdout(run(&dir, &["rollback", "--force", before_update]));
+    let restored = load();
+    let subjects = git(&dir, &["log", "--format=%s"]);
+    let unknown = run(&dir, &["rollback", "--force", "0000000"]);
+
+    assert_eq!(missing.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("not in a git repository"));
+
+    let lines: Vec<&str> = history.lines().collect();
+    assert_eq!(lines.len(), 4, "{history}");
+    assert!(lines[0].ends_with("  Remove \"order paper\""), "{history}");
+    assert!(
+        lines[1].ends_with("  Update \"call supplier\""),
+        "{history}"
+    );
+    assert!(lines[3].ends_with("  Add \"call supplier\""), "{history}");
+    assert_eq!(
+        last,
+        format!(
+            "Remove \"order paper\"\n\ntask-manager remove --force {}\n\n",
+            ids[1]
+        )
+    );
+    // The config beside the data is left out of the repository.
+    assert_eq!(tracked, "tasks.json\n");
+
+    assert!(
+        rolled_back.starts_with(&format!(
+            "Rolled back to {before_update} Add \"order paper\""
+        )),
+        "{rolled_back}"
+    );
+    assert!(rolled_back.ends_with("; 2 tasks now\n"), "{rolled_back}");
+    let tasks = restored.tasks();
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].priority, Priority::Medium);
+    assert_eq!(
+        subjects.lines().next(),
+        Some(format!("Roll back to {before_update} Add \"order paper\"").as_str())
+    );
+    assert_eq!(subjects.lines().count(), 5);
+    assert_eq!(unknown.status.code(), Some(2));
+}
+
+#[test]
+fn commits_are_pushed_when_asked() {
+    if Command::new("git").arg("--version").output().is_err() {
+        eprintln!("skipped: git is not installed");
+        return;
+    }
+    let dir = TempDir::new("git-push");
+    let data = dir.join("data");
+    let remote = dir.join("remote.git");
+    fs::create_dir(&data).unwrap();
+    git(&dir, &["init", "-q", "--bare", remote.to_str().unwrap()]);
+    git(&data, &["init", "-q"]);
+    git(
+        &data,
+        &["remote", "add", "origin", remote.to_str().unwrap()],
+    );
+    fs::write(
+        data.join("config.toml"),
+        "[git]\nauto_commit = true\nauto_push = true\nremote = \"origin\"\n",
+    )
+    .unwrap();
+
+    stdout(run(
+        &data,
+        &["add", "--no-prompt", "--name", "call supplier"],
+    ));
+    let pushed = git(&remote, &["log", "--all", "--format=%s"]);
+
+    assert_eq!(pushed, "Add \"call supplier\"\n");
+}
@@ -0,0 +1,190 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Output;
+
+use common::{binary, fake, stdout, TempDir};
+
+use task_manager::config::Config;
+use task_manager::json::{self, Value};
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Status, Task, TasksManager};
+
+/// Stands in for curl: logs each call and its config, and answers as
+/// Google's OAuth server, which makes the user take one poll to approve,
+/// and as an account with the lists Home (three tasks over two pages, one
+/// deleted) and Work (empty).
+const FAKE_CURL: &str = r#"#!/bin/sh
+dir=$(dirname "$0")
+for arg; do url=$arg; done
+config=$(cat)
+echo "$*" >> "$dir/calls"
+echo "$config" >> "$dir/config"
+case "$url" in
+  */device/code)
+    echo '{"device_code":"dev1","user_code":"ABCD-EFGH","verification_url":"https://www.google.com/device","interval":0,"expires_in":1800}' ;;
+  */token)
+    case "$config" in
+      *refresh_token*) echo '{"access_token":"ya29.fresh","expires_in":3599}' ;;
+      *)
+        if [ -e "$dir/polled" ]; then
+          echo '{"access_token":"ya29.t0ken","expires_in":3599,"refresh_token":"1//refresh"}'
+        else
+          touch "$dir/polled"
+          echo '{"error":"authorization_pending"}'
+        fi ;;
+    esac ;;
+  */users/@me/lists\?*)
+    echo '{"items":[{"id":"L1","title":"Home"},{"id":"L2","title":"Work"}]}' ;;
+  */lists/L1/tasks\?*pageToken=p2)
+    echo '{"items":[{"id":"g3","title":"Send invites","status":"needsAction","updated":"2024-01-01T09:00:00.000Z"},{"id":"g4","title":"Old idea","deleted":true,"updated":"2024-01-01T09:00:00.000Z"}]}' ;;
+  */lists/L1/tasks\?*)
+    printf '%s\n' '{"items":[{"id":"g1","title":"Buy milk","notes":"Semi-skimmed","status":"needsAction","due":"2024-06-03T00:00:00.000Z","updated":"2024-06-01T10:00:00.000Z"},{"id":"g2","title":"Print flyers","notes":"200 copies\nA5, glossy","status":"completed","updated":"2030-01-01T10:00:00.000Z"}],"nextPageToken":"p2"}' ;;
+  */lists/L2/tasks\?*) echo '{}' ;;
+  */lists/L2/tasks) echo '{"id":"new1"}' ;;
+  */lists/L1/tasks/g3) echo '{}' ;;
+  *) echo "curl: (22) The requested URL returned error: 404" >&2; exit 22 ;;
+esac
+"#;
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    binary(dir)
+        .env("GOOGLE_CLIENT_SECRET", "s3cret")
+        .args([
+            "--setting",
+            "google.client_id=123.apps.googleusercontent.com",
+        ])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn linked(name: &str, link: &str, status: Status) -> Task {
+    let mut task = Task::new(name);
+    task.google = Some(link.into());
+    task.set_status(status);
+    task
+}
+
+#[test]
+fn google_settings_parse() {
+    let config =
+        Config::parse("[google]\nclient_id = \"123.apps.googleusercontent.com\"\n").unwrap();
+    assert_eq!(
+        config.google.client_id.as_deref(),
+        Some("123.apps.googleusercontent.com")
+    );
+    assert_eq!(config.google.client_secret, None);
+    assert!(Config::parse("[google]\nlist = \"Home\"\n").is_err());
+}
+
+#[test]
+fn sync_signs_in_and_keeps_both_sides_in_step() {
+    let dir = TempDir::new("google");
+    fake(&dir, "curl", FAKE_CURL);
+    let store = JsonStore::new(dir.join("tasks.json"));
+    let mut report = Task::new("Write report");
+    report.project = Some("work".into());
+    store
+        .save(&TasksManager::from_tasks(vec![
+            linked("Print flyers", "L1/g2", Status::Todo),
+            linked("Send invites", "L1/g3", Status::Done),
+            report,
+        ]))
+        .unwrap();
+
+    let preview = stdout(run(&dir, &["sync", "google", "--dry-run"]));
+    let untouched = store.load().unwrap();
+    let previewed_calls = fs::read_to_string(dir.join("bin/calls")).unwrap();
+    let synced = stdout(run(&dir, &["sync", "google"]));
+    let again = stdout(run(&dir, &["sync", "google"]));
+    let saved = store.load().unwrap();
+    let token_path = dir.join("google-token.json");
+    let token_mode = fs::metadata(&token_path).unwrap().permissions().mode();
+    let token = json::parse(&fs::read_to_string(&token_path).unwrap()).unwrap();
+    // Once the access token has expired, the refresh token gets another.
+    fs::write(
+        &token_path,
+        r#"{"access_token":"ya29.t0ken","expires_at":"2000-01-01T00:00:00Z","refresh_token":"1//refresh"}"#,
+    )
+    .unwrap();
+    stdout(run(&dir, &["sync", "google", "--dry-run"]));
+    let refreshed = json::parse(&fs::read_to_string(&token_path).unwrap()).unwrap();
+    let calls = fs::read_to_string(dir.join("bin/calls")).unwrap();
+    let config = fs::read_to_string(dir.join("bin/config")).unwrap();
+
+    assert!(
+        preview.contains("visit https://www.google.com/device and enter ABCD-EFGH"),
+        "{preview}"
+    );
+    assert!(preview.contains("+ Buy milk\n"), "{preview}");
+    assert!(
+        preview.ends_with("Would sync: 1 task(s) added here, 1 updated here, 1 added to Google Tasks, 1 updated there, of 3 in 2 list(s).\n"),
+        "{preview}"
+    );
+    assert_eq!(untouched.len(), 3);
+    assert!(!previewed_calls.contains("PATCH"), "{previewed_calls}");
+    assert!(
+        !previewed_calls.contains("POST https://tasks"),
+        "{previewed_calls}"
+    );
+
+    assert!(!synced.contains("visit"), "{synced}");
+    assert!(synced.contains("Print flyers: updated here from Google Tasks"));
+    assert!(synced.contains("Send invites: updated in Google Tasks"));
+    assert!(synced.contains("Write report: added it to Work in Google Tasks"));
+    // Everything is linked now; only the fake's unsaved PATCH is repeated.
+    assert!(
+        again.contains("0 task(s) added here, 0 updated here, 0 added to Google Tasks"),
+        "{again}"
+    );
+
+    let tasks = saved.tasks();
+    assert_eq!(tasks[0].status, Status::Done);
+    // Notes over several lines are kept in the body.
+    assert_eq!(tasks[0].description, "");
+    assert_eq!(tasks[0].body, "200 copies\nA5, glossy");
+    assert_eq!(tasks[2].google.as_deref(), Some("L2/new1"));
+    let added = &tasks[3];
+    assert_eq!(added.name, "Buy milk");
+    assert_eq!(added.google.as_deref(), Some("L1/g1"));
+    assert_eq!(added.project.as_deref(), Some("Home"));
+    assert_eq!(added.description, "Semi-skimmed");
+    assert_eq!(added.due.unwrap().format("%Y-%m-%d"), "2024-06-03");
+    assert_eq!(saved.len(), 4);
+
+    assert_eq!(token_mode & 0o777, 0o600);
+    assert_eq!(
+        token.get("access_token").and_then(Value::as_str),
+        Some("ya29.t0ken")
+    );
+    assert_eq!(
+        refreshed.get("access_token").and_then(Value::as_str),
+        Some("ya29.fresh")
+    );
+    assert_eq!(
+        refreshed.get("refresh_token").and_then(Value::as_str),
+        Some("1//refresh")
+    );
+    assert_eq!(calls.matches("/device/code").count(), 1, "{calls}");
+    assert!(config.contains("data-urlencode = \"grant_type=refresh_token\""));
+    assert!(config.contains("header = \"Authorization: Bearer ya29.fresh\""));
+
+    // The pushed task is written whole, and secrets stay off the command
+    // line.
+    assert!(
+        calls.contains("--request PATCH https://tasks.googleapis.com/tasks/v1/lists/L1/tasks/g3")
+    );
+    assert!(config.contains(
+        r#"data = "{\"title\":\"Send invites\",\"notes\":\"\",\"status\":\"completed\",\"due\":null}""#
+    ), "{config}");
+    assert!(
+        !calls.contains("s3cret") && !calls.contains("t0ken"),
+        "{calls}"
+    );
+    assert!(config.contains("data-urlencode = \"client_secret=s3cret\""));
+}
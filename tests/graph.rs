@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+
+use task_manager::config::Glyphs;
+use task_manager::export::graph::{self, GraphFormat};
+use task_manager::{Error, Priority, Status, Task, TaskId, TasksManager};
+
+fn node(task: &Task) -> String {
+    format!("t{}", &task.id.to_hex()[..12])
+}
+
+/// design <- build <- ship, with a shortcut design <- ship, a finished
+/// prerequisite of build, a subtask of ship and a related task.
+fn project() -> Vec<Task> {
+    let design = Task::new("design");
+    let mut research = Task::new("research \"users\"");
+    research.set_status(Status::Done);
+    let mut build = Task::new("build");
+    build.depends_on = vec![design.id, research.id];
+    build.priority = Priority::Critical;
+    let mut ship = Task::new("ship");
+    ship.depends_on = vec![build.id, design.id];
+    let mut notes = Task::new("release notes");
+    notes.parent = Some(ship.id);
+    notes.related = vec![design.id];
+    vec![design, research, build, ship, notes]
+}
+
+fn refs(tasks: &[Task]) -> Vec<&Task> {
+    tasks.iter().collect()
+}
+
+fn ids(tasks: &[Task], a: usize, b: usize) -> (TaskId, TaskId) {
+    (tasks[a].id, tasks[b].id)
+}
+
+#[test]
+fn critical_path_is_the_longest_chain_of_open_dependencies() {
+    let tasks = project();
+    let path = graph::critical_path(&refs(&tasks));
+    // The finished research and the shortcut are off the path.
+    assert_eq!(path, HashSet::from([ids(&tasks, 0, 2), ids(&tasks, 2, 3)]));
+    assert!(graph::critical_path(&[&tasks[0]]).is_empty());
+}
+
+#[test]
+fn dot_draws_nodes_by_status_and_edges_by_kind() {
+    let tasks = project();
+    let dot = graph::render(&refs(&tasks), GraphFormat::Dot, &Glyphs::ascii());
+    let [design, research, build, ship, notes] = [0, 1, 2, 3, 4].map(|i| node(&tasks[i]));
+    assert!(dot.starts_with("digraph tasks {\n"), "{dot}");
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains("label=\"research \\\"users\\\"\\n"), "{dot}");
+    assert!(
+        dot.contains("fillcolor=\"#dafbe1\""),
+        "finished research: {dot}"
+    );
+    assert!(dot.contains(&format!(
+        "  {design} -> {build} [color=\"#cf222e\", penwidth=3];\n"
+    )));
+    assert!(dot.contains(&format!(
+        "  {build} -> {ship} [color=\"#cf222e\", penwidth=3];\n"
+    )));
+    assert!(dot.contains(&format!("  {research} -> {build};\n")));
+    assert!(dot.contains(&format!("  {design} -> {ship};\n")));
+    assert!(dot.contains(&format!(
+        "  {ship} -> {notes} [style=dashed, arrowhead=none];\n"
+    )));
+    assert!(dot.contains(&format!(
+        "  {notes} -> {design} [style=dotted, dir=none];\n"
+    )));
+}
+
+#[test]
+fn mermaid_styles_the_critical_links() {
+    let tasks = project();
+    let chart = graph::render(&refs(&tasks), GraphFormat::Mermaid, &Glyphs::ascii());
+    let [design, research, build, ship, notes] = [0, 1, 2, 3, 4].map(|i| node(&tasks[i]));
+    let links: Vec<&str> = chart
+        .lines()
+        .filter(|l| l.contains("-->") || l.contains("-.-") || l.contains("~~~"))
+        .collect();
+    assert_eq!(
+        links,
+        [
+            format!("  {design} --> {build}"),
+            format!("  {research} --> {build}"),
+            format!("  {build} --> {ship}"),
+            format!("  {design} --> {ship}"),
+            format!("  {ship} -.- {notes}"),
+            format!("  {notes} ~~~ {design}"),
+        ]
+    );
+    assert!(chart.contains("research #quot;users#quot;<br/>"), "{chart}");
+    assert!(
+        chart.contains("  linkStyle 0,2 stroke:#cf222e,stroke-width:3px\n"),
+        "{chart}"
+    );
+    assert!(chart.contains(&format!(
+        "  style {build} fill:#f6f8fa,stroke:#cf222e,stroke-width:3px\n"
+    )));
+}
+
+#[test]
+fn cycles_do_not_hang_the_critical_path() {
+    let mut a = Task::new("a");
+    let mut b = Task::new("b");
+    let mut c = Task::new("c");
+    a.depends_on = vec![c.id];
+    b.depends_on = vec![a.id];
+    c.depends_on = vec![b.id];
+    let tasks = vec![a, b, c];
+    let path = graph::critical_path(&refs(&tasks));
+    // Some chain around the loop is picked, but never the closing edge.
+    assert_eq!(path.len(), 2, "{path:?}");
+    let dot = graph::dot(&refs(&tasks), &Glyphs::ascii());
+    assert_eq!(dot.matches(" -> ").count(), 3);
+}
+
+#[test]
+fn links_must_exist_and_not_loop() {
+    let mut manager = TasksManager::from_tasks(project());
+    let [design, build, ship, notes] = [0, 2, 3, 4].map(|i| manager.tasks()[i].id);
+    for id in [design, build, ship, notes] {
+        assert!(manager.check_links(id).is_ok());
+    }
+
+    // A dependency back onto a dependent closes a cycle.
+    manager.get_mut(design).unwrap().depends_on.push(ship);
+    let err = manager.check_links(design).unwrap_err();
+    assert!(matches!(err, Error::Conflict(_)), "{err}");
+    assert!(err.to_string().contains("would depend on itself"));
+    manager.get_mut(design).unwrap().depends_on.clear();
+
+    // So does making a task the subtask of its own subtask.
+    manager.get_mut(ship).unwrap().parent = Some(notes);
+    let err = manager.check_links(ship).unwrap_err();
+    assert!(
+        err.to_string().contains("subtask of its own subtask"),
+        "{err}"
+    );
+    manager.get_mut(ship).unwrap().parent = None;
+
+    manager.get_mut(notes).unwrap().related.push(notes);
+    assert!(matches!(
+        manager.check_links(notes).unwrap_err(),
+        Error::Invalid(_)
+    ));
+    let missing = TaskId::generate();
+    manager.get_mut(notes).unwrap().related = vec![missing];
+    assert!(matches!(
+        manager.check_links(notes).unwrap_err(),
+        Error::NotFound(_)
+    ));
+    assert!(manager.check_links(missing).is_err());
+}
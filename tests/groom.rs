@@ -0,0 +1,60 @@
+use task_manager::config::{Config, GroomAction};
+use task_manager::dates::{Timestamp, SECONDS_PER_DAY};
+use task_manager::{Priority, Status, Task, TasksManager};
+
+fn task(name: &str, priority: Priority, days_ago: i64, now: Timestamp) -> Task {
+    let mut task = Task::new(name);
+    task.priority = priority;
+    task.updated_at = now.add_secs(-days_ago * SECONDS_PER_DAY);
+    task
+}
+
+#[test]
+fn only_untouched_low_priority_tasks_are_stale() {
+    let now = Timestamp::from_secs(1_700_000_000);
+    let mut pinned = task("Pinned", Priority::Low, 90, now);
+    pinned.pinned = true;
+    let mut done = task("Done", Priority::Low, 90, now);
+    done.status = Status::Done;
+    let mut archived = task("Archived", Priority::Low, 90, now);
+    archived.archived = true;
+    let manager = TasksManager::from_tasks(vec![
+        task("Recent", Priority::Low, 10, now),
+        task("Oldest", Priority::Low, 100, now),
+        task("Important", Priority::High, 100, now),
+        task("Old", Priority::Low, 50, now),
+        pinned,
+        done,
+        archived,
+    ]);
+    let names: Vec<&str> = manager
+        .stale(42 * SECONDS_PER_DAY, now)
+        .iter()
+        .map(|t| t.name.as_str())
+        .collect();
+    assert_eq!(names, ["Oldest", "Old"]);
+}
+
+#[test]
+fn changed_tasks_are_touched() {
+    let now = Timestamp::from_secs(1_700_000_000);
+    let mut manager = TasksManager::from_tasks(vec![
+        task("Edited", Priority::Low, 30, now),
+        task("Untouched", Priority::Low, 30, now),
+    ]);
+    let before = manager.tasks().to_vec();
+    let id = before[0].id;
+    manager.get_mut(id).unwrap().description = "more detail".into();
+    assert_eq!(manager.touch_changed(&before, now), 1);
+    assert_eq!(manager.get(id).unwrap().updated_at, now);
+    assert!(manager.tasks()[1].updated_at < now);
+}
+
+#[test]
+fn groom_policy_is_configured() {
+    assert_eq!(Config::default().groom.after, None);
+    let config = Config::parse("[groom]\nafter = \"6w\"\naction = \"trash\"\n").unwrap();
+    assert_eq!(config.groom.after, Some(42 * SECONDS_PER_DAY));
+    assert_eq!(config.groom.action, GroomAction::Trash);
+    assert!(Config::parse("[groom]\naction = \"shred\"\n").is_err());
+}
@@ -0,0 +1,43 @@
+use task_manager::dates::{DateTime, Timestamp};
+use task_manager::filter;
+use task_manager::update::{self, Update};
+use task_manager::TasksManager;
+
+/// Wednesday 2024-06-12 12:00 UTC.
+fn now() -> Timestamp {
+    DateTime {
+        year: 2024,
+        month: 6,
+        day: 12,
+        hour: 12,
+        minute: 0,
+        second: 0,
+    }
+    .to_timestamp()
+    .unwrap()
+}
+
+#[test]
+fn documented_fields_are_the_ones_parsed() {
+    for (field, _) in filter::FIELDS {
+        let message = filter::parse(&format!("{field}:x"), now())
+            .err()
+            .map(|e| e.to_string());
+        assert!(
+            !message.is_some_and(|m| m.contains("unknown field")),
+            "{field}"
+        );
+    }
+    let manager = TasksManager::new();
+    for (field, _) in update::FIELDS {
+        let message = Update::parse(&format!("{field}=x"), now(), &manager)
+            .err()
+            .map(|e| e.to_string());
+        assert!(
+            !message.is_some_and(|m| m.contains("unknown field")),
+            "{field}"
+        );
+    }
+    let err = filter::parse("colour:red", now()).unwrap_err();
+    assert!(err.to_string().contains("expected priority, due,"), "{err}");
+}
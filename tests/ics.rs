@@ -0,0 +1,70 @@
+use task_manager::dates::Timestamp;
+use task_manager::export::ics;
+use task_manager::Task;
+
+const DUE: i64 = 1_718_200_800; // 2024-06-12 14:00 UTC
+
+fn todo(start: &str, alarms: &[&str]) -> String {
+    let mut out = String::from("BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:a\r\nSUMMARY:call\r\n");
+    out.push_str("DUE:20240612T140000Z\r\n");
+    out.push_str(start);
+    for trigger in alarms {
+        out.push_str(&format!(
+            "BEGIN:VALARM\r\nACTION:DISPLAY\r\n{trigger}\r\nEND:VALARM\r\n"
+        ));
+    }
+    out.push_str("END:VTODO\r\nEND:VCALENDAR\r\n");
+    out
+}
+
+fn reminders(input: &str) -> Vec<i64> {
+    ics::parse(input).unwrap().remove(0).reminders
+}
+
+#[test]
+fn reminders_survive_a_round_trip() {
+    let mut task = Task::new("dentist");
+    task.due = Some(Timestamp::from_secs(DUE));
+    task.reminders = vec![15 * 60, 2 * 3600, 86_400 + 1800];
+    let text = ics::render(&[&task], Timestamp::from_secs(DUE));
+    assert_eq!(text.matches("BEGIN:VALARM").count(), 3);
+    assert!(text.contains("TRIGGER;RELATED=END:-PT15M\r\n"), "{text}");
+    let back = ics::parse(&text).unwrap();
+    assert_eq!(back[0].reminders, task.reminders);
+
+    // Without a due date there is nothing for an alarm to be relative to.
+    task.due = None;
+    let text = ics::render(&[&task], Timestamp::from_secs(DUE));
+    assert!(!text.contains("VALARM"));
+}
+
+#[test]
+fn triggers_relative_to_the_start_end_or_absolute() {
+    let start = "DTSTART:20240612T100000Z\r\n";
+    assert_eq!(
+        reminders(&todo(start, &["TRIGGER;RELATED=END:-PT30M"])),
+        [1800]
+    );
+    // Start-relative is the default: 10:00 minus an hour is five hours
+    // before the 14:00 due date.
+    assert_eq!(reminders(&todo(start, &["TRIGGER:-PT1H"])), [5 * 3600]);
+    assert_eq!(
+        reminders(&todo(start, &["TRIGGER;RELATED=START:PT2H"])),
+        [2 * 3600]
+    );
+    assert_eq!(
+        reminders(&todo("", &["TRIGGER;VALUE=DATE-TIME:20240611T140000Z"])),
+        [86_400]
+    );
+}
+
+#[test]
+fn alarms_without_what_they_are_relative_to_are_dropped() {
+    let alarms = [
+        "TRIGGER:-PT1H",
+        "TRIGGER;RELATED=START:-PT1H",
+        "TRIGGER;RELATED=ELSEWHERE:-PT1H",
+        "TRIGGER;RELATED=END:-PT10M",
+    ];
+    assert_eq!(reminders(&todo("", &alarms)), [600]);
+}
@@ -0,0 +1,107 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use common::{binary, fake, stdout, TempDir};
+
+use task_manager::config::Config;
+use task_manager::storage::{JsonStore, Store};
+
+/// Stands in for curl: logs each IMAP command and the config, and answers
+/// as a folder with two unread messages, one of them not email, until one
+/// is moved.
+const FAKE_CURL: &str = r#"#!/bin/sh
+dir=$(dirname "$0")
+for arg; do url=$arg; done
+while [ $# -gt 0 ]; do
+  [ "$1" = --request ] && command=$2
+  shift
+done
+cat >> "$dir/config"
+echo "$command $url" >> "$dir/calls"
+case "$command" in
+  "UID SEARCH UNSEEN")
+    if [ -e "$dir/moved" ]; then printf '* SEARCH\r\n'; else printf '* SEARCH 12 7\r\n'; fi ;;
+  "UID FETCH 7 BODY.PEEK[]")
+    printf 'From: Ana <ana@example.com>\r\nSubject: Call the plumber\r\n\r\nThe tap drips.\r\n' > "$dir/message"
+    printf '* 1 FETCH (UID 7 BODY[] {%d}\r\n' "$(wc -c < "$dir/message")"
+    cat "$dir/message"
+    printf ')\r\n' ;;
+  "UID FETCH 12 BODY.PEEK[]")
+    printf '* 2 FETCH (UID 12 BODY[] {12}\r\nnot an email)\r\n' ;;
+  "UID MOVE 7 \"Tasks\"") touch "$dir/moved" ;;
+  *) echo "curl: (78) Access denied" >&2; exit 78 ;;
+esac
+"#;
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    binary(dir)
+        .env("IMAP_PASSWORD", "pa55word")
+        .args(["--setting", "mail.imap=imaps://imap.example.com/INBOX"])
+        .args(["--setting", "mail.user=me@example.com"])
+        .args(["--setting", "mail.move_to=Tasks"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn imap_settings_parse() {
+    let config = Config::parse(
+        "[mail]\nimap = \"imaps://imap.example.com/INBOX\"\nuser = \"me\"\nmove_to = \"Tasks\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        config.mail.imap.as_deref(),
+        Some("imaps://imap.example.com/INBOX")
+    );
+    assert_eq!(config.mail.user.as_deref(), Some("me"));
+    assert_eq!(config.mail.password, None);
+    assert_eq!(config.mail.move_to.as_deref(), Some("Tasks"));
+    assert!(Config::parse("[mail]\nimap = \"imap.example.com\"\n").is_err());
+    assert!(Config::parse(
+        "[mail]\nmaildir = \"/home/me/Mail\"\nimap = \"imaps://imap.example.com/INBOX\"\n"
+    )
+    .is_err());
+}
+
+#[test]
+fn unread_mail_becomes_tasks_and_is_moved() {
+    let dir = TempDir::new("imap");
+    fake(&dir, "curl", FAKE_CURL);
+
+    let preview = run(&dir, &["mail", "--dry-run"]);
+    let previewed_calls = fs::read_to_string(dir.join("bin/calls")).unwrap();
+    let taken = stdout(run(&dir, &["mail"]));
+    let again = stdout(run(&dir, &["mail"]));
+    let saved = JsonStore::new(dir.join("tasks.json")).load().unwrap();
+    let calls = fs::read_to_string(dir.join("bin/calls")).unwrap();
+    let config = fs::read_to_string(dir.join("bin/config")).unwrap();
+
+    assert!(String::from_utf8_lossy(&preview.stderr)
+        .contains("warning: skipped 12 in imaps://imap.example.com/INBOX"));
+    assert_eq!(
+        stdout(preview),
+        "+ Call the plumber (from ana@example.com)\n"
+    );
+    assert!(!previewed_calls.contains("MOVE"), "{previewed_calls}");
+
+    assert!(taken.contains("Call the plumber"), "{taken}");
+    assert_eq!(again, "No new mail in imaps://imap.example.com/INBOX\n");
+    assert_eq!(saved.len(), 1);
+    let task = &saved.tasks()[0];
+    assert_eq!(task.name, "Call the plumber");
+    assert_eq!(task.description, "The tap drips.");
+    assert_eq!(task.contact.as_deref(), Some("ana@example.com"));
+
+    // Only the message that became a task is moved, and the password
+    // stays off the command line.
+    assert!(calls.contains("UID MOVE 7 \"Tasks\" imaps://imap.example.com/INBOX\n"));
+    assert!(!calls.contains("12 \"Tasks\""), "{calls}");
+    assert!(!calls.contains("pa55word"), "{calls}");
+    assert!(config.contains("user = \"me@example.com:pa55word\""));
+}
@@ -0,0 +1,185 @@
+use task_manager::dates::Timestamp;
+use task_manager::export::{self, Codec};
+use task_manager::import::matching::{self, Match};
+use task_manager::import::outlook::{self, DateOrder};
+use task_manager::task::{ReviewAction, ReviewEvent};
+use task_manager::{Priority, Status, Task, TaskId};
+
+#[test]
+fn imported_rows_match_by_external_id_or_name_and_due_day() {
+    let csv = "ID,Subject,Priority,Due Date\n42,Pay  RENT,High,3/1/2030\n,Call the bank,Normal,\n";
+    let first = outlook::parse(csv, DateOrder::MonthFirst).unwrap();
+    let again = outlook::parse(csv, DateOrder::MonthFirst).unwrap();
+    assert_eq!(first[0].id, again[0].id);
+    assert_ne!(first[1].id, again[1].id);
+
+    let mut rent = Task::new("pay rent");
+    rent.due = first[0]
+        .due
+        .map(|due| Timestamp::from_secs(due.secs() - 3600));
+    let mut existing = vec![Task::new("call the bank"), rent];
+    let (task, reason) = matching::find(&existing, &again[1]).unwrap();
+    assert_eq!((task.id, reason), (existing[0].id, Match::NameAndDue));
+    let (task, reason) = matching::find(&existing, &again[0]).unwrap();
+    assert_eq!((task.id, reason), (existing[1].id, Match::NameAndDue));
+    existing[1].id = first[0].id;
+    assert_eq!(matching::find(&existing, &again[0]).unwrap().1, Match::Id);
+
+    existing[1].project = Some("Home".into());
+    assert!(matching::update(&mut existing[1], &again[0]));
+    assert_eq!(existing[1].name, "Pay  RENT");
+    assert_eq!(existing[1].priority, Priority::High);
+    assert_eq!(existing[1].project.as_deref(), Some("Home"));
+    assert!(!matching::update(&mut existing[1], &again[0]));
+}
+
+#[test]
+fn every_data_format_reads_back_what_it_writes() {
+    let mut plain = Task::new("plain");
+    plain.status = Status::Done;
+    plain.completed_at = Some(Timestamp::from_secs(1_700_000_000));
+    let mut full = Task::new("  say \"hi\": - [a] # {b} ");
+    full.description = "two\nlines, 'quoted'\r\n\ttabbed ü".into();
+    full.priority = Priority::Critical;
+    full.status = Status::ReviewPending;
+    full.project = Some("a b".into());
+    full.tags = vec!["x".into(), "true".into()];
+    full.due = Some(Timestamp::from_secs(1_800_000_000));
+    full.parent = Some(plain.id);
+    full.depends_on = vec![plain.id];
+    full.related = vec![plain.id];
+    full.reminders = vec![900, 86_400];
+    full.jira = Some("PROJ-1".into());
+    full.contact = Some("ana@example.com".into());
+    full.body = "Notes\n\n  - first, \"second\"\n".into();
+    (full.pinned, full.archived, full.private, full.review) = (true, true, true, true);
+    full.starred_by = vec!["Ada Lovelace".into(), "bob".into()];
+    full.review_log = vec![ReviewEvent {
+        action: ReviewAction::Submitted,
+        by: "al \"ice\", by x".into(),
+        at: Timestamp::from_secs(1_750_000_000),
+    }];
+    let tasks = vec![full, plain];
+    for codec in Codec::ALL {
+        assert_eq!(export::round_trip(codec, &tasks).unwrap(), [], "{codec}");
+        assert_eq!(export::round_trip(codec, &[]).unwrap(), [], "{codec}");
+    }
+}
+
+/// Xorshift64*, so a failing case can be replayed from its seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self) -> bool {
+        self.next() & 1 == 1
+    }
+
+    /// Text built from pieces that each format has to quote or escape.
+    fn text(&mut self, most: usize) -> String {
+        const PIECES: &[&str] = &[
+            "a", "Zz", "42", "-1.5", " ", "  ", "\"", "'", "\\", "\n", "\r\n", "\t", ",", ":",
+            ": ", "#", " #", "- ", "[", "]", "{", "}", "=", "&", "ü", "日本", "🙂", "true", "null",
+            "~", "|", ">", "*", "%", "<!--", "\u{7f}", "\u{1}",
+        ];
+        (0..self.below(most + 1))
+            .map(|_| PIECES[self.below(PIECES.len())])
+            .collect()
+    }
+
+    /// A word as `parse_tags` leaves one: no separators or leading `#`.
+    fn word(&mut self) -> String {
+        const WORDS: &[&str] = &[
+            "home", "x", "true", "2024", "ü", "a-b", "q\"q", "o'k", "a:b",
+        ];
+        WORDS[self.below(WORDS.len())].to_string()
+    }
+
+    /// A version 4 id, as `TaskId::generate` makes.
+    fn id(&mut self) -> TaskId {
+        let raw = (u128::from(self.next()) << 64) | u128::from(self.next());
+        TaskId::from_u128((raw & !(0xf << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62))
+    }
+
+    fn time(&mut self) -> Timestamp {
+        // Up to the end of 9999, the last year a data file stores.
+        Timestamp::from_secs((self.next() % 253_402_300_800) as i64)
+    }
+
+    fn maybe<T>(&mut self, value: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        self.chance().then(|| value(self))
+    }
+
+    fn task(&mut self, id: TaskId, others: &[TaskId]) -> Task {
+        let mut task = Task::new(self.text(12));
+        task.id = id;
+        task.description = self.text(8);
+        task.body = self.text(20);
+        task.priority = Priority::ALL[self.below(Priority::ALL.len())];
+        task.status = Status::ALL[self.below(Status::ALL.len())];
+        // A blank cell or value is an absent one, so optional text is
+        // never empty.
+        let some_text = |rng: &mut Self, most| rng.maybe(|r| format!("p{}", r.text(most)));
+        task.project = some_text(self, 6);
+        task.tags = (0..self.below(4)).map(|_| self.word()).collect();
+        task.tags.dedup();
+        task.created_at = self.time();
+        task.updated_at = self.time();
+        task.due = self.maybe(Rng::time);
+        task.completed_at = self.maybe(Rng::time);
+        let link = |rng: &mut Self| others[rng.below(others.len())];
+        if !others.is_empty() {
+            task.parent = self.maybe(link);
+            task.depends_on = (0..self.below(3)).map(|_| link(self)).collect();
+            task.related = (0..self.below(3)).map(|_| link(self)).collect();
+        }
+        task.reminders = (0..self.below(3))
+            .map(|_| (self.next() % 1_000_000) as i64)
+            .collect();
+        task.jira = self.maybe(|r| format!("PROJ-{}", r.below(1000)));
+        task.notion = some_text(self, 4);
+        task.google = some_text(self, 4);
+        task.contact = self.maybe(|_| "ana@example.com".to_string());
+        task.pinned = self.chance();
+        task.starred_by = (0..self.below(3)).map(|_| self.word()).collect();
+        task.archived = self.chance();
+        task.private = self.chance();
+        task.review = self.chance();
+        task.review_log = (0..self.below(3))
+            .map(|_| ReviewEvent {
+                action: if self.chance() {
+                    ReviewAction::Submitted
+                } else {
+                    ReviewAction::Approved
+                },
+                by: self.text(5),
+                at: self.time(),
+            })
+            .collect();
+        task
+    }
+}
+
+#[test]
+fn generated_tasks_survive_every_data_format() {
+    for seed in 1..=500u64 {
+        let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1);
+        let ids: Vec<TaskId> = (0..rng.below(5)).map(|_| rng.id()).collect();
+        let tasks: Vec<Task> = ids.iter().map(|&id| rng.task(id, &ids)).collect();
+        for codec in Codec::ALL {
+            let differences = export::round_trip(codec, &tasks)
+                .unwrap_or_else(|err| panic!("seed {seed}, {codec}: {err}"));
+            assert_eq!(differences, [], "seed {seed}, {codec}");
+        }
+    }
+}
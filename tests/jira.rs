@@ -0,0 +1,124 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use common::{binary, fake, stdout, TempDir};
+
+use task_manager::config::Config;
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Status, Task, TasksManager};
+
+/// Stands in for curl: logs each call and its config, and answers as a
+/// JIRA site with PROJ-1 done, PROJ-2 in progress and no PROJ-3.
+const FAKE_CURL: &str = r#"#!/bin/sh
+dir=$(dirname "$0")
+for arg; do url=$arg; done
+echo "$*" >> "$dir/calls"
+cat >> "$dir/config"
+case "$url" in
+  */issue/PROJ-1\?fields=status)
+    echo '{"fields":{"status":{"name":"Closed","statusCategory":{"key":"done"}}}}' ;;
+  */issue/PROJ-2\?fields=status)
+    echo '{"fields":{"status":{"name":"In Progress","statusCategory":{"key":"indeterminate"}}}}' ;;
+  */issue/PROJ-2/transitions)
+    case "$*" in
+      *POST*) ;;
+      *) echo '{"transitions":[{"id":"11","to":{"name":"Backlog","statusCategory":{"key":"new"}}},{"id":"31","to":{"name":"Done","statusCategory":{"key":"done"}}}]}' ;;
+    esac ;;
+  *) echo "curl: (22) The requested URL returned error: 404" >&2; exit 22 ;;
+esac
+"#;
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    binary(dir)
+        .env("JIRA_API_TOKEN", "t0ken")
+        .args(["--setting", "jira.url=https://jira.example"])
+        .args(["--setting", "jira.email=me@example.com"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn linked(name: &str, key: &str, status: Status) -> Task {
+    let mut task = Task::new(name);
+    task.jira = Some(key.into());
+    task.set_status(status);
+    task
+}
+
+#[test]
+fn jira_settings_parse() {
+    let config = Config::parse(
+        "[jira]\nurl = \"https://example.atlassian.net/\"\nemail = \"me@example.com\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        config.jira.url.as_deref(),
+        Some("https://example.atlassian.net")
+    );
+    assert_eq!(config.jira.email.as_deref(), Some("me@example.com"));
+    assert_eq!(config.jira.token, None);
+    assert!(Config::parse("[jira]\nurl = \"example.atlassian.net\"\n").is_err());
+    assert!(Config::parse("[jira]\nproject = \"PROJ\"\n").is_err());
+}
+
+#[test]
+fn sync_mirrors_completion_both_ways() {
+    let dir = TempDir::new("jira");
+    fake(&dir, "curl", FAKE_CURL);
+    let store = JsonStore::new(dir.join("tasks.json"));
+    store
+        .save(&TasksManager::from_tasks(vec![
+            linked("fix login", "PROJ-1", Status::Todo),
+            linked("update docs", "PROJ-2", Status::Done),
+            linked("old bug", "PROJ-3", Status::Todo),
+            Task::new("water plants"),
+        ]))
+        .unwrap();
+
+    let preview = stdout(run(&dir, &["jira", "sync", "--dry-run"]));
+    let untouched = store.load().unwrap();
+    let previewed_calls = fs::read_to_string(dir.join("bin/calls")).unwrap();
+    let synced = run(&dir, &["jira", "sync"]);
+    let saved = store.load().unwrap();
+    let calls = fs::read_to_string(dir.join("bin/calls")).unwrap();
+    let config = fs::read_to_string(dir.join("bin/config")).unwrap();
+
+    assert!(
+        preview.contains("PROJ-1 is done: would complete \"fix login\""),
+        "{preview}"
+    );
+    assert!(
+        preview.contains("\"update docs\" is done: would move PROJ-2 to Done"),
+        "{preview}"
+    );
+    assert_eq!(untouched.tasks()[0].status, Status::Todo);
+    assert!(!previewed_calls.contains("POST"), "{previewed_calls}");
+
+    assert!(synced.status.success());
+    let out = String::from_utf8(synced.stdout).unwrap();
+    assert!(out.ends_with("1 task(s) completed here, 1 issue(s) moved in JIRA, 1 failed.\n"));
+    assert!(String::from_utf8_lossy(&synced.stderr).contains("warning: PROJ-3:"));
+    let statuses: Vec<Status> = saved.tasks().iter().map(|t| t.status).collect();
+    assert_eq!(
+        statuses,
+        [Status::Done, Status::Done, Status::Todo, Status::Todo]
+    );
+    assert!(
+        calls.contains("--request POST https://jira.example/rest/api/2/issue/PROJ-2/transitions")
+    );
+    // The credentials and body go through stdin, not the command line.
+    assert!(!calls.contains("t0ken"), "{calls}");
+    assert!(
+        config.contains("user = \"me@example.com:t0ken\""),
+        "{config}"
+    );
+    assert!(
+        config.contains(r#"data = "{\"transition\":{\"id\":\"31\"}}""#),
+        "{config}"
+    );
+}
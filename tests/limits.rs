@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use task_manager::config::WipLimits;
+use task_manager::dates::{DateTime, Timestamp};
+use task_manager::{Error, Status, Task, TasksManager};
+
+fn day(d: u32) -> Timestamp {
+    DateTime::date(2024, 6, d).to_timestamp().unwrap()
+}
+
+fn limits(in_progress: Option<usize>, per_day: Option<usize>) -> WipLimits {
+    WipLimits {
+        in_progress,
+        per_day,
+    }
+}
+
+#[test]
+fn limits_apply_when_a_task_enters_the_counted_state() {
+    let mut started = Task::new("draft");
+    started.set_status(Status::InProgress);
+    let mut due = Task::new("pay rent");
+    due.due = Some(day(3));
+    let mut manager = TasksManager::from_tasks(vec![started, due, Task::new("review")]);
+    let review = manager.tasks()[2].id;
+    let limits = limits(Some(1), Some(1));
+
+    // Starting a second task goes over.
+    let before = manager.get(review).unwrap().clone();
+    manager
+        .get_mut(review)
+        .unwrap()
+        .set_status(Status::InProgress);
+    let err = manager
+        .check_limits(&limits, Some(&before), review)
+        .unwrap_err();
+    assert!(matches!(err, Error::Conflict(_)));
+    assert!(
+        err.to_string().contains("1 tasks are already in progress"),
+        "{err}"
+    );
+    // Tasks already over a lowered limit can still be edited.
+    let started = manager.get(review).unwrap().clone();
+    assert!(manager
+        .check_limits(&limits, Some(&started), review)
+        .is_ok());
+
+    // A new task due on a full day goes over too; another day is fine.
+    let mut late = Task::new("file report");
+    late.due = Some(day(3));
+    let id = manager.add(late);
+    let err = manager.check_limits(&limits, None, id).unwrap_err();
+    assert!(err.to_string().contains("day limit reached"), "{err}");
+    manager.get_mut(id).unwrap().due = Some(day(4));
+    assert!(manager.check_limits(&limits, None, id).is_ok());
+    // Finished tasks do not count against the day.
+    manager.get_mut(id).unwrap().due = Some(day(3));
+    manager.get_mut(id).unwrap().set_status(Status::Done);
+    assert!(manager.check_limits(&limits, None, id).is_ok());
+}
+
+/// Runs the binary on the data file in `dir` with WIP limits of one.
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_task-manager"))
+        .env_clear()
+        .arg("--file")
+        .arg(dir.join("tasks.json"))
+        .arg("--config")
+        .arg(dir.join("config.toml"))
+        .args([
+            "--setting",
+            "wip.in_progress=1",
+            "--setting",
+            "wip.per_day=1",
+        ])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn added(output: &Output) -> String {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{stdout}{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let line = stdout
+        .lines()
+        .find(|l| l.starts_with("Added task "))
+        .unwrap();
+    line["Added task ".len()..].trim().to_string()
+}
+
+#[test]
+fn add_and_set_enforce_limits_unless_told_not_to() {
+    let dir = std::env::temp_dir().join(format!("task-manager-limits-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let add = |name: &str, extra: &[&str]| {
+        let mut args = vec!["add", "--no-prompt", "--name", name, "--due", "2030-01-02"];
+        args.extend(extra);
+        run(&dir, &args)
+    };
+    let first = added(&add("water plants", &[]));
+    let refused = add("feed cat", &[]);
+    let second = added(&add("feed cat", &["--ignore-limits"]));
+    let start = |id: &str, extra: &[&str]| {
+        let mut args = vec!["set", id, "status=in-progress"];
+        args.extend(extra);
+        run(&dir, &args)
+    };
+    let started = start(&first, &[]);
+    let blocked = start(&second, &[]);
+    let forced = start(&second, &["--ignore-limits"]);
+    let data = fs::read_to_string(dir.join("tasks.json")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!refused.status.success());
+    let message = String::from_utf8_lossy(&refused.stderr);
+    assert!(message.contains("day limit reached"), "{message}");
+    assert!(message.contains("--ignore-limits"), "{message}");
+    assert!(started.status.success());
+    assert!(!blocked.status.success());
+    assert!(String::from_utf8_lossy(&blocked.stderr).contains("WIP limit reached"));
+    assert!(forced.status.success());
+    assert_eq!(data.matches("\"in-progress\"").count(), 2, "{data}");
+}
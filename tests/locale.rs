@@ -0,0 +1,88 @@
+mod common;
+
+use std::path::Path;
+use std::process::Output;
+
+use common::{binary, stdout, TempDir};
+
+use task_manager::config::Config;
+use task_manager::dates::{self, DateTime, Weekday};
+use task_manager::schedule::Cron;
+use task_manager::storage::{JsonStore, Store};
+
+fn run(dir: &Path, settings: &[&str], args: &[&str]) -> Output {
+    let mut command = binary(dir);
+    for setting in settings {
+        command.args(["--setting", setting]);
+    }
+    command.args(args).output().unwrap()
+}
+
+#[test]
+fn week_start_and_locale_settings_parse() {
+    let config =
+        Config::parse("[display]\nweek_start = \"Sunday\"\nlocale = \"es_ES.UTF-8\"\n").unwrap();
+    assert_eq!(config.display.week_start, Weekday::Sunday);
+    assert_eq!(config.display.locale.weekday(Weekday::Monday), "lunes");
+    assert_eq!(config.display.locale.month(8), "agosto");
+    assert_eq!(
+        Config::default().display.locale.weekday(Weekday::Monday),
+        "Monday"
+    );
+    assert!(Config::parse("[display]\nweek_start = \"someday\"\n").is_err());
+}
+
+#[test]
+fn weekly_templates_fire_at_the_start_of_the_configured_week() {
+    // Saturday 2024-06-01.
+    let saturday = DateTime::date(2024, 6, 1).to_timestamp().unwrap();
+    dates::set_week_start(Weekday::Sunday);
+    let sunday_weeks: Cron = "@weekly".parse().unwrap();
+    dates::set_week_start(Weekday::Monday);
+    let monday_weeks: Cron = "@weekly".parse().unwrap();
+    let next = |cron: &Cron| cron.next_after(saturday).unwrap().weekday();
+    assert_eq!(next(&sunday_weeks), Weekday::Sunday);
+    assert_eq!(next(&monday_weeks), Weekday::Monday);
+    // Named days keep their meaning whatever the week start.
+    let fridays: Cron = "0 9 * * fri".parse().unwrap();
+    assert_eq!(next(&fridays), Weekday::Friday);
+}
+
+#[test]
+fn calendar_and_dates_follow_the_locale_and_week_start() {
+    let dir = TempDir::new("locale");
+    let german = ["display.locale=de", "display.week_start=sunday"];
+
+    let june = stdout(run(&dir, &german, &["calendar", "2024-06"]));
+    let added = run(
+        &dir,
+        &german,
+        &[
+            "add",
+            "--no-prompt",
+            "--name",
+            "Wochenbericht",
+            "--due",
+            "freitag",
+        ],
+    );
+    let english = run(
+        &dir,
+        &[],
+        &["add", "--no-prompt", "--name", "report", "--due", "freitag"],
+    );
+    let tasks = JsonStore::new(dir.join("tasks.json")).load().unwrap();
+
+    let lines: Vec<&str> = june.lines().collect();
+    assert_eq!(lines[0].trim(), "Juni 2024");
+    assert_eq!(lines[1], " So     Mo     Di     Mi     Do     Fr     Sa");
+    // The 1st is a Saturday, the last column of a Sunday-first week.
+    assert_eq!(lines[2], format!("{}  1", " ".repeat(42)));
+    assert!(lines[3].starts_with("  2 "), "{june}");
+
+    assert!(added.status.success());
+    assert_eq!(tasks.tasks()[0].due.unwrap().weekday(), Weekday::Friday);
+    // Other languages' names are only understood when chosen.
+    assert!(!english.status.success());
+    assert_eq!(tasks.len(), 1);
+}
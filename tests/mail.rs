@@ -0,0 +1,21 @@
+use task_manager::mail::Message;
+
+#[test]
+fn emails_become_tasks_from_their_plain_text_part() {
+    let raw = "From: =?utf-8?q?Ana_P=C3=A9rez?= <ana@example.com>\r\n\
+        Subject: =?utf-8?B?UmVwb3J0IGR1ZQ==?=\r\n =?utf-8?q?_today?=\r\n\
+        Content-Type: multipart/alternative; boundary=\"b\"\r\n\r\n\
+        --b\r\nContent-Type: text/html\r\n\r\n<p>Send it</p>\r\n\
+        --b\r\nContent-Type: text/plain; charset=utf-8\r\n\
+        Content-Transfer-Encoding: quoted-printable\r\n\r\n\
+        Send the caf=C3=A9 fig=\r\nures.\r\n\r\n-- \r\nAna\r\n--b--\r\n";
+    let task = Message::parse(raw).unwrap().to_task();
+    assert_eq!(task.name, "Report due today");
+    assert_eq!(task.description, "Send the café figures.");
+    assert_eq!(task.contact.as_deref(), Some("ana@example.com"));
+    assert_eq!(
+        Message::parse("Subject:\n\nHi").unwrap().to_task().name,
+        "(no subject)"
+    );
+    assert!(Message::parse("not an email").is_err());
+}
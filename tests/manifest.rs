@@ -0,0 +1,133 @@
+use task_manager::dates::{DateTime, Timestamp};
+use task_manager::manifest::{Action, Manifest};
+use task_manager::{Priority, Status, Task, TasksManager};
+
+const MANIFEST: &str = "
+templates:
+  bug:
+    priority: high
+    tags: [bug]
+projects:
+  - name: website
+    tasks:
+      - name: Fix the contact form
+        template: bug
+        due: 2024-07-01
+      - name: Update the footer
+tasks:
+  - name: Renew passport
+    remind: [1w, 1d]
+";
+
+fn now() -> Timestamp {
+    DateTime::date(2024, 6, 1).to_timestamp().unwrap()
+}
+
+fn error(input: &str) -> String {
+    Manifest::parse(input).unwrap_err().to_string()
+}
+
+fn in_project(name: &str, project: &str) -> Task {
+    let mut task = Task::new(name);
+    task.project = Some(project.into());
+    task
+}
+
+#[test]
+fn malformed_manifests_are_rejected() {
+    assert!(error("tasks:\n\t- name: x\n").contains("tabs"));
+    assert!(error("- a\n- b\n").contains("must be a mapping"));
+    assert!(error("chores:\n  - x\n").contains("unknown manifest section `chores`"));
+    assert!(error("tasks:\n  - priority: high\n").contains("needs a `name`"));
+    assert!(error("tasks:\n  - name: x\n    template: chore\n").contains("unknown template"));
+    assert!(error("tasks: none\n").contains("`tasks` must be a list"));
+    assert!(error("projects:\n  - name: a\n    owner: me\n").contains("unknown key `owner`"));
+    assert!(
+        error("projects:\n  - name: a\n    tasks:\n      - name: x\n        project: b\n")
+            .contains("cannot set `project`")
+    );
+    assert!(error("tasks:\n  - name: X\n  - name: x\n").contains("listed twice"));
+    // Bad field values show up when planning, naming the task.
+    let manifest = Manifest::parse("tasks:\n  - name: x\n    priority: urgent\n").unwrap();
+    let err = manifest
+        .plan(&TasksManager::new(), now(), false)
+        .unwrap_err();
+    assert!(err.to_string().contains("task `x`"), "{err}");
+}
+
+#[test]
+fn templates_fill_in_fields_the_task_leaves_out() {
+    let manifest = Manifest::parse(MANIFEST).unwrap();
+    assert_eq!(manifest.projects, ["website"]);
+    let form = &manifest.tasks[0];
+    assert_eq!(form.project.as_deref(), Some("website"));
+    assert_eq!(
+        form.fields,
+        [
+            ("priority".to_string(), "high".to_string()),
+            ("tags".to_string(), "bug".to_string()),
+            ("due".to_string(), "2024-07-01".to_string()),
+        ]
+    );
+    assert_eq!(manifest.tasks[2].project, None);
+}
+
+#[test]
+fn plans_create_and_change_only_what_differs() {
+    let manifest = Manifest::parse(MANIFEST).unwrap();
+    let mut footer = in_project("update the FOOTER", "website");
+    footer.set_status(Status::Done);
+    let mut form = in_project("Fix the contact form", "website");
+    form.priority = Priority::Low;
+    let mut manager = TasksManager::from_tasks(vec![footer, form]);
+
+    // Planning alone, as `--dry-run` does, changes nothing.
+    let before = manager.clone();
+    let plan = manifest.plan(&manager, now(), false).unwrap();
+    assert_eq!(manager.tasks(), before.tasks());
+    // The footer matches by project and name and the manifest does not
+    // mention its status, so it stays done and needs no change.
+    assert_eq!(plan.counts(), (1, 1, 0));
+    let Action::Change { changes, .. } = &plan.actions[0] else {
+        panic!("expected a change, got {:?}", plan.actions[0]);
+    };
+    let fields: Vec<&str> = changes.iter().map(|c| c.field).collect();
+    assert_eq!(fields, ["priority", "due", "tags"]);
+
+    plan.apply(&mut manager).unwrap();
+    assert_eq!(manager.len(), 3);
+    let passport = manager
+        .tasks()
+        .iter()
+        .find(|t| t.name == "Renew passport")
+        .unwrap();
+    assert_eq!(passport.reminders, [7 * 86_400, 86_400]);
+    assert!(manifest.plan(&manager, now(), false).unwrap().is_empty());
+}
+
+#[test]
+fn pruning_removes_unlisted_tasks_from_declared_projects_only() {
+    let manifest = Manifest::parse(MANIFEST).unwrap();
+    let mut manager = TasksManager::from_tasks(vec![
+        in_project("Old banner", "website"),
+        in_project("Paint the shed", "garden"),
+        Task::new("Call mum"),
+    ]);
+    let kept = manifest.plan(&manager, now(), false).unwrap();
+    assert_eq!(kept.counts(), (3, 0, 0));
+    let plan = manifest.plan(&manager, now(), true).unwrap();
+    assert_eq!(plan.counts(), (3, 0, 1));
+    plan.apply(&mut manager).unwrap();
+    let mut names: Vec<&str> = manager.tasks().iter().map(|t| t.name.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(
+        names,
+        [
+            "Call mum",
+            "Fix the contact form",
+            "Paint the shed",
+            "Renew passport",
+            "Update the footer"
+        ]
+    );
+}
@@ -0,0 +1,75 @@
+use task_manager::markdown;
+use task_manager::{Status, TasksManager};
+
+#[test]
+fn new_items_become_tasks_and_get_ids() {
+    let mut manager = TasksManager::new();
+    let sync = markdown::sync(
+        "# List\n- [ ] one\n  * [x] two\n- not a task\n",
+        &mut manager,
+        "me",
+    );
+    assert_eq!(sync.created.len(), 2);
+    let one = manager.get(sync.created[0]).unwrap();
+    assert_eq!(one.name, "one");
+    assert_eq!(one.status, Status::Todo);
+    assert_eq!(manager.get(sync.created[1]).unwrap().status, Status::Done);
+    assert_eq!(
+        sync.text,
+        format!(
+            "# List\n- [ ] one <!-- task:{} -->\n  * [x] two <!-- task:{} -->\n- not a task\n",
+            sync.created[0], sync.created[1]
+        )
+    );
+
+    // A second pass finds nothing to do.
+    let again = markdown::sync(&sync.text, &mut manager, "me");
+    assert!(!again.changed_file());
+    assert!(again.completed.is_empty());
+    assert_eq!(again.text, sync.text);
+}
+
+#[test]
+fn checkboxes_and_statuses_follow_each_other() {
+    let mut manager = TasksManager::new();
+    let first = markdown::sync("- [ ] a\r\n- [ ] b", &mut manager, "me");
+    let (a, b) = (first.created[0], first.created[1]);
+    manager.get_mut(b).unwrap().set_status(Status::Cancelled);
+    let ticked = first.text.replacen("[ ] a", "[x] a", 1);
+
+    let sync = markdown::sync(&ticked, &mut manager, "me");
+    assert_eq!(sync.completed, [a]);
+    assert_eq!(sync.checked, [b]);
+    assert_eq!(manager.get(a).unwrap().status, Status::Done);
+    assert_eq!(
+        sync.text,
+        format!("- [x] a <!-- task:{a} -->\r\n- [x] b <!-- task:{b} -->")
+    );
+}
+
+#[test]
+fn unknown_ids_are_reported_and_kept() {
+    let mut manager = TasksManager::new();
+    let input = "- [ ] gone <!-- task:00000000-0000-4000-8000-000000000000 -->\n";
+    let sync = markdown::sync(input, &mut manager, "me");
+    assert_eq!(sync.missing.len(), 1);
+    assert!(manager.is_empty());
+    assert_eq!(sync.text, input);
+}
+
+#[test]
+fn discarded_items_are_offered_again() {
+    let mut manager = TasksManager::new();
+    let mut sync = markdown::sync("- [ ] keep\n- [ ] drop\n", &mut manager, "me");
+    let (keep, drop) = (sync.created[0], sync.created[1]);
+    sync.discard(drop, &mut manager);
+    assert_eq!(sync.created, [keep]);
+    assert!(manager.get(drop).is_none());
+    assert_eq!(
+        sync.text,
+        format!("- [ ] keep <!-- task:{keep} -->\n- [ ] drop\n")
+    );
+
+    let again = markdown::sync(&sync.text, &mut manager, "me");
+    assert_eq!(again.created.len(), 1);
+}
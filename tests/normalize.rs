@@ -0,0 +1,65 @@
+use task_manager::config::Config;
+use task_manager::dates::{DateTime, Timestamp};
+use task_manager::rank;
+use task_manager::{Priority, Status, Task, TasksManager};
+
+fn day(d: u32) -> Timestamp {
+    DateTime::date(2024, 6, d).to_timestamp().unwrap()
+}
+
+fn task(name: &str, priority: Priority, due: Option<u32>, created: u32) -> Task {
+    let mut task = Task::new(name);
+    task.priority = priority;
+    task.due = due.map(day);
+    task.created_at = day(created);
+    task
+}
+
+fn manager() -> TasksManager {
+    let mut done = task("file taxes", Priority::Critical, Some(1), 1);
+    done.set_status(Status::Done);
+    TasksManager::from_tasks(vec![
+        task("fix bike", Priority::Low, None, 1),
+        task("plan offsite", Priority::High, None, 2),
+        task("write report", Priority::High, Some(20), 5),
+        done,
+        task("call bank", Priority::High, Some(11), 8),
+        task("book flights", Priority::High, Some(11), 3),
+    ])
+}
+
+#[test]
+fn normalizing_ranks_by_due_date_then_imported_priority() {
+    let manager = manager();
+    let open: Vec<&Task> = manager
+        .tasks()
+        .iter()
+        .filter(|t| t.status.is_open())
+        .collect();
+    let ranked = rank::by_importance(open, manager.tasks(), day(10));
+    let names: Vec<&str> = ranked.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "book flights",
+            "call bank",
+            "write report",
+            "plan offsite",
+            "fix bike"
+        ]
+    );
+    let scheme = Config::parse("[priorities]\ncritical = 0\nhigh = 2\nmedium = 2\nlow = 1\n")
+        .unwrap()
+        .priorities;
+    assert_eq!(
+        rank::spread_shares(ranked.len(), &scheme.shares),
+        [
+            Priority::High,
+            Priority::High,
+            Priority::Medium,
+            Priority::Medium,
+            Priority::Low
+        ]
+    );
+    assert!(Config::parse("[priorities]\nurgent = 5\n").is_err());
+}
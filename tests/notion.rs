@@ -0,0 +1,183 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use common::{binary, fake, stdout, TempDir};
+
+use task_manager::config::Config;
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Priority, Status, Task, TasksManager};
+
+/// Stands in for curl: logs each call and its config, and answers as a
+/// database of four rows over two pages, one of them archived.
+const FAKE_CURL: &str = r#"#!/bin/sh
+dir=$(dirname "$0")
+for arg; do url=$arg; done
+config=$(cat)
+echo "$*" >> "$dir/calls"
+echo "$config" >> "$dir/config"
+case "$url" in
+  */databases/db1/query)
+    case "$config" in
+      *start_cursor*)
+        echo '{"results":[{"id":"cccc-3","properties":{"Task":{"type":"title","title":[{"plain_text":"Send invites"}]},"Status":{"type":"select","select":{"name":"In progress"}}}}],"has_more":false,"next_cursor":null}' ;;
+      *)
+        printf '%s\n' '{"results":[{"id":"aaaa-1","properties":{"Task":{"type":"title","title":[{"plain_text":"Book "},{"plain_text":"venue"}]},"Status":{"type":"select","select":{"name":"Not started"}},"Due":{"type":"date","date":{"start":"2024-06-03"}},"Priority":{"type":"select","select":{"name":"High"}},"Description":{"type":"rich_text","rich_text":[{"plain_text":"Seats 40\nNear the station"}]}}},{"id":"bbbb-2","properties":{"Task":{"type":"title","title":[{"plain_text":"Print flyers"}]},"Status":{"type":"select","select":{"name":"done"}}}},{"id":"dddd-4","archived":true,"properties":{"Task":{"type":"title","title":[{"plain_text":"Old idea"}]}}}],"has_more":true,"next_cursor":"page2"}' ;;
+    esac ;;
+  */pages/cccc-3) echo '{}' ;;
+  *) echo "curl: (22) The requested URL returned error: 404" >&2; exit 22 ;;
+esac
+"#;
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    binary(dir)
+        .env("NOTION_TOKEN", "secret_t0ken")
+        .args(["--setting", "notion.database=db1"])
+        .args(["--setting", "notion.columns.name=Task"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn linked(name: &str, page: &str, status: Status) -> Task {
+    let mut task = Task::new(name);
+    task.notion = Some(page.into());
+    task.set_status(status);
+    task
+}
+
+#[test]
+fn notion_settings_parse() {
+    let config = Config::parse(
+        "[notion]\ndatabase = \"0123-abcd\"\ndone = \"Complete\"\n\n[notion.columns]\nname = \"Task\"\ndue = \"\"\n",
+    )
+    .unwrap();
+    let notion = &config.notion;
+    assert_eq!(notion.database.as_deref(), Some("0123abcd"));
+    assert_eq!(notion.done, "Complete");
+    assert_eq!(notion.columns.name, "Task");
+    assert_eq!(notion.columns.status, "Status");
+    assert_eq!(notion.columns.due, None);
+    assert_eq!(notion.columns.priority.as_deref(), Some("Priority"));
+    assert!(Config::parse("[notion.columns]\nstatus = \"\"\n").is_err());
+    assert!(Config::parse("[notion.columns]\nproject = \"Area\"\n").is_err());
+}
+
+#[test]
+fn sync_adds_rows_and_mirrors_completion() {
+    let dir = TempDir::new("notion");
+    fake(&dir, "curl", FAKE_CURL);
+    let store = JsonStore::new(dir.join("tasks.json"));
+    store
+        .save(&TasksManager::from_tasks(vec![
+            linked("Print flyers", "bbbb-2", Status::Todo),
+            linked("Send invites", "cccc-3", Status::Done),
+        ]))
+        .unwrap();
+
+    let preview = stdout(run(&dir, &["sync", "notion", "--dry-run"]));
+    let untouched = store.load().unwrap();
+    let previewed_calls = fs::read_to_string(dir.join("bin/calls")).unwrap();
+    let synced = stdout(run(&dir, &["sync", "notion"]));
+    let again = stdout(run(&dir, &["sync", "notion"]));
+    let saved = store.load().unwrap();
+    let calls = fs::read_to_string(dir.join("bin/calls")).unwrap();
+    let config = fs::read_to_string(dir.join("bin/config")).unwrap();
+
+    assert!(preview.contains("+ Book venue\n"), "{preview}");
+    assert!(
+        preview.ends_with("Would sync: 1 row(s) added as tasks, 1 task(s) completed here, 1 row(s) marked done, of 3 in the database.\n"),
+        "{preview}"
+    );
+    assert_eq!(untouched.len(), 2);
+    assert!(!previewed_calls.contains("PATCH"), "{previewed_calls}");
+
+    assert!(synced.contains("Print flyers is done in Notion: completed here"));
+    assert!(synced.contains("Send invites is done: marked it Done in Notion"));
+    // The new task is linked, so the next sync does not add it again.
+    assert!(
+        again.contains("0 row(s) added as tasks, 0 task(s) completed here"),
+        "{again}"
+    );
+
+    let added = &saved.tasks()[2];
+    assert_eq!(added.name, "Book venue");
+    assert_eq!(added.notion.as_deref(), Some("aaaa-1"));
+    // Text over several lines is kept in the body.
+    assert_eq!(added.description, "");
+    assert_eq!(added.body, "Seats 40\nNear the station");
+    assert_eq!(added.priority, Priority::High);
+    assert_eq!(added.status, Status::Todo);
+    assert_eq!(added.due.unwrap().format("%Y-%m-%d"), "2024-06-03");
+    assert_eq!(saved.tasks()[0].status, Status::Done);
+    assert_eq!(saved.len(), 3);
+
+    // A select column is written as one, and the token stays off the
+    // command line.
+    assert!(calls.contains("--request PATCH https://api.notion.com/v1/pages/cccc-3"));
+    assert!(!calls.contains("t0ken"), "{calls}");
+    assert!(config
+        .contains(r#"data = "{\"properties\":{\"Status\":{\"select\":{\"name\":\"Done\"}}}}""#));
+    assert!(config.contains("header = \"Authorization: Bearer secret_t0ken\""));
+    assert!(config.contains("header = \"Notion-Version: 2022-06-28\""));
+}
+
+#[test]
+fn the_token_is_taken_from_the_highest_layer() {
+    let dir = TempDir::new("notion-token");
+    fake(&dir, "curl", FAKE_CURL);
+    fs::write(
+        dir.join("config.toml"),
+        "[notion]\ndatabase = \"db1\"\ntoken = \"from_file\"\n\n[notion.columns]\nname = \"Task\"\n",
+    )
+    .unwrap();
+    let token = |vars: &[(&str, &str)], settings: &[&str]| {
+        let mut command = binary(&dir);
+        command.envs(vars.iter().copied());
+        for setting in settings {
+            command.args(["--setting", setting]);
+        }
+        stdout(
+            command
+                .args(["sync", "notion", "--dry-run"])
+                .output()
+                .unwrap(),
+        );
+        let config = fs::read_to_string(dir.join("bin/config")).unwrap();
+        fs::remove_file(dir.join("bin/config")).unwrap();
+        config
+            .lines()
+            .find_map(|line| line.strip_prefix("header = \"Authorization: Bearer "))
+            .unwrap()
+            .trim_end_matches('"')
+            .to_string()
+    };
+
+    let from_file = token(&[], &[]);
+    let from_env = token(&[("NOTION_TOKEN", "from_env")], &[]);
+    let from_prefixed = token(
+        &[
+            ("NOTION_TOKEN", "from_env"),
+            ("TASK_MANAGER_NOTION_TOKEN", "from_prefixed"),
+        ],
+        &[],
+    );
+    let from_flag = token(&[("NOTION_TOKEN", "from_env")], &["notion.token=from_flag"]);
+    // A line break would start another curl option.
+    let injected = binary(&dir)
+        .env("NOTION_TOKEN", "t0ken\nurl = \"https://example.com\"")
+        .args(["sync", "notion", "--dry-run"])
+        .output()
+        .unwrap();
+
+    assert!(!injected.status.success());
+    assert!(String::from_utf8_lossy(&injected.stderr).contains("control character"));
+    assert_eq!(from_file, "from_file");
+    assert_eq!(from_env, "from_env");
+    assert_eq!(from_prefixed, "from_prefixed");
+    assert_eq!(from_flag, "from_flag");
+}
@@ -0,0 +1,102 @@
+use task_manager::dates::{DateTime, Timestamp};
+use task_manager::import::outlook::{self, DateOrder};
+use task_manager::{Priority, Status};
+
+fn at(year: i64, month: u32, day: u32, hour: u32, minute: u32) -> Timestamp {
+    DateTime::date(year, month, day)
+        .to_timestamp()
+        .unwrap()
+        .add_secs(i64::from(hour) * 3600 + i64::from(minute) * 60)
+}
+
+#[test]
+fn csv_exports_map_importance_status_categories_and_reminders() {
+    let csv = "\u{feff}Subject,Notes,Priority,Status,Categories,Due Date,Date Completed,Reminder On/Off,Reminder Date,Reminder Time\n\
+        Pay rent,\"Landlord, flat 2\",High,Waiting on someone else,Red Category;home,3/4/2024,,True,3/4/2024,9:30:00 AM\n\
+        File taxes,,Low,Completed,,,3/1/2024,False,,\n\
+        Plan trip,,Normal,Not Started,,,,On,3/10/2024,21:00\n";
+    let tasks = outlook::parse(csv, DateOrder::MonthFirst).unwrap();
+    assert_eq!(tasks.len(), 3);
+
+    let rent = &tasks[0];
+    assert_eq!(rent.name, "Pay rent");
+    assert_eq!(rent.description, "Landlord, flat 2");
+    assert_eq!(rent.priority, Priority::High);
+    assert_eq!(rent.status, Status::Todo);
+    assert_eq!(rent.tags, ["waiting", "Red-Category", "home"]);
+    // Date-only due dates resolve to the end of the day.
+    let due = at(2024, 3, 4, 23, 59).add_secs(59);
+    assert_eq!(rent.due, Some(due));
+    assert_eq!(rent.reminder_times(), [at(2024, 3, 4, 9, 30)]);
+
+    let taxes = &tasks[1];
+    assert_eq!(
+        (taxes.priority, taxes.status),
+        (Priority::Low, Status::Done)
+    );
+    assert_eq!(taxes.completed_at, Some(at(2024, 3, 1, 0, 0)));
+
+    // A reminder without a due date sets the due time.
+    let trip = &tasks[2];
+    assert_eq!(trip.due, Some(at(2024, 3, 10, 21, 0)));
+    assert_eq!(trip.reminders, [0]);
+}
+
+#[test]
+fn numeric_dates_follow_the_configured_order() {
+    let csv = "Subject,Due Date\nA,03/04/2024\nB,03.04.2024\nC,2024-04-03\n";
+    let day = |order| {
+        outlook::parse(csv, order)
+            .unwrap()
+            .iter()
+            .map(|t| t.due.unwrap().start_of_day())
+            .collect::<Vec<_>>()
+    };
+    let april = at(2024, 4, 3, 0, 0);
+    let march = at(2024, 3, 4, 0, 0);
+    // Dots always mean day first; ISO dates are never ambiguous.
+    assert_eq!(day(DateOrder::MonthFirst), [march, april, april]);
+    assert_eq!(day(DateOrder::DayFirst), [april, april, april]);
+
+    assert!(outlook::parse_date("None", DateOrder::MonthFirst).is_none());
+    assert!(outlook::parse_date("31/31/2024", DateOrder::DayFirst).is_none());
+    let bad = outlook::parse("Subject,Due Date\nA,soon\n", DateOrder::MonthFirst).unwrap_err();
+    assert!(bad.to_string().contains("row 2"), "{bad}");
+    assert!(outlook::parse("Name\nA\n", DateOrder::MonthFirst).is_err());
+}
+
+#[test]
+fn json_tasks_read_bodies_times_and_collections() {
+    let json = r#"{"value": [
+        {"id": "AAMk", "title": "Review draft", "importance": "high",
+         "status": "inProgress", "categories": ["Work items"],
+         "body": {"contentType": "html", "content": "<p>Check &amp; send</p>"},
+         "dueDateTime": {"dateTime": "2024-06-12T00:00:00.0000000", "timeZone": "UTC"},
+         "reminderDateTime": {"dateTime": "2024-06-12T08:00:00.0000000", "timeZone": "UTC"}},
+        {"title": "Done already", "status": "completed", "isReminderOn": false,
+         "completedDateTime": {"dateTime": "2024-06-01T10:00:00Z"},
+         "reminderDateTime": {"dateTime": "2024-06-01T08:00:00"}}
+    ]}"#;
+    let tasks = outlook::parse(json, DateOrder::MonthFirst).unwrap();
+    let review = &tasks[0];
+    assert_eq!(review.name, "Review draft");
+    assert_eq!(review.description, "Check & send");
+    assert_eq!(
+        (review.priority, review.status),
+        (Priority::High, Status::InProgress)
+    );
+    assert_eq!(review.tags, ["Work-items"]);
+    assert_eq!(review.reminder_times(), [at(2024, 6, 12, 8, 0)]);
+    // The same id gives the same task on every import.
+    let again = outlook::parse_json(json).unwrap();
+    assert_eq!(again[0].id, review.id);
+
+    let done = &tasks[1];
+    assert_eq!(done.status, Status::Done);
+    assert_eq!(done.completed_at, Some(at(2024, 6, 1, 10, 0)));
+    assert!(done.due.is_none() && done.reminders.is_empty());
+
+    let single = outlook::parse_json(r#"{"subject": "One"}"#).unwrap();
+    assert_eq!(single[0].name, "One");
+    assert!(outlook::parse_json(r#"{"title": "x", "dueDateTime": 3}"#).is_err());
+}
@@ -0,0 +1,44 @@
+use task_manager::dates::Timestamp;
+use task_manager::json::Value;
+use task_manager::plugin::{self, Reply};
+use task_manager::workflow::Workflow;
+use task_manager::{Priority, Task, TasksManager};
+
+#[test]
+fn plugin_answers_apply_whole_or_not_at_all() {
+    let mut manager = TasksManager::from_tasks(vec![Task::new("Write report")]);
+    let id = manager.tasks()[0].id.to_hex();
+    let request = plugin::request("bump", &["-x".into()], &[&manager.tasks()[0]]);
+    assert_eq!(
+        request
+            .get("tasks")
+            .and_then(Value::as_array)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let now = Timestamp::now();
+    let workflow = Workflow::default();
+    let reply = Reply::parse(&format!(
+        r#"{{"update": [{{"id": "{}", "set": ["priority=high", "+bumped"]}}],
+            "create": [{{"name": "Follow up"}}], "message": "done"}}"#,
+        &id[..8]
+    ))
+    .unwrap();
+    let applied = reply.apply(&mut manager, &workflow, now).unwrap();
+    assert_eq!(
+        (applied.created, applied.updated, applied.removed),
+        (1, 1, 0)
+    );
+    assert_eq!(manager.tasks()[0].priority, Priority::High);
+    assert_eq!(manager.tasks()[0].tags, vec!["bumped".to_string()]);
+    assert_eq!(manager.tasks()[1].name, "Follow up");
+
+    // The removal of a missing task undoes the creation before it.
+    let failing =
+        Reply::parse(r#"{"create": [{"name": "Lost"}], "remove": ["ffffffff"]}"#).unwrap();
+    assert!(failing.apply(&mut manager, &workflow, now).is_err());
+    assert_eq!(manager.tasks().len(), 2);
+    assert_eq!(Reply::parse(" \n").unwrap(), Reply::default());
+}
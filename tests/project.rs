@@ -0,0 +1,108 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use task_manager::dates::Timestamp;
+use task_manager::filter;
+use task_manager::storage::{self, JsonStore, Store};
+use task_manager::{Task, TasksManager};
+
+fn in_project(name: &str, project: &str) -> Task {
+    let mut task = Task::new(name);
+    task.project = Some(project.into());
+    task
+}
+
+#[test]
+fn renaming_a_project_moves_its_tasks_and_keeps_it_archived() {
+    let mut manager = TasksManager::from_tasks(vec![
+        in_project("a", "Website"),
+        in_project("b", "website"),
+        in_project("c", "Garden"),
+    ]);
+    assert!(manager.set_archived("Website", true));
+    assert!(!manager.set_archived("WEBSITE", true));
+    assert!(manager.is_archived("website"));
+
+    assert_eq!(manager.rename_project("WEBSITE", "Blog"), 2);
+    let projects: Vec<_> = manager
+        .tasks()
+        .iter()
+        .map(|t| t.project.as_deref().unwrap())
+        .collect();
+    assert_eq!(projects, ["Blog", "Blog", "Garden"]);
+    assert_eq!(manager.archived_projects(), ["Blog"]);
+
+    let reloaded = storage::decode(&storage::encode(&manager)).unwrap();
+    assert!(reloaded.is_archived("blog"));
+    assert!(manager.set_archived("blog", false));
+    assert!(manager.archived_projects().is_empty());
+}
+
+#[test]
+fn filters_that_name_a_project_are_told_apart() {
+    let now = Timestamp::from_secs(0);
+    let names = |expression: &str| filter::parse(expression, now).unwrap().mentions_project();
+    assert!(names("project:blog"));
+    assert!(names("tag:x or not project:blog"));
+    assert!(!names("tag:x and status:todo"));
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_task-manager"))
+        .env_clear()
+        .arg("--file")
+        .arg(dir.join("tasks.json"))
+        .arg("--config")
+        .arg(dir.join("config.toml"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn archived_projects_are_hidden_from_list_unless_asked_for() {
+    let dir = std::env::temp_dir().join(format!("task-manager-project-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let mut loose = Task::new("water plants");
+    loose.tags = vec!["garden".into()];
+    let store = JsonStore::new(dir.join("tasks.json"));
+    store
+        .save(&TasksManager::from_tasks(vec![
+            in_project("write post", "Blog"),
+            in_project("mow lawn", "Garden"),
+            loose,
+        ]))
+        .unwrap();
+
+    let moved = stdout(&run(&dir, &["project", "move", "garden", "tag:garden"]));
+    stdout(&run(&dir, &["project", "archive", "blog"]));
+    let listed = stdout(&run(&dir, &["list"]));
+    let all = stdout(&run(&dir, &["list", "--all"]));
+    let named = stdout(&run(&dir, &["list", "project:blog"]));
+    let taken = run(&dir, &["project", "rename", "blog", "garden"]);
+    let tasks = store.load().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    // The existing spelling of the target project is kept.
+    assert!(
+        moved.contains("Moved 1 task(s) to project \"Garden\""),
+        "{moved}"
+    );
+    assert_eq!(tasks.tasks()[2].project.as_deref(), Some("Garden"));
+    assert!(!listed.contains("write post") && listed.contains("mow lawn"));
+    assert!(all.contains("write post"), "{all}");
+    assert!(named.contains("write post"), "{named}");
+    assert!(!taken.status.success());
+    assert!(String::from_utf8_lossy(&taken.stderr).contains("already exists"));
+}
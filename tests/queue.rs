@@ -0,0 +1,81 @@
+use task_manager::dates::{DateTime, Timestamp};
+use task_manager::{Priority, Status, Task, TasksManager};
+
+fn day(d: u32) -> Timestamp {
+    DateTime::date(2024, 6, d).to_timestamp().unwrap()
+}
+
+fn task(name: &str, priority: Priority, due: Option<u32>, created: u32) -> Task {
+    let mut task = Task::new(name);
+    task.priority = priority;
+    task.due = due.map(day);
+    task.created_at = day(created);
+    task
+}
+
+fn manager() -> TasksManager {
+    let mut done = task("file taxes", Priority::Critical, Some(1), 1);
+    done.set_status(Status::Done);
+    TasksManager::from_tasks(vec![
+        task("fix bike", Priority::Low, None, 1),
+        task("plan offsite", Priority::High, None, 2),
+        task("write report", Priority::High, Some(20), 5),
+        done,
+        task("call bank", Priority::High, Some(11), 8),
+        task("book flights", Priority::High, Some(11), 3),
+    ])
+}
+
+fn name(task: Option<&Task>) -> Option<&str> {
+    task.map(|t| t.name.as_str())
+}
+
+#[test]
+fn peek_orders_by_priority_then_due_then_age() {
+    let manager = manager();
+    assert_eq!(name(manager.peek_highest_priority()), Some("book flights"));
+}
+
+#[test]
+fn pop_drains_in_order() {
+    let mut manager = manager();
+    let mut order = Vec::new();
+    while let Some(task) = manager.pop_highest_priority() {
+        order.push(task.name);
+    }
+    assert_eq!(
+        order,
+        [
+            "book flights",
+            "call bank",
+            "write report",
+            "plan offsite",
+            "fix bike"
+        ]
+    );
+    // Finished tasks are never popped.
+    assert_eq!(manager.len(), 1);
+}
+
+#[test]
+fn peek_skips_blocked_tasks_and_archived_projects() {
+    let mut manager = manager();
+    let id = |name: &str| manager.tasks().iter().find(|t| t.name == name).unwrap().id;
+    let (flights, bank) = (id("book flights"), id("call bank"));
+    manager.get_mut(flights).unwrap().depends_on.push(bank);
+    manager.get_mut(bank).unwrap().project = Some("errands".into());
+    manager.set_archived("errands", true);
+    assert_eq!(name(manager.peek_highest_priority()), Some("write report"));
+    // Roulette draws from the same tasks.
+    for roll in 0..16 {
+        let picked = manager.roulette(day(12), roll).unwrap();
+        assert!(picked.id != flights && picked.id != bank, "{}", picked.name);
+    }
+}
+
+#[test]
+fn empty_queue() {
+    let mut manager = TasksManager::new();
+    assert!(manager.peek_highest_priority().is_none());
+    assert!(manager.pop_highest_priority().is_none());
+}
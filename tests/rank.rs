@@ -0,0 +1,24 @@
+use task_manager::rank;
+use task_manager::Priority;
+
+#[test]
+fn rank_orders_by_answers_and_spreads_over_levels() {
+    let mut asked = 0;
+    let ranked = rank::rank(vec![3, 1, 4, 5, 2], |a, b| {
+        asked += 1;
+        Some(a > b)
+    });
+    assert_eq!(ranked, Some(vec![5, 4, 3, 2, 1]));
+    assert!(asked <= rank::comparisons(5));
+    assert_eq!(rank::rank(vec![1, 2], |_, _| None), None);
+    assert_eq!(
+        rank::spread(5, &[Priority::High, Priority::Medium, Priority::Low]),
+        [
+            Priority::High,
+            Priority::High,
+            Priority::Medium,
+            Priority::Medium,
+            Priority::Low
+        ]
+    );
+}
@@ -0,0 +1,221 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use task_manager::config::{Config, Glyphs, TagStyles, ThemeName};
+use task_manager::dates::Timestamp;
+use task_manager::render::highlight::Search;
+use task_manager::render::links::{self, Links};
+use task_manager::render::theme::Theme;
+use task_manager::render::{self, Tags, Times};
+use task_manager::{Priority, Status, Task, TasksManager};
+
+/// Compares `actual` with `tests/golden/<name>.txt`. Run with
+/// `UPDATE_GOLDEN=1` to write the files after a deliberate change.
+fn golden(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.txt"));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing {}; run with UPDATE_GOLDEN=1", path.display()));
+    assert_eq!(actual, expected, "output differs from {}", path.display());
+}
+
+fn at(input: &str) -> Timestamp {
+    Timestamp::parse_rfc3339(input).unwrap()
+}
+
+fn task(id: &str, name: &str) -> Task {
+    let mut task = Task::new(name);
+    task.id = id.parse().unwrap();
+    task.created_at = at("2024-03-01T09:00:00Z");
+    task.updated_at = task.created_at;
+    task
+}
+
+fn manager() -> TasksManager {
+    let mut report = task(
+        "11111111-0000-4000-8000-000000000001",
+        "Write quarterly report",
+    );
+    report.priority = Priority::High;
+    report.status = Status::InProgress;
+    report.due = Some(at("2024-03-08T17:00:00Z"));
+    report.tags = vec!["work".into()];
+    report.project = Some("office".into());
+    report.description = "Numbers from the finance export, then the summary.".into();
+    report.pinned = true;
+
+    let mut milk = task("22222222-0000-4000-8000-000000000002", "Buy milk");
+    milk.priority = Priority::Low;
+    milk.status = Status::Done;
+    milk.completed_at = Some(at("2024-03-02T08:30:00Z"));
+
+    let mut plants = task(
+        "33333333-0000-4000-8000-000000000003",
+        "Water the plants on the balcony and repot the basil before it outgrows its pot",
+    );
+    plants.due = Some(at("2024-03-20T09:00:00Z"));
+    plants.depends_on = vec![milk.id];
+
+    let mut manager = TasksManager::new();
+    for task in [report, milk, plants] {
+        manager.add(task);
+    }
+    manager
+}
+
+fn times() -> Times {
+    Times {
+        now: at("2024-03-10T12:00:00Z"),
+        relative: false,
+    }
+}
+
+fn table(manager: &TasksManager, search: &Search, width: Option<usize>) -> String {
+    let tasks: Vec<&Task> = manager.tasks().iter().collect();
+    let styles = TagStyles::default();
+    let tags = Tags {
+        styles: &styles,
+        color: false,
+    };
+    render::task_table(&tasks, times(), tags, &Glyphs::unicode(), search, width)
+}
+
+#[test]
+fn table_layouts() {
+    let manager = manager();
+    let search = Search::new(&[], false);
+    golden("table", &table(&manager, &search, None));
+    golden("table_wrapped", &table(&manager, &search, Some(80)));
+    golden("table_stacked", &table(&manager, &search, Some(40)));
+}
+
+#[test]
+fn colour_only_adds_escapes() {
+    let manager = manager();
+    let plain = table(&manager, &Search::new(&["report"], false), Some(80));
+    let coloured = table(&manager, &Search::new(&["report"], true), Some(80));
+    assert_ne!(coloured, plain);
+    assert_eq!(render::strip_ansi(&coloured), plain);
+}
+
+#[test]
+fn detail_view() {
+    let manager = manager();
+    let show = |id: &str, width| {
+        let task = manager.get(id.parse().unwrap()).unwrap();
+        render::task_detail(
+            task,
+            task.priority,
+            &manager,
+            times(),
+            &Glyphs::ascii(),
+            width,
+        )
+    };
+    golden(
+        "detail",
+        &show("11111111-0000-4000-8000-000000000001", None),
+    );
+    golden(
+        "detail_wrapped",
+        &show("33333333-0000-4000-8000-000000000003", Some(50)),
+    );
+}
+
+#[test]
+fn markdown_checklist() {
+    let manager = manager();
+    let tasks: Vec<&Task> = manager.tasks().iter().collect();
+    golden("markdown", &render::markdown(&tasks));
+}
+
+#[test]
+fn progress_bars_fill_in_proportion_and_only_fill_when_done() {
+    let unicode = Glyphs::unicode();
+    assert_eq!(render::progress_bar(3, 5, 10, &unicode), "██████░░░░  60%");
+    assert_eq!(
+        render::progress_bar(99, 100, 10, &Glyphs::ascii()),
+        "#########.  99%"
+    );
+    assert_eq!(render::progress_bar(0, 0, 10, &unicode), "              -");
+    assert_eq!(render::bar_cells(7, 7, 40), 40);
+}
+
+#[test]
+fn links_wrap_addresses_and_known_references() {
+    let links = Links {
+        jira: Some("https://example.atlassian.net/browse/"),
+        issues: vec!["PROJ-1"],
+    };
+    let text = "See (https://example.com/a). owner/repo#12 [PROJ-1] UTF-8";
+    assert_eq!(
+        links::linkify(text, &links),
+        format!(
+            "See ({}). {} [{}] UTF-8",
+            links::hyperlink("https://example.com/a", "https://example.com/a"),
+            links::hyperlink("owner/repo#12", "https://github.com/owner/repo/issues/12"),
+            links::hyperlink("PROJ-1", "https://example.atlassian.net/browse/PROJ-1"),
+        )
+    );
+    let unlinked = Links::default();
+    assert_eq!(links::linkify("[PROJ-1] #tag", &unlinked), "[PROJ-1] #tag");
+}
+
+#[test]
+fn mailto_fills_in_subject_and_body() {
+    assert_eq!(
+        links::mailto("ana@example.com", "Invoice #42", "Paid?\n100% sure"),
+        "mailto:ana@example.com?subject=Invoice%20%2342&body=Paid%3F%0D%0A100%25%20sure"
+    );
+    assert_eq!(
+        links::mailto("ana@example.com", "", ""),
+        "mailto:ana@example.com"
+    );
+    assert!(task_manager::task::parse_contact("ana @example.com").is_err());
+}
+
+#[test]
+fn themes_read_from_config_with_overrides() {
+    let config =
+        Config::parse("[theme]\nname = \"high-contrast\"\nin_progress = \"#00ff00\"\n").unwrap();
+    assert_eq!(config.theme.name, ThemeName::HighContrast);
+    let theme = Theme::from_settings(&config.theme);
+    assert_eq!(theme.status(Status::InProgress), "\x1b[38;2;0;255;0m");
+    assert_eq!(
+        theme.priority(Priority::Critical),
+        Theme::named(ThemeName::HighContrast).priority(Priority::Critical)
+    );
+    // Monochrome sets attributes only, never a colour.
+    let mono = Theme::named(ThemeName::Monochrome);
+    assert!(mono
+        .priority
+        .iter()
+        .chain(&mono.status)
+        .all(|e| !e.contains('3')));
+    assert!(Config::parse("[theme]\nname = \"neon\"\n").is_err());
+}
+
+#[test]
+fn brief_lines_read_as_one_sentence_per_task() {
+    let now = at("2030-03-04T09:00:00Z");
+    let mut task = Task::new("Pay\nrent");
+    task.priority = Priority::High;
+    task.status = Status::InProgress;
+    task.due = Some(at("2030-03-06T09:00:00Z"));
+    task.project = Some("home".into());
+    let id = task.id.to_string();
+    assert_eq!(
+        render::brief(&task, now),
+        format!(
+            "Pay rent: high priority, in progress, due in 2 days, project home. Id {}.",
+            &id[..8]
+        )
+    );
+}
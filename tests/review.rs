@@ -0,0 +1,107 @@
+use task_manager::config::Config;
+use task_manager::task::ReviewAction;
+use task_manager::workflow::{Reason, Workflow};
+use task_manager::{Error, Status, Task};
+
+#[test]
+fn finishing_a_reviewed_task_waits_for_someone_else() {
+    let mut task = Task::new("Ship the release");
+    task.review = true;
+    assert_eq!(task.finish("alice"), Status::ReviewPending);
+    assert!(task.status.is_open());
+    assert_eq!(task.completed_at, None);
+    // Finishing again does not send it twice.
+    assert_eq!(task.finish("alice"), Status::ReviewPending);
+
+    assert!(matches!(task.approve("alice"), Err(Error::Conflict(_))));
+    task.approve("bob").unwrap();
+    assert_eq!(task.status, Status::Done);
+    assert!(task.completed_at.is_some());
+    let log: Vec<(ReviewAction, &str)> = task
+        .review_log
+        .iter()
+        .map(|e| (e.action, e.by.as_str()))
+        .collect();
+    assert_eq!(
+        log,
+        [
+            (ReviewAction::Submitted, "alice"),
+            (ReviewAction::Approved, "bob")
+        ]
+    );
+    assert!(matches!(task.approve("carol"), Err(Error::Conflict(_))));
+
+    let json = task.to_json();
+    assert_eq!(Task::from_json(&json).unwrap(), task);
+}
+
+#[test]
+fn tasks_without_review_finish_at_once() {
+    let mut task = Task::new("Water plants");
+    assert_eq!(task.finish("alice"), Status::Done);
+    assert!(task.review_log.is_empty());
+    assert!(matches!(task.approve("bob"), Err(Error::Conflict(_))));
+}
+
+#[test]
+fn the_workflow_refuses_forbidden_and_unexplained_changes() {
+    let workflow = Workflow {
+        forbid: vec!["todo -> done".parse().unwrap()],
+        require_note: vec!["cancelled -> *".parse().unwrap()],
+    };
+    let todo = Task::new("Write the docs");
+    let mut next = todo.clone();
+    next.set_status(Status::Done);
+    let rejected = workflow.check(&todo, &next, None).unwrap_err();
+    assert_eq!((rejected.from, rejected.to), (Status::Todo, Status::Done));
+    assert!(matches!(rejected.reason, Reason::Forbidden(_)));
+    next.set_status(Status::InProgress);
+    assert!(workflow.check(&todo, &next, None).is_ok());
+
+    let mut cancelled = todo.clone();
+    cancelled.set_status(Status::Cancelled);
+    let reason = |note| {
+        workflow
+            .check(&cancelled, &todo, note)
+            .map_err(|r| r.reason)
+    };
+    assert!(matches!(reason(None), Err(Reason::NeedsNote(_))));
+    assert!(matches!(reason(Some("  ")), Err(Reason::NeedsNote(_))));
+    assert_eq!(reason(Some("asked again")), Ok(()));
+
+    // A reviewed task reaches done only by approval.
+    let mut reviewed = next.clone();
+    reviewed.review = true;
+    let mut skipped = reviewed.clone();
+    skipped.set_status(Status::Done);
+    let rejected = Workflow::default().check(&reviewed, &skipped, None);
+    assert_eq!(rejected.unwrap_err().reason, Reason::NeedsReview);
+    reviewed.finish("alice");
+    let mut approved = reviewed.clone();
+    approved.approve("bob").unwrap();
+    assert!(Workflow::default()
+        .check(&reviewed, &approved, None)
+        .is_ok());
+}
+
+#[test]
+fn follow_ups_carry_on_from_the_finished_task() {
+    let mut task = Task::new("Call the bank");
+    task.description = "About the mortgage".into();
+    task.project = Some("home".into());
+    task.tags = vec!["money".into()];
+    task.finish("alice");
+    let next = task.follow_up();
+    assert_eq!(next.name, "Follow up on Call the bank");
+    assert_eq!(next.description, task.description);
+    assert_eq!((next.project, next.tags), (task.project, task.tags));
+    assert_eq!(next.related, [task.id]);
+    assert_eq!(next.status, Status::Todo);
+    assert!(
+        Config::parse("[done]\nfollow_up = true\n")
+            .unwrap()
+            .done
+            .follow_up
+    );
+    assert!(!Config::default().done.follow_up);
+}
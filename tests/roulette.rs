@@ -0,0 +1,31 @@
+use task_manager::dates::{DateTime, Timestamp};
+use task_manager::{Priority, Task, TasksManager};
+
+fn day(d: u32) -> Timestamp {
+    DateTime::date(2024, 6, d).to_timestamp().unwrap()
+}
+
+fn task(name: &str, priority: Priority, due: Option<u32>, created: u32) -> Task {
+    let mut task = Task::new(name);
+    task.priority = priority;
+    task.due = due.map(day);
+    task.created_at = day(created);
+    task
+}
+
+#[test]
+fn roulette_weights_by_priority_and_skips_blocked_tasks() {
+    let now = day(11);
+    let mut manager = TasksManager::new();
+    let low = manager.add(task("low", Priority::Low, None, 1));
+    let critical = manager.add(task("critical", Priority::Critical, None, 2));
+    let mut blocked = task("blocked", Priority::Critical, None, 3);
+    blocked.depends_on.push(low);
+    manager.add(blocked);
+    let picks: Vec<_> = (0..9)
+        .map(|roll| manager.roulette(now, roll).unwrap().id)
+        .collect();
+    assert_eq!(picks.iter().filter(|&&id| id == low).count(), 1);
+    assert_eq!(picks.iter().filter(|&&id| id == critical).count(), 8);
+    assert_eq!(TasksManager::new().roulette(now, 7), None);
+}
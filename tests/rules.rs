@@ -0,0 +1,44 @@
+use task_manager::config::Config;
+use task_manager::dates::Timestamp;
+use task_manager::rules;
+use task_manager::{Priority, Task, TasksManager};
+
+#[test]
+fn rules_fire_once_when_a_task_comes_to_match() {
+    let config = Config::parse(
+        "[[rules]]\nname = \"bugs\"\nwhen = \"tag:bug\"\nset = [\"priority=high\"]\n\n\
+         [[rules]]\nwhen = \"is:overdue\"\nset = [\"+overdue\"]\n",
+    )
+    .unwrap();
+    let now = Timestamp::now();
+    let mut late = Task::new("Pay rent");
+    late.due = Some(Timestamp::from_secs(now.secs() - 3600));
+    late.updated_at = Timestamp::from_secs(now.secs() - 7200);
+    let mut bug = Task::new("Crash on start");
+    let mut manager = TasksManager::from_tasks(vec![late, bug.clone()]);
+    let before = manager.tasks().to_vec();
+
+    bug.tags.push("bug".into());
+    *manager.get_mut(bug.id).unwrap() = bug.clone();
+    let firings = rules::run(&config.rules, &mut manager, &before, now).unwrap();
+    let fired: Vec<&str> = firings.iter().map(|f| f.rule.as_str()).collect();
+    assert_eq!(fired, ["bugs", "is:overdue"]);
+    assert_eq!(manager.get(bug.id).unwrap().priority, Priority::High);
+
+    // Lowered by hand after the tag was added: the rule leaves it alone.
+    let before: Vec<Task> = manager
+        .tasks()
+        .iter()
+        .map(|t| Task {
+            updated_at: now,
+            ..t.clone()
+        })
+        .collect();
+    manager.get_mut(bug.id).unwrap().priority = Priority::Low;
+    assert!(rules::run(&config.rules, &mut manager, &before, now)
+        .unwrap()
+        .is_empty());
+
+    assert!(Config::parse("[[rules]]\nwhen = \"tag:x\"\nset = [\"colour=red\"]\n").is_err());
+    assert!(Config::parse("[[rules]]\nset = [\"+x\"]\n").is_err());
+}
@@ -0,0 +1,153 @@
+use task_manager::dates::{self, DateTime, Timestamp, Weekday};
+use task_manager::schedule::{self, Cron, Template};
+use task_manager::{Priority, TasksManager};
+
+/// Times are UTC: no test here changes the display offset.
+fn at(year: i64, month: u32, day: u32, hour: u32, minute: u32) -> Timestamp {
+    DateTime {
+        hour,
+        minute,
+        ..DateTime::date(year, month, day)
+    }
+    .to_timestamp()
+    .unwrap()
+}
+
+fn cron(text: &str) -> Cron {
+    text.parse().unwrap()
+}
+
+/// The next `count` firings after `after`.
+fn firings(cron: &Cron, mut after: Timestamp, count: usize) -> Vec<Timestamp> {
+    let mut out = Vec::new();
+    for _ in 0..count {
+        after = cron.next_after(after).unwrap();
+        out.push(after);
+    }
+    out
+}
+
+#[test]
+fn fields_take_names_ranges_lists_and_steps() {
+    // Saturday 1 June 2024.
+    let start = at(2024, 6, 1, 0, 0);
+    assert_eq!(
+        firings(&cron("*/20 9-10 * * MON-fri"), start, 7),
+        [
+            at(2024, 6, 3, 9, 0),
+            at(2024, 6, 3, 9, 20),
+            at(2024, 6, 3, 9, 40),
+            at(2024, 6, 3, 10, 0),
+            at(2024, 6, 3, 10, 20),
+            at(2024, 6, 3, 10, 40),
+            at(2024, 6, 4, 9, 0),
+        ]
+    );
+    assert_eq!(
+        firings(&cron("30 8 1,15 jan,jul *"), start, 3),
+        [
+            at(2024, 7, 1, 8, 30),
+            at(2024, 7, 15, 8, 30),
+            at(2025, 1, 1, 8, 30),
+        ]
+    );
+    // Sunday is 0 or 7; `5/30` runs from 5 to the end of the hour.
+    assert_eq!(
+        firings(&cron("5/30 12 * * 7"), start, 3),
+        [
+            at(2024, 6, 2, 12, 5),
+            at(2024, 6, 2, 12, 35),
+            at(2024, 6, 9, 12, 5),
+        ]
+    );
+    for bad in [
+        "*/0 * * * *",
+        "5-3 * * * *",
+        "60 * * * *",
+        "* * * 13 *",
+        "* * * * funday",
+        "* * * *",
+        "@fortnightly",
+    ] {
+        assert!(bad.parse::<Cron>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn restricted_day_fields_fire_on_either() {
+    // The 13th, or any Friday.
+    let cron = cron("0 9 13 * fri");
+    let days: Vec<u32> = firings(&cron, at(2024, 9, 1, 0, 0), 6)
+        .into_iter()
+        .map(|t| t.to_datetime().day)
+        .collect();
+    assert_eq!(days, [6, 13, 20, 27, 4, 11]);
+    // With the weekday left open only the day of the month counts.
+    assert_eq!(
+        "0 9 13 * *"
+            .parse::<Cron>()
+            .unwrap()
+            .next_after(at(2024, 9, 1, 0, 0)),
+        Some(at(2024, 9, 13, 9, 0))
+    );
+}
+
+#[test]
+fn weekly_starts_with_the_configured_week() {
+    let after = at(2024, 6, 5, 12, 0); // a Wednesday
+    dates::set_week_start(Weekday::Sunday);
+    let sunday = cron("@weekly").next_after(after).unwrap();
+    dates::set_week_start(Weekday::Monday);
+    let monday = cron("@weekly").next_after(after).unwrap();
+    assert_eq!(sunday, at(2024, 6, 9, 0, 0));
+    assert_eq!(monday, at(2024, 6, 10, 0, 0));
+}
+
+#[test]
+fn missed_runs_fold_into_one_task() {
+    let mut manager = TasksManager::new();
+    let start = at(2024, 6, 1, 12, 0);
+    let standup = Template::new(
+        "standup notes".into(),
+        cron("0 9 * * *"),
+        vec!["priority=high".into(), "due=today 17:00".into()],
+        start,
+        &manager,
+    )
+    .unwrap();
+    let never = Template::new(
+        "leap day".into(),
+        cron("0 0 31 2 *"),
+        Vec::new(),
+        start,
+        &manager,
+    )
+    .unwrap();
+    assert_eq!(never.cron.next_after(start), None);
+    manager.templates_mut().extend([standup, never]);
+
+    // Three mornings have passed.
+    let now = at(2024, 6, 4, 10, 0);
+    let runs = schedule::tick(&mut manager, now).unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].fired_at, at(2024, 6, 4, 9, 0));
+    assert_eq!(runs[0].skipped, 2);
+    let task = manager.get(runs[0].task).unwrap();
+    assert_eq!(task.priority, Priority::High);
+    assert_eq!(task.due, Some(at(2024, 6, 4, 17, 0)));
+    assert!(manager.templates().iter().all(|t| t.last_run == now));
+
+    // Nothing has fired since.
+    assert!(schedule::tick(&mut manager, now).unwrap().is_empty());
+    assert_eq!(manager.len(), 1);
+
+    // Fields are checked when the template is made.
+    assert!(Template::new(
+        "bad".into(),
+        cron("@daily"),
+        vec!["priority=urgent".into()],
+        now,
+        &manager
+    )
+    .is_err());
+}
@@ -0,0 +1,121 @@
+use task_manager::config::{Glyphs, TagStyles};
+use task_manager::dates::Timestamp;
+use task_manager::export::{escpos, site};
+use task_manager::stats::Today;
+use task_manager::{Priority, Task};
+
+fn build(tasks: &[&Task]) -> Vec<site::Page> {
+    site::build(
+        tasks,
+        Timestamp::from_secs(1_700_000_000),
+        &TagStyles::default(),
+        &Glyphs::unicode(),
+    )
+}
+
+#[test]
+fn same_tasks_give_the_same_site_in_any_order() {
+    let mut tasks = Vec::new();
+    for (i, project) in ["Home & Garden", "home garden", "", "No project"]
+        .into_iter()
+        .enumerate()
+    {
+        let mut task = Task::new(format!("task {i}"));
+        task.created_at = Timestamp::from_secs(1_600_000_000 + i as i64);
+        task.project = Some(project.to_string()).filter(|p| !p.is_empty());
+        tasks.push(task);
+    }
+    let forward: Vec<&Task> = tasks.iter().collect();
+    let backward: Vec<&Task> = tasks.iter().rev().collect();
+    let pages = build(&forward);
+    assert_eq!(pages, build(&backward));
+
+    let paths: Vec<&str> = pages.iter().map(|page| page.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "index.html",
+            "tasks.json",
+            "projects/no-project.html",
+            "projects/home-garden.html",
+            "projects/no-project-2.html",
+            "projects/home-garden-2.html",
+        ]
+    );
+    assert!(pages[0].contents.contains("Home &amp; Garden"));
+    assert!(!pages[0].contents.contains("Generated"));
+}
+
+#[test]
+fn projects_with_dated_tasks_get_a_calendar_feed() {
+    let mut dated = Task::new("Ship, then rest");
+    dated.project = Some("Launch".into());
+    dated.due = Some(Timestamp::from_secs(1_700_086_400));
+    let mut undated = Task::new("Someday");
+    undated.project = Some("Ideas".into());
+    let pages = build(&[&dated, &undated]);
+    let feeds: Vec<&site::Page> = pages
+        .iter()
+        .filter(|page| page.path.ends_with(".ics"))
+        .collect();
+    assert_eq!(feeds.len(), 1);
+    assert_eq!(feeds[0].path, "projects/launch/calendar.ics");
+    assert!(feeds[0].contents.contains("X-WR-CALNAME:Launch\r\n"));
+    assert!(feeds[0].contents.contains("SUMMARY:Ship\\, then rest\r\n"));
+    assert!(!feeds[0].contents.contains("Someday"));
+    assert_eq!(pages, build(&[&undated, &dated]));
+}
+
+#[test]
+fn mermaid_overview_groups_by_project_or_status() {
+    use task_manager::export::graph::{self, Diagram};
+    use task_manager::Status;
+
+    let mut site = Task::new("Fix \"footer\"");
+    site.project = Some("Website".into());
+    let mut deploy = Task::new("Deploy");
+    deploy.project = Some("Website".into());
+    deploy.depends_on.push(site.id);
+    deploy.status = Status::InProgress;
+    let loose = Task::new("Call the bank");
+    let tasks = [&loose, &site, &deploy];
+
+    let flowchart = graph::overview(&tasks, Diagram::Flowchart, &Glyphs::unicode());
+    let website = flowchart.find("subgraph p0[\"Website\"]").unwrap();
+    let none = flowchart.find("subgraph p1[\"No project\"]").unwrap();
+    let bank = flowchart.find("Call the bank").unwrap();
+    assert!(website < none && none < bank);
+    assert!(flowchart.contains("Fix #quot;footer#quot;<br/>todo"));
+    assert_eq!(flowchart.matches("  end\n").count(), 2);
+
+    let states = graph::overview(&tasks, Diagram::State, &Glyphs::unicode());
+    assert!(states.starts_with("stateDiagram-v2\n"));
+    assert!(states.contains("state \"in-progress\" as s_in_progress {"));
+    assert!(!states.contains("s_done"));
+    assert_eq!(states.matches(" --> ").count(), 1);
+}
+
+#[test]
+fn receipts_list_todays_tasks_in_printer_commands() {
+    let now = Timestamp::parse_rfc3339("2030-03-04T09:00:00Z").unwrap();
+    let mut late = Task::new("Pay the café rent\x1b@");
+    late.due = Some(Timestamp::parse_rfc3339("2030-03-01T12:00:00Z").unwrap());
+    let mut urgent = Task::new("Call the bank");
+    urgent.priority = Priority::Critical;
+    let mut blank = Task::new("");
+    blank.priority = Priority::High;
+    let tasks = [late, urgent, blank];
+    let receipt = escpos::receipt(&Today::of(&tasks, now), now, 32);
+    assert!(receipt.starts_with(b"\x1b@\x1bt\x10"));
+    assert!(receipt.ends_with(b"\x1dVB\x00"));
+    let text = String::from_utf8_lossy(&receipt);
+    assert!(text.contains("Overdue (1)\n"));
+    assert!(text.contains("[ ] Call the bank\n    critical"));
+    assert!(text.contains("[ ] \n    high"), "{text}");
+    // Code page 1252, and no printer command smuggled in through a name.
+    let line = receipt
+        .split(|&b| b == b'\n')
+        .find(|l| l.windows(7).any(|w| w == b"[ ] Pay"))
+        .unwrap();
+    assert!(line.ends_with(b"[ ] Pay the caf\xe9 rent @"));
+}
@@ -0,0 +1,68 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Task, TasksManager};
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_task-manager"))
+        .env_clear()
+        .arg("--file")
+        .arg(dir.join("tasks.json"))
+        .arg("--config")
+        .arg(dir.join("config.toml"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn list_leaves_out_a_teammates_private_tasks() {
+    let dir = std::env::temp_dir().join(format!("task-manager-sources-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(vec![Task::new("Water plants")]))
+        .unwrap();
+    let mut doctor = Task::new("See the doctor");
+    doctor.private = true;
+    JsonStore::new(dir.join("bob.json"))
+        .save(&TasksManager::from_tasks(vec![
+            Task::new("Review budget"),
+            doctor,
+        ]))
+        .unwrap();
+    fs::write(
+        dir.join("config.toml"),
+        format!(
+            "[[sources]]\nname = \"bob\"\npath = \"{}\"\n",
+            dir.join("bob.json").display()
+        ),
+    )
+    .unwrap();
+
+    let listed = stdout(run(&dir, &["list"]));
+    let with_private = stdout(run(&dir, &["list", "--private"]));
+    let mine = stdout(run(&dir, &["list", "--mine"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(listed.contains("Water plants"), "{listed}");
+    assert!(listed.contains("bob (read-only)"), "{listed}");
+    assert!(listed.contains("Review budget"), "{listed}");
+    assert!(!listed.contains("See the doctor"), "{listed}");
+    assert!(with_private.contains("See the doctor"), "{with_private}");
+    assert!(
+        mine.contains("Water plants") && !mine.contains("bob"),
+        "{mine}"
+    );
+}
@@ -0,0 +1,48 @@
+use std::fs;
+
+use task_manager::split::{self, SplitBy};
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Task, TasksManager};
+
+#[test]
+fn directory_store_round_trips_and_moves_tasks_between_files() {
+    let dir = std::env::temp_dir().join(format!("task-manager-split-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let mut site = Task::new("Fix the footer");
+    site.project = Some("Web Site".into());
+    let mut manager = TasksManager::from_tasks(vec![site, Task::new("Call the bank")]);
+    manager.set_archived("old", true);
+    let first = manager.tasks()[0].id;
+    manager.set_alias("footer", first).unwrap();
+    split::create(&dir, &manager, SplitBy::Project).unwrap();
+    let files = |dir: &std::path::Path| {
+        let mut names: Vec<String> = fs::read_dir(dir.join(split::TASKS_DIR))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    };
+    let before = files(&dir);
+
+    let store = JsonStore::new(&dir);
+    let mut loaded = store.load().unwrap();
+    let moved = loaded
+        .tasks()
+        .iter()
+        .find(|t| t.project.is_some())
+        .unwrap()
+        .id;
+    loaded.get_mut(moved).unwrap().project = None;
+    store.save(&loaded).unwrap();
+    let after = files(&dir);
+    let reloaded = store.load().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(before, ["no-project.json", "web-site.json"]);
+    assert_eq!(after, ["no-project.json"]);
+    assert_eq!(reloaded.len(), 2);
+    assert_eq!(reloaded.archived_projects(), ["old"]);
+    assert_eq!(reloaded.resolve("footer").unwrap(), first);
+    assert!(reloaded.tasks().iter().all(|t| t.project.is_none()));
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use std::path::Path;
+use std::process::Output;
+
+use common::{binary, stdout, TempDir};
+
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Status, Task, TasksManager};
+
+/// Runs the binary on the data file in `dir` as `user`, named through the
+/// environment as a login would.
+fn run(dir: &Path, user: &str, args: &[&str]) -> Output {
+    binary(dir).env("USER", user).args(args).output().unwrap()
+}
+
+#[test]
+fn each_user_keeps_their_own_stars() {
+    let dir = TempDir::new("star");
+    let mut done = Task::new("send invoice");
+    done.set_status(Status::Done);
+    let mut tagged = Task::new("order paper");
+    tagged.tags = vec!["office".into()];
+    let tasks = vec![Task::new("call supplier"), tagged, done];
+    let ids: Vec<String> = tasks.iter().map(|t| t.id.to_hex()).collect();
+    let store = JsonStore::new(dir.join("tasks.json"));
+    store.save(&TasksManager::from_tasks(tasks)).unwrap();
+
+    for id in &ids {
+        stdout(run(&dir, "ana", &["star", id]));
+    }
+    let again = stdout(run(&dir, "ana", &["star", &ids[0]]));
+    stdout(run(&dir, "bob", &["star", &ids[1]]));
+    let ana = stdout(run(&dir, "ana", &["starred"]));
+    let ana_office = stdout(run(&dir, "ana", &["starred", "tag:office"]));
+    stdout(run(&dir, "bob", &["unstar", &ids[1]]));
+    let bob = stdout(run(&dir, "bob", &["starred"]));
+    // `user.name` in the config wins over the environment.
+    let named = stdout(run(&dir, "bob", &["--setting", "user.name=ana", "starred"]));
+    let saved = store.load().unwrap();
+
+    assert_eq!(again, "\"call supplier\" is already starred.\n");
+    // Finished tasks stay starred but are not listed.
+    assert!(ana.contains("call supplier") && ana.contains("order paper"));
+    assert!(!ana.contains("send invoice"), "{ana}");
+    assert!(!ana_office.contains("call supplier"), "{ana_office}");
+    assert_eq!(bob, "No starred tasks; star one with `star <id>`.\n");
+    assert_eq!(named, ana);
+    let starred: Vec<&[String]> = saved
+        .tasks()
+        .iter()
+        .map(|t| t.starred_by.as_slice())
+        .collect();
+    assert_eq!(starred, [&["ana"][..], &["ana"], &["ana"]]);
+}
@@ -0,0 +1,173 @@
+use std::fs;
+use std::process::Command;
+
+use task_manager::dates::{DateTime, Timestamp, SECONDS_PER_DAY};
+use task_manager::json::{self, Value};
+use task_manager::stats::{self, History, Snapshot};
+use task_manager::storage::{self, JsonStore, Store};
+use task_manager::{Priority, Status, Task, TasksManager};
+
+fn day(d: u32, hour: u32) -> Timestamp {
+    DateTime::date(2024, 3, d)
+        .to_timestamp()
+        .unwrap()
+        .add_secs(i64::from(hour) * 3600)
+}
+
+fn task(name: &str, project: Option<&str>, priority: Priority, status: Status) -> Task {
+    let mut task = Task::new(name);
+    task.project = project.map(String::from);
+    task.priority = priority;
+    task.set_status(status);
+    task
+}
+
+#[test]
+fn snapshots_count_open_and_done_tasks_by_priority_and_project() {
+    let tasks = vec![
+        task("a", Some("home"), Priority::High, Status::Todo),
+        task("b", Some("home"), Priority::High, Status::Done),
+        task("c", None, Priority::Low, Status::InProgress),
+        task("d", Some("work"), Priority::Critical, Status::Cancelled),
+    ];
+    let snapshot = Snapshot::capture(&tasks, day(5, 15));
+    assert_eq!(snapshot.day, day(5, 0));
+    assert_eq!(snapshot.open, [1, 0, 1, 0]);
+    assert_eq!(snapshot.done, [0, 0, 1, 0]);
+    assert_eq!((snapshot.open_total(), snapshot.done_total()), (2, 1));
+    let home = &snapshot.projects["home"];
+    assert_eq!((home.open, home.done), (1, 1));
+    // Cancelled work is neither open nor done, but its project is listed.
+    let work = &snapshot.projects["work"];
+    assert_eq!((work.open, work.done), (0, 0));
+}
+
+#[test]
+fn one_snapshot_is_kept_per_day() {
+    let mut manager = TasksManager::from_tasks(vec![Task::new("a")]);
+    manager.record_snapshot(day(4, 9));
+    manager.add(Task::new("b"));
+    manager.record_snapshot(day(5, 9));
+    manager.add(Task::new("c"));
+    // A later save on the same day replaces its snapshot.
+    manager.record_snapshot(day(5, 18));
+
+    let history = manager.history();
+    let totals: Vec<usize> = history
+        .snapshots()
+        .iter()
+        .map(Snapshot::open_total)
+        .collect();
+    assert_eq!(totals, [1, 3]);
+    assert_eq!(history.as_of(day(4, 23)).unwrap().open_total(), 1);
+    assert!(history.as_of(day(3, 12)).is_none());
+
+    // Days without a snapshot carry the previous one forward.
+    let trend: Vec<Option<usize>> = history
+        .trend(day(7, 12), 5)
+        .into_iter()
+        .map(|(_, s)| s.map(Snapshot::open_total))
+        .collect();
+    assert_eq!(trend, [None, Some(1), Some(3), Some(3), Some(3)]);
+}
+
+#[test]
+fn old_snapshots_expire_and_history_round_trips() {
+    let mut history = History::default();
+    history.record(Snapshot::capture(&[], day(1, 0)), 3);
+    history.record(Snapshot::capture(&[], day(3, 0)), 3);
+    history.record(Snapshot::capture(&[], day(5, 0)), 3);
+    let days: Vec<Timestamp> = history.snapshots().iter().map(|s| s.day).collect();
+    assert_eq!(days, [day(3, 0), day(5, 0)]);
+
+    let tasks = vec![task("a", Some("home"), Priority::Medium, Status::Done)];
+    let mut manager = TasksManager::from_tasks(tasks);
+    manager.record_snapshot(day(6, 10));
+    let reloaded = storage::decode(&storage::encode(&manager)).unwrap();
+    assert_eq!(reloaded.history(), manager.history());
+    assert_eq!(
+        History::from_json(&Value::Null).unwrap(),
+        History::default()
+    );
+}
+
+fn completed(days_ago: i64, now: Timestamp) -> Task {
+    let mut task = Task::new("done");
+    task.set_status(Status::Done);
+    task.completed_at = Some(now.add_secs(-days_ago * SECONDS_PER_DAY));
+    task
+}
+
+#[test]
+fn completions_are_counted_per_day_from_completion_times() {
+    let now = day(10, 15);
+    let tasks = vec![
+        completed(0, now),
+        completed(0, now),
+        completed(2, now),
+        completed(4, now),
+        Task::new("open"),
+    ];
+    let counts = stats::completions(&tasks, now, 4);
+    assert_eq!(
+        counts,
+        [
+            (day(7, 0), 0),
+            (day(8, 0), 1),
+            (day(9, 0), 0),
+            (day(10, 0), 2)
+        ]
+    );
+    assert!(stats::completions(&tasks, now, 0).is_empty());
+}
+
+#[test]
+fn heatmap_reports_totals_streaks_and_json() {
+    let dir = std::env::temp_dir().join(format!("task-manager-heatmap-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let now = Timestamp::now();
+    let tasks = [0, 1, 1, 3, 4, 5, 40].map(|back| completed(back, now));
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(tasks.to_vec()))
+        .unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_task-manager"))
+            .env_clear()
+            .arg("--file")
+            .arg(dir.join("tasks.json"))
+            .arg("--config")
+            .arg(dir.join("config.toml"))
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let grid = run(&["stats", "heatmap", "--days", "14"]);
+    let report = run(&["stats", "heatmap", "--days", "14", "--json"]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    // The grid has a month row and one row per weekday, then the summary.
+    let lines: Vec<&str> = grid.lines().collect();
+    assert_eq!(lines.len(), 11, "{grid}");
+    assert!(
+        grid.contains("6 task(s) completed in the last 14 days"),
+        "{grid}"
+    );
+    assert!(
+        grid.ends_with("Current streak 2 day(s), longest 3\n"),
+        "{grid}"
+    );
+
+    let report = json::parse(&report).unwrap();
+    assert_eq!(report.get("total").and_then(Value::as_i64), Some(6));
+    let days = report.get("days").and_then(Value::as_array).unwrap();
+    assert_eq!(days.len(), 14);
+    let today = &days[13];
+    assert_eq!(
+        today.get("date").and_then(Value::as_str),
+        Some(now.format("%Y-%m-%d").as_str())
+    );
+    assert_eq!(today.get("completed").and_then(Value::as_i64), Some(1));
+}
@@ -0,0 +1,35 @@
+use task_manager::json;
+use task_manager::storage::{self, MemoryStore, Store};
+use task_manager::{Task, TasksManager};
+
+#[test]
+fn memory_store_keeps_the_data_file_document() {
+    let store = MemoryStore::new();
+    assert!(store.load().unwrap().is_empty());
+    let mut manager = TasksManager::new();
+    manager.add(Task::new("Water the plants"));
+    store.save(&manager).unwrap();
+
+    let document = store.to_json().unwrap();
+    assert_eq!(
+        document,
+        storage::encode(&manager).to_pretty(),
+        "the same JSON as the data file"
+    );
+    let reopened = MemoryStore::from_json(document).load().unwrap();
+    assert_eq!(reopened.tasks(), manager.tasks());
+}
+
+#[test]
+fn deeply_nested_json_is_an_error_not_a_crash() {
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(json::parse(&nested(64)).is_ok());
+    let err = json::parse(&nested(65)).unwrap_err();
+    assert!(err.to_string().contains("64 levels"), "{err}");
+    assert!(json::parse(&"[".repeat(100_000)).is_err());
+    assert!(
+        MemoryStore::from_json(format!("{{\"tasks\": {}}}", nested(100_000)))
+            .load()
+            .is_err()
+    );
+}
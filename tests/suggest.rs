@@ -0,0 +1,168 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use task_manager::storage::{JsonStore, Store};
+use task_manager::suggest;
+use task_manager::{Error, Priority, Status, Task, TasksManager};
+
+#[test]
+fn distance_ignores_case_and_counts_swaps_once() {
+    assert_eq!(suggest::edit_distance("list", "LIST"), 0);
+    assert_eq!(suggest::edit_distance("lsit", "list"), 1);
+    assert_eq!(suggest::edit_distance("kitten", "sitting"), 3);
+    assert_eq!(suggest::edit_distance("", "abc"), 3);
+}
+
+#[test]
+fn closest_allows_an_edit_per_three_characters() {
+    let commands = ["list", "last", "add", "archive", "remove", "list"];
+    // Equally close names keep their order, and duplicates are dropped.
+    assert_eq!(suggest::closest("lst", commands), ["list", "last"]);
+    assert_eq!(suggest::closest("archvie", commands), ["archive"]);
+    // Short words still allow one edit, but no more.
+    assert_eq!(suggest::closest("ad", commands), ["add"]);
+    assert!(suggest::closest("xyz", commands).is_empty());
+}
+
+#[test]
+fn did_you_mean_lists_at_most_three() {
+    let names = |n: &[&str]| n.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    assert_eq!(suggest::did_you_mean(&[]), "");
+    assert_eq!(suggest::did_you_mean(&names(&["a"])), "; did you mean a?");
+    assert_eq!(
+        suggest::did_you_mean(&names(&["a", "b", "c", "d"])),
+        "; did you mean a, b or c?"
+    );
+}
+
+#[test]
+fn missing_tasks_suggest_similar_names() {
+    let manager = TasksManager::from_tasks(vec![Task::new("Pay rent"), Task::new("Plan trip")]);
+    let rent = &manager.tasks()[0].id.to_hex()[..manager.id_width()];
+    let err = manager.resolve("pay rnet").unwrap_err();
+    assert!(matches!(err, Error::NotFound(_)));
+    assert_eq!(
+        err.to_string(),
+        format!("task `pay rnet` not found; did you mean \"Pay rent\" ({rent})?")
+    );
+    assert_eq!(
+        manager.resolve("ffffffff").unwrap_err().to_string(),
+        "task `ffffffff` not found"
+    );
+}
+
+/// Runs the binary on the data file in `dir`, answering prompts with
+/// `input`.
+fn run(dir: &Path, args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_task-manager"))
+        .env_clear()
+        .arg("--file")
+        .arg(dir.join("tasks.json"))
+        .arg("--config")
+        .arg(dir.join("config.toml"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn unknown_commands_suggest_the_nearest() {
+    let dir = std::env::temp_dir().join(format!("task-manager-suggest-{}", std::process::id()));
+    let output = run(&dir, &["lsit"], "");
+    assert!(!output.status.success());
+    let message = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        message.contains("unknown command `lsit`; did you mean `list`"),
+        "{message}"
+    );
+}
+
+#[test]
+fn near_duplicates_ignore_case_punctuation_and_typos() {
+    assert_eq!(suggest::normalize("  Buy MILK!!  (2%)"), "buy milk 2");
+    assert_eq!(suggest::similarity("Buy milk!", "buy  milk"), 1.0);
+    assert_eq!(suggest::similarity("", "?"), 1.0);
+    assert!(suggest::similarity("Pay rent", "Pay the rent") < suggest::DUPLICATE_THRESHOLD);
+
+    let mut done = Task::new("Call the bank");
+    done.set_status(Status::Done);
+    let manager = TasksManager::from_tasks(vec![
+        Task::new("Call the bank."),
+        Task::new("call the bnak"),
+        Task::new("Email the bank"),
+        done,
+    ]);
+    // Only open tasks count, the most similar first.
+    let similar: Vec<&str> = manager
+        .similar_open("call the bank")
+        .iter()
+        .map(|t| t.name.as_str())
+        .collect();
+    assert_eq!(similar, ["Call the bank.", "call the bnak"]);
+}
+
+#[test]
+fn add_merges_into_a_near_duplicate_when_asked() {
+    let dir = std::env::temp_dir().join(format!("task-manager-duplicate-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let mut existing = Task::new("Renew passport");
+    existing.description = "Photos first".into();
+    existing.tags = vec!["admin".into()];
+    JsonStore::new(dir.join("tasks.json"))
+        .save(&TasksManager::from_tasks(vec![existing]))
+        .unwrap();
+
+    let add = |input: &str, extra: &[&str]| {
+        let mut args = vec![
+            "add",
+            "--name",
+            "renew pasport",
+            "--desc",
+            "Book an appointment",
+            "--priority",
+            "high",
+            "--project",
+            "Home",
+            "--tags",
+            "admin,travel",
+            "--due",
+            "",
+        ];
+        args.extend(extra);
+        run(&dir, &args, input)
+    };
+    let cancelled = add("\n", &[]);
+    let merged = add("m\n", &[]);
+    let reported = add("", &["--no-prompt"]);
+    let tasks = JsonStore::new(dir.join("tasks.json")).load().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(String::from_utf8_lossy(&cancelled.stdout).contains("Cancelled."));
+    assert!(String::from_utf8_lossy(&merged.stdout).contains("Merged into "));
+    // Without prompts the task is added and the match only reported.
+    let warning = String::from_utf8_lossy(&reported.stderr);
+    assert!(
+        warning.contains("warning: similar to open task"),
+        "{warning}"
+    );
+    assert_eq!(tasks.len(), 2);
+    let task = &tasks.tasks()[0];
+    assert_eq!(task.name, "Renew passport");
+    assert_eq!(task.description, "Photos first\n\nBook an appointment");
+    assert_eq!(task.priority, Priority::High);
+    assert_eq!(task.project.as_deref(), Some("Home"));
+    assert_eq!(task.tags, ["admin", "travel"]);
+}
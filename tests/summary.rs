@@ -0,0 +1,52 @@
+use task_manager::dates::{DateTime, Timestamp};
+use task_manager::stats::Summary;
+use task_manager::{Priority, Status, Task, TasksManager};
+
+fn day(d: u32) -> Timestamp {
+    DateTime::date(2024, 6, d).to_timestamp().unwrap()
+}
+
+fn task(name: &str, priority: Priority, due: Option<u32>, created: u32) -> Task {
+    let mut task = Task::new(name);
+    task.priority = priority;
+    task.due = due.map(day);
+    task.created_at = day(created);
+    task
+}
+
+fn manager() -> TasksManager {
+    let mut done = task("file taxes", Priority::Critical, Some(1), 1);
+    done.set_status(Status::Done);
+    TasksManager::from_tasks(vec![
+        task("fix bike", Priority::Low, None, 1),
+        task("plan offsite", Priority::High, None, 2),
+        task("write report", Priority::High, Some(20), 5),
+        done,
+        task("call bank", Priority::High, Some(11), 8),
+        task("book flights", Priority::High, Some(11), 3),
+    ])
+}
+
+#[test]
+fn summary_counts_the_open_backlog() {
+    let now = day(11).add_secs(12 * 3600);
+    let mut manager = manager();
+    let later_today = manager.add(task("pay rent", Priority::Medium, None, 9));
+    manager.get_mut(later_today).unwrap().due = Some(now.add_secs(3600));
+    let started = manager.add(task("tidy desk", Priority::Low, None, 9));
+    manager
+        .get_mut(started)
+        .unwrap()
+        .set_status(Status::InProgress);
+    let hidden = manager.add(task("old idea", Priority::Low, Some(1), 1));
+    manager.get_mut(hidden).unwrap().archived = true;
+    assert_eq!(
+        Summary::of(manager.tasks(), now),
+        Summary {
+            open: 7,
+            due_today: 1,
+            overdue: 2,
+            in_progress: 1,
+        }
+    );
+}
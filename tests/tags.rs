@@ -0,0 +1,151 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use task_manager::config::{Color, Config, Glyphs};
+use task_manager::dates::Timestamp;
+use task_manager::export::html;
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Status, Task, TasksManager};
+
+fn tagged(name: &str, tags: &[&str], status: Status) -> Task {
+    let mut task = Task::new(name);
+    task.tags = tags.iter().map(|t| t.to_string()).collect();
+    task.set_status(status);
+    task
+}
+
+fn tags_of(manager: &TasksManager) -> Vec<Vec<String>> {
+    manager.tasks().iter().map(|t| t.tags.clone()).collect()
+}
+
+#[test]
+fn tag_styles_parse_colours_and_icons() {
+    let config = Config::parse(
+        "[tags.urgent]\ncolor = \"Red\"\nicon = \"🔥\"\n\n[tags.home]\ncolor = \"#1A7F37\"\nicon = \" \"\n",
+    )
+    .unwrap();
+    let urgent = config.tags.get("URGENT").unwrap();
+    assert_eq!(urgent.color, Some(Color::Red));
+    assert_eq!(urgent.icon.as_deref(), Some("🔥"));
+    let home = config.tags.get("home").unwrap();
+    assert_eq!(home.color, Some(Color::Rgb(0x1a, 0x7f, 0x37)));
+    // A blank icon is no icon.
+    assert_eq!(home.icon, None);
+    assert!(config.tags.get("work").is_none());
+
+    assert_eq!(Color::Cyan.ansi(), "\x1b[36m");
+    assert_eq!(Color::Rgb(1, 2, 3).ansi(), "\x1b[38;2;1;2;3m");
+    assert_eq!(Color::Rgb(1, 2, 3).css(), "#010203");
+    for bad in [
+        "[tags.urgent]\ncolor = \"crimson\"\n",
+        "[tags.urgent]\ncolor = \"#12345\"\n",
+        "[tags.urgent]\nsize = 3\n",
+        "[tags]\nurgent = \"red\"\n",
+    ] {
+        assert!(Config::parse(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn html_export_draws_styled_tags() {
+    let config = Config::parse("[tags.urgent]\ncolor = \"red\"\nicon = \"<!>\"\n").unwrap();
+    let mut task = Task::new("Fix the roof");
+    task.tags = vec!["Urgent".into(), "home".into()];
+    let page = html::render(
+        &[&task],
+        Timestamp::from_secs(0),
+        &config.tags,
+        &Glyphs::ascii(),
+    );
+    assert!(
+        page.contains(
+            "<span class=\"tag\" style=\"color: red; background: none; border: 1px solid red\">&lt;!&gt; Urgent</span>"
+        ),
+        "{page}"
+    );
+    assert!(page.contains("<span class=\"tag\">home</span>"), "{page}");
+}
+
+#[test]
+fn tags_are_counted_renamed_merged_and_removed_ignoring_case() {
+    let mut manager = TasksManager::from_tasks(vec![
+        tagged("a", &["Home", "errand"], Status::Todo),
+        tagged("b", &["home"], Status::Done),
+        tagged("c", &["errands", "HOME"], Status::Todo),
+    ]);
+    let counts: Vec<(String, usize, usize)> = manager
+        .tag_counts()
+        .into_iter()
+        .map(|c| (c.tag, c.tasks, c.open))
+        .collect();
+    // Most used first, spelled as first seen; ties in name order.
+    assert_eq!(
+        counts,
+        [
+            ("Home".into(), 3, 2),
+            ("errand".into(), 1, 1),
+            ("errands".into(), 1, 1),
+        ]
+    );
+
+    // A task with both tags keeps one: renaming merges.
+    assert_eq!(manager.rename_tag("ERRANDS", "errand"), 1);
+    assert_eq!(manager.rename_tag("home", "house"), 3);
+    assert_eq!(
+        tags_of(&manager),
+        [
+            vec!["house", "errand"],
+            vec!["house"],
+            vec!["errand", "house"]
+        ]
+    );
+    assert_eq!(manager.remove_tag("House"), 3);
+    assert_eq!(manager.remove_tag("missing"), 0);
+    assert_eq!(tags_of(&manager), [vec!["errand"], vec![], vec!["errand"]]);
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_task-manager"))
+        .env_clear()
+        .arg("--file")
+        .arg(dir.join("tasks.json"))
+        .arg("--config")
+        .arg(dir.join("config.toml"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn tags_command_guards_renames_and_deletions() {
+    let dir = std::env::temp_dir().join(format!("task-manager-tags-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let store = JsonStore::new(dir.join("tasks.json"));
+    store
+        .save(&TasksManager::from_tasks(vec![
+            tagged("a", &["home"], Status::Todo),
+            tagged("b", &["house", "old"], Status::Done),
+        ]))
+        .unwrap();
+
+    let taken = run(&dir, &["tags", "rename", "home", "house"]);
+    let merged = run(&dir, &["tags", "merge", "house", "home"]);
+    let refused = run(&dir, &["tags", "delete", "home"]);
+    let unused = run(&dir, &["tags", "delete", "old"]);
+    let forced = run(&dir, &["tags", "delete", "home", "--force"]);
+    let missing = run(&dir, &["tags", "delete", "hom"]);
+    let tasks = store.load().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!taken.status.success());
+    let message = String::from_utf8_lossy(&taken.stderr);
+    assert!(message.contains("tags merge home house"), "{message}");
+    assert!(merged.status.success());
+    assert!(!refused.status.success());
+    assert!(String::from_utf8_lossy(&refused.stderr).contains("--force"));
+    assert!(unused.status.success() && forced.status.success());
+    assert!(!missing.status.success());
+    assert!(tags_of(&tasks).iter().all(Vec::is_empty), "{tasks:?}");
+}
@@ -0,0 +1,110 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use common::{answering, binary, fake, stdout, TempDir};
+
+use task_manager::storage::{JsonStore, Store};
+use task_manager::{Task, TasksManager};
+
+/// Stands in for the editor: renames "order paper".
+const RENAME: &str =
+    "#!/bin/sh\nsed -i 's/\"name\": \"order paper\"/\"name\": \"order more paper\"/' \"$1\"\n";
+
+fn run(dir: &Path, args: &[&str], input: &str) -> Output {
+    answering(binary(dir).env("VISUAL", "rename").args(args), input)
+}
+
+fn names(dir: &Path) -> Vec<String> {
+    let manager = JsonStore::new(dir.join("tasks.json")).load().unwrap();
+    manager.tasks().iter().map(|t| t.name.clone()).collect()
+}
+
+#[test]
+fn imported_tasks_are_accepted_edited_or_discarded() {
+    let dir = TempDir::new("triage-import");
+    fake(&dir, "rename", RENAME);
+    let incoming = dir.join("incoming.json");
+    JsonStore::new(&incoming)
+        .save(&TasksManager::from_tasks(vec![
+            Task::new("call supplier"),
+            Task::new("order paper"),
+            Task::new("water plants"),
+            Task::new("book venue"),
+            Task::new("send invites"),
+        ]))
+        .unwrap();
+    let incoming = incoming.to_str().unwrap();
+
+    // Accept, edit then accept, an unknown answer, discard, accept the rest.
+    let imported = stdout(run(
+        &dir,
+        &["import", "--json", incoming, "--triage"],
+        "a\ne\nmaybe\na\nd\nr\n",
+    ));
+    let kept = names(&dir);
+
+    assert!(imported.contains("(2/5)"), "{imported}");
+    assert!(imported.contains("order more paper"), "{imported}");
+    assert!(imported.contains("Unknown answer `maybe`."), "{imported}");
+    // Accepting the rest asks no more.
+    assert!(!imported.contains("(5/5)"), "{imported}");
+    assert!(
+        imported.contains("Triage: 4 accepted (1 edited), 1 discarded.\n"),
+        "{imported}"
+    );
+    assert!(
+        imported.ends_with("Imported 4 of 5 tasks (0 already present, 1 discarded)\n"),
+        "{imported}"
+    );
+    assert_eq!(
+        kept,
+        [
+            "call supplier",
+            "order more paper",
+            "book venue",
+            "send invites"
+        ]
+    );
+}
+
+#[test]
+fn quitting_discards_the_rest_and_discarded_items_are_offered_again() {
+    let dir = TempDir::new("triage-markdown");
+    let notes = dir.join("notes.md");
+    fs::write(&notes, "- [ ] keep\n- [ ] drop\n- [ ] later\n").unwrap();
+    let notes = notes.to_str().unwrap();
+
+    let synced = stdout(run(
+        &dir,
+        &["sync", "markdown", notes, "--triage"],
+        "a\nq\n",
+    ));
+    let kept = names(&dir);
+    let text = fs::read_to_string(notes).unwrap();
+    // The end of input is a quit, too.
+    let unanswered = stdout(run(&dir, &["sync", "markdown", notes, "--triage"], ""));
+    let again = stdout(run(&dir, &["sync", "markdown", notes], ""));
+
+    assert!(
+        synced.contains("Triage: 1 accepted (0 edited), 2 discarded.\n"),
+        "{synced}"
+    );
+    assert_eq!(kept, ["keep"]);
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0].starts_with("- [ ] keep <!-- task:"), "{text}");
+    assert_eq!(lines[1..], ["- [ ] drop", "- [ ] later"]);
+    assert!(
+        unanswered.contains("Triage: 0 accepted (0 edited), 2 discarded.\n"),
+        "{unanswered}"
+    );
+    assert!(
+        again.contains("+ drop") && again.contains("+ later"),
+        "{again}"
+    );
+    assert_eq!(names(&dir), ["keep", "drop", "later"]);
+}